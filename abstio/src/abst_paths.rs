@@ -405,6 +405,19 @@ pub fn path_all_edits(name: &MapName) -> String {
     ))
 }
 
+pub fn path_run_analytics(name: &MapName, run_name: &str) -> String {
+    path(format!(
+        "player/run_analytics/{}/{}/{}/{}.bin",
+        name.city.country, name.city.city, name.map, run_name
+    ))
+}
+pub fn path_all_run_analytics(name: &MapName) -> String {
+    path(format!(
+        "player/run_analytics/{}/{}/{}",
+        name.city.country, name.city.city, name.map
+    ))
+}
+
 pub fn path_ltn_proposals(name: &MapName, proposal_name: &str) -> String {
     path(format!(
         "player/ltn_proposals/{}/{}/{}/{}.json.gz",
@@ -418,6 +431,26 @@ pub fn path_all_ltn_proposals(name: &MapName) -> String {
     ))
 }
 
+pub fn path_analysis_zones(name: &MapName) -> String {
+    path(format!(
+        "player/analysis_zones/{}/{}/{}.json",
+        name.city.country, name.city.city, name.map
+    ))
+}
+
+pub fn path_challenge(name: &MapName, challenge_name: &str) -> String {
+    path(format!(
+        "player/challenges/{}/{}/{}/{}.json",
+        name.city.country, name.city.city, name.map, challenge_name
+    ))
+}
+pub fn path_all_challenges(name: &MapName) -> String {
+    path(format!(
+        "player/challenges/{}/{}/{}",
+        name.city.country, name.city.city, name.map
+    ))
+}
+
 pub fn path_save(name: &MapName, edits_name: &str, run_name: &str, time: String) -> String {
     path(format!(
         "player/saves/{}/{}/{}/{}_{}/{}.bin",
@@ -431,6 +464,14 @@ pub fn path_all_saves(name: &MapName, edits_name: &str, run_name: &str) -> Strin
     ))
 }
 
+/// Where analytics are moved during long simulations; see `SimOptions::spill_analytics`
+pub fn path_spilled_analytics(name: &MapName, edits_name: &str, run_name: &str) -> String {
+    path(format!(
+        "player/spilled_analytics/{}/{}/{}/{}_{}",
+        name.city.country, name.city.city, name.map, edits_name, run_name
+    ))
+}
+
 pub fn path_trips(name: &MapName) -> String {
     path(format!(
         "player/routes/{}/{}/{}.json",
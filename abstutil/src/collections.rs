@@ -377,6 +377,21 @@ impl<K: IndexableKey, V> FixedMap<K, V> {
         }
     }
 
+    /// An empty map with room for keys up to this index, without growing.
+    pub fn with_capacity(capacity: usize) -> FixedMap<K, V> {
+        let mut inner = Vec::new();
+        inner.resize_with(capacity, || None);
+        FixedMap {
+            inner,
+            key_type: PhantomData,
+        }
+    }
+
+    /// One more than the largest index that's ever been inserted
+    pub fn capacity(&self) -> usize {
+        self.inner.len()
+    }
+
     pub fn insert(&mut self, key: K, value: V) {
         let idx = key.index();
         // Depending on the order of calls, this could wind up pushing one value at a time. It may
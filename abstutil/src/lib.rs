@@ -16,6 +16,7 @@ pub use cli::*;
 pub use clone::*;
 pub use collections::*;
 pub use logger::*;
+pub use memory::*;
 pub use priority_queue::*;
 pub use process::*;
 pub use time::*;
@@ -25,6 +26,7 @@ mod cli;
 mod clone;
 mod collections;
 pub mod logger;
+mod memory;
 mod priority_queue;
 mod process;
 mod serde;
@@ -0,0 +1,168 @@
+//! Rough accounting of where memory goes, to find out why big simulations run out of RAM.
+//!
+//! Measuring the real heap size of nested structures is hard, so most entries use the size of
+//! the object serialized to bincode. This undercounts (no allocator overhead, unused capacity, or
+//! hashmap buckets), but is proportional enough to compare subsystems.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{prettyprint_usize, serialized_size_bytes};
+
+/// A breakdown of memory use by subsystem (like "map" or "agents") and the parts within each.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MemoryReport {
+    pub entries: Vec<MemoryEntry>,
+    /// The resident set size of the whole process, if the platform reports it. Anything not
+    /// covered by the entries shows up as unattributed.
+    pub process_bytes: Option<usize>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MemoryEntry {
+    pub subsystem: String,
+    pub name: String,
+    /// How many objects are part of this entry
+    pub count: usize,
+    pub bytes: usize,
+}
+
+impl MemoryReport {
+    pub fn new() -> MemoryReport {
+        MemoryReport {
+            entries: Vec::new(),
+            process_bytes: process_resident_bytes(),
+        }
+    }
+
+    /// Measures an object by its serialized size
+    pub fn measure<T: Serialize>(&mut self, subsystem: &str, name: &str, count: usize, obj: &T) {
+        self.add(subsystem, name, count, serialized_size_bytes(obj));
+    }
+
+    /// Records a size measured some other way
+    pub fn add(&mut self, subsystem: &str, name: &str, count: usize, bytes: usize) {
+        self.entries.push(MemoryEntry {
+            subsystem: subsystem.to_string(),
+            name: name.to_string(),
+            count,
+            bytes,
+        });
+    }
+
+    /// Adds all entries from another report. The process size is re-measured.
+    pub fn extend(&mut self, other: MemoryReport) {
+        self.entries.extend(other.entries);
+        self.process_bytes = process_resident_bytes();
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.entries.iter().map(|e| e.bytes).sum()
+    }
+
+    /// The total per subsystem, largest first
+    pub fn subsystem_totals(&self) -> Vec<(String, usize)> {
+        let mut totals: Vec<(String, usize)> = Vec::new();
+        for entry in &self.entries {
+            if let Some(pair) = totals.iter_mut().find(|(s, _)| s == &entry.subsystem) {
+                pair.1 += entry.bytes;
+            } else {
+                totals.push((entry.subsystem.clone(), entry.bytes));
+            }
+        }
+        totals.sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+        totals
+    }
+
+    /// A human-readable report. Each subsystem lists its share of the total, then its biggest
+    /// parts.
+    pub fn describe(&self) -> Vec<String> {
+        let total = self.total_bytes().max(1);
+        let mut lines = vec![format!(
+            "Measured total: {}",
+            describe_bytes(self.total_bytes())
+        )];
+        if let Some(process) = self.process_bytes {
+            lines.push(format!(
+                "Process resident size: {} ({} unattributed)",
+                describe_bytes(process),
+                describe_bytes(process.saturating_sub(self.total_bytes()))
+            ));
+        }
+        for (subsystem, bytes) in self.subsystem_totals() {
+            lines.push(String::new());
+            lines.push(format!(
+                "{}: {} ({}%)",
+                subsystem,
+                describe_bytes(bytes),
+                100 * bytes / total
+            ));
+            let mut parts: Vec<&MemoryEntry> = self
+                .entries
+                .iter()
+                .filter(|e| e.subsystem == subsystem)
+                .collect();
+            parts.sort_by_key(|e| std::cmp::Reverse(e.bytes));
+            for e in parts {
+                lines.push(format!(
+                    "- {} {}: {}",
+                    prettyprint_usize(e.count),
+                    e.name,
+                    describe_bytes(e.bytes)
+                ));
+            }
+        }
+        lines
+    }
+}
+
+/// Formats a size in bytes as KB, MB, or GB
+pub fn describe_bytes(bytes: usize) -> String {
+    let b = bytes as f64;
+    if b >= 1024.0 * 1024.0 * 1024.0 {
+        format!("{:.2} GB", b / 1024.0 / 1024.0 / 1024.0)
+    } else if b >= 1024.0 * 1024.0 {
+        format!("{:.1} MB", b / 1024.0 / 1024.0)
+    } else if b >= 1024.0 {
+        format!("{:.1} KB", b / 1024.0)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
+
+/// The resident set size of this process. Only available on Linux.
+pub fn process_resident_bytes() -> Option<usize> {
+    if cfg!(target_os = "linux") {
+        // The second field is resident pages
+        let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+        let pages: usize = statm.split_whitespace().nth(1)?.parse().ok()?;
+        // Almost always the page size on Linux; avoiding a libc dependency just for sysconf
+        Some(pages * 4096)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subsystem_totals() {
+        let mut report = MemoryReport::default();
+        report.add("map", "roads", 10, 100);
+        report.add("agents", "cars", 5, 500);
+        report.add("map", "buildings", 20, 300);
+        assert_eq!(report.total_bytes(), 900);
+        assert_eq!(
+            report.subsystem_totals(),
+            vec![("agents".to_string(), 500), ("map".to_string(), 400)]
+        );
+    }
+
+    #[test]
+    fn test_describe_bytes() {
+        assert_eq!(describe_bytes(12), "12 bytes");
+        assert_eq!(describe_bytes(2048), "2.0 KB");
+        assert_eq!(describe_bytes(3 * 1024 * 1024), "3.0 MB");
+    }
+}
@@ -47,6 +47,42 @@ pub fn to_binary<T: Serialize>(obj: &T) -> Vec<u8> {
     bincode::serialize(obj).unwrap()
 }
 
+/// Written first in a binary file, so that files with an older layout are rejected up-front with a
+/// clear error, instead of failing partway through deserializing or loading garbage. bincode has no
+/// notion of missing fields, so bump `VERSION` whenever the layout of anything inside changes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FormatVersion<const VERSION: u32>;
+
+/// Marks the upper half of a serialized `FormatVersion`, so files written before versions existed
+/// aren't mistaken for some version.
+const FORMAT_VERSION_MAGIC: u64 = 0x4142_5354 << 32;
+
+impl<const VERSION: u32> Serialize for FormatVersion<VERSION> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        (FORMAT_VERSION_MAGIC | VERSION as u64).serialize(s)
+    }
+}
+
+impl<'de, const VERSION: u32> Deserialize<'de> for FormatVersion<VERSION> {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<FormatVersion<VERSION>, D::Error> {
+        let raw = u64::deserialize(d)?;
+        if raw & 0xFFFF_FFFF_0000_0000 != FORMAT_VERSION_MAGIC {
+            return Err(serde::de::Error::custom(
+                "this file was written before format versions existed, and must be regenerated",
+            ));
+        }
+        let version = raw as u32;
+        if version != VERSION {
+            return Err(serde::de::Error::custom(format!(
+                "this file has format version {}, but version {} is expected, so it must be \
+                 regenerated",
+                version, VERSION
+            )));
+        }
+        Ok(FormatVersion)
+    }
+}
+
 /// Serializes a BTreeMap as a list of tuples. Necessary when the keys are structs; see
 /// https://github.com/serde-rs/json/issues/402.
 pub fn serialize_btreemap<S: Serializer, K: Serialize, V: Serialize>(
@@ -148,3 +184,20 @@ pub fn deserialize_usize<'de, D: Deserializer<'de>>(d: D) -> Result<usize, D::Er
     let x = <u32>::deserialize(d)?;
     Ok(x as usize)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_version() {
+        let bytes = to_binary(&(FormatVersion::<3>, "rest of the file".to_string()));
+        let (_, rest): (FormatVersion<3>, String) = from_binary(&bytes).unwrap();
+        assert_eq!(rest, "rest of the file");
+        assert!(from_binary::<(FormatVersion<4>, String)>(&bytes).is_err());
+
+        // Something written before versions, starting with the length of a list
+        let old = to_binary(&vec![1, 2, 3]);
+        assert!(from_binary::<FormatVersion<3>>(&old).is_err());
+    }
+}
@@ -462,6 +462,39 @@ pub fn prettyprint_time(seconds: f64) -> String {
     format!("{:.4}s", seconds)
 }
 
+/// Today's date in UTC, formatted like "2023-12-31". Returns `None` on web, where the system clock
+/// isn't available.
+pub fn today() -> Option<String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_secs();
+        Some(format_date((secs / 86400) as i64))
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        None
+    }
+}
+
+/// Converts days since 1970-01-01 into a date, using the proleptic Gregorian calendar. See
+/// <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn format_date(days_since_epoch: i64) -> String {
+    let z = days_since_epoch + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
 #[cfg(unix)]
 pub fn clear_current_line() {
     // Fails in the test runner.
@@ -606,3 +639,17 @@ fn finalized_println<'a>(maybe_sink: &mut Option<Box<dyn TimerSink + 'a>>, line:
         sink.println(line);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::format_date;
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(59), "1970-03-01");
+        // A leap day
+        assert_eq!(format_date(11016), "2000-02-29");
+        assert_eq!(format_date(19722), "2023-12-31");
+    }
+}
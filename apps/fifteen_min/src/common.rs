@@ -169,6 +169,12 @@ fn options_to_controls(ctx: &mut EventCtx, opts: &Options) -> Widget {
                 None,
                 opts.allow_shoulders,
             ));
+            rows.push(Toggle::switch(
+                ctx,
+                "Avoid steps, steep grades, and raised kerbs (wheelchair)",
+                None,
+                opts.wheelchair,
+            ));
             rows.push(Widget::dropdown(
                 ctx,
                 "speed",
@@ -195,6 +201,9 @@ pub fn options_from_controls(panel: &Panel) -> MovementOptions {
             walking_speed: panel
                 .maybe_dropdown_value("speed")
                 .unwrap_or_else(WalkingOptions::default_speed),
+            wheelchair: panel
+                .maybe_is_checked("Avoid steps, steep grades, and raised kerbs (wheelchair)")
+                .unwrap_or(false),
         })
     } else {
         MovementOptions::Biking
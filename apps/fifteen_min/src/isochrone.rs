@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
 use abstutil::MultiMap;
 use connectivity::Spot;
@@ -63,16 +63,22 @@ impl MovementOptions {
     /// Calculate the quickest time to reach buildings across the map from any of the starting
     /// points, subject to the walking/biking settings configured in these Options.
     pub fn times_from(self, map: &Map, starts: Vec<Spot>) -> HashMap<BuildingID, Duration> {
+        self.reachable_from(map, starts).time_to_reach_building
+    }
+
+    /// Everywhere reachable within 15 minutes from any of the starting points.
+    pub fn reachable_from(self, map: &Map, starts: Vec<Spot>) -> connectivity::Isochrone {
         match self {
             MovementOptions::Walking(opts) => {
-                connectivity::all_walking_costs_from(map, starts, Duration::minutes(15), opts)
+                connectivity::Isochrone::walking(map, starts, Duration::minutes(15), opts)
             }
-            MovementOptions::Biking => connectivity::all_vehicle_costs_from(
+            MovementOptions::Biking => connectivity::Isochrone::new(
                 map,
                 starts,
-                Duration::minutes(15),
                 PathConstraints::Bike,
-            ),
+                Duration::minutes(15),
+            )
+            .unwrap(),
         }
     }
 }
@@ -85,11 +91,14 @@ impl Isochrone {
         options: Options,
     ) -> Isochrone {
         let spot_starts = start.iter().map(|b_id| Spot::Building(*b_id)).collect();
-        let time_to_reach_building = options.movement.clone().times_from(&app.map, spot_starts);
+        let reachable = options
+            .movement
+            .clone()
+            .reachable_from(&app.map, spot_starts);
+        let time_to_reach_building = reachable.time_to_reach_building;
 
         let mut amenities_reachable = MultiMap::new();
         let mut population = 0;
-        let mut all_roads = HashSet::new();
         for b in time_to_reach_building.keys() {
             let bldg = app.map.get_b(*b);
             for amenity in &bldg.amenities {
@@ -104,11 +113,10 @@ impl Isochrone {
                 }
                 _ => {}
             }
-            all_roads.insert(bldg.sidewalk_pos.lane().road);
         }
 
         let mut onstreet_parking_spots = 0;
-        for r in all_roads {
+        for r in reachable.roads {
             let r = app.map.get_r(r);
             for l in &r.lanes {
                 if l.lane_type == LaneType::Parking {
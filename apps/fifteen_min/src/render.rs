@@ -11,8 +11,8 @@ pub fn draw_star(ctx: &mut EventCtx, b: &Building) -> GeomBatch {
 }
 
 pub fn draw_unwalkable_roads(ctx: &mut EventCtx, app: &App) -> Drawable {
-    let allow_shoulders = match app.session.movement {
-        MovementOptions::Walking(ref opts) => opts.allow_shoulders,
+    let (allow_shoulders, wheelchair) = match app.session.movement {
+        MovementOptions::Walking(ref opts) => (opts.allow_shoulders, opts.wheelchair),
         MovementOptions::Biking => {
             return Drawable::empty(ctx);
         }
@@ -23,6 +23,10 @@ pub fn draw_unwalkable_roads(ctx: &mut EventCtx, app: &App) -> Drawable {
         if road.is_light_rail() {
             continue;
         }
+        if wheelchair && road.wheelchair_barrier().is_some() {
+            batch.push(Color::BLUE.alpha(0.5), road.get_thick_polygon());
+            continue;
+        }
         for l in &road.lanes {
             if l.lane_type == LaneType::Sidewalk
                 || l.lane_type == LaneType::Footway
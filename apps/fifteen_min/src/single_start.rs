@@ -7,9 +7,10 @@
 use std::str::FromStr;
 
 use abstutil::prettyprint_usize;
-use geom::{Distance, FindClosest, Percent};
+use geom::{Distance, Percent, Pt2D};
 use map_gui::ID;
 use map_model::{AmenityType, Building, BuildingID};
+use widgetry::mapspace::SpatialIndex;
 use widgetry::tools::{ColorLegend, URLManager};
 use widgetry::{
     Cached, Color, Drawable, EventCtx, GfxCtx, Key, Line, Outcome, Panel, State, Text, Transition,
@@ -23,7 +24,7 @@ use crate::{common, render, App};
 /// This is the UI state for exploring the isochrone/walkshed from a single building.
 pub struct SingleStart {
     panel: Panel,
-    snap_to_buildings: FindClosest<BuildingID>,
+    snap_to_buildings: SpatialIndex<BuildingID>,
     draw_unwalkable_roads: Drawable,
 
     highlight_start: Drawable,
@@ -45,10 +46,13 @@ impl SingleStart {
 
         let draw_unwalkable_roads = render::draw_unwalkable_roads(ctx, app);
 
-        let mut snap_to_buildings = FindClosest::new();
-        for b in app.map.all_buildings() {
-            snap_to_buildings.add_polygon(b.id, &b.polygon);
-        }
+        let snap_to_buildings = SpatialIndex::bulk_load(
+            app.map
+                .all_buildings()
+                .iter()
+                .map(|b| (b.id, b.polygon.get_bounds()))
+                .collect(),
+        );
 
         let start = app.map.get_b(start);
         let isochrone = Isochrone::new(ctx, app, vec![start.id], app.session.clone());
@@ -67,6 +71,25 @@ impl SingleStart {
         })
     }
 
+    fn closest_building(&self, app: &App, cursor: Pt2D) -> Option<BuildingID> {
+        let (b, _) = self
+            .snap_to_buildings
+            .nearest(cursor, Distance::meters(30.0), |b| {
+                let polygon = &app.map.get_b(b).polygon;
+                if polygon.contains_pt(cursor) {
+                    return Distance::ZERO;
+                }
+                polygon
+                    .get_outer_ring()
+                    .points()
+                    .iter()
+                    .map(|pt| pt.dist_to(cursor))
+                    .min()
+                    .unwrap()
+            })?;
+        Some(b)
+    }
+
     fn change_start(&mut self, ctx: &mut EventCtx, app: &App, b: BuildingID) {
         if self.isochrone.start[0] == b {
             return;
@@ -110,10 +133,7 @@ impl State<App> for SingleStart {
 
             if ctx.is_key_down(Key::LeftControl) {
                 if let Some(cursor) = ctx.canvas.get_cursor_in_map_space() {
-                    if let Some((b, _)) = self
-                        .snap_to_buildings
-                        .closest_pt(cursor, Distance::meters(30.0))
-                    {
+                    if let Some(b) = self.closest_building(app, cursor) {
                         self.change_start(ctx, app, b);
                     }
                 }
@@ -124,10 +144,7 @@ impl State<App> for SingleStart {
         // panel.event never sees clicks.
         if let Some(cursor) = ctx.canvas.get_cursor_in_map_space() {
             if ctx.normal_left_click() {
-                if let Some((b, _)) = self
-                    .snap_to_buildings
-                    .closest_pt(cursor, Distance::meters(30.0))
-                {
+                if let Some(b) = self.closest_building(app, cursor) {
                     self.change_start(ctx, app, b);
                 }
             }
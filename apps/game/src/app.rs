@@ -11,11 +11,11 @@ use abstutil::{Tags, Timer};
 use geom::{Bounds, Circle, Distance, Duration, FindClosest, Polygon, Pt2D, Tessellation, Time};
 use map_gui::colors::ColorScheme;
 use map_gui::options::Options;
-use map_gui::render::{DrawMap, DrawOptions};
-use map_gui::tools::CameraState;
+use map_gui::render::{DrawMap, DrawOptions, OUTLINE_THICKNESS};
+use map_gui::tools::{CameraState, CollabSession};
 use map_model::AreaType;
 use map_model::{BufferType, IntersectionID, LaneType, Map, Traversable};
-use sim::{AgentID, Analytics, Sim, SimCallback, SimFlags, VehicleType};
+use sim::{AgentID, Analytics, Precipitation, Sim, SimCallback, SimFlags, VehicleType};
 use synthpop::Scenario;
 use widgetry::mapspace::ToggleZoomed;
 use widgetry::{Cached, Canvas, EventCtx, GfxCtx, Prerender, SharedAppState, State};
@@ -25,8 +25,8 @@ use crate::common::Warping;
 use crate::edit::apply_map_edits;
 use crate::layer::Layer;
 use crate::render::{unzoomed_agent_radius, AgentCache, GameRenderable};
-use crate::sandbox::dashboards::DashTab;
-use crate::sandbox::{GameplayMode, TutorialState};
+use crate::sandbox::dashboards::{AnalysisZones, DashTab};
+use crate::sandbox::{GameplayMode, Snapshots, TutorialState};
 
 // Convenient typedef
 pub type Transition = widgetry::Transition<App>;
@@ -122,8 +122,20 @@ impl App {
     }
 
     pub fn draw(&self, g: &mut GfxCtx, opts: DrawOptions, show_objs: &dyn ShowObject) {
-        let map = &self.primary.map;
-        let draw_map = &self.primary.draw_map;
+        self.draw_per_map(g, &self.primary, opts, show_objs);
+    }
+
+    /// Draws either the primary or secondary world. Normally only the primary is drawn, but the
+    /// split-screen mode shows both at once.
+    pub fn draw_per_map(
+        &self,
+        g: &mut GfxCtx,
+        per_map: &PerMap,
+        opts: DrawOptions,
+        show_objs: &dyn ShowObject,
+    ) {
+        let map = &per_map.map;
+        let draw_map = &per_map.draw_map;
 
         let mut sample_intersection: Option<String> = None;
 
@@ -139,47 +151,40 @@ impl App {
                 g.redraw(&draw_map.draw_all_unzoomed_parking_lots);
             }
             if layers.show_intersections || layers.show_lanes {
-                g.redraw(
-                    &self
-                        .primary
-                        .draw_map
-                        .draw_all_unzoomed_roads_and_intersections,
-                );
+                draw_map.draw_all_unzoomed_roads_and_intersections.draw(g);
             }
             if layers.show_buildings {
-                g.redraw(&draw_map.draw_all_buildings);
-                g.redraw(&draw_map.draw_all_building_outlines);
+                draw_map.draw_all_buildings.draw(g);
+                draw_map.draw_all_building_outlines.draw(g);
             }
 
             // Still show some shape selection when zoomed out.
             // TODO Refactor! Ideally use get_obj
-            if let Some(ID::Area(id)) = self.primary.current_selection {
+            if let Some(ID::Area(id)) = per_map.current_selection {
                 g.draw_polygon(self.cs.selected, draw_map.get_a(id).get_outline(map));
-            } else if let Some(ID::Road(id)) = self.primary.current_selection {
+            } else if let Some(ID::Road(id)) = per_map.current_selection {
                 g.draw_polygon(self.cs.selected, draw_map.get_r(id).get_outline(map));
-            } else if let Some(ID::Intersection(id)) = self.primary.current_selection {
-                // Actually, don't use get_outline here! Full polygon is easier to see.
-                g.draw_polygon(self.cs.selected, map.get_i(id).polygon.clone());
-            } else if let Some(ID::Building(id)) = self.primary.current_selection {
+            } else if let Some(ID::Intersection(id)) = per_map.current_selection {
+                // Actually, don't use get_outline here! Full polygon is easier to see. Show all of
+                // a roundabout at once, since it acts as one junction.
+                let polygon = match map.get_roundabout(id) {
+                    Some(roundabout) => roundabout.polygon.clone(),
+                    None => map.get_i(id).polygon.clone(),
+                };
+                g.draw_polygon(self.cs.selected, polygon);
+            } else if let Some(ID::Building(id)) = per_map.current_selection {
                 g.draw_polygon(self.cs.selected, map.get_b(id).polygon.clone());
             }
 
-            let mut cache = self.primary.agents.borrow_mut();
-            cache.draw_unzoomed_agents(
-                g,
-                &self.primary.map,
-                &self.primary.sim,
-                &self.cs,
-                &self.opts,
-            );
+            let mut cache = per_map.agents.borrow_mut();
+            cache.draw_unzoomed_agents(g, &per_map.map, &per_map.sim, &self.cs, &self.opts);
 
-            if let Some(a) = self
-                .primary
+            if let Some(a) = per_map
                 .current_selection
                 .as_ref()
                 .and_then(|id| id.agent_id())
             {
-                if let Some(pt) = self.primary.sim.canonical_pt_for_agent(a, map) {
+                if let Some(pt) = per_map.sim.canonical_pt_for_agent(a, map) {
                     // Usually we show selection with an outline, but no thickness/color is really
                     // visible for these tiny crowded dots.
                     g.draw_polygon(
@@ -189,8 +194,9 @@ impl App {
                 }
             }
         } else {
-            let mut cache = self.primary.agents.borrow_mut();
+            let mut cache = per_map.agents.borrow_mut();
             let objects = self.get_renderables_back_to_front(
+                per_map,
                 g.get_screen_bounds(),
                 g.prerender,
                 &mut cache,
@@ -201,13 +207,13 @@ impl App {
             let mut drawn_all_areas = false;
 
             for obj in objects {
-                obj.draw(g, self, &opts);
+                obj.draw(g, &PerMapView { app: self, per_map }, &opts);
 
                 match obj.get_id() {
                     ID::Building(_) => {
                         if !drawn_all_buildings {
-                            g.redraw(&draw_map.draw_all_buildings);
-                            g.redraw(&draw_map.draw_all_building_outlines);
+                            draw_map.draw_all_buildings.draw(g);
+                            draw_map.draw_all_building_outlines.draw(g);
                             drawn_all_buildings = true;
                         }
                     }
@@ -220,7 +226,7 @@ impl App {
                     _ => {}
                 }
 
-                if self.primary.current_selection == Some(obj.get_id()) {
+                if per_map.current_selection == Some(obj.get_id()) {
                     g.draw_polygon(self.cs.selected, obj.get_outline(map));
                 }
 
@@ -230,6 +236,29 @@ impl App {
                     }
                 }
             }
+
+            if let Some(ID::Intersection(id)) = per_map.current_selection {
+                if let Some(roundabout) = map.get_roundabout(id) {
+                    g.draw_polygon(
+                        self.cs.selected,
+                        roundabout.polygon.to_outline(OUTLINE_THICKNESS),
+                    );
+                }
+            }
+        }
+
+        if let Some(weather) = per_map.sim.get_weather() {
+            let tint = match weather.precipitation {
+                Precipitation::Dry => None,
+                Precipitation::Rain => Some(self.cs.rain),
+                Precipitation::Snow => Some(self.cs.snow),
+            };
+            if let Some(color) = tint {
+                g.draw_polygon(color, map.get_boundary_polygon().clone());
+            }
+            if per_map.sim.is_dark() {
+                g.draw_polygon(self.cs.darkness, map.get_boundary_polygon().clone());
+            }
         }
 
         if let Some(i) = sample_intersection {
@@ -279,6 +308,7 @@ impl App {
 
         let mut cache = self.primary.agents.borrow_mut();
         let mut objects = self.get_renderables_back_to_front(
+            &self.primary,
             Circle::new(pt, Distance::meters(3.0)).get_bounds(),
             ctx.prerender,
             &mut cache,
@@ -361,13 +391,14 @@ impl App {
     // State does, like show_icons_for() and show().
     fn get_renderables_back_to_front<'a>(
         &'a self,
+        per_map: &'a PerMap,
         bounds: Bounds,
         prerender: &Prerender,
         agents: &'a mut AgentCache,
         show_objs: &dyn ShowObject,
     ) -> Vec<&'a (dyn GameRenderable + 'a)> {
-        let map = &self.primary.map;
-        let draw_map = &self.primary.draw_map;
+        let map = &per_map.map;
+        let draw_map = &per_map.draw_map;
 
         let mut areas: Vec<&dyn GameRenderable> = Vec::new();
         let mut parking_lots: Vec<&dyn GameRenderable> = Vec::new();
@@ -436,7 +467,7 @@ impl App {
 
         // Expand all of the Traversables into agents, populating the cache if needed.
         for on in &agents_on {
-            agents.populate_if_needed(*on, map, &self.primary.sim, &self.cs, prerender);
+            agents.populate_if_needed(*on, map, &per_map.sim, &self.cs, prerender);
         }
 
         for on in agents_on {
@@ -445,7 +476,7 @@ impl App {
             }
         }
 
-        borrows.retain(|x| x.get_zorder() <= self.primary.draw_map.show_zorder);
+        borrows.retain(|x| x.get_zorder() <= per_map.draw_map.show_zorder);
 
         // This is a stable sort.
         borrows.sort_by_key(|x| x.get_zorder());
@@ -570,6 +601,62 @@ impl map_gui::AppLike for App {
     }
 }
 
+/// A read-only view of the app, looking at one particular world instead of always the primary.
+/// Renderables need an `AppLike` to look up things like the map, so this lets the secondary world
+/// be drawn without swapping it in.
+struct PerMapView<'a> {
+    app: &'a App,
+    per_map: &'a PerMap,
+}
+
+impl<'a> map_gui::AppLike for PerMapView<'a> {
+    fn map(&self) -> &Map {
+        &self.per_map.map
+    }
+    fn cs(&self) -> &ColorScheme {
+        &self.app.cs
+    }
+    fn mut_cs(&mut self) -> &mut ColorScheme {
+        unreachable!()
+    }
+    fn draw_map(&self) -> &DrawMap {
+        &self.per_map.draw_map
+    }
+    fn mut_draw_map(&mut self) -> &mut DrawMap {
+        unreachable!()
+    }
+    fn opts(&self) -> &Options {
+        &self.app.opts
+    }
+    fn mut_opts(&mut self) -> &mut Options {
+        unreachable!()
+    }
+    fn map_switched(&mut self, _: &mut EventCtx, _: Map, _: &mut Timer) {
+        unreachable!()
+    }
+    fn draw_with_opts(&self, g: &mut GfxCtx, opts: DrawOptions) {
+        self.app
+            .draw_per_map(g, self.per_map, opts, &ShowEverything::new());
+    }
+    fn make_warper(
+        &mut self,
+        _: &EventCtx,
+        _: Pt2D,
+        _: Option<f64>,
+        _: Option<map_gui::ID>,
+    ) -> Box<dyn State<PerMapView<'a>>> {
+        unreachable!()
+    }
+
+    fn sim_time(&self) -> Time {
+        self.per_map.sim.time()
+    }
+
+    fn current_stage_and_remaining_time(&self, id: IntersectionID) -> (usize, Duration) {
+        self.per_map.sim.current_stage_and_remaining_time(id)
+    }
+}
+
 pub struct ShowLayers {
     pub show_buildings: bool,
     pub show_parking_lots: bool,
@@ -656,6 +743,10 @@ pub struct PerMap {
     pub layer: Option<Box<dyn Layer>>,
     /// Only filled out in edit mode. Stored here once to avoid lots of clones. Used for preview.
     pub suspended_sim: Option<Sim>,
+    /// Periodic copies of `sim`, used to rewind
+    pub snapshots: Snapshots,
+    /// Zones drawn by the user to summarize trips, saved per map
+    pub analysis_zones: AnalysisZones,
     /// Only exists in some gameplay modes. Must be carefully reset otherwise. Has the map and
     /// scenario name too.
     // TODO Embed that in Analytics directly instead.
@@ -669,6 +760,8 @@ pub struct PerMap {
 
     /// Is this the original "secondary" state, loaded via --diff?
     pub is_secondary: bool,
+    /// Set while editing this map together with other people
+    pub collab: Option<CollabSession>,
 }
 
 impl PerMap {
@@ -690,6 +783,7 @@ impl PerMap {
         timer.start("draw_map");
         let draw_map = DrawMap::new(ctx, &map, opts, cs, timer);
         timer.stop("draw_map");
+        let analysis_zones = AnalysisZones::load(map.get_name());
 
         PerMap {
             map,
@@ -705,9 +799,12 @@ impl PerMap {
             unedited_map: None,
             layer: None,
             suspended_sim: None,
+            snapshots: Snapshots::new(),
+            analysis_zones,
             prebaked: None,
             scenario: None,
             is_secondary: false,
+            collab: None,
         }
     }
 
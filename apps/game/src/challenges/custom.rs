@@ -0,0 +1,206 @@
+use anyhow::Result;
+use maplit::btreeset;
+use serde::{Deserialize, Serialize};
+
+use abstio::MapName;
+use abstutil::Timer;
+use geom::{Duration, Polygon, Time};
+use map_model::{Map, RoadRank};
+use sim::{AgentType, Analytics, TripDelayCause};
+
+/// A challenge made in the in-game editor. It's saved as a standalone file, so it can be shared
+/// with other people, who just need to put it in the same place.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CustomChallenge {
+    pub name: String,
+    pub description: String,
+    pub map_name: MapName,
+    pub scenario: String,
+    pub goal: ChallengeGoal,
+    /// Measured by running the scenario on the unedited map, until `score_time()`
+    pub baseline: ChallengeMetrics,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub enum ChallengeGoal {
+    /// Cut the number of cars using local streets in an area, without making delays on bigger
+    /// roads much worse. Both limits are percentages.
+    CutNeighborhoodTraffic {
+        zone: Polygon,
+        min_reduction: usize,
+        max_arterial_delay_increase: usize,
+    },
+    /// Cut the total time everybody spends on their trips
+    SaveTime { min_savings: Duration },
+}
+
+/// Everything a challenge goal could be scored on
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ChallengeMetrics {
+    /// Cars crossing local roads inside the goal's zone, if there is one
+    pub zone_traffic: usize,
+    /// Delay from waiting at intersections and queueing along non-local roads
+    pub arterial_delay: Duration,
+    /// Over all finished trips
+    pub total_trip_time: Duration,
+}
+
+impl CustomChallenge {
+    pub fn load(map_name: &MapName, name: &str) -> Result<CustomChallenge> {
+        abstio::maybe_read_json(
+            abstio::path_challenge(map_name, name),
+            &mut Timer::throwaway(),
+        )
+    }
+
+    pub fn save(&self) -> String {
+        let path = abstio::path_challenge(&self.map_name, &self.name);
+        abstio::write_json(path.clone(), self);
+        path
+    }
+
+    /// Every custom challenge for every map available locally. Broken files are skipped.
+    pub fn list_all() -> Vec<CustomChallenge> {
+        let mut list = Vec::new();
+        for map_name in MapName::list_all_maps_locally() {
+            for name in abstio::list_all_objects(abstio::path_all_challenges(&map_name)) {
+                match CustomChallenge::load(&map_name, &name) {
+                    Ok(c) => list.push(c),
+                    Err(err) => warn!("Skipping challenge {}: {}", name, err),
+                }
+            }
+        }
+        list
+    }
+
+    /// Challenges are scored when the simulation reaches this time
+    pub fn score_time() -> Time {
+        Time::START_OF_DAY + Duration::hours(24)
+    }
+
+    pub fn describe_goal(&self) -> String {
+        match self.goal {
+            ChallengeGoal::CutNeighborhoodTraffic {
+                min_reduction,
+                max_arterial_delay_increase,
+                ..
+            } => format!(
+                "Cut traffic through the neighborhood by {}%, without increasing delays on main \
+                 roads by more than {}%",
+                min_reduction, max_arterial_delay_increase
+            ),
+            ChallengeGoal::SaveTime { min_savings } => format!(
+                "Cut the total time everybody spends on trips by at least {}",
+                min_savings
+            ),
+        }
+    }
+
+    /// Returns a message describing how close `current` came to the goal, and the score if the
+    /// goal was met. Bigger scores are better.
+    pub fn score(&self, current: &ChallengeMetrics) -> (String, Option<Duration>) {
+        let before = &self.baseline;
+        match self.goal {
+            ChallengeGoal::CutNeighborhoodTraffic {
+                min_reduction,
+                max_arterial_delay_increase,
+                ..
+            } => {
+                let reduction = pct_change(before.zone_traffic as f64, current.zone_traffic as f64);
+                let delay_increase = -pct_change(
+                    before.arterial_delay.inner_seconds(),
+                    current.arterial_delay.inner_seconds(),
+                );
+                let msg = format!(
+                    "Traffic through the neighborhood went from {} to {} cars ({:.1}% less). \
+                     Delays on main roads went from {} to {} ({:.1}% more).",
+                    before.zone_traffic,
+                    current.zone_traffic,
+                    reduction,
+                    before.arterial_delay,
+                    current.arterial_delay,
+                    delay_increase
+                );
+                if reduction >= min_reduction as f64
+                    && delay_increase <= max_arterial_delay_increase as f64
+                {
+                    // Reward the main-road delay that was avoided
+                    (msg, Some(before.arterial_delay - current.arterial_delay))
+                } else {
+                    (msg, None)
+                }
+            }
+            ChallengeGoal::SaveTime { min_savings } => {
+                let savings = before.total_trip_time - current.total_trip_time;
+                let msg = format!(
+                    "Everybody's trips went from {} to {} in total, saving {}.",
+                    before.total_trip_time, current.total_trip_time, savings
+                );
+                if savings >= min_savings {
+                    (msg, Some(savings))
+                } else {
+                    (msg, None)
+                }
+            }
+        }
+    }
+}
+
+impl ChallengeMetrics {
+    pub fn measure(map: &Map, analytics: &Analytics, goal: &ChallengeGoal) -> ChallengeMetrics {
+        let mut zone_traffic = 0;
+        if let ChallengeGoal::CutNeighborhoodTraffic { ref zone, .. } = goal {
+            for r in map.all_roads() {
+                if r.get_rank() == RoadRank::Local && zone.contains_pt(r.center_pts.middle()) {
+                    zone_traffic += analytics
+                        .road_thruput
+                        .total_for_with_agent_types(r.id, btreeset! { AgentType::Car });
+                }
+            }
+        }
+
+        let mut arterial_delay = Duration::ZERO;
+        for delays in analytics.trip_delays.values() {
+            for (cause, dt) in delays {
+                let arterial = match cause {
+                    TripDelayCause::Intersection(i) => map
+                        .get_i(*i)
+                        .roads
+                        .iter()
+                        .any(|r| map.get_r(*r).get_rank() != RoadRank::Local),
+                    TripDelayCause::Queueing(r) => map.get_r(*r).get_rank() != RoadRank::Local,
+                    TripDelayCause::WaitingForTransit(_) => false,
+                };
+                if arterial {
+                    arterial_delay += *dt;
+                }
+            }
+        }
+
+        let mut total_trip_time = Duration::ZERO;
+        for (_, _, _, maybe_dt) in &analytics.finished_trips {
+            if let Some(dt) = maybe_dt {
+                total_trip_time += *dt;
+            }
+        }
+
+        ChallengeMetrics {
+            zone_traffic,
+            arterial_delay,
+            total_trip_time,
+        }
+    }
+}
+
+/// How much smaller `after` is than `before`, as a percent. Negative if it grew.
+fn pct_change(before: f64, after: f64) -> f64 {
+    if before == 0.0 {
+        if after == 0.0 {
+            0.0
+        } else {
+            -100.0
+        }
+    } else {
+        100.0 * (before - after) / before
+    }
+}
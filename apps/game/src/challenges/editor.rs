@@ -0,0 +1,433 @@
+use geom::{Distance, Duration, Polygon};
+use map_gui::tools::CityPicker;
+use sim::{Scenario, Sim};
+use widgetry::tools::{Lasso, PopupMsg};
+use widgetry::{
+    Choice, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome, Panel,
+    Spinner, State, TextArea, TextBox, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::challenges::custom::{ChallengeGoal, ChallengeMetrics, CustomChallenge};
+use crate::challenges::ChallengesPicker;
+
+/// Define a new challenge on the current map, then save it to a file that can be shared and
+/// played from the challenge picker.
+pub struct ChallengeEditor {
+    panel: Panel,
+    zone: Option<Polygon>,
+    draw_zone: Drawable,
+    lasso: Option<Lasso>,
+}
+
+/// Everything typed into the panel so far, kept when the panel is rebuilt
+#[derive(Clone)]
+struct Inputs {
+    name: String,
+    description: String,
+    scenario: Option<String>,
+    goal: GoalType,
+    min_reduction: usize,
+    max_arterial_delay_increase: usize,
+    min_savings: Duration,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum GoalType {
+    Neighborhood,
+    Time,
+}
+
+impl ChallengeEditor {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let inputs = Inputs {
+            name: String::new(),
+            description: String::new(),
+            scenario: None,
+            goal: GoalType::Neighborhood,
+            min_reduction: 50,
+            max_arterial_delay_increase: 10,
+            min_savings: Duration::minutes(30),
+        };
+        Box::new(ChallengeEditor::new(ctx, app, inputs, None))
+    }
+
+    fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        inputs: Inputs,
+        zone: Option<Polygon>,
+    ) -> ChallengeEditor {
+        let mut batch = GeomBatch::new();
+        if let Some(ref polygon) = zone {
+            batch.push(app.cs.selected.alpha(0.3), polygon.clone());
+            batch.push(app.cs.selected, polygon.to_outline(Distance::meters(5.0)));
+        }
+
+        ChallengeEditor {
+            panel: make_panel(ctx, app, &inputs, zone.is_some()),
+            zone,
+            draw_zone: ctx.upload(batch),
+            lasso: None,
+        }
+    }
+
+    fn read_inputs(&self) -> Inputs {
+        let goal = self.panel.dropdown_value("goal");
+        Inputs {
+            name: self.panel.text_box("name"),
+            description: self.panel.text_area("description"),
+            scenario: self
+                .panel
+                .maybe_find_widget("scenario")
+                .map(|_| self.panel.dropdown_value("scenario")),
+            goal,
+            min_reduction: if goal == GoalType::Neighborhood {
+                self.panel.spinner("min reduction")
+            } else {
+                50
+            },
+            max_arterial_delay_increase: if goal == GoalType::Neighborhood {
+                self.panel.spinner("max delay increase")
+            } else {
+                10
+            },
+            min_savings: if goal == GoalType::Time {
+                self.panel.spinner("min savings")
+            } else {
+                Duration::minutes(30)
+            },
+        }
+    }
+
+    fn rebuild(&self, ctx: &mut EventCtx, app: &App, zone: Option<Polygon>) -> Transition {
+        Transition::Replace(Box::new(ChallengeEditor::new(
+            ctx,
+            app,
+            self.read_inputs(),
+            zone,
+        )))
+    }
+
+    fn save(&self, ctx: &mut EventCtx, app: &App) -> Transition {
+        let inputs = self.read_inputs();
+        let name = inputs.name.trim().to_string();
+        if name.is_empty() {
+            return error(ctx, "Give the challenge a name first");
+        }
+        if name.contains('/') || name.contains('\\') {
+            return error(ctx, "The name can't contain slashes");
+        }
+        let scenario = match inputs.scenario {
+            Some(s) => s,
+            None => {
+                return error(ctx, "This map doesn't have any scenarios to use");
+            }
+        };
+        let goal = match inputs.goal {
+            GoalType::Neighborhood => match self.zone {
+                Some(ref zone) => ChallengeGoal::CutNeighborhoodTraffic {
+                    zone: zone.clone(),
+                    min_reduction: inputs.min_reduction,
+                    max_arterial_delay_increase: inputs.max_arterial_delay_increase,
+                },
+                None => {
+                    return error(ctx, "Draw the neighborhood or pick a zone first");
+                }
+            },
+            GoalType::Time => ChallengeGoal::SaveTime {
+                min_savings: inputs.min_savings,
+            },
+        };
+
+        // The baseline always comes from the unedited map, no matter what the player has done
+        // to the current one
+        let baseline = ctx.loading_screen("measure the baseline for the challenge", |_, timer| {
+            let mut map = app.primary.map.clone();
+            if !map.get_edits().commands.is_empty() {
+                let edits = map.new_edits();
+                map.must_apply_edits(edits, timer);
+                map.recalculate_pathfinding_after_edits(timer);
+            }
+            let scenario: Scenario =
+                abstio::read_binary(abstio::path_scenario(map.get_name(), &scenario), timer);
+
+            let flags = &app.primary.current_flags.sim_flags;
+            let mut sim = Sim::new(&map, flags.opts.clone());
+            sim.instantiate(&scenario, &map, &mut flags.make_rng(), timer);
+            sim.timed_step(
+                &map,
+                CustomChallenge::score_time() - sim.time(),
+                &mut None,
+                timer,
+            );
+            ChallengeMetrics::measure(&map, sim.get_analytics(), &goal)
+        });
+
+        let challenge = CustomChallenge {
+            name,
+            description: inputs.description,
+            map_name: app.primary.map.get_name().clone(),
+            scenario,
+            goal,
+            baseline,
+        };
+        let path = challenge.save();
+        Transition::Multi(vec![
+            Transition::Replace(ChallengesPicker::new_state(ctx, app)),
+            Transition::Push(PopupMsg::new_state(
+                ctx,
+                "Challenge saved",
+                vec![
+                    format!("Saved to {}", path),
+                    "Share this file with anybody who wants to try it.".to_string(),
+                ],
+            )),
+        ])
+    }
+}
+
+impl State<App> for ChallengeEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Some(ref mut lasso) = self.lasso {
+            if let Some(polygon) = lasso.event(ctx) {
+                self.lasso = None;
+                return self.rebuild(ctx, app, Some(polygon.simplify(10.0)));
+            }
+            return Transition::Keep;
+        }
+
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Replace(ChallengesPicker::new_state(ctx, app));
+                }
+                "change map" => {
+                    return Transition::Push(CityPicker::new_state(
+                        ctx,
+                        app,
+                        Box::new(|ctx, app| {
+                            Transition::Multi(vec![
+                                Transition::Pop,
+                                Transition::Replace(ChallengeEditor::new_state(ctx, app)),
+                            ])
+                        }),
+                    ));
+                }
+                "draw the neighborhood" => {
+                    self.lasso = Some(Lasso::new(Distance::meters(1.0)));
+                }
+                "save" => {
+                    return self.save(ctx, app);
+                }
+                x => {
+                    if let Some(idx) = x.strip_prefix("use zone ") {
+                        let idx = idx.parse::<usize>().unwrap() - 1;
+                        let polygon = app.primary.analysis_zones.zones[idx].polygon.clone();
+                        return self.rebuild(ctx, app, Some(polygon));
+                    }
+                    unreachable!()
+                }
+            },
+            Outcome::Changed(x) => {
+                if x == "goal" {
+                    return self.rebuild(ctx, app, self.zone.clone());
+                }
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw_zone);
+        if let Some(ref lasso) = self.lasso {
+            lasso.draw(g);
+        } else {
+            self.panel.draw(g);
+        }
+    }
+}
+
+fn make_panel(ctx: &mut EventCtx, app: &App, inputs: &Inputs, has_zone: bool) -> Panel {
+    let map_name = app.primary.map.get_name();
+    let scenarios = abstio::list_all_objects(abstio::path_all_scenarios(map_name));
+
+    let mut col = vec![
+        Widget::row(vec![
+            Line(ctx.tr("challenge-editor-title", "Create a challenge"))
+                .small_heading()
+                .into_widget(ctx),
+            ctx.style().btn_close_widget(ctx),
+        ]),
+        Widget::row(vec![
+            ctx.tr_args(
+                "challenge-editor-map",
+                "Map: { $map }",
+                &[("map", map_name.describe())],
+            )
+            .text_widget(ctx)
+            .centered_vert(),
+            ctx.style()
+                .btn_outline
+                .text(ctx.tr("challenge-editor-change-map", "change map"))
+                .build_widget(ctx, "change map"),
+        ]),
+        Widget::row(vec![
+            ctx.tr("challenge-editor-name", "Name:")
+                .text_widget(ctx)
+                .centered_vert(),
+            TextBox::widget(ctx, "name", inputs.name.clone(), false, 50),
+        ]),
+        Widget::row(vec![
+            ctx.tr("challenge-editor-description", "Description:")
+                .text_widget(ctx)
+                .centered_vert(),
+            TextArea::widget(ctx, "description", inputs.description.clone(), 50, 4),
+        ]),
+    ];
+
+    if scenarios.is_empty() {
+        col.push(
+            ctx.tr(
+                "challenge-editor-no-scenarios",
+                "This map doesn't have any scenarios yet",
+            )
+            .text_widget(ctx),
+        );
+    } else {
+        let current = inputs
+            .scenario
+            .clone()
+            .filter(|s| scenarios.contains(s))
+            .unwrap_or_else(|| scenarios[0].clone());
+        col.push(Widget::row(vec![
+            ctx.tr("challenge-editor-scenario", "Scenario:")
+                .text_widget(ctx)
+                .centered_vert(),
+            Widget::dropdown(ctx, "scenario", current, Choice::strings(scenarios)),
+        ]));
+    }
+
+    col.push(Widget::row(vec![
+        ctx.tr("challenge-editor-goal", "Goal:")
+            .text_widget(ctx)
+            .centered_vert(),
+        Widget::dropdown(
+            ctx,
+            "goal",
+            inputs.goal,
+            vec![
+                Choice::new(
+                    ctx.tr(
+                        "challenge-editor-goal-neighborhood",
+                        "cut traffic through a neighborhood",
+                    ),
+                    GoalType::Neighborhood,
+                ),
+                Choice::new(
+                    ctx.tr("challenge-editor-goal-time", "save time on all trips"),
+                    GoalType::Time,
+                ),
+            ],
+        ),
+    ]));
+
+    match inputs.goal {
+        GoalType::Neighborhood => {
+            let mut zone_buttons = vec![ctx
+                .style()
+                .btn_outline
+                .icon_text(
+                    "system/assets/tools/select.svg",
+                    ctx.tr("challenge-editor-draw", "draw the neighborhood"),
+                )
+                .build_widget(ctx, "draw the neighborhood")];
+            for idx in 0..app.primary.analysis_zones.zones.len() {
+                zone_buttons.push(
+                    ctx.style()
+                        .btn_outline
+                        .text(&app.primary.analysis_zones.zones[idx].name)
+                        .build_widget(ctx, format!("use zone {}", idx + 1)),
+                );
+            }
+            col.push(Widget::row(zone_buttons));
+            col.push(
+                if has_zone {
+                    ctx.tr("challenge-editor-zone-chosen", "Neighborhood chosen")
+                } else {
+                    ctx.tr("challenge-editor-no-zone", "No neighborhood chosen yet")
+                }
+                .text_widget(ctx),
+            );
+            col.push(Widget::row(vec![
+                ctx.tr(
+                    "challenge-editor-min-reduction",
+                    "Cut traffic on local streets by at least (%):",
+                )
+                .text_widget(ctx)
+                .centered_vert(),
+                Spinner::widget(ctx, "min reduction", (1, 100), inputs.min_reduction, 5),
+            ]));
+            col.push(Widget::row(vec![
+                ctx.tr(
+                    "challenge-editor-max-delay-increase",
+                    "Without increasing delays on main roads by more than (%):",
+                )
+                .text_widget(ctx)
+                .centered_vert(),
+                Spinner::widget(
+                    ctx,
+                    "max delay increase",
+                    (0, 100),
+                    inputs.max_arterial_delay_increase,
+                    5,
+                ),
+            ]));
+        }
+        GoalType::Time => {
+            col.push(Widget::row(vec![
+                ctx.tr(
+                    "challenge-editor-min-savings",
+                    "Cut the total time spent on trips by at least:",
+                )
+                .text_widget(ctx)
+                .centered_vert(),
+                Spinner::widget(
+                    ctx,
+                    "min savings",
+                    (Duration::minutes(1), Duration::hours(100)),
+                    inputs.min_savings,
+                    Duration::minutes(1),
+                ),
+            ]));
+        }
+    }
+
+    col.push(
+        ctx.tr_args(
+            "challenge-editor-explanation",
+            "Players edit the map, then run the scenario until { $time }. Saving measures the \
+             unedited map first, which may take a while.",
+            &[("time", CustomChallenge::score_time().ampm_tostring())],
+        )
+        .text_widget(ctx),
+    );
+    col.push(
+        ctx.style()
+            .btn_solid_primary
+            .text(ctx.tr("challenge-editor-save", "save"))
+            .build_widget(ctx, "save"),
+    );
+
+    Panel::new_builder(Widget::col(col))
+        .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+        .build(ctx)
+}
+
+fn error(ctx: &mut EventCtx, msg: &str) -> Transition {
+    Transition::Push(PopupMsg::new_state(ctx, "Error", vec![msg]))
+}
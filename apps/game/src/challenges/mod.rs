@@ -6,10 +6,13 @@ use widgetry::{EventCtx, Key, Line, Panel, SimpleState, State, Text, TextExt, Wi
 
 use crate::app::App;
 use crate::app::Transition;
+use crate::challenges::custom::CustomChallenge;
 use crate::sandbox::gameplay::Tutorial;
 use crate::sandbox::{GameplayMode, SandboxMode};
 
+pub mod custom;
 pub mod cutscene;
+mod editor;
 pub mod prebake;
 
 // TODO Also have some kind of screenshot to display for each challenge
@@ -87,6 +90,29 @@ impl Challenge {
             }],
         );
 
+        let custom: Vec<Challenge> = CustomChallenge::list_all()
+            .into_iter()
+            .map(|c| Challenge {
+                title: c.name.clone(),
+                description: c
+                    .description
+                    .lines()
+                    .map(|l| l.to_string())
+                    .chain(vec![
+                        c.describe_goal(),
+                        format!("Map: {}", c.map_name.describe()),
+                    ])
+                    .filter(|l| !l.is_empty())
+                    .collect(),
+                alias: format!("custom/{}", c.name),
+                gameplay: GameplayMode::Custom(c.map_name, c.name),
+                cutscene: None,
+            })
+            .collect();
+        if !custom.is_empty() {
+            tree.insert("Custom challenges".to_string(), custom);
+        }
+
         tree
     }
 
@@ -138,6 +164,12 @@ impl ChallengesPicker {
                 .build_def(ctx)
                 .container()
                 .section(ctx),
+            ctx.style()
+                .btn_outline
+                .text("Create a challenge")
+                .build_def(ctx)
+                .container()
+                .section(ctx),
         ];
 
         // First list challenges
@@ -248,6 +280,9 @@ impl SimpleState<App> for ChallengesPicker {
         match x {
             "close" => Transition::Pop,
             "Introduction and tutorial" => Transition::Replace(Tutorial::start(ctx, app)),
+            "Create a challenge" => {
+                Transition::Replace(editor::ChallengeEditor::new_state(ctx, app))
+            }
             "Start!" => {
                 #[cfg(not(target_arch = "wasm32"))]
                 {
@@ -0,0 +1,145 @@
+use geom::{Circle, Distance};
+use map_model::Traversable;
+use sim::GridlockReport;
+use widgetry::tools::PopupMsg;
+use widgetry::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, State,
+    Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::app::Transition;
+use crate::common::{warp_to_id, CommonState};
+
+/// Explain why the simulation is stuck, using the report from the gridlock watchdog if it noticed
+/// anything, or diagnosing the current state otherwise.
+pub struct Viewer {
+    panel: Panel,
+    report: Option<GridlockReport>,
+    draw: Drawable,
+}
+
+impl Viewer {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let report = app
+            .primary
+            .sim
+            .get_gridlock_report()
+            .cloned()
+            .or_else(|| app.primary.sim.diagnose_gridlock(&app.primary.map));
+
+        let mut col = vec![Widget::row(vec![
+            Line("Gridlock diagnosis").small_heading().into_widget(ctx),
+            ctx.style().btn_close_widget(ctx),
+        ])];
+        let mut batch = GeomBatch::new();
+        if let Some(ref report) = report {
+            col.push(
+                Text::from(Line(report.describe()))
+                    .wrap_to_pct(ctx, 30)
+                    .into_widget(ctx),
+            );
+            col.push(format!("Diagnosed at {}", report.time).text_widget(ctx));
+
+            if !report.intersections.is_empty() {
+                col.push("Intersections in the cycle".text_widget(ctx));
+                for i in &report.intersections {
+                    col.push(
+                        ctx.style()
+                            .btn_plain
+                            .icon("system/assets/tools/location.svg")
+                            .label_text(i.to_string())
+                            .build_widget(ctx, format!("i{}", i.0)),
+                    );
+                }
+            }
+            if !report.worst_intersections.is_empty() {
+                col.push("Intersections blocking the most agents".text_widget(ctx));
+                for (i, cnt) in &report.worst_intersections {
+                    col.push(
+                        ctx.style()
+                            .btn_plain
+                            .icon("system/assets/tools/location.svg")
+                            .label_text(format!("{} is blocking {} agents", i, cnt))
+                            .build_widget(ctx, format!("i{}", i.0)),
+                    );
+                }
+            }
+            col.push(ctx.style().btn_outline.text("save report").build_def(ctx));
+
+            let map = &app.primary.map;
+            for on in &report.queues {
+                let poly = match on {
+                    Traversable::Lane(l) => map.get_l(*l).get_thick_polygon(),
+                    Traversable::Turn(t) => map.get_t(*t).geom.make_polygons(Distance::meters(1.0)),
+                };
+                batch.push(Color::RED.alpha(0.5), poly);
+            }
+            for i in &report.intersections {
+                batch.push(
+                    Color::YELLOW,
+                    Circle::new(map.get_i(*i).polygon.center(), Distance::meters(5.0))
+                        .to_outline(Distance::meters(1.0))
+                        .unwrap(),
+                );
+            }
+        } else {
+            col.push("Nobody is blocked right now".text_widget(ctx));
+        }
+
+        Box::new(Viewer {
+            panel: Panel::new_builder(Widget::col(col))
+                .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+                .build(ctx),
+            report,
+            draw: ctx.upload(batch),
+        })
+    }
+}
+
+impl State<App> for Viewer {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+        if ctx.redo_mouseover() {
+            app.recalculate_current_selection(ctx);
+        }
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "save report" => {
+                    let report = self.report.as_ref().unwrap();
+                    let path = format!(
+                        "{}/gridlock_{}.json",
+                        app.primary.sim.save_dir(),
+                        report.time.as_filename()
+                    );
+                    abstio::write_json(path.clone(), report);
+                    return Transition::Push(PopupMsg::new_state(
+                        ctx,
+                        "Saved",
+                        vec![format!("Report saved to {}", path)],
+                    ));
+                }
+                x => {
+                    // warp_to_id always replaces the current state, so insert a dummy one for it
+                    // to clobber
+                    return Transition::Multi(vec![
+                        Transition::Push(PopupMsg::new_state(ctx, "Warping", vec![""])),
+                        warp_to_id(ctx, app, x),
+                    ]);
+                }
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
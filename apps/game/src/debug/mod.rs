@@ -32,6 +32,7 @@ pub use self::routes::PathCostDebugger;
 mod blocked_by;
 mod blockfinder;
 mod floodfill;
+mod gridlock;
 mod objects;
 pub mod path_counter;
 mod polygons;
@@ -97,6 +98,14 @@ impl DebugMode {
                         .btn_outline
                         .text("save sim state")
                         .build_def(ctx),
+                    ctx.style()
+                        .btn_outline
+                        .text("save incremental checkpoint")
+                        .build_def(ctx),
+                    ctx.style()
+                        .btn_outline
+                        .text("diff against previous sim state")
+                        .build_def(ctx),
                     ctx.style()
                         .btn_outline
                         .text("load previous sim state")
@@ -125,10 +134,15 @@ impl DebugMode {
                         .btn_outline
                         .text("sim internal stats")
                         .build_def(ctx),
+                    ctx.style().btn_outline.text("memory usage").build_def(ctx),
                     ctx.style()
                         .btn_outline
                         .text("blocked-by graph")
                         .build_def(ctx),
+                    ctx.style()
+                        .btn_outline
+                        .text("diagnose gridlock")
+                        .build_def(ctx),
                     ctx.style()
                         .btn_outline
                         .text("blockfinder")
@@ -143,6 +157,10 @@ impl DebugMode {
                         .btn_outline
                         .text("export geometry to GeoJSON")
                         .build_def(ctx),
+                    ctx.style()
+                        .btn_outline
+                        .text("export full map to GeoJSON")
+                        .build_def(ctx),
                     ctx.style()
                         .btn_outline
                         .text("draw banned turns")
@@ -225,6 +243,31 @@ impl State<App> for DebugMode {
                         timer.stop("save sim state");
                     });
                 }
+                "save incremental checkpoint" => {
+                    ctx.loading_screen("checkpoint", |_, timer| {
+                        timer.start("save checkpoint");
+                        app.primary.sim.save_checkpoint(false);
+                        timer.stop("save checkpoint");
+                    });
+                }
+                "diff against previous sim state" => {
+                    let lines = ctx.loading_screen("diff savestates", |_, timer| {
+                        let prev_state = app
+                            .primary
+                            .sim
+                            .find_previous_savestate(app.primary.sim.time());
+                        match prev_state
+                            .clone()
+                            .and_then(|path| Sim::load_savestate(path, timer).ok())
+                        {
+                            Some(prev_sim) => app.primary.sim.diff(&prev_sim).describe(),
+                            None => {
+                                vec![format!("Couldn't load previous savestate {:?}", prev_state)]
+                            }
+                        }
+                    });
+                    return Transition::Push(PopupMsg::new_state(ctx, "Sim diff", lines));
+                }
                 "load previous sim state" => {
                     if let Some(t) = ctx.loading_screen("load previous savestate", |ctx, timer| {
                         let prev_state = app
@@ -337,9 +380,27 @@ impl State<App> for DebugMode {
                         app.primary.sim.describe_internal_stats(),
                     ));
                 }
+                "memory usage" => {
+                    let mut report = app.primary.map.memory_report();
+                    report.extend(app.primary.sim.memory_report());
+                    report.add(
+                        "rendering",
+                        "geometry on the GPU",
+                        1,
+                        ctx.prerender.get_gpu_bytes_in_use(),
+                    );
+                    return Transition::Push(PopupMsg::new_state(
+                        ctx,
+                        "Memory usage (approximate)",
+                        report.describe(),
+                    ));
+                }
                 "blocked-by graph" => {
                     return Transition::Push(blocked_by::Viewer::new_state(ctx, app));
                 }
+                "diagnose gridlock" => {
+                    return Transition::Push(gridlock::Viewer::new_state(ctx, app));
+                }
                 "blockfinder" => {
                     app.primary.current_selection = None;
                     return Transition::Push(blockfinder::Blockfinder::new_state(ctx, app));
@@ -366,6 +427,12 @@ impl State<App> for DebugMode {
                         &app.primary.map.export_geometry(),
                     );
                 }
+                "export full map to GeoJSON" => {
+                    abstio::write_json(
+                        "map_export.geojson".to_string(),
+                        &app.primary.map.export_to_geojson(),
+                    );
+                }
                 "draw banned turns" => {
                     // Abuse this just to draw
                     self.search_results = Some(SearchResults {
@@ -6,14 +6,14 @@ use map_gui::colors::ColorSchemeChoice;
 use map_gui::tools::{cmp_count, ColorNetwork};
 use map_gui::AppLike;
 use map_model::{
-    DirectedRoadID, Direction, PathConstraints, PathRequest, PathStepV2, Pathfinder, RoadID,
-    RoutingParams, NORMAL_LANE_THICKNESS,
+    DirectedRoadID, Direction, LevelOfTrafficStress, PathConstraints, PathRequest, PathStepV2,
+    Pathfinder, RoadID, RoutingParams, NORMAL_LANE_THICKNESS,
 };
 use synthpop::{TripEndpoint, TripMode};
 use widgetry::mapspace::ToggleZoomed;
 use widgetry::{
-    Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel,
-    RoundedF64, Spinner, State, Text, TextExt, VerticalAlignment, Widget,
+    Choice, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome,
+    Panel, RoundedF64, Spinner, State, Text, TextExt, VerticalAlignment, Widget,
 };
 
 use crate::app::{App, Transition};
@@ -257,7 +257,7 @@ fn params_to_controls(ctx: &mut EventCtx, mode: TripMode, params: &RoutingParams
             ),
         ]));
         rows.push(Widget::row(vec![
-            "Avoid steep inclines (>= 8%):"
+            "Avoid steep inclines (at 8%):"
                 .text_widget(ctx)
                 .margin_right(20),
             Spinner::f64_widget(
@@ -278,6 +278,20 @@ fn params_to_controls(ctx: &mut EventCtx, mode: TripMode, params: &RoutingParams
                 0.1,
             ),
         ]));
+        rows.push(Widget::row(vec![
+            "Tolerate traffic stress up to:"
+                .text_widget(ctx)
+                .margin_right(20),
+            Widget::dropdown(
+                ctx,
+                "stress_tolerance",
+                params.stress_tolerance,
+                LevelOfTrafficStress::all()
+                    .into_iter()
+                    .map(|lts| Choice::new(lts.describe(), lts))
+                    .collect(),
+            ),
+        ]));
     }
     Widget::col(rows)
 }
@@ -298,6 +312,7 @@ fn controls_to_params(panel: &Panel) -> (TripMode, RoutingParams) {
     params.avoid_steep_incline_penalty =
         panel.spinner::<RoundedF64>("avoid_steep_incline_penalty").0;
     params.avoid_high_stress = panel.spinner::<RoundedF64>("avoid_high_stress").0;
+    params.stress_tolerance = panel.dropdown_value("stress_tolerance");
     (TripMode::Bike, params)
 }
 
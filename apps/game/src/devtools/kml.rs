@@ -303,6 +303,18 @@ fn load_objects(
             format!("{}_clipped_for_{}.bin", dataset_name, map.get_name().map),
             &clipped_shapes,
         );
+        // Also make the clipped shapes easy to use elsewhere, keeping all of their properties
+        abstio::write_json(
+            format!(
+                "{}_clipped_for_{}.geojson",
+                dataset_name,
+                map.get_name().map
+            ),
+            &ExtraShapes {
+                shapes: clipped_shapes,
+            }
+            .to_geojson(),
+        );
     }
 
     (dataset_name, objects)
@@ -5,13 +5,13 @@ use abstutil::{prettyprint_usize, Timer};
 use geom::Speed;
 use map_gui::options::OptionsPanel;
 use map_gui::render::DrawMap;
-use map_gui::tools::grey_out_map;
+use map_gui::tools::{grey_out_map, intersection_object, road_object, JoinCollabSession};
 use map_model::{EditCmd, IntersectionID, LaneID, MapEdits};
 use widgetry::mapspace::ToggleZoomed;
 use widgetry::tools::{ChooseSomething, ColorLegend, PopupMsg};
 use widgetry::{
     lctrl, Choice, Color, ControlState, EventCtx, GfxCtx, HorizontalAlignment, Image, Key, Line,
-    Menu, Outcome, Panel, State, Text, TextBox, TextExt, VerticalAlignment, Widget,
+    Menu, Outcome, Panel, State, Text, TextArea, TextBox, TextExt, VerticalAlignment, Widget,
 };
 
 pub use self::roads::RoadEditor;
@@ -20,6 +20,7 @@ pub use self::stop_signs::StopSignEditor;
 pub use self::traffic_signals::TrafficSignalEditor;
 pub use self::validate::{check_blackholes, check_sidewalk_connectivity};
 use crate::app::{App, Transition};
+use crate::common::share::PROPOSAL_HOST_URL;
 use crate::common::{tool_panel, CommonState, Warping};
 use crate::debug::DebugMode;
 use crate::sandbox::{GameplayMode, SandboxMode, TimeWarpScreen};
@@ -28,6 +29,7 @@ mod crosswalks;
 mod multiple_roads;
 mod roads;
 mod routes;
+mod speed_limits;
 mod stop_signs;
 mod traffic_signals;
 mod validate;
@@ -44,6 +46,7 @@ pub struct EditMode {
     mode: GameplayMode,
 
     map_edit_key: usize,
+    collab_status: Option<String>,
 
     draw: ToggleZoomed,
 }
@@ -56,12 +59,13 @@ impl EditMode {
         let layer = crate::layer::map::Static::edits(ctx, app);
         Box::new(EditMode {
             tool_panel: tool_panel(ctx),
-            top_center: make_topcenter(ctx, app),
+            top_center: make_topcenter(ctx, app, &mode),
             changelist: make_changelist(ctx, app),
             orig_edits: app.primary.map.get_edits().clone(),
             orig_dirty,
             mode,
             map_edit_key: app.primary.map.get_edits_change_key(),
+            collab_status: app.primary.collab.as_ref().map(|c| c.describe()),
             draw: layer.draw,
         })
     }
@@ -74,13 +78,18 @@ impl EditMode {
             app.primary.sim = old_sim;
             app.primary.dirty_from_edits = self.orig_dirty;
             // Could happen if we load some edits, then load whatever we entered edit mode with.
+            // The pathfinders from before are still around, so this just restores them.
             ctx.loading_screen("apply edits", |_, timer| {
-                app.primary.map.recalculate_pathfinding_after_edits(timer);
+                app.primary
+                    .map
+                    .quickly_recalculate_pathfinding_after_edits(timer);
             });
             return Transition::Pop;
         }
 
         ctx.loading_screen("apply edits", move |ctx, timer| {
+            // The simulation runs next, so rebuild everything. Modes the edits don't affect are
+            // skipped.
             app.primary.map.recalculate_pathfinding_after_edits(timer);
             if GameplayMode::FixTrafficSignals == self.mode {
                 app.primary.sim = old_sim;
@@ -138,6 +147,27 @@ impl EditMode {
 
 impl State<App> for EditMode {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Some(changes) = app
+            .primary
+            .collab
+            .as_mut()
+            .and_then(|collab| collab.event(ctx, &app.primary.map))
+        {
+            apply_map_edits(ctx, app, changes.edits);
+            if !changes.rejected.is_empty() {
+                return Transition::Push(PopupMsg::new_state(
+                    ctx,
+                    "Some of your changes were undone",
+                    changes.rejected,
+                ));
+            }
+        }
+        let collab_status = app.primary.collab.as_ref().map(|c| c.describe());
+        if self.collab_status != collab_status {
+            self.collab_status = collab_status;
+            self.top_center = make_topcenter(ctx, app, &self.mode);
+        }
+
         {
             // We would normally use Cached, but so many values depend on one key, so this is more
             // clear.
@@ -197,6 +227,9 @@ impl State<App> for EditMode {
                         vec![msg],
                     ));
                 }
+                "set speed limits in bulk" => {
+                    return Transition::Push(speed_limits::BatchSpeedLimits::new_state(ctx, app));
+                }
                 _ => unreachable!(),
             }
         }
@@ -214,6 +247,12 @@ impl State<App> for EditMode {
                             Choice::string("save this proposal as..."),
                             // TODO Disable if empty edits
                             Choice::string("share proposal"),
+                            Choice::string("browse shared proposals"),
+                            Choice::string(if app.primary.collab.is_some() {
+                                "stop editing together"
+                            } else {
+                                "edit together with others"
+                            }),
                             Choice::string("delete this proposal and remove all edits")
                                 .fg(ctx.style().text_destructive_color),
                         ],
@@ -292,6 +331,39 @@ impl State<App> for EditMode {
                                     ctx, app, "--dev",
                                 ))
                             }
+                            "browse shared proposals" => {
+                                if app.primary.map.unsaved_edits() {
+                                    Transition::Multi(vec![
+                                        Transition::Replace(browse_shared_proposals(ctx, app)),
+                                        Transition::Push(SaveEdits::new_state(
+                                            ctx,
+                                            app,
+                                            "Do you want to save your proposal first?",
+                                            true,
+                                            Some(Transition::Multi(vec![
+                                                Transition::Pop,
+                                                Transition::Pop,
+                                            ])),
+                                            Box::new(|_, _| {}),
+                                        )),
+                                    ])
+                                } else {
+                                    Transition::Replace(browse_shared_proposals(ctx, app))
+                                }
+                            }
+                            "edit together with others" => {
+                                Transition::Replace(JoinCollabSession::new_state(
+                                    ctx,
+                                    Box::new(|_, app: &mut App, collab| {
+                                        app.primary.collab = Some(collab);
+                                        Transition::Pop
+                                    }),
+                                ))
+                            }
+                            "stop editing together" => {
+                                app.primary.collab = None;
+                                Transition::Pop
+                            }
                             "delete this proposal and remove all edits" => {
                                 abstio::delete_file(abstio::path_edits(
                                     app.primary.map.get_name(),
@@ -373,6 +445,10 @@ impl State<App> for EditMode {
             }
             if let Some(ID::Lane(l)) = app.primary.current_selection {
                 if app.per_obj.left_click(ctx, "edit lane") {
+                    let object = road_object(&app.primary.map, l.road);
+                    if let Some(state) = claim_for_collab(ctx, app, object) {
+                        return Transition::Push(state);
+                    }
                     return Transition::Push(RoadEditor::new_state(ctx, app, l));
                 }
             }
@@ -427,6 +503,27 @@ impl SaveEdits {
                     "Name:".text_widget(ctx).centered_vert(),
                     TextBox::default_widget(ctx, "filename", initial_name),
                 ]),
+                Widget::row(vec![
+                    "Author (optional):".text_widget(ctx).centered_vert(),
+                    TextBox::default_widget(
+                        ctx,
+                        "author",
+                        app.primary
+                            .map
+                            .get_edits()
+                            .author
+                            .clone()
+                            .unwrap_or_default(),
+                    ),
+                ]),
+                "Description:".text_widget(ctx),
+                TextArea::widget(
+                    ctx,
+                    "description",
+                    app.primary.map.get_edits().proposal_description.join("\n"),
+                    50,
+                    5,
+                ),
                 // TODO Want this to always consistently be one line high, but it isn't for a blank
                 // line
                 Widget::placeholder(ctx, "warning"),
@@ -521,6 +618,19 @@ impl State<App> for SaveEdits {
                 "Save" => {
                     let mut edits = app.primary.map.get_edits().clone();
                     edits.edits_name = self.current_name.clone();
+                    edits.proposal_description = self
+                        .panel
+                        .text_area("description")
+                        .trim()
+                        .lines()
+                        .map(|line| line.to_string())
+                        .collect();
+                    let author = self.panel.text_box("author").trim().to_string();
+                    edits.author = if author.is_empty() {
+                        None
+                    } else {
+                        Some(author)
+                    };
                     app.primary
                         .map
                         .must_apply_edits(edits, &mut Timer::throwaway());
@@ -635,14 +745,14 @@ impl State<App> for LoadEdits {
                             abstio::path_edits(app.primary.map.get_name(), path)
                         };
 
-                        match MapEdits::load_from_file(
+                        match MapEdits::load_and_migrate_from_file(
                             &app.primary.map,
                             path.clone(),
                             &mut Timer::throwaway(),
                         )
-                        .and_then(|edits| {
+                        .and_then(|(edits, report)| {
                             if self.mode.allows(&edits) {
-                                Ok(edits)
+                                Ok((edits, report))
                             } else {
                                 Err(anyhow!(
                                     "The current gameplay mode restricts edits. This proposal has \
@@ -650,12 +760,20 @@ impl State<App> for LoadEdits {
                                 ))
                             }
                         }) {
-                            Ok(edits) => {
+                            Ok((edits, report)) => {
                                 apply_map_edits(ctx, app, edits);
                                 app.primary
                                     .sim
                                     .handle_live_edited_traffic_signals(&app.primary.map);
-                                Transition::Pop
+                                if report.is_clean() {
+                                    Transition::Pop
+                                } else {
+                                    Transition::Replace(PopupMsg::new_state(
+                                        ctx,
+                                        "Some edits had to be adjusted",
+                                        report.describe(),
+                                    ))
+                                }
                             }
                             // TODO Hack. Have to replace ourselves, because the Menu might be
                             // invalidated now that something was chosen.
@@ -691,12 +809,20 @@ impl State<App> for LoadEdits {
     }
 }
 
-fn make_topcenter(ctx: &mut EventCtx, app: &App) -> Panel {
+fn make_topcenter(ctx: &mut EventCtx, app: &App, mode: &GameplayMode) -> Panel {
     Panel::new_builder(Widget::col(vec![
         Line("Editing map")
             .small_heading()
             .into_widget(ctx)
             .centered_horiz(),
+        if let Some(ref collab) = app.primary.collab {
+            Line(collab.describe())
+                .secondary()
+                .into_widget(ctx)
+                .centered_horiz()
+        } else {
+            Widget::nothing()
+        },
         ctx.style()
             .btn_solid_primary
             .text(format!(
@@ -710,6 +836,14 @@ fn make_topcenter(ctx: &mut EventCtx, app: &App) -> Panel {
             ))
             .hotkey(Key::Escape)
             .build_widget(ctx, "finish editing"),
+        if mode.can_edit_roads() {
+            ctx.style()
+                .btn_outline
+                .text("Set speed limits in bulk")
+                .build_widget(ctx, "set speed limits in bulk")
+        } else {
+            Widget::nothing()
+        },
         if app.opts.dev {
             ctx.style()
                 .btn_outline
@@ -727,6 +861,28 @@ fn make_topcenter(ctx: &mut EventCtx, app: &App) -> Panel {
     .build(ctx)
 }
 
+fn browse_shared_proposals(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+    map_gui::tools::browse_shared_proposals(
+        ctx,
+        app.primary.map.get_name(),
+        format!("{}/list", PROPOSAL_HOST_URL),
+        format!("{}/get", PROPOSAL_HOST_URL),
+        Box::new(|ctx, app: &mut App, result, title| {
+            match result.and_then(|bytes| MapEdits::load_from_bytes(&app.primary.map, bytes)) {
+                Ok(edits) => {
+                    apply_map_edits(ctx, app, edits);
+                    Transition::Pop
+                }
+                Err(err) => Transition::Replace(PopupMsg::new_state(
+                    ctx,
+                    "Error",
+                    vec![format!("Couldn't load {}: {}", title, err)],
+                )),
+            }
+        }),
+    )
+}
+
 pub fn apply_map_edits(ctx: &mut EventCtx, app: &mut App, edits: MapEdits) {
     ctx.loading_screen("apply map edits", |ctx, timer| {
         if !app.store_unedited_map_in_secondary && app.primary.unedited_map.is_none() {
@@ -784,17 +940,44 @@ pub fn apply_map_edits(ctx: &mut EventCtx, app: &mut App, edits: MapEdits) {
             app.primary.draw_map.get_pl(pl).clear_rendering();
         }
 
-        if app.primary.layer.as_ref().and_then(|l| l.name()) == Some("map edits") {
-            app.primary.layer = Some(Box::new(crate::layer::map::Static::edits(ctx, app)));
+        match app.primary.layer.as_ref().and_then(|l| l.name()) {
+            Some("map edits") => {
+                app.primary.layer = Some(Box::new(crate::layer::map::Static::edits(ctx, app)));
+            }
+            Some("traffic stress") => {
+                app.primary.layer = Some(Box::new(crate::layer::map::Static::traffic_stress(
+                    ctx, app,
+                )));
+            }
+            _ => {}
         }
         // Other parts of the UI poll map.get_edits_change_key() to recalculate things based on
         // edits.
 
         // Autosave
         app.primary.map.save_edits();
+
+        if let Some(ref mut collab) = app.primary.collab {
+            collab.publish(&app.primary.map);
+        }
     });
 }
 
+/// When editing together with other people, claims something before opening it in an editor.
+/// Returns a popup if somebody else is already editing it.
+fn claim_for_collab(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    object: String,
+) -> Option<Box<dyn State<App>>> {
+    let holder = app.primary.collab.as_mut()?.claim(object.clone())?;
+    Some(PopupMsg::new_state(
+        ctx,
+        "Somebody else is editing this",
+        vec![format!("{} is editing {}", holder, object)],
+    ))
+}
+
 pub fn can_edit_lane(app: &App, l: LaneID) -> bool {
     let map = &app.primary.map;
     let lane = map.get_l(l);
@@ -838,12 +1021,20 @@ pub fn maybe_edit_intersection(
         && mode.can_edit_stop_signs()
         && app.per_obj.left_click(ctx, "edit stop signs")
     {
+        let object = intersection_object(&app.primary.map, id);
+        if let Some(state) = claim_for_collab(ctx, app, object) {
+            return Some(state);
+        }
         return Some(StopSignEditor::new_state(ctx, app, id, mode.clone()));
     }
 
     if app.primary.map.maybe_get_traffic_signal(id).is_some()
         && app.per_obj.left_click(ctx, "edit traffic signal")
     {
+        let object = intersection_object(&app.primary.map, id);
+        if let Some(state) = claim_for_collab(ctx, app, object) {
+            return Some(state);
+        }
         return Some(TrafficSignalEditor::new_state(
             ctx,
             app,
@@ -939,7 +1130,8 @@ fn cmd_to_id(cmd: &EditCmd) -> Option<ID> {
     match cmd {
         EditCmd::ChangeRoad { r, .. } => Some(ID::Road(*r)),
         EditCmd::ChangeIntersection { i, .. } => Some(ID::Intersection(*i)),
-        EditCmd::ChangeRouteSchedule { .. } => None,
+        EditCmd::ChangeRouteSchedule { .. } | EditCmd::ChangeRouteFare { .. } => None,
+        EditCmd::Group { cmds, .. } => cmds.first().and_then(cmd_to_id),
     }
 }
 
@@ -1,17 +1,19 @@
 use std::collections::HashMap;
 
 use crate::ID;
-use geom::{Bounds, CornerRadii, Distance, Polygon, Pt2D, UnitFmt};
+use geom::{Bounds, CornerRadii, Distance, Duration, Polygon, Pt2D, Time, UnitFmt};
 use map_gui::render::{Renderable, OUTLINE_THICKNESS};
 use map_model::{
-    osm, BufferType, Direction, EditCmd, EditRoad, LaneID, LaneSpec, LaneType, MapEdits, Road,
-    RoadID,
+    osm, BufferType, BusPriority, Crossing, CrossingType, CurbUse, Direction, EditCmd, EditRoad,
+    Lane, LaneID, LaneSpec, LaneType, MapEdits, ParkingRestriction, Road, RoadID,
+    MAX_CROSSWALK_SETBACK,
 };
 use widgetry::tools::PopupMsg;
 use widgetry::{
     lctrl, Choice, Color, ControlState, DragDrop, Drawable, EdgeInsets, EventCtx, GeomBatch,
     GeomBatchStack, GfxCtx, HorizontalAlignment, Image, Key, Line, Outcome, Panel, PersistentSplit,
-    Spinner, StackAxis, State, Text, TextExt, VerticalAlignment, Widget, DEFAULT_CORNER_RADIUS,
+    Spinner, StackAxis, State, Text, TextExt, Toggle, VerticalAlignment, Widget,
+    DEFAULT_CORNER_RADIUS,
 };
 
 use crate::app::{App, Transition};
@@ -130,6 +132,32 @@ impl RoadEditor {
         Transition::Keep
     }
 
+    fn modify_crossings<F: Fn(&mut Vec<Crossing>)>(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        f: F,
+    ) -> Transition {
+        let mut edits = app.primary.map.get_edits().clone();
+        edits
+            .commands
+            .push(app.primary.map.edit_road_cmd(self.r, |new| {
+                (f)(&mut new.crossings);
+                new.crossings.sort_by_key(|c| c.dist);
+            }));
+        apply_map_edits(ctx, app, edits);
+        self.redo_stack.clear();
+
+        // Keep selecting the same lane, if one was selected
+        self.selected_lane = self
+            .selected_lane
+            .map(|id| self.lane_for_idx(app, id.offset));
+        self.recalc_hovering(ctx, app);
+        self.recalc_all_panels(ctx, app);
+
+        Transition::Keep
+    }
+
     fn recalc_all_panels(&mut self, ctx: &mut EventCtx, app: &App) {
         self.main_panel = make_main_panel(
             ctx,
@@ -308,6 +336,35 @@ impl State<App> for RoadEditor {
                         new.lanes_ltr[idx].lt = lt;
                         new.lanes_ltr[idx].width = width;
                     });
+                } else if x == "new crossing" {
+                    let dist = app.primary.map.get_r(self.r).length() / 2.0;
+                    return self.modify_crossings(ctx, app, |crossings| {
+                        crossings.push(Crossing {
+                            kind: CrossingType::Unsignalized,
+                            dist,
+                        });
+                    });
+                } else if x == "new curb regulation" {
+                    let dir = app.primary.map.get_l(self.selected_lane.unwrap()).dir;
+                    return self.modify_current_lane(ctx, app, Some(0), |new, _| {
+                        new.parking_restrictions.push(ParkingRestriction {
+                            dir,
+                            start: Time::START_OF_DAY + Duration::hours(7),
+                            end: Time::START_OF_DAY + Duration::hours(9),
+                            kind: CurbUse::NoParking,
+                            span: None,
+                        });
+                    });
+                } else if let Some(idx) = x.strip_prefix("remove curb regulation ") {
+                    let idx = idx.parse::<usize>().unwrap();
+                    return self.modify_current_lane(ctx, app, Some(0), |new, _| {
+                        new.parking_restrictions.remove(idx);
+                    });
+                } else if let Some(idx) = x.strip_prefix("remove crossing ") {
+                    let idx = idx.parse::<usize>().unwrap();
+                    return self.modify_crossings(ctx, app, |crossings| {
+                        crossings.remove(idx);
+                    });
                 } else if let Some(lt) = x.strip_prefix("add ") {
                     let lt = if lt == "buffer" {
                         self.main_panel.persistent_split_value("add buffer")
@@ -361,18 +418,50 @@ impl State<App> for RoadEditor {
                         apply_map_edits(ctx, app, edits);
                     }
                     return Transition::Replace(ZoneEditor::new_state(ctx, app, self.r));
+                } else if x == "shrink lanes to fit" {
+                    let orig_width = EditRoad::get_orig_from_osm(
+                        app.primary.map.get_r(self.r),
+                        app.primary.map.get_config(),
+                    )
+                    .total_width();
+                    let keep = self.selected_lane.map(|l| l.offset);
+                    let mut fits = true;
+                    let mut edits = app.primary.map.get_edits().clone();
+                    edits
+                        .commands
+                        .push(app.primary.map.edit_road_cmd(self.r, |new| {
+                            fits = new.fit_to_width(orig_width, keep);
+                        }));
+                    apply_map_edits(ctx, app, edits);
+                    self.redo_stack.clear();
+                    self.recalc_hovering(ctx, app);
+                    self.recalc_all_panels(ctx, app);
+
+                    if !fits {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Lanes don't fit",
+                            vec![
+                                "General traffic and parking lanes are as narrow as they can be.",
+                                "Remove a lane to fit the rest.",
+                            ],
+                        ));
+                    }
+                    return Transition::Keep;
                 } else {
                     unreachable!()
                 }
             }
             Outcome::Changed(x) => match x.as_ref() {
-                "speed limit" => {
+                "speed limit" | "shared space" => {
                     let speed_limit = self.main_panel.dropdown_value("speed limit");
+                    let shared_space = self.main_panel.is_checked("shared space");
 
                     let mut edits = app.primary.map.get_edits().clone();
                     let old = app.primary.map.get_r_edit(self.r);
                     let mut new = old.clone();
                     new.speed_limit = speed_limit;
+                    new.shared_space = shared_space;
                     edits.commands.push(EditCmd::ChangeRoad {
                         r: self.r,
                         old,
@@ -434,7 +523,108 @@ impl State<App> for RoadEditor {
                     app.session.buffer_lane_type =
                         self.main_panel.persistent_split_value("add buffer");
                 }
-                _ => unreachable!(),
+                "bus hours" | "bus hours start" | "bus hours end" | "queue jump" => {
+                    let limited = self.main_panel.is_checked("bus hours");
+                    if !limited && (x == "bus hours start" || x == "bus hours end") {
+                        return Transition::Keep;
+                    }
+                    let dir = app.primary.map.get_l(self.selected_lane.unwrap()).dir;
+                    let start = Time::START_OF_DAY + self.main_panel.spinner("bus hours start");
+                    let end = Time::START_OF_DAY + self.main_panel.spinner("bus hours end");
+                    if limited && start >= end {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Error",
+                            vec!["The bus lane hours must end after they start."],
+                        ));
+                    }
+                    let priority = BusPriority {
+                        dir,
+                        hours: if limited {
+                            vec![(start, end)]
+                        } else {
+                            Vec::new()
+                        },
+                        queue_jump: self.main_panel.spinner("queue jump"),
+                    };
+                    return self.modify_current_lane(ctx, app, Some(0), |new, _| {
+                        new.bus_priority.retain(|bp| bp.dir != dir);
+                        // Full-time bus lanes without a queue jump are the default
+                        if !priority.hours.is_empty() || priority.queue_jump > Duration::ZERO {
+                            new.bus_priority.push(priority.clone());
+                        }
+                    });
+                }
+                _ => {
+                    if let Some(idx) = [
+                        "curb use ",
+                        "curb start ",
+                        "curb end ",
+                        "curb whole side ",
+                        "curb from ",
+                        "curb to ",
+                    ]
+                    .into_iter()
+                    .find_map(|prefix| x.strip_prefix(prefix))
+                    {
+                        let idx = idx.parse::<usize>().unwrap();
+                        let dir = app.primary.map.get_l(self.selected_lane.unwrap()).dir;
+                        let restriction = ParkingRestriction {
+                            dir,
+                            start: Time::START_OF_DAY
+                                + self.main_panel.spinner(&format!("curb start {}", idx)),
+                            end: Time::START_OF_DAY
+                                + self.main_panel.spinner(&format!("curb end {}", idx)),
+                            kind: self.main_panel.dropdown_value(format!("curb use {}", idx)),
+                            span: if self
+                                .main_panel
+                                .is_checked(&format!("curb whole side {}", idx))
+                            {
+                                None
+                            } else {
+                                Some((
+                                    self.main_panel.spinner(&format!("curb from {}", idx)),
+                                    self.main_panel.spinner(&format!("curb to {}", idx)),
+                                ))
+                            },
+                        };
+                        if restriction.start >= restriction.end {
+                            return Transition::Push(PopupMsg::new_state(
+                                ctx,
+                                "Error",
+                                vec!["The curb regulation must end after it starts."],
+                            ));
+                        }
+                        if let Some((from, to)) = restriction.span {
+                            if from >= to {
+                                return Transition::Push(PopupMsg::new_state(
+                                    ctx,
+                                    "Error",
+                                    vec!["The regulated stretch of curb must have some length."],
+                                ));
+                            }
+                        }
+                        return self.modify_current_lane(ctx, app, Some(0), |new, _| {
+                            new.parking_restrictions[idx] = restriction.clone();
+                        });
+                    }
+                    if let Some(idx) = x
+                        .strip_prefix("crossing type ")
+                        .or_else(|| x.strip_prefix("crossing position "))
+                    {
+                        let idx = idx.parse::<usize>().unwrap();
+                        let kind = self
+                            .main_panel
+                            .dropdown_value(format!("crossing type {}", idx));
+                        let dist = self
+                            .main_panel
+                            .spinner(&format!("crossing position {}", idx));
+                        return self.modify_crossings(ctx, app, |crossings| {
+                            crossings[idx] = Crossing { kind, dist };
+                        });
+                    }
+                    unreachable!()
+                }
             },
             Outcome::DragDropReleased(_, old_idx, new_idx) => {
                 self.draw_drop_position = Drawable::empty(ctx);
@@ -846,7 +1036,7 @@ fn make_main_panel(
                 Widget::row(vec![
                     Line("Width").secondary().into_widget(ctx).centered_vert(),
                     Widget::dropdown(ctx, "width preset", lane.width, width_choices(app, l)),
-                    Spinner::widget_with_custom_rendering(
+                    Spinner::widget_with_units(
                         ctx,
                         "width custom",
                         (Distance::meters(0.3), Distance::meters(7.0)),
@@ -854,11 +1044,18 @@ fn make_main_panel(
                         Distance::meters(0.1),
                         // Even if the user's settings are set to feet, our step size is in meters, so
                         // just render in meters.
-                        Box::new(|x| x.to_string(&UnitFmt::metric())),
+                        UnitFmt::metric(),
                     ),
                 ])
                 .section(ctx),
             ]),
+            if lane.lane_type == LaneType::Parking {
+                curb_regulation_rows(ctx, road, lane)
+            } else if lane.lane_type == LaneType::Bus {
+                bus_priority_rows(ctx, road, lane)
+            } else {
+                Widget::nothing()
+            },
         ])
     } else {
         Widget::nothing()
@@ -899,7 +1096,25 @@ fn make_main_panel(
             .disabled_tooltip("The original road width is an estimate, so any changes might not require major construction.")
             .build_widget(ctx, "changes to total width")
             .align_right();
-        Widget::col(vec![line1, line2])
+        let mut col = vec![line1, line2];
+
+        let problems = map.get_r_edit(road.id).validate_cross_section(orig_width);
+        if !problems.is_empty() {
+            let mut txt = Text::new();
+            for problem in problems {
+                txt.add_line(Line(problem).fg(Color::RED));
+            }
+            col.push(txt.wrap_to_pct(ctx, 20).into_widget(ctx));
+        }
+        if road_width > orig_width {
+            col.push(
+                ctx.style()
+                    .btn_outline
+                    .text("shrink lanes to fit")
+                    .build_def(ctx),
+            );
+        }
+        Widget::col(col)
     };
 
     let road_settings = Widget::row(vec![
@@ -915,6 +1130,7 @@ fn make_main_panel(
             speed_limit_choices(app, Some(road.speed_limit)),
         )
         .centered_vert(),
+        Toggle::checkbox(ctx, "shared space", None, road.shared_space).centered_vert(),
         ctx.style()
             .btn_outline
             .text("Access restrictions")
@@ -927,6 +1143,8 @@ fn make_main_panel(
             Widget::col(vec![
                 road_settings,
                 Widget::horiz_separator(ctx, 1.0),
+                crossings_section(ctx, app, road),
+                Widget::horiz_separator(ctx, 1.0),
                 add_lane_row,
             ])
             .section(ctx)
@@ -948,6 +1166,186 @@ fn make_main_panel(
     .build_custom(ctx)
 }
 
+/// Pedestrian crossings along the road. One close to an intersection marks the crosswalk there;
+/// farther ones are mid-block.
+fn crossings_section(ctx: &mut EventCtx, app: &App, road: &Road) -> Widget {
+    let mut col = vec![Widget::row(vec![
+        Line("Crossings")
+            .secondary()
+            .into_widget(ctx)
+            .centered_vert(),
+        ctx.style()
+            .btn_outline
+            .text("new crossing")
+            .build_def(ctx)
+            .centered_vert(),
+    ])];
+    let len = road.length();
+    for (idx, crossing) in road.crossings.iter().enumerate() {
+        let location = if crossing.dist <= MAX_CROSSWALK_SETBACK
+            || len - crossing.dist <= MAX_CROSSWALK_SETBACK
+        {
+            "at intersection"
+        } else {
+            "mid-block"
+        };
+        col.push(Widget::row(vec![
+            Widget::dropdown(
+                ctx,
+                &format!("crossing type {}", idx),
+                crossing.kind,
+                vec![
+                    Choice::new("signalized", CrossingType::Signalized),
+                    Choice::new("zebra / unsignalized", CrossingType::Unsignalized),
+                ],
+            ),
+            Spinner::widget_with_units(
+                ctx,
+                format!("crossing position {}", idx),
+                (Distance::ZERO, len),
+                crossing.dist,
+                Distance::meters(1.0),
+                UnitFmt::metric(),
+            ),
+            Line(location).secondary().into_widget(ctx).centered_vert(),
+            ctx.style()
+                .btn_plain_destructive
+                .icon("system/assets/tools/trash.svg")
+                .build_widget(ctx, format!("remove crossing {}", idx))
+                .centered_vert(),
+        ]));
+    }
+    Widget::col(col)
+}
+
+/// Curb regulations apply to one side of the road, but they're edited from a parking lane on that
+/// side.
+fn curb_regulation_rows(ctx: &mut EventCtx, road: &Road, lane: &Lane) -> Widget {
+    let mut col = vec![Widget::row(vec![
+        Line("Curb regulations")
+            .secondary()
+            .into_widget(ctx)
+            .centered_vert(),
+        ctx.style()
+            .btn_outline
+            .text("new curb regulation")
+            .build_def(ctx)
+            .centered_vert(),
+    ])];
+    col.push(
+        Line("Traffic may drive in the lane during a full-length no stopping window")
+            .secondary()
+            .into_widget(ctx),
+    );
+    let len = lane.length();
+    for (idx, restriction) in road.parking_restrictions.iter().enumerate() {
+        if restriction.dir != lane.dir {
+            continue;
+        }
+        let (from, to) = restriction.span.unwrap_or((Distance::ZERO, len));
+        col.push(Widget::row(vec![
+            Widget::dropdown(
+                ctx,
+                &format!("curb use {}", idx),
+                restriction.kind,
+                CurbUse::all()
+                    .into_iter()
+                    .map(|kind| Choice::new(kind.describe(), kind))
+                    .collect(),
+            ),
+            "from".text_widget(ctx).centered_vert(),
+            Spinner::widget(
+                ctx,
+                format!("curb start {}", idx),
+                (Duration::ZERO, Duration::hours(24)),
+                restriction.start - Time::START_OF_DAY,
+                Duration::minutes(15),
+            ),
+            "to".text_widget(ctx).centered_vert(),
+            Spinner::widget(
+                ctx,
+                format!("curb end {}", idx),
+                (Duration::ZERO, Duration::hours(24)),
+                restriction.end - Time::START_OF_DAY,
+                Duration::minutes(15),
+            ),
+            ctx.style()
+                .btn_plain_destructive
+                .icon("system/assets/tools/trash.svg")
+                .build_widget(ctx, format!("remove curb regulation {}", idx))
+                .centered_vert(),
+        ]));
+        col.push(Widget::row(vec![
+            Toggle::checkbox(ctx, "Whole side", None, restriction.span.is_none())
+                .named(format!("curb whole side {}", idx))
+                .centered_vert(),
+            "or from".text_widget(ctx).centered_vert(),
+            Spinner::widget_with_units(
+                ctx,
+                format!("curb from {}", idx),
+                (Distance::ZERO, len),
+                from,
+                Distance::meters(1.0),
+                UnitFmt::metric(),
+            ),
+            "to".text_widget(ctx).centered_vert(),
+            Spinner::widget_with_units(
+                ctx,
+                format!("curb to {}", idx),
+                (Distance::ZERO, len),
+                to,
+                Distance::meters(1.0),
+                UnitFmt::metric(),
+            ),
+        ]));
+    }
+    Widget::col(col).section(ctx)
+}
+
+/// Like curb regulations, bus priority applies to one side of the road. Only one window of
+/// hours can be set here.
+fn bus_priority_rows(ctx: &mut EventCtx, road: &Road, lane: &Lane) -> Widget {
+    let existing = road.bus_priority.iter().find(|bp| bp.dir == lane.dir);
+    let hours = existing.and_then(|bp| bp.hours.first());
+    Widget::col(vec![
+        Widget::row(vec![
+            Toggle::checkbox(ctx, "Buses only from", None, hours.is_some())
+                .named("bus hours")
+                .centered_vert(),
+            Spinner::widget(
+                ctx,
+                "bus hours start",
+                (Duration::ZERO, Duration::hours(24)),
+                hours
+                    .map(|(start, _)| *start - Time::START_OF_DAY)
+                    .unwrap_or(Duration::hours(7)),
+                Duration::minutes(15),
+            ),
+            "to".text_widget(ctx).centered_vert(),
+            Spinner::widget(
+                ctx,
+                "bus hours end",
+                (Duration::ZERO, Duration::hours(24)),
+                hours
+                    .map(|(_, end)| *end - Time::START_OF_DAY)
+                    .unwrap_or(Duration::hours(10)),
+                Duration::minutes(15),
+            ),
+        ]),
+        Widget::row(vec![
+            "Queue jump at a signal:".text_widget(ctx).centered_vert(),
+            Spinner::widget(
+                ctx,
+                "queue jump",
+                (Duration::ZERO, Duration::seconds(30.0)),
+                existing.map(|bp| bp.queue_jump).unwrap_or(Duration::ZERO),
+                Duration::seconds(1.0),
+            ),
+        ]),
+    ])
+    .section(ctx)
+}
+
 fn selected_lane_bg(ctx: &EventCtx) -> Color {
     ctx.style().btn_tab.bg_disabled
 }
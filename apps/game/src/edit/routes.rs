@@ -1,5 +1,5 @@
 use geom::{Duration, Time};
-use map_model::{EditCmd, TransitRouteID};
+use map_model::{describe_cents, EditCmd, FareStructure, TransitFare, TransitRouteID};
 use widgetry::{
     EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, Spinner, State, TextExt,
     VerticalAlignment, Widget,
@@ -12,6 +12,9 @@ use crate::edit::apply_map_edits;
 pub struct RouteEditor {
     panel: Panel,
     route: TransitRouteID,
+    /// The fare spinners start with these, so only change the fare if they're adjusted
+    orig_fare_cents: usize,
+    orig_cap_cents: usize,
 }
 
 impl RouteEditor {
@@ -19,6 +22,8 @@ impl RouteEditor {
         app.primary.current_selection = None;
 
         let route = app.primary.map.get_tr(id);
+        let orig_fare_cents = route.fare.typical_price();
+        let orig_cap_cents = route.fare.daily_cap.unwrap_or(0);
         Box::new(RouteEditor {
             panel: Panel::new_builder(Widget::col(vec![
                 Widget::row(vec![
@@ -29,12 +34,42 @@ impl RouteEditor {
                 // TODO This UI needs design, just something to start plumbing the edits
                 Widget::row(vec![
                     "Frequency".text_widget(ctx),
-                    Spinner::widget(
+                    Spinner::widget_with_units(
                         ctx,
                         "freq_mins",
                         (Duration::minutes(1), Duration::hours(2)),
                         Duration::hours(1),
                         Duration::minutes(1),
+                        app.opts.units,
+                    ),
+                ]),
+                Line(format!("Current fare: {}", route.fare.describe())).into_widget(ctx),
+                Widget::row(vec![
+                    "Fare per ride".text_widget(ctx),
+                    Spinner::widget_with_custom_rendering(
+                        ctx,
+                        "fare_cents",
+                        (0, 2000),
+                        orig_fare_cents,
+                        25,
+                        Box::new(describe_cents),
+                    ),
+                ]),
+                Widget::row(vec![
+                    "Daily cap".text_widget(ctx),
+                    Spinner::widget_with_custom_rendering(
+                        ctx,
+                        "cap_cents",
+                        (0, 5000),
+                        orig_cap_cents,
+                        25,
+                        Box::new(|x| {
+                            if x == 0 {
+                                "none".to_string()
+                            } else {
+                                describe_cents(x)
+                            }
+                        }),
                     ),
                 ]),
                 ctx.style()
@@ -46,6 +81,8 @@ impl RouteEditor {
             .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
             .build(ctx),
             route: id,
+            orig_fare_cents,
+            orig_cap_cents,
         })
     }
 }
@@ -74,6 +111,28 @@ impl State<App> for RouteEditor {
                         old: app.primary.map.get_tr(self.route).spawn_times.clone(),
                         new: hourly_times,
                     });
+
+                    let fare_cents: usize = self.panel.spinner("fare_cents");
+                    let cap_cents: usize = self.panel.spinner("cap_cents");
+                    if fare_cents != self.orig_fare_cents || cap_cents != self.orig_cap_cents {
+                        // Zonal fares can only be set up by editing the proposal file for now
+                        edits.commands.push(EditCmd::ChangeRouteFare {
+                            id: self.route,
+                            old: app.primary.map.get_tr(self.route).fare.clone(),
+                            new: TransitFare {
+                                structure: if fare_cents == 0 {
+                                    FareStructure::Free
+                                } else {
+                                    FareStructure::Flat(fare_cents)
+                                },
+                                daily_cap: if cap_cents == 0 {
+                                    None
+                                } else {
+                                    Some(cap_cents)
+                                },
+                            },
+                        });
+                    }
                     apply_map_edits(ctx, app, edits);
 
                     return Transition::Pop;
@@ -0,0 +1,229 @@
+//! Set the speed limit on many roads at once, like making every residential street in an area a
+//! 20mph zone.
+
+use std::collections::BTreeSet;
+
+use geom::{Distance, Polygon, Speed};
+use map_model::osm::RoadRank;
+use map_model::{EditCmd, RoadID};
+use widgetry::tools::{Lasso, PopupMsg};
+use widgetry::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel,
+    State, TextExt, Toggle, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::CommonState;
+use crate::edit::{apply_map_edits, speed_limit_choices};
+
+pub struct BatchSpeedLimits {
+    panel: Panel,
+    /// Only roads inside this are selected, if it's set
+    area: Option<Polygon>,
+    lasso: Option<Lasso>,
+    roads: BTreeSet<RoadID>,
+    draw: Drawable,
+}
+
+impl BatchSpeedLimits {
+    pub fn new_state(ctx: &mut EventCtx, app: &mut App) -> Box<dyn State<App>> {
+        app.primary.current_selection = None;
+        let mut state = BatchSpeedLimits {
+            panel: Panel::new_builder(Widget::col(vec![
+                Line("Set speed limits").small_heading().into_widget(ctx),
+                "Change these kinds of roads:".text_widget(ctx),
+                Toggle::checkbox(ctx, "highways", None, false),
+                Toggle::checkbox(ctx, "major streets", None, false),
+                Toggle::checkbox(ctx, "minor streets", None, true),
+                Widget::row(vec![
+                    ctx.style()
+                        .btn_outline
+                        .icon_text("system/assets/tools/select.svg", "Draw an area")
+                        .hotkey(Key::D)
+                        .build_widget(ctx, "draw an area"),
+                    ctx.style()
+                        .btn_plain
+                        .text("Clear area")
+                        .disabled(true)
+                        .build_widget(ctx, "clear area"),
+                ]),
+                Widget::row(vec![
+                    "New speed limit:".text_widget(ctx).centered_vert(),
+                    Widget::dropdown(
+                        ctx,
+                        "speed limit",
+                        Speed::miles_per_hour(20.0),
+                        speed_limit_choices(app, None),
+                    ),
+                ]),
+                Widget::nothing().named("summary"),
+                Widget::custom_row(vec![
+                    ctx.style()
+                        .btn_solid_primary
+                        .text("Apply")
+                        .hotkey(Key::Enter)
+                        .build_def(ctx),
+                    ctx.style()
+                        .btn_solid_destructive
+                        .text("Cancel")
+                        .hotkey(Key::Escape)
+                        .build_def(ctx),
+                ])
+                .evenly_spaced(),
+            ]))
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .build(ctx),
+            area: None,
+            lasso: None,
+            roads: BTreeSet::new(),
+            draw: Drawable::empty(ctx),
+        };
+        state.recalculate(ctx, app);
+        Box::new(state)
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        let map = &app.primary.map;
+        let mut ranks = Vec::new();
+        for (name, rank) in [
+            ("highways", RoadRank::Highway),
+            ("major streets", RoadRank::Arterial),
+            ("minor streets", RoadRank::Local),
+        ] {
+            if self.panel.is_checked(name) {
+                ranks.push(rank);
+            }
+        }
+        let speed: Speed = self.panel.dropdown_value("speed limit");
+
+        self.roads.clear();
+        let mut num_changed = 0;
+        let mut batch = GeomBatch::new();
+        for r in map.all_roads() {
+            // Speed limits only matter where vehicles can drive
+            if !ranks.contains(&r.get_rank()) || !r.lanes.iter().any(|l| l.is_driving()) {
+                continue;
+            }
+            if let Some(ref area) = self.area {
+                if !area.contains_pt(r.center_pts.middle()) {
+                    continue;
+                }
+            }
+            self.roads.insert(r.id);
+            if r.speed_limit != speed {
+                num_changed += 1;
+            }
+            batch.push(Color::CYAN.alpha(0.7), r.get_thick_polygon());
+        }
+        if let Some(ref area) = self.area {
+            batch.push(Color::RED, area.to_outline(Distance::meters(5.0)));
+        }
+        self.draw = ctx.upload(batch);
+
+        let summary = format!(
+            "{} roads selected, {} will change",
+            self.roads.len(),
+            num_changed
+        )
+        .text_widget(ctx);
+        self.panel.replace(ctx, "summary", summary);
+        let clear_area = ctx
+            .style()
+            .btn_plain
+            .text("Clear area")
+            .disabled(self.area.is_none())
+            .build_widget(ctx, "clear area");
+        self.panel.replace(ctx, "clear area", clear_area);
+    }
+}
+
+impl State<App> for BatchSpeedLimits {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Some(ref mut lasso) = self.lasso {
+            if let Some(polygon) = lasso.event(ctx) {
+                self.lasso = None;
+                self.area = Some(polygon.simplify(10.0));
+                self.recalculate(ctx, app);
+            }
+            return Transition::Keep;
+        }
+
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "Apply" => {
+                    let map = &app.primary.map;
+                    let speed: Speed = self.panel.dropdown_value("speed limit");
+                    let cmds: Vec<EditCmd> = self
+                        .roads
+                        .iter()
+                        .filter(|r| map.get_r(**r).speed_limit != speed)
+                        .map(|r| {
+                            map.edit_road_cmd(*r, |new| {
+                                new.speed_limit = speed;
+                            })
+                        })
+                        .collect();
+                    if cmds.is_empty() {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Nothing to change",
+                            vec![format!(
+                                "All of the selected roads already have a speed limit of {}",
+                                speed.to_string(&app.opts.units)
+                            )],
+                        ));
+                    }
+
+                    let num_roads = cmds.len();
+                    let mut edits = map.get_edits().clone();
+                    edits.commands.push(EditCmd::Group {
+                        description: format!(
+                            "speed limit of {} roads to {}",
+                            num_roads,
+                            speed.to_string(&app.opts.units)
+                        ),
+                        cmds,
+                    });
+                    apply_map_edits(ctx, app, edits);
+                    return Transition::Replace(PopupMsg::new_state(
+                        ctx,
+                        "Speed limits changed",
+                        vec![
+                            format!("Changed the speed limit of {} roads", num_roads),
+                            "Undo this like any other edit to restore all of them".to_string(),
+                        ],
+                    ));
+                }
+                "Cancel" => {
+                    return Transition::Pop;
+                }
+                "draw an area" => {
+                    self.lasso = Some(Lasso::new(Distance::meters(1.0)));
+                }
+                "clear area" => {
+                    self.area = None;
+                    self.recalculate(ctx, app);
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed(_) => {
+                self.recalculate(ctx, app);
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        if let Some(ref lasso) = self.lasso {
+            lasso.draw(g);
+        } else {
+            self.panel.draw(g);
+            CommonState::draw_osd(g, app);
+        }
+    }
+}
@@ -35,12 +35,13 @@ impl ChangeDuration {
             ]),
             Widget::row(vec![
                 "Duration:".text_widget(ctx).centered_vert(),
-                Spinner::widget(
+                Spinner::widget_with_units(
                     ctx,
                     "duration",
                     (signal.get_min_crossing_time(idx, i), Duration::minutes(5)),
                     signal.stages[idx].stage_type.simple_duration(),
                     Duration::seconds(1.0),
+                    app.opts.units,
                 ),
             ]),
             Line("Minimum time is set by the time required for crosswalk")
@@ -63,7 +64,7 @@ impl ChangeDuration {
                     "How much additional time can this stage last?"
                         .text_widget(ctx)
                         .centered_vert(),
-                    Spinner::widget(
+                    Spinner::widget_with_units(
                         ctx,
                         "additional",
                         (Duration::ZERO, Duration::minutes(5)),
@@ -72,13 +73,14 @@ impl ChangeDuration {
                             StageType::Variable(_, _, additional) => additional,
                         },
                         Duration::seconds(1.0),
+                        app.opts.units,
                     ),
                 ]),
                 Widget::row(vec![
                     "How long with no demand before the stage ends?"
                         .text_widget(ctx)
                         .centered_vert(),
-                    Spinner::widget(
+                    Spinner::widget_with_units(
                         ctx,
                         "delay",
                         (Duration::ZERO, Duration::seconds(300.0)),
@@ -87,6 +89,7 @@ impl ChangeDuration {
                             StageType::Variable(_, delay, _) => delay,
                         },
                         Duration::seconds(1.0),
+                        app.opts.units,
                     ),
                 ]),
             ])
@@ -204,6 +207,8 @@ pub fn edit_entire_signal(
         .as_ref()
         .map(|(path, _)| format!("import from GMNS {}", path));
     let gmns_all = "import all traffic signals from a new GMNS timing.csv";
+    let import_all = "import all traffic signals from a JSON or CSV timing file";
+    let export_all = "export all traffic signals to JSON and CSV";
 
     let mut choices = vec![use_template.to_string()];
     if has_sidewalks {
@@ -221,6 +226,8 @@ pub fn edit_entire_signal(
         choices.push(x);
     }
     choices.push(gmns_all.to_string());
+    choices.push(import_all.to_string());
+    choices.push(export_all.to_string());
 
     ChooseSomething::new_state(
         ctx,
@@ -413,6 +420,40 @@ pub fn edit_entire_signal(
                     }
                 }),
             )),
+            x if x == import_all => Transition::Replace(FilePicker::new_state(
+                ctx,
+                None,
+                Box::new(move |ctx, app, maybe_file| {
+                    if let Ok(Some((path, bytes))) = maybe_file {
+                        // Like the GMNS import, this overwrites changes for all intersections and
+                        // quits the current editor.
+                        Transition::Multi(vec![
+                            Transition::Pop,
+                            Transition::Pop,
+                            Transition::Push(
+                                crate::edit::traffic_signals::timing_files::import_all(
+                                    ctx, app, &path, bytes,
+                                ),
+                            ),
+                        ])
+                    } else {
+                        Transition::Pop
+                    }
+                }),
+            )),
+            x if x == export_all => Transition::Replace(
+                match crate::edit::traffic_signals::timing_files::export_all(app) {
+                    Ok(paths) => PopupMsg::new_state(
+                        ctx,
+                        "Signal timing exported",
+                        paths
+                            .into_iter()
+                            .map(|path| format!("Exported to {}", path))
+                            .collect(),
+                    ),
+                    Err(err) => PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()]),
+                },
+            ),
             _ => unreachable!(),
         }),
     )
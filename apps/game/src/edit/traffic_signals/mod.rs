@@ -1,4 +1,4 @@
-use std::collections::{BTreeSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
 use anyhow::Result;
 
@@ -7,8 +7,8 @@ use geom::{Distance, Line, Polygon, Pt2D};
 use map_gui::options::TrafficSignalStyle;
 use map_gui::render::{traffic_signal, DrawMovement, DrawOptions};
 use map_model::{
-    ControlTrafficSignal, EditIntersectionControl, IntersectionID, MovementID, Stage, StageType,
-    TurnPriority,
+    ControlTrafficSignal, EditIntersectionControl, Intersection, IntersectionID, MovementID, Stage,
+    StageType, TurnPriority,
 };
 use widgetry::tools::PopupMsg;
 use widgetry::{
@@ -27,6 +27,7 @@ mod gmns;
 mod offsets;
 mod picker;
 mod preview;
+mod timing_files;
 
 // Welcome to one of the most overwhelmingly complicated parts of the UI...
 
@@ -125,6 +126,19 @@ impl TrafficSignalEditor {
         self.change_stage(ctx, app, idx);
     }
 
+    fn set_hard_turn_phasing(&mut self, ctx: &mut EventCtx, app: &mut App, protected: bool) {
+        let idx = self.current_stage;
+        let driving_side = app.primary.map.get_config().driving_side;
+        let intersections: BTreeMap<IntersectionID, Intersection> = self
+            .members
+            .iter()
+            .map(|i| (*i, app.primary.map.get_i(*i).clone()))
+            .collect();
+        self.add_new_edit(ctx, app, idx, |ts| {
+            ts.stages[idx].set_hard_turn_phasing(&intersections[&ts.id], driving_side, protected);
+        });
+    }
+
     fn recalc_draw_current(&mut self, ctx: &mut EventCtx, app: &App) {
         let mut batch = GeomBatch::new();
         let mut movements = Vec::new();
@@ -242,6 +256,14 @@ impl State<App> for TrafficSignalEditor {
                         self.current_stage,
                     ));
                 }
+                "protect hard turns" => {
+                    self.set_hard_turn_phasing(ctx, app, true);
+                    return Transition::Keep;
+                }
+                "make hard turns permissive" => {
+                    self.set_hard_turn_phasing(ctx, app, false);
+                    return Transition::Keep;
+                }
                 "delete stage" => {
                     let idx = self.current_stage;
                     self.add_new_edit(ctx, app, 0, |ts| {
@@ -367,7 +389,7 @@ impl State<App> for TrafficSignalEditor {
                     // Might have to do this first!
                     app.primary
                         .map
-                        .recalculate_pathfinding_after_edits(&mut Timer::throwaway());
+                        .quickly_recalculate_pathfinding_after_edits(&mut Timer::throwaway());
 
                     return Transition::Push(preview::make_previewer(
                         ctx,
@@ -707,6 +729,18 @@ fn make_side_panel(
         .padding(10)
         .bg(app.cs.inner_panel_bg),
     );
+    col.push(Widget::row(vec![
+        ctx.style()
+            .btn_outline
+            .text("protect hard turns")
+            .tooltip("Protect turns across oncoming traffic")
+            .build_def(ctx),
+        ctx.style()
+            .btn_outline
+            .text("make hard turns permissive")
+            .tooltip("Turns across oncoming traffic wait for a big enough gap")
+            .build_def(ctx),
+    ]));
 
     let translations = squish_polygons_together(
         members
@@ -0,0 +1,62 @@
+use anyhow::Result;
+
+use map_model::perma_traffic_signal;
+use widgetry::tools::PopupMsg;
+use widgetry::{EventCtx, State};
+
+use crate::edit::apply_map_edits;
+use crate::App;
+
+/// Imports timing for many traffic signals at once, from either the JSON or CSV format described
+/// in `map_model::perma_traffic_signal`. Signals that don't match the map are skipped.
+pub fn import_all(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    path: &str,
+    bytes: Vec<u8>,
+) -> Box<dyn State<App>> {
+    let signals = if path.ends_with(".csv") {
+        perma_traffic_signal::from_csv(&bytes)
+    } else {
+        abstutil::from_json(&bytes)
+    };
+    let signals = match signals {
+        Ok(signals) => signals,
+        Err(err) => {
+            return PopupMsg::new_state(ctx, "Import failed", vec![err.to_string()]);
+        }
+    };
+
+    let (cmds, errors) = app.primary.map.import_traffic_signals(signals);
+    for err in &errors {
+        error!("Couldn't import signal timing: {}", err);
+    }
+    let successes = cmds.len();
+    let mut edits = app.primary.map.get_edits().clone();
+    edits.commands.extend(cmds);
+    apply_map_edits(ctx, app, edits);
+
+    PopupMsg::new_state(
+        ctx,
+        &format!("Import from {}", path),
+        vec![
+            format!("{} traffic signals successfully imported", successes),
+            format!("{} failures (check the console logs)", errors.len()),
+        ],
+    )
+}
+
+/// Writes the timing of every traffic signal in the map, as both JSON and CSV. Returns the paths
+/// written.
+pub fn export_all(app: &App) -> Result<Vec<String>> {
+    let map = &app.primary.map;
+    let signals = map.export_traffic_signals();
+    let name = map.get_name().as_filename();
+    let json_path = format!("traffic_signals_{}.json", name);
+    abstio::write_json(json_path.clone(), &signals);
+    let csv_path = abstio::write_file(
+        format!("traffic_signals_{}.csv", name),
+        perma_traffic_signal::to_csv(&signals)?,
+    )?;
+    Ok(vec![json_path, csv_path])
+}
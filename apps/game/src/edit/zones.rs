@@ -92,7 +92,7 @@ impl State<App> for ZoneEditor {
                         edits
                             .commands
                             .push(app.primary.map.edit_road_cmd(*r, |new| {
-                                new.access_restrictions = AccessRestrictions::new();
+                                new.access_restrictions.allow_through_traffic = EnumSet::all();
                             }));
                     }
 
@@ -104,12 +104,14 @@ impl State<App> for ZoneEditor {
                     // The original allow_through_traffic always includes this, and there's no way
                     // to exclude it, so stay consistent.
                     allow_through_traffic.insert(PathConstraints::Train);
-                    let new_access_restrictions = AccessRestrictions {
-                        allow_through_traffic,
-                    };
                     for r in &self.selector.roads {
                         let old_access_restrictions =
                             app.primary.map.get_r(*r).access_restrictions.clone();
+                        // Truck bans aren't part of the zone
+                        let new_access_restrictions = AccessRestrictions {
+                            allow_through_traffic,
+                            no_hgv: old_access_restrictions.no_hgv,
+                        };
                         if old_access_restrictions != new_access_restrictions {
                             edits
                                 .commands
@@ -28,6 +28,9 @@ fn info_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BuildingI
     }
     if app.opts.dev {
         kv.push(("OSM ID", format!("{}", b.orig_id.inner())));
+        if let Some(lu) = b.inferred_land_use {
+            kv.push(("Inferred land use", format!("{:?}", lu)));
+        }
     }
 
     let num_spots = b.num_parking_spots();
@@ -392,13 +392,11 @@ fn delay_plot(
     } else {
         app.primary.sim.time()
     };
-    if let Some(list) = data.intersection_delays.get(&i) {
-        for (_, t, dt, agent_type) in list {
-            if *t > limit {
-                break;
-            }
-            by_type.get_mut(agent_type).unwrap().push((*t, *dt));
+    for (_, t, dt, agent_type) in data.intersection_delays_for(i).iter() {
+        if *t > limit {
+            break;
         }
+        by_type.get_mut(agent_type).unwrap().push((*t, *dt));
     }
     let series: Vec<Series<Time, Duration>> = by_type
         .into_iter()
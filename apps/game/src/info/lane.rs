@@ -48,6 +48,14 @@ fn info_body(ctx: &EventCtx, app: &App, id: LaneID) -> Widget {
                 l.number_parking_spots(app.primary.map.get_config())
             ),
         ));
+    } else if r.shared_space {
+        kv.push((
+            "Speed limit",
+            format!(
+                "{} (shared space)",
+                r.vehicle_speed_limit().to_string(&app.opts.units)
+            ),
+        ));
     } else {
         kv.push(("Speed limit", r.speed_limit.to_string(&app.opts.units)));
     }
@@ -135,6 +143,12 @@ fn debug_body(ctx: &EventCtx, app: &App, id: LaneID) -> Widget {
             format!("{:?}", types.into_iter().collect::<Vec<_>>()),
         ));
     }
+    if let Some(turn_type) = l.dedicated_turn_type(map) {
+        kv.push((
+            "Dedicated turn lane".to_string(),
+            format!("{:?}", turn_type),
+        ));
+    }
     for (restriction, to) in &r.turn_restrictions {
         kv.push((
             format!("Restriction from this road to {}", to),
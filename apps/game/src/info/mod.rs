@@ -8,11 +8,11 @@ use map_model::{
     AreaID, BuildingID, IntersectionID, LaneID, ParkingLotID, TransitRouteID, TransitStopID,
 };
 use sim::{
-    AgentID, AgentType, Analytics, CarID, ParkingSpot, PedestrianID, PersonID, PersonState,
-    ProblemType, TripID, VehicleType,
+    AgentDecision, AgentID, AgentType, Analytics, CarID, ParkingSpot, PedestrianID, PersonID,
+    PersonState, ProblemType, TripID, VehicleType,
 };
 use widgetry::mapspace::{ToggleZoomed, ToggleZoomedBuilder};
-use widgetry::tools::open_browser;
+use widgetry::tools::{open_browser, PopupMsg};
 use widgetry::{
     Color, EventCtx, GfxCtx, Key, Line, LinePlot, Outcome, Panel, PlotOptions, Series, Text,
     TextExt, Toggle, Widget,
@@ -23,7 +23,7 @@ use crate::common::{color_for_agent_type, Warping};
 use crate::debug::path_counter::PathCounter;
 use crate::edit::{EditMode, RouteEditor};
 use crate::layer::PANEL_PLACEMENT;
-use crate::sandbox::{dashboards, GameplayMode, SandboxMode, TimeWarpScreen};
+use crate::sandbox::{dashboards, CinematicCamera, GameplayMode, SandboxMode, TimeWarpScreen};
 
 mod building;
 mod debug;
@@ -559,6 +559,53 @@ impl InfoPanel {
                     (false, None)
                 } else if action == "close" {
                     (true, None)
+                } else if action == "cinematic camera" {
+                    match self.tab {
+                        Tab::PersonTrips(p, _) | Tab::PersonBio(p) | Tab::PersonSchedule(p) => (
+                            true,
+                            Some(Transition::Push(CinematicCamera::new_state(ctx, app, p))),
+                        ),
+                        _ => (false, None),
+                    }
+                } else if action == "Trace decisions" || action == "Stop tracing decisions" {
+                    if let Tab::PersonBio(p) = self.tab {
+                        if let Some(agent) = app.primary.sim.person_to_agent(p) {
+                            if action == "Trace decisions" {
+                                app.primary.sim.start_tracing_agent(agent);
+                            } else {
+                                app.primary.sim.stop_tracing_agent(agent);
+                            }
+                        }
+                    }
+                    let mut new = InfoPanel::new(ctx, app, self.tab.clone(), ctx_actions);
+                    new.panel.restore(ctx, &self.panel);
+                    *self = new;
+                    (false, None)
+                } else if action == "Export decision log to JSON" {
+                    if let Tab::PersonBio(p) = self.tab {
+                        let sim = &app.primary.sim;
+                        let log: BTreeMap<String, &Vec<AgentDecision>> = sim
+                            .traced_agents_for_person(p)
+                            .into_iter()
+                            .map(|agent| (agent.to_string(), sim.get_agent_trace(agent).unwrap()))
+                            .collect();
+                        let path = format!(
+                            "{}/decisions_{}_{}.json",
+                            sim.save_dir(),
+                            p.0,
+                            sim.time().as_filename()
+                        );
+                        abstio::write_json(path.clone(), &log);
+                        return (
+                            false,
+                            Some(Transition::Push(PopupMsg::new_state(
+                                ctx,
+                                "Saved",
+                                vec![format!("Decision log saved to {}", path)],
+                            ))),
+                        );
+                    }
+                    (false, None)
                 } else if action == "jump to object" {
                     // TODO Messy way of doing this
                     if let Some(id) = self.tab.to_id(app) {
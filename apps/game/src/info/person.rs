@@ -380,11 +380,57 @@ fn bio_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: PersonID)
                     .into_widget(ctx),
             );
         }
+        rows.push(decision_log(ctx, app, id));
     }
 
     Widget::col(rows)
 }
 
+/// Trace the decisions a person's current agent makes, and show everything traced so far for any
+/// of their agents.
+fn decision_log(ctx: &mut EventCtx, app: &App, id: PersonID) -> Widget {
+    let sim = &app.primary.sim;
+    let mut col = vec![Line("Decision log").small_heading().into_widget(ctx)];
+    if let Some(agent) = sim.person_to_agent(id) {
+        col.push(if sim.is_tracing_agent(agent) {
+            ctx.style()
+                .btn_outline
+                .text("Stop tracing decisions")
+                .build_def(ctx)
+        } else {
+            ctx.style()
+                .btn_outline
+                .text(format!("Trace decisions of {}", agent))
+                .build_widget(ctx, "Trace decisions")
+        });
+    }
+
+    let traced = sim.traced_agents_for_person(id);
+    if !traced.is_empty() {
+        col.push(
+            ctx.style()
+                .btn_plain
+                .text("Export decision log to JSON")
+                .build_def(ctx),
+        );
+    }
+    for agent in traced {
+        let decisions = sim.get_agent_trace(agent).unwrap();
+        let mut txt = Text::from(Line(format!("{}: {} decisions", agent, decisions.len())));
+        // The latest decisions are usually the ones that need explaining
+        for decision in decisions.iter().rev().take(30) {
+            txt.add_line(Line(decision.time.ampm_tostring()).secondary());
+            txt.append(Line(format!(
+                " [{}] {}",
+                decision.decision_type.describe(),
+                decision.description
+            )));
+        }
+        col.push(txt.wrap_to_pct(ctx, 20).into_widget(ctx));
+    }
+    Widget::col(col).section(ctx)
+}
+
 pub fn schedule(
     ctx: &mut EventCtx,
     app: &App,
@@ -678,6 +724,12 @@ fn header(
                     .hotkey(Key::F)
                     .build_widget(ctx, "unfollow (pause the simulation)")
             },
+            ctx.style()
+                .btn_plain
+                .icon("system/assets/tools/maximize.svg")
+                .hotkey(Key::C)
+                .tooltip("Follow with a cinematic camera")
+                .build_widget(ctx, "cinematic camera"),
             ctx.style().btn_close_widget(ctx),
         ])
         .align_right(),
@@ -1,12 +1,13 @@
 use crate::ID;
 use abstutil::{prettyprint_usize, Counter};
-use geom::{Circle, Distance, Time};
+use geom::{Circle, Distance, Duration, Time};
 use map_gui::tools::ColorNetwork;
-use map_model::{PathStep, TransitRoute, TransitRouteID, TransitStopID};
+use map_model::{describe_cents, PathStep, TransitRoute, TransitRouteID, TransitStopID};
 use sim::{AgentID, CarID};
 use widgetry::{Color, ControlState, EventCtx, Key, Line, RewriteColor, Text, TextExt, Widget};
 
 use crate::app::App;
+use crate::common::cmp_duration_shorter;
 use crate::info::{header_btns, make_tabs, Details, Tab};
 
 pub fn stop(ctx: &mut EventCtx, app: &App, details: &mut Details, id: TransitStopID) -> Widget {
@@ -113,21 +114,21 @@ pub fn bus_status(ctx: &mut EventCtx, app: &App, details: &mut Details, id: CarI
 fn bus_status_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: CarID) -> Widget {
     let mut rows = vec![];
 
-    let route = app
-        .primary
-        .map
-        .get_tr(app.primary.sim.bus_route_id(id).unwrap());
-
-    rows.push(
-        ctx.style()
-            .btn_outline
-            .text(format!("Serves route {}", route.short_name))
-            .build_def(ctx),
-    );
-    details.hyperlinks.insert(
-        format!("Serves route {}", route.short_name),
-        Tab::TransitRoute(route.id),
-    );
+    if let Some(r) = app.primary.sim.bus_route_id(id) {
+        let route = app.primary.map.get_tr(r);
+        rows.push(
+            ctx.style()
+                .btn_outline
+                .text(format!("Serves route {}", route.short_name))
+                .build_def(ctx),
+        );
+        details.hyperlinks.insert(
+            format!("Serves route {}", route.short_name),
+            Tab::TransitRoute(route.id),
+        );
+    } else {
+        rows.push(Line("On-demand microtransit, no fixed route").into_widget(ctx));
+    }
 
     rows.push(
         Line(format!(
@@ -141,7 +142,7 @@ fn bus_status_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: Car
 }
 
 fn bus_header(ctx: &mut EventCtx, app: &App, details: &mut Details, id: CarID, tab: Tab) -> Widget {
-    let route = app.primary.sim.bus_route_id(id).unwrap();
+    let route = app.primary.sim.bus_route_id(id);
 
     if let Some(pt) = app
         .primary
@@ -153,11 +154,15 @@ fn bus_header(ctx: &mut EventCtx, app: &App, details: &mut Details, id: CarID, t
 
     let mut rows = vec![];
     rows.push(Widget::row(vec![
-        Line(format!(
-            "{} (route {})",
-            id,
-            app.primary.map.get_tr(route).short_name
-        ))
+        Line(if let Some(route) = route {
+            format!(
+                "{} (route {})",
+                id,
+                app.primary.map.get_tr(route).short_name
+            )
+        } else {
+            format!("{} (microtransit)", id)
+        })
         .small_heading()
         .into_widget(ctx),
         header_btns(ctx),
@@ -256,6 +261,60 @@ fn route_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: TransitR
         .into_widget(ctx),
     );
 
+    rows.push(format!("Fare: {}", route.fare.describe()).text_widget(ctx));
+    if let Some((riders, revenue)) = app
+        .primary
+        .sim
+        .get_analytics()
+        .ridership_and_revenue(app.primary.sim.time())
+        .get(&id)
+    {
+        rows.push(
+            Text::from_all(vec![
+                Line("Revenue"),
+                Line(format!(
+                    ": ${} from {} riders",
+                    describe_cents(*revenue),
+                    prettyprint_usize(*riders)
+                ))
+                .secondary(),
+            ])
+            .into_widget(ctx),
+        );
+    }
+
+    // Compare with the same buses before any edits, like new bus lanes or queue jumps
+    let now = app.primary.sim.time();
+    let journeys = app
+        .primary
+        .sim
+        .get_analytics()
+        .bus_journey_times(now, id, map);
+    if !journeys.is_empty() {
+        let mut txt = Text::from_all(vec![
+            Line("Journey time"),
+            Line(format!(
+                ": {} on average, over {} {}",
+                average_duration(&journeys).to_string(&app.opts.units),
+                prettyprint_usize(journeys.len()),
+                route.plural_noun()
+            ))
+            .secondary(),
+        ]);
+        if app.has_prebaked().is_some() {
+            let before = app.prebaked().bus_journey_times(now, id, map);
+            if !before.is_empty() {
+                txt.add_line(Line("Compared to before edits: "));
+                txt.append_all(cmp_duration_shorter(
+                    app,
+                    average_duration(&journeys),
+                    average_duration(&before),
+                ));
+            }
+        }
+        rows.push(txt.into_widget(ctx));
+    }
+
     rows.push(format!("{} stops", route.stops.len()).text_widget(ctx));
     {
         let i = map.get_i(map.get_l(route.start).src_i);
@@ -412,3 +471,10 @@ fn describe_schedule(route: &TransitRoute) -> Text {
     }
     txt
 }
+
+fn average_duration(journeys: &[(Time, Duration)]) -> Duration {
+    journeys
+        .iter()
+        .fold(Duration::ZERO, |sum, (_, dt)| sum + *dt)
+        / (journeys.len() as f64)
+}
@@ -5,7 +5,10 @@ use maplit::btreemap;
 use crate::ID;
 use geom::{Distance, Duration, Percent, Polygon, Pt2D, UnitFmt};
 use map_model::{Map, Path, PathStep, Traversable};
-use sim::{AgentID, Analytics, PersonID, Problem, TripID, TripInfo, TripPhase, TripPhaseType};
+use sim::{
+    AgentID, Analytics, PersonID, Problem, TripDelayCause, TripID, TripInfo, TripPhase,
+    TripPhaseType,
+};
 use synthpop::{TripEndpoint, TripMode};
 use widgetry::{
     Color, ControlState, DrawWithTooltips, EventCtx, GeomBatch, Line, LinePlot, PlotOptions,
@@ -326,6 +329,9 @@ pub fn finished(
             .force_width_window_pct(ctx, col_width),
             waiting.to_string(&app.opts.units).text_widget(ctx),
         ]));
+        if open_trips[&id].show_after {
+            col.push(describe_delays(ctx, app, id, details, col_width));
+        }
 
         col.push(Widget::custom_row(vec![
             Widget::custom_row(vec![Line("Purpose").secondary().into_widget(ctx)])
@@ -390,6 +396,82 @@ pub fn cancelled(
     Widget::col(col)
 }
 
+/// Explain where the trip lost time compared to free-flow conditions, with the worst delays
+/// linking to their location.
+fn describe_delays(
+    ctx: &mut EventCtx,
+    app: &App,
+    id: TripID,
+    details: &mut Details,
+    col_width: Percent,
+) -> Widget {
+    let map = &app.primary.map;
+    let breakdown = match app.primary.sim.explain_trip_delay(map, id) {
+        Ok(breakdown) => breakdown,
+        Err(err) => {
+            return Line(format!("Can't explain delays: {}", err))
+                .secondary()
+                .into_widget(ctx);
+        }
+    };
+
+    let mut col = vec![Widget::custom_row(vec![
+        Widget::custom_row(vec![Line("Free-flow time").secondary().into_widget(ctx)])
+            .force_width_window_pct(ctx, col_width),
+        breakdown
+            .free_flow_time
+            .to_string(&app.opts.units)
+            .text_widget(ctx),
+    ])];
+    if breakdown.delays.is_empty() {
+        return Widget::col(col);
+    }
+
+    col.push(Line("Biggest delays").secondary().into_widget(ctx));
+    // Don't overwhelm the panel; the full list is available headlessly
+    for (idx, (cause, delay)) in breakdown.delays.iter().take(5).enumerate() {
+        let (label, warp_to) = match cause {
+            TripDelayCause::Intersection(i) => (
+                format!(
+                    "waiting at {}",
+                    map.get_i(*i).name(app.opts.language.as_ref(), map)
+                ),
+                ID::Intersection(*i),
+            ),
+            TripDelayCause::Queueing(r) => (
+                format!(
+                    "queueing on {}",
+                    map.get_r(*r).get_name(app.opts.language.as_ref())
+                ),
+                ID::Road(*r),
+            ),
+            TripDelayCause::WaitingForTransit(ts) => (
+                format!("waiting for transit at {}", map.get_ts(*ts).name),
+                ID::TransitStop(*ts),
+            ),
+        };
+        let label = format!(
+            "{}. {} {}",
+            idx + 1,
+            delay.to_string(&app.opts.units),
+            label
+        );
+        col.push(ctx.style().btn_plain.text(&label).build_def(ctx));
+        details.warpers.insert(label, warp_to);
+    }
+    if breakdown.other > Duration::ZERO {
+        col.push(
+            Line(format!(
+                "{} lost elsewhere, like following slower traffic",
+                breakdown.other.to_string(&app.opts.units)
+            ))
+            .secondary()
+            .into_widget(ctx),
+        );
+    }
+    Widget::col(col)
+}
+
 fn describe_problems(
     ctx: &mut EventCtx,
     analytics: &Analytics,
@@ -718,10 +800,12 @@ fn make_timeline(
                     TripPhaseType::Walking => "system/assets/timeline/walking.svg",
                     TripPhaseType::Biking => "system/assets/timeline/biking.svg",
                     TripPhaseType::Parking => "system/assets/timeline/parking.svg",
-                    TripPhaseType::WaitingForBus(_, _) => {
+                    TripPhaseType::WaitingForBus(_, _) | TripPhaseType::WaitingForMicrotransit => {
                         "system/assets/timeline/waiting_for_bus.svg"
                     }
-                    TripPhaseType::RidingBus(_, _, _) => "system/assets/timeline/riding_bus.svg",
+                    TripPhaseType::RidingBus(_, _, _) | TripPhaseType::RidingMicrotransit(_) => {
+                        "system/assets/timeline/riding_bus.svg"
+                    }
                     TripPhaseType::Cancelled | TripPhaseType::Finished => unreachable!(),
                     TripPhaseType::DelayedStart => "system/assets/timeline/delayed_start.svg",
                 },
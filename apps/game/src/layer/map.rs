@@ -1,10 +1,16 @@
+use std::collections::BTreeSet;
+
 use maplit::btreeset;
 
 use crate::ID;
 use abstutil::{prettyprint_usize, Counter};
 use geom::{Distance, Time};
 use map_gui::tools::{ColorDiscrete, ColorNetwork};
-use map_model::{AmenityType, Direction, LaneType};
+use map_model::connectivity::{find_low_stress_islands, WheelchairAccess};
+use map_model::{
+    AccessibilityBarrier, AmenityType, Direction, LaneType, LevelOfTrafficStress, Map,
+    PathConstraints, RoadID,
+};
 use sim::AgentType;
 use widgetry::mapspace::ToggleZoomed;
 use widgetry::tools::ColorLegend;
@@ -323,28 +329,131 @@ impl Static {
         )
     }
 
-    pub fn high_stress(ctx: &mut EventCtx, app: &App) -> Static {
-        let mut colorer = ColorDiscrete::new(app, vec![("high stress", app.cs.edits_layer)]);
+    pub fn traffic_stress(ctx: &mut EventCtx, app: &App) -> Static {
+        let map = &app.primary.map;
+        let mut colorer = ColorDiscrete::new(
+            app,
+            LevelOfTrafficStress::all()
+                .into_iter()
+                .map(|lts| (lts.describe(), traffic_stress_color(lts)))
+                .collect(),
+        );
 
-        for r in app.primary.map.all_roads() {
-            if r.high_stress_for_bikes(&app.primary.map, Direction::Fwd)
-                || r.high_stress_for_bikes(&app.primary.map, Direction::Back)
+        for r in map.all_roads() {
+            // Show the worse direction
+            if let Some(lts) = [Direction::Fwd, Direction::Back]
+                .into_iter()
+                .filter_map(|dir| r.level_of_traffic_stress(map, dir, None))
+                .max()
             {
-                colorer.add_r(r.id, "high stress");
+                colorer.add_r(r.id, lts.describe());
             }
         }
 
+        let islands = find_low_stress_islands(map, LevelOfTrafficStress::LTS2);
+        Static::new(
+            ctx,
+            colorer,
+            "traffic stress",
+            "Level of traffic stress for cycling".to_string(),
+            Text::from_multiline(vec![
+                Line("Based on speed limits, lanes, and road type"),
+                Line(format!(
+                    "Largest connected low-stress network: {}",
+                    describe_network(map, islands.get(0))
+                )),
+            ])
+            .into_widget(ctx),
+        )
+    }
+
+    pub fn wheelchair_access(ctx: &mut EventCtx, app: &App) -> Static {
+        let map = &app.primary.map;
+        let access = WheelchairAccess::new(map);
+
+        let mut categories: Vec<(&str, Color)> = AccessibilityBarrier::all()
+            .into_iter()
+            .map(|barrier| (barrier.describe(), wheelchair_barrier_color(barrier)))
+            .collect();
+        categories.push(("unreachable by wheelchair", Color::RED));
+        let mut colorer = ColorDiscrete::new(app, categories);
+
+        for (r, barrier) in &access.barriers {
+            colorer.add_r(*r, barrier.describe());
+        }
+        for (_, i) in &access.blocked_crosswalks {
+            colorer.add_i(*i, AccessibilityBarrier::RaisedKerb.describe());
+        }
+        for b in &access.unreachable {
+            colorer.add_b(*b, "unreachable by wheelchair");
+        }
+
         Static::new(
             ctx,
             colorer,
-            "high stress",
-            "High stress roads for biking".to_string(),
+            "wheelchair access",
+            "Wheelchair access".to_string(),
             Text::from_multiline(vec![
-                Line("High stress defined as:"),
-                Line("- arterial classification"),
-                Line("- no dedicated cycle lane"),
+                Line("Avoiding steps, steep grades, rough surfaces, and raised kerbs"),
+                Line(format!(
+                    "{} buildings reachable by wheelchair",
+                    prettyprint_usize(access.num_reachable)
+                )),
+                Line(format!(
+                    "{} more buildings reachable on foot",
+                    prettyprint_usize(access.unreachable.len())
+                )),
+                Line(format!(
+                    "{} crosswalks with raised kerbs",
+                    prettyprint_usize(access.blocked_crosswalks.len())
+                )),
             ])
             .into_widget(ctx),
         )
     }
 }
+
+fn wheelchair_barrier_color(barrier: AccessibilityBarrier) -> Color {
+    match barrier {
+        AccessibilityBarrier::Steps => Color::PURPLE,
+        AccessibilityBarrier::SteepIncline => Color::ORANGE,
+        AccessibilityBarrier::RoughSurface => Color::YELLOW,
+        AccessibilityBarrier::RaisedKerb => Color::BLUE,
+        AccessibilityBarrier::TaggedInaccessible => Color::PINK,
+    }
+}
+
+pub fn traffic_stress_color(lts: LevelOfTrafficStress) -> Color {
+    match lts {
+        LevelOfTrafficStress::LTS1 => Color::hex("#1A9641"),
+        LevelOfTrafficStress::LTS2 => Color::hex("#A6D96A"),
+        LevelOfTrafficStress::LTS3 => Color::hex("#FDAE61"),
+        LevelOfTrafficStress::LTS4 => Color::hex("#D7191C"),
+    }
+}
+
+/// The length of some roads, compared to all roads usable by bikes
+pub fn describe_network(map: &Map, roads: Option<&BTreeSet<RoadID>>) -> String {
+    let mut total = Distance::ZERO;
+    for r in map.all_roads() {
+        if r.lanes
+            .iter()
+            .any(|l| PathConstraints::Bike.can_use(l, map))
+        {
+            total += r.length();
+        }
+    }
+    let network = roads
+        .into_iter()
+        .flatten()
+        .fold(Distance::ZERO, |sum, r| sum + map.get_r(*r).length());
+    format!(
+        "{} ({}% of bikeable roads)",
+        network,
+        if total == Distance::ZERO {
+            0.0
+        } else {
+            (100.0 * (network / total)).round()
+        }
+    )
+}
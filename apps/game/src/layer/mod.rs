@@ -16,6 +16,7 @@ mod parking;
 mod population;
 mod problems;
 mod problems_diff;
+mod realtime;
 pub mod traffic;
 pub mod transit;
 
@@ -133,9 +134,12 @@ impl PickLayer {
                     btn("steep streets", Key::V),
                     btn("elevation", Key::G),
                     btn("parking efficiency", Key::O),
+                    btn("parking search", Key::I),
+                    btn("bike parking", Key::Q),
                     btn("blackholes", Key::L),
                     btn("problem map", Key::K),
-                    btn("high stress", Key::H),
+                    btn("traffic stress", Key::H),
+                    btn("wheelchair access", Key::Num1),
                     if app.primary.sim.get_pandemic_model().is_some() {
                         btn("pandemic model", Key::Y)
                     } else {
@@ -146,6 +150,7 @@ impl PickLayer {
                     "Data".text_widget(ctx),
                     btn("traffic signal demand", Key::M),
                     btn("commuter patterns", Key::R),
+                    btn("live transit", Key::W),
                 ]),
             ])
             .evenly_spaced(),
@@ -194,8 +199,11 @@ impl State<App> for PickLayer {
                 "no sidewalks" => {
                     app.primary.layer = Some(Box::new(map::Static::no_sidewalks(ctx, app)));
                 }
-                "high stress" => {
-                    app.primary.layer = Some(Box::new(map::Static::high_stress(ctx, app)));
+                "traffic stress" => {
+                    app.primary.layer = Some(Box::new(map::Static::traffic_stress(ctx, app)));
+                }
+                "wheelchair access" => {
+                    app.primary.layer = Some(Box::new(map::Static::wheelchair_access(ctx, app)));
                 }
                 "favorite buildings" => {
                     app.primary.layer = Some(Box::new(favorites::ShowFavorites::new(ctx, app)));
@@ -221,6 +229,12 @@ impl State<App> for PickLayer {
                 "parking efficiency" => {
                     app.primary.layer = Some(Box::new(parking::Efficiency::new(ctx, app)));
                 }
+                "parking search" => {
+                    app.primary.layer = Some(Box::new(parking::Cruising::new(ctx, app)));
+                }
+                "bike parking" => {
+                    app.primary.layer = Some(Box::new(parking::BikeParking::new(ctx, app)));
+                }
                 "population map" => {
                     app.primary.layer = Some(Box::new(population::PopulationMap::new(
                         ctx,
@@ -260,6 +274,12 @@ impl State<App> for PickLayer {
                 "commuter patterns" => {
                     return Transition::Replace(dashboards::CommuterPatterns::new_state(ctx, app));
                 }
+                "live transit" => {
+                    return Transition::Multi(vec![
+                        Transition::Pop,
+                        realtime::LiveTransit::prompt_url(ctx),
+                    ]);
+                }
                 _ => unreachable!(),
             },
             _ => {
@@ -401,3 +401,223 @@ impl Efficiency {
         }
     }
 }
+
+/// Where do drivers spend the longest circling for a parking spot?
+pub struct Cruising {
+    time: Time,
+    draw: ToggleZoomed,
+    panel: Panel,
+}
+
+impl Layer for Cruising {
+    fn name(&self) -> Option<&'static str> {
+        Some("parking search")
+    }
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = Cruising::new(ctx, app);
+        }
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                _ => unreachable!(),
+            }
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+        self.draw.draw(g);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw.unzoomed);
+    }
+}
+
+impl Cruising {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Cruising {
+        let per_road = app
+            .primary
+            .sim
+            .get_analytics()
+            .parking_search_per_road(app.primary.sim.time(), &app.primary.map);
+
+        let mut total_time = Duration::ZERO;
+        let mut total_searches = 0;
+        let mut colorer = ColorNetwork::new(app);
+        for (r, (time, searches)) in per_road {
+            total_time += time;
+            total_searches += searches;
+            let avg = time / (searches as f64);
+            colorer.add_r(
+                r,
+                app.cs
+                    .good_to_bad_red
+                    .eval((avg / Duration::minutes(10)).min(1.0)),
+            );
+        }
+
+        let mut txt = Text::from(Line(
+            "How long do drivers search for parking near their destination? (minutes)",
+        ));
+        if total_searches == 0 {
+            txt.add_line(Line("Nobody has parked yet").secondary());
+        } else {
+            txt.add_line(Line(format!(
+                "{} drivers spent {} searching in total, {} on average",
+                prettyprint_usize(total_searches),
+                total_time,
+                total_time / (total_searches as f64)
+            )));
+        }
+        if !app.primary.sim.cruising_for_parking() {
+            txt.add_line(
+                Line("Drivers know where free spots are, so they don't circle to find one")
+                    .secondary(),
+            );
+        }
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            header(ctx, "Parking search"),
+            txt.wrap_to_pct(ctx, 15).into_widget(ctx),
+            ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["0", "3", "6", "10+"]),
+        ]))
+        .aligned_pair(PANEL_PLACEMENT)
+        .build(ctx);
+
+        Cruising {
+            time: app.primary.sim.time(),
+            draw: colorer.build(ctx),
+            panel,
+        }
+    }
+}
+
+/// Where do cyclists fail to find a free bike rack near their destination?
+pub struct BikeParking {
+    time: Time,
+    draw: ToggleZoomed,
+    panel: Panel,
+}
+
+impl Layer for BikeParking {
+    fn name(&self) -> Option<&'static str> {
+        Some("bike parking")
+    }
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = BikeParking::new(ctx, app);
+        }
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                _ => unreachable!(),
+            }
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+        self.draw.draw(g);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw.unzoomed);
+    }
+}
+
+impl BikeParking {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> BikeParking {
+        let map = &app.primary.map;
+        let per_road = app
+            .primary
+            .sim
+            .get_analytics()
+            .bike_parking_per_road(app.primary.sim.time(), map);
+
+        let mut total_arrivals = 0;
+        let mut total_other_rack = 0;
+        let mut total_informal = 0;
+        let mut colorer = ColorNetwork::new(app);
+        for (r, demand) in per_road {
+            total_arrivals += demand.arrivals;
+            total_other_rack += demand.used_other_rack;
+            total_informal += demand.parked_informally;
+            if demand.unmet() > 0 {
+                colorer.add_r(
+                    r,
+                    app.cs
+                        .good_to_bad_red
+                        .eval((demand.unmet() as f64) / (demand.arrivals as f64)),
+                );
+            }
+        }
+
+        // Full racks are red, racks with free spots green
+        let mut full_racks = 0;
+        let racks = app.primary.sim.all_bike_racks();
+        for (b, (occupied, capacity)) in &racks {
+            let color = if occupied >= capacity {
+                full_racks += 1;
+                app.cs.good_to_bad_red.eval(1.0)
+            } else {
+                app.cs.good_to_bad_red.eval(0.0)
+            };
+            let pt = map.get_b(*b).label_center;
+            colorer
+                .draw
+                .unzoomed
+                .push(color, Circle::new(pt, Distance::meters(8.0)).to_polygon());
+            colorer.draw.zoomed.push(
+                color.alpha(0.5),
+                Circle::new(pt, Distance::meters(3.0)).to_polygon(),
+            );
+        }
+
+        let mut txt = Text::from(Line(
+            "What share of cyclists can't park at a rack near their destination?",
+        ));
+        if !app.primary.sim.modeling_bike_parking() {
+            txt.add_line(
+                Line(
+                    "Bike parking isn't being modeled; cyclists leave bikes outside every building",
+                )
+                .secondary(),
+            );
+        } else if total_arrivals == 0 {
+            txt.add_line(Line("Nobody has parked a bike yet").secondary());
+        } else {
+            txt.add_line(Line(format!(
+                "{} of {} bike racks are full",
+                prettyprint_usize(full_racks),
+                prettyprint_usize(racks.len())
+            )));
+            txt.add_line(Line(format!(
+                "{} cyclists parked, {} used a rack near a different building, and {} found no \
+                 free rack nearby",
+                prettyprint_usize(total_arrivals),
+                prettyprint_usize(total_other_rack),
+                prettyprint_usize(total_informal)
+            )));
+        }
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            header(ctx, "Bike parking"),
+            txt.wrap_to_pct(ctx, 15).into_widget(ctx),
+            ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["0%", "50%", "100%"]),
+        ]))
+        .aligned_pair(PANEL_PLACEMENT)
+        .build(ctx);
+
+        BikeParking {
+            time: app.primary.sim.time(),
+            draw: colorer.build(ctx),
+            panel,
+        }
+    }
+}
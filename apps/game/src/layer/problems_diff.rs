@@ -160,7 +160,7 @@ impl RelativeProblemMap {
 
         let mut colorer = ColorNetwork::new(app);
 
-        let scale = DivergingScale::new(Color::hex("#5D9630"), Color::WHITE, Color::hex("#A32015"))
+        let scale = DivergingScale::new(app.cs.better, Color::WHITE, app.cs.worse)
             .range(0.0, 2.0)
             .ignore(0.7, 1.3);
 
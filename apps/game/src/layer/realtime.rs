@@ -0,0 +1,302 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+use prost::Message;
+
+use geom::{Circle, Distance, Duration, LonLat, Pt2D};
+use map_model::Map;
+use widgetry::mapspace::ToggleZoomed;
+use widgetry::tools::{ColorLegend, FutureLoader, PopupMsg, PromptInput};
+use widgetry::{Color, EventCtx, GfxCtx, Line, Outcome, Panel, State, Text, Widget};
+
+use crate::app::{App, Transition};
+use crate::layer::{header, Layer, LayerOutcome, PANEL_PLACEMENT};
+
+/// Shows the actual positions and delays of transit vehicles from a GTFS-realtime feed, to compare
+/// the simulated buses against reality.
+pub struct LiveTransit {
+    url: String,
+    draw: ToggleZoomed,
+    panel: Panel,
+}
+
+impl Layer for LiveTransit {
+    fn name(&self) -> Option<&'static str> {
+        Some("live transit")
+    }
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Option<LayerOutcome> {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => Some(LayerOutcome::Close),
+                "refresh" => Some(LayerOutcome::Transition(Transition::Push(fetch_feed(
+                    ctx,
+                    self.url.clone(),
+                )))),
+                _ => unreachable!(),
+            },
+            _ => None,
+        }
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+        self.draw.draw(g);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw.unzoomed);
+    }
+}
+
+impl LiveTransit {
+    /// Asks for the URL of a GTFS-realtime feed, then loads it.
+    pub fn prompt_url(ctx: &mut EventCtx) -> Transition {
+        Transition::Push(PromptInput::new_state(
+            ctx,
+            "URL of a GTFS-realtime vehicle positions feed",
+            String::new(),
+            Box::new(|url, ctx, _| Transition::Replace(fetch_feed(ctx, url))),
+        ))
+    }
+
+    fn new(ctx: &mut EventCtx, app: &App, url: String, vehicles: Vec<LiveVehicle>) -> LiveTransit {
+        let map = &app.primary.map;
+        let mut draw = ToggleZoomed::builder();
+        let unzoomed_circle = Circle::new(Pt2D::new(0.0, 0.0), Distance::meters(15.0)).to_polygon();
+        let zoomed_circle = Circle::new(Pt2D::new(0.0, 0.0), Distance::meters(3.0)).to_polygon();
+        for vehicle in &vehicles {
+            let color = match vehicle.delay {
+                Some(delay) => app
+                    .cs
+                    .good_to_bad_red
+                    .eval((delay / Duration::minutes(10)).clamp(0.0, 1.0)),
+                None => Color::grey(0.5),
+            };
+            let pt = vehicle.pos.to_pt(map.get_gps_bounds());
+            draw.unzoomed
+                .push(color.alpha(0.8), unzoomed_circle.translate(pt.x(), pt.y()));
+            draw.zoomed
+                .push(color.alpha(0.8), zoomed_circle.translate(pt.x(), pt.y()));
+        }
+
+        // Compare the number of vehicles per route
+        let mut live_per_route: HashMap<&str, usize> = HashMap::new();
+        for vehicle in &vehicles {
+            if let Some(ref route) = vehicle.route_id {
+                *live_per_route.entry(route.as_str()).or_insert(0) += 1;
+            }
+        }
+        let mut comparisons = BTreeMap::new();
+        for route in map.all_transit_routes() {
+            let live = live_per_route
+                .get(route.gtfs_id.as_str())
+                .cloned()
+                .unwrap_or(0);
+            let simulated = app.primary.sim.status_of_buses(route.id, map).len();
+            if live > 0 || simulated > 0 {
+                // Different directions of a route often share a name
+                let entry = comparisons
+                    .entry(route.short_name.clone())
+                    .or_insert((0, 0));
+                entry.0 += live;
+                entry.1 += simulated;
+            }
+        }
+
+        let mut txt = Text::from(format!("{} vehicles in the live feed", vehicles.len()));
+        let with_delay: Vec<Duration> = vehicles.iter().filter_map(|v| v.delay).collect();
+        if !with_delay.is_empty() {
+            let avg = with_delay
+                .iter()
+                .fold(Duration::ZERO, |sum, delay| sum + *delay)
+                / (with_delay.len() as f64);
+            txt.add_line(format!("Average delay: {}", avg));
+        }
+        // Only rebuilt when a new snapshot of the feed is loaded, so the simulated buses are
+        // counted then
+        txt.add_line(
+            Line(format!(
+                "Live / simulated vehicles per route, at {}",
+                app.primary.sim.time().ampm_tostring()
+            ))
+            .secondary(),
+        );
+        for (name, (live, simulated)) in comparisons {
+            txt.add_line(format!("{}: {} / {}", name, live, simulated));
+        }
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            header(ctx, "Live transit"),
+            txt.into_widget(ctx),
+            ColorLegend::gradient(
+                ctx,
+                &app.cs.good_to_bad_red,
+                vec!["on time", "5 mins late", "10+ mins late"],
+            ),
+            ColorLegend::row(ctx, Color::grey(0.5), "unknown delay"),
+            ctx.style().btn_outline.text("refresh").build_def(ctx),
+        ]))
+        .aligned_pair(PANEL_PLACEMENT)
+        .build(ctx);
+
+        LiveTransit {
+            url,
+            draw: draw.build(ctx),
+            panel,
+        }
+    }
+}
+
+struct LiveVehicle {
+    pos: LonLat,
+    route_id: Option<String>,
+    // Positive means late. Early vehicles are negative.
+    delay: Option<Duration>,
+}
+
+fn fetch_feed(ctx: &mut EventCtx, url: String) -> Box<dyn State<App>> {
+    let (_, outer_progress_rx) = futures_channel::mpsc::channel(1);
+    let (_, inner_progress_rx) = futures_channel::mpsc::channel(1);
+    let url_copy = url.clone();
+    FutureLoader::<App, Vec<u8>>::new_state(
+        ctx,
+        Box::pin(async move {
+            let bytes = abstio::http_get(url_copy).await?;
+            let wrapper: Box<dyn Send + FnOnce(&App) -> Vec<u8>> = Box::new(move |_| bytes);
+            Ok(wrapper)
+        }),
+        outer_progress_rx,
+        inner_progress_rx,
+        "Downloading GTFS-realtime feed",
+        Box::new(move |ctx, app, result| {
+            match result.and_then(|bytes| parse_feed(&app.primary.map, &bytes)) {
+                Ok(vehicles) => {
+                    app.primary.layer = Some(Box::new(LiveTransit::new(ctx, app, url, vehicles)));
+                    Transition::Pop
+                }
+                Err(err) => Transition::Replace(PopupMsg::new_state(
+                    ctx,
+                    "Error",
+                    vec![format!("Couldn't load the GTFS-realtime feed: {}", err)],
+                )),
+            }
+        }),
+    )
+}
+
+/// Extracts the vehicles within the map's boundary, matching them to trip updates to find delays.
+fn parse_feed(map: &Map, bytes: &[u8]) -> Result<Vec<LiveVehicle>> {
+    let feed = gtfs_rt::FeedMessage::decode(bytes)?;
+    let num_entities = feed.entity.len();
+
+    let mut delay_per_trip: HashMap<String, Duration> = HashMap::new();
+    for entity in &feed.entity {
+        if let Some(ref update) = entity.trip_update {
+            // Feeds usually drop stops already visited, so the first update is the next stop
+            let delay = update.delay.or_else(|| {
+                let stop = update.stop_time_update.get(0)?;
+                stop.arrival
+                    .as_ref()
+                    .and_then(|event| event.delay)
+                    .or_else(|| stop.departure.as_ref()?.delay)
+            });
+            if let (Some(trip), Some(delay)) = (update.trip.trip_id.clone(), delay) {
+                delay_per_trip.insert(trip, Duration::seconds(delay as f64));
+            }
+        }
+    }
+
+    let mut vehicles = Vec::new();
+    for entity in feed.entity {
+        let vehicle = match entity.vehicle {
+            Some(vehicle) => vehicle,
+            None => continue,
+        };
+        let position = match vehicle.position {
+            Some(position) => position,
+            None => continue,
+        };
+        let pos = LonLat::new(position.longitude as f64, position.latitude as f64);
+        if !map.get_gps_bounds().contains(pos) {
+            continue;
+        }
+        let trip = vehicle.trip.unwrap_or_default();
+        vehicles.push(LiveVehicle {
+            pos,
+            route_id: trip.route_id,
+            delay: trip.trip_id.and_then(|id| delay_per_trip.get(&id).cloned()),
+        });
+    }
+    info!(
+        "{} live vehicles from {} feed entities are within the map",
+        vehicles.len(),
+        num_entities
+    );
+    Ok(vehicles)
+}
+
+/// The subset of the GTFS-realtime protobuf schema
+/// (https://gtfs.org/realtime/proto/) needed to read vehicle positions and delays.
+mod gtfs_rt {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct FeedMessage {
+        #[prost(message, repeated, tag = "2")]
+        pub entity: Vec<FeedEntity>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct FeedEntity {
+        #[prost(string, required, tag = "1")]
+        pub id: String,
+        #[prost(message, optional, tag = "3")]
+        pub trip_update: Option<TripUpdate>,
+        #[prost(message, optional, tag = "4")]
+        pub vehicle: Option<VehiclePosition>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TripUpdate {
+        #[prost(message, required, tag = "1")]
+        pub trip: TripDescriptor,
+        #[prost(message, repeated, tag = "2")]
+        pub stop_time_update: Vec<StopTimeUpdate>,
+        #[prost(int32, optional, tag = "5")]
+        pub delay: Option<i32>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StopTimeUpdate {
+        #[prost(message, optional, tag = "2")]
+        pub arrival: Option<StopTimeEvent>,
+        #[prost(message, optional, tag = "3")]
+        pub departure: Option<StopTimeEvent>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StopTimeEvent {
+        #[prost(int32, optional, tag = "1")]
+        pub delay: Option<i32>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct VehiclePosition {
+        #[prost(message, optional, tag = "1")]
+        pub trip: Option<TripDescriptor>,
+        #[prost(message, optional, tag = "2")]
+        pub position: Option<Position>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Position {
+        #[prost(float, required, tag = "1")]
+        pub latitude: f32,
+        #[prost(float, required, tag = "2")]
+        pub longitude: f32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TripDescriptor {
+        #[prost(string, optional, tag = "1")]
+        pub trip_id: Option<String>,
+        #[prost(string, optional, tag = "5")]
+        pub route_id: Option<String>,
+    }
+}
@@ -12,7 +12,10 @@ use sim::{AgentType, VehicleType};
 use widgetry::mapspace::ToggleZoomed;
 use widgetry::mapspace::{DummyID, World};
 use widgetry::tools::{ColorLegend, DivergingScale, PopupMsg};
-use widgetry::{Color, EventCtx, GfxCtx, Line, Outcome, Panel, Text, TextExt, Toggle, Widget};
+use widgetry::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, Line, Outcome, Panel, Text, TextExt, Toggle,
+    Widget,
+};
 
 use crate::app::{App, Transition};
 use crate::layer::{header, Layer, LayerOutcome, PANEL_PLACEMENT};
@@ -381,7 +384,7 @@ impl CompareThroughput {
 
         let mut colorer = ColorNetwork::new(app);
 
-        let scale = DivergingScale::new(Color::hex("#5D9630"), Color::WHITE, Color::hex("#A32015"))
+        let scale = DivergingScale::new(app.cs.better, Color::WHITE, app.cs.worse)
             .range(0.0, 2.0)
             .ignore(0.7, 1.3);
 
@@ -623,6 +626,8 @@ pub struct PedestrianCrowding {
     time: Time,
     panel: Panel,
     world: World<DummyID>,
+    // The world's hitboxes aren't drawn on the minimap, so mirror them separately
+    minimap: Drawable,
 }
 
 impl Layer for PedestrianCrowding {
@@ -652,8 +657,9 @@ impl Layer for PedestrianCrowding {
         self.panel.draw(g);
         self.world.draw(g);
     }
-    // TODO This doesn't seem to be showing up
-    fn draw_minimap(&self, _: &mut GfxCtx) {}
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.minimap);
+    }
 }
 
 impl PedestrianCrowding {
@@ -689,6 +695,7 @@ impl PedestrianCrowding {
             (x * 10.0).ceil() / 10.0
         }
 
+        let mut minimap = GeomBatch::new();
         let (roads, intersections) = app.primary.sim.get_pedestrian_density(map);
         let mut max_density: f64 = 0.0;
         for (r, density) in roads {
@@ -701,6 +708,7 @@ impl PedestrianCrowding {
                 .iter()
                 .find(|pair| pair.0 == bucket(density))
                 .unwrap();
+            minimap.push(*color, map.get_r(r).get_thick_polygon());
             world
                 .add_unnamed()
                 .hitbox(map.get_r(r).get_thick_polygon())
@@ -718,6 +726,7 @@ impl PedestrianCrowding {
                 .iter()
                 .find(|pair| pair.0 == bucket(density))
                 .unwrap();
+            minimap.push(*color, map.get_i(i).polygon.clone());
             world
                 .add_unnamed()
                 .hitbox(map.get_i(i).polygon.clone())
@@ -731,6 +740,7 @@ impl PedestrianCrowding {
         Self {
             time: app.primary.sim.time(),
             world,
+            minimap: ctx.upload(minimap),
             panel: Panel::new_builder(Widget::col(vec![
                 header(ctx, "Pedestrian crowding"),
                 "(people / m²)".text_widget(ctx),
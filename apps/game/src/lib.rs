@@ -23,7 +23,7 @@ use crate::app::{App, Flags, PerMap};
 use crate::common::jump_to_time_upon_startup;
 use crate::id::ID;
 use crate::pregame::TitleScreen;
-use crate::sandbox::{GameplayMode, SandboxMode};
+use crate::sandbox::{GameplayMode, RunScript, SandboxMode};
 
 mod app;
 mod challenges;
@@ -62,12 +62,23 @@ struct Args {
     /// Load the map at this path as a secondary debug map to compare to the main one
     #[structopt(long = "diff")]
     diff_map: Option<String>,
+    /// Once the simulation starts, run this Rhai script to spawn trips, apply edits, and move the
+    /// camera at scheduled times
+    #[structopt(long)]
+    script: Option<String>,
     /// Print raw widgetry events to the console for debugging
     #[structopt(long)]
     dump_raw_events: bool,
     /// Override the monitor's auto-detected scale factor
     #[structopt(long)]
     scale_factor: Option<f64>,
+    /// Record all input and camera movement to a replay file at this path
+    #[structopt(long)]
+    record_replay: Option<String>,
+    /// Play back a replay file recorded with --record-replay. All other flags are ignored; the
+    /// ones used while recording apply instead.
+    #[structopt(long)]
+    replay: Option<String>,
 
     /// Dev mode exposes experimental tools useful for debugging, but that'd likely confuse most
     /// players.
@@ -122,6 +133,21 @@ struct Args {
     compare_counts: Option<Vec<String>>,
 }
 
+/// The command-line arguments to store in a replay, so playback starts the same way
+fn args_without_recording(raw_args: Vec<String>) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut iter = raw_args.into_iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--record-replay" {
+            // Skip the path too
+            iter.next();
+        } else if !arg.starts_with("--record-replay=") {
+            result.push(arg);
+        }
+    }
+    result
+}
+
 struct Setup {
     flags: Flags,
     opts: Options,
@@ -130,6 +156,7 @@ struct Setup {
     center_camera: Option<String>,
     start_time: Option<Duration>,
     diff_map: Option<String>,
+    script: Option<String>,
     mode: Mode,
 }
 
@@ -159,7 +186,25 @@ fn run(mut settings: Settings) {
         // This is approximately how much the 3 top panels in sandbox mode require.
         .require_minimum_width(1500.0);
 
-    let mut args = Args::from_iter(abstutil::cli_args());
+    let raw_args: Vec<String> = abstutil::cli_args()
+        .map(|x| x.to_string_lossy().to_string())
+        .collect();
+    let mut args = Args::from_iter(raw_args.clone());
+    if let Some(path) = args.replay.take() {
+        let replay = widgetry::Replay::load(&path)
+            .unwrap_or_else(|err| panic!("Couldn't load replay {}: {}", path, err));
+        // Start exactly like the recording did
+        let recorded_args: Vec<String> = serde_json::from_str(&replay.metadata).unwrap();
+        args = Args::from_iter(recorded_args);
+        settings = settings
+            .scale_factor(replay.scale_factor)
+            .play_replay(replay);
+    } else if let Some(path) = args.record_replay.take() {
+        settings = settings.record_replay(
+            path,
+            serde_json::to_string(&args_without_recording(raw_args)).unwrap(),
+        );
+    }
     args.flags.sim_flags.initialize();
 
     if args.prebake {
@@ -175,6 +220,7 @@ fn run(mut settings: Settings) {
         center_camera: args.cam,
         start_time: args.start_time,
         diff_map: args.diff_map,
+        script: args.script,
         mode: if args.tutorial_intro {
             Mode::TutorialIntro
         } else if args.challenges {
@@ -293,23 +339,27 @@ fn setup_app(ctx: &mut EventCtx, mut setup: Setup) -> (App, Vec<Box<dyn State<Ap
         }
     }
 
-    // If we're starting directly in a challenge mode, the tutorial, or by playing a scenario,
-    // usually time is midnight, so save some effort and start with the correct color scheme. If
-    // we're loading a savestate and it's actually daytime, we'll pay a small penalty to switch
-    // colors.
-    if let Mode::Gameplay(
-        GameplayMode::PlayScenario(_, _, _)
-        | GameplayMode::FixTrafficSignals
-        | GameplayMode::OptimizeCommute(_, _)
-        | GameplayMode::Tutorial(_),
-    ) = setup.mode
-    {
-        setup.opts.color_scheme = map_gui::colors::ColorSchemeChoice::NightMode;
-    }
-    if setup.mode != Mode::SomethingElse {
-        setup.opts.color_scheme = map_gui::colors::ColorSchemeChoice::DayMode;
+    // Unless the colors should follow the time of day, respect the scheme the player picked in
+    // the settings.
+    if setup.opts.toggle_day_night_colors {
+        // If we're starting directly in a challenge mode, the tutorial, or by playing a scenario,
+        // usually time is midnight, so save some effort and start with the correct color scheme.
+        // If we're loading a savestate and it's actually daytime, we'll pay a small penalty to
+        // switch colors.
+        if let Mode::Gameplay(
+            GameplayMode::PlayScenario(_, _, _)
+            | GameplayMode::FixTrafficSignals
+            | GameplayMode::OptimizeCommute(_, _)
+            | GameplayMode::Tutorial(_),
+        ) = setup.mode
+        {
+            setup.opts.color_scheme = map_gui::colors::ColorSchemeChoice::NightMode;
+        } else {
+            setup.opts.color_scheme = map_gui::colors::ColorSchemeChoice::DayMode;
+        }
     }
-    let cs = map_gui::colors::ColorScheme::new(ctx, setup.opts.color_scheme);
+    setup.opts.apply_ui_language(ctx);
+    let cs = map_gui::colors::ColorScheme::from_options(ctx, &setup.opts);
 
     // No web support; this uses blocking IO
     let secondary = setup.diff_map.as_ref().map(|path| {
@@ -513,6 +563,7 @@ fn finish_app_setup(
         return vec![TitleScreen::new_state(ctx, app)];
     }
 
+    let script = setup.script.clone();
     let state = if let Some(ss) = savestate {
         app.primary.sim = ss;
         SandboxMode::start_from_savestate(app)
@@ -523,12 +574,20 @@ fn finish_app_setup(
                     SandboxMode::async_new(
                         app,
                         gameplay,
-                        jump_to_time_upon_startup(Duration::hours(8)),
+                        and_run_script(jump_to_time_upon_startup(Duration::hours(8)), script),
                     )
                 } else if let Some(t) = setup.start_time {
-                    SandboxMode::async_new(app, gameplay, jump_to_time_upon_startup(t))
+                    SandboxMode::async_new(
+                        app,
+                        gameplay,
+                        and_run_script(jump_to_time_upon_startup(t), script),
+                    )
                 } else {
-                    SandboxMode::simple_new(app, gameplay)
+                    SandboxMode::async_new(
+                        app,
+                        gameplay,
+                        and_run_script(Box::new(|_, _| Vec::new()), script),
+                    )
                 }
             }
             Mode::SomethingElse => {
@@ -551,18 +610,19 @@ fn finish_app_setup(
                 SandboxMode::async_new(
                     app,
                     GameplayMode::Freeform(app.primary.map.get_name().clone()),
-                    jump_to_time_upon_startup(start_time),
+                    and_run_script(jump_to_time_upon_startup(start_time), script),
                 )
             }
             Mode::TutorialIntro => sandbox::gameplay::Tutorial::start(ctx, app),
             Mode::Challenges => challenges::ChallengesPicker::new_state(ctx, app),
-            Mode::Sandbox => SandboxMode::simple_new(
+            Mode::Sandbox => SandboxMode::async_new(
                 app,
                 GameplayMode::PlayScenario(
                     app.primary.map.get_name().clone(),
                     Scenario::default_scenario_for_map(app.primary.map.get_name()),
                     Vec::new(),
                 ),
+                and_run_script(Box::new(|_, _| Vec::new()), script),
             ),
             Mode::Proposals => pregame::proposals::Proposals::new_state(ctx, None),
             Mode::Ungap => {
@@ -584,6 +644,22 @@ fn finish_app_setup(
     vec![TitleScreen::new_state(ctx, app), state]
 }
 
+/// After the sandbox loads, run `finalize`, and then start running a script, if there is one. The
+/// script's state goes underneath anything `finalize` pushes, so it starts once those are done.
+fn and_run_script(
+    finalize: Box<dyn FnOnce(&mut EventCtx, &mut App) -> Vec<Transition<App>>>,
+    script: Option<String>,
+) -> Box<dyn FnOnce(&mut EventCtx, &mut App) -> Vec<Transition<App>>> {
+    Box::new(move |ctx, app| {
+        let mut transitions = Vec::new();
+        if let Some(path) = script {
+            transitions.push(Transition::Push(RunScript::new_state(ctx, app, path)));
+        }
+        transitions.extend(finalize(ctx, app));
+        transitions
+    })
+}
+
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
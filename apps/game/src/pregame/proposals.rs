@@ -30,6 +30,18 @@ impl Proposals {
             if current == Some(name.clone()) {
                 let mut txt = Text::new();
                 txt.add_line(Line(edits.get_title()).small_heading());
+                match (&edits.author, &edits.created) {
+                    (Some(author), Some(created)) => {
+                        txt.add_line(Line(format!("By {}, {}", author, created)).secondary());
+                    }
+                    (Some(author), None) => {
+                        txt.add_line(Line(format!("By {}", author)).secondary());
+                    }
+                    (None, Some(created)) => {
+                        txt.add_line(Line(format!("Created {}", created)).secondary());
+                    }
+                    (None, None) => {}
+                }
                 for l in edits.proposal_description.iter().skip(1) {
                     txt.add_line(l);
                 }
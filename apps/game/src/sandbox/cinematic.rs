@@ -0,0 +1,221 @@
+use geom::{Angle, Circle, Distance, Duration, Pt2D};
+use sim::{AgentID, PersonID, PersonState, VehicleType};
+use synthpop::TripEndpoint;
+use widgetry::{
+    Choice, Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, State, Text,
+    TextExt, Toggle, UpdateType, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+
+/// How quickly the camera catches up to its target. Higher is snappier.
+const CAMERA_STIFFNESS: f64 = 3.0;
+/// In chase mode, look this far ahead of the agent, in the direction they're moving
+const CHASE_LEAD: Distance = Distance::const_meters(30.0);
+const NORMAL_ZOOM: f64 = 8.0;
+const CHASE_ZOOM: f64 = 25.0;
+
+/// Runs the simulation while smoothly keeping the camera on one person, through all of their
+/// trips. Useful for presentations, or for watching how one agent behaves.
+pub struct CinematicCamera {
+    panel: Panel,
+    person: PersonID,
+    /// Only set while the person is on a trip
+    agent_pt: Option<Pt2D>,
+    /// Where the person was last seen moving, to figure out which way they're heading
+    last_pt: Option<Pt2D>,
+    heading: Option<Angle>,
+    /// Where the camera is easing towards
+    target: Option<Pt2D>,
+}
+
+impl CinematicCamera {
+    pub fn new_state(ctx: &mut EventCtx, app: &App, person: PersonID) -> Box<dyn State<App>> {
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line(format!("Following {}", person))
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Widget::placeholder(ctx, "status"),
+            Widget::row(vec![
+                "Speed:".text_widget(ctx).centered_vert(),
+                Widget::dropdown(
+                    ctx,
+                    "speed",
+                    5.0,
+                    vec![
+                        Choice::new("real-time", 1.0),
+                        Choice::new("5x", 5.0),
+                        Choice::new("30x", 30.0),
+                    ],
+                ),
+            ]),
+            Toggle::checkbox(ctx, "chase camera", Key::C, false),
+        ]))
+        .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+        .build(ctx);
+
+        let mut state = CinematicCamera {
+            panel,
+            person,
+            agent_pt: None,
+            last_pt: None,
+            heading: None,
+            target: None,
+        };
+        state.update(ctx, app);
+        Box::new(state)
+    }
+
+    /// Find where the person is now and describe what they're doing
+    fn update(&mut self, ctx: &mut EventCtx, app: &App) {
+        let map = &app.primary.map;
+        let sim = &app.primary.sim;
+        let mut txt = Text::from(Line(sim.time().ampm_tostring()).secondary());
+        self.agent_pt = None;
+        let pt = match sim.get_person(self.person).state {
+            PersonState::Inside(b) => {
+                txt.add_line(format!("Inside {}", map.get_b(b).address));
+                Some(map.get_b(b).label_center)
+            }
+            PersonState::OffMap => {
+                txt.add_line("Outside the map boundaries");
+                None
+            }
+            PersonState::Trip(t) => {
+                let info = sim.trip_info(t);
+                let destination = match info.end {
+                    TripEndpoint::Building(b) => map.get_b(b).address.clone(),
+                    TripEndpoint::Border(_) => "somewhere off the map".to_string(),
+                    TripEndpoint::SuddenlyAppear(_) => "somewhere".to_string(),
+                };
+                match sim.trip_to_agent(t).ok() {
+                    Some(agent) => {
+                        txt.add_line(format!(
+                            "{} to {} ({})",
+                            describe_agent(agent),
+                            destination,
+                            info.purpose
+                        ));
+                        let props = sim.agent_properties(map, agent);
+                        txt.add_line(format!(
+                            "{}% of the way there, {} so far",
+                            (100.0 * props.dist_crossed.safe_percent(props.total_dist)) as usize,
+                            props.total_time
+                        ));
+                        if props.waiting_here > Duration::ZERO {
+                            txt.add_line(
+                                Line(format!("Waiting here for {}", props.waiting_here))
+                                    .fg(Color::RED),
+                            );
+                        }
+                        txt.add_line(format!("Delayed {} in total", props.total_waiting));
+                        self.agent_pt = sim.canonical_pt_for_agent(agent, map);
+                        self.agent_pt
+                    }
+                    None => {
+                        txt.add_line(format!("Starting a trip to {}", destination));
+                        None
+                    }
+                }
+            }
+        };
+        self.panel.replace(ctx, "status", txt.into_widget(ctx));
+
+        let pt = match pt {
+            Some(pt) => pt,
+            None => return,
+        };
+        match self.last_pt {
+            Some(last) if last.dist_to(pt) < Distance::meters(0.5) => {}
+            Some(last) => {
+                self.heading = Some(last.angle_to(pt));
+                self.last_pt = Some(pt);
+            }
+            None => {
+                self.last_pt = Some(pt);
+            }
+        }
+        // Look ahead of the agent, so more of the road they're about to use is visible
+        self.target = Some(match self.heading {
+            Some(angle) if self.panel.is_checked("chase camera") => {
+                pt.project_away(CHASE_LEAD, angle)
+            }
+            _ => pt,
+        });
+    }
+}
+
+impl State<App> for CinematicCamera {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Some(real_dt) = ctx.input.nonblocking_is_update_event() {
+            ctx.input.use_update_event();
+            let multiplier: f64 = self.panel.dropdown_value("speed");
+            app.primary.sim.time_limited_step(
+                &app.primary.map,
+                multiplier * real_dt,
+                Duration::seconds(0.033),
+                &mut app.primary.sim_cb,
+            );
+            app.primary.snapshots.maybe_capture(
+                &app.primary.sim,
+                &app.primary.map,
+                app.secondary.is_some(),
+            );
+            self.update(ctx, app);
+
+            // Ease the camera towards the target, instead of jumping
+            if let Some(target) = self.target {
+                let pct = (CAMERA_STIFFNESS * real_dt.inner_seconds()).min(1.0);
+                let zoom = if self.panel.is_checked("chase camera") {
+                    CHASE_ZOOM
+                } else {
+                    NORMAL_ZOOM
+                };
+                ctx.canvas.cam_zoom += (zoom - ctx.canvas.cam_zoom) * pct;
+                let current = ctx.canvas.center_to_map_pt();
+                ctx.canvas.center_on_map_pt(Pt2D::new(
+                    current.x() + (target.x() - current.x()) * pct,
+                    current.y() + (target.y() - current.y()) * pct,
+                ));
+            }
+        }
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        ctx.request_update(UpdateType::Game);
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        if let Some(pt) = self.agent_pt {
+            if let Ok(outline) =
+                Circle::new(pt, Distance::meters(8.0)).to_outline(Distance::meters(1.0))
+            {
+                g.draw_polygon(Color::YELLOW.alpha(0.8), outline);
+            }
+        }
+        self.panel.draw(g);
+    }
+}
+
+fn describe_agent(agent: AgentID) -> &'static str {
+    match agent {
+        AgentID::Pedestrian(_) => "Walking",
+        AgentID::Car(c) => match c.vehicle_type {
+            VehicleType::Car => "Driving",
+            VehicleType::Bike => "Biking",
+            VehicleType::Bus | VehicleType::Train => "Riding transit",
+        },
+        AgentID::BusPassenger(_, _) => "Riding transit",
+    }
+}
@@ -0,0 +1,409 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use abstio::MapName;
+use abstutil::{prettyprint_usize, Counter, Timer};
+use geom::{Distance, Duration, Polygon, Pt2D, Speed};
+use map_model::{Map, PathConstraints, PathStepV2, RoadID};
+use sim::TripID;
+use synthpop::{TripEndpoint, TripMode};
+use widgetry::tools::Lasso;
+use widgetry::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, State,
+    Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::CommonState;
+use crate::sandbox::dashboards::DashTab;
+
+/// From https://www.epa.gov/greenvehicles/greenhouse-gas-emissions-typical-passenger-vehicle,
+/// 404 grams per mile
+const CO2_GRAMS_PER_METER_DRIVEN: f64 = 404.0 / 1609.344;
+
+/// Scales the average emissions for the speed limit of a road, so that changing speed limits is
+/// reflected. Engines are inefficient at low speeds and fight air resistance at high speeds. This
+/// is a rough fit to average-speed emission curves for petrol cars, like the ones in COPERT, in
+/// grams per km. It's normalized so that 30mph leaves the EPA average alone.
+fn co2_speed_factor(speed: Speed) -> f64 {
+    let grams_per_km = |kph: f64| 80.0 + 3000.0 / kph + 0.0044 * kph * kph;
+    // Nobody actually drives that slowly
+    let kph = speed.to_miles_per_hour().max(5.0) * 1.609344;
+    grams_per_km(kph) / grams_per_km(30.0 * 1.609344)
+}
+
+/// Areas drawn by the player to summarize the trips starting, ending, or passing through them.
+#[derive(Serialize, Deserialize, Default)]
+pub struct AnalysisZones {
+    pub zones: Vec<AnalysisZone>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AnalysisZone {
+    pub name: String,
+    pub polygon: Polygon,
+}
+
+impl AnalysisZones {
+    /// Returns no zones if the file is missing or broken.
+    pub fn load(name: &MapName) -> AnalysisZones {
+        abstio::maybe_read_json(abstio::path_analysis_zones(name), &mut Timer::throwaway())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, name: &MapName) {
+        abstio::write_json(abstio::path_analysis_zones(name), self);
+    }
+
+    /// Adds a zone with a default name and saves
+    pub fn add(&mut self, map: &Map, polygon: Polygon) {
+        let mut idx = self.zones.len() + 1;
+        while self.zones.iter().any(|z| z.name == format!("Zone {}", idx)) {
+            idx += 1;
+        }
+        self.zones.push(AnalysisZone {
+            name: format!("Zone {}", idx),
+            polygon,
+        });
+        self.save(map.get_name());
+    }
+
+    pub fn remove(&mut self, map: &Map, idx: usize) {
+        self.zones.remove(idx);
+        self.save(map.get_name());
+    }
+}
+
+/// Summarizes trips per analysis zone.
+pub struct AnalysisZonesDashboard {
+    panel: Panel,
+    draw: Drawable,
+    lasso: Option<Lasso>,
+}
+
+struct ZoneStats {
+    starting: usize,
+    ending: usize,
+    /// Modes of trips starting or ending here
+    modes: Counter<TripMode>,
+    /// Over finished trips starting or ending here
+    total_delay: Duration,
+    finished_trips: usize,
+    /// Only filled out after calculating routes
+    passing_through: Option<usize>,
+    co2_grams: Option<f64>,
+}
+
+impl AnalysisZonesDashboard {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let stats = calculate_stats(app, None);
+        Box::new(AnalysisZonesDashboard {
+            panel: make_panel(ctx, app, &stats),
+            draw: draw_zones(ctx, app),
+            lasso: None,
+        })
+    }
+}
+
+impl State<App> for AnalysisZonesDashboard {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Some(ref mut lasso) = self.lasso {
+            if let Some(polygon) = lasso.event(ctx) {
+                self.lasso = None;
+                app.primary
+                    .analysis_zones
+                    .add(&app.primary.map, polygon.simplify(10.0));
+                return Transition::Replace(Self::new_state(ctx, app));
+            }
+            return Transition::Keep;
+        }
+
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => Transition::Pop,
+                "draw a new zone" => {
+                    self.lasso = Some(Lasso::new(Distance::meters(1.0)));
+                    Transition::Keep
+                }
+                "calculate routes" => {
+                    let stats = ctx.loading_screen("calculate routes", |_, timer| {
+                        calculate_stats(app, Some(timer))
+                    });
+                    self.panel = make_panel(ctx, app, &stats);
+                    Transition::Keep
+                }
+                x => {
+                    if let Some(idx) = x.strip_prefix("delete zone ") {
+                        app.primary
+                            .analysis_zones
+                            .remove(&app.primary.map, idx.parse::<usize>().unwrap());
+                        return Transition::Replace(Self::new_state(ctx, app));
+                    }
+                    unreachable!()
+                }
+            },
+            Outcome::Changed(_) => DashTab::AnalysisZones
+                .transition(ctx, app, &self.panel)
+                .unwrap_or(Transition::Keep),
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        if let Some(ref lasso) = self.lasso {
+            lasso.draw(g);
+        } else {
+            self.panel.draw(g);
+            CommonState::draw_osd(g, app);
+        }
+    }
+}
+
+fn make_panel(ctx: &mut EventCtx, app: &App, stats: &[ZoneStats]) -> Panel {
+    let mut col = vec![
+        DashTab::AnalysisZones.picker(ctx, app),
+        Text::from_multiline(vec![
+            Line("Draw areas to summarize the trips starting, ending, or passing through them."),
+            Line("Zones are saved for this map.").secondary(),
+        ])
+        .into_widget(ctx),
+        Widget::row(vec![
+            ctx.style()
+                .btn_solid_primary
+                .text("draw a new zone")
+                .build_def(ctx),
+            ctx.style()
+                .btn_outline
+                .text("calculate routes")
+                .disabled(app.primary.analysis_zones.zones.is_empty())
+                .disabled_tooltip("Draw a zone first")
+                .build_def(ctx),
+        ]),
+        "Trips passing through and emissions need the routes of every trip started so far, which \
+         may be slow to calculate."
+            .text_widget(ctx),
+    ];
+
+    for (idx, (zone, stats)) in app
+        .primary
+        .analysis_zones
+        .zones
+        .iter()
+        .zip(stats.iter())
+        .enumerate()
+    {
+        let mut txt = Text::from(Line(&zone.name).small_heading());
+        txt.add_line(format!(
+            "{} trips start here, {} end here",
+            prettyprint_usize(stats.starting),
+            prettyprint_usize(stats.ending)
+        ));
+        if let Some(through) = stats.passing_through {
+            txt.add_line(format!("{} trips pass through", prettyprint_usize(through)));
+        }
+        if stats.finished_trips > 0 {
+            txt.add_line(format!(
+                "Finished trips starting or ending here were delayed {} on average",
+                stats.total_delay / (stats.finished_trips as f64)
+            ));
+        }
+        if let Some(grams) = stats.co2_grams {
+            txt.add_line(format!(
+                "{:.1} kg of CO2 emitted driving to, from, or through here",
+                grams / 1000.0
+            ));
+        }
+        let total = stats.modes.sum();
+        if total > 0 {
+            txt.add_line(
+                Line(
+                    TripMode::all()
+                        .into_iter()
+                        .map(|m| {
+                            format!(
+                                "{}% {}",
+                                (100.0 * (stats.modes.get(m) as f64) / (total as f64)).round(),
+                                m.noun().to_lowercase()
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                )
+                .secondary(),
+            );
+        }
+
+        col.push(
+            Widget::row(vec![
+                txt.into_widget(ctx),
+                ctx.style()
+                    .btn_plain_destructive
+                    .icon("system/assets/tools/trash.svg")
+                    .build_widget(ctx, &format!("delete zone {}", idx))
+                    .align_right(),
+            ])
+            .section(ctx),
+        );
+    }
+
+    Panel::new_builder(Widget::col(col))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+        .build(ctx)
+}
+
+fn draw_zones(ctx: &mut EventCtx, app: &App) -> Drawable {
+    let mut batch = GeomBatch::new();
+    for zone in &app.primary.analysis_zones.zones {
+        batch.push(Color::BLUE.alpha(0.2), zone.polygon.clone());
+        batch.push(Color::BLUE, zone.polygon.to_outline(Distance::meters(3.0)));
+        batch.append(
+            Text::from(Line(&zone.name).fg(Color::BLACK))
+                .render_autocropped(ctx)
+                .scale(0.5)
+                .centered_on(zone.polygon.center()),
+        );
+    }
+    ctx.upload(batch)
+}
+
+/// If a timer is passed in, also calculate routes for trips passing through zones and emissions.
+fn calculate_stats(app: &App, timer: Option<&mut Timer>) -> Vec<ZoneStats> {
+    let map = &app.primary.map;
+    let zones = &app.primary.analysis_zones.zones;
+    let analytics = app.primary.sim.get_analytics();
+    let mut stats: Vec<ZoneStats> = zones
+        .iter()
+        .map(|_| ZoneStats {
+            starting: 0,
+            ending: 0,
+            modes: Counter::new(),
+            total_delay: Duration::ZERO,
+            finished_trips: 0,
+            passing_through: None,
+            co2_grams: None,
+        })
+        .collect();
+
+    let zones_containing = |pt: Pt2D| -> Vec<usize> {
+        zones
+            .iter()
+            .enumerate()
+            .filter(|(_, z)| z.polygon.contains_pt(pt))
+            .map(|(idx, _)| idx)
+            .collect()
+    };
+    let endpoint_pt = |endpt: TripEndpoint| -> Pt2D {
+        match endpt {
+            TripEndpoint::Building(b) => map.get_b(b).label_center,
+            TripEndpoint::Border(i) => map.get_i(i).polygon.center(),
+            TripEndpoint::SuddenlyAppear(pos) => pos.pt(map),
+        }
+    };
+
+    let finished: BTreeSet<TripID> = analytics
+        .finished_trips
+        .iter()
+        .filter(|(_, _, _, dt)| dt.is_some())
+        .map(|(_, id, _, _)| *id)
+        .collect();
+    // The zones each trip starts or ends in
+    let mut trip_zones: BTreeMap<TripID, BTreeSet<usize>> = BTreeMap::new();
+    for (id, trip) in app.primary.sim.all_trip_info() {
+        let from = zones_containing(endpoint_pt(trip.start));
+        let to = zones_containing(endpoint_pt(trip.end));
+        for idx in &from {
+            stats[*idx].starting += 1;
+        }
+        for idx in &to {
+            stats[*idx].ending += 1;
+        }
+        let touching: BTreeSet<usize> = from.into_iter().chain(to.into_iter()).collect();
+        for idx in &touching {
+            stats[*idx].modes.inc(trip.mode);
+            if finished.contains(&id) {
+                stats[*idx].finished_trips += 1;
+                if let Some(delays) = analytics.trip_delays.get(&id) {
+                    for (_, dt) in delays {
+                        stats[*idx].total_delay += *dt;
+                    }
+                }
+            }
+        }
+        trip_zones.insert(id, touching);
+    }
+
+    let timer = match timer {
+        Some(timer) => timer,
+        None => {
+            return stats;
+        }
+    };
+
+    let roads_per_zone: Vec<HashSet<RoadID>> = zones
+        .iter()
+        .map(|z| {
+            map.all_roads()
+                .iter()
+                .filter(|r| z.polygon.contains_pt(r.center_pts.middle()))
+                .map(|r| r.id)
+                .collect()
+        })
+        .collect();
+    let mut passing_through: Vec<BTreeSet<TripID>> =
+        zones.iter().map(|_| BTreeSet::new()).collect();
+    let mut co2_grams = vec![0.0; zones.len()];
+    let trip_log = analytics.full_trip_log();
+    timer.start_iter("calculate routes", trip_log.len());
+    for (_, id, maybe_req, _) in trip_log.iter() {
+        timer.next();
+        let req = match maybe_req {
+            Some(req) => req.clone(),
+            None => continue,
+        };
+        let is_driving = req.constraints == PathConstraints::Car;
+        let path = match map.pathfind_v2(req) {
+            Ok(path) => path,
+            Err(_) => continue,
+        };
+        let roads: HashSet<RoadID> = path
+            .get_steps()
+            .iter()
+            .filter_map(|step| match step {
+                PathStepV2::Along(dr) | PathStepV2::Contraflow(dr) => Some(dr.road),
+                _ => None,
+            })
+            .collect();
+
+        let mut touching = trip_zones.get(id).cloned().unwrap_or_default();
+        for (idx, zone_roads) in roads_per_zone.iter().enumerate() {
+            if !touching.contains(&idx) && roads.iter().any(|r| zone_roads.contains(r)) {
+                passing_through[idx].insert(*id);
+                touching.insert(idx);
+            }
+        }
+        if is_driving {
+            let grams: f64 = roads
+                .iter()
+                .map(|r| {
+                    let road = map.get_r(*r);
+                    CO2_GRAMS_PER_METER_DRIVEN
+                        * co2_speed_factor(road.speed_limit)
+                        * road.length().inner_meters()
+                })
+                .sum();
+            for idx in touching {
+                co2_grams[idx] += grams;
+            }
+        }
+    }
+
+    for (idx, stats) in stats.iter_mut().enumerate() {
+        stats.passing_through = Some(passing_through[idx].len());
+        stats.co2_grams = Some(co2_grams[idx]);
+    }
+    stats
+}
@@ -6,7 +6,9 @@ use maplit::hashset;
 use abstutil::{prettyprint_usize, Counter, MultiMap, Timer};
 use geom::{Distance, PolyLine, Polygon, Time};
 use map_gui::tools::checkbox_per_mode;
-use map_model::{osm, BuildingID, BuildingType, IntersectionID, LaneID, Map, RoadID, TurnType};
+use map_model::{
+    concave_hull, osm, BuildingID, BuildingType, IntersectionID, LaneID, Map, RoadID, TurnType,
+};
 use sim::TripInfo;
 use synthpop::{TripEndpoint, TripMode};
 use widgetry::tools::{ColorLegend, PopupMsg};
@@ -80,7 +82,7 @@ impl CommuterPatterns {
         let (bldg_to_block, border_to_block, blocks) = match maybe_groups {
             Ok(result) => result,
             Err(_) => {
-                // TODO If concave hull fails anywhere, give up on this tool entirely. This is
+                // TODO If any block has no outline, give up on this tool entirely. This is
                 // pretty harsh. Revisit this tool anyway and consider 2 bigger changes -- using
                 // the LTN-style blockfinding and using World. Find a way to create simpler
                 // fallback geometry that's still selectable.
@@ -520,7 +522,12 @@ fn group_bldgs(
                     hull_points.append(&mut lane_line.points().clone());
                 }
             }
-            Polygon::concave_hull(hull_points, 10).map(|shape| Block {
+            // If buildings in the group are spread apart, just use the biggest cluster
+            let shape = concave_hull::alpha_shape(hull_points, Distance::meters(50.0))
+                .into_iter()
+                .max_by(|p1, p2| p1.area().partial_cmp(&p2.area()).unwrap())
+                .ok_or_else(|| anyhow!("no outline for block {}", block_id))?;
+            Ok(Block {
                 id: block_id,
                 bldgs: group.bldgs,
                 borders: HashSet::new(),
@@ -0,0 +1,298 @@
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+
+use abstutil::{prettyprint_usize, Timer};
+use geom::Duration;
+use map_gui::tools::compare_counts::{CompareCounts, Layer};
+use sim::{AgentType, Analytics, DeltaSummary, RunComparison};
+use synthpop::{TrafficCounts, TripMode};
+use widgetry::tools::PopupMsg;
+use widgetry::{
+    Choice, EventCtx, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, State, Text, TextExt,
+    TextSpan, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::sandbox::dashboards::DashTab;
+
+const CURRENT: &str = "current";
+const PREBAKED: &str = "prebaked";
+
+/// Compares the results of two runs that have already happened, without running both at once
+/// like the A/B mode does. Any run can be saved from here and compared later against the current
+/// simulation, the prebaked baseline, or another saved run.
+pub struct CompareRuns {
+    panel: Panel,
+    compare: Option<CompareCounts>,
+}
+
+impl CompareRuns {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let before = if app.has_prebaked().is_some() {
+            PREBAKED.to_string()
+        } else {
+            saved_runs(app)
+                .into_iter()
+                .next()
+                .unwrap_or_else(|| CURRENT.to_string())
+        };
+        let mut state = CompareRuns {
+            panel: Panel::empty(ctx),
+            compare: None,
+        };
+        state.rebuild(ctx, app, before, CURRENT.to_string());
+        Box::new(state)
+    }
+
+    fn rebuild(&mut self, ctx: &mut EventCtx, app: &App, before: String, after: String) {
+        let mut choices = vec![Choice::new("Current simulation", CURRENT.to_string())];
+        if app.has_prebaked().is_some() {
+            choices.push(Choice::new("Baseline (prebaked)", PREBAKED.to_string()));
+        }
+        choices.extend(Choice::strings(saved_runs(app)));
+
+        let mut col = vec![
+            DashTab::CompareRuns.picker(ctx, app),
+            Text::from(Line(
+                "Compare two finished runs of the same scenario. Save the current run to compare \
+                 it later against a run with different edits.",
+            ))
+            .wrap_to_pct(ctx, 30)
+            .into_widget(ctx),
+            Widget::row(vec![
+                "Before:".text_widget(ctx).centered_vert(),
+                Widget::dropdown(ctx, "before", before.clone(), choices.clone()),
+                "After:".text_widget(ctx).centered_vert(),
+                Widget::dropdown(ctx, "after", after.clone(), choices),
+            ]),
+            ctx.style()
+                .btn_outline
+                .text("Save the current run")
+                .build_def(ctx),
+        ];
+
+        self.compare = None;
+        match compare(app, &before, &after) {
+            Ok((comparison, compare_counts)) => {
+                col.push(summary(ctx, app, &comparison));
+                let compare_counts = CompareCounts::new(
+                    ctx,
+                    app,
+                    compare_counts.0,
+                    compare_counts.1,
+                    Layer::Compare,
+                    false,
+                );
+                col.push(compare_counts.get_panel_widget(ctx).named("compare counts"));
+                self.compare = Some(compare_counts);
+            }
+            Err(err) => {
+                col.push(format!("Couldn't load the runs: {}", err).text_widget(ctx));
+            }
+        }
+
+        self.panel = Panel::new_builder(Widget::col(col))
+            .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+            .build(ctx);
+    }
+}
+
+impl State<App> for CompareRuns {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Save the current run" => {
+                    let name = format!(
+                        "{}_{}",
+                        app.primary.map.get_edits().edits_name,
+                        app.primary.sim.time().as_filename()
+                    );
+                    abstio::write_binary(
+                        abstio::path_run_analytics(app.primary.map.get_name(), &name),
+                        app.primary.sim.get_analytics(),
+                    );
+                    let before = self.panel.dropdown_value("before");
+                    let after = self.panel.dropdown_value("after");
+                    self.rebuild(ctx, app, before, after);
+                    return Transition::Push(PopupMsg::new_state(
+                        ctx,
+                        "Run saved",
+                        vec![format!("Saved this run as {}", name)],
+                    ));
+                }
+                x => {
+                    if let Some(ref mut compare) = self.compare {
+                        let widget = compare
+                            .on_click(ctx, app, x)
+                            .expect("button click didn't belong to CompareCounts");
+                        self.panel.replace(ctx, "compare counts", widget);
+                    }
+                    return Transition::Keep;
+                }
+            },
+            Outcome::Changed(_) => {
+                if let Some(t) = DashTab::CompareRuns.transition(ctx, app, &self.panel) {
+                    return t;
+                }
+                let before = self.panel.dropdown_value("before");
+                let after = self.panel.dropdown_value("after");
+                self.rebuild(ctx, app, before, after);
+            }
+            _ => {}
+        }
+
+        if let Some(ref mut compare) = self.compare {
+            compare.other_event(ctx);
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        if let Some(ref compare) = self.compare {
+            compare.draw(g, app);
+        }
+        self.panel.draw(g);
+    }
+}
+
+fn saved_runs(app: &App) -> Vec<String> {
+    abstio::list_all_objects(abstio::path_all_run_analytics(app.primary.map.get_name()))
+}
+
+fn load_run<'a>(app: &'a App, source: &str) -> Result<Cow<'a, Analytics>> {
+    match source {
+        CURRENT => Ok(Cow::Borrowed(app.primary.sim.get_analytics())),
+        PREBAKED => Ok(Cow::Borrowed(app.prebaked())),
+        name => abstio::maybe_read_binary(
+            abstio::path_run_analytics(app.primary.map.get_name(), name),
+            &mut Timer::throwaway(),
+        )
+        .map(Cow::Owned),
+    }
+}
+
+fn compare(
+    app: &App,
+    before: &str,
+    after: &str,
+) -> Result<(RunComparison, (TrafficCounts, TrafficCounts))> {
+    let before_analytics = load_run(app, before)?;
+    let after_analytics = load_run(app, after)?;
+    let agent_types: BTreeSet<AgentType> = AgentType::all().into_iter().collect();
+
+    let mut comparison = RunComparison::new(&before_analytics, &after_analytics, &agent_types);
+    comparison.retain_existing(&app.primary.map);
+    let counts = (
+        before_analytics.traffic_counts(&app.primary.map, before.to_string(), &agent_types),
+        after_analytics.traffic_counts(&app.primary.map, after.to_string(), &agent_types),
+    );
+    Ok((comparison, counts))
+}
+
+fn summary(ctx: &mut EventCtx, app: &App, comparison: &RunComparison) -> Widget {
+    let mut txt = Text::new();
+
+    txt.add_line(Line("Trip times").small_heading());
+    match comparison.trip_summary(None) {
+        Some(summary) => {
+            txt.add_line(describe(&summary, "trips", "slower", "faster", seconds));
+            for mode in TripMode::all() {
+                if let Some(summary) = comparison.trip_summary(Some(mode)) {
+                    txt.add_line(Line(format!(
+                        "- {}: mean change {}",
+                        mode.ongoing_verb(),
+                        seconds(summary.mean)
+                    )));
+                }
+            }
+        }
+        None => {
+            txt.add_line(Line("No trips finished in both runs"));
+        }
+    }
+
+    txt.add_line(Line("Road throughput").small_heading());
+    if let Some(summary) = comparison.road_summary() {
+        txt.add_line(describe(&summary, "roads", "busier", "quieter", count));
+    }
+    txt.add_line(Line("Intersection throughput").small_heading());
+    if let Some(summary) = comparison.intersection_summary() {
+        txt.add_line(describe(
+            &summary,
+            "intersections",
+            "busier",
+            "quieter",
+            count,
+        ));
+    }
+    txt.add_line(Line("Mean delay at traffic signals").small_heading());
+    if let Some(summary) = comparison.intersection_delay_summary() {
+        txt.add_line(describe(
+            &summary,
+            "signals",
+            "more delay",
+            "less delay",
+            seconds,
+        ));
+    }
+
+    let biggest = comparison.biggest_road_changes(5);
+    if !biggest.is_empty() {
+        txt.add_line(Line("Roads that changed the most").small_heading());
+        for (r, before, after) in biggest {
+            txt.add_line(Line(format!(
+                "- {}: {} -> {}",
+                app.primary
+                    .map
+                    .get_r(r)
+                    .get_name(app.opts.language.as_ref()),
+                prettyprint_usize(before),
+                prettyprint_usize(after)
+            )));
+        }
+    }
+
+    txt.into_widget(ctx)
+}
+
+fn describe(
+    summary: &DeltaSummary,
+    noun: &str,
+    increased: &str,
+    decreased: &str,
+    fmt: fn(f64) -> String,
+) -> TextSpan {
+    Line(format!(
+        "{} {}: mean change {}, median {}, 10th-90th percentile {} to {}. {} {}, {} {}",
+        prettyprint_usize(summary.count),
+        noun,
+        fmt(summary.mean),
+        fmt(summary.p50),
+        fmt(summary.p10),
+        fmt(summary.p90),
+        prettyprint_usize(summary.num_increased),
+        increased,
+        prettyprint_usize(summary.num_decreased),
+        decreased
+    ))
+}
+
+fn seconds(x: f64) -> String {
+    if x < 0.0 {
+        format!("-{}", Duration::seconds(-x))
+    } else {
+        format!("+{}", Duration::seconds(x))
+    }
+}
+
+fn count(x: f64) -> String {
+    format!("{:+.1}", x)
+}
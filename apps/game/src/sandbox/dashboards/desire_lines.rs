@@ -0,0 +1,466 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use abstutil::{prettyprint_usize, Counter, Timer};
+use blockfinding::Perimeter;
+use geom::{Bounds, Distance, PolyLine, Polygon, Pt2D, Time};
+use map_gui::tools::checkbox_per_mode;
+use map_model::{IntersectionID, Map, RoadRank};
+use synthpop::{TripEndpoint, TripMode};
+use widgetry::tools::{ColorLegend, Lasso, PopupMsg};
+use widgetry::{
+    Choice, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome,
+    Panel, Slider, State, Text, TextExt, Toggle, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::CommonState;
+use crate::sandbox::dashboards::DashTab;
+
+/// The busiest pair of zones gets a line this wide
+const MAX_LINE_WIDTH: Distance = Distance::const_meters(40.0);
+const MIN_LINE_WIDTH: Distance = Distance::const_meters(2.0);
+
+/// Groups trips into an origin-destination matrix between zones, and draws a straight "desire
+/// line" between each pair of zones, with the width proportional to the number of trips.
+pub struct DesireLines {
+    panel: Panel,
+    source: ZoneSource,
+    zones: Vec<Zone>,
+    /// The origin and destination zone of every trip that starts and ends in some zone
+    trips: Vec<(usize, usize, Time, TripMode)>,
+    filter: Filter,
+    draw: Drawable,
+    lasso: Option<Lasso>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum ZoneSource {
+    Neighborhoods,
+    Census,
+    AnalysisZones,
+}
+
+struct Zone {
+    name: String,
+    shape: ZoneShape,
+    center: Pt2D,
+}
+
+enum ZoneShape {
+    Area(Polygon),
+    /// Trips entering or leaving the map through one border
+    Border(IntersectionID),
+}
+
+#[derive(PartialEq)]
+struct Filter {
+    include_borders: bool,
+    depart_from: Time,
+    depart_until: Time,
+    modes: BTreeSet<TripMode>,
+}
+
+impl Zone {
+    fn is_border(&self) -> bool {
+        matches!(self.shape, ZoneShape::Border(_))
+    }
+}
+
+impl Filter {
+    fn includes(
+        &self,
+        zones: &[Zone],
+        from: usize,
+        to: usize,
+        departure: Time,
+        mode: TripMode,
+    ) -> bool {
+        departure >= self.depart_from
+            && departure <= self.depart_until
+            && self.modes.contains(&mode)
+            && (self.include_borders || !(zones[from].is_border() || zones[to].is_border()))
+    }
+}
+
+impl DesireLines {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let source = if app.primary.map.all_census_zones().is_empty() {
+            ZoneSource::Neighborhoods
+        } else {
+            ZoneSource::Census
+        };
+        Self::new_state_with_source(ctx, app, source)
+    }
+
+    fn new_state_with_source(
+        ctx: &mut EventCtx,
+        app: &App,
+        source: ZoneSource,
+    ) -> Box<dyn State<App>> {
+        let zones = ctx.loading_screen("find zones", |_, timer| make_zones(app, source, timer));
+        let trips = assign_trips(app, &zones);
+        let mut state = DesireLines {
+            panel: make_panel(ctx, app, source),
+            source,
+            zones,
+            trips,
+            filter: Filter {
+                include_borders: true,
+                depart_from: Time::START_OF_DAY,
+                depart_until: app.primary.sim.get_end_of_day(),
+                modes: TripMode::all().into_iter().collect(),
+            },
+            draw: Drawable::empty(ctx),
+            lasso: None,
+        };
+        state.recalculate(ctx, app);
+        Box::new(state)
+    }
+
+    /// Counts trips between each pair of zones, ignoring direction
+    fn count_pairs(&self) -> (Counter<(usize, usize)>, usize) {
+        let mut pairs = Counter::new();
+        let mut within_zone = 0;
+        for (from, to, departure, mode) in &self.trips {
+            if !self
+                .filter
+                .includes(&self.zones, *from, *to, *departure, *mode)
+            {
+                continue;
+            }
+            if from == to {
+                within_zone += 1;
+            } else {
+                pairs.inc((*from.min(to), *from.max(to)));
+            }
+        }
+        (pairs, within_zone)
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        let (pairs, within_zone) = self.count_pairs();
+        let max_count = pairs.max();
+
+        let mut batch = GeomBatch::new();
+        for zone in &self.zones {
+            if let ZoneShape::Area(ref polygon) = zone.shape {
+                batch.push(
+                    Color::BLACK.alpha(0.5),
+                    polygon.to_outline(Distance::meters(3.0)),
+                );
+            }
+        }
+        // Draw the busiest lines last, so they're on top
+        let mut sorted: Vec<((usize, usize), usize)> = pairs.consume().into_iter().collect();
+        sorted.sort_by_key(|(_, count)| *count);
+        for ((from, to), count) in &sorted {
+            let pct = (*count as f64) / (max_count as f64);
+            let width = MIN_LINE_WIDTH.max(MAX_LINE_WIDTH * pct);
+            if let Ok(pl) = PolyLine::new(vec![self.zones[*from].center, self.zones[*to].center]) {
+                batch.push(
+                    app.cs.good_to_bad_red.eval(pct).alpha(0.8),
+                    pl.make_polygons(width),
+                );
+            }
+        }
+        self.draw = ctx.upload(batch);
+
+        let total: usize = sorted.iter().map(|(_, count)| *count).sum();
+        let mut txt = Text::new();
+        txt.add_line(format!(
+            "{} trips between {} zones",
+            prettyprint_usize(total),
+            prettyprint_usize(self.zones.len())
+        ));
+        txt.add_line(
+            Line(format!(
+                "{} trips start and end in the same zone",
+                prettyprint_usize(within_zone)
+            ))
+            .secondary(),
+        );
+        if !sorted.is_empty() {
+            txt.add_line(Line("Busiest pairs").small_heading());
+        }
+        for ((from, to), count) in sorted.iter().rev().take(5) {
+            txt.add_line(format!(
+                "{} and {}: {}",
+                self.zones[*from].name,
+                self.zones[*to].name,
+                prettyprint_usize(*count)
+            ));
+        }
+        self.panel.replace(ctx, "summary", txt.into_widget(ctx));
+        self.panel.replace(
+            ctx,
+            "scale",
+            ColorLegend::gradient(
+                ctx,
+                &app.cs.good_to_bad_red,
+                vec!["0".to_string(), prettyprint_usize(max_count)],
+            ),
+        );
+    }
+
+    fn export_matrix(&self, app: &App) -> Result<String> {
+        let path = format!(
+            "od_matrix_{}_{}.csv",
+            app.primary.map.get_name().as_filename(),
+            app.primary.sim.time().as_filename()
+        );
+        // Unlike the lines, keep the direction of trips here
+        let mut matrix: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+        for (from, to, departure, mode) in &self.trips {
+            if self
+                .filter
+                .includes(&self.zones, *from, *to, *departure, *mode)
+            {
+                *matrix
+                    .entry((
+                        self.zones[*from].name.as_str(),
+                        self.zones[*to].name.as_str(),
+                    ))
+                    .or_insert(0) += 1;
+            }
+        }
+        let mut out = String::new();
+        writeln!(out, "origin,destination,trips")?;
+        for ((from, to), count) in matrix {
+            writeln!(out, "{},{},{}", from, to, count)?;
+        }
+        abstio::write_file(path, out)
+    }
+}
+
+impl State<App> for DesireLines {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Some(ref mut lasso) = self.lasso {
+            if let Some(polygon) = lasso.event(ctx) {
+                self.lasso = None;
+                app.primary
+                    .analysis_zones
+                    .add(&app.primary.map, polygon.simplify(10.0));
+                return Transition::Replace(Self::new_state_with_source(
+                    ctx,
+                    app,
+                    ZoneSource::AnalysisZones,
+                ));
+            }
+            return Transition::Keep;
+        }
+
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "draw a zone" => {
+                    self.lasso = Some(Lasso::new(Distance::meters(1.0)));
+                }
+                "Export to CSV" => {
+                    return Transition::Push(match self.export_matrix(app) {
+                        Ok(path) => PopupMsg::new_state(
+                            ctx,
+                            "Data exported",
+                            vec![format!("Data exported to {}", path)],
+                        ),
+                        Err(err) => {
+                            PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                        }
+                    });
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed(_) => {
+                if let Some(t) = DashTab::DesireLines.transition(ctx, app, &self.panel) {
+                    return t;
+                }
+                let source: ZoneSource = self.panel.dropdown_value("zones");
+                if source != self.source {
+                    return Transition::Replace(Self::new_state_with_source(ctx, app, source));
+                }
+            }
+            _ => {}
+        }
+
+        let end_of_day = app.primary.sim.get_end_of_day();
+        let mut filter = Filter {
+            include_borders: self.panel.is_checked("include borders"),
+            depart_from: end_of_day.percent_of(self.panel.slider("depart from").get_percent()),
+            depart_until: end_of_day.percent_of(self.panel.slider("depart until").get_percent()),
+            modes: BTreeSet::new(),
+        };
+        for m in TripMode::all() {
+            if self.panel.is_checked(m.ongoing_verb()) {
+                filter.modes.insert(m);
+            }
+        }
+        if filter != self.filter {
+            self.filter = filter;
+            self.recalculate(ctx, app);
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        if let Some(ref lasso) = self.lasso {
+            lasso.draw(g);
+        } else {
+            self.panel.draw(g);
+            CommonState::draw_osd(g, app);
+        }
+    }
+}
+
+fn make_panel(ctx: &mut EventCtx, app: &App, source: ZoneSource) -> Panel {
+    let mut choices = vec![Choice::new("neighborhoods", ZoneSource::Neighborhoods)];
+    if !app.primary.map.all_census_zones().is_empty() {
+        choices.push(Choice::new("census zones", ZoneSource::Census));
+    }
+    choices.push(Choice::new("analysis zones", ZoneSource::AnalysisZones));
+
+    Panel::new_builder(Widget::col(vec![
+        DashTab::DesireLines.picker(ctx, app),
+        Widget::row(vec![
+            "Group trips by:".text_widget(ctx).centered_vert(),
+            Widget::dropdown(ctx, "zones", source, choices),
+        ]),
+        if source == ZoneSource::AnalysisZones {
+            ctx.style().btn_outline.text("draw a zone").build_def(ctx)
+        } else {
+            Widget::nothing()
+        },
+        Toggle::switch(ctx, "include borders", None, true),
+        Widget::row(vec![
+            "Departing from:".text_widget(ctx).margin_right(20),
+            Slider::area(ctx, 0.15 * ctx.canvas.window_width, 0.0, "depart from"),
+        ]),
+        Widget::row(vec![
+            "Departing until:".text_widget(ctx).margin_right(20),
+            Slider::area(ctx, 0.15 * ctx.canvas.window_width, 1.0, "depart until"),
+        ]),
+        checkbox_per_mode(ctx, app, &TripMode::all().into_iter().collect()),
+        ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["0", "0"]).named("scale"),
+        Text::new().into_widget(ctx).named("summary"),
+        ctx.style().btn_outline.text("Export to CSV").build_def(ctx),
+    ]))
+    .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+    .build(ctx)
+}
+
+fn make_zones(app: &App, source: ZoneSource, timer: &mut Timer) -> Vec<Zone> {
+    let map = &app.primary.map;
+    let polygons = match source {
+        ZoneSource::Neighborhoods => find_neighborhoods(map, timer),
+        ZoneSource::Census => map
+            .all_census_zones()
+            .iter()
+            .map(|(polygon, _)| polygon.clone())
+            .collect(),
+        ZoneSource::AnalysisZones => app
+            .primary
+            .analysis_zones
+            .zones
+            .iter()
+            .map(|z| z.polygon.clone())
+            .collect(),
+    };
+    let mut zones: Vec<Zone> = polygons
+        .into_iter()
+        .enumerate()
+        .map(|(idx, polygon)| Zone {
+            name: match source {
+                ZoneSource::Neighborhoods => format!("Neighborhood {}", idx + 1),
+                ZoneSource::Census => map.all_census_zones()[idx].1.id.clone(),
+                ZoneSource::AnalysisZones => app.primary.analysis_zones.zones[idx].name.clone(),
+            },
+            center: polygon.center(),
+            shape: ZoneShape::Area(polygon),
+        })
+        .collect();
+    for i in map.all_intersections() {
+        if i.is_border() {
+            zones.push(Zone {
+                name: format!("Border {}", i.id.0),
+                shape: ZoneShape::Border(i.id),
+                center: i.polygon.center(),
+            });
+        }
+    }
+    zones
+}
+
+/// Like the LTN tool, merge city blocks until reaching arterial roads
+fn find_neighborhoods(map: &Map, timer: &mut Timer) -> Vec<Polygon> {
+    timer.start("find single blocks");
+    let input = Perimeter::merge_holes(map, Perimeter::find_all_single_blocks(map));
+    let mut perimeters = Vec::new();
+    for mut perim in input {
+        perim.collapse_deadends();
+        if let Ok(block) = perim.to_block(map) {
+            perimeters.push(block.perimeter);
+        }
+    }
+    timer.stop("find single blocks");
+
+    timer.start("merge into neighborhoods");
+    let mut merged = Vec::new();
+    for partition in Perimeter::partition_by_predicate(perimeters, |r| {
+        map.get_r(r).get_rank() == RoadRank::Local
+    }) {
+        merged.extend(Perimeter::merge_all(map, partition, false));
+    }
+    timer.stop("merge into neighborhoods");
+
+    merged
+        .into_iter()
+        .filter_map(|perim| perim.to_block(map).ok())
+        .map(|block| block.polygon)
+        .collect()
+}
+
+fn assign_trips(app: &App, zones: &[Zone]) -> Vec<(usize, usize, Time, TripMode)> {
+    let map = &app.primary.map;
+    let mut border_zones: HashMap<IntersectionID, usize> = HashMap::new();
+    // Zones are usually big, so check the bounding box first
+    let mut areas: Vec<(usize, Bounds, &Polygon)> = Vec::new();
+    for (idx, zone) in zones.iter().enumerate() {
+        match zone.shape {
+            ZoneShape::Area(ref polygon) => {
+                areas.push((idx, polygon.get_bounds(), polygon));
+            }
+            ZoneShape::Border(i) => {
+                border_zones.insert(i, idx);
+            }
+        }
+    }
+    let zone_for_pt = |pt: Pt2D| -> Option<usize> {
+        areas
+            .iter()
+            .find(|(_, bounds, polygon)| bounds.contains(pt) && polygon.contains_pt(pt))
+            .map(|(idx, _, _)| *idx)
+    };
+    let mut cache: BTreeMap<TripEndpoint, Option<usize>> = BTreeMap::new();
+    let mut zone_for_endpt = |endpt: TripEndpoint| -> Option<usize> {
+        *cache.entry(endpt).or_insert_with(|| match endpt {
+            TripEndpoint::Building(b) => zone_for_pt(map.get_b(b).label_center),
+            TripEndpoint::Border(i) => border_zones.get(&i).cloned(),
+            TripEndpoint::SuddenlyAppear(pos) => zone_for_pt(pos.pt(map)),
+        })
+    };
+
+    let mut trips = Vec::new();
+    for (_, trip) in app.primary.sim.all_trip_info() {
+        if let (Some(from), Some(to)) = (zone_for_endpt(trip.start), zone_for_endpt(trip.end)) {
+            trips.push((from, to, trip.departure, trip.mode));
+        }
+    }
+    trips
+}
@@ -0,0 +1,166 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+
+use map_gui::tools::compare_counts::{CompareCounts, Layer};
+use map_gui::tools::FilePicker;
+use sim::AgentType;
+use synthpop::TrafficCounts;
+use widgetry::tools::PopupMsg;
+use widgetry::{
+    EventCtx, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, State, Text, VerticalAlignment,
+    Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::sandbox::dashboards::DashTab;
+
+/// Compares simulated throughput against counts from another tool (like SUMO) or real
+/// observations on the same network, to help judge how much to trust the model.
+pub struct ExternalCounts {
+    panel: Panel,
+    compare: Option<CompareCounts>,
+}
+
+impl ExternalCounts {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let mut state = ExternalCounts {
+            panel: Panel::empty(ctx),
+            compare: None,
+        };
+        state.rebuild_panel(ctx, app);
+        Box::new(state)
+    }
+
+    fn rebuild_panel(&mut self, ctx: &mut EventCtx, app: &App) {
+        let mut col = vec![
+            DashTab::ExternalCounts.picker(ctx, app),
+            Text::from(Line(
+                "Load vehicle counts per road from SUMO edgeData output (XML), a CSV file with \
+                 osm_way_id and count columns, or counts saved by another A/B Street tool (JSON). \
+                 The simulated counts include cars and buses so far today.",
+            ))
+            .wrap_to_pct(ctx, 30)
+            .into_widget(ctx),
+            ctx.style().btn_outline.text("Load counts").build_def(ctx),
+        ];
+        if let Some(ref compare) = self.compare {
+            col.push(compare.get_panel_widget(ctx).named("compare counts"));
+        }
+        self.panel = Panel::new_builder(Widget::col(col))
+            .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+            .build(ctx);
+    }
+
+    fn set_counts(&mut self, ctx: &mut EventCtx, app: &App, external: TrafficCounts) {
+        let agent_types: BTreeSet<AgentType> =
+            vec![AgentType::Car, AgentType::Bus].into_iter().collect();
+        let simulated = app.primary.sim.get_analytics().traffic_counts(
+            &app.primary.map,
+            "Simulated".to_string(),
+            &agent_types,
+        );
+        external.quickly_compare(&simulated);
+        self.compare = Some(CompareCounts::new(
+            ctx,
+            app,
+            external,
+            simulated,
+            Layer::Compare,
+            false,
+        ));
+        self.rebuild_panel(ctx, app);
+    }
+}
+
+impl State<App> for ExternalCounts {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Load counts" => {
+                    return Transition::Push(FilePicker::new_state(
+                        ctx,
+                        None,
+                        Box::new(|ctx, app, maybe_file| {
+                            let result = match maybe_file {
+                                Ok(Some((path, bytes))) => parse_counts(app, path, bytes),
+                                // The user didn't pick a file
+                                Ok(None) => {
+                                    return Transition::Pop;
+                                }
+                                Err(err) => Err(err),
+                            };
+                            match result {
+                                Ok(counts) => Transition::Multi(vec![
+                                    Transition::Pop,
+                                    Transition::ModifyState(Box::new(move |state, ctx, app| {
+                                        let state = state.downcast_mut::<ExternalCounts>().unwrap();
+                                        state.set_counts(ctx, app, counts);
+                                    })),
+                                ]),
+                                Err(err) => Transition::Replace(PopupMsg::new_state(
+                                    ctx,
+                                    "Error",
+                                    vec![format!("Couldn't load counts: {}", err)],
+                                )),
+                            }
+                        }),
+                    ));
+                }
+                x => {
+                    if let Some(ref mut compare) = self.compare {
+                        let widget = compare
+                            .on_click(ctx, app, x)
+                            .expect("button click didn't belong to CompareCounts");
+                        self.panel.replace(ctx, "compare counts", widget);
+                    }
+                    return Transition::Keep;
+                }
+            },
+            Outcome::Changed(_) => {
+                return DashTab::ExternalCounts
+                    .transition(ctx, app, &self.panel)
+                    .unwrap();
+            }
+            _ => {}
+        }
+
+        if let Some(ref mut compare) = self.compare {
+            compare.other_event(ctx);
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        if let Some(ref compare) = self.compare {
+            compare.draw(g, app);
+        }
+        self.panel.draw(g);
+    }
+}
+
+fn parse_counts(app: &App, path: String, bytes: Vec<u8>) -> Result<TrafficCounts> {
+    let map = &app.primary.map;
+    let description = abstutil::basename(&path);
+    if path.ends_with(".xml") {
+        TrafficCounts::import_sumo_edgedata(map, description, &String::from_utf8(bytes)?)
+    } else if path.ends_with(".csv") {
+        TrafficCounts::import_link_volumes_csv(map, description, &bytes)
+    } else {
+        let counts: TrafficCounts = abstutil::from_json(&bytes)?;
+        if &counts.map != map.get_name() {
+            bail!(
+                "These counts are for {}, not {}",
+                counts.map.describe(),
+                map.get_name().describe()
+            );
+        }
+        Ok(counts)
+    }
+}
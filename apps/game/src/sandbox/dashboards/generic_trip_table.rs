@@ -47,11 +47,10 @@ pub(crate) fn preview_trip(
 
     g.redraw(&app.primary.draw_map.boundary_polygon);
     g.redraw(&app.primary.draw_map.draw_all_areas);
-    g.redraw(
-        &app.primary
-            .draw_map
-            .draw_all_unzoomed_roads_and_intersections,
-    );
+    app.primary
+        .draw_map
+        .draw_all_unzoomed_roads_and_intersections
+        .draw_within(g, &map_bounds, zoom);
 
     if let Some(x) = panel.currently_hovering() {
         if let Ok(idx) = x.parse::<usize>() {
@@ -1,9 +1,11 @@
 use abstutil::{prettyprint_usize, Counter};
-use geom::Time;
+use geom::{Duration, Time};
+use map_gui::tools::color_for_mode;
 use map_model::TransitRouteID;
+use synthpop::TripMode;
 use widgetry::{
-    Autocomplete, EventCtx, GfxCtx, Image, Line, LinePlot, Outcome, Panel, PlotOptions, Series,
-    State, TextExt, Widget,
+    Autocomplete, BoxGroup, BoxPlot, EventCtx, GfxCtx, HistogramPlot, Image, Line, LinePlot,
+    Outcome, Panel, PlotOptions, Series, StackedAreaPlot, State, TextExt, Widget,
 };
 
 use crate::app::{App, Transition};
@@ -53,6 +55,7 @@ impl ActiveTraffic {
                     app.opts.units,
                 )
                 .section(ctx),
+                finished_trip_plots(ctx, app).section(ctx),
             ]))
             .exact_size_percent(90, 90)
             .build(ctx),
@@ -79,6 +82,85 @@ impl State<App> for ActiveTraffic {
     }
 }
 
+fn finished_trip_plots(ctx: &mut EventCtx, app: &App) -> Widget {
+    let num_hours = app.primary.sim.time().get_hours() + 1;
+
+    let mut per_hour: Vec<Counter<TripMode>> = std::iter::repeat_with(Counter::new)
+        .take(num_hours)
+        .collect();
+    let mut durations = Vec::new();
+    let mut durations_per_mode: Vec<Vec<Duration>> = vec![Vec::new(); TripMode::all().len()];
+    for (t, _, mode, maybe_duration) in &app.primary.sim.get_analytics().finished_trips {
+        if let Some(dt) = maybe_duration {
+            per_hour[t.get_hours().min(num_hours - 1)].inc(*mode);
+            durations.push(*dt);
+            durations_per_mode[TripMode::all().iter().position(|m| m == mode).unwrap()].push(*dt);
+        }
+    }
+
+    let mode_share = TripMode::all()
+        .into_iter()
+        .map(|mode| Series {
+            label: mode.noun().to_string(),
+            color: color_for_mode(app, mode),
+            pts: per_hour
+                .iter()
+                .enumerate()
+                .map(|(hour, cnt)| (Time::START_OF_DAY + Duration::hours(hour), cnt.get(mode)))
+                .collect(),
+        })
+        .collect();
+    let duration_per_mode = TripMode::all()
+        .into_iter()
+        .zip(durations_per_mode)
+        .map(|(mode, values)| BoxGroup {
+            label: mode.noun().to_string(),
+            color: color_for_mode(app, mode),
+            values,
+        })
+        .collect();
+
+    Widget::col(vec![
+        Line("Trips finished per hour, by mode")
+            .small_heading()
+            .into_widget(ctx),
+        StackedAreaPlot::new_widget(
+            ctx,
+            "mode share",
+            mode_share,
+            PlotOptions::fixed(),
+            app.opts.units,
+        ),
+        Widget::row(vec![
+            Widget::col(vec![
+                Line("Trip durations").small_heading().into_widget(ctx),
+                HistogramPlot::new_widget(
+                    ctx,
+                    "trip durations",
+                    durations,
+                    20,
+                    app.cs.after_changes,
+                    PlotOptions::fixed(),
+                    app.opts.units,
+                ),
+            ]),
+            Widget::col(vec![
+                Line("Trip durations by mode")
+                    .small_heading()
+                    .into_widget(ctx),
+                BoxPlot::new_widget(
+                    ctx,
+                    "trip durations by mode",
+                    duration_per_mode,
+                    PlotOptions::fixed(),
+                    app.opts.units,
+                ),
+            ]),
+        ])
+        .evenly_spaced(),
+    ])
+}
+
 fn downsample(raw: Vec<(Time, usize)>) -> Vec<(Time, usize)> {
     if raw.is_empty() {
         return raw;
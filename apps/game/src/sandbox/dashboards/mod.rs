@@ -1,3 +1,4 @@
+pub use analysis_zones::AnalysisZones;
 pub use commuter::CommuterPatterns;
 pub use traffic_signals::TrafficSignalDemand;
 
@@ -6,7 +7,11 @@ use widgetry::{Choice, EventCtx, Image, Line, Panel, State, TextExt, Widget};
 use crate::app::App;
 use crate::app::Transition;
 
+mod analysis_zones;
 mod commuter;
+mod compare_runs;
+mod desire_lines;
+mod external_counts;
 mod generic_trip_table;
 mod misc;
 mod mode_shift;
@@ -28,8 +33,12 @@ pub enum DashTab {
     ActiveTraffic,
     TransitRoutes,
     CommuterPatterns,
+    DesireLines,
+    AnalysisZones,
     TrafficSignals,
     ModeShift,
+    ExternalCounts,
+    CompareRuns,
 }
 
 impl DashTab {
@@ -42,8 +51,12 @@ impl DashTab {
             Choice::new("Active Traffic", DashTab::ActiveTraffic),
             Choice::new("Transit Routes", DashTab::TransitRoutes),
             Choice::new("Commuter Patterns", DashTab::CommuterPatterns),
+            Choice::new("Origin-Destination Desire Lines", DashTab::DesireLines),
+            Choice::new("Analysis Zones", DashTab::AnalysisZones),
             Choice::new("Traffic Signal Demand", DashTab::TrafficSignals),
             Choice::new("Mode shift (experimental)", DashTab::ModeShift),
+            Choice::new("Compare with external counts", DashTab::ExternalCounts),
+            Choice::new("Compare two runs", DashTab::CompareRuns),
         ];
         if app.has_prebaked().is_none() {
             choices.remove(1);
@@ -71,8 +84,12 @@ impl DashTab {
             DashTab::ActiveTraffic => misc::ActiveTraffic::new_state(ctx, app),
             DashTab::TransitRoutes => misc::TransitRoutes::new_state(ctx, app),
             DashTab::CommuterPatterns => CommuterPatterns::new_state(ctx, app),
+            DashTab::DesireLines => desire_lines::DesireLines::new_state(ctx, app),
+            DashTab::AnalysisZones => analysis_zones::AnalysisZonesDashboard::new_state(ctx, app),
             DashTab::TrafficSignals => TrafficSignalDemand::new_state(ctx, app),
             DashTab::ModeShift => mode_shift::ModeShift::new_state(ctx, app),
+            DashTab::ExternalCounts => external_counts::ExternalCounts::new_state(ctx, app),
+            DashTab::CompareRuns => compare_runs::CompareRuns::new_state(ctx, app),
         }
     }
 
@@ -77,8 +77,8 @@ impl State<App> for ParkingOverhead {
             Outcome::Clicked(x) => {
                 if self.table.clicked(&x) {
                     self.table.replace_render(ctx, app, &mut self.panel);
-                } else if let Ok(idx) = x.parse::<usize>() {
-                    return open_trip_transition(app, idx);
+                } else if let Some(entry) = self.table.clicked_row(&x) {
+                    return open_trip_transition(app, entry.trip.0);
                 } else if x == "close" {
                     return Transition::Pop;
                 } else {
@@ -207,6 +207,7 @@ fn make_table(app: &App) -> Table<App, Entry, Filters> {
         "Percent overhead",
         filter,
     );
+    table.searchable(Box::new(|x| x.trip.0.to_string()));
     table.static_col("Trip ID", Box::new(|x| x.trip.0.to_string()));
     table.column(
         "Total duration",
@@ -119,6 +119,20 @@ impl TripTable {
     }
 }
 
+impl TripTable {
+    fn clicked_trip(&self, action: &str) -> Option<TripID> {
+        match self.table_tabs.active_tab_idx() {
+            0 => self.finished_trips_table.clicked_row(action).map(|x| x.id),
+            1 => self.cancelled_trips_table.clicked_row(action).map(|x| x.id),
+            2 => self
+                .unfinished_trips_table
+                .clicked_row(action)
+                .map(|x| x.id),
+            _ => unreachable!(),
+        }
+    }
+}
+
 impl State<App> for TripTable {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
         match self.panel.event(ctx) {
@@ -136,8 +150,8 @@ impl State<App> for TripTable {
                 {
                     self.unfinished_trips_table
                         .replace_render(ctx, app, &mut self.panel);
-                } else if let Ok(idx) = x.parse::<usize>() {
-                    return open_trip_transition(app, idx);
+                } else if let Some(id) = self.clicked_trip(&x) {
+                    return open_trip_transition(app, id.0);
                 } else if x == "close" {
                     return Transition::Pop;
                 } else if self.table_tabs.handle_action(ctx, &x, &mut self.panel) {
@@ -416,6 +430,9 @@ fn make_table_finished_trips(app: &App) -> Table<App, FinishedTrip, Filters> {
         "Percent waiting",
         filter,
     );
+    table.searchable(Box::new(|x| {
+        format!("{} {}", x.id.0, x.mode.ongoing_verb())
+    }));
     table.static_col("Trip ID", Box::new(|x| x.id.0.to_string()));
     if app.primary.has_modified_trips {
         table.static_col(
@@ -572,6 +589,9 @@ fn make_table_cancelled_trips(app: &App) -> Table<App, CancelledTrip, Filters> {
         "Departure",
         filter,
     );
+    table.searchable(Box::new(|x| {
+        format!("{} {} {}", x.id.0, x.mode.ongoing_verb(), x.reason)
+    }));
     table.static_col("Trip ID", Box::new(|x| x.id.0.to_string()));
     table.column(
         "Type",
@@ -673,6 +693,9 @@ fn make_table_unfinished_trips(app: &App) -> Table<App, UnfinishedTrip, Filters>
         "Departure",
         filter,
     );
+    table.searchable(Box::new(|x| {
+        format!("{} {}", x.id.0, x.mode.ongoing_verb())
+    }));
     table.static_col("Trip ID", Box::new(|x| x.id.0.to_string()));
     table.column(
         "Type",
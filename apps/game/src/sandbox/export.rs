@@ -0,0 +1,187 @@
+use abstutil::Timer;
+use geom::Duration;
+use map_gui::tools::{CameraKeyframe, RecordVideo, VideoOptions};
+use widgetry::{
+    Choice, DrawBaselayer, EventCtx, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, State,
+    Text, TextExt, Toggle, UpdateType, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+
+/// Save the current view as an image, or record the simulation as a sequence of frames. The map
+/// can be moved while this is open, to set up the camera.
+pub struct ExportView {
+    panel: Panel,
+    camera_path: Vec<CameraKeyframe>,
+}
+
+impl ExportView {
+    pub fn new_state(ctx: &mut EventCtx) -> Box<dyn State<App>> {
+        let mut state = ExportView {
+            panel: Panel::empty(ctx),
+            camera_path: Vec::new(),
+        };
+        state.recreate_panel(ctx, None);
+        Box::new(state)
+    }
+
+    fn recreate_panel(&mut self, ctx: &mut EventCtx, status: Option<String>) {
+        let mut panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Export").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            ctx.style()
+                .btn_outline
+                .text("save a screenshot")
+                .build_def(ctx),
+            Widget::horiz_separator(ctx, 1.0),
+            Line("Record the simulation")
+                .small_heading()
+                .into_widget(ctx),
+            Widget::row(vec![
+                "Simulate for".text_widget(ctx).centered_vert(),
+                Widget::dropdown(
+                    ctx,
+                    "duration",
+                    Duration::minutes(30),
+                    vec![
+                        Choice::new("10 minutes", Duration::minutes(10)),
+                        Choice::new("30 minutes", Duration::minutes(30)),
+                        Choice::new("1 hour", Duration::hours(1)),
+                        Choice::new("3 hours", Duration::hours(3)),
+                    ],
+                ),
+            ]),
+            Widget::row(vec![
+                "One frame every".text_widget(ctx).centered_vert(),
+                Widget::dropdown(
+                    ctx,
+                    "time per frame",
+                    Duration::seconds(10.0),
+                    vec![
+                        Choice::new("5 seconds", Duration::seconds(5.0)),
+                        Choice::new("10 seconds", Duration::seconds(10.0)),
+                        Choice::new("30 seconds", Duration::seconds(30.0)),
+                        Choice::new("1 minute", Duration::minutes(1)),
+                    ],
+                ),
+            ]),
+            Text::from_multiline(vec![
+                Line(format!("{} camera keyframes", self.camera_path.len())),
+                Line("With none, the camera stays here. Otherwise it moves through each one.")
+                    .secondary(),
+            ])
+            .into_widget(ctx),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_outline
+                    .text("add keyframe here")
+                    .build_def(ctx),
+                ctx.style()
+                    .btn_outline
+                    .text("clear keyframes")
+                    .disabled(self.camera_path.is_empty())
+                    .build_def(ctx),
+            ]),
+            Toggle::checkbox(ctx, "also make a GIF", None, false),
+            ctx.style()
+                .btn_solid_primary
+                .text("start recording")
+                .build_def(ctx),
+            status
+                .map(|x| x.text_widget(ctx))
+                .unwrap_or_else(Widget::nothing),
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+        .build(ctx);
+        panel.restore(ctx, &self.panel);
+        self.panel = panel;
+    }
+}
+
+impl State<App> for ExportView {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "save a screenshot" => {
+                    let filename = format!(
+                        "screenshots/{}_{}.png",
+                        app.primary.map.get_name().as_filename(),
+                        app.primary.sim.time().as_filename()
+                    );
+                    ctx.request_update(UpdateType::ScreenCaptureViewport {
+                        filename: filename.clone(),
+                    });
+                    self.recreate_panel(ctx, Some(format!("Saved {}", filename)));
+                }
+                "add keyframe here" => {
+                    self.camera_path.push(CameraKeyframe::current(ctx));
+                    self.recreate_panel(ctx, None);
+                }
+                "clear keyframes" => {
+                    self.camera_path.clear();
+                    self.recreate_panel(ctx, None);
+                }
+                "start recording" => {
+                    let duration: Duration = self.panel.dropdown_value("duration");
+                    let time_per_frame: Duration = self.panel.dropdown_value("time per frame");
+                    let dir = format!(
+                        "recordings/{}_{}",
+                        app.primary.map.get_name().as_filename(),
+                        app.primary.sim.time().as_filename()
+                    );
+                    let gif = if self.panel.is_checked("also make a GIF") {
+                        Some(format!("{}.gif", dir))
+                    } else {
+                        None
+                    };
+                    let opts = VideoOptions {
+                        dir,
+                        num_frames: (duration / time_per_frame) as usize + 1,
+                        time_per_frame,
+                        camera_path: self.camera_path.clone(),
+                        gif,
+                        gif_frame_delay_ms: 100,
+                    };
+                    return Transition::Replace(RecordVideo::new_state(
+                        ctx,
+                        opts,
+                        Box::new(|_, app: &mut App, dt| {
+                            app.primary.sim.timed_step(
+                                &app.primary.map,
+                                dt,
+                                &mut app.primary.sim_cb,
+                                &mut Timer::throwaway(),
+                            );
+                            app.primary.snapshots.maybe_capture(
+                                &app.primary.sim,
+                                &app.primary.map,
+                                app.secondary.is_some(),
+                            );
+                        }),
+                    ));
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::DefaultDraw
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        // Don't include this panel in screenshots
+        if !g.is_screencap() {
+            self.panel.draw(g);
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use abstio::MapName;
+use geom::{Distance, Time};
+use widgetry::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, Text,
+    TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::app::Transition;
+use crate::challenges::custom::{ChallengeGoal, ChallengeMetrics, CustomChallenge};
+use crate::challenges::cutscene::ShowMessage;
+use crate::challenges::HighScore;
+use crate::edit::EditMode;
+use crate::sandbox::gameplay::{challenge_header, FinalScore, GameplayMode, GameplayState};
+use crate::sandbox::{Actions, SandboxControls};
+
+/// Plays a challenge made with the challenge editor. The score is based on comparing the
+/// simulation at `CustomChallenge::score_time` with the baseline recorded in the challenge.
+pub struct PlayCustomChallenge {
+    top_right: Panel,
+    mode: GameplayMode,
+    challenge: CustomChallenge,
+    draw_zone: Drawable,
+    time: Time,
+    done: bool,
+}
+
+impl PlayCustomChallenge {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        app: &App,
+        map_name: &MapName,
+        name: &str,
+    ) -> Box<dyn GameplayState> {
+        let challenge = CustomChallenge::load(map_name, name).unwrap();
+
+        let mut batch = GeomBatch::new();
+        if let ChallengeGoal::CutNeighborhoodTraffic { ref zone, .. } = challenge.goal {
+            batch.push(
+                app.cs.selected.alpha(0.8),
+                zone.to_outline(Distance::meters(5.0)),
+            );
+        }
+
+        Box::new(PlayCustomChallenge {
+            top_right: Panel::empty(ctx),
+            mode: GameplayMode::Custom(map_name.clone(), name.to_string()),
+            challenge,
+            draw_zone: ctx.upload(batch),
+            time: Time::START_OF_DAY,
+            done: false,
+        })
+    }
+
+    fn instructions(&self, ctx: &mut EventCtx) -> Widget {
+        let mut txt = Text::from(Line(&self.challenge.name).small_heading().fg(Color::BLACK));
+        if !self.challenge.description.is_empty() {
+            txt.add_line(Line(&self.challenge.description).fg(Color::BLACK));
+        }
+        txt.add_line(Line(self.challenge.describe_goal()).fg(Color::BLACK));
+        txt.add_line(
+            Line(format!(
+                "Edit the map, then simulate until {} to see how you did.",
+                CustomChallenge::score_time().ampm_tostring()
+            ))
+            .fg(Color::BLACK),
+        );
+        txt.wrap_to_pct(ctx, 50).into_widget(ctx)
+    }
+}
+
+impl GameplayState for PlayCustomChallenge {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        _: &mut SandboxControls,
+        _: &mut Actions,
+    ) -> Option<Transition> {
+        if self.time != app.primary.sim.time() && !self.done {
+            self.time = app.primary.sim.time();
+            self.recreate_panels(ctx, app);
+
+            if self.time >= CustomChallenge::score_time() {
+                self.done = true;
+                let current = ChallengeMetrics::measure(
+                    &app.primary.map,
+                    app.primary.sim.get_analytics(),
+                    &self.challenge.goal,
+                );
+                let (mut msg, score) = self.challenge.score(&current);
+                if let Some(score) = score {
+                    HighScore {
+                        goal: self.challenge.describe_goal(),
+                        score,
+                        edits_name: app.primary.map.get_edits().edits_name.clone(),
+                    }
+                    .record(app, self.mode.clone());
+                    msg = format!("{} You did it!", msg);
+                } else {
+                    msg = format!("{} That's not enough to meet the goal.", msg);
+                }
+                return Some(Transition::Push(FinalScore::new_state(
+                    ctx,
+                    msg,
+                    self.mode.clone(),
+                    // Custom challenges don't form a sequence
+                    None,
+                )));
+            }
+        }
+
+        if let Outcome::Clicked(x) = self.top_right.event(ctx) {
+            match x.as_ref() {
+                "edit map" => {
+                    return Some(Transition::Push(EditMode::new_state(
+                        ctx,
+                        app,
+                        self.mode.clone(),
+                    )));
+                }
+                "instructions" => {
+                    let contents = self.instructions(ctx);
+                    return Some(Transition::Push(ShowMessage::new_state(
+                        ctx,
+                        contents,
+                        Color::WHITE,
+                    )));
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        None
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw_zone);
+        self.top_right.draw(g);
+    }
+
+    fn recreate_panels(&mut self, ctx: &mut EventCtx, app: &App) {
+        let score_time = CustomChallenge::score_time();
+        let status = if app.primary.sim.time() >= score_time {
+            "Time's up".to_string()
+        } else {
+            format!("{} left", score_time - app.primary.sim.time())
+        };
+
+        self.top_right = Panel::new_builder(Widget::col(vec![
+            challenge_header(ctx, &self.challenge.name),
+            Text::from(self.challenge.describe_goal())
+                .wrap_to_pct(ctx, 30)
+                .into_widget(ctx),
+            status.text_widget(ctx),
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+        .build(ctx);
+    }
+}
@@ -122,10 +122,6 @@ impl GameplayState for Freeform {
     fn recreate_panels(&mut self, ctx: &mut EventCtx, app: &App) {
         let rows = vec![
             Widget::custom_row(vec![
-                Line("Sandbox")
-                    .small_heading()
-                    .into_widget(ctx)
-                    .margin_right(18),
                 map_gui::tools::change_map_btn(ctx, app).margin_right(8),
                 ctx.style()
                     .btn_popup_icon_text("system/assets/tools/calendar.svg", "none")
@@ -166,6 +162,7 @@ impl GameplayState for Freeform {
 
         self.top_right = Panel::new_builder(Widget::col(rows))
             .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .dockable(ctx, "freeform", "Sandbox")
             .build(ctx);
     }
 }
@@ -18,6 +18,7 @@ pub use self::freeform::spawn_agents_around;
 pub use self::tutorial::{Tutorial, TutorialPointer, TutorialState};
 use crate::app::App;
 use crate::app::Transition;
+use crate::challenges::custom::CustomChallenge;
 use crate::challenges::{Challenge, ChallengesPicker};
 use crate::edit::SaveEdits;
 use crate::pregame::TitleScreen;
@@ -26,6 +27,7 @@ use crate::sandbox::{Actions, SandboxControls, SandboxMode};
 // TODO pub so challenges can grab cutscenes and SandboxMode can dispatch to actions. Weird?
 mod actdev;
 pub mod commute;
+mod custom;
 pub mod fix_traffic_signals;
 pub mod freeform;
 pub mod play_scenario;
@@ -41,6 +43,8 @@ pub enum GameplayMode {
     OptimizeCommute(OrigPersonID, Duration),
     // Map name, scenario name, background traffic
     Actdev(MapName, String, bool),
+    // Map name, challenge name
+    Custom(MapName, String),
 
     // current
     Tutorial(TutorialPointer),
@@ -100,6 +104,7 @@ impl GameplayMode {
             GameplayMode::OptimizeCommute(_, _) => MapName::seattle("montlake"),
             GameplayMode::Tutorial(_) => MapName::seattle("montlake"),
             GameplayMode::Actdev(ref name, _, _) => name.clone(),
+            GameplayMode::Custom(ref name, _) => name.clone(),
         }
     }
 
@@ -130,6 +135,15 @@ impl GameplayMode {
             GameplayMode::FixTrafficSignals | GameplayMode::OptimizeCommute(_, _) => {
                 "weekday".to_string()
             }
+            GameplayMode::Custom(ref map_name, ref name) => {
+                match CustomChallenge::load(map_name, name) {
+                    Ok(challenge) => challenge.scenario,
+                    Err(err) => {
+                        error!("Couldn't load challenge {}: {}", name, err);
+                        return LoadScenario::Nothing;
+                    }
+                }
+            }
         };
         if name == "random" {
             LoadScenario::Scenario(ScenarioGenerator::small_run(map).generate(map, &mut rng, timer))
@@ -175,7 +189,7 @@ impl GameplayMode {
     }
 
     pub fn allows(&self, edits: &MapEdits) -> bool {
-        for cmd in &edits.commands {
+        for cmd in edits.commands.iter().flat_map(|cmd| cmd.flatten()) {
             match cmd {
                 EditCmd::ChangeRoad { .. } => {
                     if !self.can_edit_roads() {
@@ -199,7 +213,8 @@ impl GameplayMode {
                         return false;
                     }
                 }
-                EditCmd::ChangeRouteSchedule { .. } => {}
+                EditCmd::ChangeRouteSchedule { .. } | EditCmd::ChangeRouteFare { .. } => {}
+                EditCmd::Group { .. } => unreachable!(),
             }
         }
         true
@@ -223,6 +238,9 @@ impl GameplayMode {
             GameplayMode::Actdev(_, ref scenario, bg_traffic) => {
                 actdev::Actdev::new_state(ctx, scenario.clone(), *bg_traffic)
             }
+            GameplayMode::Custom(ref map_name, ref name) => {
+                custom::PlayCustomChallenge::new_state(ctx, app, map_name, name)
+            }
         }
     }
 }
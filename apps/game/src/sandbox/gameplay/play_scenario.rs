@@ -5,7 +5,9 @@ use maplit::btreeset;
 use abstutil::prettyprint_usize;
 use geom::{Duration, Time};
 use map_gui::tools::{checkbox_per_mode, grey_out_map, CityPicker};
+use map_model::BuildingID;
 use sim::SlidingWindow;
+use synthpop::make::EventSpec;
 use synthpop::{ScenarioModifier, TripMode};
 use widgetry::tools::{ChooseSomething, PopupMsg, URLManager};
 use widgetry::{
@@ -19,6 +21,7 @@ use crate::edit::EditMode;
 use crate::sandbox::gameplay::freeform::ChangeScenario;
 use crate::sandbox::gameplay::{GameplayMode, GameplayState};
 use crate::sandbox::{Actions, SandboxControls, SandboxMode, TimeWarpScreen};
+use crate::ID;
 
 pub struct PlayScenario {
     top_right: Panel,
@@ -227,7 +230,8 @@ impl EditScenarioModifiers {
             Text::from(
                 "This scenario determines the exact trips everybody takes, when they leave, where \
                  they go, and how they choose to get there. You can modify those patterns here. \
-                 The modifications apply in order.",
+                 The modifications apply in order. To add a large event, click its venue on the \
+                 map.",
             )
             .wrap_to_pct(ctx, 50)
             .into_widget(ctx),
@@ -570,6 +574,205 @@ impl State<App> for ChangeMode {
     }
 }
 
+/// Lets the player describe a large event at a building, generating a surge of trips to and from
+/// it on top of the scenario.
+struct AddEvent {
+    panel: Panel,
+    scenario_name: String,
+    modifiers: Vec<ScenarioModifier>,
+    venue: BuildingID,
+}
+
+impl AddEvent {
+    fn new_state(
+        ctx: &mut EventCtx,
+        app: &App,
+        scenario_name: String,
+        modifiers: Vec<ScenarioModifier>,
+        venue: BuildingID,
+    ) -> Box<dyn State<App>> {
+        let default_split = [
+            (TripMode::Walk, 10),
+            (TripMode::Bike, 5),
+            (TripMode::Transit, 45),
+            (TripMode::Drive, 40),
+        ];
+        let mut mode_row = Vec::new();
+        for (mode, pct) in default_split {
+            mode_row.push(mode.ongoing_verb().text_widget(ctx).centered_vert());
+            mode_row.push(Spinner::widget(
+                ctx,
+                format!("pct {}", mode.ongoing_verb()),
+                (0, 100),
+                pct as usize,
+                5,
+            ));
+        }
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            Line("Host a large event").small_heading().into_widget(ctx),
+            Text::from(format!(
+                "At {}. Attendees come from homes all over the map and go back home afterwards.",
+                app.primary.map.get_b(venue).address
+            ))
+            .wrap_to_pct(ctx, 50)
+            .into_widget(ctx),
+            Widget::row(vec![
+                "Attendance:".text_widget(ctx).centered_vert(),
+                Spinner::widget(ctx, "attendance", (100, 100_000), 20_000_usize, 500),
+            ]),
+            Widget::row(vec![
+                "Starts at:".text_widget(ctx).centered_vert(),
+                Spinner::widget(
+                    ctx,
+                    "start",
+                    (Duration::ZERO, Duration::hours(24)),
+                    Duration::hours(19),
+                    Duration::minutes(15),
+                ),
+                "and ends at:".text_widget(ctx).centered_vert(),
+                Spinner::widget(
+                    ctx,
+                    "end",
+                    (Duration::ZERO, Duration::hours(24)),
+                    Duration::hours(22),
+                    Duration::minutes(15),
+                ),
+            ]),
+            Widget::row(vec![
+                "Attendees arrive over the previous:"
+                    .text_widget(ctx)
+                    .centered_vert(),
+                Spinner::widget(
+                    ctx,
+                    "arrival spread",
+                    (Duration::minutes(15), Duration::hours(4)),
+                    Duration::hours(2),
+                    Duration::minutes(15),
+                ),
+            ]),
+            Widget::row(vec![
+                "and leave over the following:"
+                    .text_widget(ctx)
+                    .centered_vert(),
+                Spinner::widget(
+                    ctx,
+                    "departure spread",
+                    (Duration::minutes(15), Duration::hours(4)),
+                    Duration::hours(1),
+                    Duration::minutes(15),
+                ),
+            ]),
+            "Percent of attendees:".text_widget(ctx),
+            Widget::row(mode_row),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_solid_primary
+                    .text("Apply")
+                    .hotkey(Key::Enter)
+                    .build_def(ctx),
+                ctx.style()
+                    .btn_solid_destructive
+                    .text("Discard changes")
+                    .hotkey(Key::Escape)
+                    .build_def(ctx),
+            ])
+            .centered(),
+        ]))
+        .exact_size_percent(80, 80)
+        .build(ctx);
+
+        Box::new(AddEvent {
+            panel,
+            scenario_name,
+            modifiers,
+            venue,
+        })
+    }
+}
+
+impl State<App> for AddEvent {
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition {
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "Discard changes" => {
+                    return Transition::Pop;
+                }
+                "Apply" => {
+                    let event = EventSpec {
+                        venue: self.venue,
+                        attendance: self.panel.spinner("attendance"),
+                        start: Time::START_OF_DAY + self.panel.spinner("start"),
+                        end: Time::START_OF_DAY + self.panel.spinner("end"),
+                        arrival_spread: self.panel.spinner("arrival spread"),
+                        departure_spread: self.panel.spinner("departure spread"),
+                        mode_split: TripMode::all()
+                            .into_iter()
+                            .map(|m| (m, self.panel.spinner(&format!("pct {}", m.ongoing_verb()))))
+                            .filter(|(_, pct)| *pct > 0)
+                            .collect(),
+                    };
+                    if event.start >= event.end {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Error",
+                            vec!["The event must end after it starts"],
+                        ));
+                    }
+                    if event.mode_split.is_empty() {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Error",
+                            vec!["Attendees have to travel somehow"],
+                        ));
+                    }
+
+                    let mut mods = self.modifiers.clone();
+                    mods.push(ScenarioModifier::AddEvent(event));
+                    return Transition::Multi(vec![
+                        Transition::Pop,
+                        Transition::Push(EditScenarioModifiers::new_state(
+                            ctx,
+                            self.scenario_name.clone(),
+                            mods,
+                        )),
+                    ]);
+                }
+                _ => unreachable!(),
+            }
+        }
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+        self.panel.draw(g);
+    }
+}
+
+pub fn actions(_: &App, id: ID) -> Vec<(Key, String)> {
+    match id {
+        ID::Building(_) => vec![(Key::V, "host a large event here".to_string())],
+        _ => Vec::new(),
+    }
+}
+
+pub fn execute(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    id: ID,
+    action: &str,
+    scenario_name: String,
+    modifiers: Vec<ScenarioModifier>,
+) -> Transition {
+    match (id, action) {
+        (ID::Building(b), "host a large event here") => {
+            Transition::Push(AddEvent::new_state(ctx, app, scenario_name, modifiers, b))
+        }
+        _ => unreachable!(),
+    }
+}
+
 pub struct DepartureSummary {
     first_trip: Time,
 }
@@ -9,6 +9,7 @@ use crate::app::App;
 use crate::app::Transition;
 use crate::common::Warping;
 use crate::layer::PickLayer;
+use crate::sandbox::{ExportView, SplitScreen};
 
 pub struct MinimapController;
 
@@ -94,6 +95,8 @@ impl MinimapControls<App> for MinimapController {
                 return Some(Transition::Push(PickLayer::pick(ctx, app)));
             }
             "more data" => Some(Transition::Push(app.session.dash_tab.launch(ctx, app))),
+            "export" => Some(Transition::Push(ExportView::new_state(ctx))),
+            "split screen" => Some(Transition::Push(SplitScreen::new_state(ctx, app))),
             _ => unreachable!(),
         }
     }
@@ -307,7 +310,7 @@ fn make_tool_panel(ctx: &mut EventCtx, app: &App) -> Widget {
         .bg_color(app.cs.inner_panel_bg, ControlState::Default)
         .padding(8);
 
-    Widget::col(vec![
+    let mut col = vec![
         (if ctx.canvas.is_zoomed() {
             buttons
                 .clone()
@@ -330,8 +333,28 @@ fn make_tool_panel(ctx: &mut EventCtx, app: &App) -> Widget {
             .hotkey(Key::K)
             .build_widget(ctx, "search"),
         buttons
+            .clone()
             .image_path("system/assets/meters/trip_histogram.svg")
             .hotkey(Key::Q)
             .build_widget(ctx, "more data"),
-    ])
+    ];
+    // Writing image files isn't supported on the web
+    if !cfg!(target_arch = "wasm32") {
+        col.push(
+            buttons
+                .image_path("system/assets/tools/export.svg")
+                .tooltip("Export screenshots and recordings")
+                .build_widget(ctx, "export"),
+        );
+    }
+    if app.secondary.is_some() {
+        col.push(
+            buttons
+                .clone()
+                .image_path("system/assets/tools/split_screen.svg")
+                .tooltip("Compare both versions of the map side-by-side")
+                .build_widget(ctx, "split screen"),
+        );
+    }
+    Widget::col(col)
 }
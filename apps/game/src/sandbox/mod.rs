@@ -12,10 +12,15 @@ use synthpop::Scenario;
 use widgetry::tools::{ChooseSomething, FileLoader, FutureLoader, URLManager};
 use widgetry::{lctrl, Choice, EventCtx, GfxCtx, Key, Outcome, Panel, State, UpdateType};
 
+pub use self::cinematic::CinematicCamera;
+pub use self::export::ExportView;
 pub use self::gameplay::{spawn_agents_around, GameplayMode, TutorialPointer, TutorialState};
 pub use self::minimap::MinimapController;
 use self::misc_tools::{RoutePreview, TrafficRecorder};
+pub use self::rewind::Snapshots;
+pub use self::scripting::RunScript;
 pub use self::speed::{SpeedSetting, TimePanel};
+pub use self::split_screen::SplitScreen;
 pub use self::time_warp::TimeWarpScreen;
 use crate::app::{App, Transition};
 use crate::common::{tool_panel, CommonState};
@@ -30,11 +35,16 @@ use crate::pregame::TitleScreen;
 use crate::render::{unzoomed_agent_radius, UnzoomedAgents};
 use crate::ID;
 
+mod cinematic;
 pub mod dashboards;
+mod export;
 pub mod gameplay;
 mod minimap;
 mod misc_tools;
+mod rewind;
+mod scripting;
 mod speed;
+mod split_screen;
 mod time_warp;
 mod turn_explorer;
 
@@ -72,8 +82,10 @@ impl SandboxMode {
         finalize: Box<dyn FnOnce(&mut EventCtx, &mut App) -> Vec<Transition>>,
     ) -> Box<dyn State<App>> {
         app.primary.clear_sim();
+        app.primary.snapshots.clear();
         if let Some(ref mut secondary) = app.secondary {
             secondary.clear_sim();
+            secondary.snapshots.clear();
         }
         Box::new(SandboxLoader {
             stage: Some(LoadStage::LoadingMap),
@@ -292,7 +304,9 @@ struct BackToTitleScreen;
 
 impl State<App> for BackToTitleScreen {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
-        app.change_color_scheme(ctx, ColorSchemeChoice::DayMode);
+        if app.opts.toggle_day_night_colors {
+            app.change_color_scheme(ctx, ColorSchemeChoice::DayMode);
+        }
         app.clear_everything(ctx);
         Transition::Clear(vec![TitleScreen::new_state(ctx, app)])
     }
@@ -345,6 +359,7 @@ impl ContextualActions for Actions {
         actions.extend(match self.gameplay {
             GameplayMode::Freeform(_) => gameplay::freeform::actions(app, id),
             GameplayMode::Tutorial(_) => gameplay::tutorial::actions(app, id),
+            GameplayMode::PlayScenario(_, _, _) => gameplay::play_scenario::actions(app, id),
             _ => Vec::new(),
         });
         actions
@@ -417,6 +432,16 @@ impl ContextualActions for Actions {
             (id, action) => match self.gameplay {
                 GameplayMode::Freeform(_) => gameplay::freeform::execute(ctx, app, id, action),
                 GameplayMode::Tutorial(_) => gameplay::tutorial::execute(ctx, app, id, action),
+                GameplayMode::PlayScenario(_, ref scenario, ref modifiers) => {
+                    gameplay::play_scenario::execute(
+                        ctx,
+                        app,
+                        id,
+                        action,
+                        scenario.clone(),
+                        modifiers.clone(),
+                    )
+                }
                 _ => unreachable!(),
             },
         }
@@ -558,15 +583,32 @@ impl State<App> for SandboxLoader {
                         // and instantiating a scenario from CLI flags.
                         let mut rng = app.primary.current_flags.sim_flags.make_rng();
 
+                        // Only warm start when freely exploring a scenario; other modes rely on
+                        // starting at midnight.
+                        let mut warm_start = None;
                         if let GameplayMode::PlayScenario(_, _, ref modifiers) = self.mode {
                             for m in modifiers {
                                 scenario = m.apply(&app.primary.map, scenario, &mut rng);
                             }
+                            warm_start = app.primary.current_flags.sim_flags.start_time;
                         }
 
-                        app.primary
-                            .sim
-                            .instantiate(&scenario, &app.primary.map, &mut rng, timer);
+                        if let Some(start) = warm_start {
+                            app.primary.sim.warm_start(
+                                &scenario,
+                                &app.primary.map,
+                                &mut rng,
+                                start,
+                                timer,
+                            );
+                        } else {
+                            app.primary.sim.instantiate(
+                                &scenario,
+                                &app.primary.map,
+                                &mut rng,
+                                timer,
+                            );
+                        }
                         app.primary
                             .sim
                             .tiny_step(&app.primary.map, &mut app.primary.sim_cb);
@@ -575,14 +617,25 @@ impl State<App> for SandboxLoader {
                             // TODO Modifiers already applied
                             secondary.scenario = Some(scenario.clone());
 
-                            secondary.sim.instantiate(
-                                &scenario,
-                                &secondary.map,
-                                // Start fresh here. This will match up with the primary sim,
-                                // unless modifiers used the RNG
-                                &mut secondary.current_flags.sim_flags.make_rng(),
-                                timer,
-                            );
+                            // Start fresh here. This will match up with the primary sim, unless
+                            // modifiers used the RNG
+                            let mut rng = secondary.current_flags.sim_flags.make_rng();
+                            if let Some(start) = warm_start {
+                                secondary.sim.warm_start(
+                                    &scenario,
+                                    &secondary.map,
+                                    &mut rng,
+                                    start,
+                                    timer,
+                                );
+                            } else {
+                                secondary.sim.instantiate(
+                                    &scenario,
+                                    &secondary.map,
+                                    &mut rng,
+                                    timer,
+                                );
+                            }
                             secondary
                                 .sim
                                 .tiny_step(&secondary.map, &mut secondary.sim_cb);
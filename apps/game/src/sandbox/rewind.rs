@@ -0,0 +1,85 @@
+use geom::{Duration, Time};
+use map_model::Map;
+use sim::Sim;
+
+/// At first, copy the simulation this often
+const INITIAL_INTERVAL: Duration = Duration::const_seconds(30.0 * 60.0);
+/// Each snapshot may use lots of memory for large maps. When there are more than this, drop every
+/// other one and start taking them half as often. When comparing against a secondary simulation,
+/// both share this budget.
+const MAX_SNAPSHOTS: usize = 12;
+
+/// Periodically copies the simulation in memory as it runs, so that rewinding to an earlier time
+/// only has to replay from the nearest snapshot, instead of resetting to midnight.
+pub struct Snapshots {
+    interval: Duration,
+    /// Sorted by time
+    snapshots: Vec<Sim>,
+    /// Snapshots from before the map was edited are useless
+    edits_key: usize,
+}
+
+impl Snapshots {
+    pub fn new() -> Snapshots {
+        Snapshots {
+            interval: INITIAL_INTERVAL,
+            snapshots: Vec::new(),
+            edits_key: 0,
+        }
+    }
+
+    /// Call when starting a different simulation.
+    pub fn clear(&mut self) {
+        self.interval = INITIAL_INTERVAL;
+        self.snapshots.clear();
+    }
+
+    /// Call after the simulation advances. Copies it if enough time has passed since the last
+    /// snapshot. `ab_test` is set when there's also a secondary simulation keeping snapshots.
+    pub fn maybe_capture(&mut self, sim: &Sim, map: &Map, ab_test: bool) {
+        if map.get_edits_change_key() != self.edits_key {
+            self.edits_key = map.get_edits_change_key();
+            self.clear();
+        }
+        // If we've gone backwards in time, the later snapshots might not happen again the same
+        // way
+        let now = sim.time();
+        self.snapshots.retain(|s| s.time() <= now);
+
+        let due = match self.snapshots.last() {
+            Some(last) => last.time() + self.interval,
+            None => Time::START_OF_DAY,
+        };
+        if now < due {
+            return;
+        }
+        self.snapshots.push(sim.clone());
+        let max = if ab_test {
+            MAX_SNAPSHOTS / 2
+        } else {
+            MAX_SNAPSHOTS
+        };
+        if self.snapshots.len() > max {
+            // Always keep the earliest one
+            let mut idx = 0;
+            self.snapshots.retain(|_| {
+                idx += 1;
+                idx % 2 == 1
+            });
+            self.interval = self.interval * 2.0;
+        }
+    }
+
+    pub fn times(&self) -> Vec<Time> {
+        self.snapshots.iter().map(|s| s.time()).collect()
+    }
+
+    /// Returns a copy of the latest snapshot at or before this time.
+    pub fn restore(&self, time: Time) -> Option<Sim> {
+        self.snapshots
+            .iter()
+            .rev()
+            .find(|s| s.time() <= time)
+            .cloned()
+    }
+}
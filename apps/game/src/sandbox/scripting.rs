@@ -0,0 +1,481 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use anyhow::Result;
+use rhai::{Dynamic, Engine, EvalAltResult, FnPtr, AST, FLOAT, INT};
+
+use abstutil::Timer;
+use geom::{Duration, LonLat, Time};
+use map_model::{BuildingID, MapEdits};
+use synthpop::{IndividTrip, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose};
+use widgetry::tools::PopupMsg;
+use widgetry::{
+    EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, State, Text, UpdateType,
+    VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::edit::apply_map_edits;
+
+/// How many lines of script output to show
+const LOG_LINES: usize = 10;
+
+/// Runs a [Rhai](https://rhai.rs) script that drives the simulation, so demos and experiments can
+/// be reproduced without changing any Rust code. The script's top level runs once when the
+/// simulation starts. These functions are available:
+///
+/// - `at("07:30:00", || { ... })` and `every("00:15:00", || { ... })` schedule a closure for a
+///   simulation time or interval
+/// - `spawn_trip(from_building, to_building, "walk" | "bike" | "transit" | "drive")`
+/// - `apply_edits(name)` loads saved map edits and applies them to the running simulation
+/// - `camera(lon, lat, zoom)`, `set_speed(multiplier)`, and `pause()`
+/// - `time()`, `finished_trips()`, `unfinished_trips()`, `finished_trips_by(mode)`, and
+///   `average_trip_time()` (in seconds) query the simulation
+/// - `print(msg)` shows a message in the panel
+pub struct RunScript {
+    panel: Panel,
+    name: String,
+    engine: Engine,
+    ast: AST,
+    shared: Rc<RefCell<Shared>>,
+    started: bool,
+    paused: bool,
+    speed: f64,
+}
+
+/// Functions called from the script can't borrow the App, so they read from a snapshot of the
+/// simulation and queue up commands to run after the script returns.
+struct Shared {
+    now: Time,
+    finished_trips: usize,
+    unfinished_trips: usize,
+    finished_by_mode: BTreeMap<TripMode, usize>,
+    average_trip_time: Duration,
+
+    scheduled: Vec<Scheduled>,
+    commands: Vec<Command>,
+    log: Vec<String>,
+}
+
+struct Scheduled {
+    time: Time,
+    repeat: Option<Duration>,
+    callback: FnPtr,
+}
+
+enum Command {
+    SpawnTrip {
+        from: BuildingID,
+        to: BuildingID,
+        mode: TripMode,
+    },
+    ApplyEdits(String),
+    Camera(LonLat, f64),
+    SetSpeed(f64),
+    Pause,
+}
+
+impl RunScript {
+    pub fn new_state(ctx: &mut EventCtx, app: &App, path: String) -> Box<dyn State<App>> {
+        let name = abstutil::basename(&path);
+        let shared = Rc::new(RefCell::new(Shared {
+            now: app.primary.sim.time(),
+            finished_trips: 0,
+            unfinished_trips: 0,
+            finished_by_mode: BTreeMap::new(),
+            average_trip_time: Duration::ZERO,
+            scheduled: Vec::new(),
+            commands: Vec::new(),
+            log: Vec::new(),
+        }));
+        let engine = make_engine(&shared);
+        let ast = match abstio::slurp_file(&path)
+            .and_then(|bytes| Ok(String::from_utf8(bytes)?))
+            .and_then(|code| engine.compile(code).map_err(|err| anyhow!("{}", err)))
+        {
+            Ok(ast) => ast,
+            Err(err) => {
+                return PopupMsg::new_state(
+                    ctx,
+                    "Couldn't load script",
+                    vec![format!("{}: {}", path, err)],
+                );
+            }
+        };
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line(format!("Running {}", name))
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            play_pause_button(ctx, false),
+            Widget::placeholder(ctx, "status"),
+        ]))
+        .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+        .build(ctx);
+
+        let mut state = RunScript {
+            panel,
+            name,
+            engine,
+            ast,
+            shared,
+            started: false,
+            paused: false,
+            speed: 1.0,
+        };
+        state.update_status(ctx, app);
+        Box::new(state)
+    }
+
+    fn update_status(&mut self, ctx: &mut EventCtx, app: &App) {
+        let mut txt = Text::from(Line(format!(
+            "{}, {}x speed",
+            app.primary.sim.time().ampm_tostring(),
+            self.speed
+        )));
+        for line in &self.shared.borrow().log {
+            txt.add_line(Line(line).secondary());
+        }
+        self.panel.replace(ctx, "status", txt.into_widget(ctx));
+    }
+
+    fn set_paused(&mut self, ctx: &mut EventCtx, paused: bool) {
+        if self.paused != paused {
+            self.paused = paused;
+            let button = play_pause_button(ctx, paused);
+            self.panel.replace(ctx, "play/pause", button);
+        }
+    }
+
+    /// Copy the parts of the simulation the script can query
+    fn update_snapshot(&self, app: &App) {
+        let mut shared = self.shared.borrow_mut();
+        let sim = &app.primary.sim;
+        shared.now = sim.time();
+        let (finished, unfinished) = sim.num_trips();
+        shared.finished_trips = finished;
+        shared.unfinished_trips = unfinished;
+        shared.finished_by_mode.clear();
+        let mut total_time = Duration::ZERO;
+        let mut num_completed = 0;
+        for (_, _, mode, maybe_dt) in &sim.get_analytics().finished_trips {
+            *shared.finished_by_mode.entry(*mode).or_insert(0) += 1;
+            if let Some(dt) = maybe_dt {
+                total_time += *dt;
+                num_completed += 1;
+            }
+        }
+        shared.average_trip_time = if num_completed == 0 {
+            Duration::ZERO
+        } else {
+            total_time / (num_completed as f64)
+        };
+    }
+
+    /// Run the script's top level the first time, then anything scheduled up to now
+    fn run_script(&mut self, ctx: &mut EventCtx, app: &mut App) {
+        self.update_snapshot(app);
+
+        let mut result = Ok(());
+        if !self.started {
+            self.started = true;
+            result = self.engine.run_ast(&self.ast);
+        }
+
+        let now = app.primary.sim.time();
+        loop {
+            if result.is_err() {
+                break;
+            }
+            // Don't hold the borrow while calling back into the script
+            let due = {
+                let mut shared = self.shared.borrow_mut();
+                let idx = shared.scheduled.iter().position(|x| x.time <= now);
+                idx.map(|idx| shared.scheduled.remove(idx))
+            };
+            let mut due = match due {
+                Some(x) => x,
+                None => break,
+            };
+            result = due
+                .callback
+                .call::<Dynamic>(&self.engine, &self.ast, ())
+                .map(|_| ());
+            if let Some(repeat) = due.repeat {
+                due.time = due.time + repeat;
+                self.shared.borrow_mut().scheduled.push(due);
+            }
+        }
+
+        if let Err(err) = result {
+            self.log(format!("Error: {}", err));
+            self.set_paused(ctx, true);
+        }
+
+        let commands = std::mem::take(&mut self.shared.borrow_mut().commands);
+        for cmd in commands {
+            if let Err(err) = self.apply(ctx, app, cmd) {
+                self.log(format!("Error: {}", err));
+                self.set_paused(ctx, true);
+            }
+        }
+        self.update_status(ctx, app);
+    }
+
+    fn apply(&mut self, ctx: &mut EventCtx, app: &mut App, cmd: Command) -> Result<()> {
+        match cmd {
+            Command::SpawnTrip { from, to, mode } => {
+                let map = &app.primary.map;
+                for b in [from, to] {
+                    if b.0 >= map.all_buildings().len() {
+                        bail!("{} doesn't exist", b);
+                    }
+                }
+                let mut scenario = Scenario::empty(map, "script");
+                scenario.people.push(PersonSpec {
+                    orig_id: None,
+                    trips: vec![IndividTrip::new(
+                        app.primary.sim.time(),
+                        TripPurpose::Shopping,
+                        TripEndpoint::Building(from),
+                        TripEndpoint::Building(to),
+                        mode,
+                    )],
+                });
+                let mut rng = app.primary.current_flags.sim_flags.make_rng();
+                app.primary.sim.instantiate(
+                    &scenario,
+                    map,
+                    &mut rng,
+                    &mut Timer::new("spawn trip"),
+                );
+                app.primary.sim.tiny_step(map, &mut app.primary.sim_cb);
+            }
+            Command::ApplyEdits(name) => {
+                let edits = MapEdits::load_from_file(
+                    &app.primary.map,
+                    abstio::path_edits(app.primary.map.get_name(), &name),
+                    &mut Timer::throwaway(),
+                )?;
+                let (trips, parked_cars) = ctx.loading_screen("apply edits", |ctx, timer| {
+                    apply_map_edits(ctx, app, edits);
+                    app.primary.map.recalculate_pathfinding_after_edits(timer);
+                    app.primary.dirty_from_edits = true;
+                    app.primary
+                        .sim
+                        .handle_live_edited_traffic_signals(&app.primary.map);
+                    app.primary.sim.handle_live_edits(&app.primary.map, timer)
+                });
+                self.log(format!(
+                    "Applied {}, interrupting {} trips and displacing {} parked cars",
+                    name, trips, parked_cars
+                ));
+            }
+            Command::Camera(gps, zoom) => {
+                ctx.canvas.cam_zoom = zoom;
+                ctx.canvas
+                    .center_on_map_pt(gps.to_pt(app.primary.map.get_gps_bounds()));
+            }
+            Command::SetSpeed(speed) => {
+                self.speed = speed;
+            }
+            Command::Pause => {
+                self.set_paused(ctx, true);
+            }
+        }
+        Ok(())
+    }
+
+    fn log(&self, msg: String) {
+        info!("{}: {}", self.name, msg);
+        push_log(&mut self.shared.borrow_mut(), msg);
+    }
+
+    /// The next time something is scheduled to run
+    fn next_scheduled(&self) -> Option<Time> {
+        self.shared.borrow().scheduled.iter().map(|x| x.time).min()
+    }
+}
+
+impl State<App> for RunScript {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if !self.started {
+            self.run_script(ctx, app);
+        }
+
+        if !self.paused {
+            if let Some(real_dt) = ctx.input.nonblocking_is_update_event() {
+                ctx.input.use_update_event();
+                // Stop exactly at the next scheduled time
+                let mut dt = self.speed * real_dt;
+                if let Some(t) = self.next_scheduled() {
+                    let until = t - app.primary.sim.time();
+                    if until < dt {
+                        dt = until;
+                    }
+                }
+                app.primary.sim.time_limited_step(
+                    &app.primary.map,
+                    dt,
+                    Duration::seconds(0.033),
+                    &mut app.primary.sim_cb,
+                );
+                app.primary.snapshots.maybe_capture(
+                    &app.primary.sim,
+                    &app.primary.map,
+                    app.secondary.is_some(),
+                );
+                self.run_script(ctx, app);
+            }
+            ctx.request_update(UpdateType::Game);
+        }
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "play/pause" => {
+                    self.set_paused(ctx, !self.paused);
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
+
+fn play_pause_button(ctx: &mut EventCtx, paused: bool) -> Widget {
+    ctx.style()
+        .btn_outline
+        .text(if paused { "play" } else { "pause" })
+        .hotkey(Key::Space)
+        .build_widget(ctx, "play/pause")
+}
+
+fn push_log(shared: &mut Shared, msg: String) {
+    shared.log.push(msg);
+    if shared.log.len() > LOG_LINES {
+        shared.log.remove(0);
+    }
+}
+
+fn make_engine(shared: &Rc<RefCell<Shared>>) -> Engine {
+    let mut engine = Engine::new();
+
+    let s = shared.clone();
+    engine.on_print(move |msg| {
+        info!("script: {}", msg);
+        push_log(&mut s.borrow_mut(), msg.to_string());
+    });
+
+    let s = shared.clone();
+    engine.register_fn(
+        "at",
+        move |time: &str, callback: FnPtr| -> Result<(), Box<EvalAltResult>> {
+            let time = Time::parse(time).map_err(|err| err.to_string())?;
+            s.borrow_mut().scheduled.push(Scheduled {
+                time,
+                repeat: None,
+                callback,
+            });
+            Ok(())
+        },
+    );
+    let s = shared.clone();
+    engine.register_fn(
+        "every",
+        move |interval: &str, callback: FnPtr| -> Result<(), Box<EvalAltResult>> {
+            let interval = Duration::parse(interval).map_err(|err| err.to_string())?;
+            if interval <= Duration::ZERO {
+                return Err("every() needs a positive interval".into());
+            }
+            let mut shared = s.borrow_mut();
+            let time = shared.now + interval;
+            shared.scheduled.push(Scheduled {
+                time,
+                repeat: Some(interval),
+                callback,
+            });
+            Ok(())
+        },
+    );
+
+    let s = shared.clone();
+    engine.register_fn(
+        "spawn_trip",
+        move |from: INT, to: INT, mode: &str| -> Result<(), Box<EvalAltResult>> {
+            let mode = parse_mode(mode)?;
+            if from < 0 || to < 0 {
+                return Err("building IDs can't be negative".into());
+            }
+            s.borrow_mut().commands.push(Command::SpawnTrip {
+                from: BuildingID(from as usize),
+                to: BuildingID(to as usize),
+                mode,
+            });
+            Ok(())
+        },
+    );
+    let s = shared.clone();
+    engine.register_fn("apply_edits", move |name: &str| {
+        s.borrow_mut()
+            .commands
+            .push(Command::ApplyEdits(name.to_string()));
+    });
+    let s = shared.clone();
+    engine.register_fn("camera", move |lon: FLOAT, lat: FLOAT, zoom: FLOAT| {
+        s.borrow_mut()
+            .commands
+            .push(Command::Camera(LonLat::new(lon, lat), zoom));
+    });
+    let s = shared.clone();
+    engine.register_fn("set_speed", move |speed: FLOAT| {
+        s.borrow_mut().commands.push(Command::SetSpeed(speed));
+    });
+    let s = shared.clone();
+    engine.register_fn("pause", move || {
+        s.borrow_mut().commands.push(Command::Pause);
+    });
+
+    let s = shared.clone();
+    engine.register_fn("time", move || s.borrow().now.to_string());
+    let s = shared.clone();
+    engine.register_fn("finished_trips", move || s.borrow().finished_trips as INT);
+    let s = shared.clone();
+    engine.register_fn("unfinished_trips", move || {
+        s.borrow().unfinished_trips as INT
+    });
+    let s = shared.clone();
+    engine.register_fn(
+        "finished_trips_by",
+        move |mode: &str| -> Result<INT, Box<EvalAltResult>> {
+            let mode = parse_mode(mode)?;
+            Ok(s.borrow().finished_by_mode.get(&mode).cloned().unwrap_or(0) as INT)
+        },
+    );
+    let s = shared.clone();
+    engine.register_fn("average_trip_time", move || {
+        s.borrow().average_trip_time.inner_seconds() as FLOAT
+    });
+
+    engine
+}
+
+fn parse_mode(mode: &str) -> Result<TripMode, Box<EvalAltResult>> {
+    TripMode::all()
+        .into_iter()
+        .find(|m| m.verb() == mode || (*m == TripMode::Transit && mode == "transit"))
+        .ok_or_else(|| format!("unknown mode {}; use walk, bike, transit, or drive", mode).into())
+}
@@ -315,8 +315,19 @@ impl TimePanel {
             Widget::nothing()
         };
 
+        let weather = if let Some(weather) = app.primary.sim.get_weather() {
+            let mut txt = weather.describe();
+            if app.primary.sim.is_dark() {
+                txt.push_str(", dark");
+            }
+            Text::from(Line(txt).secondary()).into_widget(ctx)
+        } else {
+            Widget::nothing()
+        };
+
         Widget::col(vec![
             Text::from(Line(self.time.ampm_tostring()).big_monospaced()).into_widget(ctx),
+            weather,
             trips_bar.margin_above(12),
             if app.primary.dirty_from_edits {
                 ctx.style()
@@ -494,6 +505,11 @@ impl TimePanel {
                     Duration::seconds(0.033),
                     &mut app.primary.sim_cb,
                 );
+                app.primary.snapshots.maybe_capture(
+                    &app.primary.sim,
+                    &app.primary.map,
+                    app.secondary.is_some(),
+                );
                 app.recalculate_current_selection(ctx);
             }
         }
@@ -0,0 +1,200 @@
+use abstutil::Timer;
+use geom::{Duration, Polygon, Pt2D};
+use map_gui::render::DrawOptions;
+use widgetry::{
+    Choice, DrawBaselayer, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel,
+    ScreenPt, ScreenRectangle, State, Text, TextExt, UpdateType, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, PerMap, ShowEverything, Transition};
+
+/// Shows the primary and secondary worlds side-by-side, looking at the same place and running the
+/// simulations in lockstep. Easier than flipping back and forth with Ctrl+Tab to compare an edited
+/// map against the original.
+pub struct SplitScreen {
+    panel: Panel,
+    paused: bool,
+}
+
+impl SplitScreen {
+    pub fn new_state(ctx: &mut EventCtx, app: &mut App) -> Box<dyn State<App>> {
+        // Start both simulations at the same time
+        ctx.loading_screen("catch up", |_, timer| {
+            let secondary = app.secondary.as_mut().unwrap();
+            let (behind, ahead) = if app.primary.sim.time() < secondary.sim.time() {
+                (&mut app.primary, secondary.sim.time())
+            } else {
+                let time = app.primary.sim.time();
+                (secondary, time)
+            };
+            let dt = ahead - behind.sim.time();
+            if dt > Duration::ZERO {
+                behind
+                    .sim
+                    .timed_step(&behind.map, dt, &mut behind.sim_cb, timer);
+            }
+        });
+
+        let secondary = app.secondary.as_ref().unwrap();
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Split screen").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Text::from_multiline(vec![
+                Line(format!("Left: {}", describe(&app.primary))),
+                Line(format!("Right: {}", describe(secondary))),
+            ])
+            .into_widget(ctx),
+            Widget::placeholder(ctx, "time"),
+            Widget::row(vec![
+                play_pause_button(ctx, true),
+                Widget::dropdown(
+                    ctx,
+                    "speed",
+                    5.0,
+                    vec![
+                        Choice::new("real-time", 1.0),
+                        Choice::new("5x", 5.0),
+                        Choice::new("30x", 30.0),
+                        Choice::new("1 hour / second", 3600.0),
+                    ],
+                ),
+            ]),
+        ]))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+        .build(ctx);
+
+        let mut state = SplitScreen {
+            panel,
+            paused: true,
+        };
+        state.update_time(ctx, app);
+        Box::new(state)
+    }
+
+    fn update_time(&mut self, ctx: &mut EventCtx, app: &App) {
+        self.panel.replace(
+            ctx,
+            "time",
+            app.primary.sim.time().ampm_tostring().text_widget(ctx),
+        );
+    }
+}
+
+impl State<App> for SplitScreen {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if !self.paused {
+            if let Some(real_dt) = ctx.input.nonblocking_is_update_event() {
+                ctx.input.use_update_event();
+                let multiplier: f64 = self.panel.dropdown_value("speed");
+                app.primary.sim.time_limited_step(
+                    &app.primary.map,
+                    multiplier * real_dt,
+                    Duration::seconds(0.033),
+                    &mut app.primary.sim_cb,
+                );
+                app.primary
+                    .snapshots
+                    .maybe_capture(&app.primary.sim, &app.primary.map, true);
+
+                // The primary might not have made it all the way there. Keep the secondary at
+                // exactly the same time, so the two are comparable.
+                let time = app.primary.sim.time();
+                let secondary = app.secondary.as_mut().unwrap();
+                let dt = time - secondary.sim.time();
+                if dt > Duration::ZERO {
+                    secondary.sim.timed_step(
+                        &secondary.map,
+                        dt,
+                        &mut secondary.sim_cb,
+                        &mut Timer::throwaway(),
+                    );
+                }
+                secondary
+                    .snapshots
+                    .maybe_capture(&secondary.sim, &secondary.map, true);
+
+                self.update_time(ctx, app);
+            }
+            ctx.request_update(UpdateType::Game);
+        }
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "play/pause" => {
+                    self.paused = !self.paused;
+                    let button = play_pause_button(ctx, self.paused);
+                    self.panel.replace(ctx, "play/pause", button);
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::Custom
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        // Both halves of the screen are centered on whatever the normal camera is looking at
+        let half_width = g.canvas.window_width / 2.0;
+        let height = g.canvas.window_height;
+        let zoom = g.canvas.cam_zoom;
+        let center = g.canvas.center_to_map_pt();
+        let top_left_map = Pt2D::new(
+            center.x() - half_width / 2.0 / zoom,
+            center.y() - height / 2.0 / zoom,
+        );
+
+        for (per_map, x1) in [
+            (&app.primary, 0.0),
+            (app.secondary.as_ref().unwrap(), half_width),
+        ] {
+            g.fork(top_left_map, ScreenPt::new(x1, 0.0), zoom, None);
+            g.enable_clipping(ScreenRectangle {
+                x1,
+                y1: 0.0,
+                x2: x1 + half_width,
+                y2: height,
+            });
+            app.draw_per_map(g, per_map, DrawOptions::new(), &ShowEverything::new());
+            g.disable_clipping();
+            g.unfork();
+        }
+
+        // Divide the two halves
+        g.fork_screenspace();
+        g.draw_polygon(
+            app.cs.panel_bg,
+            Polygon::rectangle(4.0, height).translate(half_width - 2.0, 0.0),
+        );
+        g.unfork();
+
+        self.panel.draw(g);
+    }
+}
+
+fn play_pause_button(ctx: &mut EventCtx, paused: bool) -> Widget {
+    ctx.style()
+        .btn_outline
+        .text(if paused { "play" } else { "pause" })
+        .hotkey(Key::Space)
+        .build_widget(ctx, "play/pause")
+}
+
+fn describe(per_map: &PerMap) -> String {
+    let edits = per_map.map.get_edits();
+    if edits.commands.is_empty() {
+        "no edits".to_string()
+    } else {
+        edits.edits_name.clone()
+    }
+}
@@ -8,8 +8,8 @@ use map_gui::render::DrawOptions;
 use map_gui::tools::grey_out_map;
 use widgetry::tools::PopupMsg;
 use widgetry::{
-    Choice, DrawBaselayer, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, Panel, PanelDims,
-    Slider, State, TabController, Text, Toggle, UpdateType, Widget,
+    Choice, Color, DrawBaselayer, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, Panel,
+    PanelDims, Slider, State, TabController, Text, Toggle, UpdateType, Widget,
 };
 
 use crate::app::{App, FindDelayedIntersections, ShowEverything, Transition};
@@ -70,6 +70,7 @@ impl JumpToTime {
                     target.to_percent(end_of_day).min(1.0),
                     "time slider",
                 ),
+                snapshot_markers(ctx, app, slider_width),
                 build_jump_to_time_btn(ctx, target),
             ])
         };
@@ -111,7 +112,7 @@ impl JumpToTime {
                 tabs.build_widget(ctx),
             ]))
             .dims_width(PanelDims::ExactPixels(640.0))
-            .dims_height(PanelDims::ExactPixels(360.0))
+            .dims_height(PanelDims::ExactPixels(400.0))
             .build(ctx),
             tabs,
         })
@@ -127,6 +128,16 @@ impl State<App> for JumpToTime {
                 }
                 "jump to time" => {
                     if self.target < app.primary.sim.time() {
+                        // Replay from the nearest snapshot, if there is one
+                        if restore_snapshots(app, self.target) {
+                            app.recalculate_current_selection(ctx);
+                            return Transition::Replace(TimeWarpScreen::new_state(
+                                ctx,
+                                app,
+                                self.target,
+                                None,
+                            ));
+                        }
                         if let Some(mode) = self.maybe_mode.take() {
                             let target_time = self.target;
                             return Transition::Replace(SandboxMode::async_new(
@@ -284,6 +295,11 @@ impl State<App> for TimeWarpScreen {
                 Duration::seconds(0.033),
                 &mut app.primary.sim_cb,
             );
+            app.primary.snapshots.maybe_capture(
+                &app.primary.sim,
+                &app.primary.map,
+                app.secondary.is_some(),
+            );
             #[allow(clippy::never_loop)]
             for (t, maybe_i, alert) in app.primary.sim.clear_alerts() {
                 // TODO Just the first :(
@@ -408,6 +424,58 @@ impl State<App> for TimeWarpScreen {
     }
 }
 
+/// Rewind the primary simulation, and the secondary one too when comparing, to their latest
+/// snapshots before the target time. If either is missing a snapshot, nothing changes, so the two
+/// never get out of sync.
+fn restore_snapshots(app: &mut App, target: Time) -> bool {
+    let primary = match app.primary.snapshots.restore(target) {
+        Some(sim) => sim,
+        None => {
+            return false;
+        }
+    };
+    if let Some(ref mut secondary) = app.secondary {
+        if secondary.sim.time() > target {
+            match secondary.snapshots.restore(target) {
+                Some(sim) => {
+                    secondary.sim = sim;
+                }
+                None => {
+                    return false;
+                }
+            }
+        }
+    }
+    app.primary.sim = primary;
+    true
+}
+
+/// Show when the simulation was copied, since rewinding to just after these times is quick.
+fn snapshot_markers(ctx: &EventCtx, app: &App, width: f64) -> Widget {
+    let times = app.primary.snapshots.times();
+    if times.is_empty() {
+        return Widget::nothing();
+    }
+    let end_of_day = app.primary.sim.get_end_of_day();
+    let mut batch = GeomBatch::new();
+    // Make sure the batch spans the whole slider
+    batch.push(Color::CLEAR, Polygon::rectangle(width, 10.0));
+    for t in times {
+        let x = width * t.to_percent(end_of_day).min(1.0);
+        batch.push(
+            ctx.style().icon_fg,
+            Polygon::rectangle(2.0, 10.0).translate((x - 1.0).max(0.0), 0.0),
+        );
+    }
+    Widget::col(vec![
+        batch.into_widget(ctx),
+        Text::from(
+            Line("Rewinding replays from the nearest saved point, marked above").secondary(),
+        )
+        .into_widget(ctx),
+    ])
+}
+
 fn area_under_curve(raw: Vec<(Time, usize)>, width: f64, height: f64) -> Result<Polygon> {
     assert!(!raw.is_empty());
     let min_x = Time::START_OF_DAY;
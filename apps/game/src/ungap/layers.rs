@@ -2,8 +2,9 @@ use std::collections::HashMap;
 
 use geom::Distance;
 use map_gui::tools::{DrawRoadLabels, Navigator};
+use map_model::connectivity::find_low_stress_islands;
 use map_model::osm::RoadRank;
-use map_model::LaneType;
+use map_model::{Direction, LaneType, LevelOfTrafficStress};
 use widgetry::tools::PopupMsg;
 use widgetry::{
     ButtonBuilder, Color, ControlState, Drawable, EdgeInsets, EventCtx, GeomBatch, GfxCtx,
@@ -12,6 +13,7 @@ use widgetry::{
 };
 
 use crate::app::{App, Transition};
+use crate::layer::map::{describe_network, traffic_stress_color};
 use crate::ungap::bike_network;
 use crate::ungap::bike_network::DrawNetworkLayer;
 
@@ -23,6 +25,8 @@ pub struct Layers {
     labels: Option<DrawRoadLabels>,
     elevation: bool,
     steep_streets: Option<Drawable>,
+    // Roads colored by level of traffic stress, and a description of the low-stress network
+    traffic_stress: Option<(Drawable, String)>,
     // TODO Once widgetry buttons can take custom enums, that'd be perfect here
     road_types: HashMap<String, Drawable>,
     fade_map: Drawable,
@@ -40,6 +44,7 @@ impl Layers {
             labels: Some(DrawRoadLabels::only_major_roads()),
             elevation: false,
             steep_streets: None,
+            traffic_stress: None,
             road_types: HashMap::new(),
             fade_map: GeomBatch::from(vec![(
                 Color::BLACK.alpha(0.4),
@@ -62,6 +67,10 @@ impl Layers {
                 self.bike_network = Some(DrawNetworkLayer::new(ctx, app));
             }
             self.road_types.clear();
+            if self.traffic_stress.is_some() {
+                self.traffic_stress = Some(make_traffic_stress(ctx, app));
+                self.update_panel(ctx, app);
+            }
         }
 
         if ctx.redo_mouseover() && self.elevation && !self.minimized {
@@ -174,6 +183,14 @@ impl Layers {
                     }
                     self.update_panel(ctx, app);
                 }
+                "traffic stress" => {
+                    if self.panel.is_checked("traffic stress") {
+                        self.traffic_stress = Some(make_traffic_stress(ctx, app));
+                    } else {
+                        self.traffic_stress = None;
+                    }
+                    self.update_panel(ctx, app);
+                }
                 _ => unreachable!(),
             },
             _ => {}
@@ -228,6 +245,9 @@ impl Layers {
             if let Some(ref draw) = self.steep_streets {
                 g.redraw(draw);
             }
+            if let Some((ref draw, _)) = self.traffic_stress {
+                g.redraw(draw);
+            }
         }
     }
 
@@ -351,6 +371,33 @@ impl Layers {
                 }
                 row
             }),
+            Widget::col({
+                let mut col = vec![Toggle::checkbox(
+                    ctx,
+                    "traffic stress",
+                    Key::T,
+                    self.traffic_stress.is_some(),
+                )];
+                if let Some((_, ref summary)) = self.traffic_stress {
+                    col.push(Widget::custom_row(
+                        LevelOfTrafficStress::all()
+                            .into_iter()
+                            .map(|lts| {
+                                legend_btn(traffic_stress_color(lts), lts.describe())
+                                    .label_color(Color::WHITE, ControlState::Default)
+                                    .disabled(true)
+                                    .build_def(ctx)
+                            })
+                            .collect(),
+                    ));
+                    col.push(
+                        Text::from(Line(format!("Largest low-stress network: {}", summary)))
+                            .wrap_to_pct(ctx, 15)
+                            .into_widget(ctx),
+                    );
+                }
+                col
+            }),
             // TODO Probably a collisions layer
         ])
     }
@@ -361,6 +408,7 @@ impl Layers {
             || name == "road labels"
             || name == "elevation"
             || name == "steep streets"
+            || name == "traffic stress"
             || name.starts_with("about ")
         {
             return;
@@ -407,6 +455,22 @@ impl Layers {
     }
 }
 
+fn make_traffic_stress(ctx: &mut EventCtx, app: &App) -> (Drawable, String) {
+    let map = &app.primary.map;
+    let mut batch = GeomBatch::new();
+    for r in map.all_roads() {
+        if let Some(lts) = [Direction::Fwd, Direction::Back]
+            .into_iter()
+            .filter_map(|dir| r.level_of_traffic_stress(map, dir, None))
+            .max()
+        {
+            batch.push(traffic_stress_color(lts), r.get_thick_polygon());
+        }
+    }
+    let islands = find_low_stress_islands(map, LevelOfTrafficStress::LTS2);
+    (ctx.upload(batch), describe_network(map, islands.get(0)))
+}
+
 fn make_zoom_controls(ctx: &mut EventCtx) -> Widget {
     let builder = ctx
         .style()
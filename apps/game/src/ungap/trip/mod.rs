@@ -55,8 +55,11 @@ impl ObjectID for ID {}
 
 impl TripPlanner {
     pub fn new_state(ctx: &mut EventCtx, app: &mut App, layers: Layers) -> Box<dyn State<App>> {
+        // Only a few routes are calculated here
         ctx.loading_screen("apply edits", |_, timer| {
-            app.primary.map.recalculate_pathfinding_after_edits(timer);
+            app.primary
+                .map
+                .quickly_recalculate_pathfinding_after_edits(timer);
         });
 
         let mut rp = TripPlanner {
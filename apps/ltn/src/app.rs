@@ -5,8 +5,8 @@ use map_gui::colors::ColorScheme;
 use map_gui::load::MapLoader;
 use map_gui::options::Options;
 use map_gui::render::{DrawMap, DrawOptions};
-use map_gui::tools::CameraState;
 use map_gui::tools::DrawSimpleRoadLabels;
+use map_gui::tools::{CameraState, CollabSession};
 use map_gui::{AppLike, ID};
 use map_model::{osm, CrossingType, FilterType, IntersectionID, Map, MapEdits, RoutingParams};
 use widgetry::tools::URLManager;
@@ -50,6 +50,9 @@ pub struct PerMap {
     pub draw_turn_restrictions: Drawable,
 
     pub current_trip_name: Option<String>,
+
+    /// Set while editing this map together with other people
+    pub collab: Option<CollabSession>,
 }
 
 impl PerMap {
@@ -97,6 +100,8 @@ impl PerMap {
             draw_turn_restrictions,
 
             current_trip_name: None,
+
+            collab: None,
         };
 
         if !CameraState::load(ctx, per_map.map.get_name()) {
@@ -234,7 +239,8 @@ impl App {
             manage_proposals: false,
         };
 
-        let cs = ColorScheme::new(ctx, opts.color_scheme);
+        opts.apply_ui_language(ctx);
+        let cs = ColorScheme::from_options(ctx, &opts);
         let app = App {
             // Start with a blank map
             per_map: PerMap::new(
@@ -268,14 +274,12 @@ impl App {
         g.redraw(&self.per_map.draw_map.draw_all_areas);
         custom(g);
         g.redraw(&self.per_map.draw_map.draw_all_unzoomed_parking_lots);
-        g.redraw(
-            &self
-                .per_map
-                .draw_map
-                .draw_all_unzoomed_roads_and_intersections,
-        );
-        g.redraw(&self.per_map.draw_map.draw_all_buildings);
-        g.redraw(&self.per_map.draw_map.draw_all_building_outlines);
+        self.per_map
+            .draw_map
+            .draw_all_unzoomed_roads_and_intersections
+            .draw(g);
+        self.per_map.draw_map.draw_all_buildings.draw(g);
+        self.per_map.draw_map.draw_all_building_outlines.draw(g);
     }
 
     pub fn partitioning(&self) -> &Partitioning {
@@ -299,6 +303,9 @@ impl App {
         self.per_map
             .map
             .must_apply_edits(edits, &mut Timer::throwaway());
+        if let Some(ref mut collab) = self.per_map.collab {
+            collab.publish(&self.per_map.map);
+        }
     }
 }
 
@@ -1,3 +1,4 @@
+use map_gui::tools::JoinCollabSession;
 use widgetry::tools::ChooseSomething;
 use widgetry::tools::PopupMsg;
 use widgetry::{
@@ -12,6 +13,10 @@ use crate::{pages, App, Transition};
 pub struct AppwidePanel {
     pub top_panel: Panel,
     pub left_panel: Panel,
+    // Pages for drawing boundaries can't be recreated, so other people's changes wait until
+    // they're done
+    sync_collab: bool,
+    collab_status: Option<String>,
 }
 
 impl AppwidePanel {
@@ -21,6 +26,8 @@ impl AppwidePanel {
         Self {
             top_panel,
             left_panel,
+            sync_collab: !matches!(mode, Mode::SelectBoundary | Mode::FreehandBoundary),
+            collab_status: app.per_map.collab.as_ref().map(|c| c.describe()),
         }
     }
 
@@ -31,6 +38,32 @@ impl AppwidePanel {
         preserve_state: &crate::save::PreserveState,
         help: F,
     ) -> Option<Transition> {
+        // Every page has this panel, so pick up changes from other people here
+        if let Some(changes) = app
+            .per_map
+            .collab
+            .as_mut()
+            .filter(|_| self.sync_collab)
+            .and_then(|collab| collab.event(ctx, &app.per_map.map))
+        {
+            app.apply_edits(changes.edits);
+            crate::redraw_all_icons(ctx, app);
+            let mut transitions = vec![Transition::Recreate];
+            if !changes.rejected.is_empty() {
+                transitions.push(Transition::Push(PopupMsg::new_state(
+                    ctx,
+                    "Some of your changes were undone",
+                    changes.rejected,
+                )));
+            }
+            return Some(Transition::Multi(transitions));
+        }
+        if self.sync_collab
+            && self.collab_status != app.per_map.collab.as_ref().map(|c| c.describe())
+        {
+            return Some(Transition::Recreate);
+        }
+
         if let Outcome::Clicked(x) = self.top_panel.event(ctx) {
             return match x.as_ref() {
                 "Home" => {
@@ -55,7 +88,16 @@ impl AppwidePanel {
                 "search" => Some(Transition::Push(
                     map_gui::tools::Navigator::new_state_with_target_zoom(ctx, app, 4.0),
                 )),
-                "help" => Some(Transition::Push(PopupMsg::new_state(ctx, "Help", help()))),
+                "measure" => Some(Transition::Push(
+                    map_gui::tools::MeasureDistance::new_state(ctx, app),
+                )),
+                "help" => {
+                    let mut lines = help();
+                    lines.push("");
+                    lines
+                        .push("Press Shift+F1, then hover over any control to learn what it does.");
+                    Some(Transition::Push(PopupMsg::new_state(ctx, "Help", lines)))
+                }
                 "about this tool" => Some(Transition::Push(pages::About::new_state(ctx))),
                 "Pick area" => Some(Transition::Replace(pages::PickArea::new_state(ctx, app))),
                 "Design LTN" => Some(Transition::Replace(pages::DesignLTN::new_state(
@@ -83,6 +125,17 @@ impl AppwidePanel {
             } else if x == "hide proposals" {
                 app.session.manage_proposals = false;
                 Some(Transition::Recreate)
+            } else if x == "Edit together" {
+                Some(Transition::Push(JoinCollabSession::new_state(
+                    ctx,
+                    Box::new(|_, app: &mut App, collab| {
+                        app.per_map.collab = Some(collab);
+                        Transition::Multi(vec![Transition::Pop, Transition::Recreate])
+                    }),
+                )))
+            } else if x == "Stop editing together" {
+                app.per_map.collab = None;
+                Some(Transition::Recreate)
             } else {
                 crate::save::Proposals::handle_action(ctx, app, preserve_state, &x)
             };
@@ -228,9 +281,16 @@ fn make_top_panel(ctx: &mut EventCtx, app: &App, mode: Mode) -> Panel {
                 .hotkey(lctrl(Key::F))
                 .build_widget(ctx, "search")
                 .centered_vert(),
+            ctx.style()
+                .btn_plain
+                .icon("system/assets/tools/ruler.svg")
+                .tooltip("Measure distances")
+                .build_widget(ctx, "measure")
+                .centered_vert(),
             ctx.style()
                 .btn_plain
                 .icon("system/assets/tools/help.svg")
+                .tooltip("Help (press Shift+F1 to explain any control)")
                 .build_widget(ctx, "help")
                 .centered_vert(),
         ])
@@ -257,6 +317,22 @@ fn make_left_panel(ctx: &mut EventCtx, app: &App, top_panel: &Panel, mode: Mode)
                 .align_right(),
         );
         col.push(app.per_map.proposals.to_widget_expanded(ctx));
+        col.push(if mode == Mode::FreehandBoundary {
+            Widget::nothing()
+        } else if let Some(ref collab) = app.per_map.collab {
+            Widget::col(vec![
+                Line(collab.describe()).secondary().into_widget(ctx),
+                ctx.style()
+                    .btn_plain
+                    .text("Stop editing together")
+                    .build_def(ctx),
+            ])
+        } else {
+            ctx.style()
+                .btn_plain
+                .icon_text("system/assets/tools/pencil.svg", "Edit together")
+                .build_def(ctx)
+        });
     } else {
         col.push(
             ctx.style()
@@ -0,0 +1,118 @@
+use geom::Duration;
+use map_gui::colors::ColorScheme;
+use map_model::connectivity::{DailyNeed, DailyNeedsAccess};
+use map_model::PathConstraints;
+use widgetry::mapspace::ToggleZoomed;
+use widgetry::{Color, EventCtx, GfxCtx, Line, Text};
+
+use crate::components::Mode;
+use crate::PerMap;
+
+const TIME_LIMIT: Duration = Duration::const_seconds(15.0 * 60.0);
+
+/// Colors every home by how many daily needs can be reached within 15 minutes, and labels each
+/// neighbourhood with the average for its residents.
+pub struct DailyNeedsLayer {
+    // The scores only change when the map is edited
+    access_key: (usize, PathConstraints),
+    access: DailyNeedsAccess,
+    // Neighbourhood boundaries change in their own modes, so entering a new mode is enough to
+    // catch that
+    draw_key: (usize, Mode),
+    draw: ToggleZoomed,
+}
+
+impl DailyNeedsLayer {
+    /// Calculates or updates the layer, if the map or mode has changed since last time.
+    pub fn update(
+        layer: &mut Option<DailyNeedsLayer>,
+        ctx: &mut EventCtx,
+        cs: &ColorScheme,
+        per_map: &PerMap,
+        constraints: PathConstraints,
+        mode: Mode,
+    ) {
+        let edits_key = per_map.map.get_edits_change_key();
+        let access_key = (edits_key, constraints);
+        let draw_key = (edits_key, mode);
+        if let Some(ref layer) = layer {
+            if layer.access_key == access_key && layer.draw_key == draw_key {
+                return;
+            }
+        }
+
+        let access = match layer.take() {
+            Some(layer) if layer.access_key == access_key => layer.access,
+            _ => ctx.loading_screen("calculate access to daily needs", |_, _| {
+                DailyNeedsAccess::new(&per_map.map, constraints, TIME_LIMIT).unwrap()
+            }),
+        };
+        let draw = draw(ctx, cs, per_map, &access);
+        *layer = Some(DailyNeedsLayer {
+            access_key,
+            access,
+            draw_key,
+            draw,
+        });
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        self.draw.draw(g);
+    }
+
+    pub fn describe_needs() -> String {
+        let needs: Vec<&str> = DailyNeed::all().into_iter().map(|n| n.describe()).collect();
+        format!("Homes within 15 minutes of: {}", needs.join(", "))
+    }
+}
+
+fn draw(
+    ctx: &mut EventCtx,
+    cs: &ColorScheme,
+    per_map: &PerMap,
+    access: &DailyNeedsAccess,
+) -> ToggleZoomed {
+    let map = &per_map.map;
+    let mut builder = ToggleZoomed::builder();
+    for b in map.all_buildings() {
+        if b.bldg_type.has_residents() {
+            builder.zoomed.push(
+                cs.good_to_bad_red.eval(1.0 - access.score(b.id)),
+                b.polygon.clone(),
+            );
+        }
+    }
+
+    for info in per_map
+        .proposals
+        .get_current()
+        .partitioning
+        .all_neighbourhoods()
+        .values()
+    {
+        let polygon = &info.block.polygon;
+        let bounds = polygon.get_bounds();
+        let buildings = map.all_buildings().iter().filter_map(|b| {
+            let pt = b.polygon.center();
+            if bounds.contains(pt) && polygon.contains_pt(pt) {
+                Some(b.id)
+            } else {
+                None
+            }
+        });
+        if let Some(score) = access.average_score(map, buildings) {
+            builder.unzoomed.push(
+                cs.good_to_bad_red.eval(1.0 - score).alpha(0.5),
+                polygon.clone(),
+            );
+            builder.unzoomed.append(
+                Text::from(Line(format!("{}%", (100.0 * score).round())).fg(Color::BLACK))
+                    .bg(Color::WHITE)
+                    .render_autocropped(ctx)
+                    .scale(2.0)
+                    .centered_on(polygon.polylabel()),
+            );
+        }
+    }
+    builder.build(ctx)
+}
@@ -1,6 +1,6 @@
 use geom::Polygon;
 use map_gui::colors::ColorScheme;
-use map_model::{CrossingType, FilterType};
+use map_model::{CrossingType, FilterType, PathConstraints};
 use widgetry::tools::ColorLegend;
 use widgetry::{
     ButtonBuilder, Color, ControlState, EdgeInsets, EventCtx, GeomBatch, GfxCtx,
@@ -8,9 +8,9 @@ use widgetry::{
     VerticalAlignment, Widget,
 };
 
-use crate::components::Mode;
+use crate::components::{DailyNeedsLayer, Mode};
 use crate::render::{colors, filter_svg_path};
-use crate::{pages, App, Transition};
+use crate::{pages, App, PerMap, Transition};
 
 // Partly copied from ungap/layers.s
 
@@ -22,6 +22,9 @@ pub struct Layers {
     show_bus_routes: bool,
     show_turn_restrictions: bool,
     pub show_crossing_time: bool,
+    show_daily_needs: bool,
+    daily_needs_by_bike: bool,
+    daily_needs: Option<DailyNeedsLayer>,
 
     // For the design LTN mode
     pub autofix_bus_gates: bool,
@@ -38,6 +41,9 @@ impl Layers {
             show_bus_routes: false,
             show_turn_restrictions: true,
             show_crossing_time: false,
+            show_daily_needs: false,
+            daily_needs_by_bike: false,
+            daily_needs: None,
 
             autofix_bus_gates: false,
             autofix_one_ways: false,
@@ -48,9 +54,22 @@ impl Layers {
         &mut self,
         ctx: &mut EventCtx,
         cs: &ColorScheme,
+        per_map: &PerMap,
         mode: Mode,
         bottom_panel: Option<&Panel>,
     ) -> Option<Transition> {
+        if self.show_daily_needs && mode.shows_daily_needs() {
+            // The map may have been edited since the last event
+            DailyNeedsLayer::update(
+                &mut self.daily_needs,
+                ctx,
+                cs,
+                per_map,
+                self.daily_needs_constraints(),
+                mode,
+            );
+        }
+
         match self.panel.event(ctx) {
             Outcome::Clicked(x) => {
                 match x.as_ref() {
@@ -84,6 +103,34 @@ impl Layers {
                     self.show_crossing_time = self.panel.is_checked(&x);
                     self.update_panel(ctx, cs, bottom_panel);
                     return Some(Transition::Keep);
+                } else if x == "show 15-minute access" {
+                    self.show_daily_needs = self.panel.is_checked(&x);
+                    if self.show_daily_needs {
+                        DailyNeedsLayer::update(
+                            &mut self.daily_needs,
+                            ctx,
+                            cs,
+                            per_map,
+                            self.daily_needs_constraints(),
+                            mode,
+                        );
+                    } else {
+                        self.daily_needs = None;
+                    }
+                    self.update_panel(ctx, cs, bottom_panel);
+                    return Some(Transition::Keep);
+                } else if x == "walking / cycling" {
+                    self.daily_needs_by_bike = !self.panel.is_checked(&x);
+                    DailyNeedsLayer::update(
+                        &mut self.daily_needs,
+                        ctx,
+                        cs,
+                        per_map,
+                        self.daily_needs_constraints(),
+                        mode,
+                    );
+                    self.update_panel(ctx, cs, bottom_panel);
+                    return Some(Transition::Keep);
                 } else if x == "Use bus gates when needed" {
                     self.autofix_bus_gates = self.panel.is_checked(&x);
                     self.update_panel(ctx, cs, bottom_panel);
@@ -120,6 +167,11 @@ impl Layers {
     // Draw after road labels
     pub fn draw(&self, g: &mut GfxCtx, app: &App) {
         self.panel.draw(g);
+        if self.show_daily_needs && self.panel_cache_key.0.shows_daily_needs() {
+            if let Some(ref layer) = self.daily_needs {
+                layer.draw(g);
+            }
+        }
         if self.show_bus_routes {
             g.redraw(&app.per_map.draw_bus_routes);
         }
@@ -149,6 +201,14 @@ impl Layers {
         self.update_panel(ctx, cs, bottom_panel);
     }
 
+    fn daily_needs_constraints(&self) -> PathConstraints {
+        if self.daily_needs_by_bike {
+            PathConstraints::Bike
+        } else {
+            PathConstraints::Pedestrian
+        }
+    }
+
     fn update_panel(&mut self, ctx: &mut EventCtx, cs: &ColorScheme, bottom_panel: Option<&Panel>) {
         let mut builder = Panel::new_builder(
             Widget::col(vec![
@@ -231,6 +291,33 @@ impl Layers {
             } else {
                 Widget::nothing()
             },
+            if !self.panel_cache_key.0.shows_daily_needs() {
+                Widget::nothing()
+            } else {
+                Widget::col(vec![
+                    Toggle::checkbox(ctx, "show 15-minute access", None, self.show_daily_needs),
+                    Widget::col(vec![
+                        Toggle::choice(
+                            ctx,
+                            "walking / cycling",
+                            "walking",
+                            "cycling",
+                            None,
+                            !self.daily_needs_by_bike,
+                        ),
+                        Line(DailyNeedsLayer::describe_needs())
+                            .secondary()
+                            .into_widget(ctx),
+                        ColorLegend::gradient_with_width(
+                            ctx,
+                            &cs.good_to_bad_red,
+                            vec!["all", "none"],
+                            150.0,
+                        ),
+                    ])
+                    .hide(!self.show_daily_needs),
+                ])
+            },
             Widget::row(vec![
                 "Adjust the size of text:".text_widget(ctx).centered_vert(),
                 Spinner::f64_widget(
@@ -283,6 +370,11 @@ fn make_zoom_controls(ctx: &mut EventCtx) -> Widget {
 }
 
 impl Mode {
+    // Neighbourhood boundaries change in these modes, so the averages would be wrong
+    fn shows_daily_needs(self) -> bool {
+        !matches!(self, Mode::SelectBoundary | Mode::FreehandBoundary)
+    }
+
     fn legend(&self, ctx: &mut EventCtx, cs: &ColorScheme, layers: &Layers) -> Widget {
         // TODO Light/dark buildings? Traffic signals?
 
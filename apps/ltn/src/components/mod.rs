@@ -1,8 +1,10 @@
 mod appwide_panel;
+mod daily_needs;
 mod layers;
 mod left_panel;
 
 pub use appwide_panel::AppwidePanel;
+pub use daily_needs::DailyNeedsLayer;
 pub use layers::{legend_entry, Layers};
 pub use left_panel::{BottomPanel, LeftPanel};
 
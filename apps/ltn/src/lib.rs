@@ -84,9 +84,13 @@ fn run(mut settings: Settings) {
             args.app_args.cam,
             move |ctx, app| {
                 // We need app to fully initialize this
-                app.session
-                    .layers
-                    .event(ctx, &app.cs, components::Mode::PickArea, None);
+                app.session.layers.event(
+                    ctx,
+                    &app.cs,
+                    &app.per_map,
+                    components::Mode::PickArea,
+                    None,
+                );
 
                 if let Some(ref name) = args.proposal {
                     // Remote edits require another intermediate state to load
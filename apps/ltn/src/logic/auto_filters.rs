@@ -26,6 +26,12 @@ pub enum AutoFilterHeuristic {
     /// Per cell, close all borders except for one. This doesn't affect connectivity, but prevents
     /// all shortcuts.
     OnlyOneBorder,
+    /// Repeat the greedy heuristic until there are no shortcuts left, or no road can be filtered
+    /// without disconnecting a cell. This approximates the smallest set of filters breaking all
+    /// through-routes.
+    BreakAllShortcuts,
+    /// Per cell with any shortcuts, filter the road inside it with the most shortcuts.
+    OnePerCell,
 }
 
 impl AutoFilterHeuristic {
@@ -44,6 +50,11 @@ impl AutoFilterHeuristic {
                 "only one entrance per cell",
                 AutoFilterHeuristic::OnlyOneBorder,
             ),
+            Choice::new(
+                "break all shortcuts with as few filters as possible",
+                AutoFilterHeuristic::BreakAllShortcuts,
+            ),
+            Choice::new("one filter per cell", AutoFilterHeuristic::OnePerCell),
         ]
     }
 
@@ -64,17 +75,23 @@ impl AutoFilterHeuristic {
             bail!("This neighbourhood has a disconnected cell; fix that first");
         }
 
-        // TODO If we already have no shortcuts, stop
+        if neighbourhood.shortcuts.paths.is_empty() {
+            bail!("This neighbourhood already has no shortcuts");
+        }
 
+        let orig_filters = app.per_map.map.all_roads_with_modal_filter().count();
         match self {
             AutoFilterHeuristic::Greedy => greedy(app, neighbourhood),
             AutoFilterHeuristic::BruteForce => brute_force(app, neighbourhood, timer),
             AutoFilterHeuristic::SplitCells => split_cells(app, neighbourhood, timer),
             AutoFilterHeuristic::OnlyOneBorder => only_one_border(app, neighbourhood),
+            AutoFilterHeuristic::BreakAllShortcuts => {
+                break_all_shortcuts(app, neighbourhood, timer)
+            }
+            AutoFilterHeuristic::OnePerCell => one_per_cell(app, neighbourhood),
         }
 
-        // TODO Detect if we changed anything
-        let empty = false;
+        let empty = app.per_map.map.all_roads_with_modal_filter().count() == orig_filters;
         redraw_all_icons(ctx, app);
         if empty {
             bail!("No new filters created");
@@ -212,6 +229,69 @@ fn only_one_border(app: &mut App, neighbourhood: &Neighbourhood) {
     }
 }
 
+fn break_all_shortcuts(app: &mut App, neighbourhood: &Neighbourhood, timer: &mut Timer) {
+    let mut current = Neighbourhood::new(app, neighbourhood.id);
+    // Each round filters a new interior road, so this bounds the number of rounds
+    timer.start_iter("place filters", neighbourhood.interior_roads.len());
+    for _ in 0..neighbourhood.interior_roads.len() {
+        timer.next();
+        if current.shortcuts.paths.is_empty() {
+            break;
+        }
+        // Try roads with the most shortcuts first, skipping any that'd disconnect a cell
+        let mut candidates: Vec<(RoadID, usize)> = current
+            .shortcuts
+            .count_per_road
+            .borrow()
+            .iter()
+            .filter(|(r, _)| {
+                current.interior_roads.contains(r)
+                    && app.per_map.map.get_r(**r).modal_filter.is_none()
+            })
+            .map(|(r, cnt)| (*r, *cnt))
+            .collect();
+        candidates.sort_by_key(|(_, cnt)| std::cmp::Reverse(*cnt));
+
+        let mut filtered = None;
+        for (r, _) in candidates {
+            if let Some(new) = try_to_filter_road(app, &current, r) {
+                filtered = Some(new);
+                break;
+            }
+        }
+        match filtered {
+            Some(new) => {
+                current = new;
+            }
+            None => {
+                warn!(
+                    "{} shortcuts remain, but no more roads can be filtered",
+                    current.shortcuts.paths.len()
+                );
+                break;
+            }
+        }
+    }
+}
+
+fn one_per_cell(app: &mut App, neighbourhood: &Neighbourhood) {
+    for cell in &neighbourhood.cells {
+        // Try the roads with the most shortcuts first, skipping any that'd disconnect a cell
+        let mut candidates: Vec<(RoadID, usize)> = cell
+            .roads
+            .keys()
+            .map(|r| (*r, neighbourhood.shortcuts.count_per_road.get(*r)))
+            .filter(|(r, cnt)| *cnt > 0 && app.per_map.map.get_r(*r).modal_filter.is_none())
+            .collect();
+        candidates.sort_by_key(|(_, cnt)| std::cmp::Reverse(*cnt));
+        for (r, _) in candidates {
+            if try_to_filter_road(app, neighbourhood, r).is_some() {
+                break;
+            }
+        }
+    }
+}
+
 // If successful, returns a Neighbourhood and leaves the new filter in place. If it disconncts a
 // cell, reverts the change and returns None
 fn try_to_filter_road(
@@ -0,0 +1,106 @@
+//! Filters push traffic onto the main roads around a neighbourhood, which makes those roads harder
+//! to cross on foot. This estimates that severance using the before/after traffic volumes from
+//! the impact prediction.
+
+use geom::{Duration, Speed};
+use map_model::osm::RoadRank;
+use map_model::{Road, RoadID};
+
+use crate::{App, NeighbourhoodID};
+
+/// How long it takes to start crossing, on top of walking the width of the road
+const STARTUP_TIME: Duration = Duration::const_seconds(2.0);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CrossingStats {
+    /// The average time somebody waits at an uncontrolled crossing for a big enough gap in traffic
+    pub mean_wait: Duration,
+    /// How many gaps per hour are long enough to cross
+    pub gaps_per_hour: f64,
+}
+
+impl CrossingStats {
+    /// Assumes vehicles arrive randomly (a Poisson process) at `vehicles_per_hour`, and a
+    /// pedestrian needs a gap long enough to walk across the whole road. Uses Adams' delay formula
+    /// for the mean wait.
+    pub fn new(road: &Road, vehicles_per_hour: f64) -> Self {
+        let critical_gap =
+            (road.get_width() / Speed::meters_per_second(1.2) + STARTUP_TIME).inner_seconds();
+        let flow = vehicles_per_hour / 3600.0;
+        if flow <= 0.0 {
+            return Self {
+                mean_wait: Duration::ZERO,
+                // Unlimited, really
+                gaps_per_hour: 3600.0 / critical_gap,
+            };
+        }
+        let x = flow * critical_gap;
+        Self {
+            mean_wait: Duration::seconds(((x.exp() - x - 1.0) / flow).min(3600.0)),
+            gaps_per_hour: vehicles_per_hour * (-x).exp(),
+        }
+    }
+}
+
+/// Crossing conditions along the main roads bounding one neighbourhood, before and after filters
+pub struct PerimeterCrossings {
+    pub roads: Vec<(RoadID, CrossingStats, CrossingStats)>,
+}
+
+impl PerimeterCrossings {
+    /// Uses the counts and departure time window from the current impact prediction
+    pub fn new(app: &App, id: NeighbourhoodID) -> Self {
+        let map = &app.per_map.map;
+        let impact = &app.per_map.impact;
+        let (start, end) = impact.filters.departure_time;
+        let hours = (end - start).inner_seconds().max(1.0) / 3600.0;
+
+        let mut roads = Vec::new();
+        let mut seen = Vec::new();
+        for road_side in &app.partitioning().neighbourhood_block(id).perimeter.roads {
+            let road = map.get_r(road_side.road);
+            if road.get_rank() == RoadRank::Local || seen.contains(&road.id) {
+                continue;
+            }
+            seen.push(road.id);
+            let before = impact.compare_counts.counts_a.per_road.get(road.id) as f64 / hours;
+            let after = impact.compare_counts.counts_b.per_road.get(road.id) as f64 / hours;
+            roads.push((
+                road.id,
+                CrossingStats::new(road, before),
+                CrossingStats::new(road, after),
+            ));
+        }
+        Self { roads }
+    }
+
+    /// The mean wait over all perimeter roads, before and after
+    pub fn mean_wait(&self) -> (Duration, Duration) {
+        if self.roads.is_empty() {
+            return (Duration::ZERO, Duration::ZERO);
+        }
+        let mut before = Duration::ZERO;
+        let mut after = Duration::ZERO;
+        for (_, b, a) in &self.roads {
+            before += b.mean_wait;
+            after += a.mean_wait;
+        }
+        let n = self.roads.len() as f64;
+        (before / n, after / n)
+    }
+
+    /// The fewest crossing gaps per hour on any perimeter road, before and after
+    pub fn min_gaps_per_hour(&self) -> (f64, f64) {
+        let mut before = f64::MAX;
+        let mut after = f64::MAX;
+        for (_, b, a) in &self.roads {
+            before = before.min(b.gaps_per_hour);
+            after = after.min(a.gaps_per_hour);
+        }
+        if self.roads.is_empty() {
+            (0.0, 0.0)
+        } else {
+            (before, after)
+        }
+    }
+}
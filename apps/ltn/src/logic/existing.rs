@@ -1,17 +1,14 @@
-use std::collections::BTreeMap;
-
 use abstutil::{Tags, Timer};
 use geom::Distance;
-use map_model::{osm, Crossing, FilterType, Map, RoadFilter, RoadID};
+use map_model::{osm, FilterType, Map, RoadFilter, RoadID};
 
-/// Edit the map, adding modal filters and crossings that're modelled in OSM in various ways.
+/// Edit the map, adding modal filters that're modelled in OSM in various ways. Crossings from OSM
+/// are already part of the map.
 ///
 /// TODO Maybe do this in the map importer pipeline!
 pub fn transform_existing(map: &mut Map, timer: &mut Timer) {
     let mut edits = map.get_edits().clone();
 
-    let mut crossings = detect_crossings(map);
-
     for (r, dist) in detect_filters(map) {
         edits.commands.push(map.edit_road_cmd(r, |new| {
             // If this road wasn't driveable already, then make it that way.
@@ -34,17 +31,6 @@ pub fn transform_existing(map: &mut Map, timer: &mut Timer) {
                     FilterType::BusGate
                 },
             });
-
-            new.crossings = crossings.remove(&r).unwrap_or_else(Vec::new);
-        }));
-    }
-
-    // Add crossings for roads that weren't transformed above. Ideally this could just be a second
-    // loop, but the EditCmd::ChangeRoad totally ovewrites the new state, so crossings would erase
-    // filters unless we do it this way.
-    for (r, list) in crossings {
-        edits.commands.push(map.edit_road_cmd(r, |new| {
-            new.crossings = list;
         }));
     }
 
@@ -107,21 +93,3 @@ fn detect_filters(map: &Map) -> Vec<(RoadID, Distance)> {
     }
     results
 }
-
-fn detect_crossings(map: &Map) -> BTreeMap<RoadID, Vec<Crossing>> {
-    let mut results = BTreeMap::new();
-    for road in map.all_roads() {
-        let mut list = Vec::new();
-        for (dist, kind) in &road.crossing_nodes {
-            list.push(Crossing {
-                kind: *kind,
-                dist: *dist,
-            });
-        }
-        list.sort_by_key(|c| c.dist);
-        if !list.is_empty() {
-            results.insert(road.id, list);
-        }
-    }
-    results
-}
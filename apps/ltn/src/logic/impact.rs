@@ -1,3 +1,4 @@
+use std::cell::{Ref, RefCell};
 use std::collections::BTreeSet;
 
 use abstio::MapName;
@@ -8,6 +9,7 @@ use map_model::{PathConstraints, PathRequest, PathV2, Pathfinder, RoadID};
 use synthpop::{Scenario, TrafficCounts, TripEndpoint, TripMode};
 use widgetry::EventCtx;
 
+use crate::logic::PeopleFlow;
 use crate::App;
 
 // TODO Configurable main road penalty, like in the pathfinding tool
@@ -26,6 +28,9 @@ pub struct Impact {
     // Handles all modes
     // TODO Maybe try to use this app-wide
     pathfinder_before_changes: Pathfinder,
+    // The map_edit_key and modes it was built for. Kept around, so later edits can update it
+    // instead of starting over.
+    pathfinder_after: RefCell<Option<(usize, BTreeSet<PathConstraints>, Pathfinder)>>,
 
     all_trips: Vec<PathRequest>,
     // A subset of all_trips, and the number of times somebody takes the same trip
@@ -55,6 +60,7 @@ impl Impact {
             },
 
             pathfinder_before_changes: Pathfinder::empty(),
+            pathfinder_after: RefCell::new(None),
 
             all_trips: Vec::new(),
             filtered_trips: Vec::new(),
@@ -94,20 +100,42 @@ impl Impact {
         impact
     }
 
-    // TODO Cache? It depends both on the map_edit_key and modes belonging to filtered_trips.
-    fn pathfinder_after(&self, app: &App, timer: &mut Timer) -> Pathfinder {
+    fn pathfinder_after(&self, app: &App, timer: &mut Timer) -> Ref<Pathfinder> {
+        let map = &app.per_map.map;
         let constraints: BTreeSet<PathConstraints> = self
             .filters
             .modes
             .iter()
             .map(|m| m.to_constraints())
             .collect();
-        Pathfinder::new_ch(
-            &app.per_map.map,
-            app.per_map.map.routing_params_respecting_modal_filters(),
-            constraints.into_iter().collect(),
-            timer,
-        )
+        let edit_key = map.get_edits_change_key();
+
+        {
+            let mut cache = self.pathfinder_after.borrow_mut();
+            match *cache {
+                Some((key, ref modes, _)) if key == edit_key && modes == &constraints => {}
+                Some((ref mut key, ref modes, ref mut pathfinder)) if modes == &constraints => {
+                    pathfinder.apply_edits_with_params(
+                        map,
+                        map.routing_params_respecting_modal_filters(),
+                        timer,
+                    );
+                    *key = edit_key;
+                }
+                _ => {
+                    let pathfinder = Pathfinder::new_ch(
+                        map,
+                        map.routing_params_respecting_modal_filters(),
+                        constraints.iter().cloned().collect(),
+                        timer,
+                    );
+                    *cache = Some((edit_key, constraints, pathfinder));
+                }
+            }
+        }
+        Ref::map(self.pathfinder_after.borrow(), |cache| {
+            &cache.as_ref().unwrap().2
+        })
     }
 
     pub fn trips_changed(&mut self, ctx: &mut EventCtx, app: &App, timer: &mut Timer) {
@@ -191,6 +219,13 @@ impl Impact {
         counts
     }
 
+    /// Aggregates the filtered trips by the neighbourhoods they start and end in, using routes
+    /// after changes.
+    pub fn people_flow(&self, app: &App, timer: &mut Timer) -> PeopleFlow {
+        let pathfinder_after = self.pathfinder_after(app, timer);
+        PeopleFlow::new(app, &self.filtered_trips, &pathfinder_after, timer)
+    }
+
     /// Returns routes that start or stop crossing the given road. Returns paths (before filters,
     /// after)
     pub fn find_changed_routes(
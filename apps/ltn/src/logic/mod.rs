@@ -1,13 +1,21 @@
 mod auto_filters;
+mod crossing_delay;
 mod existing;
 pub mod impact;
 mod partition;
+mod partition_quality;
+mod people_flow;
+mod school_streets;
 mod shortcuts;
 pub mod turn_restrictions;
 
 pub use auto_filters::AutoFilterHeuristic;
+pub use crossing_delay::{CrossingStats, PerimeterCrossings};
 pub use existing::transform_existing;
 pub use impact::Impact;
 pub use partition::{BlockID, CustomBoundary, NeighbourhoodID, Partitioning};
+pub use partition_quality::PartitionQuality;
+pub use people_flow::{Area, PeopleFlow};
+pub use school_streets::{find_school_street_candidates, SchoolStreetCandidate};
 pub use shortcuts::Shortcuts;
 pub use turn_restrictions::possible_destination_roads;
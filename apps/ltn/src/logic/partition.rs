@@ -195,9 +195,9 @@ impl Partitioning {
         new_owner_blocks.extend(add_all.clone());
         let mut new_neighbourhood_blocks = self.make_merged_blocks(map, new_owner_blocks)?;
         if new_neighbourhood_blocks.len() != 1 {
-            // This happens when a hole would be created by adding this block. There are probably
-            // some smaller blocks nearby to add first.
-            bail!("Couldn't add block -- you may need to add an intermediate block first to avoid a hole, or there's a bug you can't workaround yet. Try adding pink blocks first.");
+            // Holes are fine, but sometimes the blocks still don't merge. There are probably some
+            // smaller blocks nearby to add first.
+            bail!("Couldn't add block -- you may need to add an intermediate block first, or there's a bug you can't workaround yet. Try adding pink blocks first.");
         }
         let new_neighbourhood_block = new_neighbourhood_blocks.pop().unwrap();
 
@@ -264,8 +264,7 @@ impl Partitioning {
         let current_perim_set: BTreeSet<RoadSideID> = self.neighbourhoods[&old_owner]
             .block
             .perimeter
-            .roads
-            .iter()
+            .all_road_sides()
             .cloned()
             .collect();
         for road_side in &self.get_block(id).perimeter.roads {
@@ -275,11 +274,12 @@ impl Partitioning {
             // Is there another neighbourhood that has the other side of this road on its perimeter?
             // TODO We could map road -> BlockID then use block_to_neighbourhood
             let other_side = road_side.other_side();
-            if let Some((new_owner, _)) = self
-                .neighbourhoods
-                .iter()
-                .find(|(_, info)| info.block.perimeter.roads.contains(&other_side))
-            {
+            if let Some((new_owner, _)) = self.neighbourhoods.iter().find(|(_, info)| {
+                info.block
+                    .perimeter
+                    .all_road_sides()
+                    .any(|id| *id == other_side)
+            }) {
                 return self.transfer_blocks(map, vec![id], *new_owner);
             }
         }
@@ -399,11 +399,11 @@ impl Partitioning {
 
     /// Blocks on the "frontier" are adjacent to the perimeter, either just inside or outside.
     pub fn calculate_frontier(&self, perim: &Perimeter) -> BTreeSet<BlockID> {
-        let perim_roads: BTreeSet<RoadID> = perim.roads.iter().map(|id| id.road).collect();
+        let perim_roads: BTreeSet<RoadID> = perim.all_road_sides().map(|id| id.road).collect();
 
         let mut frontier = BTreeSet::new();
         for (block_id, block) in self.all_single_blocks() {
-            for road_side_id in &block.perimeter.roads {
+            for road_side_id in block.perimeter.all_road_sides() {
                 // If the perimeter has this RoadSideID on the same side, we're just inside. If it has
                 // the other side, just on the outside. Either way, on the frontier.
                 if perim_roads.contains(&road_side_id.road) {
@@ -0,0 +1,144 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use geom::Distance;
+use map_model::osm::RoadRank;
+use map_model::{Map, RoadSideID};
+
+use crate::logic::{BlockID, NeighbourhoodID, Partitioning};
+
+/// Rough measures of how sensible one neighbourhood produced by partitioning is. None of these
+/// are hard rules; they just help find neighbourhoods worth a second look.
+#[derive(Clone, Debug)]
+pub struct PartitionQuality {
+    /// The Polsby-Popper score, 4π * area / perimeter², using the length of perimeter roads. 1 is
+    /// a perfect circle; long, thin, or ragged shapes approach 0.
+    pub compactness: f64,
+    /// The fraction of the perimeter (by length) made up of main roads. Ideally a neighbourhood
+    /// is bounded entirely by main roads; local streets on the boundary usually mean the
+    /// neighbourhood should grow past them.
+    pub arterial_perimeter: f64,
+    /// The area of this neighbourhood divided by the median area of all neighbourhoods
+    pub size_ratio: f64,
+}
+
+impl PartitionQuality {
+    /// Combines all metrics into a score from 0 (bad) to 1 (good)
+    pub fn score(&self) -> f64 {
+        let size_balance = self.size_ratio.min(1.0 / self.size_ratio);
+        (self.compactness.min(1.0) + self.arterial_perimeter + size_balance) / 3.0
+    }
+
+    /// Describes anything unusual about this neighbourhood
+    pub fn problems(&self) -> Vec<&'static str> {
+        let mut problems = Vec::new();
+        if self.compactness < 0.3 {
+            problems.push("irregular shape");
+        }
+        if self.arterial_perimeter < 0.7 {
+            problems.push("boundary follows local streets");
+        }
+        if self.size_ratio < 0.25 {
+            problems.push("much smaller than most neighbourhoods");
+        } else if self.size_ratio > 4.0 {
+            problems.push("much larger than most neighbourhoods");
+        }
+        problems
+    }
+}
+
+impl Partitioning {
+    /// Calculates quality metrics for every neighbourhood
+    pub fn quality(&self, map: &Map) -> BTreeMap<NeighbourhoodID, PartitionQuality> {
+        let mut areas: Vec<f64> = self
+            .all_neighbourhoods()
+            .values()
+            .map(|info| info.block.polygon.area())
+            .collect();
+        areas.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let median_area = areas.get(areas.len() / 2).cloned().unwrap_or(1.0).max(1.0);
+
+        let mut results = BTreeMap::new();
+        for (id, info) in self.all_neighbourhoods() {
+            let mut total_length = Distance::ZERO;
+            let mut arterial_length = Distance::ZERO;
+            for road_side in &info.block.perimeter.roads {
+                let road = map.get_r(road_side.road);
+                total_length += road.length();
+                if road.get_rank() != RoadRank::Local {
+                    arterial_length += road.length();
+                }
+            }
+            let area = info.block.polygon.area();
+            let perimeter = total_length.inner_meters();
+
+            results.insert(
+                *id,
+                PartitionQuality {
+                    compactness: if perimeter == 0.0 {
+                        0.0
+                    } else {
+                        4.0 * std::f64::consts::PI * area / perimeter.powi(2)
+                    },
+                    arterial_perimeter: if perimeter == 0.0 {
+                        0.0
+                    } else {
+                        arterial_length.inner_meters() / perimeter
+                    },
+                    size_ratio: area / median_area,
+                },
+            );
+        }
+        results
+    }
+
+    /// The lowest scoring neighbourhoods with at least one problem, worst first
+    pub fn worst_neighbourhoods(
+        &self,
+        map: &Map,
+        limit: usize,
+    ) -> Vec<(NeighbourhoodID, PartitionQuality)> {
+        let mut flagged: Vec<(NeighbourhoodID, PartitionQuality)> = self
+            .quality(map)
+            .into_iter()
+            .filter(|(_, quality)| !quality.problems().is_empty())
+            .collect();
+        flagged.sort_by(|a, b| a.1.score().partial_cmp(&b.1.score()).unwrap());
+        flagged.truncate(limit);
+        flagged
+    }
+
+    /// Suggests single blocks to transfer into a neighbourhood, best first. These are blocks just
+    /// across a local street on the current boundary; absorbing them pushes the boundary out
+    /// towards main roads. The suggestions aren't guaranteed to succeed with `transfer_blocks`.
+    pub fn suggest_transfers(&self, map: &Map, id: NeighbourhoodID, limit: usize) -> Vec<BlockID> {
+        let mut road_side_to_block: BTreeMap<RoadSideID, BlockID> = BTreeMap::new();
+        for (block_id, block) in self.all_single_blocks() {
+            for road_side in block.perimeter.all_road_sides() {
+                road_side_to_block.insert(*road_side, block_id);
+            }
+        }
+
+        // How much local street each candidate block shares with the current boundary
+        let mut shared: BTreeMap<BlockID, Distance> = BTreeMap::new();
+        let mut seen_roads = BTreeSet::new();
+        for road_side in &self.neighbourhood_block(id).perimeter.roads {
+            let road = map.get_r(road_side.road);
+            if road.get_rank() != RoadRank::Local || !seen_roads.insert(road.id) {
+                continue;
+            }
+            if let Some(block) = road_side_to_block.get(&road_side.other_side()) {
+                if self.block_to_neighbourhood(*block) != id {
+                    *shared.entry(*block).or_insert(Distance::ZERO) += road.length();
+                }
+            }
+        }
+
+        let mut candidates: Vec<(BlockID, Distance)> = shared.into_iter().collect();
+        candidates.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        candidates
+            .into_iter()
+            .take(limit)
+            .map(|(block, _)| block)
+            .collect()
+    }
+}
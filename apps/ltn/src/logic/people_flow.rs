@@ -0,0 +1,110 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use abstutil::{Counter, Timer};
+use map_model::osm::RoadRank;
+use map_model::{Map, PathRequest, PathStepV2, Pathfinder, RoadID};
+use synthpop::TripMode;
+
+use crate::logic::{NeighbourhoodID, Partitioning};
+use crate::App;
+
+/// Where a trip starts or ends
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Area {
+    Neighbourhood(NeighbourhoodID),
+    /// Main roads and anywhere not covered by a neighbourhood
+    Elsewhere,
+}
+
+/// Trips aggregated by the neighbourhoods they start and end in, to show how much traffic in an
+/// area is local versus passing through.
+pub struct PeopleFlow {
+    /// (origin, destination, mode) to the number of trips
+    pub flows: Counter<(Area, Area, TripMode)>,
+    /// Trips that cross the interior of a neighbourhood without starting or ending there
+    pub through: Counter<NeighbourhoodID>,
+}
+
+impl PeopleFlow {
+    pub fn new(
+        app: &App,
+        trips: &[(PathRequest, usize)],
+        pathfinder: &Pathfinder,
+        timer: &mut Timer,
+    ) -> PeopleFlow {
+        let map = &app.per_map.map;
+        let mut road_to_area: BTreeMap<RoadID, Area> = BTreeMap::new();
+        for id in app.partitioning().all_neighbourhoods().keys() {
+            for r in interior_roads(map, app.partitioning(), *id) {
+                road_to_area.insert(r, Area::Neighbourhood(*id));
+            }
+        }
+        let area = |r: RoadID| road_to_area.get(&r).cloned().unwrap_or(Area::Elsewhere);
+
+        let mut flows = Counter::new();
+        let mut through = Counter::new();
+        for (req, count, maybe_path) in timer.parallelize(
+            "calculate routes",
+            trips.iter().collect(),
+            |(req, count)| (req, *count, pathfinder.pathfind_v2(req.clone(), map)),
+        ) {
+            let from = area(req.start.lane().road);
+            let to = area(req.end.lane().road);
+            flows.add(
+                (from, to, TripMode::from_constraints(req.constraints)),
+                count,
+            );
+
+            if let Some(path) = maybe_path {
+                let mut crossed = BTreeSet::new();
+                for step in path.get_steps() {
+                    if let PathStepV2::Along(dr) = step {
+                        if let Area::Neighbourhood(id) = area(dr.road) {
+                            crossed.insert(id);
+                        }
+                    }
+                }
+                for id in crossed {
+                    if from != Area::Neighbourhood(id) && to != Area::Neighbourhood(id) {
+                        through.add(id, count);
+                    }
+                }
+            }
+        }
+
+        PeopleFlow { flows, through }
+    }
+
+    /// Returns (trips staying inside, trips leaving, trips arriving, trips passing through)
+    pub fn summarize(&self, id: NeighbourhoodID) -> (usize, usize, usize, usize) {
+        let area = Area::Neighbourhood(id);
+        let mut local = 0;
+        let mut outbound = 0;
+        let mut inbound = 0;
+        for ((from, to, _), count) in self.flows.borrow() {
+            if *from == area && *to == area {
+                local += count;
+            } else if *from == area {
+                outbound += count;
+            } else if *to == area {
+                inbound += count;
+            }
+        }
+        (local, outbound, inbound, self.through.get(id))
+    }
+}
+
+// Like the calculation in Neighbourhood, but much cheaper, because it skips cells and shortcuts
+fn interior_roads(map: &Map, partitioning: &Partitioning, id: NeighbourhoodID) -> BTreeSet<RoadID> {
+    if let Some(custom) = partitioning.custom_boundaries.get(&id) {
+        return custom.interior_roads.clone();
+    }
+    let perimeter = &partitioning.neighbourhood_block(id).perimeter;
+    let mut roads = perimeter.interior.clone();
+    for id in &perimeter.roads {
+        if map.get_r(id.road).get_rank() == RoadRank::Local {
+            roads.insert(id.road);
+        }
+    }
+    roads
+}
@@ -0,0 +1,109 @@
+//! Find streets next to schools that could be closed to through-traffic during school hours.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use abstutil::Timer;
+use map_model::osm::RoadRank;
+use map_model::{AmenityType, BuildingID, FilterType, Map, RoadFilter, RoadID};
+
+use crate::{App, Neighbourhood, NeighbourhoodID};
+
+/// A street that a school fronts onto, which looks reasonable to close during school hours
+pub struct SchoolStreetCandidate {
+    pub road: RoadID,
+    pub schools: Vec<BuildingID>,
+    pub neighbourhood: NeighbourhoodID,
+    /// How many shortcuts through the neighbourhood currently use this street. Lower is better.
+    pub shortcuts: usize,
+}
+
+impl SchoolStreetCandidate {
+    /// Place a school street filter in the middle of the road
+    pub fn apply(&self, app: &mut App) {
+        let map = &app.per_map.map;
+        let mut edits = map.get_edits().clone();
+        let road = map.get_r(self.road);
+        edits.commands.push(map.edit_road_cmd(self.road, |new| {
+            new.modal_filter = Some(RoadFilter::new(
+                road.length() / 2.0,
+                FilterType::SchoolStreet,
+            ));
+        }));
+        app.apply_edits(edits);
+    }
+}
+
+/// Returns candidates in order from most to least suitable. A candidate must be a local street
+/// inside some neighbourhood, not already filtered, with no bus routes, and connected to other
+/// driveable roads on both ends, so everything along it is still reachable once it's closed.
+pub fn find_school_street_candidates(app: &App, timer: &mut Timer) -> Vec<SchoolStreetCandidate> {
+    let map = &app.per_map.map;
+
+    let mut schools_per_road: BTreeMap<RoadID, Vec<BuildingID>> = BTreeMap::new();
+    for b in map.all_buildings() {
+        if b.amenities
+            .iter()
+            .any(|a| AmenityType::categorize(&a.amenity_type) == Some(AmenityType::School))
+        {
+            let r = b.sidewalk_pos.lane().road;
+            if is_suitable(map, r) {
+                schools_per_road
+                    .entry(r)
+                    .or_insert_with(Vec::new)
+                    .push(b.id);
+            }
+        }
+    }
+
+    // Only calculate shortcuts for neighbourhoods that might contain a candidate
+    let mut candidates = Vec::new();
+    let mut remaining: BTreeSet<RoadID> = schools_per_road.keys().cloned().collect();
+    timer.start_iter(
+        "check neighbourhoods",
+        app.partitioning().all_neighbourhoods().len(),
+    );
+    for (id, info) in app.partitioning().all_neighbourhoods() {
+        timer.next();
+        if !remaining.iter().any(|r| {
+            info.block
+                .polygon
+                .contains_pt(map.get_r(*r).center_pts.middle())
+        }) {
+            continue;
+        }
+        let neighbourhood = Neighbourhood::new(app, *id);
+        for r in &neighbourhood.interior_roads {
+            if remaining.remove(r) {
+                candidates.push(SchoolStreetCandidate {
+                    road: *r,
+                    schools: schools_per_road.remove(r).unwrap(),
+                    neighbourhood: *id,
+                    shortcuts: neighbourhood.shortcuts.count_per_road.get(*r),
+                });
+            }
+        }
+    }
+
+    // Prefer streets with little through-traffic, then streets serving more schools
+    candidates.sort_by_key(|c| (c.shortcuts, std::cmp::Reverse(c.schools.len()), c.road));
+    candidates
+}
+
+fn is_suitable(map: &Map, r: RoadID) -> bool {
+    let road = map.get_r(r);
+    if !crate::is_driveable(road, map)
+        || road.get_rank() != RoadRank::Local
+        || road.modal_filter.is_some()
+        || !map.get_bus_routes_on_road(r).is_empty()
+    {
+        return false;
+    }
+
+    // There must be another way to reach both ends
+    [road.src_i, road.dst_i].into_iter().all(|i| {
+        map.get_i(i)
+            .roads
+            .iter()
+            .any(|other| *other != r && crate::is_driveable(map.get_r(*other), map))
+    })
+}
@@ -158,7 +158,8 @@ impl Neighbourhood {
             n.boundary_polygon = polygon;
         }
 
-        for id in &orig_perimeter.roads {
+        // Roads around holes are part of the perimeter too
+        for id in orig_perimeter.all_road_sides() {
             let road = map.get_r(id.road);
             // Part of the perimeter may be a local road. This is all it takes to correct cell and
             // shortcut calculation, and allow edits on local perimeter roads.
@@ -51,9 +51,13 @@ impl Census {
         );
 
         // Just force the layers panel to align above the bottom panel
-        app.session
-            .layers
-            .event(ctx, &app.cs, Mode::Census, Some(&bottom_panel));
+        app.session.layers.event(
+            ctx,
+            &app.cs,
+            &app.per_map,
+            Mode::Census,
+            Some(&bottom_panel),
+        );
 
         let mut world = World::new();
 
@@ -118,11 +122,13 @@ impl State<App> for Census {
             self.world.hack_unset_hovering();
             return t;
         }
-        if let Some(t) =
-            app.session
-                .layers
-                .event(ctx, &app.cs, Mode::Census, Some(&self.bottom_panel))
-        {
+        if let Some(t) = app.session.layers.event(
+            ctx,
+            &app.cs,
+            &app.per_map,
+            Mode::Census,
+            Some(&self.bottom_panel),
+        ) {
             return t;
         }
         if let Outcome::Clicked(x) = self.bottom_panel.event(ctx) {
@@ -30,9 +30,13 @@ impl Crossings {
         let bottom_panel = BottomPanel::new(ctx, &appwide_panel, contents);
 
         // Just force the layers panel to align above the bottom panel
-        app.session
-            .layers
-            .event(ctx, &app.cs, Mode::Crossings, Some(&bottom_panel));
+        app.session.layers.event(
+            ctx,
+            &app.cs,
+            &app.per_map,
+            Mode::Crossings,
+            Some(&bottom_panel),
+        );
 
         let mut state = Self {
             appwide_panel,
@@ -80,11 +84,13 @@ impl State<App> for Crossings {
         {
             return t;
         }
-        if let Some(t) =
-            app.session
-                .layers
-                .event(ctx, &app.cs, Mode::Crossings, Some(&self.bottom_panel))
-        {
+        if let Some(t) = app.session.layers.event(
+            ctx,
+            &app.cs,
+            &app.per_map,
+            Mode::Crossings,
+            Some(&self.bottom_panel),
+        ) {
             if app.session.layers.show_crossing_time != self.draw_nearest_crossing.is_some() {
                 if app.session.layers.show_crossing_time {
                     let (draw, time) = draw_nearest_crossing(ctx, app);
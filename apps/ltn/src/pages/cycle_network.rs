@@ -48,11 +48,13 @@ impl State<App> for CycleNetwork {
         {
             return t;
         }
-        if let Some(t) =
-            app.session
-                .layers
-                .event(ctx, &app.cs, Mode::CycleNetwork, Some(&self.bottom_panel))
-        {
+        if let Some(t) = app.session.layers.event(
+            ctx,
+            &app.cs,
+            &app.per_map,
+            Mode::CycleNetwork,
+            Some(&self.bottom_panel),
+        ) {
             return t;
         }
         if let Outcome::Clicked(x) = self.bottom_panel.event(ctx) {
@@ -161,6 +161,7 @@ impl State<App> for DesignLTN {
         if let Some(t) = app.session.layers.event(
             ctx,
             &app.cs,
+            &app.per_map,
             Mode::ModifyNeighbourhood,
             Some(&self.bottom_panel),
         ) {
@@ -299,7 +300,10 @@ fn setup_editing(
 
     let render_cells = render::RenderCells::new(map, neighbourhood);
 
-    let draw_under_roads_layer = render_cells.draw_colored_areas();
+    let mut draw_under_roads_layer = render_cells.draw_colored_areas();
+    if app.opts.pattern_fills {
+        draw_under_roads_layer.append(render_cells.draw_patterns());
+    }
     draw_top_layer.append(render_cells.draw_island_outlines());
 
     // Highlight border arrows when hovered
@@ -453,7 +457,7 @@ fn launch_advanced(ctx: &mut EventCtx, app: &App, id: NeighbourhoodID) -> Transi
             } else {
                 Transition::Replace(ChooseSomething::new_state(
                     ctx,
-                    "Add one filter automatically, using different heuristics",
+                    "Place filters automatically, using different heuristics",
                     AutoFilterHeuristic::choices(),
                     Box::new(move |heuristic, ctx, app| {
                         match ctx.loading_screen(
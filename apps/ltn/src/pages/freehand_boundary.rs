@@ -155,10 +155,10 @@ impl State<App> for FreehandBoundary {
         {
             return t;
         }
-        if let Some(t) = app
-            .session
-            .layers
-            .event(ctx, &app.cs, Mode::FreehandBoundary, None)
+        if let Some(t) =
+            app.session
+                .layers
+                .event(ctx, &app.cs, &app.per_map, Mode::FreehandBoundary, None)
         {
             return t;
         }
@@ -5,10 +5,12 @@ mod customize_boundary;
 mod cycle_network;
 pub mod design_ltn;
 mod freehand_boundary;
+mod people_flow;
 mod per_resident_impact;
 mod pick_area;
 mod predict_impact;
 mod route_planner;
+mod school_streets;
 mod select_boundary;
 
 pub use about::About;
@@ -18,8 +20,10 @@ pub use customize_boundary::CustomizeBoundary;
 pub use cycle_network::CycleNetwork;
 pub use design_ltn::{turn_restrictions::handle_edited_turn_restrictions, DesignLTN, EditMode};
 pub use freehand_boundary::FreehandBoundary;
+pub use people_flow::ShowPeopleFlow;
 pub use per_resident_impact::PerResidentImpact;
 pub use pick_area::{PickArea, PickAreaStyle};
 pub use predict_impact::ShowImpactResults;
 pub use route_planner::RoutePlanner;
+pub use school_streets::SchoolStreets;
 pub use select_boundary::SelectBoundary;
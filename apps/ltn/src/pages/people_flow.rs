@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+
+use abstutil::{prettyprint_usize, Counter};
+use geom::{Polygon, Pt2D, Ring};
+use map_gui::tools::color_for_mode;
+use synthpop::TripMode;
+use widgetry::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, State,
+    Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::logic::{Area, NeighbourhoodID, PeopleFlow};
+use crate::{App, Transition};
+
+// Only show the neighbourhoods with the most trips; group the rest
+const MAX_NEIGHBOURHOODS: usize = 8;
+
+const DIAGRAM_WIDTH: f64 = 600.0;
+const DIAGRAM_HEIGHT: f64 = 400.0;
+const LABEL_WIDTH: f64 = 130.0;
+const BAR_WIDTH: f64 = 15.0;
+const GAP: f64 = 8.0;
+
+/// One side of the diagram
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Node {
+    Area(Area),
+    OtherNeighbourhoods,
+}
+
+/// A Sankey diagram of trips between neighbourhoods, by mode
+pub struct ShowPeopleFlow {
+    panel: Panel,
+    draw_labels: Drawable,
+}
+
+impl ShowPeopleFlow {
+    pub fn new_state(ctx: &mut EventCtx, app: &App, flow: PeopleFlow) -> Box<dyn State<App>> {
+        // Rank neighbourhoods by how many trips start or end there
+        let mut involvement = Counter::new();
+        for ((from, to, _), count) in flow.flows.borrow() {
+            for area in [from, to] {
+                if let Area::Neighbourhood(id) = area {
+                    involvement.add(*id, *count);
+                }
+            }
+        }
+        let top: Vec<NeighbourhoodID> = involvement
+            .highest_n(MAX_NEIGHBOURHOODS)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        let to_node = |area: Area| match area {
+            Area::Neighbourhood(id) if !top.contains(&id) => Node::OtherNeighbourhoods,
+            _ => Node::Area(area),
+        };
+
+        let mut grouped = Counter::new();
+        for ((from, to, mode), count) in flow.flows.borrow() {
+            grouped.add((to_node(*from), to_node(*to), *mode), *count);
+        }
+        let mut order: Vec<Node> = top
+            .iter()
+            .map(|id| Node::Area(Area::Neighbourhood(*id)))
+            .collect();
+        order.push(Node::OtherNeighbourhoods);
+        order.push(Node::Area(Area::Elsewhere));
+
+        let mut summary = Vec::new();
+        for id in &top {
+            let (local, outbound, inbound, through) = flow.summarize(*id);
+            summary.push(
+                Text::from_multiline(vec![
+                    Line(label(Node::Area(Area::Neighbourhood(*id)))).small_heading(),
+                    Line(format!(
+                        "{} trips stay inside, {} leave, {} arrive",
+                        prettyprint_usize(local),
+                        prettyprint_usize(outbound),
+                        prettyprint_usize(inbound)
+                    )),
+                    Line(format!("{} trips pass through", prettyprint_usize(through))),
+                ])
+                .into_widget(ctx),
+            );
+        }
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("People flow between neighbourhoods")
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Text::from(Line(
+                "Trips start on the left and end on the right. Bands are colored by mode. Through \
+                 traffic crosses a neighbourhood's interior without starting or ending there.",
+            ))
+            .wrap_to_pct(ctx, 40)
+            .into_widget(ctx),
+            Widget::custom_row(
+                TripMode::all()
+                    .into_iter()
+                    .map(|m| {
+                        Line(m.ongoing_verb())
+                            .fg(color_for_mode(app, m))
+                            .into_widget(ctx)
+                            .margin_right(24)
+                    })
+                    .collect(),
+            ),
+            if grouped.is_empty() {
+                "No trips match the current filters".text_widget(ctx)
+            } else {
+                draw_sankey(ctx, app, &grouped, &order).into_widget(ctx)
+            },
+            Widget::col(summary),
+        ]))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Center)
+        .build(ctx);
+
+        // Label the neighbourhoods on the map, so they can be matched up with the diagram
+        let mut batch = GeomBatch::new();
+        for id in &top {
+            let polygon = &app.partitioning().neighbourhood_block(*id).polygon;
+            batch.push(Color::YELLOW.alpha(0.3), polygon.clone());
+            batch.append(
+                Text::from(Line(label(Node::Area(Area::Neighbourhood(*id)))).fg(Color::BLACK))
+                    .bg(Color::WHITE)
+                    .render_autocropped(ctx)
+                    .scale(2.0)
+                    .centered_on(polygon.polylabel()),
+            );
+        }
+
+        Box::new(Self {
+            panel,
+            draw_labels: ctx.upload(batch),
+        })
+    }
+}
+
+impl State<App> for ShowPeopleFlow {
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw_labels);
+        self.panel.draw(g);
+    }
+}
+
+fn label(node: Node) -> String {
+    match node {
+        Node::Area(Area::Neighbourhood(id)) => format!("Neighbourhood {}", id.0),
+        Node::Area(Area::Elsewhere) => "Elsewhere".to_string(),
+        Node::OtherNeighbourhoods => "Other neighbourhoods".to_string(),
+    }
+}
+
+fn draw_sankey(
+    ctx: &EventCtx,
+    app: &App,
+    flows: &Counter<(Node, Node, TripMode)>,
+    order: &[Node],
+) -> GeomBatch {
+    let mut origin_totals = Counter::new();
+    let mut destination_totals = Counter::new();
+    for ((from, to, _), count) in flows.borrow() {
+        origin_totals.add(*from, *count);
+        destination_totals.add(*to, *count);
+    }
+    let max_nodes = order
+        .iter()
+        .filter(|n| origin_totals.get(**n) > 0)
+        .count()
+        .max(
+            order
+                .iter()
+                .filter(|n| destination_totals.get(**n) > 0)
+                .count(),
+        );
+    let scale = (DIAGRAM_HEIGHT - GAP * (max_nodes - 1) as f64) / (flows.sum() as f64);
+
+    let left_x = LABEL_WIDTH;
+    let right_x = DIAGRAM_WIDTH - LABEL_WIDTH - BAR_WIDTH;
+    let mut batch = GeomBatch::new();
+
+    // Where each node's bar starts vertically on each side
+    let mut place_nodes = |totals: &Counter<Node>, x: f64, labels_on_left: bool| {
+        let mut tops = BTreeMap::new();
+        let mut y = 0.0;
+        for node in order {
+            let total = totals.get(*node);
+            if total == 0 {
+                continue;
+            }
+            let height = scale * (total as f64);
+            tops.insert(*node, y);
+            batch.push(
+                Color::grey(0.6),
+                Polygon::rectangle(BAR_WIDTH, height).translate(x, y),
+            );
+
+            let txt = Text::from(Line(label(*node)))
+                .render_autocropped(ctx)
+                .scale_to_fit_height(12.0);
+            let txt_width = txt.get_dims().width;
+            let label_x = if labels_on_left {
+                x - 5.0 - txt_width
+            } else {
+                x + BAR_WIDTH + 5.0
+            };
+            batch.append(txt.translate(label_x, y + height / 2.0 - 6.0));
+
+            y += height + GAP;
+        }
+        tops
+    };
+    let mut origin_cursor = place_nodes(&origin_totals, left_x, true);
+    let mut destination_cursor = place_nodes(&destination_totals, right_x, false);
+
+    for ((from, to, mode), count) in flows.borrow() {
+        let thickness = scale * (*count as f64);
+        let y1 = origin_cursor[from];
+        let y2 = destination_cursor[to];
+        *origin_cursor.get_mut(from).unwrap() += thickness;
+        *destination_cursor.get_mut(to).unwrap() += thickness;
+
+        if let Ok(ring) = band(left_x + BAR_WIDTH, y1, right_x, y2, thickness) {
+            batch.push(color_for_mode(app, *mode).alpha(0.6), ring.into_polygon());
+        }
+    }
+
+    batch
+}
+
+// A band with smoothly curved edges between two bars
+fn band(x1: f64, y1: f64, x2: f64, y2: f64, thickness: f64) -> anyhow::Result<Ring> {
+    let steps = 20;
+    let mut top = Vec::new();
+    let mut bottom = Vec::new();
+    for i in 0..=steps {
+        let t = (i as f64) / (steps as f64);
+        let x = x1 + (x2 - x1) * t;
+        // Smoothstep, so the band leaves and enters the bars horizontally
+        let y = y1 + (y2 - y1) * t * t * (3.0 - 2.0 * t);
+        top.push(Pt2D::new(x, y));
+        bottom.push(Pt2D::new(x, y + thickness));
+    }
+    bottom.reverse();
+    top.extend(bottom);
+    top.push(top[0]);
+    Ring::new(top)
+}
@@ -302,6 +302,7 @@ impl State<App> for PerResidentImpact {
         if let Some(t) = app.session.layers.event(
             ctx,
             &app.cs,
+            &app.per_map,
             Mode::PerResidentImpact,
             Some(&self.bottom_panel),
         ) {
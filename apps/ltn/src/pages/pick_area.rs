@@ -1,10 +1,14 @@
+use std::collections::BTreeMap;
 use widgetry::mapspace::{World, WorldOutcome};
-use widgetry::tools::{ChooseSomething, PromptInput};
-use widgetry::{Choice, Color, DrawBaselayer, EventCtx, GfxCtx, Outcome, Panel, State, Widget};
+
+use widgetry::tools::{ChooseSomething, PopupMsg, PromptInput};
+use widgetry::{
+    Choice, Color, DrawBaselayer, EventCtx, GfxCtx, Line, Outcome, Panel, State, Text, Widget,
+};
 
 use crate::components::{AppwidePanel, BottomPanel, Mode};
 use crate::render::colors;
-use crate::{pages, render, App, Neighbourhood, NeighbourhoodID, Transition};
+use crate::{logic, pages, render, App, Neighbourhood, NeighbourhoodID, Transition};
 
 pub struct PickArea {
     appwide_panel: AppwidePanel,
@@ -39,13 +43,25 @@ impl PickArea {
                     .btn_outline
                     .text("Manage custom boundaries")
                     .build_def(ctx),
+                ctx.style()
+                    .btn_outline
+                    .text("Find school street candidates")
+                    .build_def(ctx),
+                ctx.style()
+                    .btn_outline
+                    .text("Review worst neighbourhoods")
+                    .build_def(ctx),
             ]),
         );
 
         // Just force the layers panel to align above the bottom panel
-        app.session
-            .layers
-            .event(ctx, &app.cs, Mode::PickArea, Some(&bottom_panel));
+        app.session.layers.event(
+            ctx,
+            &app.cs,
+            &app.per_map,
+            Mode::PickArea,
+            Some(&bottom_panel),
+        );
 
         Box::new(Self {
             appwide_panel,
@@ -63,11 +79,13 @@ impl State<App> for PickArea {
         {
             return t;
         }
-        if let Some(t) =
-            app.session
-                .layers
-                .event(ctx, &app.cs, Mode::PickArea, Some(&self.bottom_panel))
-        {
+        if let Some(t) = app.session.layers.event(
+            ctx,
+            &app.cs,
+            &app.per_map,
+            Mode::PickArea,
+            Some(&self.bottom_panel),
+        ) {
             return t;
         }
 
@@ -76,6 +94,13 @@ impl State<App> for PickArea {
                 return change_draw_style(ctx);
             } else if x == "Manage custom boundaries" {
                 return manage_custom_boundary(ctx, app);
+            } else if x == "Find school street candidates" {
+                let candidates = ctx.loading_screen("find school street candidates", |_, timer| {
+                    logic::find_school_street_candidates(app, timer)
+                });
+                return Transition::Push(pages::SchoolStreets::new_state(ctx, app, candidates));
+            } else if x == "Review worst neighbourhoods" {
+                return review_worst_neighbourhoods(ctx, app);
             } else {
                 unreachable!()
             }
@@ -116,6 +141,11 @@ fn make_world(ctx: &mut EventCtx, app: &App) -> World<NeighbourhoodID> {
             "render neighbourhoods",
             app.partitioning().all_neighbourhoods().len(),
         );
+        let quality = if app.session.draw_neighbourhood_style == PickAreaStyle::Quality {
+            app.partitioning().quality(map)
+        } else {
+            BTreeMap::new()
+        };
         for (id, info) in app.partitioning().all_neighbourhoods() {
             timer.next();
             match app.session.draw_neighbourhood_style {
@@ -131,7 +161,10 @@ fn make_world(ctx: &mut EventCtx, app: &App) -> World<NeighbourhoodID> {
                 PickAreaStyle::Cells => {
                     let neighbourhood = Neighbourhood::new(app, *id);
                     let render_cells = render::RenderCells::new(map, &neighbourhood);
-                    let hovered_batch = render_cells.draw_colored_areas();
+                    let mut hovered_batch = render_cells.draw_colored_areas();
+                    if app.opts.pattern_fills {
+                        hovered_batch.append(render_cells.draw_patterns());
+                    }
                     world
                         .add(*id)
                         .hitbox(info.block.polygon.clone())
@@ -159,6 +192,23 @@ fn make_world(ctx: &mut EventCtx, app: &App) -> World<NeighbourhoodID> {
                         .clickable()
                         .build(ctx);
                 }
+                PickAreaStyle::Quality => {
+                    let color = app.cs.good_to_bad_red.eval(1.0 - quality[id].score());
+                    let mut txt = Text::new();
+                    for problem in quality[id].problems() {
+                        txt.add_line(Line(problem));
+                    }
+                    let mut obj = world
+                        .add(*id)
+                        .hitbox(info.block.polygon.clone())
+                        .draw_color(color.alpha(0.5))
+                        .hover_color(colors::HOVER)
+                        .clickable();
+                    if !txt.is_empty() {
+                        obj = obj.tooltip(txt);
+                    }
+                    obj.build(ctx);
+                }
             }
         }
     });
@@ -170,6 +220,7 @@ pub enum PickAreaStyle {
     Simple,
     Cells,
     Quietness,
+    Quality,
 }
 
 fn help() -> Vec<&'static str> {
@@ -177,6 +228,7 @@ fn help() -> Vec<&'static str> {
         "Basic map navigation: click and drag to pan, swipe or scroll to zoom",
         "",
         "Click a neighbourhood to analyze it. You can adjust boundaries there.",
+        "Review the worst neighbourhoods to find boundaries that may need fixing.",
     ]
 }
 
@@ -191,6 +243,10 @@ fn change_draw_style(ctx: &mut EventCtx) -> Transition {
                 "color areas by how much shortcutting they have",
                 PickAreaStyle::Quietness,
             ),
+            Choice::new(
+                "color areas by the quality of their boundary",
+                PickAreaStyle::Quality,
+            ),
         ],
         Box::new(move |choice, _, app| {
             app.session.draw_neighbourhood_style = choice;
@@ -199,6 +255,37 @@ fn change_draw_style(ctx: &mut EventCtx) -> Transition {
     ))
 }
 
+fn review_worst_neighbourhoods(ctx: &mut EventCtx, app: &App) -> Transition {
+    let worst = app
+        .partitioning()
+        .worst_neighbourhoods(&app.per_map.map, 10);
+    if worst.is_empty() {
+        return Transition::Push(PopupMsg::new_state(
+            ctx,
+            "Partitioning quality",
+            vec!["No neighbourhood boundaries look unusual"],
+        ));
+    }
+
+    let choices = worst
+        .into_iter()
+        .map(|(id, quality)| {
+            Choice::new(
+                format!("Neighbourhood {}: {}", id.0, quality.problems().join(", ")),
+                id,
+            )
+        })
+        .collect();
+    Transition::Push(ChooseSomething::new_state(
+        ctx,
+        "Adjust the boundary of a neighbourhood",
+        choices,
+        Box::new(|id, ctx, app| {
+            Transition::Clear(vec![pages::SelectBoundary::new_state(ctx, app, id)])
+        }),
+    ))
+}
+
 fn manage_custom_boundary(ctx: &mut EventCtx, app: &App) -> Transition {
     let mut choices = vec![Choice::new("Create new", None)];
     for (id, custom) in &app.partitioning().custom_boundaries {
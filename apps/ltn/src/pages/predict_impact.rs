@@ -6,6 +6,8 @@ use rand::SeedableRng;
 use rand_xorshift::XorShiftRng;
 use serde::Serialize;
 
+use abstutil::prettyprint_usize;
+use geom::Duration;
 use map_gui::tools::checkbox_per_mode;
 use map_model::{PathV2, Road};
 use synthpop::make::ScenarioGenerator;
@@ -18,6 +20,8 @@ use widgetry::{
 
 use crate::components::{AppwidePanel, Mode};
 use crate::logic::impact::{end_of_day, Filters, Impact};
+use crate::logic::PerimeterCrossings;
+use crate::pages::ShowPeopleFlow;
 use crate::render::colors;
 use crate::{App, Transition};
 
@@ -88,6 +92,11 @@ impl ShowImpactResults {
                 .compare_counts
                 .get_panel_widget(ctx)
                 .named("compare counts"),
+            crossing_delays(ctx, app).named("crossing delays"),
+            ctx.style()
+                .btn_outline
+                .text("Show people flow between neighbourhoods")
+                .build_def(ctx),
             ctx.style()
                 .btn_outline
                 .text("Save before/after counts to files (JSON)")
@@ -121,11 +130,21 @@ impl State<App> for ShowImpactResults {
         {
             return t;
         }
-        if let Some(t) = app.session.layers.event(ctx, &app.cs, Mode::Impact, None) {
+        if let Some(t) = app
+            .session
+            .layers
+            .event(ctx, &app.cs, &app.per_map, Mode::Impact, None)
+        {
             return t;
         }
         match self.left_panel.event(ctx) {
             Outcome::Clicked(x) => match x.as_ref() {
+                "Show people flow between neighbourhoods" => {
+                    let flow = ctx.loading_screen("calculate people flow", |_, timer| {
+                        app.per_map.impact.people_flow(app, timer)
+                    });
+                    return Transition::Push(ShowPeopleFlow::new_state(ctx, app, flow));
+                }
                 "Save before/after counts to files (JSON)" => {
                     let path1 = "counts_a.json";
                     let path2 = "counts_b.json";
@@ -190,6 +209,8 @@ impl State<App> for ShowImpactResults {
                     impact.trips_changed(ctx, app, timer);
                 });
                 app.per_map.impact = impact;
+                let widget = crossing_delays(ctx, app);
+                self.left_panel.replace(ctx, "crossing delays", widget);
                 return Transition::Keep;
             }
             _ => {}
@@ -275,6 +296,46 @@ impl Filters {
     }
 }
 
+/// Summarizes how much harder it gets to cross the main roads around each neighbourhood, showing
+/// the worst changes
+fn crossing_delays(ctx: &mut EventCtx, app: &App) -> Widget {
+    let mut changes = Vec::new();
+    for id in app.partitioning().all_neighbourhoods().keys() {
+        let crossings = PerimeterCrossings::new(app, *id);
+        if crossings.roads.is_empty() {
+            continue;
+        }
+        let (before, after) = crossings.mean_wait();
+        changes.push((*id, after - before, crossings));
+    }
+    changes.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+
+    let mut txt = Text::from(Line("Crossing main roads on foot").small_heading());
+    txt.add_line(Line(
+        "Average wait for a gap in traffic on the main roads around each neighbourhood, assuming \
+         uncontrolled crossings",
+    ));
+    if changes.iter().all(|(_, delta, _)| *delta <= Duration::ZERO) {
+        txt.add_line(Line("No neighbourhood boundary is harder to cross"));
+    }
+    for (id, delta, crossings) in changes.into_iter().take(5) {
+        if delta <= Duration::ZERO {
+            break;
+        }
+        let (wait_before, wait_after) = crossings.mean_wait();
+        let (gaps_before, gaps_after) = crossings.min_gaps_per_hour();
+        txt.add_line(Line(format!(
+            "Neighbourhood {}: wait {} -> {}, fewest gaps per hour {} -> {}",
+            id.0,
+            wait_before,
+            wait_after,
+            prettyprint_usize(gaps_before as usize),
+            prettyprint_usize(gaps_after as usize)
+        )));
+    }
+    txt.wrap_to_pct(ctx, 20).into_widget(ctx).section(ctx)
+}
+
 fn help() -> Vec<&'static str> {
     vec![
         "This tool is still experimental.",
@@ -282,6 +343,8 @@ fn help() -> Vec<&'static str> {
         "because we don't know where trips begin and end.",
         "",
         "And note this tool doesn't predict traffic dissipation as people decide to not drive.",
+        "",
+        "Crossing delays only count the modes selected above, and assume nobody crosses at a signal.",
     ]
 }
 
@@ -388,10 +388,10 @@ impl State<App> for RoutePlanner {
         {
             return t;
         }
-        if let Some(t) = app
-            .session
-            .layers
-            .event(ctx, &app.cs, Mode::RoutePlanner, None)
+        if let Some(t) =
+            app.session
+                .layers
+                .event(ctx, &app.cs, &app.per_map, Mode::RoutePlanner, None)
         {
             return t;
         }
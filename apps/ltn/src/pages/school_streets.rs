@@ -0,0 +1,135 @@
+use widgetry::{
+    Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, State, Text,
+    TextExt, VerticalAlignment, Widget,
+};
+
+use crate::logic::SchoolStreetCandidate;
+use crate::render::colors;
+use crate::{redraw_all_icons, App, Transition};
+
+// Long lists aren't useful; the best candidates are first anyway
+const MAX_CANDIDATES: usize = 20;
+
+/// Lists streets next to schools that could become school streets, best first
+pub struct SchoolStreets {
+    panel: Panel,
+    candidates: Vec<SchoolStreetCandidate>,
+    draw_candidates: Drawable,
+}
+
+impl SchoolStreets {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        app: &App,
+        mut candidates: Vec<SchoolStreetCandidate>,
+    ) -> Box<dyn State<App>> {
+        candidates.truncate(MAX_CANDIDATES);
+
+        let mut batch = GeomBatch::new();
+        for c in &candidates {
+            batch.push(
+                colors::HOVER,
+                app.per_map.map.get_r(c.road).get_thick_polygon(),
+            );
+        }
+
+        let mut state = Self {
+            panel: Panel::empty(ctx),
+            candidates,
+            draw_candidates: ctx.upload(batch),
+        };
+        state.panel = state.make_panel(ctx, app);
+        Box::new(state)
+    }
+
+    fn make_panel(&self, ctx: &mut EventCtx, app: &App) -> Panel {
+        let map = &app.per_map.map;
+        let mut col = vec![
+            Widget::row(vec![
+                Line("School street candidates")
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Text::from(Line(
+                "Local streets next to schools, with no bus routes and another way in at both \
+                 ends. Streets with the fewest shortcuts through them are listed first.",
+            ))
+            .wrap_to_pct(ctx, 30)
+            .into_widget(ctx),
+        ];
+        if self.candidates.is_empty() {
+            col.push("No suitable streets found".text_widget(ctx));
+        }
+
+        for (idx, c) in self.candidates.iter().enumerate() {
+            let road = map.get_r(c.road);
+            let closed = road.modal_filter.is_some();
+            col.push(Widget::row(vec![
+                ctx.style()
+                    .btn_plain
+                    .text(road.get_name(app.opts.language.as_ref()))
+                    .build_widget(ctx, format!("show {}", idx)),
+                Line(format!(
+                    "{} school(s), {} shortcut(s)",
+                    c.schools.len(),
+                    c.shortcuts
+                ))
+                .secondary()
+                .into_widget(ctx)
+                .centered_vert(),
+                ctx.style()
+                    .btn_outline
+                    .text(if closed {
+                        "Closed"
+                    } else {
+                        "Close during school hours"
+                    })
+                    .disabled(closed)
+                    .build_widget(ctx, format!("close {}", idx))
+                    .align_right(),
+            ]));
+        }
+
+        Panel::new_builder(Widget::col(col))
+            .aligned(HorizontalAlignment::Left, VerticalAlignment::Center)
+            .build(ctx)
+    }
+}
+
+impl State<App> for SchoolStreets {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            if x == "close" {
+                return Transition::Pop;
+            } else if let Some(idx) = x.strip_prefix("show ") {
+                let idx = idx.parse::<usize>().unwrap();
+                let pt = app
+                    .per_map
+                    .map
+                    .get_r(self.candidates[idx].road)
+                    .center_pts
+                    .middle();
+                ctx.canvas.center_on_map_pt(pt);
+            } else if let Some(idx) = x.strip_prefix("close ") {
+                let idx = idx.parse::<usize>().unwrap();
+                self.candidates[idx].apply(app);
+                redraw_all_icons(ctx, app);
+                self.panel = self.make_panel(ctx, app);
+            } else {
+                unreachable!()
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw_candidates);
+        app.per_map.draw_all_filters.draw(g);
+        app.per_map.draw_poi_icons.draw(g);
+        self.panel.draw(g);
+    }
+}
@@ -30,6 +30,10 @@ pub struct SelectBoundary {
     draw_last_error: Drawable,
 
     lasso: Option<Lasso>,
+
+    // Blocks that'd likely improve the boundary, from Partitioning::suggest_transfers
+    suggestions: Vec<BlockID>,
+    draw_suggestions: Drawable,
 }
 
 impl SelectBoundary {
@@ -60,7 +64,7 @@ impl SelectBoundary {
         }
 
         let appwide_panel = AppwidePanel::new(ctx, app, Mode::SelectBoundary);
-        let left_panel = make_panel(ctx, app, id, &appwide_panel.top_panel);
+        let left_panel = Panel::empty(ctx);
         let mut state = SelectBoundary {
             appwide_panel,
             left_panel,
@@ -73,7 +77,11 @@ impl SelectBoundary {
             draw_last_error: Drawable::empty(ctx),
 
             lasso: None,
+
+            suggestions: Vec::new(),
+            draw_suggestions: Drawable::empty(ctx),
         };
+        state.refresh_panel(ctx, app);
 
         let initial_boundary = app.partitioning().neighbourhood_block(id);
         state.frontier = app
@@ -156,7 +164,7 @@ impl SelectBoundary {
                     self.add_block(ctx, app, changed);
                 }
 
-                self.left_panel = make_panel(ctx, app, self.id, &self.appwide_panel.top_panel);
+                self.refresh_panel(ctx, app);
             }
             Err(err) => {
                 self.last_failed_change = Some((id, self.currently_have_block(app, id)));
@@ -204,6 +212,27 @@ impl SelectBoundary {
         }
     }
 
+    fn refresh_panel(&mut self, ctx: &mut EventCtx, app: &App) {
+        self.suggestions = app
+            .partitioning()
+            .suggest_transfers(&app.per_map.map, self.id, 3);
+        let mut batch = GeomBatch::new();
+        for block in &self.suggestions {
+            batch.push(
+                colors::BLOCK_SUGGESTED,
+                app.partitioning().get_block(*block).polygon.clone(),
+            );
+        }
+        self.draw_suggestions = ctx.upload(batch);
+        self.left_panel = make_panel(
+            ctx,
+            app,
+            self.id,
+            &self.suggestions,
+            &self.appwide_panel.top_panel,
+        );
+    }
+
     fn currently_have_block(&self, app: &App, id: BlockID) -> bool {
         app.partitioning().block_to_neighbourhood(id) == self.id
     }
@@ -277,14 +306,14 @@ impl State<App> for SelectBoundary {
             if let Some(polygon) = lasso.event(ctx) {
                 self.lasso = None;
                 self.add_blocks_freehand(ctx, app, polygon);
-                self.left_panel = make_panel(ctx, app, self.id, &self.appwide_panel.top_panel);
+                self.refresh_panel(ctx, app);
                 return Transition::Keep;
             }
 
             if let Outcome::Clicked(x) = self.left_panel.event(ctx) {
                 if x == "Cancel" {
                     self.lasso = None;
-                    self.left_panel = make_panel(ctx, app, self.id, &self.appwide_panel.top_panel);
+                    self.refresh_panel(ctx, app);
                 }
             }
 
@@ -298,10 +327,10 @@ impl State<App> for SelectBoundary {
         {
             return t;
         }
-        if let Some(t) = app
-            .session
-            .layers
-            .event(ctx, &app.cs, Mode::SelectBoundary, None)
+        if let Some(t) =
+            app.session
+                .layers
+                .event(ctx, &app.cs, &app.per_map, Mode::SelectBoundary, None)
         {
             return t;
         }
@@ -321,7 +350,13 @@ impl State<App> for SelectBoundary {
                     self.lasso = Some(Lasso::new(Distance::meters(1.0)));
                     self.left_panel = make_panel_for_lasso(ctx, &self.appwide_panel.top_panel);
                 }
-                _ => unreachable!(),
+                x => {
+                    if let Some(idx) = x.strip_prefix("accept suggestion ") {
+                        let block = self.suggestions[idx.parse::<usize>().unwrap()];
+                        return self.toggle_block(ctx, app, block);
+                    }
+                    unreachable!()
+                }
             },
             Outcome::Changed(_) => {
                 app.session.add_intermediate_blocks = self
@@ -358,6 +393,9 @@ impl State<App> for SelectBoundary {
     fn draw(&self, g: &mut GfxCtx, app: &App) {
         self.world.draw(g);
         g.redraw(&self.draw_last_error);
+        if self.lasso.is_none() {
+            g.redraw(&self.draw_suggestions);
+        }
         self.appwide_panel.draw(g);
         self.left_panel.draw(g);
         app.per_map
@@ -373,7 +411,13 @@ impl State<App> for SelectBoundary {
     }
 }
 
-fn make_panel(ctx: &mut EventCtx, app: &App, id: NeighbourhoodID, top_panel: &Panel) -> Panel {
+fn make_panel(
+    ctx: &mut EventCtx,
+    app: &App,
+    id: NeighbourhoodID,
+    suggestions: &[BlockID],
+    top_panel: &Panel,
+) -> Panel {
     crate::components::LeftPanel::builder(
         ctx,
         top_panel,
@@ -427,17 +471,66 @@ fn make_panel(ctx: &mut EventCtx, app: &App, id: NeighbourhoodID, top_panel: &Pa
                     .build_def(ctx),
             ]),
             Widget::placeholder(ctx, "warning"),
+            make_quality_panel(ctx, app, id, suggestions),
             legend_entry(
                 ctx,
                 colors::BLOCK_IN_BOUNDARY,
                 "block part of current neighbourhood",
             ),
             legend_entry(ctx, colors::BLOCK_IN_FRONTIER, "block could be added"),
+            legend_entry(ctx, colors::BLOCK_SUGGESTED, "suggested block to add"),
         ]),
     )
     .build(ctx)
 }
 
+fn make_quality_panel(
+    ctx: &mut EventCtx,
+    app: &App,
+    id: NeighbourhoodID,
+    suggestions: &[BlockID],
+) -> Widget {
+    let quality = match app.partitioning().quality(&app.per_map.map).remove(&id) {
+        Some(quality) => quality,
+        // Custom boundaries aren't made of blocks
+        None => {
+            return Widget::nothing();
+        }
+    };
+
+    let mut col = vec![
+        Line("Boundary quality").small_heading().into_widget(ctx),
+        format!("Compactness: {:.0}%", 100.0 * quality.compactness.min(1.0)).text_widget(ctx),
+        format!(
+            "Perimeter along main roads: {:.0}%",
+            100.0 * quality.arterial_perimeter
+        )
+        .text_widget(ctx),
+        format!(
+            "Size compared to a typical neighbourhood: {:.1}x",
+            quality.size_ratio
+        )
+        .text_widget(ctx),
+    ];
+    for problem in quality.problems() {
+        col.push(
+            Line(format!("Problem: {}", problem))
+                .fg(Color::RED)
+                .into_widget(ctx),
+        );
+    }
+    for (idx, block) in suggestions.iter().enumerate() {
+        let from = app.partitioning().block_to_neighbourhood(*block);
+        col.push(
+            ctx.style()
+                .btn_outline
+                .text(format!("Add suggested block from neighbourhood {}", from.0))
+                .build_widget(ctx, format!("accept suggestion {}", idx)),
+        );
+    }
+    Widget::col(col).section(ctx)
+}
+
 fn make_panel_for_lasso(ctx: &mut EventCtx, top_panel: &Panel) -> Panel {
     crate::components::LeftPanel::builder(
         ctx,
@@ -469,5 +562,7 @@ fn help() -> Vec<&'static str> {
         "The aqua blocks show where you can currently expand the boundary.",
         "Hint: There may be very small blocks near complex roads.",
         "Try the freehand tool to select them.",
+        "",
+        "Orange blocks are suggestions that would push the boundary out to main roads.",
     ]
 }
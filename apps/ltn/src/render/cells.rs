@@ -2,7 +2,8 @@ use std::collections::{HashSet, VecDeque};
 
 use geom::{Bounds, Distance, Polygon};
 use map_gui::tools::Grid;
-use map_model::Map;
+use map_model::{polygon_ops, Map};
+use widgetry::tools::Pattern;
 use widgetry::{Color, GeomBatch};
 
 use crate::render::colors;
@@ -50,6 +51,29 @@ impl RenderCells {
         batch
     }
 
+    /// Draw hatching over each cell, so that adjacent cells can be told apart without relying on
+    /// the colors alone.
+    pub fn draw_patterns(&self) -> GeomBatch {
+        let mut batch = GeomBatch::new();
+        for (color, polygons) in self.colors.iter().zip(self.polygons_per_cell.iter()) {
+            let pattern = if *color == colors::DISCONNECTED_CELL {
+                Pattern::CrossHatch
+            } else {
+                // Colors are assigned from a fixed palette, so the same color always gets the
+                // same pattern
+                colors::CELLS
+                    .iter()
+                    .position(|c| c.alpha(0.8) == *color)
+                    .map(Pattern::nth)
+                    .unwrap_or(Pattern::Solid)
+            };
+            for poly in polygons {
+                pattern.draw(&mut batch, poly, Distance::meters(RESOLUTION_M));
+            }
+        }
+        batch
+    }
+
     /// Draw the boundary between cells as a thick outline. It's meant to look like the
     /// neighbourhood is split into disconnected islands.
     pub fn draw_island_outlines(&self) -> GeomBatch {
@@ -72,7 +96,7 @@ impl RenderCells {
 
                 let color = cell_color.alpha(1.0).shade(0.2);
                 // If possible, try to erase where the cell boundary touches the perimeter road.
-                if let Ok(list) = boundary.difference(&neighbourhood_boundary) {
+                if let Ok(list) = polygon_ops::difference(&boundary, &neighbourhood_boundary) {
                     batch.extend(color, list);
                 } else {
                     batch.push(color, boundary);
@@ -235,16 +259,7 @@ impl RenderCellsBuilder {
 
             // Sometimes one cell "leaks" out of the neighbourhood boundary. Not sure why. But we
             // can just clip the result.
-            let mut clipped = Vec::new();
-            for p in cell_polygons {
-                // If clipping fails, just use the original polygon.
-                if let Ok(list) = p.intersection(&result.boundary_polygon) {
-                    clipped.extend(list);
-                } else {
-                    clipped.push(p);
-                }
-            }
-
+            let clipped = polygon_ops::clip_all(cell_polygons, &result.boundary_polygon);
             result.polygons_per_cell.push(clipped);
             result.colors.push(color);
         }
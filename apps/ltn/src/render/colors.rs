@@ -46,6 +46,7 @@ pub const DISCONNECTED_CELL: Color = Color::RED.alpha(0.5);
 
 pub const BLOCK_IN_BOUNDARY: Color = Color::BLUE.alpha(0.5);
 pub const BLOCK_IN_FRONTIER: Color = Color::CYAN.alpha(0.2);
+pub const BLOCK_SUGGESTED: Color = Color::ORANGE.alpha(0.5);
 
 // TODO This doesn't show up easily against roads with dark red shortcuts
 pub const LOCAL_ROAD_LABEL: Color = Color::BLACK;
@@ -1,10 +1,10 @@
 use abstutil::Timer;
-use map_gui::tools::{FilePicker, FileSaver, FileSaverContents};
+use map_gui::tools::{browse_shared_proposals, FilePicker, FileSaver, FileSaverContents};
 use widgetry::tools::{ChooseSomething, PopupMsg};
 use widgetry::{lctrl, Choice, EventCtx, Key, MultiKey, State, Widget};
 
 use super::save_dialog::SaveDialog;
-use super::share::ShareProposal;
+use super::share::{ShareProposal, PROPOSAL_HOST_URL};
 use super::{PreserveState, Proposal, Proposals};
 use crate::{App, Transition};
 
@@ -16,6 +16,7 @@ impl Proposals {
             ("Load", "folder", None),
             ("Save", "save", Some(MultiKey::from(lctrl(Key::S)))),
             ("Share", "share", None),
+            ("Browse shared", "search", None),
             ("Export GeoJSON", "export", None),
         ] {
             col.push(
@@ -67,6 +68,7 @@ impl Proposals {
             ("Load", "folder"),
             ("Save", "save"),
             ("Share", "share"),
+            ("Browse shared", "search"),
             ("Export GeoJSON", "export"),
         ] {
             col.push(
@@ -109,6 +111,21 @@ impl Proposals {
             "Share" => {
                 return Some(Transition::Push(ShareProposal::new_state(ctx, app)));
             }
+            "Browse shared" => {
+                let preserve_state = preserve_state.clone();
+                return Some(Transition::Push(browse_shared_proposals(
+                    ctx,
+                    app.per_map.map.get_name(),
+                    format!("{}/list-ltn", PROPOSAL_HOST_URL),
+                    format!("{}/get-ltn", PROPOSAL_HOST_URL),
+                    Box::new(move |ctx, app, result, title| {
+                        match Proposal::load_from_bytes(ctx, app, &title, result) {
+                            Some(err_state) => Transition::Replace(err_state),
+                            None => preserve_state.switch_to_state(ctx, app),
+                        }
+                    }),
+                )));
+            }
             "Export GeoJSON" => {
                 return Some(Transition::Push(match crate::export::geojson_string(app) {
                     Ok(contents) => FileSaver::with_default_messages(
@@ -94,6 +94,7 @@ impl MainState {
                     .section(ctx),
                     Widget::col(vec![
                         Toggle::choice(ctx, "create", "intersection", "building", None, true),
+                        Toggle::choice(ctx, "new link", "road", "footpath", None, true),
                         Toggle::switch(ctx, "show intersection geometry", Key::G, false),
                         ctx.style()
                             .btn_outline
@@ -136,6 +137,21 @@ impl MainState {
         let instructions = txt.into_widget(ctx);
         self.panel.replace(ctx, "instructions", instructions);
     }
+
+    fn finish_road(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        i1: IntersectionID,
+        i2: IntersectionID,
+    ) {
+        let footpath = !self.panel.is_checked("new link");
+        if app.model.create_r(ctx, i1, i2, footpath) {
+            self.mode = Mode::Neutral;
+        }
+        app.model.world.initialize_hover(ctx);
+        self.update_instructions(ctx, app);
+    }
 }
 
 impl State<App> for MainState {
@@ -243,6 +259,13 @@ impl State<App> for MainState {
                     WorldOutcome::Keypress("mark/unmark as a junction", ID::Road(r)) => {
                         app.model.toggle_junction(ctx, r);
                     }
+                    WorldOutcome::Keypress("split here", ID::Road(r)) => {
+                        if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+                            app.model.split_r(ctx, r, pt);
+                            app.model.world.initialize_hover(ctx);
+                            self.update_instructions(ctx, app);
+                        }
+                    }
                     WorldOutcome::Keypress("debug in OSM", ID::Road(r)) => {
                         if let Some(id) = app.model.map.streets.roads[&r].osm_ids.get(0) {
                             open_browser(id.to_string());
@@ -289,7 +312,7 @@ impl State<App> for MainState {
                         }
                         _ => unreachable!(),
                     },
-                    Outcome::Changed(_) => {
+                    Outcome::Changed(x) if x == "show intersection geometry" => {
                         app.model.show_intersection_geometry(
                             ctx,
                             self.panel.is_checked("show intersection geometry"),
@@ -306,11 +329,24 @@ impl State<App> for MainState {
                 if ctx.input.pressed(Key::Escape) {
                     self.mode = Mode::Neutral;
                     // TODO redo mouseover?
-                } else if let Some(ID::Intersection(i2)) = app.model.world.calculate_hovering(ctx) {
-                    if i1 != i2 && ctx.input.pressed(Key::R) {
-                        app.model.create_r(ctx, i1, i2);
-                        self.mode = Mode::Neutral;
-                        // TODO redo mouseover?
+                    return Transition::Keep;
+                }
+                let hovering = app.model.world.calculate_hovering(ctx);
+                if let Some(ID::Intersection(i2)) = hovering {
+                    if i1 != i2 && (ctx.input.pressed(Key::R) || ctx.normal_left_click()) {
+                        self.finish_road(ctx, app, i1, i2);
+                    }
+                } else if ctx.normal_left_click() {
+                    // Snap to an existing road by splitting it, or end somewhere new
+                    let i2 = match (hovering, ctx.canvas.get_cursor_in_map_space()) {
+                        (Some(ID::Road(r)), Some(pt)) | (Some(ID::RoadPoint(r, _)), Some(pt)) => {
+                            app.model.split_r(ctx, r, pt)
+                        }
+                        (None, Some(pt)) => Some(app.model.create_i(ctx, pt)),
+                        _ => None,
+                    };
+                    if let Some(i2) = i2 {
+                        self.finish_road(ctx, app, i1, i2);
                     }
                 }
             }
@@ -7,12 +7,13 @@ use geom::{
     Circle, Distance, FindClosest, GPSBounds, HashablePt2D, LonLat, PolyLine, Polygon, Pt2D,
 };
 use osm2streets::{osm, IntersectionControl, IntersectionID, IntersectionKind, Road, RoadID};
-use raw_map::{RawBuilding, RawMap};
+use raw_map::{ExtraRoadData, RawBuilding, RawMap};
 use widgetry::mapspace::{ObjectID, World};
 use widgetry::{Color, EventCtx, GeomBatch, Key};
 
 const INTERSECTION_RADIUS: Distance = Distance::const_meters(2.5);
 const BUILDING_LENGTH: Distance = Distance::const_meters(30.0);
+const MIN_SPLIT_LENGTH: Distance = Distance::const_meters(5.0);
 
 // The caller should generally call world.initialize_hover after a mutation.
 pub struct Model {
@@ -181,7 +182,7 @@ impl Model {
             .build(ctx);
     }
 
-    pub fn create_i(&mut self, ctx: &EventCtx, point: Pt2D) {
+    pub fn create_i(&mut self, ctx: &EventCtx, point: Pt2D) -> IntersectionID {
         let id = self.map.streets.insert_intersection(
             Vec::new(),
             point,
@@ -189,7 +190,12 @@ impl Model {
             IntersectionKind::Intersection,
             IntersectionControl::Signed,
         );
+        // TODO Look up real elevation data, if this map has any
+        self.map
+            .elevation_per_intersection
+            .insert(id, Distance::ZERO);
         self.intersection_added(ctx, id);
+        id
     }
 
     pub fn move_i(&mut self, ctx: &EventCtx, id: IntersectionID, point: Pt2D) {
@@ -231,6 +237,7 @@ impl Model {
             return;
         }
         self.map.streets.remove_intersection(id);
+        self.map.elevation_per_intersection.remove(&id);
         self.world.delete(ID::Intersection(id));
     }
 
@@ -304,6 +311,7 @@ impl Model {
             .clickable()
             .hotkey(Key::Backspace, "delete")
             .hotkey(Key::P, "insert a new point here")
+            .hotkey(Key::S, "split here")
             .hotkey(Key::X, "remove interior points")
             .hotkey(Key::M, "merge")
             .hotkey(Key::J, "mark/unmark as a junction")
@@ -315,7 +323,14 @@ impl Model {
         self.world.delete(ID::Road(id));
     }
 
-    pub fn create_r(&mut self, ctx: &EventCtx, i1: IntersectionID, i2: IntersectionID) {
+    /// Connect two intersections with a new road or footpath. Returns false if this isn't possible.
+    pub fn create_r(
+        &mut self,
+        ctx: &EventCtx,
+        i1: IntersectionID,
+        i2: IntersectionID,
+        footpath: bool,
+    ) -> bool {
         // Ban cul-de-sacs, since they get stripped out later anyway.
         if self
             .map
@@ -325,17 +340,22 @@ impl Model {
             .any(|r| (r.src_i == i1 && r.dst_i == i2) || (r.src_i == i2 && r.dst_i == i1))
         {
             error!("Road already exists");
-            return;
+            return false;
         }
 
         let mut osm_tags = Tags::empty();
-        osm_tags.insert("highway", "residential");
-        osm_tags.insert("parking:both:lane", "parallel");
-        osm_tags.insert("sidewalk", "both");
-        osm_tags.insert("lanes", "2");
-        // Reasonable defaults.
-        osm_tags.insert("name", "Streety McStreetFace");
-        osm_tags.insert("maxspeed", "25 mph");
+        if footpath {
+            osm_tags.insert("highway", "footway");
+            osm_tags.insert("bicycle", "yes");
+        } else {
+            osm_tags.insert("highway", "residential");
+            osm_tags.insert("parking:both:lane", "parallel");
+            osm_tags.insert("sidewalk", "both");
+            osm_tags.insert("lanes", "2");
+            // Reasonable defaults.
+            osm_tags.insert("name", "Streety McStreetFace");
+            osm_tags.insert("maxspeed", "25 mph");
+        }
 
         let reference_line = match PolyLine::new(vec![
             self.map.streets.intersections[&i1].polygon.center(),
@@ -344,33 +364,136 @@ impl Model {
             Ok(pl) => pl,
             Err(err) => {
                 error!("Can't create road: {err}");
-                return;
+                return false;
             }
         };
 
         self.world.delete_before_replacement(ID::Intersection(i1));
         self.world.delete_before_replacement(ID::Intersection(i2));
 
+        // Importing the map looks up tags by OSM way, so make up a way for the new road.
+        let way = self.synthetic_way_id();
+        self.map.osm_tags.insert(way, osm_tags.clone());
+
         let id = self.map.streets.next_road_id();
         self.map.streets.insert_road(Road::new(
             id,
-            Vec::new(),
+            vec![way],
             i1,
             i2,
             reference_line,
             osm_tags,
             &self.map.streets.config,
         ));
+        self.map
+            .extra_road_data
+            .insert(id, ExtraRoadData::default());
         self.road_added(ctx, id);
 
         self.intersection_added(ctx, i1);
         self.intersection_added(ctx, i2);
+        true
+    }
+
+    /// Split a road in two at the point closest to `pt`, creating a new intersection there.
+    pub fn split_r(&mut self, ctx: &EventCtx, id: RoadID, pt: Pt2D) -> Option<IntersectionID> {
+        let road = &self.map.streets.roads[&id];
+        let pl = road.reference_line.clone();
+        let (dist, _) = pl.dist_along_of_point(pl.project_pt(pt))?;
+        if dist < MIN_SPLIT_LENGTH || pl.length() - dist < MIN_SPLIT_LENGTH {
+            error!("Can't split a road so close to one end");
+            return None;
+        }
+        let (first_half, second_half) = match (
+            pl.maybe_exact_slice(Distance::ZERO, dist),
+            pl.maybe_exact_slice(dist, pl.length()),
+        ) {
+            (Ok(pl1), Ok(pl2)) => (pl1, pl2),
+            _ => {
+                error!("Can't split {id} here");
+                return None;
+            }
+        };
+        let (src_i, dst_i) = (road.src_i, road.dst_i);
+        let osm_ids = road.osm_ids.clone();
+        let osm_tags = self
+            .map
+            .road_to_osm_tags(id)
+            .cloned()
+            .unwrap_or_else(Tags::empty);
+
+        self.stop_showing_pts(id);
+        self.road_deleted(id);
+        self.map.streets.remove_road(id);
+        let extra = self
+            .map
+            .extra_road_data
+            .remove(&id)
+            .unwrap_or_else(ExtraRoadData::default);
+        self.world
+            .delete_before_replacement(ID::Intersection(src_i));
+        self.world
+            .delete_before_replacement(ID::Intersection(dst_i));
+
+        let split_pt = first_half.last_pt();
+        let new_i = self.map.streets.insert_intersection(
+            Vec::new(),
+            split_pt,
+            IntersectionKind::Intersection,
+            IntersectionControl::Signed,
+        );
+        // Interpolate the elevation between the two original ends
+        let elevation1 = self.elevation(src_i);
+        let elevation2 = self.elevation(dst_i);
+        self.map.elevation_per_intersection.insert(
+            new_i,
+            elevation1 + (elevation2 - elevation1) * (dist / pl.length()),
+        );
+
+        // Barrier, crossing, and kerb nodes belong to whichever half they're on
+        let on_first_half = |node: &Pt2D| {
+            pl.dist_along_of_point(pl.project_pt(*node))
+                .map(|(d, _)| d <= dist)
+                .unwrap_or(true)
+        };
+        let mut extra1 = extra.clone();
+        extra1.barrier_nodes.retain(|pt| on_first_half(pt));
+        extra1.crossing_nodes.retain(|(pt, _)| on_first_half(pt));
+        extra1.raised_kerbs.retain(|pt| on_first_half(pt));
+        let mut extra2 = extra;
+        extra2.barrier_nodes.retain(|pt| !on_first_half(pt));
+        extra2.crossing_nodes.retain(|(pt, _)| !on_first_half(pt));
+        extra2.raised_kerbs.retain(|pt| !on_first_half(pt));
+
+        for (i1, i2, reference_line, extra) in [
+            (src_i, new_i, first_half, extra1),
+            (new_i, dst_i, second_half, extra2),
+        ] {
+            let r = self.map.streets.next_road_id();
+            self.map.streets.insert_road(Road::new(
+                r,
+                osm_ids.clone(),
+                i1,
+                i2,
+                reference_line,
+                osm_tags.clone(),
+                &self.map.streets.config,
+            ));
+            self.map.extra_road_data.insert(r, extra);
+            self.road_added(ctx, r);
+        }
+
+        self.intersection_added(ctx, src_i);
+        self.intersection_added(ctx, dst_i);
+        self.intersection_added(ctx, new_i);
+        Some(new_i)
     }
 
     pub fn delete_r(&mut self, ctx: &EventCtx, id: RoadID) {
         self.stop_showing_pts(id);
         self.road_deleted(id);
         let road = self.map.streets.remove_road(id);
+        self.map.extra_road_data.remove(&id);
         self.world
             .delete_before_replacement(ID::Intersection(road.src_i));
         self.world
@@ -508,6 +631,20 @@ impl Model {
         self.recreate_world(ctx, &mut Timer::throwaway());
     }
 
+    fn synthetic_way_id(&self) -> osm::WayID {
+        // Real OSM IDs are positive, so count down from the lowest one already made up
+        let lowest = self.map.osm_tags.keys().map(|way| way.0).min().unwrap_or(0);
+        osm::WayID(lowest.min(0) - 1)
+    }
+
+    fn elevation(&self, i: IntersectionID) -> Distance {
+        self.map
+            .elevation_per_intersection
+            .get(&i)
+            .cloned()
+            .unwrap_or(Distance::ZERO)
+    }
+
     pub fn toggle_junction(&mut self, ctx: &EventCtx, id: RoadID) {
         self.road_deleted(id);
 
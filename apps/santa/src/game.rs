@@ -1,9 +1,10 @@
 use std::collections::HashSet;
 
 use abstutil::prettyprint_usize;
-use geom::{ArrowCap, Circle, Distance, Duration, PolyLine, Pt2D, Time};
+use geom::{ArrowCap, Bounds, Circle, Distance, Duration, PolyLine, Pt2D, Time};
 use map_gui::tools::{Minimap, MinimapControls};
 use map_model::BuildingID;
+use widgetry::mapspace::SpatialIndex;
 use widgetry::tools::{ChooseSomething, ColorLegend};
 use widgetry::{
     Choice, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Image, Key, Line,
@@ -292,7 +293,7 @@ impl Game {
             if self.state.energyless_arrow.is_none() {
                 self.state.energyless_arrow = Some(EnergylessArrow::new(
                     ctx,
-                    app.time,
+                    app,
                     self.state.bldgs.all_stores(),
                 ));
                 let label = Text::from(
@@ -614,16 +615,25 @@ struct EnergylessArrow {
     draw: Drawable,
     started: Time,
     last_update: Time,
-    all_stores: Vec<BuildingID>,
+    // Indexed by the end of each store's driveway
+    all_stores: SpatialIndex<BuildingID>,
 }
 
 impl EnergylessArrow {
-    fn new(ctx: &EventCtx, started: Time, all_stores: Vec<BuildingID>) -> EnergylessArrow {
+    fn new(ctx: &EventCtx, app: &App, all_stores: Vec<BuildingID>) -> EnergylessArrow {
         EnergylessArrow {
             draw: Drawable::empty(ctx),
-            started,
+            started: app.time,
             last_update: Time::START_OF_DAY,
-            all_stores,
+            all_stores: SpatialIndex::bulk_load(
+                all_stores
+                    .into_iter()
+                    .map(|b| {
+                        let pt = app.map.get_b(b).driveway_geom.last_pt();
+                        (b, Bounds::from(&[pt]))
+                    })
+                    .collect(),
+            ),
         }
     }
 
@@ -636,13 +646,13 @@ impl EnergylessArrow {
         // driveway, since sometimes it's hard to quickly spot which road a building is connected
         // to.
         // TODO Or pathfind and show them that?
-        let store = app.map.get_b(
-            *self
-                .all_stores
-                .iter()
-                .min_by_key(|b| app.map.get_b(**b).driveway_geom.last_pt().fast_dist(sleigh))
-                .unwrap(),
-        );
+        let (store, _) = self
+            .all_stores
+            .nearest(sleigh, Distance::meters(f64::MAX), |b| {
+                app.map.get_b(b).driveway_geom.last_pt().dist_to(sleigh)
+            })
+            .unwrap();
+        let store = app.map.get_b(store);
 
         // Vibrate in size slightly
         let period = Duration::seconds(0.5);
@@ -13,7 +13,8 @@ use abstutil::wraparound_get;
 use geom::{Polygon, Pt2D, Ring};
 
 use map_model::{
-    osm, CommonEndpoint, Direction, LaneID, Map, PathConstraints, RoadID, RoadSideID, SideOfRoad,
+    osm, CommonEndpoint, Direction, IntersectionID, LaneID, Map, PathConstraints, RoadID,
+    RoadSideID, SideOfRoad,
 };
 
 /// A block is defined by a perimeter that traces along the sides of roads. Inside the perimeter,
@@ -29,7 +30,7 @@ pub struct Block {
 }
 
 /// A sequence of roads in order, beginning and ending at the same place. No "crossings" -- tracing
-/// along this sequence should geometrically yield a simple polygon.
+/// along this sequence should geometrically yield a simple polygon, possibly with holes.
 // TODO Handle the map boundary. Sometimes this perimeter should be broken up by border
 // intersections or possibly by water/park areas.
 #[derive(Clone, Serialize, Deserialize)]
@@ -37,6 +38,11 @@ pub struct Perimeter {
     pub roads: Vec<RoadSideID>,
     /// These roads exist entirely within the perimeter
     pub interior: BTreeSet<RoadID>,
+    /// Areas fully surrounded by this perimeter, but not part of it, like an unselected block in
+    /// the middle of a neighborhood. Each hole is traced along the sides of roads facing into the
+    /// perimeter, with the same first=last invariant as `roads`.
+    #[serde(default)]
+    pub holes: Vec<Vec<RoadSideID>>,
 }
 
 impl Perimeter {
@@ -113,6 +119,7 @@ impl Perimeter {
         Ok(Perimeter {
             roads,
             interior: BTreeSet::new(),
+            holes: Vec::new(),
         })
     }
 
@@ -184,7 +191,7 @@ impl Perimeter {
     }
 
     /// Try to merge two blocks. This'll only succeed when the blocks are adjacent, but the merge
-    /// wouldn't create an interior "hole".
+    /// wouldn't create an interior "hole". Use `merge_with_holes` otherwise.
     ///
     /// Note this always modifies both perimeters, even upon failure. The caller should copy the
     /// input and only use the output upon success.
@@ -194,6 +201,10 @@ impl Perimeter {
         other: &mut Perimeter,
         debug_failures: bool,
     ) -> Result<()> {
+        if !self.holes.is_empty() || !other.holes.is_empty() {
+            bail!("The simple merge doesn't handle perimeters with holes");
+        }
+
         for reverse_to_fix_winding_order in [false, true] {
             self.undo_invariant();
             other.undo_invariant();
@@ -335,8 +346,93 @@ impl Perimeter {
         unreachable!()
     }
 
+    /// Merge two adjacent perimeters, even if the result surrounds an area belonging to neither.
+    /// Any holes the perimeters already have are kept, unless the other perimeter fills them.
+    ///
+    /// Both perimeters are cut apart where they share roads. The remaining pieces are chained
+    /// back together into rings; the one covering the most area is the new outer perimeter, and
+    /// the rest become holes.
+    fn merge_with_holes(&self, map: &Map, other: &Perimeter) -> Result<Perimeter> {
+        let roads1: HashSet<RoadID> = self.all_road_sides().map(|id| id.road).collect();
+        let roads2: HashSet<RoadID> = other.all_road_sides().map(|id| id.road).collect();
+        let common: HashSet<RoadID> = roads1.intersection(&roads2).cloned().collect();
+        if common.is_empty() {
+            bail!("No common roads");
+        }
+
+        // Chaining the pieces only works when both perimeters wind the same way. Rather than
+        // guess the winding order, try both.
+        let mut last_err = None;
+        for reverse_other in [false, true] {
+            let mut rings = self.all_rings();
+            for mut ring in other.all_rings() {
+                if reverse_other {
+                    ring.reverse();
+                }
+                rings.push(ring);
+            }
+
+            match chain_rings(map, rings, &common) {
+                Ok(rings) => {
+                    let mut interior = self.interior.clone();
+                    interior.extend(other.interior.iter().cloned());
+                    interior.extend(common);
+                    return Perimeter::from_rings(map, rings, interior);
+                }
+                Err(err) => {
+                    last_err = Some(err);
+                }
+            }
+        }
+        Err(last_err.unwrap())
+    }
+
+    /// The rings of the perimeter, outer first, without the first=last invariant.
+    fn all_rings(&self) -> Vec<Vec<RoadSideID>> {
+        let mut rings = vec![self.roads.clone()];
+        rings.extend(self.holes.iter().cloned());
+        for ring in &mut rings {
+            ring.pop();
+        }
+        rings
+    }
+
+    /// Given rings without the first=last invariant, figure out which one is on the outside.
+    fn from_rings(
+        map: &Map,
+        mut rings: Vec<Vec<RoadSideID>>,
+        interior: BTreeSet<RoadID>,
+    ) -> Result<Perimeter> {
+        if rings.is_empty() {
+            bail!("The perimeters cancelled each other out entirely");
+        }
+        for ring in &mut rings {
+            ring.push(ring[0]);
+        }
+        let mut areas = Vec::new();
+        for ring in &rings {
+            areas.push(trace_ring(map, ring)?.into_polygon().area());
+        }
+        let outer_idx = (0..rings.len())
+            .max_by(|a, b| areas[*a].partial_cmp(&areas[*b]).unwrap())
+            .unwrap();
+
+        let mut perim = Perimeter {
+            roads: rings.remove(outer_idx),
+            interior,
+            holes: rings,
+        };
+        perim.collapse_deadends();
+        perim.check_continuity(map)?;
+        Ok(perim)
+    }
+
     fn check_continuity(&self, map: &Map) -> Result<()> {
-        for pair in self.roads.windows(2) {
+        for pair in self
+            .roads
+            .windows(2)
+            .chain(self.holes.iter().flat_map(|hole| hole.windows(2)))
+        {
             let r1 = map.get_r(pair[0].road);
             let r2 = map.get_r(pair[1].road);
             if r1.common_endpoint(r2) == CommonEndpoint::None {
@@ -420,6 +516,14 @@ impl Perimeter {
                         debug = stepwise_debug;
                         continue 'INPUT;
                     }
+
+                    // Maybe the merge would surround a hole, or one of the perimeters already has
+                    // one
+                    if let Ok(merged) = other.merge_with_holes(map, &perimeter) {
+                        *other = merged;
+                        debug = stepwise_debug;
+                        continue 'INPUT;
+                    }
                 }
 
                 // No match
@@ -491,7 +595,7 @@ impl Perimeter {
     ) -> Vec<Vec<Perimeter>> {
         let mut road_to_perimeters: HashMap<RoadID, Vec<usize>> = HashMap::new();
         for (idx, perimeter) in input.iter().enumerate() {
-            for id in &perimeter.roads {
+            for id in perimeter.all_road_sides() {
                 road_to_perimeters
                     .entry(id.road)
                     .or_insert_with(Vec::new)
@@ -510,7 +614,7 @@ impl Perimeter {
                     continue;
                 }
                 visited.insert(current);
-                for id in &input[current].roads {
+                for id in input[current].all_road_sides() {
                     if predicate(id.road) {
                         queue.extend(road_to_perimeters[&id.road].clone());
                     }
@@ -552,7 +656,7 @@ impl Perimeter {
     pub fn calculate_coloring(input: &[Perimeter], num_colors: usize) -> Option<Vec<usize>> {
         let mut road_to_perimeters: HashMap<RoadID, Vec<usize>> = HashMap::new();
         for (idx, perimeter) in input.iter().enumerate() {
-            for id in &perimeter.roads {
+            for id in perimeter.all_road_sides() {
                 road_to_perimeters
                     .entry(id.road)
                     .or_insert_with(Vec::new)
@@ -566,7 +670,7 @@ impl Perimeter {
             let mut available_colors: Vec<bool> =
                 std::iter::repeat(true).take(num_colors).collect();
             // Find all neighbors
-            for id in &perimeter.roads {
+            for id in perimeter.all_road_sides() {
                 for other_idx in &road_to_perimeters[&id.road] {
                     // We assign colors in order, so any neighbor index smaller than us has been
                     // chosen
@@ -586,116 +690,16 @@ impl Perimeter {
     }
 
     pub fn to_block(self, map: &Map) -> Result<Block> {
-        // Trace along the perimeter and build the polygon
-        let mut pts: Vec<Pt2D> = Vec::new();
-        let mut first_intersection = None;
-        for pair in self.roads.windows(2) {
-            let lane1 = pair[0].get_outermost_lane(map);
-            let road1 = map.get_parent(lane1.id);
-            let lane2 = pair[1].get_outermost_lane(map);
-            // If lane1 and lane2 are the same, then it just means we found a dead-end road with
-            // exactly one lane, which is usually a footway or cycleway that legitimately is a
-            // dead-end, or connects to some other road we didn't import. We'll just trace around
-            // it like a normal dead-end road.
-            let mut pl = match pair[0].side {
-                SideOfRoad::Right => road1
-                    .center_pts
-                    .shift_right(road1.get_half_width())
-                    // TODO Remove after fixing whatever map import error allows a bad PolyLine to
-                    // wind up here at all
-                    .unwrap_or_else(|err| {
-                        warn!(
-                            "Can't get right edge of {} ({}): {}",
-                            road1.id, err, road1.orig_id
-                        );
-                        road1.center_pts.clone()
-                    }),
-                SideOfRoad::Left => road1
-                    .center_pts
-                    .shift_left(road1.get_half_width())
-                    .unwrap_or_else(|err| {
-                        warn!(
-                            "Can't get left edge of {} ({}): {}",
-                            road1.id, err, road1.orig_id
-                        );
-                        road1.center_pts.clone()
-                    }),
-            };
-            if lane1.dir == Direction::Back {
-                pl = pl.reversed();
-            }
-            let keep_lane_orientation = if pair[0].road == pair[1].road {
-                // We're doubling back at a dead-end. Always follow the orientation of the lane.
-                true
-            } else {
-                match lane1.common_endpoint(lane2) {
-                    CommonEndpoint::One(i) => i == lane1.dst_i,
-                    CommonEndpoint::Both => {
-                        // Two different roads link the same two intersections. I don't think we
-                        // can decide the order of points other than seeing which endpoint is
-                        // closest to our last point.
-                        if let Some(last) = pts.last() {
-                            last.dist_to(pl.first_pt()) < last.dist_to(pl.last_pt())
-                        } else {
-                            // The orientation doesn't matter
-                            true
-                        }
-                    }
-                    CommonEndpoint::None => bail!(
-                        "{} and {} don't share a common endpoint",
-                        lane1.id,
-                        lane2.id
-                    ),
-                }
-            };
-            if !keep_lane_orientation {
-                pl = pl.reversed();
-            }
-
-            // Before we add this road's points, try to trace along the polygon's boundary. Usually
-            // this has no effect (we'll dedupe points), but sometimes there's an extra curve.
-            //
-            // Note this logic is similar to how we find SharedSidewalkCorners. Don't rely on that
-            // existing, since the outermost lane mightn't be a sidewalk.
-            //
-            // If the ring.doubles_back(), don't bother. If we tried to trace the boundary, it
-            // usually breaks the final Ring we produce. Better to skip bad intersection polygons
-            // and still produce a reasonable looking block.
-            let prev_i = if keep_lane_orientation {
-                lane1.src_i
-            } else {
-                lane1.dst_i
-            };
-            if first_intersection.is_none() {
-                first_intersection = Some(prev_i);
-            }
-            if let Some(last_pt) = pts.last() {
-                let prev_i = map.get_i(prev_i);
-                let ring = prev_i.polygon.get_outer_ring();
-                if !ring.doubles_back() {
-                    // At dead-ends, trace around the intersection on the longer side
-                    let longer = prev_i.is_deadend_for_driving(map);
-                    if let Some(slice) = ring.get_slice_between(*last_pt, pl.first_pt(), longer) {
-                        pts.extend(slice.into_points());
-                    }
-                }
-            }
-
-            pts.extend(pl.into_points());
-        }
-        // Do the intersection boundary tracing for the last piece. We didn't know enough to do it
-        // the first time.
-        let first_intersection = map.get_i(first_intersection.unwrap());
-        let ring = first_intersection.polygon.get_outer_ring();
-        if !ring.doubles_back() {
-            let longer = first_intersection.is_deadend_for_driving(map);
-            if let Some(slice) = ring.get_slice_between(*pts.last().unwrap(), pts[0], longer) {
-                pts.extend(slice.into_points());
-            }
+        let outer = trace_ring(map, &self.roads)?;
+        let mut holes = Vec::new();
+        for hole in &self.holes {
+            holes.push(trace_ring(map, hole)?);
         }
-        pts.push(pts[0]);
-        pts.dedup();
-        let polygon = Ring::unsafe_deduping_new(pts)?.into_polygon();
+        let polygon = if holes.is_empty() {
+            outer.into_polygon()
+        } else {
+            Polygon::with_holes(outer, holes)
+        };
         // TODO To debug anyway, we could plumb through a Tessellation, but there's pretty much
         // always a root problem in the map geometry that should be properly fixed.
 
@@ -710,12 +714,17 @@ impl Perimeter {
         other
             .roads
             .iter()
-            .all(|id| self.interior.contains(&id.road) || self.roads.contains(id))
+            .all(|id| self.interior.contains(&id.road) || self.all_road_sides().any(|x| x == id))
+    }
+
+    /// All of the road sides along the perimeter, including those around holes.
+    pub fn all_road_sides(&self) -> impl Iterator<Item = &RoadSideID> {
+        self.roads.iter().chain(self.holes.iter().flatten())
     }
 
     /// Shrinks or expands the perimeter by tracing the opposite side of the road.
     pub fn flip_side_of_road(mut self) -> Self {
-        for road_side in &mut self.roads {
+        for road_side in self.roads.iter_mut().chain(self.holes.iter_mut().flatten()) {
             *road_side = road_side.other_side();
         }
         self
@@ -775,6 +784,206 @@ impl fmt::Debug for Perimeter {
         for id in &self.roads {
             writeln!(f, "- {:?} of {}", id.side, id.road)?;
         }
+        for (idx, hole) in self.holes.iter().enumerate() {
+            writeln!(f, "Hole {}:", idx)?;
+            for id in hole {
+                writeln!(f, "- {:?} of {}", id.side, id.road)?;
+            }
+        }
         Ok(())
     }
 }
+
+/// Trace along one ring of a perimeter, with the first=last invariant, and build its outline.
+fn trace_ring(map: &Map, roads: &[RoadSideID]) -> Result<Ring> {
+    let mut pts: Vec<Pt2D> = Vec::new();
+    let mut first_intersection = None;
+    for pair in roads.windows(2) {
+        let lane1 = pair[0].get_outermost_lane(map);
+        let road1 = map.get_parent(lane1.id);
+        let lane2 = pair[1].get_outermost_lane(map);
+        // If lane1 and lane2 are the same, then it just means we found a dead-end road with
+        // exactly one lane, which is usually a footway or cycleway that legitimately is a
+        // dead-end, or connects to some other road we didn't import. We'll just trace around
+        // it like a normal dead-end road.
+        let mut pl = match pair[0].side {
+            SideOfRoad::Right => road1
+                .center_pts
+                .shift_right(road1.get_half_width())
+                // TODO Remove after fixing whatever map import error allows a bad PolyLine to
+                // wind up here at all
+                .unwrap_or_else(|err| {
+                    warn!(
+                        "Can't get right edge of {} ({}): {}",
+                        road1.id, err, road1.orig_id
+                    );
+                    road1.center_pts.clone()
+                }),
+            SideOfRoad::Left => road1
+                .center_pts
+                .shift_left(road1.get_half_width())
+                .unwrap_or_else(|err| {
+                    warn!(
+                        "Can't get left edge of {} ({}): {}",
+                        road1.id, err, road1.orig_id
+                    );
+                    road1.center_pts.clone()
+                }),
+        };
+        if lane1.dir == Direction::Back {
+            pl = pl.reversed();
+        }
+        let keep_lane_orientation = if pair[0].road == pair[1].road {
+            // We're doubling back at a dead-end. Always follow the orientation of the lane.
+            true
+        } else {
+            match lane1.common_endpoint(lane2) {
+                CommonEndpoint::One(i) => i == lane1.dst_i,
+                CommonEndpoint::Both => {
+                    // Two different roads link the same two intersections. I don't think we
+                    // can decide the order of points other than seeing which endpoint is
+                    // closest to our last point.
+                    if let Some(last) = pts.last() {
+                        last.dist_to(pl.first_pt()) < last.dist_to(pl.last_pt())
+                    } else {
+                        // The orientation doesn't matter
+                        true
+                    }
+                }
+                CommonEndpoint::None => bail!(
+                    "{} and {} don't share a common endpoint",
+                    lane1.id,
+                    lane2.id
+                ),
+            }
+        };
+        if !keep_lane_orientation {
+            pl = pl.reversed();
+        }
+
+        // Before we add this road's points, try to trace along the polygon's boundary. Usually
+        // this has no effect (we'll dedupe points), but sometimes there's an extra curve.
+        //
+        // Note this logic is similar to how we find SharedSidewalkCorners. Don't rely on that
+        // existing, since the outermost lane mightn't be a sidewalk.
+        //
+        // If the ring.doubles_back(), don't bother. If we tried to trace the boundary, it
+        // usually breaks the final Ring we produce. Better to skip bad intersection polygons
+        // and still produce a reasonable looking block.
+        let prev_i = if keep_lane_orientation {
+            lane1.src_i
+        } else {
+            lane1.dst_i
+        };
+        if first_intersection.is_none() {
+            first_intersection = Some(prev_i);
+        }
+        if let Some(last_pt) = pts.last() {
+            let prev_i = map.get_i(prev_i);
+            let ring = prev_i.polygon.get_outer_ring();
+            if !ring.doubles_back() {
+                // At dead-ends, trace around the intersection on the longer side
+                let longer = prev_i.is_deadend_for_driving(map);
+                if let Some(slice) = ring.get_slice_between(*last_pt, pl.first_pt(), longer) {
+                    pts.extend(slice.into_points());
+                }
+            }
+        }
+
+        pts.extend(pl.into_points());
+    }
+    // Do the intersection boundary tracing for the last piece. We didn't know enough to do it
+    // the first time.
+    let first_intersection = map.get_i(first_intersection.unwrap());
+    let ring = first_intersection.polygon.get_outer_ring();
+    if !ring.doubles_back() {
+        let longer = first_intersection.is_deadend_for_driving(map);
+        if let Some(slice) = ring.get_slice_between(*pts.last().unwrap(), pts[0], longer) {
+            pts.extend(slice.into_points());
+        }
+    }
+    pts.push(pts[0]);
+    pts.dedup();
+    Ring::unsafe_deduping_new(pts)
+}
+
+/// A stretch of a ring between two roads shared with another perimeter
+struct Piece {
+    roads: Vec<RoadSideID>,
+    start: IntersectionID,
+    end: IntersectionID,
+}
+
+/// Cut rings apart wherever they use a common road, then chain the remaining pieces back into
+/// rings. The input and output rings don't have the first=last invariant.
+fn chain_rings(
+    map: &Map,
+    rings: Vec<Vec<RoadSideID>>,
+    common: &HashSet<RoadID>,
+) -> Result<Vec<Vec<RoadSideID>>> {
+    let mut results = Vec::new();
+    let mut pieces = Vec::new();
+    for mut ring in rings {
+        if ring.iter().all(|id| common.contains(&id.road)) {
+            // Completely filled in by the other perimeter
+            continue;
+        }
+        if !ring.iter().any(|id| common.contains(&id.road)) {
+            // Nothing to cut
+            results.push(ring);
+            continue;
+        }
+
+        // Rotate so the ring starts just after a common road
+        while common.contains(&ring[0].road) || !common.contains(&ring.last().unwrap().road) {
+            ring.rotate_left(1);
+        }
+        let mut idx = 0;
+        while idx < ring.len() {
+            if common.contains(&ring[idx].road) {
+                idx += 1;
+                continue;
+            }
+            let before = wraparound_get(&ring, idx as isize - 1).road;
+            let mut roads = Vec::new();
+            while idx < ring.len() && !common.contains(&ring[idx].road) {
+                roads.push(ring[idx]);
+                idx += 1;
+            }
+            let after = wraparound_get(&ring, idx as isize).road;
+            pieces.push(Piece {
+                start: shared_intersection(map, before, roads[0].road)?,
+                end: shared_intersection(map, roads.last().unwrap().road, after)?,
+                roads,
+            });
+        }
+    }
+
+    let mut used = vec![false; pieces.len()];
+    for first in 0..pieces.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let mut ring = pieces[first].roads.clone();
+        let mut end = pieces[first].end;
+        while end != pieces[first].start {
+            let next = (0..pieces.len())
+                .find(|idx| !used[*idx] && pieces[*idx].start == end)
+                .ok_or_else(|| anyhow!("Nothing continues the perimeter from {}", end))?;
+            used[next] = true;
+            ring.extend(pieces[next].roads.iter().cloned());
+            end = pieces[next].end;
+        }
+        results.push(ring);
+    }
+    Ok(results)
+}
+
+fn shared_intersection(map: &Map, r1: RoadID, r2: RoadID) -> Result<IntersectionID> {
+    match map.get_r(r1).common_endpoint(map.get_r(r2)) {
+        CommonEndpoint::One(i) => Ok(i),
+        CommonEndpoint::Both => bail!("{} and {} share both endpoints", r1, r2),
+        CommonEndpoint::None => bail!("{} and {} don't share a common endpoint", r1, r2),
+    }
+}
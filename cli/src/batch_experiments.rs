@@ -0,0 +1,211 @@
+//! Runs many simulations in parallel worker processes and collates the results, so parameter
+//! sweeps don't need hand-written shell scripts.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use anyhow::{bail, Result};
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use abstutil::{prettyprint_usize, Timer};
+use geom::Duration;
+use map_model::{Map, MapEdits};
+use sim::{AlertHandler, Sim, SimOptions};
+use synthpop::{Scenario, TripMode};
+
+/// One combination of inputs to simulate
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Experiment {
+    /// The path to a scenario file. This determines the map.
+    pub scenario: String,
+    /// The path to map edits to apply before simulating, if any
+    #[serde(default)]
+    pub edits: Option<String>,
+    /// Different seeds affect results, but the same seed always produces the same results.
+    #[serde(default = "default_rng_seed")]
+    pub rng_seed: u64,
+    /// How long to simulate
+    #[serde(default = "default_hours")]
+    pub hours: usize,
+}
+
+fn default_rng_seed() -> u64 {
+    sim::SimFlags::RNG_SEED
+}
+
+fn default_hours() -> usize {
+    24
+}
+
+/// The standard metrics collected from one experiment. Everything except the wall-clock time is
+/// deterministic.
+#[derive(Serialize, Deserialize)]
+pub struct ExperimentResults {
+    pub map: String,
+    pub scenario: String,
+    pub edits: String,
+    pub rng_seed: u64,
+    pub finished_trips: usize,
+    pub cancelled_trips: usize,
+    pub unfinished_trips: usize,
+    // Use f64 seconds, since a serialized Duration has a low cap.
+    pub total_trip_duration_seconds: f64,
+    pub walk_trips: usize,
+    pub bike_trips: usize,
+    pub transit_trips: usize,
+    pub drive_trips: usize,
+    pub wall_clock_seconds: f64,
+}
+
+/// Run all experiments listed in a JSON file, using up to `num_workers` processes at a time. The
+/// results are written to `{output}.json` and `{output}.csv`, in the same order as the input,
+/// regardless of which worker finishes first.
+pub fn run(input: String, output: String, num_workers: usize) -> Result<()> {
+    let experiments: Vec<Experiment> = abstio::maybe_read_json(input, &mut Timer::throwaway())?;
+    let results = run_all(&experiments, &output, num_workers)?;
+
+    abstio::write_json(format!("{}.json", output), &results);
+    write_csv(&results, format!("{}.csv", output))?;
+    println!("Wrote {}.json and {}.csv", output, output);
+    Ok(())
+}
+
+/// Simulate experiments using up to `num_workers` processes at a time, returning results in the
+/// same order as the input. Workers write intermediate files to `{output}_workers`.
+pub fn run_all(
+    experiments: &[Experiment],
+    output: &str,
+    num_workers: usize,
+) -> Result<Vec<ExperimentResults>> {
+    let exe = std::env::current_exe()?;
+    let tmp_dir = format!("{}_workers", output);
+    fs_err::create_dir_all(&tmp_dir)?;
+
+    let next = AtomicUsize::new(0);
+    let failures = Mutex::new(Vec::new());
+    std::thread::scope(|s| {
+        for _ in 0..num_workers.max(1) {
+            s.spawn(|| loop {
+                let idx = next.fetch_add(1, Ordering::SeqCst);
+                if idx >= experiments.len() {
+                    break;
+                }
+                info!(
+                    "Starting experiment {}/{}",
+                    prettyprint_usize(idx + 1),
+                    prettyprint_usize(experiments.len())
+                );
+                let status = std::process::Command::new(&exe)
+                    .arg("run-experiment")
+                    .arg(format!(
+                        "--experiment={}",
+                        abstutil::to_json_terse(&experiments[idx])
+                    ))
+                    .arg(format!("--output={}/{}.json", tmp_dir, idx))
+                    .status();
+                match status {
+                    Ok(status) if status.success() => {}
+                    Ok(status) => failures
+                        .lock()
+                        .unwrap()
+                        .push(format!("experiment {} failed: {}", idx, status)),
+                    Err(err) => failures
+                        .lock()
+                        .unwrap()
+                        .push(format!("couldn't start experiment {}: {}", idx, err)),
+                }
+            });
+        }
+    });
+    let failures = failures.into_inner().unwrap();
+    if !failures.is_empty() {
+        bail!("{}", failures.join("\n"));
+    }
+
+    let mut results = Vec::new();
+    for idx in 0..experiments.len() {
+        let path = format!("{}/{}.json", tmp_dir, idx);
+        let result: ExperimentResults = abstio::maybe_read_json(path, &mut Timer::throwaway())?;
+        results.push(result);
+    }
+    fs_err::remove_dir_all(&tmp_dir)?;
+    Ok(results)
+}
+
+pub fn write_csv<T: Serialize>(rows: &[T], path: String) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(fs_err::File::create(path)?);
+    for row in rows {
+        writer.serialize(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Simulate a single experiment. This is called by the worker processes.
+pub fn run_one(experiment: String, output: String) -> Result<()> {
+    let experiment: Experiment = abstutil::from_json(&experiment.into_bytes())?;
+    let mut timer = Timer::new(format!("run experiment {}", experiment.scenario));
+    let started = instant::Instant::now();
+
+    let scenario: Scenario = abstio::read_object(experiment.scenario.clone(), &mut timer)?;
+    let mut map = Map::load_synchronously(scenario.map_name.path(), &mut timer);
+    let edits_name = if let Some(path) = experiment.edits.clone() {
+        let edits = MapEdits::load_from_file(&map, path, &mut timer)?;
+        let name = edits.edits_name.clone();
+        map.must_apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+        name
+    } else {
+        "none".to_string()
+    };
+
+    let mut opts = SimOptions::new("batch");
+    opts.alerts = AlertHandler::Silence;
+    let mut sim = Sim::new(&map, opts);
+    let mut rng = XorShiftRng::seed_from_u64(experiment.rng_seed);
+    sim.instantiate(&scenario, &map, &mut rng, &mut timer);
+    sim.timed_step(
+        &map,
+        Duration::hours(experiment.hours),
+        &mut None,
+        &mut timer,
+    );
+
+    let mut results = ExperimentResults {
+        map: scenario.map_name.describe(),
+        scenario: scenario.scenario_name.clone(),
+        edits: edits_name,
+        rng_seed: experiment.rng_seed,
+        finished_trips: 0,
+        cancelled_trips: 0,
+        unfinished_trips: 0,
+        total_trip_duration_seconds: 0.0,
+        walk_trips: 0,
+        bike_trips: 0,
+        transit_trips: 0,
+        drive_trips: 0,
+        wall_clock_seconds: 0.0,
+    };
+    for (_, _, mode, maybe_duration) in &sim.get_analytics().finished_trips {
+        if let Some(dt) = maybe_duration {
+            results.finished_trips += 1;
+            results.total_trip_duration_seconds += dt.inner_seconds();
+            match mode {
+                TripMode::Walk => results.walk_trips += 1,
+                TripMode::Bike => results.bike_trips += 1,
+                TripMode::Transit => results.transit_trips += 1,
+                TripMode::Drive => results.drive_trips += 1,
+            }
+        } else {
+            results.cancelled_trips += 1;
+        }
+    }
+    results.unfinished_trips =
+        sim.all_trip_info().len() - results.finished_trips - results.cancelled_trips;
+    results.wall_clock_seconds = Duration::realtime_elapsed(started).inner_seconds();
+
+    abstio::write_json(output, &results);
+    Ok(())
+}
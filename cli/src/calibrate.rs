@@ -0,0 +1,330 @@
+//! Compare simulated traffic volumes against observed counts, and optionally scale a scenario's
+//! demand to better match them.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{bail, Result};
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use abstutil::{prettyprint_usize, Counter, Timer};
+use geom::{Duration, Polygon};
+use map_model::{osm, Map, PathStepV2, RoadID};
+use sim::{AgentType, AlertHandler, Sim, SimFlags, SimOptions};
+use synthpop::{Scenario, TripEndpoint, TripMode};
+
+/// A common rule of thumb is for 85% of counts to have a GEH below 5
+const GOOD_GEH: f64 = 5.0;
+/// Don't scale any zone's demand by more than this factor (or its inverse) in one round, since a
+/// few counts can easily mislead
+const MAX_SCALE: f64 = 2.0;
+
+#[derive(Deserialize)]
+struct ObservedCount {
+    osm_way_id: i64,
+    /// 0 is midnight to 1am
+    hour: usize,
+    count: f64,
+}
+
+#[derive(Serialize)]
+struct Comparison {
+    osm_way_id: i64,
+    hour: usize,
+    observed: f64,
+    simulated: usize,
+    geh: f64,
+}
+
+/// Simulate a scenario, then compare hourly motor vehicle volumes against observed counts. The
+/// CSV file needs `osm_way_id`, `hour`, and `count` columns. Results per count are written to
+/// `output`, and a summary of GEH statistics is printed.
+///
+/// If `zones` is a GeoJSON file of polygons, the demand from each zone is scaled by how much the
+/// roads its trips use are over- or under-counted, and a new scenario is saved. Zones are named
+/// by their `name` property.
+pub fn run(
+    scenario: String,
+    counts: String,
+    zones: Option<String>,
+    hours: usize,
+    output: String,
+) -> Result<()> {
+    let mut timer = Timer::new("calibrate scenario against counts");
+    let scenario: Scenario = abstio::read_object(scenario, &mut timer)?;
+    let map = Map::load_synchronously(scenario.map_name.path(), &mut timer);
+
+    let mut observed: BTreeMap<(osm::WayID, usize), f64> = BTreeMap::new();
+    for rec in csv::Reader::from_reader(fs_err::File::open(counts)?).deserialize() {
+        let rec: ObservedCount = rec?;
+        *observed
+            .entry((osm::WayID(rec.osm_way_id), rec.hour))
+            .or_insert(0.0) += rec.count;
+    }
+
+    let simulated = simulate(&map, &scenario, hours, &mut timer);
+
+    let mut comparisons = Vec::new();
+    for ((way, hour), observed) in &observed {
+        let simulated = match simulated.get(&(*way, *hour)) {
+            Some(cnt) => *cnt,
+            None => {
+                if *hour >= hours {
+                    continue;
+                }
+                0
+            }
+        };
+        comparisons.push(Comparison {
+            osm_way_id: way.0,
+            hour: *hour,
+            observed: *observed,
+            simulated,
+            geh: geh(*observed, simulated as f64),
+        });
+    }
+    if comparisons.is_empty() {
+        bail!("None of the counts match this map and time range");
+    }
+
+    let num_good = comparisons.iter().filter(|c| c.geh < GOOD_GEH).count();
+    let total_observed: f64 = comparisons.iter().map(|c| c.observed).sum();
+    let total_simulated: usize = comparisons.iter().map(|c| c.simulated).sum();
+    println!(
+        "{} counts compared. {} ({:.1}%) have GEH < {}. Mean GEH is {:.2}",
+        prettyprint_usize(comparisons.len()),
+        prettyprint_usize(num_good),
+        100.0 * (num_good as f64) / (comparisons.len() as f64),
+        GOOD_GEH,
+        comparisons.iter().map(|c| c.geh).sum::<f64>() / (comparisons.len() as f64)
+    );
+    println!(
+        "{} vehicles observed, {} simulated",
+        prettyprint_usize(total_observed.round() as usize),
+        prettyprint_usize(total_simulated)
+    );
+
+    let mut writer = csv::Writer::from_writer(fs_err::File::create(&output)?);
+    for c in &comparisons {
+        writer.serialize(c)?;
+    }
+    writer.flush()?;
+    println!("Wrote {}", output);
+
+    if let Some(zones) = zones {
+        let zones = load_zones(&map, zones)?;
+        // How much should traffic on each way be scaled, over the whole day?
+        let mut ratio_per_way: HashMap<osm::WayID, (f64, f64)> = HashMap::new();
+        for c in &comparisons {
+            let pair = ratio_per_way
+                .entry(osm::WayID(c.osm_way_id))
+                .or_insert((0.0, 0.0));
+            pair.0 += c.observed;
+            pair.1 += c.simulated as f64;
+        }
+        let scale = scale_per_zone(&map, &scenario, &zones, &ratio_per_way, &mut timer);
+        for (zone, factor) in &scale {
+            println!("Scaling demand from {} by {:.2}", zone, factor);
+        }
+        let scaled = scale_scenario(&map, scenario, &zones, &scale);
+        scaled.save();
+    }
+
+    Ok(())
+}
+
+/// The GEH statistic compares hourly volumes, tolerating larger absolute differences for busier
+/// roads.
+fn geh(observed: f64, simulated: f64) -> f64 {
+    if observed + simulated == 0.0 {
+        return 0.0;
+    }
+    (2.0 * (simulated - observed).powi(2) / (simulated + observed)).sqrt()
+}
+
+/// Returns the hourly motor vehicle volume per OSM way. When a way is split into multiple roads,
+/// the busiest is used.
+fn simulate(
+    map: &Map,
+    scenario: &Scenario,
+    hours: usize,
+    timer: &mut Timer,
+) -> BTreeMap<(osm::WayID, usize), usize> {
+    let mut opts = SimOptions::new("calibration");
+    opts.alerts = AlertHandler::Silence;
+    let mut sim = Sim::new(map, opts);
+    let mut rng = XorShiftRng::seed_from_u64(SimFlags::RNG_SEED);
+    sim.instantiate(scenario, map, &mut rng, timer);
+    sim.timed_step(map, Duration::hours(hours), &mut None, timer);
+
+    let mut per_road: BTreeMap<(RoadID, usize), usize> = BTreeMap::new();
+    for ((r, agent_type, hour), cnt) in &sim.get_analytics().road_thruput.counts {
+        if matches!(agent_type, AgentType::Car | AgentType::Bus) {
+            *per_road.entry((*r, *hour)).or_insert(0) += cnt;
+        }
+    }
+    let mut results: BTreeMap<(osm::WayID, usize), usize> = BTreeMap::new();
+    for ((r, hour), cnt) in per_road {
+        let max = results
+            .entry((map.get_r(r).orig_id.osm_way_id, hour))
+            .or_insert(0);
+        *max = (*max).max(cnt);
+    }
+    results
+}
+
+fn load_zones(map: &Map, path: String) -> Result<Vec<(String, Polygon)>> {
+    let mut zones = Vec::new();
+    let require_in_bounds = false;
+    for (idx, (polygon, tags)) in Polygon::from_geojson_bytes(
+        &abstio::slurp_file(path)?,
+        map.get_gps_bounds(),
+        require_in_bounds,
+    )?
+    .into_iter()
+    .enumerate()
+    {
+        let name = tags
+            .get("name")
+            .cloned()
+            .unwrap_or_else(|| format!("zone {}", idx));
+        zones.push((name, polygon));
+    }
+    Ok(zones)
+}
+
+fn find_zone(map: &Map, endpt: TripEndpoint, zones: &[(String, Polygon)]) -> Option<String> {
+    let pt = endpt.pt(map);
+    zones
+        .iter()
+        .find(|(_, polygon)| polygon.contains_pt(pt))
+        .map(|(name, _)| name.clone())
+}
+
+/// Route every driving trip, and weigh the observed/simulated ratio of the counted roads it uses
+/// by the origin zone.
+fn scale_per_zone(
+    map: &Map,
+    scenario: &Scenario,
+    zones: &[(String, Polygon)],
+    ratio_per_way: &HashMap<osm::WayID, (f64, f64)>,
+    timer: &mut Timer,
+) -> BTreeMap<String, f64> {
+    // Per zone, the sum of weighted ratios and the number of counted roads used
+    let mut per_zone: BTreeMap<String, (f64, usize)> = BTreeMap::new();
+    timer.start_iter("route driving trips", scenario.people.len());
+    for person in &scenario.people {
+        timer.next();
+        for trip in &person.trips {
+            if trip.mode != TripMode::Drive {
+                continue;
+            }
+            let zone = match find_zone(map, trip.origin, zones) {
+                Some(zone) => zone,
+                None => continue,
+            };
+            let path = match TripEndpoint::path_req(trip.origin, trip.destination, trip.mode, map)
+                .and_then(|req| map.pathfind_v2(req).ok())
+            {
+                Some(path) => path,
+                None => continue,
+            };
+            let mut used: Counter<RoadID> = Counter::new();
+            for step in path.get_steps() {
+                if let PathStepV2::Along(dr) | PathStepV2::Contraflow(dr) = step {
+                    used.inc(dr.road);
+                }
+            }
+            for r in used.borrow().keys() {
+                if let Some((observed, simulated)) =
+                    ratio_per_way.get(&map.get_r(*r).orig_id.osm_way_id)
+                {
+                    // Avoid dividing by zero; a road with no simulated traffic is very
+                    // undercounted
+                    let ratio = (observed / simulated.max(1.0)).clamp(1.0 / MAX_SCALE, MAX_SCALE);
+                    let entry = per_zone.entry(zone.clone()).or_insert((0.0, 0));
+                    entry.0 += ratio;
+                    entry.1 += 1;
+                }
+            }
+        }
+    }
+
+    per_zone
+        .into_iter()
+        .map(|(zone, (sum, n))| (zone, sum / (n as f64)))
+        .collect()
+}
+
+/// Duplicate or remove people, depending on their first trip's origin zone.
+fn scale_scenario(
+    map: &Map,
+    mut scenario: Scenario,
+    zones: &[(String, Polygon)],
+    scale: &BTreeMap<String, f64>,
+) -> Scenario {
+    let mut rng = XorShiftRng::seed_from_u64(SimFlags::RNG_SEED);
+    let orig_num = scenario.people.len();
+    let mut people = Vec::new();
+    for person in scenario.people.drain(..) {
+        let factor = person
+            .trips
+            .get(0)
+            .and_then(|trip| find_zone(map, trip.origin, zones))
+            .and_then(|zone| scale.get(&zone).cloned())
+            .unwrap_or(1.0);
+        // A factor of 1.3 means everyone is kept, and 30% are duplicated
+        let mut copies = factor.floor() as usize;
+        if rng.gen_bool(factor.fract()) {
+            copies += 1;
+        }
+        for _ in 0..copies {
+            people.push(person.clone());
+        }
+    }
+    scenario.people = people;
+    scenario.scenario_name = format!("{}_calibrated", scenario.scenario_name);
+    println!(
+        "Scaled {} people to {} in {}",
+        prettyprint_usize(orig_num),
+        prettyprint_usize(scenario.people.len()),
+        scenario.scenario_name
+    );
+    scenario
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_geh() {
+        // sqrt(2 * 56^2 / 200) = sqrt(31.36)
+        assert_close(geh(72.0, 128.0), 5.6);
+        // The statistic is symmetric
+        assert_close(geh(128.0, 72.0), 5.6);
+        // sqrt(2 * 100^2 / 200) = sqrt(100)
+        assert_close(geh(150.0, 50.0), 10.0);
+        assert_close(geh(300.0, 300.0), 0.0);
+    }
+
+    #[test]
+    fn test_geh_zero_counts() {
+        // Nothing observed or simulated isn't an error
+        assert_close(geh(0.0, 0.0), 0.0);
+        // sqrt(2 * 50^2 / 50) = sqrt(100)
+        assert_close(geh(50.0, 0.0), 10.0);
+        // sqrt(2 * 8^2 / 8) = sqrt(16)
+        assert_close(geh(0.0, 8.0), 4.0);
+    }
+}
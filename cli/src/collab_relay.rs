@@ -0,0 +1,319 @@
+//! A small server that lets several copies of the game or LTN tool edit the same map at once. It
+//! doesn't understand map edits at all; clients send it the latest version of each object they
+//! change, and it hands those out to everybody else in the same session.
+//!
+//! Conflicts are handled with a lock per object. Changing an object or opening it in an editor
+//! claims it, and each client holds at most one lock at a time. Changes to an object somebody else
+//! holds are rejected. Locks are released when their holder claims something else or stops
+//! polling. Once every client in a session has stopped polling for a while, the session and all of
+//! its changes are forgotten.
+//!
+//! Every endpoint takes a JSON body with a POST:
+//!
+//! - `/poll`: `{"session", "client", "since"}`. Returns everything changed after sequence number
+//!   `since`, who holds which locks, and who's connected.
+//! - `/publish`: `{"session", "client", "changes": [{"object", "payload"}]}`. A null payload means
+//!   the object was reverted. Returns the objects rejected, and who holds them.
+//! - `/claim`: `{"session", "client", "object"}`. Returns the holder of the lock afterwards.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use hyper::{Body, Request, Response, Server, StatusCode};
+use serde::{Deserialize, Serialize};
+
+/// Clients poll every few seconds, so one that's been quiet this long has left
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(30);
+/// When the last client leaves a session, keep it around this long, so that everybody briefly
+/// dropping off, like when a laptop goes to sleep, doesn't lose the session's edits
+const SESSION_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+pub async fn run(port: u16) -> Result<()> {
+    let relay = Arc::new(Mutex::new(BTreeMap::new()));
+    let addr = std::net::SocketAddr::from(([0, 0, 0, 0], port));
+    info!("Relaying collaborative edits on http://{}", addr);
+    Server::bind(&addr)
+        .serve(hyper::service::make_service_fn(move |_| {
+            let relay = relay.clone();
+            async move {
+                Ok::<_, hyper::Error>(hyper::service::service_fn(move |req| {
+                    serve_req(relay.clone(), req)
+                }))
+            }
+        }))
+        .await?;
+    Ok(())
+}
+
+async fn serve_req(
+    relay: Arc<Mutex<BTreeMap<String, Session>>>,
+    req: Request<Body>,
+) -> Result<Response<Body>, hyper::Error> {
+    let path = req.uri().path().to_string();
+    let body = hyper::body::to_bytes(req).await?.to_vec();
+    let result = handle(&mut relay.lock().unwrap(), &path, &body, Instant::now());
+    Ok(match result {
+        Ok(resp) => Response::builder()
+            .header("Content-Type", "application/json")
+            // The web version of the game runs on a different origin
+            .header("Access-Control-Allow-Origin", "*")
+            .body(Body::from(resp))
+            .unwrap(),
+        Err(err) => {
+            error!("{}: {}", path, err);
+            Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .header("Access-Control-Allow-Origin", "*")
+                .body(Body::from(format!("Bad request to {}: {}", path, err)))
+                .unwrap()
+        }
+    })
+}
+
+fn handle(
+    sessions: &mut BTreeMap<String, Session>,
+    path: &str,
+    body: &[u8],
+    now: Instant,
+) -> Result<String> {
+    sessions.retain(|_, session| !session.is_idle(now));
+    match path {
+        "/poll" => {
+            let req: PollRequest = abstutil::from_json(body)?;
+            let session = Session::get(sessions, &req.session, &req.client, now);
+            Ok(abstutil::to_json(&session.poll(req.since)))
+        }
+        "/publish" => {
+            let req: PublishRequest = abstutil::from_json(body)?;
+            let session = Session::get(sessions, &req.session, &req.client, now);
+            Ok(abstutil::to_json(
+                &session.publish(&req.client, req.changes),
+            ))
+        }
+        "/claim" => {
+            let req: ClaimRequest = abstutil::from_json(body)?;
+            let session = Session::get(sessions, &req.session, &req.client, now);
+            Ok(abstutil::to_json(&ClaimResponse {
+                holder: session.claim(&req.client, req.object),
+            }))
+        }
+        _ => anyhow::bail!("unknown endpoint"),
+    }
+}
+
+#[derive(Default)]
+struct Session {
+    seq: usize,
+    /// The latest change to every object
+    objects: BTreeMap<String, Update>,
+    /// Object to the client holding it
+    locks: BTreeMap<String, String>,
+    last_seen: BTreeMap<String, Instant>,
+    /// When any client last made a request
+    last_active: Option<Instant>,
+}
+
+impl Session {
+    /// Finds or starts a session, and notes that a client is still around.
+    fn get<'a>(
+        sessions: &'a mut BTreeMap<String, Session>,
+        name: &str,
+        client: &str,
+        now: Instant,
+    ) -> &'a mut Session {
+        let session = sessions.entry(name.to_string()).or_default();
+        session.last_active = Some(now);
+        session.last_seen.insert(client.to_string(), now);
+        session
+            .last_seen
+            .retain(|_, seen| now.duration_since(*seen) < CLIENT_TIMEOUT);
+        let last_seen = &session.last_seen;
+        session
+            .locks
+            .retain(|_, holder| last_seen.contains_key(holder));
+        session
+    }
+
+    fn is_idle(&self, now: Instant) -> bool {
+        self.last_active
+            .map(|t| now.duration_since(t) >= CLIENT_TIMEOUT + SESSION_TIMEOUT)
+            .unwrap_or(true)
+    }
+
+    fn poll(&self, since: usize) -> PollResponse {
+        let mut updates: Vec<Update> = self
+            .objects
+            .values()
+            .filter(|update| update.seq > since)
+            .cloned()
+            .collect();
+        updates.sort_by_key(|update| update.seq);
+        PollResponse {
+            seq: self.seq,
+            updates,
+            locks: self.locks.clone(),
+            clients: self.last_seen.keys().cloned().collect(),
+        }
+    }
+
+    fn publish(&mut self, client: &str, changes: Vec<Change>) -> PublishResponse {
+        let mut rejected = BTreeMap::new();
+        for change in changes {
+            let holder = self.claim(client, change.object.clone());
+            if holder != client {
+                rejected.insert(change.object, holder);
+                continue;
+            }
+            self.seq += 1;
+            self.objects.insert(
+                change.object.clone(),
+                Update {
+                    seq: self.seq,
+                    client: client.to_string(),
+                    object: change.object,
+                    payload: change.payload,
+                },
+            );
+        }
+        PublishResponse { rejected }
+    }
+
+    /// Returns whoever holds the lock afterwards.
+    fn claim(&mut self, client: &str, object: String) -> String {
+        if let Some(holder) = self.locks.get(&object) {
+            if holder != client {
+                return holder.clone();
+            }
+        }
+        self.locks.retain(|_, holder| holder != client);
+        self.locks.insert(object, client.to_string());
+        client.to_string()
+    }
+}
+
+#[derive(Deserialize)]
+struct PollRequest {
+    session: String,
+    client: String,
+    since: usize,
+}
+
+#[derive(Serialize)]
+struct PollResponse {
+    seq: usize,
+    updates: Vec<Update>,
+    locks: BTreeMap<String, String>,
+    clients: Vec<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct Update {
+    seq: usize,
+    client: String,
+    object: String,
+    payload: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PublishRequest {
+    session: String,
+    client: String,
+    changes: Vec<Change>,
+}
+
+#[derive(Deserialize)]
+struct Change {
+    object: String,
+    payload: Option<String>,
+}
+
+#[derive(Serialize)]
+struct PublishResponse {
+    rejected: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct ClaimRequest {
+    session: String,
+    client: String,
+    object: String,
+}
+
+#[derive(Serialize)]
+struct ClaimResponse {
+    holder: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(object: &str) -> Vec<Change> {
+        vec![Change {
+            object: object.to_string(),
+            payload: Some("{}".to_string()),
+        }]
+    }
+
+    #[test]
+    fn test_locks() {
+        let mut sessions = BTreeMap::new();
+        let start = Instant::now();
+
+        let session = Session::get(&mut sessions, "workshop", "alice", start);
+        assert!(session
+            .publish("alice", change("road 1"))
+            .rejected
+            .is_empty());
+        let session = Session::get(&mut sessions, "workshop", "bob", start);
+        // Alice holds road 1, so Bob can't change it
+        assert_eq!(
+            session.publish("bob", change("road 1")).rejected["road 1"],
+            "alice"
+        );
+        assert!(session.publish("bob", change("road 2")).rejected.is_empty());
+        assert_eq!(session.poll(0).updates.len(), 2);
+        assert_eq!(session.poll(1).updates.len(), 1);
+
+        // Alice moves on, releasing road 1
+        assert_eq!(session.claim("alice", "road 3".to_string()), "alice");
+        assert_eq!(session.claim("bob", "road 1".to_string()), "bob");
+
+        // Bob stops polling, so his lock expires
+        let later = start + CLIENT_TIMEOUT + Duration::from_secs(1);
+        let session = Session::get(&mut sessions, "workshop", "alice", later);
+        assert_eq!(session.claim("alice", "road 1".to_string()), "alice");
+    }
+
+    #[test]
+    fn test_evict_idle_sessions() {
+        let mut sessions = BTreeMap::new();
+        let start = Instant::now();
+        let poll = |sessions: &mut BTreeMap<String, Session>, name: &str, now: Instant| {
+            let body = format!(
+                r#"{{"session": "{}", "client": "alice", "since": 0}}"#,
+                name
+            );
+            handle(sessions, "/poll", body.as_bytes(), now).unwrap();
+        };
+
+        poll(&mut sessions, "workshop", start);
+        Session::get(&mut sessions, "workshop", "alice", start).publish("alice", change("road 1"));
+        poll(&mut sessions, "other", start);
+
+        // Alice has timed out of both sessions, but they're kept for a while in case she returns
+        let later = start + CLIENT_TIMEOUT + Duration::from_secs(60);
+        poll(&mut sessions, "other", later);
+        assert_eq!(sessions["workshop"].objects.len(), 1);
+
+        // Nobody has come back to the workshop, so it's gone
+        let much_later = later + SESSION_TIMEOUT;
+        poll(&mut sessions, "other", much_later);
+        assert_eq!(
+            sessions.keys().cloned().collect::<Vec<_>>(),
+            vec!["other".to_string()]
+        );
+    }
+}
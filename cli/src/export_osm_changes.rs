@@ -0,0 +1,342 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Read, Write};
+
+use anyhow::Result;
+use fs_err::File;
+use osmio::{Node, OSMObj, OSMObjBase, OSMObjectType, OSMReader, Way};
+
+use abstutil::{Tags, Timer};
+use geom::LonLat;
+use map_model::{osm, Map, MapEdits, OsmChanges};
+
+/// Express map edits as an OsmChange file. The original .osm XML file is needed, because the
+/// changes must list the full nodes and version of every modified way.
+pub fn run(map: String, edits: String, osm_input: String, output: String) -> Result<()> {
+    let mut timer = Timer::new("export edits to OSM");
+    let mut map = Map::load_synchronously(map, &mut timer);
+    let edits = MapEdits::load_from_file(&map, edits, &mut timer)?;
+    map.must_apply_edits(edits, &mut timer);
+
+    let changes = map.edits_to_osm_changes();
+    for warning in &changes.warnings {
+        warn!("{}", warning);
+    }
+
+    let mut f = File::create(&output)?;
+    write_osm_change(changes, File::open(osm_input)?, &mut f)?;
+    println!(
+        "Wrote {}. Load it in an OSM editor like JOSM, verify, and upload!",
+        output
+    );
+    Ok(())
+}
+
+fn write_osm_change<R: Read, W: Write>(changes: OsmChanges, osm_input: R, f: &mut W) -> Result<()> {
+    let mut affected_ways: HashSet<i64> = changes.ways.keys().map(|way| way.0).collect();
+    affected_ways.extend(changes.new_barriers.iter().map(|b| b.way.0));
+    affected_ways.extend(changes.removed_barriers.iter().map(|b| b.way.0));
+
+    // Find the nodes and version of every affected way, plus the position of all nodes. Barrier
+    // nodes might be deleted, so remember their version too.
+    let mut node_positions: HashMap<i64, LonLat> = HashMap::new();
+    let mut barrier_versions: HashMap<i64, Option<u32>> = HashMap::new();
+    let mut ways: BTreeMap<i64, (Vec<i64>, Option<u32>, Tags)> = BTreeMap::new();
+    let mut reader = osmio::xml::XMLReader::new(osm_input);
+    for obj in reader.objects() {
+        match obj.object_type() {
+            OSMObjectType::Node => {
+                let node = obj.into_node().unwrap();
+                if let Some((lat, lon)) = node.lat_lon() {
+                    node_positions.insert(node.id(), LonLat::new(lon.into(), lat.into()));
+                }
+                if node.has_tag("barrier") {
+                    barrier_versions.insert(node.id(), node.version());
+                }
+            }
+            OSMObjectType::Way => {
+                let way = obj.into_way().unwrap();
+                if affected_ways.contains(&way.id()) {
+                    let mut tags = Tags::empty();
+                    for (k, v) in way.tags() {
+                        tags.insert(k, v);
+                    }
+                    ways.insert(way.id(), (way.nodes().to_vec(), way.version(), tags));
+                }
+            }
+            OSMObjectType::Relation => {}
+        }
+    }
+
+    // Modal filters become new barrier nodes inserted into the way, with negative IDs as
+    // placeholders
+    let mut new_nodes = Vec::new();
+    for barrier in changes.new_barriers {
+        let (nodes, _, _) = match ways.get_mut(&barrier.way.0) {
+            Some(way) => way,
+            None => {
+                warn!("{} isn't in the OSM input, skipping a filter", barrier.way);
+                continue;
+            }
+        };
+        let id = -1 - (new_nodes.len() as i64);
+        if let Some(idx) = best_insertion(nodes, &node_positions, barrier.pt) {
+            nodes.insert(idx, id);
+            new_nodes.push((id, barrier.pt, barrier.tags));
+        }
+    }
+
+    // Removed filters delete the closest barrier node from the way
+    let mut deleted_nodes = Vec::new();
+    for barrier in changes.removed_barriers {
+        let (nodes, _, _) = match ways.get_mut(&barrier.way.0) {
+            Some(way) => way,
+            None => {
+                warn!(
+                    "{} isn't in the OSM input, skipping a removed filter",
+                    barrier.way
+                );
+                continue;
+            }
+        };
+        let closest = nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, id)| barrier_versions.contains_key(id))
+            .filter_map(|(idx, id)| {
+                let dist = node_positions.get(id)?.fast_dist(barrier.pt);
+                Some((idx, dist.inner_meters()))
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        match closest {
+            Some((idx, _)) => {
+                let id = nodes.remove(idx);
+                deleted_nodes.push((id, node_positions[&id], barrier_versions[&id]));
+            }
+            None => {
+                warn!("No barrier on {} to remove", barrier.way);
+            }
+        }
+    }
+
+    writeln!(f, r#"<osmChange version="0.6" generator="abstreet">"#)?;
+    writeln!(f, "  <create>")?;
+    for (id, pt, tags) in new_nodes {
+        writeln!(
+            f,
+            r#"    <node id="{}" lat="{}" lon="{}">"#,
+            id,
+            pt.y(),
+            pt.x()
+        )?;
+        write_tags(f, &tags)?;
+        writeln!(f, "    </node>")?;
+    }
+    writeln!(f, "  </create>")?;
+    writeln!(f, "  <modify>")?;
+    for (id, (nodes, version, orig_tags)) in ways {
+        match version {
+            Some(version) => writeln!(f, r#"    <way id="{}" version="{}">"#, id, version)?,
+            None => {
+                warn!(
+                    "way {} has no version in the OSM input; set it before uploading",
+                    id
+                );
+                writeln!(f, r#"    <way id="{}">"#, id)?
+            }
+        }
+        for node in nodes {
+            writeln!(f, r#"      <nd ref="{}"/>"#, node)?;
+        }
+        write_tags(f, changes.ways.get(&osm::WayID(id)).unwrap_or(&orig_tags))?;
+        writeln!(f, "    </way>")?;
+    }
+    writeln!(f, "  </modify>")?;
+    // Deletions come last, after the ways no longer refer to the nodes
+    writeln!(f, "  <delete>")?;
+    for (id, pt, version) in deleted_nodes {
+        match version {
+            Some(version) => writeln!(
+                f,
+                r#"    <node id="{}" version="{}" lat="{}" lon="{}"/>"#,
+                id,
+                version,
+                pt.y(),
+                pt.x()
+            )?,
+            None => {
+                warn!(
+                    "node {} has no version in the OSM input; set it before uploading",
+                    id
+                );
+                writeln!(
+                    f,
+                    r#"    <node id="{}" lat="{}" lon="{}"/>"#,
+                    id,
+                    pt.y(),
+                    pt.x()
+                )?
+            }
+        }
+    }
+    writeln!(f, "  </delete>")?;
+    writeln!(f, "</osmChange>")?;
+    Ok(())
+}
+
+/// Find the index in a way's nodes to insert a new point, minimizing the detour.
+fn best_insertion(nodes: &[i64], positions: &HashMap<i64, LonLat>, pt: LonLat) -> Option<usize> {
+    let dist = |a: LonLat, b: LonLat| ((a.x() - b.x()).powi(2) + (a.y() - b.y()).powi(2)).sqrt();
+    let mut best: Option<(usize, f64)> = None;
+    for (idx, pair) in nodes.windows(2).enumerate() {
+        let (a, b) = (*positions.get(&pair[0])?, *positions.get(&pair[1])?);
+        let detour = dist(a, pt) + dist(pt, b) - dist(a, b);
+        if best.map(|(_, d)| detour < d).unwrap_or(true) {
+            best = Some((idx + 1, detour));
+        }
+    }
+    best.map(|(idx, _)| idx)
+}
+
+fn write_tags<W: Write>(f: &mut W, tags: &Tags) -> Result<()> {
+    for (k, v) in tags.inner() {
+        writeln!(f, r#"      <tag k="{}" v="{}"/>"#, escape(k), escape(v))?;
+    }
+    Ok(())
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use map_model::{NewBarrier, RemovedBarrier};
+
+    use super::*;
+
+    const OSM_INPUT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<osm version="0.6">
+  <node id="1" version="1" lat="47.6" lon="-122.3"/>
+  <node id="2" version="3" lat="47.6" lon="-122.299">
+    <tag k="barrier" v="bollard"/>
+  </node>
+  <node id="3" version="1" lat="47.6" lon="-122.298"/>
+  <node id="4" version="1" lat="47.601" lon="-122.3"/>
+  <node id="5" version="1" lat="47.602" lon="-122.3"/>
+  <way id="10" version="7">
+    <nd ref="1"/>
+    <nd ref="2"/>
+    <nd ref="3"/>
+    <tag k="highway" v="residential"/>
+  </way>
+  <way id="11" version="2">
+    <nd ref="4"/>
+    <nd ref="5"/>
+    <tag k="highway" v="residential"/>
+  </way>
+  <way id="12" version="1">
+    <nd ref="3"/>
+    <nd ref="5"/>
+    <tag k="highway" v="residential"/>
+  </way>
+</osm>"#;
+
+    fn tags(kv: &[(&str, &str)]) -> Tags {
+        let mut tags = Tags::empty();
+        for (k, v) in kv {
+            tags.insert(*k, *v);
+        }
+        tags
+    }
+
+    /// The `ref`s of a way's nodes and its tags
+    fn parse_way<'a>(node: roxmltree::Node<'a, 'a>) -> (Vec<&'a str>, Vec<(&'a str, &'a str)>) {
+        let nodes = node
+            .children()
+            .filter(|n| n.has_tag_name("nd"))
+            .map(|n| n.attribute("ref").unwrap())
+            .collect();
+        let tags = node
+            .children()
+            .filter(|n| n.has_tag_name("tag"))
+            .map(|n| (n.attribute("k").unwrap(), n.attribute("v").unwrap()))
+            .collect();
+        (nodes, tags)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let changes = OsmChanges {
+            ways: vec![(
+                osm::WayID(11),
+                tags(&[("highway", "residential"), ("maxspeed", "20 mph")]),
+            )]
+            .into_iter()
+            .collect(),
+            // A new filter on way 11, and an old one removed from way 10
+            new_barriers: vec![NewBarrier {
+                way: osm::WayID(11),
+                pt: LonLat::new(-122.3, 47.6015),
+                tags: tags(&[("barrier", "bollard")]),
+            }],
+            removed_barriers: vec![RemovedBarrier {
+                way: osm::WayID(10),
+                pt: LonLat::new(-122.2991, 47.6),
+            }],
+            warnings: Vec::new(),
+        };
+        let mut output = Vec::new();
+        write_osm_change(changes, OSM_INPUT.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let doc = roxmltree::Document::parse(&output).unwrap();
+        let section = |name: &str| {
+            doc.root_element()
+                .children()
+                .find(|n| n.has_tag_name(name))
+                .unwrap()
+                .children()
+                .filter(|n| n.is_element())
+                .collect::<Vec<_>>()
+        };
+
+        let created = section("create");
+        assert_eq!(created.len(), 1);
+        assert!(created[0].has_tag_name("node"));
+        assert_eq!(created[0].attribute("id"), Some("-1"));
+        assert_eq!(created[0].attribute("lat"), Some("47.6015"));
+        assert_eq!(created[0].attribute("lon"), Some("-122.3"));
+        assert_eq!(parse_way(created[0]).1, vec![("barrier", "bollard")]);
+
+        // Way 12 isn't affected. Way 10 keeps its tags, but loses the barrier node.
+        let modified = section("modify");
+        assert_eq!(
+            modified
+                .iter()
+                .map(|n| (n.attribute("id").unwrap(), n.attribute("version").unwrap()))
+                .collect::<Vec<_>>(),
+            vec![("10", "7"), ("11", "2")]
+        );
+        assert_eq!(
+            parse_way(modified[0]),
+            (vec!["1", "3"], vec![("highway", "residential")])
+        );
+        assert_eq!(
+            parse_way(modified[1]),
+            (
+                vec!["4", "-1", "5"],
+                vec![("highway", "residential"), ("maxspeed", "20 mph")]
+            )
+        );
+
+        let deleted = section("delete");
+        assert_eq!(deleted.len(), 1);
+        assert!(deleted[0].has_tag_name("node"));
+        assert_eq!(deleted[0].attribute("id"), Some("2"));
+        assert_eq!(deleted[0].attribute("version"), Some("3"));
+    }
+}
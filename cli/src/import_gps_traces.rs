@@ -0,0 +1,75 @@
+use anyhow::{bail, Result};
+
+use abstutil::{prettyprint_usize, Counter, Timer};
+use geom::Duration;
+use map_model::Map;
+use synthpop::{ExternalPerson, GpsTrace, Scenario};
+
+pub fn run(
+    input: String,
+    map: String,
+    min_dwell_minutes: usize,
+    utc_offset_hours: f64,
+) -> Result<()> {
+    let mut timer = Timer::new("import GPS traces");
+    let map = Map::load_synchronously(map, &mut timer);
+
+    timer.start("parse traces");
+    let raw = fs_err::read_to_string(&input)?;
+    let utc_offset = Duration::seconds(3600.0 * utc_offset_hours);
+    let traces = if input.ends_with(".gpx") {
+        GpsTrace::parse_gpx(&raw, utc_offset)?
+    } else if input.ends_with(".geojson") || input.ends_with(".json") {
+        GpsTrace::parse_geojson(&raw, utc_offset)?
+    } else {
+        bail!("{} should be a .gpx or .geojson file", input);
+    };
+    timer.stop("parse traces");
+
+    let roads = synthpop::closest_roads(&map);
+    let min_dwell = Duration::minutes(min_dwell_minutes);
+    let mut people = Vec::new();
+    timer.start_iter("split traces into trips", traces.len());
+    for trace in traces {
+        timer.next();
+        let person = trace.to_person(&map, &roads, min_dwell);
+        if person.trips.is_empty() {
+            warn!("{} didn't go anywhere", trace.id);
+            continue;
+        }
+        people.push(person);
+    }
+
+    // These are small samples, so summarize them for comparing against other demand models
+    let mut modes = Counter::new();
+    let mut num_trips = 0;
+    for person in &people {
+        for trip in &person.trips {
+            modes.inc(trip.mode);
+            num_trips += 1;
+        }
+    }
+    println!(
+        "Found {} trips from {} people",
+        prettyprint_usize(num_trips),
+        prettyprint_usize(people.len())
+    );
+    for (mode, cnt) in modes.consume() {
+        println!(
+            "- {}: {} trips ({:.1}%)",
+            mode.ongoing_verb(),
+            prettyprint_usize(cnt),
+            100.0 * (cnt as f64) / (num_trips as f64)
+        );
+    }
+
+    let mut s = Scenario::empty(&map, "gps_traces");
+    // Include all buses/trains
+    s.only_seed_buses = None;
+    let skip_problems = true;
+    s.people = ExternalPerson::import(&map, people, skip_problems)?;
+    s = s.remove_weird_schedules(true);
+    s.save();
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use anyhow::Result;
+
+use abstutil::{prettyprint_usize, Timer};
+use map_model::Map;
+use synthpop::{ExternalPerson, Scenario};
+
+pub fn run(input: String, map: String, skip_problems: bool) -> Result<()> {
+    let mut timer = Timer::new("import MATSim plans");
+    timer.start("parse XML");
+    let people = ExternalPerson::parse_matsim_plans(&fs_err::read_to_string(&input)?)?;
+    timer.stop("parse XML");
+    let map = Map::load_synchronously(map, &mut timer);
+
+    let mut s = Scenario::empty(&map, &abstutil::basename(&input));
+    // Include all buses/trains
+    s.only_seed_buses = None;
+    let orig_num = people.len();
+    s.people = ExternalPerson::import(&map, people, skip_problems)?;
+    // Always clean up people with no-op trips (going between the same buildings)
+    s = s.remove_weird_schedules(true);
+    println!(
+        "Imported {}/{} people",
+        prettyprint_usize(s.people.len()),
+        prettyprint_usize(orig_num)
+    );
+    s.save();
+
+    Ok(())
+}
@@ -5,11 +5,20 @@
 extern crate log;
 
 mod augment_scenario;
+mod batch_experiments;
+mod calibrate;
 mod clip_osm;
+mod collab_relay;
+mod export_osm_changes;
 mod generate_houses;
+mod import_gps_traces;
 mod import_grid2demand;
+mod import_matsim;
 mod import_scenario;
+mod mode_choice;
+mod monte_carlo;
 mod one_step_import;
+mod parallel_import;
 
 use std::io::Write;
 
@@ -17,6 +26,8 @@ use abstio::CityName;
 use anyhow::Result;
 use fs_err::File;
 use importer::Job;
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
 use structopt::StructOpt;
 
 use abstutil::Timer;
@@ -79,6 +90,28 @@ enum Command {
         #[structopt(long)]
         out_path: String,
     },
+    /// Runs a server relaying map edits between people editing the same map together. See
+    /// `collab_relay.rs` for the protocol.
+    CollabRelay {
+        /// What port to listen on
+        #[structopt(long, default_value = "8090")]
+        port: u16,
+    },
+    /// Express map edits as an OsmChange (.osc) file, so they can be applied to OSM.
+    ExportOsmChanges {
+        /// The path to a map
+        #[structopt(long)]
+        map: String,
+        /// The path to edits for the map
+        #[structopt(long)]
+        edits: String,
+        /// The path to the .osm XML file the map was imported from
+        #[structopt(long)]
+        osm_input: String,
+        /// The path to write the .osc file
+        #[structopt(long, default_value = "diff.osc")]
+        output: String,
+    },
     /// Import a scenario from https://github.com/asu-trans-ai-lab/grid2demand.
     ImportGrid2Demand {
         /// The path to a grid2demand CSV file
@@ -88,6 +121,26 @@ enum Command {
         #[structopt(long)]
         map: String,
     },
+    /// Import a scenario from GPS traces, like the ones collected by travel surveys. Each trace is
+    /// split into trips wherever somebody stays in one place for a while, and the mode of each trip
+    /// is guessed from its speed and the roads it follows.
+    ImportGPSTraces {
+        /// The path to a .gpx file, with one track per person, or a .geojson file. See
+        /// `synthpop::GpsTrace::parse_geojson` for the GeoJSON format.
+        #[structopt(long)]
+        input: String,
+        /// The path to a map covering the traces
+        #[structopt(long)]
+        map: String,
+        /// Staying within 100m of one spot for this many minutes ends a trip
+        #[structopt(long, default_value = "5")]
+        min_dwell_minutes: usize,
+        /// Added to timestamps in UTC to get local time. For example, use -7 for Seattle in the
+        /// summer, since GPX timestamps are usually in UTC. Timestamps with their own offset, like
+        /// `+01:00`, are already local.
+        #[structopt(long, default_value = "0", allow_hyphen_values = true)]
+        utc_offset_hours: f64,
+    },
     /// Import a JSON scenario in the
     /// https://a-b-street.github.io/docs/tech/dev/formats/scenarios.html format
     ImportScenario {
@@ -102,6 +155,72 @@ enum Command {
         #[structopt(long)]
         skip_problems: bool,
     },
+    /// Import a scenario from a MATSim population XML file, using the selected plan of each person.
+    /// Activity coordinates must be WGS84 longitude/latitude.
+    ImportMATSim {
+        /// The path to a MATSim plans or population XML file
+        #[structopt(long)]
+        input: String,
+        /// The path to a map covering the population
+        #[structopt(long)]
+        map: String,
+        /// Problems occur when a position is within the map boundary, but not close enough to
+        /// buildings. Skip people with problematic positions if true, abort otherwise.
+        #[structopt(long)]
+        skip_problems: bool,
+    },
+    /// Simulate a scenario and compare hourly motor vehicle volumes against observed counts,
+    /// reporting GEH statistics. Optionally scale demand per origin zone to reduce the error.
+    Calibrate {
+        /// The path to a scenario file. This determines the map.
+        #[structopt(long)]
+        scenario: String,
+        /// The path to a CSV file with `osm_way_id`, `hour` (0-23), and `count` columns
+        #[structopt(long)]
+        counts: String,
+        /// The path to a GeoJSON file with origin zone polygons. If specified, a new scenario
+        /// with scaled demand is saved.
+        #[structopt(long)]
+        zones: Option<String>,
+        /// How long to simulate
+        #[structopt(long, default_value = "24")]
+        hours: usize,
+        /// The path to write a CSV file comparing each count
+        #[structopt(long, default_value = "calibration.csv")]
+        output: String,
+    },
+    /// Iteratively simulate a scenario and re-choose each person's mode, based on how congested
+    /// each mode was. A new scenario with the final modes is saved.
+    ChooseModes {
+        /// The path to a scenario file. This determines the map.
+        #[structopt(long)]
+        scenario: String,
+        /// Optional edits to apply to the map first, like a new bike network
+        #[structopt(long)]
+        edits: Option<String>,
+        /// How many rounds of simulation and mode choice to run
+        #[structopt(long, default_value = "3")]
+        iterations: usize,
+        /// How long to simulate each round
+        #[structopt(long, default_value = "24")]
+        hours: usize,
+        /// A seed for generating random numbers
+        #[structopt(long, default_value = "42")]
+        rng_seed: u64,
+    },
+    /// Import traffic counts produced by another tool, for comparing against A/B Street. SUMO
+    /// edgeData output (.xml) and CSV files with osm_way_id and count columns are supported.
+    ImportCounts {
+        /// The path to a SUMO edgeData XML or link volumes CSV file
+        #[structopt(long)]
+        input: String,
+        /// The path to a map built from the same OSM data
+        #[structopt(long)]
+        map: String,
+        /// The path to write the counts as JSON
+        #[structopt(long)]
+        output: String,
+    },
     /// Transform a JSON map that's been manually edited into the binary format suitable for
     /// simulation.
     ImportJSONMap {
@@ -118,6 +237,19 @@ enum Command {
         #[structopt()]
         map: String,
     },
+    /// Exports roads, lanes, intersections, buildings, and transit from a map to GeoJSON, for use
+    /// in other GIS tools.
+    ExportGeoJSON {
+        /// The path to a map
+        #[structopt(long)]
+        map: String,
+        /// Optional edits to apply to the map first
+        #[structopt(long)]
+        edits: Option<String>,
+        /// The GeoJSON file to write
+        #[structopt(long)]
+        output: String,
+    },
     /// Procedurally generates houses along empty residential roads of a map
     GenerateHouses {
         /// The path to a map to generate houses for
@@ -134,6 +266,20 @@ enum Command {
         #[structopt(long)]
         output: String,
     },
+    /// Generates a scenario of home-to-work trips from local population data. Polygons with a
+    /// `population` property (.geojson), the Eurostat population grid (.csv), and WorldPop
+    /// rasters converted to ESRI ASCII grids (.asc) are supported.
+    GenerateCensusScenario {
+        /// The path to a map to generate a scenario for
+        #[structopt(long)]
+        map: String,
+        /// The path to the population data
+        #[structopt(long)]
+        population: String,
+        /// A seed for generating random numbers
+        #[structopt(long, default_value = "42")]
+        rng_seed: u64,
+    },
     /// Prints the osm.pbf file from download.geofabrik.de that covers a given boundary.
     ///
     /// This is a useful tool when importing a new map, if you don't already know which geofabrik
@@ -206,6 +352,62 @@ enum Command {
         /// total shards there are.
         #[structopt(long, default_value = "1")]
         num_shards: usize,
+        /// Import using this many worker processes. Different maps are converted in parallel, as
+        /// are the download and scenario stages of different cities.
+        #[structopt(long, default_value = "1")]
+        num_workers: usize,
+    },
+    /// Simulate a list of experiments in parallel and collate the results. The input is a JSON list
+    /// of objects with a `scenario` path, and optionally `edits` (a path), `rng_seed`, and `hours`.
+    BatchExperiments {
+        /// The path to the JSON list of experiments
+        #[structopt(long)]
+        input: String,
+        /// Results will be written to this path, with .json and .csv extensions
+        #[structopt(long)]
+        output: String,
+        /// How many worker processes to run at a time
+        #[structopt(long, default_value = "4")]
+        num_workers: usize,
+    },
+    /// Simulate the same scenario many times with different random seeds, and report the mean and
+    /// variance of key metrics. Use this to judge whether a change is bigger than the run-to-run
+    /// noise.
+    MonteCarlo {
+        /// The path to a scenario file. This determines the map.
+        #[structopt(long)]
+        scenario: String,
+        /// The path to map edits to apply before simulating, if any
+        #[structopt(long)]
+        edits: Option<String>,
+        /// If specified, also simulate with these edits using the same seeds, and report whether
+        /// the paired differences are likely real or just noise
+        #[structopt(long)]
+        compare_edits: Option<String>,
+        /// How many times to simulate
+        #[structopt(long, default_value = "10")]
+        num_runs: usize,
+        /// How long to simulate each run
+        #[structopt(long, default_value = "24")]
+        hours: usize,
+        /// The seed for the first run. Each run after that uses the next number.
+        #[structopt(long, default_value = "42")]
+        rng_seed: u64,
+        /// How many worker processes to run at a time
+        #[structopt(long, default_value = "4")]
+        num_workers: usize,
+        /// Results will be written to this path, with .json and _summary.csv extensions
+        #[structopt(long, default_value = "monte_carlo")]
+        output: String,
+    },
+    /// Simulate a single experiment for `batch-experiments`. You don't need to call this directly.
+    RunExperiment {
+        /// One experiment, as JSON
+        #[structopt(long)]
+        experiment: String,
+        /// The path to write the JSON results
+        #[structopt(long)]
+        output: String,
     },
     /// Generate a shell script to regenerate all cities that uses an external task runner.
     RegenerateEverythingExternally,
@@ -272,20 +474,59 @@ async fn main() -> Result<()> {
             clip_path,
             out_path,
         } => clip_osm::run(pbf_path, clip_path, out_path)?,
+        Command::ExportOsmChanges {
+            map,
+            edits,
+            osm_input,
+            output,
+        } => export_osm_changes::run(map, edits, osm_input, output)?,
+        Command::CollabRelay { port } => collab_relay::run(port).await?,
         Command::ImportGrid2Demand { input, map } => import_grid2demand::run(input, map)?,
+        Command::ImportGPSTraces {
+            input,
+            map,
+            min_dwell_minutes,
+            utc_offset_hours,
+        } => import_gps_traces::run(input, map, min_dwell_minutes, utc_offset_hours)?,
         Command::ImportScenario {
             input,
             map,
             skip_problems,
         } => import_scenario::run(input, map, skip_problems),
+        Command::ImportMATSim {
+            input,
+            map,
+            skip_problems,
+        } => import_matsim::run(input, map, skip_problems)?,
+        Command::Calibrate {
+            scenario,
+            counts,
+            zones,
+            hours,
+            output,
+        } => calibrate::run(scenario, counts, zones, hours, output)?,
+        Command::ChooseModes {
+            scenario,
+            edits,
+            iterations,
+            hours,
+            rng_seed,
+        } => mode_choice::run(scenario, edits, iterations, hours, rng_seed)?,
+        Command::ImportCounts { input, map, output } => import_counts(input, map, output)?,
         Command::ImportJSONMap { input, output } => import_json_map(input, output),
         Command::MinifyMap { map } => minify_map(map),
+        Command::ExportGeoJSON { map, edits, output } => export_geojson(map, edits, output)?,
         Command::GenerateHouses {
             map,
             num_required,
             rng_seed,
             output,
         } => generate_houses::run(map, num_required, rng_seed, output),
+        Command::GenerateCensusScenario {
+            map,
+            population,
+            rng_seed,
+        } => generate_census_scenario(map, population, rng_seed).await?,
         Command::PickGeofabrik { input } => {
             println!("{}", importer::pick_geofabrik(input).await?.0)
         }
@@ -336,8 +577,42 @@ async fn main() -> Result<()> {
         Command::RegenerateEverything {
             shard_num,
             num_shards,
-        } => importer::regenerate_everything(shard_num, num_shards).await,
+            num_workers,
+        } => {
+            if num_workers > 1 {
+                parallel_import::run(shard_num, num_shards, num_workers)?
+            } else {
+                importer::regenerate_everything(shard_num, num_shards).await
+            }
+        }
         Command::RegenerateEverythingExternally => regenerate_everything_externally()?,
+        Command::BatchExperiments {
+            input,
+            output,
+            num_workers,
+        } => batch_experiments::run(input, output, num_workers)?,
+        Command::MonteCarlo {
+            scenario,
+            edits,
+            compare_edits,
+            num_runs,
+            hours,
+            rng_seed,
+            num_workers,
+            output,
+        } => monte_carlo::run(
+            scenario,
+            edits,
+            compare_edits,
+            num_runs,
+            hours,
+            rng_seed,
+            num_workers,
+            output,
+        )?,
+        Command::RunExperiment { experiment, output } => {
+            batch_experiments::run_one(experiment, output)?
+        }
         Command::Import { job } => job.run(&mut Timer::new("import one city")).await,
         Command::PrebakeScenario { scenario_path } => prebake_scenario(scenario_path),
     }
@@ -392,6 +667,23 @@ fn random_scenario(rng_seed: u64, map: String, scenario_name: String) {
     );
 }
 
+fn import_counts(input: String, map: String, output: String) -> Result<()> {
+    let map = map_model::Map::load_synchronously(map, &mut Timer::throwaway());
+    let description = abstutil::basename(&input);
+    let bytes = fs_err::read(&input)?;
+    let counts = if input.ends_with(".xml") {
+        synthpop::TrafficCounts::import_sumo_edgedata(
+            &map,
+            description,
+            &String::from_utf8(bytes)?,
+        )?
+    } else {
+        synthpop::TrafficCounts::import_link_volumes_csv(&map, description, &bytes)?
+    };
+    abstio::write_json(output, &counts);
+    Ok(())
+}
+
 fn import_json_map(input: String, output: String) {
     // TODO This can't handle the output of dump_map! What?!
     let mut map: map_model::Map = abstio::read_json(input, &mut Timer::throwaway());
@@ -399,6 +691,32 @@ fn import_json_map(input: String, output: String) {
     abstio::write_binary(output, &map);
 }
 
+fn export_geojson(map: String, edits: Option<String>, output: String) -> Result<()> {
+    let mut timer = Timer::new("export map to GeoJSON");
+    let mut map = map_model::Map::load_synchronously(map, &mut timer);
+    if let Some(path) = edits {
+        let edits = map_model::MapEdits::load_from_file(&map, path, &mut timer)?;
+        map.must_apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+    }
+    abstio::write_json(output, &map.export_to_geojson());
+    Ok(())
+}
+
+async fn generate_census_scenario(map: String, population: String, rng_seed: u64) -> Result<()> {
+    let map = map_model::Map::load_synchronously(map, &mut Timer::throwaway());
+    let source = <dyn popdat::PopulationSource>::from_path(population)?;
+    let areas = source
+        .load(map.get_boundary_polygon(), map.get_gps_bounds())
+        .await?;
+    println!("Loaded {} areas from {}", areas.len(), source.describe());
+    let mut rng = XorShiftRng::seed_from_u64(rng_seed);
+    let scenario =
+        popdat::generate_scenario("census", areas, popdat::Config::default(), &map, &mut rng);
+    scenario.save();
+    Ok(())
+}
+
 fn minify_map(path: String) {
     let mut timer = Timer::new("minify map");
     let mut map = map_model::Map::load_synchronously(path, &mut timer);
@@ -0,0 +1,301 @@
+//! Re-choose the mode of people in a scenario based on network conditions. Each round simulates
+//! the scenario, measures how much slower than free-flow each mode actually was, and then lets
+//! people pick a mode again with a simple logit model. Since the free-flow routes are calculated
+//! on the edited map, changes like a protected bike network or a low-traffic neighbourhood shift
+//! mode share, not just routes.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+use abstutil::{prettyprint_usize, Counter, Timer};
+use geom::Duration;
+use map_model::{Map, MapEdits};
+use sim::{AlertHandler, Sim, SimOptions};
+use synthpop::{PersonSpec, Scenario, TripEndpoint, TripMode};
+
+/// Utility lost per minute of travel
+const TIME_WEIGHT: f64 = 0.05;
+/// Utility lost per dollar spent. Together with `TIME_WEIGHT`, this values time at $15 an hour.
+const COST_WEIGHT: f64 = 0.2;
+/// Utility lost per kilometer walked or biked, for the physical effort
+const WALK_EFFORT_WEIGHT: f64 = 0.6;
+const BIKE_EFFORT_WEIGHT: f64 = 0.15;
+/// Running a car, not counting parking
+const DRIVING_COST_PER_KM: f64 = 0.25;
+/// Transit routes aren't simulated precisely here; a ride is assumed to be this much slower than
+/// driving, plus time spent walking to and waiting at stops.
+const TRANSIT_SLOWDOWN: f64 = 1.5;
+const TRANSIT_ACCESS_TIME: Duration = Duration::const_seconds(600.0);
+/// Don't trust a few unusual trips to make a mode look too fast or too slow
+const MIN_CONGESTION: f64 = 0.5;
+const MAX_CONGESTION: f64 = 5.0;
+
+/// The free-flow cost of every trip a person makes by one mode
+struct ModeCost {
+    time: Duration,
+    dollars: f64,
+    km: f64,
+}
+
+/// Run `iterations` rounds of simulation and mode choice, then save the scenario with the final
+/// modes as `{name}_mode_choice`. Only people whose trips all start and end at buildings change
+/// modes; trips to or from the map boundary keep their mode.
+pub fn run(
+    scenario: String,
+    edits: Option<String>,
+    iterations: usize,
+    hours: usize,
+    rng_seed: u64,
+) -> Result<()> {
+    let mut timer = Timer::new("iterate mode choice");
+    let mut scenario: Scenario = abstio::read_object(scenario, &mut timer)?;
+    let mut map = Map::load_synchronously(scenario.map_name.path(), &mut timer);
+    if let Some(path) = edits {
+        let edits = MapEdits::load_from_file(&map, path, &mut timer)?;
+        map.must_apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+    }
+    let mut rng = XorShiftRng::seed_from_u64(rng_seed);
+
+    // The network doesn't change between rounds, so the free-flow options only need calculating
+    // once
+    timer.start_iter("calculate options per person", scenario.people.len());
+    let options: Vec<Option<BTreeMap<TripMode, ModeCost>>> = scenario
+        .people
+        .iter()
+        .map(|person| {
+            timer.next();
+            person_options(&map, person)
+        })
+        .collect();
+    print_mode_share("Initially", &scenario);
+
+    for round in 1..=iterations {
+        let congestion = simulate(&map, &scenario, hours, rng_seed, &mut timer);
+        for (mode, factor) in &congestion {
+            println!(
+                "  {:?} trips took {:.2}x their free-flow time",
+                mode, factor
+            );
+        }
+
+        // Only let some people switch in later rounds, so mode share settles instead of
+        // oscillating
+        let switch_prob = 1.0 / (round as f64);
+        for (person, options) in scenario.people.iter_mut().zip(options.iter()) {
+            let options = match options {
+                Some(options) => options,
+                None => continue,
+            };
+            if !rng.gen_bool(switch_prob) {
+                continue;
+            }
+            let mode = choose_mode(options, &congestion, &mut rng);
+            for trip in &mut person.trips {
+                trip.mode = mode;
+            }
+        }
+        print_mode_share(&format!("After round {}", round), &scenario);
+    }
+
+    scenario.scenario_name = format!("{}_mode_choice", scenario.scenario_name);
+    scenario.save();
+    println!("Saved {}", scenario.scenario_name);
+    Ok(())
+}
+
+/// For every mode available to a person, sum the free-flow cost of all of their trips.
+fn person_options(map: &Map, person: &PersonSpec) -> Option<BTreeMap<TripMode, ModeCost>> {
+    if person.trips.is_empty()
+        || person.trips.iter().any(|trip| {
+            !matches!(trip.origin, TripEndpoint::Building(_))
+                || !matches!(trip.destination, TripEndpoint::Building(_))
+        })
+    {
+        return None;
+    }
+
+    let mut options = BTreeMap::new();
+    'mode: for mode in TripMode::all() {
+        let mut total = ModeCost {
+            time: Duration::ZERO,
+            dollars: 0.0,
+            km: 0.0,
+        };
+        for trip in &person.trips {
+            match trip_cost(map, trip.origin, trip.destination, mode) {
+                Some(cost) => {
+                    total.time += cost.time;
+                    total.dollars += cost.dollars;
+                    total.km += cost.km;
+                }
+                None => continue 'mode,
+            }
+        }
+        options.insert(mode, total);
+    }
+    if options.is_empty() {
+        None
+    } else {
+        Some(options)
+    }
+}
+
+fn trip_cost(map: &Map, from: TripEndpoint, to: TripEndpoint, mode: TripMode) -> Option<ModeCost> {
+    let km = from.pt(map).dist_to(to.pt(map)).inner_meters() / 1000.0;
+    match mode {
+        TripMode::Walk | TripMode::Bike | TripMode::Drive => {
+            let time = free_flow_time(map, from, to, mode)?;
+            Some(ModeCost {
+                time,
+                dollars: if mode == TripMode::Drive {
+                    km * DRIVING_COST_PER_KM
+                } else {
+                    0.0
+                },
+                km,
+            })
+        }
+        TripMode::Transit => {
+            let req = TripEndpoint::path_req(from, to, TripMode::Transit, map)?;
+            let (_, _, route) = map.should_use_transit(req.start, req.end)?;
+            let driving = free_flow_time(map, from, to, TripMode::Drive)?;
+            Some(ModeCost {
+                time: TRANSIT_SLOWDOWN * driving + TRANSIT_ACCESS_TIME,
+                dollars: (map.get_tr(route).fare.typical_price() as f64) / 100.0,
+                km,
+            })
+        }
+    }
+}
+
+/// The routing cost is used, so penalties like riding a bike in mixed traffic count.
+fn free_flow_time(
+    map: &Map,
+    from: TripEndpoint,
+    to: TripEndpoint,
+    mode: TripMode,
+) -> Option<Duration> {
+    let req = TripEndpoint::path_req(from, to, mode, map)?;
+    map.pathfind_v2(req).ok().map(|path| path.get_cost())
+}
+
+/// Simulate the scenario, and for each mode, return how much longer finished trips took than
+/// their free-flow time.
+fn simulate(
+    map: &Map,
+    scenario: &Scenario,
+    hours: usize,
+    rng_seed: u64,
+    timer: &mut Timer,
+) -> BTreeMap<TripMode, f64> {
+    let mut opts = SimOptions::new("mode_choice");
+    opts.alerts = AlertHandler::Silence;
+    let mut sim = Sim::new(map, opts);
+    let mut rng = XorShiftRng::seed_from_u64(rng_seed);
+    sim.instantiate(scenario, map, &mut rng, timer);
+    sim.timed_step(map, Duration::hours(hours), &mut None, timer);
+
+    // Per mode, the total actual and free-flow time
+    let mut totals: BTreeMap<TripMode, (Duration, Duration)> = BTreeMap::new();
+    let finished = &sim.get_analytics().finished_trips;
+    timer.start_iter("compare trips to free-flow", finished.len());
+    for (_, id, mode, maybe_duration) in finished {
+        timer.next();
+        let actual = match maybe_duration {
+            Some(dt) => *dt,
+            None => continue,
+        };
+        let info = sim.trip_info(*id);
+        let free_flow = if *mode == TripMode::Transit {
+            trip_cost(map, info.start, info.end, *mode).map(|cost| cost.time)
+        } else {
+            free_flow_time(map, info.start, info.end, *mode)
+        };
+        if let Some(free_flow) = free_flow {
+            let entry = totals
+                .entry(*mode)
+                .or_insert((Duration::ZERO, Duration::ZERO));
+            entry.0 += actual;
+            entry.1 += free_flow;
+        }
+    }
+
+    totals
+        .into_iter()
+        .filter(|(_, (_, free_flow))| *free_flow > Duration::ZERO)
+        .map(|(mode, (actual, free_flow))| {
+            (
+                mode,
+                (actual / free_flow).clamp(MIN_CONGESTION, MAX_CONGESTION),
+            )
+        })
+        .collect()
+}
+
+/// Pick a mode randomly, weighted by a multinomial logit model
+fn choose_mode(
+    options: &BTreeMap<TripMode, ModeCost>,
+    congestion: &BTreeMap<TripMode, f64>,
+    rng: &mut XorShiftRng,
+) -> TripMode {
+    let utilities: Vec<(TripMode, f64)> = options
+        .iter()
+        .map(|(mode, cost)| {
+            let minutes =
+                congestion.get(mode).cloned().unwrap_or(1.0) * cost.time.inner_seconds() / 60.0;
+            let effort = match mode {
+                TripMode::Walk => WALK_EFFORT_WEIGHT * cost.km,
+                TripMode::Bike => BIKE_EFFORT_WEIGHT * cost.km,
+                TripMode::Transit | TripMode::Drive => 0.0,
+            };
+            (
+                *mode,
+                -TIME_WEIGHT * minutes - COST_WEIGHT * cost.dollars - effort,
+            )
+        })
+        .collect();
+
+    // Subtract the best utility before exponentiating, to avoid underflow for long trips
+    let best = utilities
+        .iter()
+        .map(|(_, u)| *u)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let weights: Vec<(TripMode, f64)> = utilities
+        .into_iter()
+        .map(|(mode, u)| (mode, (u - best).exp()))
+        .collect();
+    let total: f64 = weights.iter().map(|(_, w)| *w).sum();
+    let mut pick = rng.gen_range(0.0..total);
+    for (mode, weight) in &weights {
+        if pick < *weight {
+            return *mode;
+        }
+        pick -= weight;
+    }
+    weights.last().unwrap().0
+}
+
+fn print_mode_share(label: &str, scenario: &Scenario) {
+    let mut counts = Counter::new();
+    let mut total = 0;
+    for person in &scenario.people {
+        for trip in &person.trips {
+            counts.inc(trip.mode);
+            total += 1;
+        }
+    }
+    println!("{}:", label);
+    for mode in TripMode::all() {
+        let cnt = counts.get(mode);
+        println!(
+            "  {:?}: {} trips ({:.1}%)",
+            mode,
+            prettyprint_usize(cnt),
+            100.0 * (cnt as f64) / (total.max(1) as f64)
+        );
+    }
+}
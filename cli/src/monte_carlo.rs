@@ -0,0 +1,191 @@
+//! Simulates the same scenario many times with different random seeds, to measure how much key
+//! metrics vary from run to run. A difference between two designs smaller than this noise
+//! shouldn't be trusted.
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::batch_experiments::{run_all, write_csv, Experiment, ExperimentResults};
+
+#[derive(Serialize)]
+struct MonteCarloResults {
+    runs: Vec<ExperimentResults>,
+    summary: Vec<MetricSummary>,
+    /// Only filled out when comparing against other edits. Each run with the other edits is
+    /// paired with the run using the same seed, and these summarize the per-pair differences
+    /// (other edits minus the original).
+    comparison_runs: Vec<ExperimentResults>,
+    differences: Vec<MetricSummary>,
+}
+
+#[derive(Serialize)]
+struct MetricSummary {
+    metric: String,
+    mean: f64,
+    std_dev: f64,
+    variance: f64,
+    min: f64,
+    max: f64,
+    /// An approximate 95% confidence interval for the mean, assuming a normal distribution
+    ci95_low: f64,
+    ci95_high: f64,
+}
+
+impl MetricSummary {
+    fn new(metric: &str, values: &[f64]) -> MetricSummary {
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        // The sample variance
+        let variance = if values.len() > 1 {
+            values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0)
+        } else {
+            0.0
+        };
+        let std_dev = variance.sqrt();
+        let margin = 1.96 * std_dev / n.sqrt();
+        MetricSummary {
+            metric: metric.to_string(),
+            mean,
+            std_dev,
+            variance,
+            min: values.iter().cloned().fold(f64::INFINITY, f64::min),
+            max: values.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            ci95_low: mean - margin,
+            ci95_high: mean + margin,
+        }
+    }
+
+    /// Does the confidence interval exclude zero?
+    fn significant(&self) -> bool {
+        self.ci95_low > 0.0 || self.ci95_high < 0.0
+    }
+}
+
+fn metrics(results: &ExperimentResults) -> Vec<(&'static str, f64)> {
+    let mean_trip_duration = if results.finished_trips == 0 {
+        0.0
+    } else {
+        results.total_trip_duration_seconds / (results.finished_trips as f64)
+    };
+    vec![
+        ("finished trips", results.finished_trips as f64),
+        ("cancelled trips", results.cancelled_trips as f64),
+        ("unfinished trips", results.unfinished_trips as f64),
+        ("mean trip duration (s)", mean_trip_duration),
+        (
+            "total trip duration (s)",
+            results.total_trip_duration_seconds,
+        ),
+        ("walking trips", results.walk_trips as f64),
+        ("biking trips", results.bike_trips as f64),
+        ("transit trips", results.transit_trips as f64),
+        ("driving trips", results.drive_trips as f64),
+    ]
+}
+
+fn summarize(runs: &[Vec<(&'static str, f64)>]) -> Vec<MetricSummary> {
+    let mut summary = Vec::new();
+    for (idx, (metric, _)) in runs[0].iter().enumerate() {
+        let values: Vec<f64> = runs.iter().map(|run| run[idx].1).collect();
+        summary.push(MetricSummary::new(metric, &values));
+    }
+    summary
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    scenario: String,
+    edits: Option<String>,
+    compare_edits: Option<String>,
+    num_runs: usize,
+    hours: usize,
+    rng_seed: u64,
+    num_workers: usize,
+    output: String,
+) -> Result<()> {
+    let num_runs = num_runs.max(1);
+    let experiments = |edits: Option<String>| -> Vec<Experiment> {
+        (0..num_runs)
+            .map(|i| Experiment {
+                scenario: scenario.clone(),
+                edits: edits.clone(),
+                rng_seed: rng_seed + (i as u64),
+                hours,
+            })
+            .collect()
+    };
+
+    let runs = run_all(&experiments(edits), &output, num_workers)?;
+    let run_metrics: Vec<Vec<(&'static str, f64)>> = runs.iter().map(metrics).collect();
+    let summary = summarize(&run_metrics);
+
+    println!("Over {} runs:", num_runs);
+    for metric in &summary {
+        println!(
+            "- {}: mean {:.1}, standard deviation {:.1} ({:.1}% of the mean), 95% CI [{:.1}, {:.1}]",
+            metric.metric,
+            metric.mean,
+            metric.std_dev,
+            percent(metric.std_dev, metric.mean),
+            metric.ci95_low,
+            metric.ci95_high
+        );
+    }
+
+    let mut comparison_runs = Vec::new();
+    let mut differences = Vec::new();
+    if let Some(compare_edits) = compare_edits {
+        comparison_runs = run_all(&experiments(Some(compare_edits)), &output, num_workers)?;
+        let paired: Vec<Vec<(&'static str, f64)>> = comparison_runs
+            .iter()
+            .zip(run_metrics.iter())
+            .map(|(after, before)| {
+                metrics(after)
+                    .into_iter()
+                    .zip(before.iter())
+                    .map(|((metric, a), (_, b))| (metric, a - b))
+                    .collect()
+            })
+            .collect();
+        differences = summarize(&paired);
+
+        println!();
+        println!("Paired differences with the other edits, using the same seeds:");
+        for (diff, base) in differences.iter().zip(summary.iter()) {
+            println!(
+                "- {}: mean change {:+.1} ({:+.1}%), 95% CI [{:.1}, {:.1}], {}",
+                diff.metric,
+                diff.mean,
+                percent(diff.mean, base.mean),
+                diff.ci95_low,
+                diff.ci95_high,
+                if diff.significant() {
+                    "likely a real change"
+                } else {
+                    "within the noise"
+                }
+            );
+        }
+    }
+
+    write_csv(&summary, format!("{}_summary.csv", output))?;
+    abstio::write_json(
+        format!("{}.json", output),
+        &MonteCarloResults {
+            runs,
+            summary,
+            comparison_runs,
+            differences,
+        },
+    );
+    println!("Wrote {}.json and {}_summary.csv", output, output);
+    Ok(())
+}
+
+fn percent(x: f64, total: f64) -> f64 {
+    if total == 0.0 {
+        0.0
+    } else {
+        100.0 * x / total
+    }
+}
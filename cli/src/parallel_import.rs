@@ -0,0 +1,155 @@
+//! Regenerates all maps using many worker processes. Downloading and clipping input is shared by
+//! all maps in a city, so that happens once per city. Converting RawMaps to maps is the slowest
+//! part, and every map is independent, so that's fully parallel. Within one map, the contraction
+//! hierarchies for each mode are also built at the same time, since that dominates the conversion.
+//! Finally scenarios and city overviews are built per city.
+
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use anyhow::{bail, Result};
+
+use abstio::CityName;
+use abstutil::prettyprint_usize;
+use geom::Duration;
+use importer::Job;
+
+pub fn run(shard_num: usize, num_shards: usize, num_workers: usize) -> Result<()> {
+    let cities: Vec<CityName> = CityName::list_all_cities_from_importer_config()
+        .into_iter()
+        .enumerate()
+        .filter(|(cnt, _)| cnt % num_shards == shard_num)
+        .map(|(_, city)| city)
+        .collect();
+    let log_dir = "import_logs";
+    fs_err::create_dir_all(log_dir)?;
+    let mut failed_cities = BTreeSet::new();
+
+    let mut raw_jobs = Vec::new();
+    for city in &cities {
+        let mut job = Job::full_for_city(city.clone());
+        job.raw_to_map = false;
+        job.scenario = false;
+        job.city_overview = false;
+        raw_jobs.push(job);
+    }
+    failed_cities.extend(run_stage("raw", raw_jobs, num_workers, log_dir)?);
+
+    let mut map_jobs = Vec::new();
+    for city in &cities {
+        if failed_cities.contains(city) {
+            continue;
+        }
+        for name in city.list_all_maps_in_city_from_importer_config() {
+            let mut job = Job::full_for_city(city.clone());
+            job.osm_to_raw = false;
+            job.scenario = false;
+            job.city_overview = false;
+            job.only_map = Some(name.map);
+            map_jobs.push(job);
+        }
+    }
+    failed_cities.extend(run_stage("map", map_jobs, num_workers, log_dir)?);
+
+    let mut final_jobs = Vec::new();
+    for city in &cities {
+        let mut job = Job::full_for_city(city.clone());
+        if failed_cities.contains(city) || (!job.scenario && !job.city_overview) {
+            continue;
+        }
+        job.osm_to_raw = false;
+        job.raw_to_map = false;
+        final_jobs.push(job);
+    }
+    failed_cities.extend(run_stage("scenario", final_jobs, num_workers, log_dir)?);
+
+    if !failed_cities.is_empty() {
+        bail!(
+            "Importing failed for {}. See logs in {}/",
+            failed_cities
+                .into_iter()
+                .map(|city| city.describe())
+                .collect::<Vec<_>>()
+                .join(", "),
+            log_dir
+        );
+    }
+    Ok(())
+}
+
+/// Runs every job in a separate process, with up to `num_workers` at a time. Returns the cities
+/// with a failed job.
+fn run_stage(
+    stage: &str,
+    jobs: Vec<Job>,
+    num_workers: usize,
+    log_dir: &str,
+) -> Result<BTreeSet<CityName>> {
+    let exe = std::env::current_exe()?;
+    let started = instant::Instant::now();
+    println!(
+        "Starting {} stage with {} jobs",
+        stage,
+        prettyprint_usize(jobs.len())
+    );
+
+    let next = AtomicUsize::new(0);
+    let finished = AtomicUsize::new(0);
+    let failed = Mutex::new(BTreeSet::new());
+    std::thread::scope(|s| {
+        for _ in 0..num_workers.max(1) {
+            s.spawn(|| loop {
+                let idx = next.fetch_add(1, Ordering::SeqCst);
+                if idx >= jobs.len() {
+                    break;
+                }
+                let job = &jobs[idx];
+                let name = match job.only_map {
+                    Some(ref map) => format!("{} {}", job.city.describe(), map),
+                    None => job.city.describe(),
+                };
+                // Keep the console readable; each job's output goes to its own file
+                let log_path = format!(
+                    "{}/{}_{}_{}_{}.log",
+                    log_dir,
+                    stage,
+                    job.city.country,
+                    job.city.city,
+                    job.only_map.as_deref().unwrap_or("all")
+                );
+                let status = fs_err::File::create(&log_path).and_then(|log| {
+                    let (stdout, _) = log.into_parts();
+                    let stderr = stdout.try_clone()?;
+                    std::process::Command::new(&exe)
+                        .arg("import")
+                        .args(job.flags())
+                        .stdout(stdout)
+                        .stderr(stderr)
+                        .status()
+                });
+                let ok = match status {
+                    Ok(status) => status.success(),
+                    Err(err) => {
+                        error!("Couldn't start {}: {}", name, err);
+                        false
+                    }
+                };
+                if !ok {
+                    failed.lock().unwrap().insert(job.city.clone());
+                }
+                let done = finished.fetch_add(1, Ordering::SeqCst) + 1;
+                println!(
+                    "[{} stage, {}/{} done, {} elapsed] {} {}",
+                    stage,
+                    prettyprint_usize(done),
+                    prettyprint_usize(jobs.len()),
+                    Duration::realtime_elapsed(started),
+                    if ok { "finished" } else { "FAILED" },
+                    name
+                );
+            });
+        }
+    });
+    Ok(failed.into_inner().unwrap())
+}
@@ -5,7 +5,7 @@ use geom::{Distance, FindClosest, GPSBounds, HashablePt2D, LonLat, Polygon, Pt2D
 use osm2streets::osm::{OsmID, RelationID, WayID};
 use osm2streets::{osm, NamePerLanguage};
 use raw_map::{
-    Amenity, AreaType, CrossingType, ExtraPOI, ExtraPOIType, RawArea, RawBuilding, RawMap,
+    Amenity, AreaType, CrossingType, ExtraPOI, ExtraPOIType, LandUse, RawArea, RawBuilding, RawMap,
     RawParkingLot,
 };
 
@@ -21,6 +21,10 @@ pub struct Extract {
     pub crossing_nodes: HashSet<(HashablePt2D, CrossingType)>,
     /// Some kind of barrier nodes at these points.
     pub barrier_nodes: Vec<(osm::NodeID, HashablePt2D)>,
+    /// Kerbs that wheelchairs can't get over, usually at crossings
+    pub raised_kerbs: Vec<(osm::NodeID, HashablePt2D)>,
+    /// Gates closed to the public, like the entrance to a gated community.
+    pub private_gates: Vec<(osm::NodeID, HashablePt2D)>,
     pub extra_pois: Vec<ExtraPOI>,
 }
 
@@ -60,14 +64,24 @@ pub fn extract_osm(
     let mut bus_routes_on_roads: MultiMap<WayID, String> = MultiMap::new();
     let mut crossing_nodes = HashSet::new();
     let mut barrier_nodes = Vec::new();
+    let mut raised_kerbs = Vec::new();
+    let mut private_gates = Vec::new();
     let mut extra_pois = Vec::new();
 
     timer.start_iter("processing OSM nodes", doc.nodes.len());
     for (id, node) in &doc.nodes {
         timer.next();
         out.handle_node(*id, node);
-        for amenity in get_bldg_amenities(&node.tags) {
-            amenity_points.push((node.pt, amenity));
+        // Bike racks are usually on the street, so don't treat them like businesses
+        if node.tags.is("amenity", "bicycle_parking") {
+            extra_pois.push(ExtraPOI {
+                pt: node.pt,
+                kind: ExtraPOIType::BicycleParking(bicycle_parking_capacity(&node.tags)),
+            });
+        } else {
+            for amenity in get_bldg_amenities(&node.tags) {
+                amenity_points.push((node.pt, amenity));
+            }
         }
         if node.tags.is(osm::HIGHWAY, "crossing") {
             // TODO Look for crossing:signals:* too.
@@ -83,6 +97,16 @@ pub fn extract_osm(
         if node.tags.is("barrier", "bollard") {
             barrier_nodes.push((*id, node.pt.to_hashable()));
         }
+        if node.tags.is("kerb", "raised")
+            || (node.tags.is(osm::HIGHWAY, "crossing") && node.tags.is("wheelchair", "no"))
+        {
+            raised_kerbs.push((*id, node.pt.to_hashable()));
+        }
+        if node.tags.is("barrier", "gate")
+            && node.tags.is_any("access", vec!["private", "no", "permit"])
+        {
+            private_gates.push((*id, node.pt.to_hashable()));
+        }
 
         if node.tags.is("railway", "station") {
             if let Some(network) = node.tags.get("network") {
@@ -132,6 +156,10 @@ pub fn extract_osm(
             continue;
         };
 
+        if let Some(landuse) = LandUse::from_tags(&way.tags) {
+            map.landuse.push((polygon.clone(), landuse));
+        }
+
         if is_bldg(&way.tags) {
             map.buildings.insert(
                 OsmID::Way(id),
@@ -158,6 +186,11 @@ pub fn extract_osm(
             });
         } else if way.tags.is("historic", "memorial") {
             memorial_areas.push(polygon);
+        } else if way.tags.is("amenity", "bicycle_parking") {
+            extra_pois.push(ExtraPOI {
+                pt: polygon.center(),
+                kind: ExtraPOIType::BicycleParking(bicycle_parking_capacity(&way.tags)),
+            });
         } else if way.tags.contains_key("amenity") {
             let amenity = Amenity {
                 names: NamePerLanguage::new(&way.tags).unwrap_or_else(NamePerLanguage::unnamed),
@@ -177,7 +210,19 @@ pub fn extract_osm(
 
         if out.handle_relation(id, rel) {
             continue;
-        } else if let Some(area_type) = get_area_type(&rel.tags) {
+        }
+
+        if let Some(landuse) = LandUse::from_tags(&rel.tags) {
+            if rel.tags.is("type", "multipolygon") {
+                for polygon in
+                    glue_multipolygon(id, doc.get_multipolygon_members(id, rel), Some(&boundary))
+                {
+                    map.landuse.push((polygon, landuse));
+                }
+            }
+        }
+
+        if let Some(area_type) = get_area_type(&rel.tags) {
             if rel.tags.is("type", "multipolygon") {
                 for polygon in
                     glue_multipolygon(id, doc.get_multipolygon_members(id, rel), Some(&boundary))
@@ -324,6 +369,8 @@ pub fn extract_osm(
         bus_routes_on_roads,
         crossing_nodes,
         barrier_nodes,
+        raised_kerbs,
+        private_gates,
         extra_pois,
     }
 }
@@ -333,6 +380,13 @@ fn is_bldg(tags: &Tags) -> bool {
     tags.contains_key("building") && !tags.contains_key("abandoned:man_made")
 }
 
+/// Racks are often mapped without a capacity. Assume a single stand, which holds two bikes.
+fn bicycle_parking_capacity(tags: &Tags) -> usize {
+    tags.get("capacity")
+        .and_then(|x| x.parse::<usize>().ok())
+        .unwrap_or(2)
+}
+
 fn get_bldg_amenities(tags: &Tags) -> Vec<Amenity> {
     let mut amenities = Vec::new();
     for key in ["amenity", "shop", "craft", "office", "tourism", "leisure"] {
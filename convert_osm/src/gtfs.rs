@@ -2,14 +2,18 @@ use std::collections::{BTreeMap, HashMap, HashSet};
 
 use anyhow::Result;
 use fs_err::File;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 
 use abstutil::MultiMap;
-use geom::{LonLat, PolyLine, Pt2D};
+use geom::{Duration, LonLat, PolyLine, Pt2D, Time};
 use kml::{ExtraShape, ExtraShapes};
 use raw_map::{RawMap, RawTransitRoute, RawTransitStop, RawTransitType};
 
-pub fn import(map: &mut RawMap) -> Result<()> {
+use crate::DayOfWeek;
+
+/// Import transit routes from GTFS, with the service running on one day of the week.
+pub fn import(map: &mut RawMap, day: DayOfWeek) -> Result<()> {
     // Collect metadata about routes
     for rec in csv::Reader::from_reader(File::open(map.name.city.input_path("gtfs/routes.txt"))?)
         .deserialize()
@@ -34,17 +38,29 @@ pub fn import(map: &mut RawMap) -> Result<()> {
             shape: PolyLine::dummy(),
             stops: Vec::new(),
             route_type,
+            spawn_times: Vec::new(),
         });
     }
 
+    let active_services = active_services(map, day);
+
     // Map route_id to shape_id
     let mut route_to_shapes = MultiMap::new();
     // Map (route_id, shape_id) to trip_id
     let mut route_and_shape_to_trips = MultiMap::new();
+    // Only trips running on the chosen day
+    let mut active_trips = HashSet::new();
     for rec in csv::Reader::from_reader(File::open(map.name.city.input_path("gtfs/trips.txt"))?)
         .deserialize()
     {
         let rec: Trip = rec?;
+        if active_services
+            .as_ref()
+            .map(|services| services.contains(&rec.service_id))
+            .unwrap_or(true)
+        {
+            active_trips.insert(rec.trip_id.clone());
+        }
         route_to_shapes.insert(rec.route_id.clone(), rec.shape_id.clone());
         route_and_shape_to_trips.insert((rec.route_id, rec.shape_id), rec.trip_id);
     }
@@ -105,18 +121,23 @@ pub fn import(map: &mut RawMap) -> Result<()> {
     }
     map.transit_routes = transit_routes;
 
-    // For now, every route uses exactly one trip ID, and there's no schedule. Just pick an
-    // arbitrary trip per route.
-    let mut route_to_trip = HashMap::new();
+    // Every route uses exactly one shape, so only consider the trips following it. The stops
+    // come from an arbitrary one of those trips, preferring one running on the chosen day.
+    let mut route_to_trips: HashMap<RouteID, Vec<TripID>> = HashMap::new();
     for (route_id, shape_id) in &route_to_shape {
-        let trips = route_and_shape_to_trips.get((route_id.clone(), shape_id.clone()));
-        if let Some(trip_id) = trips.into_iter().next() {
-            route_to_trip.insert(route_id.clone(), trip_id);
+        let mut trips: Vec<TripID> = route_and_shape_to_trips
+            .get((route_id.clone(), shape_id.clone()))
+            .iter()
+            .cloned()
+            .collect();
+        if !trips.is_empty() {
+            trips.sort_by_key(|trip| !active_trips.contains(trip));
+            route_to_trips.insert(route_id.clone(), trips);
         }
     }
 
-    // Scrape the trip ID -> (stop ID, sequence number)
-    let mut trip_to_stops: HashMap<TripID, Vec<(StopID, usize)>> = HashMap::new();
+    // Scrape the trip ID -> (stop ID, sequence number, departure time)
+    let mut trip_to_stops: HashMap<TripID, Vec<(StopID, usize, String)>> = HashMap::new();
     for rec in
         csv::Reader::from_reader(File::open(map.name.city.input_path("gtfs/stop_times.txt"))?)
             .deserialize()
@@ -125,19 +146,77 @@ pub fn import(map: &mut RawMap) -> Result<()> {
         trip_to_stops
             .entry(rec.trip_id)
             .or_insert_with(Vec::new)
-            .push((rec.stop_id, rec.stop_sequence));
+            .push((rec.stop_id, rec.stop_sequence, rec.departure_time));
+    }
+    for stops in trip_to_stops.values_mut() {
+        stops.sort_by_key(|(_, seq, _)| *seq);
     }
 
-    // Assign the stops for every route
+    // Some trips are defined by a headway over a time range, instead of by fixed times
+    let mut frequencies: HashMap<TripID, Vec<(Time, Time, Duration)>> = HashMap::new();
+    if let Ok(f) = File::open(map.name.city.input_path("gtfs/frequencies.txt")) {
+        for rec in csv::Reader::from_reader(f).deserialize() {
+            let rec: Frequency = rec?;
+            if rec.headway_secs == 0 {
+                continue;
+            }
+            frequencies
+                .entry(rec.trip_id)
+                .or_insert_with(Vec::new)
+                .push((
+                    parse_time(&rec.start_time)?,
+                    parse_time(&rec.end_time)?,
+                    Duration::seconds(rec.headway_secs as f64),
+                ));
+        }
+    }
+
+    // Assign the stops and departure times for every route
     let mut stop_ids = HashSet::new();
     for route in &mut map.transit_routes {
-        let trip_id = route_to_trip[&RouteID(route.gtfs_id.clone())];
-        let mut stops = trip_to_stops.remove(&trip_id).unwrap_or_else(Vec::new);
-        stops.sort_by_key(|(_, seq)| *seq);
-        for (stop_id, _) in stops {
-            route.stops.push(stop_id.0.clone());
-            stop_ids.insert(stop_id);
+        let trips = match route_to_trips.get(&RouteID(route.gtfs_id.clone())) {
+            Some(trips) => trips,
+            None => continue,
+        };
+        if let Some(stops) = trip_to_stops.get(&trips[0]) {
+            for (stop_id, _, _) in stops {
+                route.stops.push(stop_id.0.clone());
+                stop_ids.insert(stop_id.clone());
+            }
         }
+
+        for trip in trips {
+            if !active_trips.contains(trip) {
+                continue;
+            }
+            if let Some(ranges) = frequencies.get(trip) {
+                for (start, end, headway) in ranges {
+                    let mut time = *start;
+                    while time < *end {
+                        route.spawn_times.push(time);
+                        time = time + *headway;
+                    }
+                }
+            } else if let Some(Ok(time)) = trip_to_stops
+                .get(trip)
+                .and_then(|stops| stops.first())
+                .map(|(_, _, time)| parse_time(time))
+            {
+                route.spawn_times.push(time);
+            }
+        }
+        route.spawn_times.sort();
+        route.spawn_times.dedup();
+    }
+    if active_services.is_some() {
+        map.transit_routes.retain(|route| {
+            if route.spawn_times.is_empty() {
+                warn!("Route {} doesn't run on {:?}", route.gtfs_id, day);
+                false
+            } else {
+                true
+            }
+        });
     }
 
     // Scrape stop metadata
@@ -187,6 +266,8 @@ struct TripID(String);
 struct StopID(String);
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
 struct RouteID(String);
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+struct ServiceID(String);
 
 #[derive(Deserialize)]
 struct Route {
@@ -204,6 +285,7 @@ struct Trip {
     route_id: RouteID,
     shape_id: ShapeID,
     trip_id: TripID,
+    service_id: ServiceID,
 }
 
 #[derive(Deserialize)]
@@ -227,6 +309,191 @@ struct StopTime {
     trip_id: TripID,
     stop_id: StopID,
     stop_sequence: usize,
+    // Optional for stops that aren't timepoints
+    #[serde(default)]
+    departure_time: String,
+}
+
+#[derive(Deserialize)]
+struct Frequency {
+    trip_id: TripID,
+    start_time: String,
+    end_time: String,
+    headway_secs: usize,
+}
+
+#[derive(Deserialize)]
+struct Calendar {
+    service_id: ServiceID,
+    monday: u8,
+    tuesday: u8,
+    wednesday: u8,
+    thursday: u8,
+    friday: u8,
+    saturday: u8,
+    sunday: u8,
+    start_date: u32,
+    end_date: u32,
+}
+
+impl Calendar {
+    fn runs_on(&self, day: DayOfWeek) -> bool {
+        1 == match day {
+            DayOfWeek::Monday => self.monday,
+            DayOfWeek::Tuesday => self.tuesday,
+            DayOfWeek::Wednesday => self.wednesday,
+            DayOfWeek::Thursday => self.thursday,
+            DayOfWeek::Friday => self.friday,
+            DayOfWeek::Saturday => self.saturday,
+            DayOfWeek::Sunday => self.sunday,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CalendarDate {
+    service_id: ServiceID,
+    date: u32,
+    /// 1 means the service is added on this date, and 2 means it's removed
+    exception_type: u8,
+}
+
+/// Find the services running on one day of the week. Returns `None` if there's no calendar at all,
+/// meaning every trip should be used.
+fn active_services(map: &RawMap, day: DayOfWeek) -> Option<HashSet<ServiceID>> {
+    let calendars = read_calendar(map.name.city.input_path("gtfs/calendar.txt"));
+    let exceptions = read_calendar(map.name.city.input_path("gtfs/calendar_dates.txt"));
+    if calendars.is_none() && exceptions.is_none() {
+        warn!(
+            "No GTFS calendar.txt or calendar_dates.txt, so using every trip regardless of the day"
+        );
+        return None;
+    }
+    Some(services_on(
+        day,
+        calendars.unwrap_or_default(),
+        exceptions.unwrap_or_default(),
+    ))
+}
+
+/// Returns `None` if the file doesn't exist
+fn read_calendar<T: DeserializeOwned>(path: String) -> Option<Vec<T>> {
+    let f = File::open(path).ok()?;
+    let mut results = Vec::new();
+    for rec in csv::Reader::from_reader(f).deserialize() {
+        match rec {
+            Ok(rec) => {
+                results.push(rec);
+            }
+            Err(err) => {
+                warn!("Skipping bad GTFS calendar entry: {}", err);
+            }
+        }
+    }
+    Some(results)
+}
+
+/// Feeds often describe several periods, like a summer and winter schedule, so one date is picked
+/// to avoid counting trips twice: the first one on this day of the week in the most recent period.
+/// Exceptions for that date, like holidays, are then applied. When there are only exceptions, the
+/// date on this day of the week with the most services is used.
+fn services_on(
+    day: DayOfWeek,
+    calendars: Vec<Calendar>,
+    exceptions: Vec<CalendarDate>,
+) -> HashSet<ServiceID> {
+    let calendars: Vec<Calendar> = calendars.into_iter().filter(|c| c.runs_on(day)).collect();
+    let date = match calendars.iter().map(|c| parse_date(c.start_date)).max() {
+        Some(mut date) => {
+            while day_of_week(date) != day {
+                date += 1;
+            }
+            date
+        }
+        None => {
+            let mut added_per_date: BTreeMap<i64, usize> = BTreeMap::new();
+            for exception in &exceptions {
+                let date = parse_date(exception.date);
+                if exception.exception_type == 1 && day_of_week(date) == day {
+                    *added_per_date.entry(date).or_insert(0) += 1;
+                }
+            }
+            // Break ties by using the latest date
+            match added_per_date
+                .into_iter()
+                .max_by_key(|(date, count)| (*count, *date))
+            {
+                Some((date, _)) => date,
+                None => {
+                    return HashSet::new();
+                }
+            }
+        }
+    };
+
+    let mut services: HashSet<ServiceID> = calendars
+        .into_iter()
+        .filter(|c| parse_date(c.start_date) <= date && date <= parse_date(c.end_date))
+        .map(|c| c.service_id)
+        .collect();
+    for exception in exceptions {
+        if parse_date(exception.date) != date {
+            continue;
+        }
+        match exception.exception_type {
+            1 => {
+                services.insert(exception.service_id);
+            }
+            2 => {
+                services.remove(&exception.service_id);
+            }
+            x => {
+                warn!("Skipping GTFS calendar exception with unknown type {}", x);
+            }
+        }
+    }
+    services
+}
+
+/// GTFS dates are YYYYMMDD. Returns days since 1970-01-01.
+fn parse_date(date: u32) -> i64 {
+    let date = date as i64;
+    days_since_epoch(date / 10000, (date / 100) % 100, date % 100)
+}
+
+/// From http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_since_epoch(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn day_of_week(days_since_epoch: i64) -> DayOfWeek {
+    // 1970-01-01 was a Thursday
+    match (days_since_epoch + 3).rem_euclid(7) {
+        0 => DayOfWeek::Monday,
+        1 => DayOfWeek::Tuesday,
+        2 => DayOfWeek::Wednesday,
+        3 => DayOfWeek::Thursday,
+        4 => DayOfWeek::Friday,
+        5 => DayOfWeek::Saturday,
+        _ => DayOfWeek::Sunday,
+    }
+}
+
+/// GTFS times are HH:MM:SS, and may exceed 24 hours for trips running past midnight.
+fn parse_time(x: &str) -> Result<Time> {
+    let parts: Vec<&str> = x.trim().split(':').collect();
+    if parts.len() != 3 {
+        bail!("Bad GTFS time {}", x);
+    }
+    let hours: usize = parts[0].parse()?;
+    let minutes: usize = parts[1].parse()?;
+    let seconds: usize = parts[2].parse()?;
+    Ok(Time::START_OF_DAY + Duration::seconds((hours * 3600 + minutes * 60 + seconds) as f64))
 }
 
 fn dump_kml(map: &RawMap) {
@@ -241,7 +508,11 @@ fn dump_kml(map: &RawMap) {
         attributes.insert("gtfs_id".to_string(), route.gtfs_id.clone());
         attributes.insert("num_stops".to_string(), route.stops.len().to_string());
         attributes.insert("route_type".to_string(), format!("{:?}", route.route_type));
-        shapes.push(ExtraShape { points, attributes });
+        shapes.push(ExtraShape {
+            points,
+            attributes,
+            properties: BTreeMap::new(),
+        });
     }
 
     // One point per stop
@@ -250,7 +521,11 @@ fn dump_kml(map: &RawMap) {
         attributes.insert("gtfs_id".to_string(), stop.gtfs_id.clone());
         attributes.insert("name".to_string(), stop.name.clone());
         let points = vec![stop.position.to_gps(&map.streets.gps_bounds)];
-        shapes.push(ExtraShape { points, attributes });
+        shapes.push(ExtraShape {
+            points,
+            attributes,
+            properties: BTreeMap::new(),
+        });
     }
 
     abstio::write_binary(
@@ -260,3 +535,138 @@ fn dump_kml(map: &RawMap) {
         &ExtraShapes { shapes },
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calendar(service: &str, weekdays: bool, weekends: bool, start: u32, end: u32) -> Calendar {
+        let weekday = u8::from(weekdays);
+        let weekend = u8::from(weekends);
+        Calendar {
+            service_id: ServiceID(service.to_string()),
+            monday: weekday,
+            tuesday: weekday,
+            wednesday: weekday,
+            thursday: weekday,
+            friday: weekday,
+            saturday: weekend,
+            sunday: weekend,
+            start_date: start,
+            end_date: end,
+        }
+    }
+
+    fn exception(service: &str, date: u32, exception_type: u8) -> CalendarDate {
+        CalendarDate {
+            service_id: ServiceID(service.to_string()),
+            date,
+            exception_type,
+        }
+    }
+
+    fn services(list: &[&str]) -> HashSet<ServiceID> {
+        list.iter().map(|x| ServiceID(x.to_string())).collect()
+    }
+
+    #[test]
+    fn test_day_of_week() {
+        assert_eq!(day_of_week(parse_date(19700101)), DayOfWeek::Thursday);
+        assert_eq!(day_of_week(parse_date(20240101)), DayOfWeek::Monday);
+        assert_eq!(day_of_week(parse_date(20240229)), DayOfWeek::Thursday);
+        assert_eq!(day_of_week(parse_date(20240407)), DayOfWeek::Sunday);
+    }
+
+    #[test]
+    fn test_weekday_masks() {
+        let calendars = || {
+            vec![
+                calendar("weekday", true, false, 20240101, 20241231),
+                calendar("weekend", false, true, 20240101, 20241231),
+            ]
+        };
+        assert_eq!(
+            services_on(DayOfWeek::Wednesday, calendars(), Vec::new()),
+            services(&["weekday"])
+        );
+        assert_eq!(
+            services_on(DayOfWeek::Sunday, calendars(), Vec::new()),
+            services(&["weekend"])
+        );
+    }
+
+    #[test]
+    fn test_date_ranges() {
+        let calendars = || {
+            vec![
+                calendar("winter", true, true, 20240101, 20240331),
+                calendar("summer", true, false, 20240401, 20240930),
+                calendar("summer_weekend", false, true, 20240401, 20240930),
+            ]
+        };
+        // Only the most recent period counts
+        assert_eq!(
+            services_on(DayOfWeek::Wednesday, calendars(), Vec::new()),
+            services(&["summer"])
+        );
+        assert_eq!(
+            services_on(DayOfWeek::Saturday, calendars(), Vec::new()),
+            services(&["summer_weekend"])
+        );
+    }
+
+    #[test]
+    fn test_exceptions() {
+        let calendars = || {
+            vec![
+                calendar("winter", true, false, 20240101, 20240331),
+                calendar("summer", true, false, 20240401, 20240930),
+            ]
+        };
+        // The first Wednesday of summer is 20240403. Swap the normal service for a holiday one.
+        // Exceptions on other days don't matter.
+        let exceptions = vec![
+            exception("summer", 20240403, 2),
+            exception("holiday", 20240403, 1),
+            exception("extra", 20240410, 1),
+            exception("winter", 20240403, 1),
+        ];
+        assert_eq!(
+            services_on(DayOfWeek::Wednesday, calendars(), exceptions),
+            services(&["holiday", "winter"])
+        );
+        assert_eq!(
+            services_on(
+                DayOfWeek::Thursday,
+                calendars(),
+                vec![exception("summer", 20240403, 2)]
+            ),
+            services(&["summer"])
+        );
+    }
+
+    #[test]
+    fn test_only_exceptions() {
+        let exceptions = || {
+            vec![
+                exception("a", 20240403, 1),
+                exception("a", 20240410, 1),
+                exception("b", 20240410, 1),
+                // A Saturday
+                exception("c", 20240406, 1),
+                exception("c", 20240413, 1),
+                exception("d", 20240413, 1),
+            ]
+        };
+        // The Wednesday with the most service
+        assert_eq!(
+            services_on(DayOfWeek::Wednesday, Vec::new(), exceptions()),
+            services(&["a", "b"])
+        );
+        assert_eq!(
+            services_on(DayOfWeek::Saturday, Vec::new(), exceptions()),
+            services(&["c", "d"])
+        );
+        assert!(services_on(DayOfWeek::Monday, Vec::new(), exceptions()).is_empty());
+    }
+}
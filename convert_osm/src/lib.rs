@@ -29,6 +29,8 @@ pub struct Options {
     pub extra_buildings: Option<String>,
     /// Configure public transit using this URL to a static GTFS feed in .zip format.
     pub gtfs_url: Option<String>,
+    /// A simulation covers a single day, so only import the transit service running on this day.
+    pub gtfs_service_day: DayOfWeek,
     pub elevation: bool,
     /// Only include crosswalks that match a `highway=crossing` OSM node.
     pub filter_crosswalks: bool,
@@ -43,12 +45,24 @@ impl Options {
             private_offstreet_parking: PrivateOffstreetParking::FixedPerBldg(1),
             extra_buildings: None,
             gtfs_url: None,
+            gtfs_service_day: DayOfWeek::Wednesday,
             elevation: false,
             filter_crosswalks: false,
         }
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DayOfWeek {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
 /// What roads will have on-street parking lanes? Data from
 /// <https://wiki.openstreetmap.org/wiki/Key:parking:lane> is always used if available.
 pub enum OnstreetParking {
@@ -131,7 +145,9 @@ pub fn convert(
 
     timer.start("use barrier and crossing nodes");
     use_barrier_nodes(&mut map, extract.barrier_nodes, &pt_to_road);
+    use_private_gates(&mut map, extract.private_gates, &pt_to_road);
     use_crossing_nodes(&mut map, &extract.crossing_nodes, &pt_to_road);
+    use_raised_kerbs(&mut map, extract.raised_kerbs, &pt_to_road);
     timer.stop("use barrier and crossing nodes");
 
     if opts.filter_crosswalks {
@@ -150,7 +166,7 @@ pub fn convert(
     }
 
     if opts.gtfs_url.is_some() {
-        gtfs::import(&mut map).unwrap();
+        gtfs::import(&mut map, opts.gtfs_service_day).unwrap();
     }
 
     timer.start("Add census data");
@@ -330,6 +346,40 @@ fn use_barrier_nodes(
     }
 }
 
+fn use_private_gates(
+    map: &mut RawMap,
+    private_gates: Vec<(osm::NodeID, HashablePt2D)>,
+    pt_to_road: &HashMap<HashablePt2D, RoadID>,
+) {
+    let mut node_to_intersection = HashMap::new();
+    for i in map.streets.intersections.values() {
+        for node in &i.osm_ids {
+            node_to_intersection.insert(*node, i.id);
+        }
+    }
+
+    for (node, pt) in private_gates {
+        if let Some(data) = pt_to_road
+            .get(&pt)
+            .and_then(|r| map.extra_road_data.get_mut(r))
+        {
+            data.private_gate = true;
+        } else if let Some(i) = node_to_intersection.get(&node) {
+            // Gates are often mapped right where a private service road meets a public street. Only
+            // close off the service roads.
+            for r in &map.streets.intersections[i].roads {
+                if map
+                    .road_to_osm_tags(*r)
+                    .map(|tags| tags.is(osm::HIGHWAY, "service"))
+                    .unwrap_or(false)
+                {
+                    map.extra_road_data.get_mut(r).unwrap().private_gate = true;
+                }
+            }
+        }
+    }
+}
+
 fn use_crossing_nodes(
     map: &mut RawMap,
     crossing_nodes: &HashSet<(HashablePt2D, CrossingType)>,
@@ -346,6 +396,46 @@ fn use_crossing_nodes(
     }
 }
 
+fn use_raised_kerbs(
+    map: &mut RawMap,
+    raised_kerbs: Vec<(osm::NodeID, HashablePt2D)>,
+    pt_to_road: &HashMap<HashablePt2D, RoadID>,
+) {
+    let mut node_to_intersection = HashMap::new();
+    for i in map.streets.intersections.values() {
+        for node in &i.osm_ids {
+            node_to_intersection.insert(*node, i.id);
+        }
+    }
+
+    for (node, pt) in raised_kerbs {
+        if let Some(road) = pt_to_road
+            .get(&pt)
+            .and_then(|r| map.extra_road_data.get_mut(r))
+        {
+            road.raised_kerbs.push(pt.to_pt2d());
+        } else if let Some(i) = node_to_intersection.get(&node) {
+            // Kerbs are usually tagged where a separately mapped crossing meets the sidewalk. The
+            // kerb belongs to the crossing.
+            for r in &map.streets.intersections[i].roads {
+                let is_crossing = map.streets.roads[r]
+                    .osm_ids
+                    .get(0)
+                    .and_then(|id| map.osm_tags.get(id))
+                    .map(|tags| tags.is("footway", "crossing"))
+                    .unwrap_or(false);
+                if is_crossing {
+                    map.extra_road_data
+                        .get_mut(r)
+                        .unwrap()
+                        .raised_kerbs
+                        .push(pt.to_pt2d());
+                }
+            }
+        }
+    }
+}
+
 fn filter_crosswalks(
     map: &mut RawMap,
     crosswalks: HashSet<(HashablePt2D, CrossingType)>,
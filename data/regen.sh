@@ -7,6 +7,7 @@ rm -fv data/input/us/seattle/raw_maps/huge_seattle.bin data/system/us/seattle/ma
 
 RUST_BACKTRACE=1 cargo run --release --bin cli --features importer/scenarios -- regenerate-everything
 # Or more efficiently:
+# ./target/release/cli regenerate-everything --num-workers=16
 # ./target/release/cli regenerate-everything-externally
 
 # If a map changes that has external JSON scenarios, enable this!
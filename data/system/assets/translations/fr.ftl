@@ -0,0 +1,45 @@
+# French translation of the UI. To add another language, copy this file to <code>.ftl (like
+# de.ftl or pt-BR.ftl) and translate each message. Anything missing falls back to English. Keep
+# placeholders like { $map } as they are.
+
+options-title = Paramètres
+options-camera-controls = Contrôles de la caméra
+options-invert-scroll = Inverser le sens du défilement vertical
+options-autopan = Déplacer la carte quand le curseur est au bord de l'écran
+options-touchpad = Utiliser le pavé tactile pour déplacer et Contrôle pour zoomer
+options-keys-to-pan = Utiliser les flèches pour déplacer et Q/W pour zoomer
+options-gui-scroll-speed = Vitesse de défilement des menus
+options-canvas-scroll-speed = Vitesse de zoom de la carte
+options-appearance = Apparence
+options-traffic-signals = Affichage des feux :
+options-camera-angle = Angle de la caméra :
+options-color-scheme = Couleurs :
+options-customize-colors = Personnaliser les couleurs
+options-min-zoom = Zoom à partir duquel la carte est détaillée
+options-ui-language = Langue de l'interface
+options-map-language = Langue des noms sur la carte
+options-map-native-language = Langue locale de la carte
+options-colorblind-safe = Utiliser des couleurs adaptées au daltonisme
+options-pattern-fills = Dessiner des motifs pour distinguer les catégories
+options-debug = Débogage
+options-dev-mode = Activer le mode développeur
+options-apply = Appliquer
+
+challenge-editor-title = Créer un défi
+challenge-editor-map = Carte : { $map }
+challenge-editor-change-map = changer de carte
+challenge-editor-name = Nom :
+challenge-editor-description = Description :
+challenge-editor-no-scenarios = Cette carte n'a pas encore de scénario
+challenge-editor-scenario = Scénario :
+challenge-editor-goal = Objectif :
+challenge-editor-goal-neighborhood = réduire le trafic dans un quartier
+challenge-editor-goal-time = faire gagner du temps sur tous les trajets
+challenge-editor-draw = dessiner le quartier
+challenge-editor-zone-chosen = Quartier choisi
+challenge-editor-no-zone = Aucun quartier choisi
+challenge-editor-min-reduction = Réduire le trafic des rues locales d'au moins (%) :
+challenge-editor-max-delay-increase = Sans augmenter les retards sur les grands axes de plus de (%) :
+challenge-editor-min-savings = Réduire la durée totale des trajets d'au moins :
+challenge-editor-explanation = Les joueurs modifient la carte, puis lancent le scénario jusqu'à { $time }. L'enregistrement mesure d'abord la carte sans modifications, ce qui peut prendre du temps.
+challenge-editor-save = enregistrer
@@ -8,6 +8,21 @@
 //! it's now 01:01:00.0
 //! > curl http://localhost:1234/data/get-road-thruput
 //! ... huge JSON blob
+//!
+//! Passing --ws-port=1235 also streams agent positions and finished trips over a WebSocket at
+//! ws://localhost:1235, emitting one JSON frame every --stream-period-secs of simulated time while
+//! the sim advances.
+//!
+//! http://localhost:1234/metrics exposes live counters in the Prometheus text format, so long runs
+//! can be scraped and alerted on (for example, when abst_seconds_since_sim_advanced grows).
+//!
+//! Passing --webhook-url=https://... POSTs a JSON summary there every --webhook-period-secs of
+//! simulated time, and once more when every trip is done. The summary has a `text` field, so it can
+//! go straight to a Slack incoming webhook.
+//!
+//! http://localhost:1234/data/get-memory-usage estimates how much memory the map, pathfinding
+//! graphs, agents, and analytics use. Passing --profile-memory also logs this after loading and
+//! after every /sim/goto-time.
 
 #[macro_use]
 extern crate anyhow;
@@ -15,21 +30,29 @@ extern crate anyhow;
 extern crate log;
 
 use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::RwLock;
+use std::time::Instant;
 
 use anyhow::Result;
+use futures::SinkExt;
 use hyper::{Body, Request, Response, Server, StatusCode};
 use rand::SeedableRng;
 use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::Message;
 
 use abstio::MapName;
-use abstutil::{serialize_btreemap, Timer};
-use geom::{Distance, Duration, FindClosest, LonLat, Time};
+use abstutil::{prettyprint_usize, serialize_btreemap, MemoryReport, Timer};
+use geom::{Distance, Duration, FindClosest, LonLat, Ring, Time};
+use map_model::connectivity::{Isochrone, Spot, WalkingOptions};
 use map_model::{
-    CompressedMovementID, ControlTrafficSignal, EditIntersectionControl, IntersectionID, Map,
-    MovementID, PermanentMapEdits, RoadID, TurnID,
+    BuildingID, CompressedMovementID, ControlTrafficSignal, EditIntersectionControl,
+    IntersectionID, Map, MovementID, PathRequest, PathStepV2, PathfinderCaching, PermanentMapEdits,
+    RoadID, TransitFare, TurnID,
 };
 use sim::{
     AgentID, AgentType, DelayCause, PersonID, Sim, SimFlags, SimOptions, TripID, VehicleType,
@@ -48,8 +71,33 @@ lazy_static::lazy_static! {
             opts: SimOptions::default(),
         }
     });
+    // Every WebSocket client subscribes to this. Lagging clients just miss frames.
+    static ref STREAM_TX: broadcast::Sender<String> = broadcast::channel(16).0;
+    static ref STREAM: RwLock<StreamState> = RwLock::new(StreamState {
+        period: Duration::minutes(1),
+        num_finished_trips_sent: 0,
+    });
+    // Answering /metrics can't wait for SIM, which is locked while the sim runs, so keep a copy
+    static ref METRICS: RwLock<Metrics> = RwLock::new(Metrics {
+        time: Time::START_OF_DAY,
+        agents: Vec::new(),
+        trips_finished: 0,
+        trips_unfinished: 0,
+        alerts: 0,
+        realtime_ratio: 0.0,
+        last_advanced: None,
+    });
+    static ref WEBHOOK: RwLock<WebhookState> = RwLock::new(WebhookState {
+        url: None,
+        period: Duration::hours(1),
+        next_progress: Time::START_OF_DAY,
+        sent_finished: false,
+        loaded_at: Instant::now(),
+    });
 }
 
+static PROFILE_MEMORY: AtomicBool = AtomicBool::new(false);
+
 #[derive(StructOpt)]
 #[structopt(
     name = "headless",
@@ -64,6 +112,23 @@ struct Args {
     // TODO default_value can only handle strings, so copying SimFlags::RNG_SEED
     #[structopt(long, default_value = "42")]
     rng_seed: u64,
+    /// If specified, also stream the live simulation state over a WebSocket on this port.
+    #[structopt(long)]
+    ws_port: Option<u16>,
+    /// How much simulated time passes between each frame sent to WebSocket clients.
+    #[structopt(long, default_value = "60")]
+    stream_period_secs: f64,
+    /// If specified, POST a JSON summary of progress to this URL periodically, and again when all
+    /// trips are done.
+    #[structopt(long)]
+    webhook_url: Option<String>,
+    /// How much simulated time passes between each progress summary sent to the webhook.
+    #[structopt(long, default_value = "3600")]
+    webhook_period_secs: f64,
+    /// Log an estimate of memory used by each subsystem after loading a simulation and after
+    /// advancing time.
+    #[structopt(long)]
+    profile_memory: bool,
     #[structopt(flatten)]
     opts: SimOptions,
 }
@@ -72,6 +137,7 @@ struct Args {
 async fn main() {
     abstutil::logger::setup();
     let args = Args::from_args();
+    PROFILE_MEMORY.store(args.profile_memory, Ordering::Relaxed);
 
     {
         let mut load = LOAD.write().unwrap();
@@ -79,9 +145,22 @@ async fn main() {
         load.opts = args.opts;
 
         let (map, sim) = load.setup(&mut Timer::new("setup headless"));
+        maybe_log_memory(&sim, &map);
         *MAP.write().unwrap() = map;
+        METRICS.write().unwrap().update(&sim, None);
         *SIM.write().unwrap() = sim;
     }
+    STREAM.write().unwrap().period = Duration::seconds(args.stream_period_secs);
+    {
+        let mut webhook = WEBHOOK.write().unwrap();
+        webhook.url = args.webhook_url;
+        webhook.period = Duration::seconds(args.webhook_period_secs);
+        webhook.reset();
+    }
+
+    if let Some(port) = args.ws_port {
+        tokio::spawn(serve_websockets(port));
+    }
 
     let addr = std::net::SocketAddr::from(([127, 0, 0, 1], args.port));
     info!("Listening on http://{}", addr);
@@ -103,26 +182,33 @@ async fn serve_req(req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
             .map(|(k, v)| (k.to_string(), v.to_string()))
             .collect();
     let body = hyper::body::to_bytes(req).await?.to_vec();
+    if path == "/metrics" {
+        return Ok(Response::builder()
+            .header("Content-Type", "text/plain; version=0.0.4")
+            .body(Body::from(METRICS.read().unwrap().render()))
+            .unwrap());
+    }
     info!("Handling {}", path);
-    Ok(
-        match handle_command(
-            &path,
-            &params,
-            &body,
-            &mut SIM.write().unwrap(),
-            &mut MAP.write().unwrap(),
-            &mut LOAD.write().unwrap(),
-        ) {
-            Ok(resp) => Response::new(Body::from(resp)),
-            Err(err) => {
-                error!("{}: {}", path, err);
-                Response::builder()
-                    .status(StatusCode::BAD_REQUEST)
-                    .body(Body::from(format!("Bad command {}: {}", path, err)))
-                    .unwrap()
-            }
-        },
-    )
+    let mut sim = SIM.write().unwrap();
+    let result = handle_command(
+        &path,
+        &params,
+        &body,
+        &mut sim,
+        &mut MAP.write().unwrap(),
+        &mut LOAD.write().unwrap(),
+    );
+    METRICS.write().unwrap().update(&sim, None);
+    Ok(match result {
+        Ok(resp) => Response::new(Body::from(resp)),
+        Err(err) => {
+            error!("{}: {}", path, err);
+            Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::from(format!("Bad command {}: {}", path, err)))
+                .unwrap()
+        }
+    })
 }
 
 fn handle_command(
@@ -145,6 +231,8 @@ fn handle_command(
             let (new_map, new_sim) = load.setup(&mut Timer::new("reset sim"));
             *map = new_map;
             *sim = new_sim;
+            WEBHOOK.write().unwrap().reset();
+            maybe_log_memory(sim, map);
             Ok("sim reloaded".to_string())
         }
         "/sim/load" => {
@@ -158,6 +246,8 @@ fn handle_command(
             let (new_map, new_sim) = load.setup(&mut Timer::new("reset sim"));
             *map = new_map;
             *sim = new_sim;
+            WEBHOOK.write().unwrap().reset();
+            maybe_log_memory(sim, map);
 
             Ok("flags changed and sim reloaded".to_string())
         }
@@ -165,6 +255,8 @@ fn handle_command(
             *map =
                 Map::load_synchronously(get("map")?.to_string(), &mut Timer::new("load new map"));
             *sim = Sim::new(&map, SimOptions::default());
+            WEBHOOK.write().unwrap().reset();
+            maybe_log_memory(sim, map);
             Ok("map changed, blank simulation".to_string())
         }
         "/sim/get-time" => Ok(sim.time().to_string()),
@@ -173,8 +265,28 @@ fn handle_command(
             if t <= sim.time() {
                 bail!("{} is in the past. call /sim/reset first?", t)
             } else {
-                let dt = t - sim.time();
-                sim.timed_step(map, dt, &mut None, &mut Timer::new("goto-time"));
+                // Step in smaller increments, so /metrics stays live and anybody watching the
+                // stream gets a frame after each one
+                let period = STREAM.read().unwrap().period;
+                let mut timer = Timer::new("goto-time");
+                while sim.time() < t {
+                    let dt = if t - sim.time() < period {
+                        t - sim.time()
+                    } else {
+                        period
+                    };
+                    let started = Instant::now();
+                    sim.timed_step(map, dt, &mut None, &mut timer);
+                    METRICS.write().unwrap().update(sim, Some((dt, started)));
+                    if STREAM_TX.receiver_count() > 0 {
+                        broadcast_frame(sim, map);
+                    }
+                    WEBHOOK
+                        .write()
+                        .unwrap()
+                        .maybe_notify(sim, &METRICS.read().unwrap());
+                }
+                maybe_log_memory(sim, map);
                 Ok(format!("it's now {}", t))
             }
         }
@@ -238,15 +350,13 @@ fn handle_command(
             for m in i.movements.keys() {
                 delays.per_direction.insert(*m, Vec::new());
             }
-            if let Some(list) = sim.get_analytics().intersection_delays.get(&i.id) {
-                for (idx, t, dt, _) in list {
-                    if *t >= t1 && *t <= t2 {
-                        delays
-                            .per_direction
-                            .get_mut(movements[*idx as usize])
-                            .unwrap()
-                            .push(*dt);
-                    }
+            for (idx, t, dt, _) in sim.get_analytics().intersection_delays_for(i.id).iter() {
+                if *t >= t1 && *t <= t2 {
+                    delays
+                        .per_direction
+                        .get_mut(movements[*idx as usize])
+                        .unwrap()
+                        .push(*dt);
                 }
             }
             Ok(abstutil::to_json(&delays))
@@ -317,19 +427,7 @@ fn handle_command(
             Ok(abstutil::to_json(&trips))
         }
         "/data/get-agent-positions" => Ok(abstutil::to_json(&AgentPositions {
-            agents: sim
-                .get_unzoomed_agents(map)
-                .into_iter()
-                .chain(sim.get_unzoomed_transit_riders(map))
-                .map(|a| AgentPosition {
-                    id: a.id,
-                    trip: sim.agent_to_trip(a.id),
-                    person: a.person,
-                    vehicle_type: a.id.to_vehicle_type(),
-                    pos: a.pos.to_gps(map.get_gps_bounds()),
-                    distance_crossed: sim.agent_properties(map, a.id).dist_crossed,
-                })
-                .collect(),
+            agents: get_agent_positions(sim, map),
         })),
         "/data/get-road-thruput" => Ok(abstutil::to_json(&RoadThroughput {
             counts: sim
@@ -357,6 +455,10 @@ fn handle_command(
             let duration = sim.get_trip_time_lower_bound(map, id)?;
             Ok(duration.inner_seconds().to_string())
         }
+        "/data/explain-trip-delay" => {
+            let id = TripID(get("id")?.parse::<usize>()?);
+            Ok(abstutil::to_json(&sim.explain_trip_delay(map, id)?))
+        }
         "/data/all-trip-time-lower-bounds" => {
             let results: BTreeMap<TripID, Duration> = Timer::throwaway()
                 .parallelize(
@@ -373,6 +475,23 @@ fn handle_command(
                 .collect();
             Ok(abstutil::to_json(&results))
         }
+        "/data/get-memory-usage" => Ok(abstutil::to_json(&memory_report(sim, map))),
+        "/data/get-transit-revenue" => {
+            let analytics = sim.get_analytics();
+            let mut routes = Vec::new();
+            for (id, (riders, revenue)) in analytics.ridership_and_revenue(sim.time()) {
+                let route = map.get_tr(id);
+                routes.push(RouteRevenue {
+                    gtfs_id: route.gtfs_id.clone(),
+                    name: route.short_name.clone(),
+                    fare: route.fare.clone(),
+                    riders,
+                    revenue,
+                    hourly: analytics.hourly_ridership_and_revenue(sim.time(), id),
+                });
+            }
+            Ok(abstutil::to_json(&routes))
+        }
         // Controlling the map
         "/map/get-edits" => {
             let mut edits = map.get_edits().clone();
@@ -403,12 +522,174 @@ fn handle_command(
                 None => bail!("No road within {} of {}", threshold, pt),
             }
         }
+        "/map/find-route" => {
+            let query: RouteQuery = abstutil::from_json(body)?;
+            if query.mode == TripMode::Transit {
+                bail!("Routing between buildings by transit isn't supported");
+            }
+            let req = PathRequest::between_buildings(
+                map,
+                query.from,
+                query.to,
+                query.mode.to_constraints(),
+            )
+            .ok_or_else(|| anyhow!("{} can't go between these buildings", query.mode.noun()))?;
+
+            let mut params = map.routing_params_respecting_modal_filters();
+            params.avoid_roads.extend(query.avoid_roads);
+            if let Some(pts) = query.avoid_region {
+                let region = Ring::deduping_new(
+                    pts.into_iter()
+                        .map(|pt| pt.to_pt(map.get_gps_bounds()))
+                        .collect(),
+                )?
+                .into_polygon();
+                params.avoid_region(map, &region);
+            }
+            params.road_penalties.extend(query.penalize_roads);
+
+            let path = map.pathfind_v2_with_params(req, &params, PathfinderCaching::NoCache)?;
+            let cost = path.get_cost();
+            let mut roads: Vec<RoadID> = Vec::new();
+            for step in path.get_steps() {
+                if let PathStepV2::Along(dr) | PathStepV2::Contraflow(dr) = step {
+                    if roads.last() != Some(&dr.road) {
+                        roads.push(dr.road);
+                    }
+                }
+            }
+            let distance = path.into_v1(map)?.total_length();
+            Ok(abstutil::to_json(&Route {
+                roads,
+                cost,
+                distance,
+            }))
+        }
+        "/map/isochrone" => {
+            let query: IsochroneQuery = abstutil::from_json(body)?;
+            if query.mode == TripMode::Transit {
+                bail!("Isochrones by transit aren't supported");
+            }
+            let time_limit = Duration::minutes(query.minutes);
+            let starts = query.from.into_iter().map(Spot::Building).collect();
+            let isochrone = if query.wheelchair {
+                if query.mode != TripMode::Walk {
+                    bail!("The wheelchair profile only applies to walking");
+                }
+                Isochrone::walking(
+                    map,
+                    starts,
+                    time_limit,
+                    WalkingOptions {
+                        wheelchair: true,
+                        ..WalkingOptions::default()
+                    },
+                )
+            } else {
+                Isochrone::new(map, starts, query.mode.to_constraints(), time_limit)?
+            };
+
+            let mut thresholds: Vec<Duration> = query
+                .threshold_minutes
+                .into_iter()
+                .map(Duration::minutes)
+                .filter(|t| *t <= time_limit)
+                .collect();
+            if thresholds.is_empty() {
+                thresholds.push(time_limit);
+            }
+            let mut pairs = Vec::new();
+            for (threshold, polygons) in thresholds.iter().zip(isochrone.polygons(map, &thresholds))
+            {
+                for polygon in polygons {
+                    let mut props = serde_json::Map::new();
+                    props.insert(
+                        "minutes".to_string(),
+                        (threshold.inner_seconds() / 60.0).into(),
+                    );
+                    pairs.push((polygon.to_geojson(Some(map.get_gps_bounds())), props));
+                }
+            }
+
+            let mut buildings: Vec<(BuildingID, Duration)> =
+                isochrone.time_to_reach_building.into_iter().collect();
+            buildings.sort();
+            Ok(abstutil::to_json(&Reachable {
+                buildings,
+                roads: isochrone.roads.into_iter().collect(),
+                contours: geom::geometries_with_properties_to_geojson(pairs),
+            }))
+        }
         _ => Err(anyhow!("Unknown command")),
     }
 }
 
+fn get_agent_positions(sim: &Sim, map: &Map) -> Vec<AgentPosition> {
+    sim.get_unzoomed_agents(map)
+        .into_iter()
+        .chain(sim.get_unzoomed_transit_riders(map))
+        .map(|a| AgentPosition {
+            id: a.id,
+            trip: sim.agent_to_trip(a.id),
+            person: a.person,
+            vehicle_type: a.id.to_vehicle_type(),
+            pos: a.pos.to_gps(map.get_gps_bounds()),
+            distance_crossed: sim.agent_properties(map, a.id).dist_crossed,
+        })
+        .collect()
+}
+
 // TODO I think specifying the API with protobufs or similar will be a better idea.
 
+#[derive(Deserialize)]
+struct RouteQuery {
+    from: BuildingID,
+    to: BuildingID,
+    mode: TripMode,
+    /// Never cross these roads
+    #[serde(default)]
+    avoid_roads: Vec<RoadID>,
+    /// Never cross any road inside this polygon
+    #[serde(default)]
+    avoid_region: Option<Vec<LonLat>>,
+    /// Multiply the cost of crossing these roads
+    #[serde(default)]
+    penalize_roads: Vec<(RoadID, f64)>,
+}
+
+#[derive(Serialize)]
+struct Route {
+    /// The roads crossed, in order
+    roads: Vec<RoadID>,
+    /// The pathfinding cost, including any penalties
+    cost: Duration,
+    distance: Distance,
+}
+
+#[derive(Deserialize)]
+struct IsochroneQuery {
+    from: Vec<BuildingID>,
+    mode: TripMode,
+    /// The time budget
+    minutes: usize,
+    /// Produce contours for each of these, in minutes. Defaults to the whole time budget.
+    #[serde(default)]
+    threshold_minutes: Vec<usize>,
+    /// When walking, avoid steps, steep grades, rough surfaces, and raised kerbs
+    #[serde(default)]
+    wheelchair: bool,
+}
+
+#[derive(Serialize)]
+struct Reachable {
+    /// Every reachable building and how long it takes to get there
+    buildings: Vec<(BuildingID, Duration)>,
+    /// The roads the reachable buildings are on
+    roads: Vec<RoadID>,
+    /// Polygons covering the buildings reachable within each threshold, with a `minutes` property
+    contours: geojson::GeoJson,
+}
+
 #[derive(Serialize)]
 struct FinishedTrip {
     id: TripID,
@@ -461,12 +742,231 @@ struct AgentPosition {
     distance_crossed: Distance,
 }
 
+struct StreamState {
+    period: Duration,
+    /// How many of the sim's finished trips have already been sent to clients
+    num_finished_trips_sent: usize,
+}
+
+/// A snapshot of the live sim, for /metrics
+struct Metrics {
+    time: Time,
+    agents: Vec<(AgentType, usize)>,
+    trips_finished: usize,
+    trips_unfinished: usize,
+    alerts: usize,
+    /// Simulated seconds per wall-clock second, over the most recent step
+    realtime_ratio: f64,
+    /// When the sim time last moved forward
+    last_advanced: Option<Instant>,
+}
+
+impl Metrics {
+    fn update(&mut self, sim: &Sim, step: Option<(Duration, Instant)>) {
+        if sim.time() != self.time {
+            self.last_advanced = Some(Instant::now());
+        }
+        if let Some((dt, started)) = step {
+            let elapsed = started.elapsed().as_secs_f64();
+            if elapsed > 0.0 {
+                self.realtime_ratio = dt.inner_seconds() / elapsed;
+            }
+        }
+
+        self.time = sim.time();
+        let counts = sim.num_agents();
+        self.agents = AgentType::all()
+            .into_iter()
+            .map(|agent_type| (agent_type, counts.get(agent_type)))
+            .collect();
+        let (finished, unfinished) = sim.num_trips();
+        self.trips_finished = finished;
+        self.trips_unfinished = unfinished;
+        self.alerts = sim.get_analytics().num_alerts;
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, values: Vec<(String, f64)>| {
+            writeln!(out, "# HELP {} {}", name, help).unwrap();
+            writeln!(out, "# TYPE {} {}", name, kind).unwrap();
+            for (labels, value) in values {
+                writeln!(out, "{}{} {}", name, labels, value).unwrap();
+            }
+        };
+
+        metric(
+            "abst_sim_time_seconds",
+            "gauge",
+            "Simulated time since midnight",
+            vec![(
+                String::new(),
+                (self.time - Time::START_OF_DAY).inner_seconds(),
+            )],
+        );
+        metric(
+            "abst_agents_active",
+            "gauge",
+            "Agents currently in the simulation",
+            self.agents
+                .iter()
+                .map(|(agent_type, count)| {
+                    (format!("{{type=\"{}\"}}", agent_type.noun()), *count as f64)
+                })
+                .collect(),
+        );
+        metric(
+            "abst_trips_finished_total",
+            "counter",
+            "Trips that have finished or been cancelled",
+            vec![(String::new(), self.trips_finished as f64)],
+        );
+        metric(
+            "abst_trips_unfinished",
+            "gauge",
+            "Trips that haven't started or are still underway",
+            vec![(String::new(), self.trips_unfinished as f64)],
+        );
+        metric(
+            "abst_alerts_total",
+            "counter",
+            "Alerts raised by the simulation, such as gridlock",
+            vec![(String::new(), self.alerts as f64)],
+        );
+        metric(
+            "abst_realtime_ratio",
+            "gauge",
+            "Simulated seconds per wall-clock second, over the most recent step",
+            vec![(String::new(), self.realtime_ratio)],
+        );
+        if let Some(last) = self.last_advanced {
+            metric(
+                "abst_seconds_since_sim_advanced",
+                "gauge",
+                "Wall-clock seconds since simulated time last moved forward",
+                vec![(String::new(), last.elapsed().as_secs_f64())],
+            );
+        }
+        out
+    }
+}
+
+struct WebhookState {
+    url: Option<String>,
+    period: Duration,
+    /// When the next progress summary is due
+    next_progress: Time,
+    sent_finished: bool,
+    /// When the current sim was loaded
+    loaded_at: Instant,
+}
+
+impl WebhookState {
+    fn reset(&mut self) {
+        self.next_progress = Time::START_OF_DAY + self.period;
+        self.sent_finished = false;
+        self.loaded_at = Instant::now();
+    }
+
+    /// Called after each step. Sends a summary if one is due, without waiting for a response.
+    fn maybe_notify(&mut self, sim: &Sim, metrics: &Metrics) {
+        let url = if let Some(ref url) = self.url {
+            url.clone()
+        } else {
+            return;
+        };
+
+        let event = if sim.is_done() && !self.sent_finished {
+            self.sent_finished = true;
+            "finished"
+        } else if sim.time() >= self.next_progress {
+            while self.next_progress <= sim.time() {
+                self.next_progress = self.next_progress + self.period;
+            }
+            "progress"
+        } else {
+            return;
+        };
+
+        let wall_clock = Duration::realtime_elapsed(self.loaded_at);
+        let summary = WebhookSummary {
+            text: format!(
+                "{} {} at {}: {} trips done, {} to go, {} alerts ({} real time)",
+                sim.get_run_name(),
+                event,
+                metrics.time,
+                prettyprint_usize(metrics.trips_finished),
+                prettyprint_usize(metrics.trips_unfinished),
+                prettyprint_usize(metrics.alerts),
+                wall_clock
+            ),
+            event,
+            run_name: sim.get_run_name().clone(),
+            time: metrics.time,
+            trips_finished: metrics.trips_finished,
+            trips_unfinished: metrics.trips_unfinished,
+            agents: metrics
+                .agents
+                .iter()
+                .map(|(agent_type, count)| (agent_type.noun().to_string(), *count))
+                .collect(),
+            alerts: metrics.alerts,
+            realtime_ratio: metrics.realtime_ratio,
+            wall_clock,
+        };
+        let body = abstutil::to_json(&summary);
+        tokio::spawn(async move {
+            if let Err(err) = abstio::http_post(&url, body).await {
+                error!("Couldn't notify webhook: {}", err);
+            }
+        });
+    }
+}
+
+#[derive(Serialize)]
+struct WebhookSummary {
+    /// A human-readable version of everything else
+    text: String,
+    /// "progress" or "finished"
+    event: &'static str,
+    run_name: String,
+    time: Time,
+    trips_finished: usize,
+    trips_unfinished: usize,
+    agents: BTreeMap<String, usize>,
+    alerts: usize,
+    /// Simulated seconds per wall-clock second, over the most recent step
+    realtime_ratio: f64,
+    /// How long the sim has been running for real
+    wall_clock: Duration,
+}
+
+#[derive(Serialize)]
+struct StreamFrame {
+    time: Time,
+    agents: Vec<AgentPosition>,
+    /// Trips that finished or were cancelled since the previous frame
+    finished_trips: Vec<FinishedTrip>,
+}
+
 #[derive(Serialize)]
 struct RoadThroughput {
     // (road, agent type, hour since midnight, throughput for that one hour period)
     counts: Vec<(RoadID, AgentType, usize, usize)>,
 }
 
+#[derive(Serialize)]
+struct RouteRevenue {
+    gtfs_id: String,
+    name: String,
+    fare: TransitFare,
+    riders: usize,
+    // In cents
+    revenue: usize,
+    // (riders, revenue) for each hour since midnight
+    hourly: Vec<(usize, usize)>,
+}
+
 #[derive(Serialize)]
 struct TrafficSignalState {
     current_stage_idx: usize,
@@ -556,3 +1056,87 @@ fn export_geometry(map: &Map, i: IntersectionID) -> geojson::GeoJson {
 
     geom::geometries_with_properties_to_geojson(pairs)
 }
+
+fn memory_report(sim: &Sim, map: &Map) -> MemoryReport {
+    let mut report = map.memory_report();
+    report.extend(sim.memory_report());
+    report
+}
+
+fn maybe_log_memory(sim: &Sim, map: &Map) {
+    if PROFILE_MEMORY.load(Ordering::Relaxed) {
+        info!("Memory usage at {}:", sim.time());
+        for line in memory_report(sim, map).describe() {
+            info!("{}", line);
+        }
+    }
+}
+
+fn broadcast_frame(sim: &Sim, map: &Map) {
+    let mut stream = STREAM.write().unwrap();
+    let all_finished = &sim.get_analytics().finished_trips;
+    // The sim may have been reset since the last frame
+    if stream.num_finished_trips_sent > all_finished.len() {
+        stream.num_finished_trips_sent = 0;
+    }
+    let finished_trips = all_finished[stream.num_finished_trips_sent..]
+        .iter()
+        .map(|(_, id, mode, maybe_duration)| FinishedTrip {
+            id: *id,
+            person: sim.trip_to_person(*id).unwrap(),
+            duration: *maybe_duration,
+            distance_crossed: if maybe_duration.is_some() {
+                sim.finished_trip_details(*id).unwrap().2
+            } else {
+                Distance::ZERO
+            },
+            mode: *mode,
+        })
+        .collect();
+    stream.num_finished_trips_sent = all_finished.len();
+
+    let frame = StreamFrame {
+        time: sim.time(),
+        agents: get_agent_positions(sim, map),
+        finished_trips,
+    };
+    // This only fails if every client disconnected in the meantime
+    let _ = STREAM_TX.send(abstutil::to_json_terse(&frame));
+}
+
+async fn serve_websockets(port: u16) {
+    let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+    let listener = match tokio::net::TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(err) => panic!("Can't listen for WebSockets on {}: {}", addr, err),
+    };
+    info!("Streaming sim state on ws://{}", addr);
+    while let Ok((socket, _)) = listener.accept().await {
+        tokio::spawn(stream_to_client(socket));
+    }
+}
+
+async fn stream_to_client(socket: tokio::net::TcpStream) {
+    let mut ws = match tokio_tungstenite::accept_async(socket).await {
+        Ok(ws) => ws,
+        Err(err) => {
+            error!("WebSocket handshake failed: {}", err);
+            return;
+        }
+    };
+    let mut rx = STREAM_TX.subscribe();
+    loop {
+        match rx.recv().await {
+            Ok(frame) => {
+                if ws.send(Message::Text(frame)).await.is_err() {
+                    break;
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                warn!("WebSocket client is too slow, skipped {} frames", n);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+    info!("WebSocket client disconnected");
+}
@@ -75,6 +75,8 @@ pub fn config_for_map(name: &MapName) -> convert_osm::Options {
         } else {
             None
         },
+        // A typical weekday
+        gtfs_service_day: convert_osm::DayOfWeek::Wednesday,
         // We only have a few elevation sources working
         elevation: name.city == CityName::new("us", "seattle") || name.city.country == "gb",
     }
@@ -216,6 +216,7 @@ fn import_parcels(
                 ExtraShape {
                     points: vec![gps],
                     attributes,
+                    properties: BTreeMap::new(),
                 },
             );
         }
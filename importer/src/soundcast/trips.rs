@@ -4,7 +4,8 @@ use abstutil::{prettyprint_usize, MultiMap, Timer};
 use geom::PolyLine;
 use map_model::{osm, BuildingID, Map, Path, PathConstraints, PathRequest, PathStep};
 use synthpop::{
-    IndividTrip, MapBorder, MapBorders, OrigPersonID, PersonSpec, Scenario, TripEndpoint, TripMode,
+    FleetMix, IndividTrip, MapBorder, MapBorders, OrigPersonID, PersonSpec, Scenario, TripEndpoint,
+    TripMode,
 };
 
 use crate::soundcast::popdat::{Endpoint, OrigTrip, PopDat};
@@ -293,6 +294,7 @@ pub fn make_scenario(
         map_name: map.get_name().clone(),
         people,
         only_seed_buses: None,
+        fleet_mix: FleetMix::default(),
     }
     .remove_weird_schedules(true)
 }
@@ -8,10 +8,11 @@ extern crate log;
 use std::collections::BTreeMap;
 
 use anyhow::Result;
+use geojson::{Feature, FeatureCollection, GeoJson, JsonValue, Value};
 use serde::{Deserialize, Serialize};
 
 use abstutil::{prettyprint_usize, Timer};
-use geom::{GPSBounds, LonLat, PolyLine, Polygon};
+use geom::{GPSBounds, LonLat};
 
 /// Some dataset imported from KML, CSV, or something else. If the dataset is large, converting to
 /// this format and serializing is faster than parsing the original again.
@@ -30,6 +31,65 @@ pub struct ExtraShape {
     pub points: Vec<LonLat>,
     /// Arbitrary key/value pairs associated with this object; no known schema.
     pub attributes: BTreeMap<String, String>,
+    /// When the source format has typed values (like numbers in GeoJSON), they're kept here, so
+    /// the shape can be exported again without changing them. Every key is also in `attributes`,
+    /// as a string.
+    #[serde(default)]
+    pub properties: BTreeMap<String, PropertyValue>,
+}
+
+/// A typed attribute value
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PropertyValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    /// Arrays and nested objects, kept as JSON text
+    Json(String),
+}
+
+impl PropertyValue {
+    fn from_json(value: JsonValue) -> PropertyValue {
+        match value {
+            JsonValue::Null => PropertyValue::Null,
+            JsonValue::Bool(x) => PropertyValue::Bool(x),
+            JsonValue::Number(x) => match x.as_f64() {
+                Some(x) => PropertyValue::Number(x),
+                None => PropertyValue::Json(x.to_string()),
+            },
+            JsonValue::String(x) => PropertyValue::String(x),
+            x => PropertyValue::Json(x.to_string()),
+        }
+    }
+
+    fn to_json(&self) -> JsonValue {
+        match self {
+            PropertyValue::Null => JsonValue::Null,
+            PropertyValue::Bool(x) => JsonValue::Bool(*x),
+            PropertyValue::Number(x) => {
+                // Don't turn integers into floats
+                if x.fract().abs() < f64::EPSILON && x.abs() < i64::MAX as f64 {
+                    JsonValue::from(*x as i64)
+                } else {
+                    JsonValue::from(*x)
+                }
+            }
+            PropertyValue::String(x) => JsonValue::String(x.clone()),
+            PropertyValue::Json(x) => x
+                .parse::<JsonValue>()
+                .unwrap_or_else(|_| JsonValue::String(x.clone())),
+        }
+    }
+
+    /// How the value looks as a plain attribute
+    pub fn to_attribute(&self) -> String {
+        match self {
+            PropertyValue::Null => String::new(),
+            PropertyValue::String(x) | PropertyValue::Json(x) => x.clone(),
+            x => x.to_json().to_string(),
+        }
+    }
 }
 
 /// Parses a .kml file and returns ExtraShapes. Objects will be clipped to the given gps_bounds. If
@@ -119,6 +179,7 @@ fn recurse(
             shapes.push(ExtraShape {
                 points: pts,
                 attributes,
+                properties: BTreeMap::new(),
             });
         } else {
             *skipped_count += 1;
@@ -166,6 +227,7 @@ impl ExtraShapes {
                             shapes.push(ExtraShape {
                                 points: vec![pt],
                                 attributes: rec,
+                                properties: BTreeMap::new(),
                             });
                         }
                     }
@@ -176,6 +238,7 @@ impl ExtraShapes {
                             shapes.push(ExtraShape {
                                 points,
                                 attributes: rec,
+                                properties: BTreeMap::new(),
                             });
                         }
                     }
@@ -195,33 +258,91 @@ impl ExtraShapes {
 }
 
 impl ExtraShapes {
-    /// Parses a .geojson file and returns ExtraShapes
+    /// Parses a .geojson file and returns ExtraShapes. Each part of a multi-part geometry becomes
+    /// its own shape, and polygons only keep their outer ring. If `require_in_bounds` is true,
+    /// shapes partly out-of-bounds are skipped. Properties keep their types; see `to_geojson`.
     pub fn load_geojson_no_clipping(
         path: String,
         gps_bounds: &GPSBounds,
         require_in_bounds: bool,
     ) -> Result<ExtraShapes> {
-        let bytes = abstio::slurp_file(path)?;
+        let geojson: GeoJson = String::from_utf8(abstio::slurp_file(path)?)?.parse()?;
+        let collection = FeatureCollection::try_from(geojson)?;
+
         let mut shapes = Vec::new();
+        for feature in collection.features {
+            let mut properties = BTreeMap::new();
+            for (key, value) in feature.properties.unwrap_or_default() {
+                properties.insert(key, PropertyValue::from_json(value));
+            }
+            let attributes: BTreeMap<String, String> = properties
+                .iter()
+                .map(|(k, v)| (k.clone(), v.to_attribute()))
+                .collect();
 
-        for (polygon, attributes) in
-            Polygon::from_geojson_bytes(&bytes, gps_bounds, require_in_bounds)?
-        {
-            shapes.push(ExtraShape {
-                // Awkward, but we have to convert back
-                points: gps_bounds.convert_back(polygon.get_outer_ring().points()),
-                attributes,
-            });
-        }
-        for (pl, attributes) in PolyLine::from_geojson_bytes(&bytes, gps_bounds, require_in_bounds)?
-        {
-            shapes.push(ExtraShape {
-                // Awkward, but we have to convert back
-                points: gps_bounds.convert_back(pl.points()),
-                attributes,
-            });
+            let parts = match feature.geometry.map(|g| g.value) {
+                Some(Value::Point(pt)) => vec![vec![pt]],
+                Some(Value::MultiPoint(pts)) => pts.into_iter().map(|pt| vec![pt]).collect(),
+                Some(Value::LineString(pts)) => vec![pts],
+                Some(Value::MultiLineString(lines)) => lines,
+                Some(Value::Polygon(rings)) => rings.into_iter().take(1).collect(),
+                Some(Value::MultiPolygon(polygons)) => polygons
+                    .into_iter()
+                    .filter_map(|rings| rings.into_iter().next())
+                    .collect(),
+                _ => continue,
+            };
+            for part in parts {
+                let points: Vec<LonLat> = part
+                    .into_iter()
+                    .map(|pt| LonLat::new(pt[0], pt[1]))
+                    .collect();
+                if points.is_empty()
+                    || (require_in_bounds && !points.iter().all(|pt| gps_bounds.contains(*pt)))
+                {
+                    continue;
+                }
+                shapes.push(ExtraShape {
+                    points,
+                    attributes: attributes.clone(),
+                    properties: properties.clone(),
+                });
+            }
         }
 
         Ok(ExtraShapes { shapes })
     }
+
+    /// Exports every shape as a GeoJSON feature, using the same rules as `ExtraShape::points`
+    /// to pick a Point, Polygon, or LineString. Attributes with typed `properties` keep their
+    /// type; the rest become strings.
+    pub fn to_geojson(&self) -> GeoJson {
+        let mut features = Vec::new();
+        for shape in &self.shapes {
+            let pts: Vec<Vec<f64>> = shape.points.iter().map(|pt| vec![pt.x(), pt.y()]).collect();
+            let value = if pts.len() == 1 {
+                Value::Point(pts[0].clone())
+            } else if pts.len() >= 4 && pts[0] == pts[pts.len() - 1] {
+                Value::Polygon(vec![pts])
+            } else {
+                Value::LineString(pts)
+            };
+            let mut feature = Feature::from(value);
+            for (key, value) in &shape.attributes {
+                feature.set_property(
+                    key.clone(),
+                    match shape.properties.get(key) {
+                        Some(typed) => typed.to_json(),
+                        None => JsonValue::String(value.clone()),
+                    },
+                );
+            }
+            features.push(feature);
+        }
+        GeoJson::FeatureCollection(FeatureCollection {
+            features,
+            bbox: None,
+            foreign_members: None,
+        })
+    }
 }
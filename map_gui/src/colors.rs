@@ -1,6 +1,7 @@
 //! A color scheme groups colors used for different map, dynamic, and UI elements in one place, to
 //! encourage deduplication. The player can also switch between different color schemes.
 
+use std::collections::BTreeMap;
 use std::io::Write;
 
 use anyhow::Result;
@@ -12,6 +13,7 @@ use map_model::{LaneType, Map};
 use widgetry::tools::ColorScale;
 use widgetry::{Choice, Color, EventCtx, Fill, Style, Texture};
 
+use crate::options::Options;
 use crate::tools::loading_tips;
 
 // I've gone back and forth how to organize color scheme code. I was previously against having one
@@ -29,6 +31,8 @@ use crate::tools::loading_tips;
 pub enum ColorSchemeChoice {
     DayMode,
     NightMode,
+    DarkMode,
+    HighContrast,
     Textured,
     ClassicDayMode,
     LTN,
@@ -39,6 +43,8 @@ impl ColorSchemeChoice {
         vec![
             Choice::new("day mode", ColorSchemeChoice::DayMode),
             Choice::new("night mode", ColorSchemeChoice::NightMode),
+            Choice::new("dark mode", ColorSchemeChoice::DarkMode),
+            Choice::new("high contrast", ColorSchemeChoice::HighContrast),
             Choice::new("textured", ColorSchemeChoice::Textured),
             Choice::new("classic", ColorSchemeChoice::ClassicDayMode),
             Choice::new("LTN", ColorSchemeChoice::LTN),
@@ -77,6 +83,10 @@ pub struct ColorScheme {
     pub current_object: Color,
     pub perma_selected_object: Color,
     pub fade_map_dark: Color,
+    // Drawn over the whole map for simulated weather
+    pub darkness: Color,
+    pub rain: Color,
+    pub snow: Color,
     gui_style: Style,
     pub minimap_cursor_border: Color,
     pub minimap_cursor_bg: Option<Color>,
@@ -136,6 +146,7 @@ pub struct ColorScheme {
 
     // Agents
     agent_colors: Vec<Color>,
+    plot_colors: Vec<Color>,
     pub route: Color,
     pub turn_arrow: Color,
     pub brake_light: Color,
@@ -152,6 +163,9 @@ pub struct ColorScheme {
     // Layers
     pub good_to_bad_red: ColorScale,
     pub good_to_bad_green: ColorScale,
+    /// For diverging scales showing changes, where `better` means less of something bad
+    pub better: Color,
+    pub worse: Color,
     pub bus_layer: Color,
     pub edits_layer: Color,
 
@@ -163,17 +177,62 @@ pub struct ColorScheme {
     pub after_changes: Color,
 }
 
+/// Semantic colors that the player can override in the settings, on top of any scheme. The names
+/// are used in the settings file.
+pub const OVERRIDABLE_COLORS: [&str; 21] = [
+    "map_background",
+    "void_background",
+    "grass",
+    "water",
+    "residential_building",
+    "commercial_building",
+    "building_outline",
+    "parking_lot",
+    "driving_lane",
+    "parking_lane",
+    "bus_lane",
+    "bike_lane",
+    "sidewalk",
+    "unzoomed_highway",
+    "unzoomed_arterial",
+    "unzoomed_residential",
+    "unzoomed_cycleway",
+    "selected",
+    "route",
+    "panel_bg",
+    "inner_panel_bg",
+];
+
 impl ColorScheme {
     pub fn new(ctx: &mut EventCtx, scheme: ColorSchemeChoice) -> ColorScheme {
+        let cs = ColorScheme::preset(scheme);
+        ctx.set_style(cs.gui_style.clone());
+        cs
+    }
+
+    /// The scheme chosen in the options, with the player's overridden colors applied
+    pub fn from_options(ctx: &mut EventCtx, opts: &Options) -> ColorScheme {
+        let mut cs = ColorScheme::preset(opts.color_scheme);
+        if opts.colorblind_safe {
+            cs.make_colorblind_safe();
+        }
+        cs.apply_overrides(&opts.color_overrides);
+        ctx.set_style(cs.gui_style.clone());
+        cs
+    }
+
+    /// Doesn't change the GUI style, unlike `new`
+    pub fn preset(scheme: ColorSchemeChoice) -> ColorScheme {
         let mut cs = match scheme {
             ColorSchemeChoice::DayMode => ColorScheme::day_mode(),
             ColorSchemeChoice::NightMode => ColorScheme::night_mode(),
+            ColorSchemeChoice::DarkMode => ColorScheme::dark_mode(),
+            ColorSchemeChoice::HighContrast => ColorScheme::high_contrast(),
             ColorSchemeChoice::Textured => ColorScheme::textured(),
             ColorSchemeChoice::ClassicDayMode => ColorScheme::classic(),
             ColorSchemeChoice::LTN => ColorScheme::ltn(),
         };
         cs.scheme = scheme;
-        ctx.set_style(cs.gui_style.clone());
         cs
     }
 
@@ -201,6 +260,9 @@ impl ColorScheme {
             current_object: Color::WHITE,
             perma_selected_object: Color::BLUE,
             fade_map_dark: Color::BLACK.alpha(0.6),
+            darkness: hex("#0B1A3A").alpha(0.45),
+            rain: hex("#5A6E82").alpha(0.2),
+            snow: Color::WHITE.alpha(0.3),
             minimap_cursor_border: Color::BLACK,
             minimap_cursor_bg: None,
             gui_style,
@@ -267,6 +329,13 @@ impl ColorScheme {
                 hex("#96322F"),
                 hex("#00A27B"),
             ],
+            plot_colors: vec![
+                Color::RED,
+                Color::BLUE,
+                Color::GREEN,
+                Color::PURPLE,
+                Color::BLACK,
+            ],
             route: Color::ORANGE.alpha(0.5),
             turn_arrow: hex("#DF8C3D"),
             brake_light: hex("#FF1300"),
@@ -283,6 +352,8 @@ impl ColorScheme {
             // Layers
             good_to_bad_red: ColorScale(vec![hex("#F19A93"), hex("#A32015")]),
             good_to_bad_green: ColorScale(vec![hex("#BEDB92"), hex("#397A4C")]),
+            better: hex("#5D9630"),
+            worse: hex("#A32015"),
             bus_layer: hex("#4CA7E9"),
             edits_layer: hex("#12409D"),
 
@@ -332,6 +403,78 @@ impl ColorScheme {
         cs
     }
 
+    /// Unlike night mode, uses neutral greys everywhere, for people who just want a dark UI
+    fn dark_mode() -> ColorScheme {
+        let mut cs = ColorScheme::night_mode();
+        cs.scheme = ColorSchemeChoice::DarkMode;
+
+        cs.gui_style.panel_bg = hex("#1E1E1E").alpha(0.95);
+        cs.gui_style.section_bg = hex("#2A2A2A");
+        cs.gui_style.field_bg = hex("#121212");
+        cs.gui_style.section_outline = (2.0, hex("#121212"));
+        cs.gui_style.btn_tab.bg_disabled = cs.gui_style.section_bg;
+        cs.gui_style.text_hotkey_color = hex("#F2994A");
+        cs.panel_bg = cs.gui_style.panel_bg;
+        cs.inner_panel_bg = cs.gui_style.section_bg;
+
+        cs.void_background = hex("#0A0A0A");
+        cs.map_background = hex("#121212").into();
+        cs.grass = hex("#1C2B1C").into();
+        cs.water = hex("#14222E").into();
+        cs.residential_building = hex("#2E2E2E");
+        cs.commercial_building = hex("#3A3A44");
+        cs.building_outline = hex("#0A0A0A");
+        cs.private_road = Some(hex("#4A3A3E"));
+        cs.study_area = hex("#5C5026").into();
+
+        cs
+    }
+
+    /// Stark black-and-white roads, saturated colors, and opaque panels with dark text
+    fn high_contrast() -> ColorScheme {
+        let mut cs = ColorScheme::classic();
+        cs.scheme = ColorSchemeChoice::HighContrast;
+        cs.road_outlines = true;
+
+        cs.gui_style.panel_bg = Color::WHITE;
+        cs.gui_style.section_bg = Color::WHITE;
+        cs.gui_style.field_bg = Color::WHITE;
+        cs.gui_style.section_outline = (2.0, Color::BLACK);
+        cs.gui_style.dropdown_border = Color::BLACK;
+        cs.gui_style.icon_fg = Color::BLACK;
+        cs.gui_style.text_primary_color = Color::BLACK;
+        cs.gui_style.text_secondary_color = Color::BLACK;
+        cs.panel_bg = cs.gui_style.panel_bg;
+        cs.inner_panel_bg = cs.gui_style.section_bg;
+
+        cs.map_background = Color::WHITE.into();
+        cs.grass = hex("#00A651").into();
+        cs.water = hex("#0057E7").into();
+        cs.residential_building = Color::grey(0.75);
+        cs.commercial_building = Color::grey(0.55);
+        cs.building_outline = Color::BLACK;
+        cs.parking_lot = Color::grey(0.6);
+
+        cs.driving_lane = Color::BLACK;
+        cs.parking_lane = Color::grey(0.3);
+        cs.sidewalk = Color::grey(0.6);
+        cs.sidewalk_lines = Color::BLACK;
+        cs.bike_lane = hex("#00A651");
+        cs.bus_lane = hex("#D00000");
+        cs.normal_intersection = Color::BLACK;
+
+        cs.unzoomed_highway = hex("#D00000");
+        cs.unzoomed_arterial = hex("#FF8C00");
+        cs.unzoomed_residential = Color::BLACK;
+        cs.unzoomed_cycleway = hex("#00A651");
+        cs.unzoomed_interesting_intersection = Color::BLACK;
+
+        cs.selected = hex("#FF00FF").alpha(0.8);
+        cs.route = hex("#FF00FF").alpha(0.7);
+
+        cs
+    }
+
     fn textured() -> ColorScheme {
         let mut cs = ColorScheme::day_mode();
         cs.scheme = ColorSchemeChoice::Textured;
@@ -398,16 +541,7 @@ impl ColorScheme {
 
 impl ColorScheme {
     pub fn rotating_color_plot(&self, idx: usize) -> Color {
-        modulo_color(
-            &[
-                Color::RED,
-                Color::BLUE,
-                Color::GREEN,
-                Color::PURPLE,
-                Color::BLACK,
-            ],
-            idx,
-        )
+        modulo_color(&self.plot_colors, idx)
     }
 
     pub fn rotating_color_agents(&self, idx: usize) -> Color {
@@ -479,6 +613,120 @@ impl ColorScheme {
         }
     }
 
+    /// Replace red/green pairs with colors that people with the common forms of red-green
+    /// colorblindness can distinguish. Mostly uses the Okabe-Ito palette from
+    /// https://jfly.uni-koeln.de/color/
+    fn make_colorblind_safe(&mut self) {
+        let orange = hex("#E69F00");
+        let sky_blue = hex("#56B4E9");
+        let bluish_green = hex("#009E73");
+        let yellow = hex("#F0E442");
+        let blue = hex("#0072B2");
+        let vermillion = hex("#D55E00");
+        let reddish_purple = hex("#CC79A7");
+
+        self.good_to_bad_red = ColorScale(vec![hex("#FDBE85"), hex("#A63603")]);
+        self.good_to_bad_green = ColorScale(vec![hex("#BDD7E7"), hex("#08519C")]);
+        self.better = blue;
+        self.worse = vermillion;
+
+        self.signal_protected_turn = bluish_green;
+        self.signal_permitted_turn = sky_blue;
+        self.signal_banned_turn = vermillion;
+
+        self.slowest_intersection = vermillion;
+        self.slower_intersection = yellow;
+        self.slow_intersection = sky_blue;
+
+        self.bike_lane = bluish_green;
+        self.bus_lane = vermillion;
+        self.unzoomed_cycleway = bluish_green;
+        self.before_changes = blue;
+        self.after_changes = orange;
+        self.plot_colors = vec![vermillion, blue, orange, reddish_purple, Color::BLACK];
+    }
+
+    /// Looks up one of `OVERRIDABLE_COLORS`. Returns None for unknown names, or for textures.
+    pub fn get_named_color(&self, name: &str) -> Option<Color> {
+        let fill = |f: &Fill| match f {
+            Fill::Color(c) => Some(*c),
+            _ => None,
+        };
+        match name {
+            "map_background" => fill(&self.map_background),
+            "void_background" => Some(self.void_background),
+            "grass" => fill(&self.grass),
+            "water" => fill(&self.water),
+            "residential_building" => Some(self.residential_building),
+            "commercial_building" => Some(self.commercial_building),
+            "building_outline" => Some(self.building_outline),
+            "parking_lot" => Some(self.parking_lot),
+            "driving_lane" => Some(self.driving_lane),
+            "parking_lane" => Some(self.parking_lane),
+            "bus_lane" => Some(self.bus_lane),
+            "bike_lane" => Some(self.bike_lane),
+            "sidewalk" => Some(self.sidewalk),
+            "unzoomed_highway" => Some(self.unzoomed_highway),
+            "unzoomed_arterial" => Some(self.unzoomed_arterial),
+            "unzoomed_residential" => Some(self.unzoomed_residential),
+            "unzoomed_cycleway" => Some(self.unzoomed_cycleway),
+            "selected" => Some(self.selected),
+            "route" => Some(self.route),
+            "panel_bg" => Some(self.panel_bg),
+            "inner_panel_bg" => Some(self.inner_panel_bg),
+            _ => None,
+        }
+    }
+
+    fn set_named_color(&mut self, name: &str, color: Color) -> Result<()> {
+        match name {
+            "map_background" => self.map_background = color.into(),
+            "void_background" => self.void_background = color,
+            "grass" => self.grass = color.into(),
+            "water" => self.water = color.into(),
+            "residential_building" => self.residential_building = color,
+            "commercial_building" => self.commercial_building = color,
+            "building_outline" => self.building_outline = color,
+            "parking_lot" => self.parking_lot = color,
+            "driving_lane" => self.driving_lane = color,
+            "parking_lane" => self.parking_lane = color,
+            "bus_lane" => self.bus_lane = color,
+            "bike_lane" => self.bike_lane = color,
+            "sidewalk" => self.sidewalk = color,
+            "unzoomed_highway" => self.unzoomed_highway = color,
+            "unzoomed_arterial" => self.unzoomed_arterial = color,
+            "unzoomed_residential" => self.unzoomed_residential = color,
+            "unzoomed_cycleway" => self.unzoomed_cycleway = color,
+            // Overrides are opaque, but these need to stay translucent
+            "selected" => self.selected = color.alpha(self.selected.a),
+            "route" => self.route = color.alpha(self.route.a),
+            "panel_bg" => {
+                self.panel_bg = color.alpha(self.panel_bg.a);
+                self.gui_style.panel_bg = self.panel_bg;
+            }
+            "inner_panel_bg" => {
+                self.inner_panel_bg = color;
+                self.gui_style.section_bg = color;
+            }
+            _ => bail!("Unknown color {}", name),
+        }
+        Ok(())
+    }
+
+    /// Overrides are hex strings keyed by one of `OVERRIDABLE_COLORS`. Bad entries are skipped.
+    pub fn apply_overrides(&mut self, overrides: &BTreeMap<String, String>) {
+        for (name, raw) in overrides {
+            match parse_hex(raw) {
+                Some(color) => {
+                    if let Err(err) = self.set_named_color(name, color) {
+                        warn!("Ignoring color override: {}", err);
+                    }
+                }
+                None => warn!("Ignoring color override {} = {}", name, raw),
+            }
+        }
+    }
+
     // These two could try to use serde, but... Color serializes with a separate RGB by default,
     // and changing it to use a nice hex string is way too hard.
     pub fn export(&self, path: &str) -> Result<()> {
@@ -541,6 +789,16 @@ fn modulo_color(colors: &[Color], idx: usize) -> Color {
     colors[idx % colors.len()]
 }
 
+/// Like `Color::hex`, but doesn't crash on player input
+pub fn parse_hex(raw: &str) -> Option<Color> {
+    let raw = raw.trim();
+    if raw.len() == 7 && raw.starts_with('#') && raw[1..].chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(Color::hex(raw))
+    } else {
+        None
+    }
+}
+
 // Convenience
 fn hex(x: &str) -> Color {
     Color::hex(x)
@@ -64,13 +64,18 @@ pub trait AppLike {
             return false;
         }
         self.mut_opts().color_scheme = cs;
-        *self.mut_cs() = ColorScheme::new(ctx, self.opts().color_scheme);
+        self.reload_color_scheme(ctx);
+        true
+    }
+
+    /// Rebuild the color scheme from the options, including any colors the player overrode, and
+    /// rerender the map.
+    fn reload_color_scheme(&mut self, ctx: &mut EventCtx) {
+        *self.mut_cs() = ColorScheme::from_options(ctx, self.opts());
 
         ctx.loading_screen("rerendering map colors", |ctx, timer| {
             *self.mut_draw_map() = DrawMap::new(ctx, self.map(), self.opts(), self.cs(), timer);
         });
-
-        true
     }
 }
 
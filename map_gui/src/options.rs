@@ -1,13 +1,17 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 use abstutil::Timer;
-use geom::{Duration, UnitFmt};
+use geom::{Duration, Polygon, UnitFmt};
+use widgetry::mapspace::TiledDrawable;
+use widgetry::tools::{PopupMsg, Translations};
 use widgetry::{
     CanvasSettings, Choice, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, Panel, Spinner, State,
-    TextExt, Toggle, Widget,
+    TextBox, TextExt, Toggle, Widget,
 };
 
-use crate::colors::ColorSchemeChoice;
+use crate::colors::{parse_hex, ColorScheme, ColorSchemeChoice, OVERRIDABLE_COLORS};
 use crate::render::DrawBuilding;
 use crate::tools::grey_out_map;
 use crate::AppLike;
@@ -28,6 +32,18 @@ pub struct Options {
     pub traffic_signal_style: TrafficSignalStyle,
     /// The color scheme for map elements, agents, and the UI.
     pub color_scheme: ColorSchemeChoice,
+    /// Individual colors the player has changed, applied on top of color_scheme. Keyed by one of
+    /// `colors::OVERRIDABLE_COLORS`, with hex values.
+    #[serde(default)]
+    pub color_overrides: BTreeMap<String, String>,
+    /// Swap colors that are hard to tell apart with red-green colorblindness, like the red and
+    /// green used for good and bad changes.
+    #[serde(default)]
+    pub colorblind_safe: bool,
+    /// Draw stripes over areas in layers with a few categories, so they can be distinguished by
+    /// more than color.
+    #[serde(default)]
+    pub pattern_fills: bool,
     /// Automatically change color_scheme based on simulation time to reflect day/night
     pub toggle_day_night_colors: bool,
     /// Draw buildings in different perspectives
@@ -61,6 +77,10 @@ pub struct Options {
     /// Display roads and buildings in an alternate language, if possible. None means to use the
     /// OSM native name.
     pub language: Option<String>,
+    /// Translate the UI into this language, using a catalog in
+    /// `data/system/assets/translations`. None means English.
+    #[serde(default)]
+    pub ui_language: Option<String>,
     /// How to render geometric units
     pub units: UnitFmt,
 }
@@ -87,6 +107,9 @@ impl Options {
 
             traffic_signal_style: TrafficSignalStyle::Brian,
             color_scheme: ColorSchemeChoice::DayMode,
+            color_overrides: BTreeMap::new(),
+            colorblind_safe: false,
+            pattern_fills: false,
             toggle_day_night_colors: false,
             camera_angle: CameraAngle::TopDown,
             show_building_driveways: true,
@@ -103,6 +126,7 @@ impl Options {
             minimal_controls: false,
             canvas_settings: CanvasSettings::new(),
             language: None,
+            ui_language: None,
             units: UnitFmt {
                 round_durations: true,
                 // TODO Should default be based on the map?
@@ -110,6 +134,30 @@ impl Options {
             },
         }
     }
+
+    /// Switch the UI to `ui_language`. If the catalog is missing or broken, stay in English.
+    pub fn apply_ui_language(&self, ctx: &mut EventCtx) {
+        let translations = match self.ui_language {
+            Some(ref lang) => match Translations::load(lang) {
+                Ok(t) => t,
+                Err(err) => {
+                    warn!("Couldn't load translations for {}: {}", lang, err);
+                    Translations::english()
+                }
+            },
+            None => Translations::english(),
+        };
+        ctx.set_translations(translations);
+    }
+
+    /// Persist these options in the player's settings.
+    fn save(&self) {
+        // Be careful -- there are some options not exposed by the settings panel, but per app.
+        let mut opts = self.clone();
+        opts.show_building_driveways = true;
+        opts.show_building_outlines = true;
+        abstio::write_json(abstio::path_player("settings.json"), &opts);
+    }
 }
 
 /// Different ways of drawing traffic signals. The names of these aren't super meaningful...
@@ -139,38 +187,58 @@ impl OptionsPanel {
         Box::new(OptionsPanel {
             panel: Panel::new_builder(Widget::col(vec![
                 Widget::custom_row(vec![
-                    Line("Settings").small_heading().into_widget(ctx),
+                    Line(ctx.tr("options-title", "Settings"))
+                        .small_heading()
+                        .into_widget(ctx),
                     ctx.style().btn_close_widget(ctx),
                 ]),
-                "Camera controls".text_widget(ctx),
+                ctx.tr("options-camera-controls", "Camera controls")
+                    .text_widget(ctx),
                 Widget::col(vec![
                     Toggle::checkbox(
                         ctx,
-                        "Invert direction of vertical scrolling",
+                        &ctx.tr(
+                            "options-invert-scroll",
+                            "Invert direction of vertical scrolling",
+                        ),
                         None,
                         ctx.canvas.settings.invert_scroll,
-                    ),
+                    )
+                    .named("Invert direction of vertical scrolling"),
                     Toggle::checkbox(
                         ctx,
-                        "Pan map when cursor is at edge of screen",
+                        &ctx.tr(
+                            "options-autopan",
+                            "Pan map when cursor is at edge of screen",
+                        ),
                         None,
                         ctx.canvas.settings.edge_auto_panning,
                     )
                     .named("autopan"),
                     Toggle::checkbox(
                         ctx,
-                        "Use touchpad to pan and hold Control to zoom",
+                        &ctx.tr(
+                            "options-touchpad",
+                            "Use touchpad to pan and hold Control to zoom",
+                        ),
                         None,
                         ctx.canvas.settings.touchpad_to_move,
-                    ),
+                    )
+                    .named("Use touchpad to pan and hold Control to zoom"),
                     Toggle::checkbox(
                         ctx,
-                        "Use arrow keys to pan and Q/W to zoom",
+                        &ctx.tr(
+                            "options-keys-to-pan",
+                            "Use arrow keys to pan and Q/W to zoom",
+                        ),
                         None,
                         ctx.canvas.settings.keys_to_pan,
-                    ),
+                    )
+                    .named("Use arrow keys to pan and Q/W to zoom"),
                     Widget::row(vec![
-                        "Scroll speed for menus".text_widget(ctx).centered_vert(),
+                        ctx.tr("options-gui-scroll-speed", "Scroll speed for menus")
+                            .text_widget(ctx)
+                            .centered_vert(),
                         Spinner::widget(
                             ctx,
                             "gui_scroll_speed",
@@ -180,7 +248,9 @@ impl OptionsPanel {
                         ),
                     ]),
                     Widget::row(vec![
-                        "Zoom speed for the map".text_widget(ctx).centered_vert(),
+                        ctx.tr("options-canvas-scroll-speed", "Zoom speed for the map")
+                            .text_widget(ctx)
+                            .centered_vert(),
                         Spinner::widget(
                             ctx,
                             "canvas_scroll_speed",
@@ -192,10 +262,11 @@ impl OptionsPanel {
                 ])
                 .bg(app.cs().inner_panel_bg)
                 .padding(8),
-                "Appearance".text_widget(ctx),
+                ctx.tr("options-appearance", "Appearance").text_widget(ctx),
                 Widget::col(vec![
                     Widget::row(vec![
-                        "Traffic signal rendering:".text_widget(ctx),
+                        ctx.tr("options-traffic-signals", "Traffic signal rendering:")
+                            .text_widget(ctx),
                         Widget::dropdown(
                             ctx,
                             "Traffic signal rendering",
@@ -211,7 +282,8 @@ impl OptionsPanel {
                         ),
                     ]),
                     Widget::row(vec![
-                        "Camera angle:".text_widget(ctx),
+                        ctx.tr("options-camera-angle", "Camera angle:")
+                            .text_widget(ctx),
                         Widget::dropdown(
                             ctx,
                             "Camera angle",
@@ -227,16 +299,22 @@ impl OptionsPanel {
                         ),
                     ]),
                     Widget::row(vec![
-                        "Color scheme:".text_widget(ctx),
+                        ctx.tr("options-color-scheme", "Color scheme:")
+                            .text_widget(ctx),
                         Widget::dropdown(
                             ctx,
                             "Color scheme",
                             app.opts().color_scheme,
                             ColorSchemeChoice::choices(),
                         ),
+                        ctx.style()
+                            .btn_outline
+                            .text(ctx.tr("options-customize-colors", "Customize colors"))
+                            .build_widget(ctx, "Customize colors"),
                     ]),
                     Widget::row(vec![
-                        "Camera zoom to switch to unzoomed view".text_widget(ctx),
+                        ctx.tr("options-min-zoom", "Camera zoom to switch to unzoomed view")
+                            .text_widget(ctx),
                         Widget::dropdown(
                             ctx,
                             "min zoom",
@@ -251,23 +329,63 @@ impl OptionsPanel {
                             ],
                         ),
                     ]),
-                    Widget::row(vec!["Language".text_widget(ctx), {
-                        let mut default = app.opts().language.clone();
-                        let mut have_default = false;
-                        let mut choices = vec![Choice::new("Map native language", None)];
-                        for lang in app.map().get_languages() {
-                            if default.as_ref() == Some(&lang) {
-                                have_default = true;
+                    Widget::row(vec![
+                        ctx.tr("options-ui-language", "Interface language")
+                            .text_widget(ctx),
+                        {
+                            let mut choices = vec![Choice::new("English", None)];
+                            for lang in Translations::list_languages() {
+                                choices.push(Choice::new(lang.clone(), Some(lang)));
                             }
-                            choices.push(Choice::new(lang.clone(), Some(lang)));
-                        }
-                        // We might be switching from a map that has more languages than this
-                        // map
-                        if !have_default {
-                            default = None;
-                        }
-                        Widget::dropdown(ctx, "language", default, choices)
-                    }]),
+                            Widget::dropdown(
+                                ctx,
+                                "ui language",
+                                app.opts().ui_language.clone(),
+                                choices,
+                            )
+                        },
+                    ]),
+                    Widget::row(vec![
+                        ctx.tr("options-map-language", "Language for map labels")
+                            .text_widget(ctx),
+                        {
+                            let mut default = app.opts().language.clone();
+                            let mut have_default = false;
+                            let mut choices = vec![Choice::new(
+                                ctx.tr("options-map-native-language", "Map native language"),
+                                None,
+                            )];
+                            for lang in app.map().get_languages() {
+                                if default.as_ref() == Some(&lang) {
+                                    have_default = true;
+                                }
+                                choices.push(Choice::new(lang.clone(), Some(lang)));
+                            }
+                            // We might be switching from a map that has more languages than this
+                            // map
+                            if !have_default {
+                                default = None;
+                            }
+                            Widget::dropdown(ctx, "language", default, choices)
+                        },
+                    ]),
+                    Toggle::checkbox(
+                        ctx,
+                        &ctx.tr("options-colorblind-safe", "Use colorblind-safe colors"),
+                        None,
+                        app.opts().colorblind_safe,
+                    )
+                    .named("Use colorblind-safe colors"),
+                    Toggle::checkbox(
+                        ctx,
+                        &ctx.tr(
+                            "options-pattern-fills",
+                            "Draw patterns to tell categories apart",
+                        ),
+                        None,
+                        app.opts().pattern_fills,
+                    )
+                    .named("Draw patterns to tell categories apart"),
                     Toggle::choice(
                         ctx,
                         "metric / imperial units",
@@ -279,9 +397,15 @@ impl OptionsPanel {
                 ])
                 .bg(app.cs().inner_panel_bg)
                 .padding(8),
-                "Debug".text_widget(ctx),
+                ctx.tr("options-debug", "Debug").text_widget(ctx),
                 Widget::col(vec![
-                    Toggle::checkbox(ctx, "Enable developer mode", None, app.opts().dev),
+                    Toggle::checkbox(
+                        ctx,
+                        &ctx.tr("options-dev-mode", "Enable developer mode"),
+                        None,
+                        app.opts().dev,
+                    )
+                    .named("Enable developer mode"),
                     Toggle::checkbox(
                         ctx,
                         "Draw all agents to debug geometry (Slow!)",
@@ -293,9 +417,9 @@ impl OptionsPanel {
                 .padding(8),
                 ctx.style()
                     .btn_solid_primary
-                    .text("Apply")
+                    .text(ctx.tr("options-apply", "Apply"))
                     .hotkey(Key::Enter)
-                    .build_def(ctx)
+                    .build_widget(ctx, "Apply")
                     .centered_horiz(),
             ]))
             .build(ctx),
@@ -310,6 +434,9 @@ impl<A: AppLike> State<A> for OptionsPanel {
                 "close" => {
                     return widgetry::Transition::Pop;
                 }
+                "Customize colors" => {
+                    return widgetry::Transition::Push(ColorSchemeEditor::new_state(ctx, app));
+                }
                 "Apply" => {
                     let mut opts = app.opts().clone();
                     opts.dev = self.panel.is_checked("Enable developer mode");
@@ -349,6 +476,7 @@ impl<A: AppLike> State<A> for OptionsPanel {
                         ctx.loading_screen("rerendering buildings", |ctx, timer| {
                             let mut all_buildings = GeomBatch::new();
                             let mut all_building_outlines = GeomBatch::new();
+                            let mut coarse_buildings = GeomBatch::new();
                             timer
                                 .start_iter("rendering buildings", app.map().all_buildings().len());
                             for b in app.map().all_buildings() {
@@ -362,28 +490,61 @@ impl<A: AppLike> State<A> for OptionsPanel {
                                     &mut all_buildings,
                                     &mut all_building_outlines,
                                 );
+                                DrawBuilding::draw_coarse(
+                                    b,
+                                    app.cs(),
+                                    &opts,
+                                    &mut coarse_buildings,
+                                );
                             }
                             for r in &mut app.mut_draw_map().roads {
                                 r.clear_rendering();
                             }
 
                             timer.start("upload geometry");
-                            app.mut_draw_map().draw_all_buildings = all_buildings.upload(ctx);
-                            app.mut_draw_map().draw_all_building_outlines =
-                                all_building_outlines.upload(ctx);
+                            app.mut_draw_map().draw_all_buildings = TiledDrawable::new(
+                                ctx,
+                                vec![all_buildings],
+                                vec![coarse_buildings],
+                            );
+                            app.mut_draw_map().draw_all_building_outlines = TiledDrawable::new(
+                                ctx,
+                                vec![all_building_outlines],
+                                vec![GeomBatch::new()],
+                            );
                             timer.stop("upload geometry");
                         });
                     }
 
-                    if app.change_color_scheme(ctx, self.panel.dropdown_value("Color scheme")) {
-                        // change_color_scheme doesn't modify our local copy of Options!
-                        opts.color_scheme = app.opts().color_scheme;
-                        // If the player picks a different scheme, don't undo it later.
-                        opts.toggle_day_night_colors = false;
+                    opts.pattern_fills = self
+                        .panel
+                        .is_checked("Draw patterns to tell categories apart");
+
+                    let color_scheme = self.panel.dropdown_value("Color scheme");
+                    let colorblind_safe = self.panel.is_checked("Use colorblind-safe colors");
+                    if opts.color_scheme != color_scheme || opts.colorblind_safe != colorblind_safe
+                    {
+                        if opts.color_scheme != color_scheme {
+                            // If the player picks a different scheme, don't undo it later.
+                            opts.toggle_day_night_colors = false;
+                        }
+                        opts.color_scheme = color_scheme;
+                        opts.colorblind_safe = colorblind_safe;
+                        // reload_color_scheme reads from the app's copy of Options, not ours
+                        app.mut_opts().color_scheme = color_scheme;
+                        app.mut_opts().colorblind_safe = colorblind_safe;
+                        app.reload_color_scheme(ctx);
                     }
 
                     opts.units.metric = self.panel.is_checked("metric / imperial units");
 
+                    let ui_language = self.panel.dropdown_value("ui language");
+                    if ui_language != opts.ui_language {
+                        opts.ui_language = ui_language;
+                        // Only panels built from now on will use the new language
+                        opts.apply_ui_language(ctx);
+                    }
+
                     let language = self.panel.dropdown_value("language");
                     if language != opts.language {
                         opts.language = language;
@@ -392,14 +553,7 @@ impl<A: AppLike> State<A> for OptionsPanel {
                         }
                     }
 
-                    // Be careful -- there are some options not exposed by this panel, but per app.
-                    let show_building_driveways = opts.show_building_driveways;
-                    opts.show_building_driveways = true;
-                    let show_building_outlines = opts.show_building_outlines;
-                    opts.show_building_outlines = true;
-                    abstio::write_json(abstio::path_player("settings.json"), &opts);
-                    opts.show_building_driveways = show_building_driveways;
-                    opts.show_building_outlines = show_building_outlines;
+                    opts.save();
                     *app.mut_opts() = opts;
 
                     return widgetry::Transition::Pop;
@@ -416,3 +570,164 @@ impl<A: AppLike> State<A> for OptionsPanel {
         self.panel.draw(g);
     }
 }
+
+/// Pick a color scheme, then override individual colors in it
+pub struct ColorSchemeEditor {
+    panel: Panel,
+    scheme: ColorSchemeChoice,
+}
+
+impl ColorSchemeEditor {
+    pub fn new_state<A: AppLike>(ctx: &mut EventCtx, app: &A) -> Box<dyn State<A>> {
+        Box::new(ColorSchemeEditor::new(
+            ctx,
+            app.opts().color_scheme,
+            &app.opts().color_overrides,
+        ))
+    }
+
+    fn new(
+        ctx: &mut EventCtx,
+        scheme: ColorSchemeChoice,
+        overrides: &BTreeMap<String, String>,
+    ) -> ColorSchemeEditor {
+        let preset = ColorScheme::preset(scheme);
+        let mut rows = Vec::new();
+        for name in OVERRIDABLE_COLORS {
+            // Textures can't be overridden
+            let default = match preset.get_named_color(name) {
+                Some(c) => c,
+                None => continue,
+            };
+            let current = overrides
+                .get(name)
+                .and_then(|x| parse_hex(x))
+                .unwrap_or(default);
+            rows.push(Widget::row(vec![
+                GeomBatch::from(vec![(current, Polygon::rectangle(30.0, 30.0))])
+                    .into_widget(ctx)
+                    .centered_vert(),
+                name.replace('_', " ").text_widget(ctx).centered_vert(),
+                TextBox::widget(ctx, name, current.as_hex(), false, 7).align_right(),
+                ctx.style()
+                    .btn_plain
+                    .icon("system/assets/tools/undo.svg")
+                    .disabled(!overrides.contains_key(name))
+                    .build_widget(ctx, format!("reset {}", name)),
+            ]));
+        }
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::custom_row(vec![
+                Line("Colors").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Widget::row(vec![
+                "Start from:".text_widget(ctx).centered_vert(),
+                Widget::dropdown(ctx, "preset", scheme, ColorSchemeChoice::choices()),
+            ]),
+            "Type a hex color like #FF0000 to change anything below.".text_widget(ctx),
+            Widget::col(rows),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_solid_primary
+                    .text("Apply")
+                    .hotkey(Key::Enter)
+                    .build_def(ctx),
+                ctx.style()
+                    .btn_outline
+                    .text("Reset all colors")
+                    .disabled(overrides.is_empty())
+                    .build_def(ctx),
+            ]),
+        ]))
+        .build(ctx);
+
+        ColorSchemeEditor { panel, scheme }
+    }
+
+    /// Returns the colors changed from the preset, and the names of any that couldn't be parsed.
+    fn read_overrides(&self) -> (BTreeMap<String, String>, Vec<String>) {
+        let preset = ColorScheme::preset(self.scheme);
+        let mut overrides = BTreeMap::new();
+        let mut bad = Vec::new();
+        for name in OVERRIDABLE_COLORS {
+            let default = match preset.get_named_color(name) {
+                Some(c) => c,
+                None => continue,
+            };
+            match parse_hex(&self.panel.text_box(name)) {
+                Some(c) => {
+                    if c.as_hex() != default.as_hex() {
+                        overrides.insert(name.to_string(), c.as_hex());
+                    }
+                }
+                None => bad.push(name.replace('_', " ")),
+            }
+        }
+        (overrides, bad)
+    }
+}
+
+impl<A: AppLike> State<A> for ColorSchemeEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut A) -> widgetry::Transition<A> {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return widgetry::Transition::Pop;
+                }
+                "Reset all colors" => {
+                    *self = ColorSchemeEditor::new(ctx, self.scheme, &BTreeMap::new());
+                }
+                "Apply" => {
+                    let (overrides, bad) = self.read_overrides();
+                    if !bad.is_empty() {
+                        return widgetry::Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Invalid colors",
+                            vec![format!(
+                                "Use hex colors like #FF0000 for: {}",
+                                bad.join(", ")
+                            )],
+                        ));
+                    }
+
+                    let mut opts = app.opts().clone();
+                    opts.color_scheme = self.scheme;
+                    opts.color_overrides = overrides;
+                    // If the player picks a scheme, don't undo it later.
+                    opts.toggle_day_night_colors = false;
+                    opts.save();
+                    *app.mut_opts() = opts;
+                    app.reload_color_scheme(ctx);
+
+                    // The settings panel underneath uses the old style and scheme
+                    return widgetry::Transition::Multi(vec![
+                        widgetry::Transition::Pop,
+                        widgetry::Transition::Replace(OptionsPanel::new_state(ctx, app)),
+                    ]);
+                }
+                x => {
+                    let name = x.strip_prefix("reset ").unwrap();
+                    let (mut overrides, _) = self.read_overrides();
+                    overrides.remove(name);
+                    *self = ColorSchemeEditor::new(ctx, self.scheme, &overrides);
+                }
+            },
+            Outcome::Changed(x) if x == "preset" => {
+                // Keep whatever the player changed, relative to the new preset
+                let (overrides, _) = self.read_overrides();
+                let scheme = self.panel.dropdown_value("preset");
+                *self = ColorSchemeEditor::new(ctx, scheme, &overrides);
+            }
+            _ => {}
+        }
+
+        widgetry::Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &A) {
+        grey_out_map(g, app);
+        self.panel.draw(g);
+    }
+}
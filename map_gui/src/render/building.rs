@@ -15,6 +15,21 @@ pub struct DrawBuilding {
 }
 
 impl DrawBuilding {
+    /// A simpler version of the building for zoomed-out views, without outlines, icons, or 3D
+    /// effects
+    pub fn draw_coarse(bldg: &Building, cs: &ColorScheme, opts: &Options, batch: &mut GeomBatch) {
+        let polygon = if opts.camera_angle == CameraAngle::Abstract {
+            Polygon::rectangle_centered(
+                bldg.polygon.center(),
+                Distance::meters(5.0),
+                Distance::meters(5.0),
+            )
+        } else {
+            bldg.polygon.clone()
+        };
+        batch.push(building_color(bldg, cs), polygon);
+    }
+
     pub fn new(
         ctx: &EventCtx,
         bldg: &Building,
@@ -24,11 +39,7 @@ impl DrawBuilding {
         bldg_batch: &mut GeomBatch,
         outlines_batch: &mut GeomBatch,
     ) -> DrawBuilding {
-        let bldg_color = if bldg.amenities.is_empty() {
-            cs.residential_building
-        } else {
-            cs.commercial_building
-        };
+        let bldg_color = building_color(bldg, cs);
 
         match &opts.camera_angle {
             CameraAngle::TopDown => {
@@ -260,3 +271,11 @@ impl Renderable for DrawBuilding {
         map.get_b(self.id).polygon.contains_pt(pt)
     }
 }
+
+fn building_color(bldg: &Building, cs: &ColorScheme) -> Color {
+    if bldg.amenities.is_empty() {
+        cs.residential_building
+    } else {
+        cs.commercial_building
+    }
+}
@@ -39,13 +39,19 @@ impl DrawLane {
 
         if !lane.is_light_rail() {
             batch.push(
-                app.cs().zoomed_road_surface(lane.lane_type, rank),
+                if road.shared_space {
+                    app.cs().sidewalk
+                } else {
+                    app.cs().zoomed_road_surface(lane.lane_type, rank)
+                },
                 self.polygon.clone(),
             );
         }
         let general_road_marking = app.cs().general_road_marking;
 
         match lane.lane_type {
+            // A shared space is paved like a sidewalk across its whole width, without markings
+            _ if road.shared_space && !lane.is_light_rail() => {}
             LaneType::Sidewalk | LaneType::Shoulder => {
                 // Don't draw these for shoulders
                 if lane.is_sidewalk() {
@@ -200,6 +206,27 @@ impl DrawLane {
             }
         }
 
+        if road.is_tunnel() {
+            // Dashed walls along the outer edges of the tunnel
+            let mut edges = Vec::new();
+            if lane.id.offset == 0 {
+                edges.push(lane.lane_center_pts.shift_left(lane.width / 2.0));
+            }
+            if lane.id.offset == road.lanes.len() - 1 {
+                edges.push(lane.lane_center_pts.shift_right(lane.width / 2.0));
+            }
+            for pl in edges.into_iter().flatten() {
+                batch.extend(
+                    app.cs().general_road_marking,
+                    pl.exact_dashed_polygons(
+                        Distance::meters(0.25),
+                        Distance::meters(2.0),
+                        Distance::meters(1.0),
+                    ),
+                );
+            }
+        }
+
         if self.zorder < 0 {
             batch = batch.color(RewriteColor::ChangeAlpha(0.5));
         }
@@ -5,6 +5,7 @@ use geom::{Bounds, Distance, QuadTree, Tessellation};
 use map_model::{
     AreaID, BuildingID, IntersectionID, LaneID, Map, ParkingLotID, Road, RoadID, TransitStopID,
 };
+use widgetry::mapspace::TiledDrawable;
 use widgetry::{Color, Drawable, EventCtx, Fill, GeomBatch};
 
 use crate::colors::ColorScheme;
@@ -27,9 +28,9 @@ pub struct DrawMap {
     pub areas: Vec<DrawArea>,
 
     pub boundary_polygon: Drawable,
-    pub draw_all_unzoomed_roads_and_intersections: Drawable,
-    pub draw_all_buildings: Drawable,
-    pub draw_all_building_outlines: Drawable,
+    pub draw_all_unzoomed_roads_and_intersections: TiledDrawable,
+    pub draw_all_buildings: TiledDrawable,
+    pub draw_all_building_outlines: TiledDrawable,
     pub draw_all_unzoomed_parking_lots: Drawable,
     pub draw_all_areas: Drawable,
 
@@ -156,10 +157,11 @@ impl DrawMap {
         cs: &ColorScheme,
         opts: &Options,
         timer: &mut Timer,
-    ) -> (Vec<DrawBuilding>, Drawable, Drawable) {
+    ) -> (Vec<DrawBuilding>, TiledDrawable, TiledDrawable) {
         let mut buildings: Vec<DrawBuilding> = Vec::new();
         let mut all_buildings = GeomBatch::new();
         let mut all_building_outlines = GeomBatch::new();
+        let mut coarse_buildings = GeomBatch::new();
         timer.start_iter("make DrawBuildings", map.all_buildings().len());
         for b in map.all_buildings() {
             timer.next();
@@ -172,10 +174,14 @@ impl DrawMap {
                 &mut all_buildings,
                 &mut all_building_outlines,
             ));
+            DrawBuilding::draw_coarse(b, cs, opts, &mut coarse_buildings);
         }
         timer.start("upload all buildings");
-        let draw_all_buildings = all_buildings.upload(ctx);
-        let draw_all_building_outlines = all_building_outlines.upload(ctx);
+        let draw_all_buildings =
+            TiledDrawable::new(ctx, vec![all_buildings], vec![coarse_buildings]);
+        // Outlines are too thin to see when zoomed out
+        let draw_all_building_outlines =
+            TiledDrawable::new(ctx, vec![all_building_outlines], vec![GeomBatch::new()]);
         timer.stop("upload all buildings");
         (buildings, draw_all_buildings, draw_all_building_outlines)
     }
@@ -206,7 +212,7 @@ impl DrawMap {
         cs: &ColorScheme,
         opts: &Options,
         timer: &mut Timer,
-    ) -> Drawable {
+    ) -> TiledDrawable {
         timer.start("generate unzoomed roads and intersections");
 
         // TODO Different in night mode
@@ -216,25 +222,32 @@ impl DrawMap {
         // makes sort_by_key annoying, so just multiply the existing z-orders by 10.
         let outline_z_offset = 5;
         let mut unzoomed_pieces: Vec<(isize, Fill, Tessellation)> = Vec::new();
+        // Just the road and intersection surfaces, for zoomed-out levels of detail
+        let mut coarse_pieces: Vec<(isize, Fill, Tessellation)> = Vec::new();
 
         for r in map.all_roads() {
             let width = r.get_width();
 
-            unzoomed_pieces.push((
-                10 * r.zorder,
-                Fill::Color(if r.is_light_rail() {
-                    cs.light_rail_track
-                } else if r.is_cycleway() {
-                    cs.unzoomed_cycleway
-                } else if r.is_footway() {
-                    cs.unzoomed_footway
-                } else if r.is_private() && cs.private_road.is_some() {
-                    cs.private_road.unwrap()
-                } else {
-                    cs.unzoomed_road_surface(r.get_rank())
-                }),
-                r.center_pts.make_polygons(width).into(),
-            ));
+            let color = if r.is_light_rail() {
+                cs.light_rail_track
+            } else if r.is_cycleway() {
+                cs.unzoomed_cycleway
+            } else if r.is_footway() {
+                cs.unzoomed_footway
+            } else if r.is_private() && cs.private_road.is_some() {
+                cs.private_road.unwrap()
+            } else {
+                cs.unzoomed_road_surface(r.get_rank())
+            };
+            let surface: Tessellation = r.center_pts.make_polygons(width).into();
+            // Fade tunnels, so the roads above them stand out
+            let fill = Fill::Color(if r.is_tunnel() {
+                color.alpha(0.5)
+            } else {
+                color
+            });
+            coarse_pieces.push((10 * r.zorder, fill.clone(), surface.clone()));
+            unzoomed_pieces.push((10 * r.zorder, fill, surface));
 
             if cs.road_outlines {
                 // Draw a thick outline on the left and right
@@ -245,7 +258,8 @@ impl DrawMap {
                 .into_iter()
                 .flatten()
                 {
-                    if (opts.simplify_basemap && r.is_cycleway()) || r.is_footway() {
+                    if (opts.simplify_basemap && r.is_cycleway()) || r.is_footway() || r.is_tunnel()
+                    {
                         for p in pl.exact_dashed_polygons(
                             0.5 * outline_thickness,
                             Distance::meters(5.0),
@@ -297,6 +311,7 @@ impl DrawMap {
             } else {
                 cs.unzoomed_interesting_intersection
             };
+            coarse_pieces.push((zorder, intersection_color.into(), i.polygon.clone().into()));
             unzoomed_pieces.push((zorder, intersection_color.into(), i.polygon.clone().into()));
 
             if cs.road_outlines {
@@ -326,12 +341,25 @@ impl DrawMap {
             }
         }
         unzoomed_pieces.sort_by_key(|(z, _, _)| *z);
-        let mut unzoomed_batch = GeomBatch::new();
-        for (_, fill, poly) in unzoomed_pieces {
-            unzoomed_batch.push(fill, poly);
+        // The map is drawn in tiles, so each z-order needs to be its own layer, or a tile's roads
+        // could cover the neighboring tile's outlines
+        let mut zorders: Vec<isize> = unzoomed_pieces.iter().map(|(z, _, _)| *z).collect();
+        zorders.dedup();
+        let mut layers: Vec<GeomBatch> = std::iter::repeat_with(GeomBatch::new)
+            .take(zorders.len())
+            .collect();
+        let mut coarse_layers: Vec<GeomBatch> = std::iter::repeat_with(GeomBatch::new)
+            .take(zorders.len())
+            .collect();
+        for (z, fill, poly) in unzoomed_pieces {
+            layers[zorders.binary_search(&z).unwrap()].push(fill, poly);
+        }
+        for (z, fill, poly) in coarse_pieces {
+            coarse_layers[zorders.binary_search(&z).unwrap()].push(fill, poly);
         }
 
-        let draw_all_unzoomed_roads_and_intersections = unzoomed_batch.upload(ctx);
+        let draw_all_unzoomed_roads_and_intersections =
+            TiledDrawable::new(ctx, layers, coarse_layers);
         timer.stop("generate unzoomed roads and intersections");
         draw_all_unzoomed_roads_and_intersections
     }
@@ -205,7 +205,10 @@ fn draw_building_driveway(app: &dyn AppLike, bldg: &Building, batch: &mut GeomBa
         .unwrap_or_else(|_| orig_pl.clone());
     if driveway.length() > Distance::meters(0.1) {
         batch.push(
-            if app.opts().color_scheme == ColorSchemeChoice::NightMode {
+            if matches!(
+                app.opts().color_scheme,
+                ColorSchemeChoice::NightMode | ColorSchemeChoice::DarkMode
+            ) {
                 Color::hex("#4B4B4B")
             } else {
                 app.cs().zoomed_road_surface(
@@ -109,7 +109,8 @@ impl<T: 'static> SimpleApp<T> {
         abstutil::logger::setup();
         ctx.canvas.settings = opts.canvas_settings.clone();
 
-        let cs = ColorScheme::new(ctx, opts.color_scheme);
+        opts.apply_ui_language(ctx);
+        let cs = ColorScheme::from_options(ctx, &opts);
         // Start with a minimal map
         let map = Map::almost_blank();
         let draw_map = DrawMap::new(ctx, &map, &opts, &cs, &mut Timer::throwaway());
@@ -144,9 +145,11 @@ impl<T: 'static> SimpleApp<T> {
         g.redraw(&self.draw_map.boundary_polygon);
         g.redraw(&self.draw_map.draw_all_areas);
         g.redraw(&self.draw_map.draw_all_unzoomed_parking_lots);
-        g.redraw(&self.draw_map.draw_all_unzoomed_roads_and_intersections);
-        g.redraw(&self.draw_map.draw_all_buildings);
-        g.redraw(&self.draw_map.draw_all_building_outlines);
+        self.draw_map
+            .draw_all_unzoomed_roads_and_intersections
+            .draw(g);
+        self.draw_map.draw_all_buildings.draw(g);
+        self.draw_map.draw_all_building_outlines.draw(g);
         // Not the building paths
 
         // Still show some shape selection when zoomed out.
@@ -186,8 +189,8 @@ impl<T: 'static> SimpleApp<T> {
             match obj.get_id() {
                 ID::Building(_) => {
                     if !drawn_all_buildings {
-                        g.redraw(&self.draw_map.draw_all_buildings);
-                        g.redraw(&self.draw_map.draw_all_building_outlines);
+                        self.draw_map.draw_all_buildings.draw(g);
+                        self.draw_map.draw_all_building_outlines.draw(g);
                         drawn_all_buildings = true;
                     }
                 }
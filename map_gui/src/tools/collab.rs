@@ -0,0 +1,514 @@
+//! Lets several people edit the same map together, by relaying changes through a small server
+//! (`cli collab-relay`). Edits are shared per object -- a road, an intersection, or part of a
+//! transit route -- as the net change from the basemap, so the order people make changes in
+//! doesn't matter. Changing an object or opening it in an editor claims a lock on it, and changes
+//! to something somebody else holds are undone.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use futures_channel::oneshot;
+use instant::Instant;
+use serde::{Deserialize, Serialize};
+
+use geom::Duration;
+use map_model::osm::NodeID;
+use map_model::{IntersectionID, Map, MapEdits, OriginalRoad, PermanentEditCmd, RoadID};
+use widgetry::tools::BackgroundRunner;
+use widgetry::{EventCtx, Line, Panel, SimpleState, State, TextBox, TextExt, Transition, Widget};
+
+const POLL_PERIOD: Duration = Duration::const_seconds(2.0);
+
+pub struct CollabSession {
+    relay_url: String,
+    session: String,
+    client: String,
+
+    /// The net change to every object anybody in the session has edited, as JSON
+    shared: BTreeMap<String, String>,
+    /// Object to the client holding its lock
+    locks: BTreeMap<String, String>,
+    clients: Vec<String>,
+    /// The relay's sequence number as of the last poll
+    seq: usize,
+
+    runner: BackgroundRunner,
+    poll: Option<oneshot::Receiver<Result<String>>>,
+    last_poll: Instant,
+    requests: Vec<(Request, oneshot::Receiver<Result<String>>)>,
+    /// Our view of the session is out of date, so ask for everything in the next poll
+    resync: bool,
+    /// Nothing has been heard from the relay yet
+    joining: bool,
+    /// Local changes to undo, because somebody else holds the object
+    rejected: Vec<String>,
+    error: Option<String>,
+}
+
+enum Request {
+    Publish,
+    Claim(String),
+}
+
+/// What changed in the session since the last event
+pub struct RemoteChanges {
+    /// Apply these to the map
+    pub edits: MapEdits,
+    /// Local changes that were undone, because somebody else is editing the same thing
+    pub rejected: Vec<String>,
+}
+
+impl CollabSession {
+    pub fn new(relay_url: String, session: String, client: String) -> CollabSession {
+        CollabSession {
+            relay_url: relay_url.trim_end_matches('/').to_string(),
+            session,
+            client,
+            shared: BTreeMap::new(),
+            locks: BTreeMap::new(),
+            clients: Vec::new(),
+            seq: 0,
+            runner: BackgroundRunner::new(),
+            poll: None,
+            last_poll: Instant::now(),
+            requests: Vec::new(),
+            resync: true,
+            joining: true,
+            rejected: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Call this every event while the session is active. If anybody else changed something, or
+    /// some local changes were rejected, returns the edits the map should have now.
+    ///
+    /// Joining a session adopts its edits, unless nobody has changed anything yet. Then the local
+    /// edits are shared instead.
+    pub fn event(&mut self, ctx: &mut EventCtx, map: &Map) -> Option<RemoteChanges> {
+        // Keep polling even when the player isn't doing anything
+        ctx.request_update(widgetry::UpdateType::Game);
+
+        let mut changed = false;
+        if let Some(ref mut rx) = self.poll {
+            match rx.try_recv() {
+                Ok(None) => {}
+                Ok(Some(result)) => {
+                    self.poll = None;
+                    match result
+                        .and_then(|resp| abstutil::from_json::<PollResponse>(resp.as_bytes()))
+                    {
+                        Ok(resp) => {
+                            let session_empty = self.resync && resp.updates.is_empty();
+                            changed |= self.handle_poll(resp);
+                            self.error = None;
+                            if std::mem::take(&mut self.joining) && session_empty {
+                                changed = false;
+                                self.publish(map);
+                            }
+                        }
+                        Err(err) => self.failed(err),
+                    }
+                }
+                Err(_) => {
+                    self.poll = None;
+                }
+            }
+        }
+
+        let mut still_waiting = Vec::new();
+        for (req, mut rx) in std::mem::take(&mut self.requests) {
+            let resp = match rx.try_recv() {
+                Ok(None) => {
+                    still_waiting.push((req, rx));
+                    continue;
+                }
+                Ok(Some(Ok(resp))) => resp,
+                Ok(Some(Err(err))) => {
+                    self.failed(err);
+                    continue;
+                }
+                Err(_) => continue,
+            };
+            if let Err(err) = self.handle_response(req, resp) {
+                self.failed(err);
+            }
+        }
+        self.requests = still_waiting;
+
+        // Wait for changes to be published before asking for the latest, so a stale response
+        // doesn't undo them
+        if self.poll.is_none()
+            && self.requests.is_empty()
+            && Duration::realtime_elapsed(self.last_poll) >= POLL_PERIOD
+        {
+            self.last_poll = Instant::now();
+            let url = format!("{}/poll", self.relay_url);
+            let body = abstutil::to_json(&PollRequest {
+                session: self.session.clone(),
+                client: self.client.clone(),
+                since: if self.resync { 0 } else { self.seq },
+            });
+            self.poll = Some(
+                self.runner
+                    .spawn(Box::pin(async move { abstio::http_post(url, body).await })),
+            );
+        }
+
+        // Wait until the session is up to date before undoing anything
+        if self.resync || (!changed && self.rejected.is_empty()) {
+            return None;
+        }
+        Some(RemoteChanges {
+            edits: self.to_edits(map),
+            rejected: std::mem::take(&mut self.rejected),
+        })
+    }
+
+    /// Call this after applying any local edits to the map, to share them.
+    pub fn publish(&mut self, map: &Map) {
+        let local = net_changes(map);
+        let mut changes = Vec::new();
+        for (object, payload) in &local {
+            if self.shared.get(object) != Some(payload) {
+                changes.push(Change {
+                    object: object.clone(),
+                    payload: Some(payload.clone()),
+                });
+            }
+        }
+        for object in self.shared.keys() {
+            if !local.contains_key(object) {
+                changes.push(Change {
+                    object: object.clone(),
+                    payload: None,
+                });
+            }
+        }
+
+        let mut rejected = Vec::new();
+        changes.retain(|change| match self.locked_by_other(&change.object) {
+            Some(holder) => {
+                rejected.push(format!("{} is being edited by {}", change.object, holder));
+                false
+            }
+            None => true,
+        });
+        self.rejected.extend(rejected);
+        if changes.is_empty() {
+            return;
+        }
+        for change in &changes {
+            match change.payload {
+                Some(ref payload) => {
+                    self.shared.insert(change.object.clone(), payload.clone());
+                }
+                None => {
+                    self.shared.remove(&change.object);
+                }
+            }
+            self.claim_locally(change.object.clone());
+        }
+
+        let url = format!("{}/publish", self.relay_url);
+        let body = abstutil::to_json(&PublishRequest {
+            session: self.session.clone(),
+            client: self.client.clone(),
+            changes,
+        });
+        self.requests.push((
+            Request::Publish,
+            self.runner
+                .spawn(Box::pin(async move { abstio::http_post(url, body).await })),
+        ));
+        // Any poll in flight might not include these changes
+        self.poll = None;
+    }
+
+    /// Claims the lock on an object, before opening it in an editor. Returns the other person
+    /// editing it, if any.
+    pub fn claim(&mut self, object: String) -> Option<String> {
+        if let Some(holder) = self.locked_by_other(&object) {
+            return Some(holder.clone());
+        }
+        self.claim_locally(object.clone());
+        let url = format!("{}/claim", self.relay_url);
+        let body = abstutil::to_json(&ClaimRequest {
+            session: self.session.clone(),
+            client: self.client.clone(),
+            object: object.clone(),
+        });
+        self.requests.push((
+            Request::Claim(object),
+            self.runner
+                .spawn(Box::pin(async move { abstio::http_post(url, body).await })),
+        ));
+        None
+    }
+
+    pub fn locked_by_other(&self, object: &str) -> Option<&String> {
+        self.locks
+            .get(object)
+            .filter(|holder| **holder != self.client)
+    }
+
+    /// Who else is in the session, and whether the relay is reachable. This changes as people
+    /// come and go, so callers should compare it to what they last showed.
+    pub fn describe(&self) -> String {
+        let others: Vec<&str> = self
+            .clients
+            .iter()
+            .filter(|c| **c != self.client)
+            .map(|c| c.as_str())
+            .collect();
+        let mut msg = if others.is_empty() {
+            format!("Session \"{}\": nobody else here yet", self.session)
+        } else {
+            format!("Session \"{}\" with {}", self.session, others.join(", "))
+        };
+        if self.error.is_some() {
+            msg.push_str(" (can't reach the relay)");
+        }
+        msg
+    }
+
+    fn handle_poll(&mut self, resp: PollResponse) -> bool {
+        let mut changed = false;
+        if std::mem::take(&mut self.resync) {
+            // Starting over, so the response has everything
+            let shared: BTreeMap<String, String> = resp
+                .updates
+                .into_iter()
+                .filter_map(|update| update.payload.map(|payload| (update.object, payload)))
+                .collect();
+            changed = shared != self.shared;
+            self.shared = shared;
+        } else {
+            for update in resp.updates {
+                let old = match update.payload {
+                    Some(ref payload) => self.shared.insert(update.object, payload.clone()),
+                    None => self.shared.remove(&update.object),
+                };
+                changed |= old != update.payload;
+            }
+        }
+        self.seq = resp.seq;
+        self.locks = resp.locks;
+        self.clients = resp.clients;
+        changed
+    }
+
+    fn handle_response(&mut self, req: Request, resp: String) -> Result<()> {
+        match req {
+            Request::Publish => {
+                let resp: PublishResponse = abstutil::from_json(resp.as_bytes())?;
+                if !resp.rejected.is_empty() {
+                    for (object, holder) in resp.rejected {
+                        self.rejected
+                            .push(format!("{} is being edited by {}", object, holder));
+                    }
+                    self.resync = true;
+                }
+            }
+            Request::Claim(object) => {
+                // If somebody else got there first, the next poll will also say so
+                let resp: ClaimResponse = abstutil::from_json(resp.as_bytes())?;
+                self.locks.insert(object, resp.holder);
+            }
+        }
+        Ok(())
+    }
+
+    fn to_edits(&self, map: &Map) -> MapEdits {
+        let mut edits = map.get_edits().clone();
+        edits.commands = self
+            .shared
+            .iter()
+            .filter_map(|(object, payload)| {
+                match abstutil::from_json::<PermanentEditCmd>(payload.as_bytes())
+                    .and_then(|cmd| cmd.into_cmd(map))
+                {
+                    Ok(cmd) => Some(cmd),
+                    Err(err) => {
+                        warn!("Skipping shared change to {}: {}", object, err);
+                        None
+                    }
+                }
+            })
+            .collect();
+        edits
+    }
+
+    // Each client holds one lock at a time
+    fn claim_locally(&mut self, object: String) {
+        let client = self.client.clone();
+        self.locks.retain(|_, holder| *holder != client);
+        self.locks.insert(object, client);
+    }
+
+    fn failed(&mut self, err: anyhow::Error) {
+        warn!("Collaborative editing relay: {}", err);
+        self.error = Some(err.to_string());
+    }
+}
+
+/// The object key for a road, to use with `claim`
+pub fn road_object(map: &Map, r: RoadID) -> String {
+    describe_road(map.get_r(r).orig_id)
+}
+
+/// The object key for an intersection, to use with `claim`
+pub fn intersection_object(map: &Map, i: IntersectionID) -> String {
+    describe_intersection(map.get_i(i).orig_id)
+}
+
+fn describe_road(r: OriginalRoad) -> String {
+    format!("road {} ({} to {})", r.osm_way_id.0, r.i1.0, r.i2.0)
+}
+
+fn describe_intersection(i: NodeID) -> String {
+    format!("intersection {}", i.0)
+}
+
+/// Every object changed from the basemap, with its net change as JSON
+fn net_changes(map: &Map) -> BTreeMap<String, String> {
+    let mut edits = map.get_edits().clone();
+    edits.commands.clear();
+    edits.compress(map);
+    edits
+        .commands
+        .iter()
+        .map(|cmd| {
+            let cmd = cmd.to_perma(map);
+            let object = match cmd {
+                PermanentEditCmd::ChangeRoad { r, .. } => describe_road(r),
+                PermanentEditCmd::ChangeIntersection { i, .. } => describe_intersection(i),
+                PermanentEditCmd::ChangeRouteSchedule { ref gtfs_id, .. } => {
+                    format!("schedule of route {}", gtfs_id)
+                }
+                PermanentEditCmd::ChangeRouteFare { ref gtfs_id, .. } => {
+                    format!("fare of route {}", gtfs_id)
+                }
+                // compress() never produces groups
+                PermanentEditCmd::Group { .. } => unreachable!(),
+            };
+            (object, abstutil::to_json(&cmd))
+        })
+        .collect()
+}
+
+/// Asks where the relay is and what to call the session, then joins it.
+pub struct JoinCollabSession<A> {
+    on_join: Option<Box<dyn FnOnce(&mut EventCtx, &mut A, CollabSession) -> Transition<A>>>,
+}
+
+impl<A: 'static> JoinCollabSession<A> {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        on_join: Box<dyn FnOnce(&mut EventCtx, &mut A, CollabSession) -> Transition<A>>,
+    ) -> Box<dyn State<A>> {
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Edit together").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            "Everybody joining the same session on the same map sees each other's changes."
+                .text_widget(ctx),
+            Widget::row(vec![
+                "Relay:".text_widget(ctx).centered_vert(),
+                TextBox::default_widget(ctx, "relay", "http://localhost:8090".to_string()),
+            ]),
+            Widget::row(vec![
+                "Session:".text_widget(ctx).centered_vert(),
+                TextBox::default_widget(ctx, "session", String::new()),
+            ]),
+            Widget::row(vec![
+                "Your name:".text_widget(ctx).centered_vert(),
+                TextBox::default_widget(ctx, "name", String::new()),
+            ]),
+            ctx.style().btn_solid_primary.text("Join").build_def(ctx),
+        ]))
+        .build(ctx);
+        <dyn SimpleState<_>>::new_state(
+            panel,
+            Box::new(JoinCollabSession {
+                on_join: Some(on_join),
+            }),
+        )
+    }
+}
+
+impl<A: 'static> SimpleState<A> for JoinCollabSession<A> {
+    fn on_click(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut A,
+        x: &str,
+        panel: &mut Panel,
+    ) -> Transition<A> {
+        match x {
+            "close" => Transition::Pop,
+            "Join" => {
+                let relay = panel.text_box("relay");
+                let session = panel.text_box("session");
+                let name = panel.text_box("name");
+                if relay.is_empty() || session.is_empty() || name.is_empty() {
+                    return Transition::Keep;
+                }
+                let on_join = self.on_join.take().unwrap();
+                on_join(ctx, app, CollabSession::new(relay, session, name))
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PollRequest {
+    session: String,
+    client: String,
+    since: usize,
+}
+
+#[derive(Deserialize)]
+struct PollResponse {
+    seq: usize,
+    updates: Vec<Update>,
+    locks: BTreeMap<String, String>,
+    clients: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Update {
+    object: String,
+    payload: Option<String>,
+}
+
+#[derive(Serialize)]
+struct PublishRequest {
+    session: String,
+    client: String,
+    changes: Vec<Change>,
+}
+
+#[derive(Serialize)]
+struct Change {
+    object: String,
+    payload: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PublishResponse {
+    rejected: BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+struct ClaimRequest {
+    session: String,
+    client: String,
+    object: String,
+}
+
+#[derive(Deserialize)]
+struct ClaimResponse {
+    holder: String,
+}
@@ -1,10 +1,10 @@
 use std::collections::HashMap;
 
 use abstutil::Counter;
-use geom::{Circle, Distance};
+use geom::{Circle, Distance, Polygon};
 use map_model::{BuildingID, IntersectionID, LaneID, Map, ParkingLotID, RoadID, TransitStopID};
 use widgetry::mapspace::{ToggleZoomed, ToggleZoomedBuilder};
-use widgetry::tools::{ColorLegend, ColorScale};
+use widgetry::tools::{ColorLegend, ColorScale, Pattern};
 use widgetry::{Color, EventCtx, GeomBatch, Widget};
 
 use crate::AppLike;
@@ -17,6 +17,9 @@ pub struct ColorDiscrete<'a> {
     // Store both, so we can build the legend in the original order later
     pub categories: Vec<(String, Color)>,
     colors: HashMap<String, Color>,
+    /// Only if the player wants pattern fills. Only areas like buildings and intersections get
+    /// them; roads are too thin.
+    patterns: Option<HashMap<String, Pattern>>,
 }
 
 impl<'a> ColorDiscrete<'a> {
@@ -31,11 +34,23 @@ impl<'a> ColorDiscrete<'a> {
         );
         let categories: Vec<(String, Color)> =
             categories.into_iter().map(|(k, v)| (k.into(), v)).collect();
+        let patterns = if app.opts().pattern_fills {
+            Some(
+                categories
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, (k, _))| (k.clone(), Pattern::nth(idx)))
+                    .collect(),
+            )
+        } else {
+            None
+        };
         ColorDiscrete {
             map: app.map(),
             draw,
             colors: categories.iter().cloned().collect(),
             categories,
+            patterns,
         }
     }
 
@@ -77,6 +92,8 @@ impl<'a> ColorDiscrete<'a> {
         self.draw
             .zoomed
             .push(color.alpha(0.4), self.map.get_i(i).polygon.clone());
+        let map = self.map;
+        self.add_pattern(category.as_ref(), &map.get_i(i).polygon);
     }
 
     pub fn add_b<I: AsRef<str>>(&mut self, b: BuildingID, category: I) {
@@ -87,6 +104,16 @@ impl<'a> ColorDiscrete<'a> {
         self.draw
             .zoomed
             .push(color.alpha(0.4), self.map.get_b(b).polygon.clone());
+        let map = self.map;
+        self.add_pattern(category.as_ref(), &map.get_b(b).polygon);
+    }
+
+    fn add_pattern(&mut self, category: &str, polygon: &Polygon) {
+        if let Some(ref patterns) = self.patterns {
+            let pattern = patterns[category];
+            pattern.draw(&mut self.draw.unzoomed, polygon, Distance::meters(4.0));
+            pattern.draw(&mut self.draw.zoomed, polygon, Distance::meters(2.0));
+        }
     }
 
     pub fn add_ts<I: AsRef<str>>(&mut self, ts: TransitStopID, category: I) {
@@ -102,10 +129,14 @@ impl<'a> ColorDiscrete<'a> {
     }
 
     pub fn build(self, ctx: &EventCtx) -> (ToggleZoomed, Widget) {
+        let patterns = self.patterns;
         let legend = self
             .categories
             .into_iter()
-            .map(|(name, color)| ColorLegend::row(ctx, color, name))
+            .map(|(name, color)| match patterns {
+                Some(ref patterns) => ColorLegend::pattern_row(ctx, color, patterns[&name], name),
+                None => ColorLegend::row(ctx, color, name),
+            })
             .collect();
         (self.draw.build(ctx), Widget::col(legend))
     }
@@ -3,9 +3,11 @@ use std::io::Write;
 use anyhow::Result;
 
 use abstio::MapName;
+use geom::{Distance, Polygon, Pt2D};
 use widgetry::tools::{open_browser, PopupMsg};
 use widgetry::{
-    EventCtx, GfxCtx, Line, Outcome, Panel, State, TextBox, TextExt, Toggle, Transition, Widget,
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, State,
+    Text, TextBox, TextExt, Toggle, Transition, VerticalAlignment, Widget,
 };
 
 use crate::load::MapLoader;
@@ -16,6 +18,8 @@ pub struct ImportCity<A: AppLike> {
     panel: Panel,
     // Wrapped in an Option just to make calling from event() work.
     on_load: Option<Box<dyn FnOnce(&mut EventCtx, &mut A) -> Transition<A>>>,
+    // Set by DrawBoundary after it writes boundary.geojson
+    boundary_drawn: bool,
 }
 
 impl<A: AppLike + 'static> ImportCity<A> {
@@ -49,6 +53,14 @@ impl<A: AppLike + 'static> ImportCity<A> {
                     "Copy the JSON text on the right into your clipboard".text_widget(ctx),
                 ])
                 .margin_below(16),
+                Widget::row(vec![
+                    "Or:".text_widget(ctx).centered_vert(),
+                    ctx.style()
+                        .btn_outline
+                        .text("Draw a rectangle near this map")
+                        .build_def(ctx),
+                ])
+                .margin_below(16),
                 Widget::row(vec![
                     "Name the map:".text_widget(ctx).centered_vert(),
                     TextBox::widget(ctx, "new_map_name", generate_new_map_name(), true, 20),
@@ -88,12 +100,65 @@ impl<A: AppLike + 'static> ImportCity<A> {
         Box::new(ImportCity {
             panel,
             on_load: Some(on_load),
+            boundary_drawn: false,
         })
     }
+
+    /// Run the importer on boundary.geojson, then load the new map.
+    fn start_import(&self, ctx: &mut EventCtx) -> Transition<A> {
+        let name = sanitize_name(self.panel.text_box("new_map_name"));
+
+        let mut args = vec![
+            find_exe("cli"),
+            "one-step-import".to_string(),
+            "--geojson-path=boundary.geojson".to_string(),
+            format!("--map-name={}", name),
+        ];
+        if self.panel.is_checked("source") {
+            args.push("--use-geofabrik".to_string());
+        }
+        if self.panel.is_checked("Infer sidewalks on roads") {
+            args.push("--inferred-sidewalks".to_string());
+        }
+        if self.panel.is_checked("Filter crosswalks") {
+            args.push("--filter-crosswalks".to_string());
+        }
+        if self
+            .panel
+            .is_checked("Generate travel demand model (UK only)")
+        {
+            args.push("--create-uk-travel-demand-model".to_string());
+        }
+        Transition::Push(crate::tools::RunCommand::new_state(
+            ctx,
+            true,
+            args,
+            Box::new(|_, _, success, _| {
+                if success {
+                    abstio::delete_file("boundary.geojson");
+
+                    Transition::ConsumeState(Box::new(move |state, ctx, app| {
+                        let mut state = state.downcast::<ImportCity<A>>().ok().unwrap();
+                        let on_load = state.on_load.take().unwrap();
+                        let map_name = MapName::new("zz", "oneshot", &name);
+                        vec![MapLoader::new_state(ctx, app, map_name, on_load)]
+                    }))
+                } else {
+                    // The popup already explained the failure
+                    Transition::Keep
+                }
+            }),
+        ))
+    }
 }
 
 impl<A: AppLike + 'static> State<A> for ImportCity<A> {
     fn event(&mut self, ctx: &mut EventCtx, _: &mut A) -> Transition<A> {
+        if self.boundary_drawn {
+            self.boundary_drawn = false;
+            return self.start_import(ctx);
+        }
+
         match self.panel.event(ctx) {
             Outcome::Clicked(x) => match x.as_ref() {
                 "close" => Transition::Pop,
@@ -105,69 +170,186 @@ impl<A: AppLike + 'static> State<A> for ImportCity<A> {
                     open_browser("http://geojson.io");
                     Transition::Keep
                 }
-                "Import the area from your clipboard" => {
-                    let name = sanitize_name(self.panel.text_box("new_map_name"));
-
-                    let mut args = vec![
-                        find_exe("cli"),
-                        "one-step-import".to_string(),
-                        "--geojson-path=boundary.geojson".to_string(),
-                        format!("--map-name={}", name),
-                    ];
-                    if self.panel.is_checked("source") {
-                        args.push("--use-geofabrik".to_string());
-                    }
-                    if self.panel.is_checked("Infer sidewalks on roads") {
-                        args.push("--inferred-sidewalks".to_string());
-                    }
-                    if self.panel.is_checked("Filter crosswalks") {
-                        args.push("--filter-crosswalks".to_string());
-                    }
-                    if self
-                        .panel
-                        .is_checked("Generate travel demand model (UK only)")
-                    {
-                        args.push("--create-uk-travel-demand-model".to_string());
-                    }
-                    match grab_geojson_from_clipboard() {
-                        Ok(()) => Transition::Push(crate::tools::RunCommand::new_state(
-                            ctx,
-                            true,
-                            args,
-                            Box::new(|_, _, success, _| {
-                                if success {
-                                    abstio::delete_file("boundary.geojson");
-
-                                    Transition::ConsumeState(Box::new(move |state, ctx, app| {
-                                        let mut state =
-                                            state.downcast::<ImportCity<A>>().ok().unwrap();
-                                        let on_load = state.on_load.take().unwrap();
-                                        let map_name = MapName::new("zz", "oneshot", &name);
-                                        vec![MapLoader::new_state(ctx, app, map_name, on_load)]
-                                    }))
-                                } else {
-                                    // The popup already explained the failure
-                                    Transition::Keep
-                                }
-                            }),
-                        )),
-                        Err(err) => Transition::Push(PopupMsg::new_state(
+                "Draw a rectangle near this map" => {
+                    Transition::Push(DrawBoundary::new_state::<A>(ctx))
+                }
+                "Import the area from your clipboard" => match grab_geojson_from_clipboard() {
+                    Ok(()) => self.start_import(ctx),
+                    Err(err) => Transition::Push(PopupMsg::new_state(
+                        ctx,
+                        "Error",
+                        vec![
+                            "Couldn't get GeoJSON from your clipboard".to_string(),
+                            err.to_string(),
+                        ],
+                    )),
+                },
+                _ => unreachable!(),
+            },
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &A) {
+        self.panel.draw(g);
+    }
+}
+
+/// Lets the user draw a rectangle to import by clicking two corners. The canvas can only be panned
+/// around the current map, whose projection is used to convert the rectangle back to GPS, so this
+/// is for importing an area overlapping or next to the current map. Anywhere else has to be drawn
+/// in geojson.io.
+struct DrawBoundary {
+    panel: Panel,
+    first_corner: Option<Pt2D>,
+    rectangle: Option<Polygon>,
+    draw: Drawable,
+}
+
+impl DrawBoundary {
+    fn new_state<A: AppLike + 'static>(ctx: &mut EventCtx) -> Box<dyn State<A>> {
+        let mut state = DrawBoundary {
+            panel: Panel::empty(ctx),
+            first_corner: None,
+            rectangle: None,
+            draw: Drawable::empty(ctx),
+        };
+        state.update(ctx, None);
+        Box::new(state)
+    }
+
+    /// Rebuild the preview and panel, with the cursor as the second corner if the rectangle isn't
+    /// finished yet.
+    fn update(&mut self, ctx: &mut EventCtx, cursor: Option<Pt2D>) {
+        let preview = self.rectangle.clone().or_else(|| {
+            self.first_corner
+                .zip(cursor)
+                .and_then(|(pt1, pt2)| Polygon::rectangle_two_corners(pt1, pt2))
+        });
+
+        let mut txt = Text::from(Line("Import a new area").small_heading());
+        txt.add_line("Click two opposite corners of a rectangle near the current map.");
+        txt.add_line(
+            Line("To import somewhere farther away, draw a boundary in geojson.io instead")
+                .secondary(),
+        );
+        if let Some(ref polygon) = preview {
+            let bounds = polygon.get_bounds();
+            txt.add_line(format!(
+                "{} by {}",
+                Distance::meters(bounds.width()),
+                Distance::meters(bounds.height())
+            ));
+            if bounds.width().max(bounds.height()) > 20_000.0 {
+                txt.add_line(
+                    Line("Large areas may fail to download and will be slow to import")
+                        .fg(Color::RED),
+                );
+            }
+        }
+        self.panel = Panel::new_builder(Widget::col(vec![
+            txt.into_widget(ctx),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_solid_primary
+                    .text("Import this area")
+                    .disabled(self.rectangle.is_none())
+                    .build_def(ctx),
+                ctx.style().btn_outline.text("Cancel").build_def(ctx),
+            ]),
+        ]))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+        .build(ctx);
+
+        let mut batch = GeomBatch::new();
+        if let Some(polygon) = preview {
+            batch.push(Color::BLUE.alpha(0.5), polygon);
+        }
+        self.draw = ctx.upload(batch);
+    }
+
+    /// Write the rectangle as boundary.geojson
+    fn save_boundary<A: AppLike>(&self, app: &A) -> Result<()> {
+        let bounds = self.rectangle.as_ref().unwrap().get_bounds();
+        let corners = vec![
+            Pt2D::new(bounds.min_x, bounds.min_y),
+            Pt2D::new(bounds.max_x, bounds.min_y),
+            Pt2D::new(bounds.max_x, bounds.max_y),
+            Pt2D::new(bounds.min_x, bounds.max_y),
+            Pt2D::new(bounds.min_x, bounds.min_y),
+        ];
+        let ring = app
+            .map()
+            .get_gps_bounds()
+            .convert_back(&corners)
+            .into_iter()
+            .map(|gps| vec![gps.x(), gps.y()])
+            .collect();
+        let geojson = geojson::GeoJson::from(geojson::FeatureCollection {
+            bbox: None,
+            features: vec![geojson::Feature {
+                geometry: Some(geojson::Geometry::new(geojson::Value::Polygon(vec![ring]))),
+                ..Default::default()
+            }],
+            foreign_members: None,
+        });
+        fs_err::write("boundary.geojson", geojson.to_string())?;
+        Ok(())
+    }
+}
+
+impl<A: AppLike + 'static> State<A> for DrawBoundary {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut A) -> Transition<A> {
+        ctx.canvas_movement();
+        if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+            if ctx.normal_left_click() {
+                if self.first_corner.is_none() || self.rectangle.is_some() {
+                    self.first_corner = Some(pt);
+                    self.rectangle = None;
+                } else {
+                    self.rectangle = Polygon::rectangle_two_corners(self.first_corner.unwrap(), pt);
+                }
+                self.update(ctx, Some(pt));
+            } else if ctx.redo_mouseover()
+                && self.first_corner.is_some()
+                && self.rectangle.is_none()
+            {
+                self.update(ctx, Some(pt));
+            }
+        }
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "Cancel" => {
+                    return Transition::Pop;
+                }
+                "Import this area" => {
+                    if let Err(err) = self.save_boundary(app) {
+                        return Transition::Push(PopupMsg::new_state(
                             ctx,
                             "Error",
-                            vec![
-                                "Couldn't get GeoJSON from your clipboard".to_string(),
-                                err.to_string(),
-                            ],
-                        )),
+                            vec![format!("Couldn't write boundary.geojson: {}", err)],
+                        ));
                     }
+                    return Transition::Multi(vec![
+                        Transition::Pop,
+                        Transition::ModifyState(Box::new(|state, _, _| {
+                            state
+                                .downcast_mut::<ImportCity<A>>()
+                                .unwrap()
+                                .boundary_drawn = true;
+                        })),
+                    ]);
                 }
                 _ => unreachable!(),
-            },
-            _ => Transition::Keep,
+            }
         }
+
+        Transition::Keep
     }
 
     fn draw(&self, g: &mut GfxCtx, _: &A) {
+        g.redraw(&self.draw);
         self.panel.draw(g);
     }
 }
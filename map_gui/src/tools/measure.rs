@@ -0,0 +1,191 @@
+use geom::{Circle, Distance, FindClosest, PolyLine, Pt2D};
+use map_model::{
+    LaneID, PathConstraints, PathRequest, Position, MAX_BIKE_SPEED, MAX_WALKING_SPEED,
+};
+use widgetry::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel,
+    State, Text, Transition, VerticalAlignment, Widget,
+};
+
+use crate::AppLike;
+
+/// Click points on the map to measure the distance between them, both in a straight line and
+/// walking along the road network.
+pub struct MeasureDistance {
+    panel: Panel,
+    draw: Drawable,
+    points: Vec<Pt2D>,
+    /// For each pair of consecutive points, the walking route between them, if there is one
+    routes: Vec<Option<PolyLine>>,
+    snap_to_sidewalks: FindClosest<LaneID>,
+}
+
+impl MeasureDistance {
+    pub fn new_state<A: AppLike + 'static>(ctx: &mut EventCtx, app: &A) -> Box<dyn State<A>> {
+        let map = app.map();
+        let mut snap_to_sidewalks = FindClosest::new();
+        for l in map.all_lanes() {
+            if PathConstraints::Pedestrian.can_use(l, map) {
+                snap_to_sidewalks.add_polygon(l.id, &l.get_thick_polygon());
+            }
+        }
+
+        let mut state = MeasureDistance {
+            panel: Panel::empty(ctx),
+            draw: Drawable::empty(ctx),
+            points: Vec::new(),
+            routes: Vec::new(),
+            snap_to_sidewalks,
+        };
+        state.update(ctx, app);
+        Box::new(state)
+    }
+
+    fn snap(&self, app: &dyn AppLike, pt: Pt2D) -> Option<Position> {
+        let (l, _) = self
+            .snap_to_sidewalks
+            .closest_pt(pt, Distance::meters(30.0))?;
+        let pl = &app.map().get_l(l).lane_center_pts;
+        let (dist, _) = pl.dist_along_of_point(pl.project_pt(pt))?;
+        Some(Position::new(l, dist.min(pl.length())))
+    }
+
+    fn add_point(&mut self, app: &dyn AppLike, pt: Pt2D) {
+        if let Some(last) = self.points.last().cloned() {
+            let route = self
+                .snap(app, last)
+                .zip(self.snap(app, pt))
+                .and_then(|(start, end)| {
+                    app.map()
+                        .pathfind(PathRequest::walking(start, end))
+                        .ok()
+                        .and_then(|path| path.trace(app.map()))
+                });
+            self.routes.push(route);
+        }
+        self.points.push(pt);
+    }
+
+    fn update<A: AppLike>(&mut self, ctx: &mut EventCtx, app: &A) {
+        let units = &app.opts().units;
+        let mut txt = Text::from(Line("Measure distance").small_heading());
+        if self.points.len() < 2 {
+            txt.add_line("Click points on the map. You can pan anywhere.");
+        } else {
+            let straight_line = self
+                .points
+                .windows(2)
+                .fold(Distance::ZERO, |sum, pair| sum + pair[0].dist_to(pair[1]));
+            txt.add_line(format!("Straight line: {}", straight_line.to_string(units)));
+
+            if self.routes.iter().all(|r| r.is_some()) {
+                let along_roads = self
+                    .routes
+                    .iter()
+                    .fold(Distance::ZERO, |sum, r| sum + r.as_ref().unwrap().length());
+                txt.add_line(format!("Along roads: {}", along_roads.to_string(units)));
+                txt.add_line(format!(
+                    "Walking: {}",
+                    (along_roads / MAX_WALKING_SPEED).to_string(units)
+                ));
+                txt.add_line(format!(
+                    "Cycling: {}",
+                    (along_roads / MAX_BIKE_SPEED).to_string(units)
+                ));
+            } else {
+                txt.add_line(
+                    Line("Some points aren't connected by a walking route").fg(Color::RED),
+                );
+            }
+        }
+
+        self.panel = Panel::new_builder(Widget::col(vec![
+            txt.into_widget(ctx),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_outline
+                    .text("undo")
+                    .hotkey(Key::Backspace)
+                    .disabled(self.points.is_empty())
+                    .build_def(ctx),
+                ctx.style()
+                    .btn_outline
+                    .text("clear")
+                    .disabled(self.points.is_empty())
+                    .build_def(ctx),
+                ctx.style()
+                    .btn_outline
+                    .text("done")
+                    .hotkey(Key::Escape)
+                    .build_def(ctx),
+            ]),
+        ]))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+        .build(ctx);
+
+        let mut batch = GeomBatch::new();
+        for route in self.routes.iter().flatten() {
+            batch.push(
+                Color::CYAN.alpha(0.8),
+                route.make_polygons(Distance::meters(2.0)),
+            );
+        }
+        for pair in self.points.windows(2) {
+            if let Ok(pl) = PolyLine::new(vec![pair[0], pair[1]]) {
+                batch.extend(
+                    Color::BLACK,
+                    pl.dashed_lines(
+                        Distance::meters(0.5),
+                        Distance::meters(3.0),
+                        Distance::meters(1.5),
+                    ),
+                );
+            }
+        }
+        for pt in &self.points {
+            batch.push(
+                Color::RED,
+                Circle::new(*pt, Distance::meters(2.0)).to_polygon(),
+            );
+        }
+        self.draw = ctx.upload(batch);
+    }
+}
+
+impl<A: AppLike + 'static> State<A> for MeasureDistance {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut A) -> Transition<A> {
+        ctx.canvas_movement();
+        if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+            if ctx.normal_left_click() {
+                self.add_point(app, pt);
+                self.update(ctx, app);
+            }
+        }
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "undo" => {
+                    self.points.pop();
+                    self.routes.pop();
+                    self.update(ctx, app);
+                }
+                "clear" => {
+                    self.points.clear();
+                    self.routes.clear();
+                    self.update(ctx, app);
+                }
+                "done" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &A) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+    }
+}
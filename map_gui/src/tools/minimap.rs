@@ -17,7 +17,10 @@ pub struct Minimap<A: AppLike, T: MinimapControls<A>> {
     time: Time,
     app_type: PhantomData<A>,
 
-    dragging: bool,
+    // While dragging, the offset from the point grabbed on the minimap to the camera's center, in
+    // map-space. This is zero when clicking outside the viewport jumps there.
+    dragging: Option<(f64, f64)>,
+    hovering_viewport: bool,
     panel: Panel,
     // Update panel when other things change
     zoomed: bool,
@@ -79,7 +82,8 @@ impl<A: AppLike + 'static, T: MinimapControls<A>> Minimap<A, T> {
             time: Time::START_OF_DAY,
             app_type: PhantomData,
 
-            dragging: false,
+            dragging: None,
+            hovering_viewport: false,
             panel: Panel::empty(ctx),
             zoomed: ctx.canvas.is_zoomed(),
             layer,
@@ -229,6 +233,14 @@ impl<A: AppLike + 'static, T: MinimapControls<A>> Minimap<A, T> {
         (pct_x, pct_y)
     }
 
+    fn minimap_to_map(&self, pt: ScreenPt) -> Pt2D {
+        let inner_rect = self.panel.rect_of("minimap");
+        Pt2D::new(
+            (self.offset_x + pt.x - inner_rect.x1) / self.zoom,
+            (self.offset_y + pt.y - inner_rect.y1) / self.zoom,
+        )
+    }
+
     pub fn set_zoom(&mut self, ctx: &mut EventCtx, app: &A, zoom_lvl: usize) {
         // Make the frame wind up in the same relative position on the minimap
         let (pct_x, pct_y) = self.map_to_minimap_pct(ctx.canvas.center_to_map_pt());
@@ -283,7 +295,7 @@ impl<A: AppLike + 'static, T: MinimapControls<A>> Minimap<A, T> {
             if just_zoomed_in {
                 self.recenter(ctx, app);
             }
-        } else if self.zoomed && !self.dragging {
+        } else if self.zoomed && self.dragging.is_none() {
             // If either corner of the cursor is out of bounds on the minimap, recenter.
             // TODO This means clicking the pan buttons while along the boundary won't work.
             let mut ok = true;
@@ -376,32 +388,44 @@ impl<A: AppLike + 'static, T: MinimapControls<A>> Minimap<A, T> {
         }
 
         if self.zoomed {
-            let inner_rect = self.panel.rect_of("minimap");
+            let inner_rect = self.panel.rect_of("minimap").clone();
 
             // TODO Not happy about reaching in like this. The minimap logic should be an widgetry
             // Widget eventually, a generalization of Canvas.
             let mut pt = ctx.canvas.get_cursor();
-            if self.dragging {
+            let grab = if let Some(grab) = self.dragging {
                 if ctx.input.left_mouse_button_released() {
-                    self.dragging = false;
+                    self.dragging = None;
                 }
                 // Don't drag out of inner_rect
                 pt.x = pt.x.clamp(inner_rect.x1, inner_rect.x2);
                 pt.y = pt.y.clamp(inner_rect.y1, inner_rect.y2);
+                grab
             } else if inner_rect.contains(pt) && ctx.input.left_mouse_button_pressed() {
-                self.dragging = true;
+                // Grabbing the viewport drags it without jumping. Clicking anywhere else centers
+                // the camera there first.
+                let map_pt = self.minimap_to_map(pt);
+                let grab = if ctx.canvas.get_screen_bounds().contains(map_pt) {
+                    let center = ctx.canvas.center_to_map_pt();
+                    (center.x() - map_pt.x(), center.y() - map_pt.y())
+                } else {
+                    (0.0, 0.0)
+                };
+                self.dragging = Some(grab);
+                grab
             } else {
+                if ctx.redo_mouseover() {
+                    self.hovering_viewport = inner_rect.contains(pt)
+                        && ctx
+                            .canvas
+                            .get_screen_bounds()
+                            .contains(self.minimap_to_map(pt));
+                }
                 return None;
-            }
-
-            let percent_x = (pt.x - inner_rect.x1) / inner_rect.width();
-            let percent_y = (pt.y - inner_rect.y1) / inner_rect.height();
+            };
 
-            let map_pt = Pt2D::new(
-                (self.offset_x + percent_x * inner_rect.width()) / self.zoom,
-                (self.offset_y + percent_y * inner_rect.height()) / self.zoom,
-            );
-            ctx.canvas.center_on_map_pt(map_pt);
+            ctx.canvas
+                .center_on_map_pt(self.minimap_to_map(pt).offset(grab.0, grab.1));
         }
 
         None
@@ -437,9 +461,13 @@ impl<A: AppLike + 'static, T: MinimapControls<A>> Minimap<A, T> {
         g.redraw(&draw_map.boundary_polygon);
         g.redraw(&draw_map.draw_all_areas);
         g.redraw(&draw_map.draw_all_unzoomed_parking_lots);
-        g.redraw(&draw_map.draw_all_unzoomed_roads_and_intersections);
+        draw_map
+            .draw_all_unzoomed_roads_and_intersections
+            .draw_within(g, &map_bounds, self.zoom);
         if app.cs().show_buildings_in_minimap {
-            g.redraw(&draw_map.draw_all_buildings);
+            draw_map
+                .draw_all_buildings
+                .draw_within(g, &map_bounds, self.zoom);
         }
         for draw in extra {
             g.redraw(draw);
@@ -469,10 +497,13 @@ impl<A: AppLike + 'static, T: MinimapControls<A>> Minimap<A, T> {
             if let Some(color) = app.cs().minimap_cursor_bg {
                 g.draw_polygon(color, rect.clone().into_polygon());
             }
-            g.draw_polygon(
-                app.cs().minimap_cursor_border,
-                rect.to_outline(Distance::meters(10.0)),
-            );
+            // Emphasize the viewport when it can be grabbed
+            let thickness = if self.hovering_viewport || self.dragging.is_some() {
+                Distance::meters(20.0)
+            } else {
+                Distance::meters(10.0)
+            };
+            g.draw_polygon(app.cs().minimap_cursor_border, rect.to_outline(thickness));
         }
         g.disable_clipping();
         g.unfork();
@@ -9,20 +9,26 @@ use widgetry::{lctrl, EventCtx, GfxCtx, Key, Line, Text, Widget};
 
 pub use self::camera::{CameraState, DefaultMap};
 pub use self::city_picker::CityPicker;
+pub use self::collab::{
+    intersection_object, road_object, CollabSession, JoinCollabSession, RemoteChanges,
+};
 pub use self::colors::{ColorDiscrete, ColorNetwork};
 pub use self::draw_overlapping_paths::draw_overlapping_paths;
 pub use self::heatmap::{draw_isochrone, make_heatmap, Grid, HeatmapOptions};
 pub use self::icons::{goal_marker, start_marker};
 pub use self::labels::{DrawRoadLabels, DrawSimpleRoadLabels};
+pub use self::measure::MeasureDistance;
 pub use self::minimap::{Minimap, MinimapControls};
 pub use self::navigate::Navigator;
 pub use self::polygon::EditPolygon;
+pub use self::shared_proposals::{browse_shared_proposals, SharedProposal};
 pub use self::title_screen::{Executable, TitleScreen};
 pub use self::trip_files::{TripManagement, TripManagementState};
 pub use self::ui::{
     checkbox_per_mode, cmp_count, cmp_dist, cmp_duration, color_for_mode, percentage_bar,
     FilePicker, FileSaver, FileSaverContents,
 };
+pub use self::video::{CameraKeyframe, RecordVideo, VideoOptions};
 pub use self::waypoints::{InputWaypoints, WaypointID};
 use crate::AppLike;
 
@@ -33,6 +39,7 @@ pub use self::updater::prompt_to_download_missing_data;
 
 mod camera;
 mod city_picker;
+mod collab;
 mod colors;
 #[cfg(not(target_arch = "wasm32"))]
 mod command;
@@ -43,14 +50,17 @@ mod icons;
 #[cfg(not(target_arch = "wasm32"))]
 mod importer;
 mod labels;
+mod measure;
 mod minimap;
 mod navigate;
 mod polygon;
+mod shared_proposals;
 mod title_screen;
 mod trip_files;
 mod ui;
 #[cfg(not(target_arch = "wasm32"))]
 mod updater;
+mod video;
 mod waypoints;
 
 // Update this ___before___ pushing the commit with "[rebuild] [release]".
@@ -9,7 +9,8 @@ use widgetry::{
 use crate::tools::grey_out_map;
 use crate::{AppLike, ID};
 
-// TODO Canonicalize names, handling abbreviations like east/e and street/st
+/// Search for a street, then optionally a cross street. The autocomplete matches fuzzily, so
+/// abbreviations like "e pine st" and small typos work.
 pub struct Navigator {
     panel: Panel,
     target_zoom: f64,
@@ -255,11 +256,14 @@ impl SearchBuildings {
                                     b.id,
                                 ));
                             }
+                            // Include the type, so searching for "cafe" or "pharmacy" finds
+                            // places without those words in their name
                             for a in &b.amenities {
                                 results.push((
                                     format!(
-                                        "{} (at {})",
+                                        "{} ({} at {})",
                                         a.names.get(app.opts().language.as_ref()),
+                                        a.amenity_type.replace('_', " "),
                                         b.address
                                     ),
                                     b.id,
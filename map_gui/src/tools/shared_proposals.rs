@@ -0,0 +1,133 @@
+//! Proposals uploaded through the "share" buttons are kept by a simple HTTP store. This lists the
+//! ones made for the current map and downloads one, so people can look at each other's ideas
+//! without passing around files.
+
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use abstio::MapName;
+use widgetry::tools::{ChooseSomething, FutureLoader, PopupMsg};
+use widgetry::{Choice, EventCtx, State, Transition};
+
+/// One entry returned by the store's listing endpoint. The store fills these in from the uploaded
+/// file.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SharedProposal {
+    /// The md5sum of the uploaded file, used to download it
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    /// Formatted as YYYY-MM-DD
+    #[serde(default)]
+    pub created: Option<String>,
+}
+
+impl SharedProposal {
+    fn label(&self) -> String {
+        match (&self.author, &self.created) {
+            (Some(author), Some(created)) => format!("{} (by {}, {})", self.title, author, created),
+            (Some(author), None) => format!("{} (by {})", self.title, author),
+            (None, Some(created)) => format!("{} ({})", self.title, created),
+            (None, None) => self.title.clone(),
+        }
+    }
+}
+
+/// Lists the proposals shared for a map, lets the player pick one, and downloads it.
+/// `{list_url}?map=...` must return a JSON list of `SharedProposal`, and `{get_url}?id=...` the
+/// proposal itself. `on_download` receives the raw bytes and the title of the chosen proposal.
+pub fn browse_shared_proposals<A: 'static>(
+    ctx: &mut EventCtx,
+    map_name: &MapName,
+    list_url: String,
+    get_url: String,
+    on_download: Box<dyn FnOnce(&mut EventCtx, &mut A, Result<Vec<u8>>, String) -> Transition<A>>,
+) -> Box<dyn State<A>> {
+    let map_path = map_name
+        .path()
+        .strip_prefix(&abstio::path(""))
+        .unwrap()
+        .to_string();
+    let url = format!("{}?map={}", list_url, map_path);
+    let (_, outer_progress_rx) = futures_channel::mpsc::channel(1);
+    let (_, inner_progress_rx) = futures_channel::mpsc::channel(1);
+    FutureLoader::<A, Vec<u8>>::new_state(
+        ctx,
+        Box::pin(async move {
+            let bytes = abstio::http_get(url).await?;
+            let wrapper: Box<dyn Send + FnOnce(&A) -> Vec<u8>> = Box::new(move |_| bytes);
+            Ok(wrapper)
+        }),
+        outer_progress_rx,
+        inner_progress_rx,
+        "Listing shared proposals",
+        Box::new(move |ctx, _, result| {
+            let list =
+                match result.and_then(|bytes| abstutil::from_json::<Vec<SharedProposal>>(&bytes)) {
+                    Ok(list) => list,
+                    Err(err) => {
+                        return Transition::Replace(PopupMsg::new_state(
+                            ctx,
+                            "Error",
+                            vec![format!("Couldn't list shared proposals: {}", err)],
+                        ));
+                    }
+                };
+            if list.is_empty() {
+                return Transition::Replace(PopupMsg::new_state(
+                    ctx,
+                    "No shared proposals",
+                    vec!["Nobody has shared a proposal for this map yet"],
+                ));
+            }
+
+            // Each choice needs a unique label
+            let mut labels = BTreeSet::new();
+            let choices = list
+                .into_iter()
+                .map(|proposal| {
+                    let mut label = proposal.label();
+                    if labels.contains(&label) {
+                        label = format!("{} [{}]", label, proposal.id);
+                    }
+                    labels.insert(label.clone());
+                    Choice::new(label, proposal)
+                })
+                .collect();
+            Transition::Replace(ChooseSomething::new_state(
+                ctx,
+                "Open which shared proposal?",
+                choices,
+                Box::new(move |proposal, ctx, _| {
+                    Transition::Replace(download(ctx, get_url, proposal, on_download))
+                }),
+            ))
+        }),
+    )
+}
+
+fn download<A: 'static>(
+    ctx: &mut EventCtx,
+    get_url: String,
+    proposal: SharedProposal,
+    on_download: Box<dyn FnOnce(&mut EventCtx, &mut A, Result<Vec<u8>>, String) -> Transition<A>>,
+) -> Box<dyn State<A>> {
+    let url = format!("{}?id={}", get_url, proposal.id);
+    let (_, outer_progress_rx) = futures_channel::mpsc::channel(1);
+    let (_, inner_progress_rx) = futures_channel::mpsc::channel(1);
+    FutureLoader::<A, Vec<u8>>::new_state(
+        ctx,
+        Box::pin(async move {
+            let bytes = abstio::http_get(url).await?;
+            let wrapper: Box<dyn Send + FnOnce(&A) -> Vec<u8>> = Box::new(move |_| bytes);
+            Ok(wrapper)
+        }),
+        outer_progress_rx,
+        inner_progress_rx,
+        "Downloading proposal",
+        Box::new(move |ctx, app, result| on_download(ctx, app, result, proposal.title)),
+    )
+}
@@ -0,0 +1,180 @@
+use geom::{Duration, Pt2D};
+use widgetry::tools::{frames_to_gif, PopupMsg};
+use widgetry::{
+    DrawBaselayer, EventCtx, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, State, Text,
+    Transition, UpdateType, VerticalAlignment, Widget,
+};
+
+use crate::AppLike;
+
+/// A position of the camera.
+#[derive(Clone, Copy)]
+pub struct CameraKeyframe {
+    pub center: Pt2D,
+    pub zoom: f64,
+}
+
+impl CameraKeyframe {
+    pub fn current(ctx: &EventCtx) -> CameraKeyframe {
+        CameraKeyframe {
+            center: ctx.canvas.center_to_map_pt(),
+            zoom: ctx.canvas.cam_zoom,
+        }
+    }
+
+    fn apply(self, ctx: &mut EventCtx) {
+        ctx.canvas.cam_zoom = self.zoom;
+        ctx.canvas.center_on_map_pt(self.center);
+    }
+
+    fn lerp(self, other: CameraKeyframe, pct: f64) -> CameraKeyframe {
+        CameraKeyframe {
+            center: Pt2D::new(
+                self.center.x() + (other.center.x() - self.center.x()) * pct,
+                self.center.y() + (other.center.y() - self.center.y()) * pct,
+            ),
+            zoom: self.zoom + (other.zoom - self.zoom) * pct,
+        }
+    }
+}
+
+pub struct VideoOptions {
+    /// Each frame is saved as a PNG file in this directory
+    pub dir: String,
+    pub num_frames: usize,
+    /// How much the app should advance (usually in simulation time) between frames
+    pub time_per_frame: Duration,
+    /// The camera moves through these, spending equal time between each. With fewer than two, the
+    /// camera stays wherever it is.
+    pub camera_path: Vec<CameraKeyframe>,
+    /// If set, also combine the frames into an animated GIF with this filename
+    pub gif: Option<String>,
+    /// How long to show each frame of the GIF
+    pub gif_frame_delay_ms: u32,
+}
+
+/// Renders a sequence of frames, advancing the app and moving the camera in between. The app's
+/// panels aren't drawn, just the map.
+pub struct RecordVideo<A: AppLike> {
+    opts: VideoOptions,
+    advance: Box<dyn Fn(&mut EventCtx, &mut A, Duration)>,
+    frames: Vec<String>,
+    panel: Panel,
+}
+
+impl<A: AppLike + 'static> RecordVideo<A> {
+    /// `advance` is called between frames, and should run the app forward by the given time.
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        opts: VideoOptions,
+        advance: Box<dyn Fn(&mut EventCtx, &mut A, Duration)>,
+    ) -> Box<dyn State<A>> {
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::placeholder(ctx, "progress"),
+            ctx.style()
+                .btn_outline
+                .text("cancel")
+                .build_def(ctx)
+                .centered_horiz(),
+        ]))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+        .build(ctx);
+        Box::new(RecordVideo {
+            opts,
+            advance,
+            frames: Vec::new(),
+            panel,
+        })
+    }
+
+    fn camera_at(&self, frame: usize) -> Option<CameraKeyframe> {
+        let path = &self.opts.camera_path;
+        if path.len() < 2 {
+            return None;
+        }
+        let pct = if self.opts.num_frames <= 1 {
+            0.0
+        } else {
+            (frame as f64) / ((self.opts.num_frames - 1) as f64)
+        };
+        // Which pair of keyframes are we between?
+        let segment = pct * ((path.len() - 1) as f64);
+        let idx = (segment.floor() as usize).min(path.len() - 2);
+        Some(path[idx].lerp(path[idx + 1], segment - (idx as f64)))
+    }
+
+    fn finish(&self, ctx: &mut EventCtx) -> Transition<A> {
+        let mut lines = vec![format!(
+            "Saved {} frames to {}",
+            self.frames.len(),
+            self.opts.dir
+        )];
+        if let Some(ref gif) = self.opts.gif {
+            let result = ctx.loading_screen("encode GIF", |_, _| {
+                frames_to_gif(&self.frames, gif, self.opts.gif_frame_delay_ms)
+            });
+            match result {
+                Ok(()) => lines.push(format!("Saved an animated GIF to {}", gif)),
+                Err(err) => lines.push(format!("Couldn't make a GIF: {}", err)),
+            }
+        }
+        lines.push(format!(
+            "To make a video, try: ffmpeg -framerate 10 -i {}/frame_%05d.png video.mp4",
+            self.opts.dir
+        ));
+        Transition::Replace(PopupMsg::new_state(ctx, "Recording finished", lines))
+    }
+}
+
+impl<A: AppLike + 'static> State<A> for RecordVideo<A> {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut A) -> Transition<A> {
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "cancel" => {
+                    return self.finish(ctx);
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        // The previous frame has been captured by now
+        if self.frames.len() == self.opts.num_frames {
+            return self.finish(ctx);
+        }
+
+        let frame = self.frames.len();
+        if frame > 0 {
+            (self.advance)(ctx, app, self.opts.time_per_frame);
+        }
+        if let Some(camera) = self.camera_at(frame) {
+            camera.apply(ctx);
+        }
+
+        let filename = format!("{}/frame_{:05}.png", self.opts.dir, frame);
+        ctx.request_update(UpdateType::ScreenCaptureViewport {
+            filename: filename.clone(),
+        });
+        self.frames.push(filename);
+        // Keep going, even without any input
+        ctx.request_update(UpdateType::Game);
+
+        let txt = Text::from(Line(format!(
+            "Recording frame {} / {}",
+            self.frames.len(),
+            self.opts.num_frames
+        )));
+        self.panel.replace(ctx, "progress", txt.into_widget(ctx));
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::DefaultDraw
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &A) {
+        if !g.is_screencap() {
+            self.panel.draw(g);
+        }
+    }
+}
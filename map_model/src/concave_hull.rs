@@ -0,0 +1,359 @@
+//! Outlines hugging a set of points much more tightly than a convex hull. A point set's alpha
+//! shape is built from the Delaunay triangulation by throwing away triangles too big to fit inside
+//! a circle of some radius. Unlike a convex hull, the result can have several disconnected pieces
+//! and holes, so clusters of buildings separated by a park or a river get separate outlines.
+
+use std::collections::{HashMap, HashSet};
+
+use geom::{Distance, Polygon, Pt2D, Ring};
+
+/// Returns polygons covering all the points, excluding any triangle between them that doesn't
+/// fit inside a circle of radius `alpha`. Smaller values hug the points more tightly, but split
+/// sparser areas into more pieces. Points that don't belong to any small enough triangle aren't
+/// covered at all.
+pub fn alpha_shape(points: Vec<Pt2D>, alpha: Distance) -> Vec<Polygon> {
+    if points.len() < 3 {
+        return Vec::new();
+    }
+    let mut tris = Triangulation::new(points);
+    tris.triangulate();
+
+    let alpha = alpha.inner_meters();
+    let keep: HashSet<usize> = tris
+        .triangles
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, tri)| {
+            let tri = tri.as_ref()?;
+            if tri.iter().any(|v| *v >= tris.num_real_pts) || tris.circumradius(tri) > alpha {
+                return None;
+            }
+            Some(idx)
+        })
+        .collect();
+
+    // Boundary edges of the kept triangles, pointing around them counter-clockwise
+    let mut next_pts: HashMap<usize, Vec<usize>> = HashMap::new();
+    for idx in &keep {
+        let tri = tris.triangles[*idx].unwrap();
+        for i in 0..3 {
+            let (a, b) = (tri[i], tri[(i + 1) % 3]);
+            if !tris
+                .edges
+                .get(&(b, a))
+                .map(|t| keep.contains(t))
+                .unwrap_or(false)
+            {
+                next_pts.entry(a).or_insert_with(Vec::new).push(b);
+            }
+        }
+    }
+
+    let mut outers = Vec::new();
+    let mut holes = Vec::new();
+    for ring in chain_rings(next_pts) {
+        let pts: Vec<Pt2D> = ring.iter().map(|v| tris.pts[*v]).collect();
+        let is_outer = signed_area(&pts) > 0.0;
+        let mut closed = pts;
+        closed.push(closed[0]);
+        if let Ok(ring) = Ring::deduping_new(closed) {
+            if is_outer {
+                outers.push((ring, Vec::new()));
+            } else {
+                holes.push(ring);
+            }
+        }
+    }
+
+    for hole in holes {
+        let pt = hole.points()[0];
+        // Pick the smallest outer ring containing the hole, in case pieces are nested
+        if let Some((_, list)) = outers
+            .iter_mut()
+            .filter(|(outer, _)| ring_contains_pt(outer.points(), pt))
+            .min_by(|(a, _), (b, _)| {
+                signed_area(a.points())
+                    .partial_cmp(&signed_area(b.points()))
+                    .unwrap()
+            })
+        {
+            list.push(hole);
+        }
+    }
+    outers
+        .into_iter()
+        .map(|(outer, holes)| Polygon::with_holes(outer, holes))
+        .collect()
+}
+
+// Vertices of each triangle, counter-clockwise
+type Triangle = [usize; 3];
+
+/// An incremental Delaunay triangulation
+struct Triangulation {
+    // The real points, followed by the 3 corners of a huge triangle surrounding everything
+    pts: Vec<Pt2D>,
+    num_real_pts: usize,
+    // Deleted triangles leave a hole
+    triangles: Vec<Option<Triangle>>,
+    // Every directed edge, counter-clockwise around its triangle, to that triangle. The triangle
+    // on the other side of (a, b) is the one owning (b, a).
+    edges: HashMap<(usize, usize), usize>,
+}
+
+impl Triangulation {
+    fn new(points: Vec<Pt2D>) -> Triangulation {
+        let mut seen = HashSet::new();
+        let mut pts: Vec<Pt2D> = points
+            .into_iter()
+            .filter(|pt| seen.insert(pt.to_hashable()))
+            .collect();
+        // Inserting points in order means the search for the triangle containing the next point
+        // starts nearby
+        pts.sort_by(|a, b| a.x().partial_cmp(&b.x()).unwrap());
+        let num_real_pts = pts.len();
+
+        let (mut min_x, mut min_y) = (f64::MAX, f64::MAX);
+        let (mut max_x, mut max_y) = (f64::MIN, f64::MIN);
+        for pt in &pts {
+            min_x = min_x.min(pt.x());
+            min_y = min_y.min(pt.y());
+            max_x = max_x.max(pt.x());
+            max_y = max_y.max(pt.y());
+        }
+        let size = (max_x - min_x).max(max_y - min_y).max(1.0) * 20.0;
+        let (cx, cy) = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+        pts.push(Pt2D::new(cx - size, cy - size));
+        pts.push(Pt2D::new(cx + size, cy - size));
+        pts.push(Pt2D::new(cx, cy + size));
+
+        let mut tris = Triangulation {
+            pts,
+            num_real_pts,
+            triangles: Vec::new(),
+            edges: HashMap::new(),
+        };
+        let (a, b, c) = (num_real_pts, num_real_pts + 1, num_real_pts + 2);
+        if orient(tris.pts[a], tris.pts[b], tris.pts[c]) > 0.0 {
+            tris.add([a, b, c]);
+        } else {
+            tris.add([a, c, b]);
+        }
+        tris
+    }
+
+    fn triangulate(&mut self) {
+        let mut last = 0;
+        for pt in 0..self.num_real_pts {
+            let containing = self.locate(last, self.pts[pt]);
+
+            // Find every triangle whose circumcircle contains the new point. They're all
+            // connected, so flood outwards from the one containing the point.
+            let mut bad = HashSet::new();
+            bad.insert(containing);
+            let mut queue = vec![containing];
+            while let Some(idx) = queue.pop() {
+                let tri = self.triangles[idx].unwrap();
+                for i in 0..3 {
+                    let (a, b) = (tri[i], tri[(i + 1) % 3]);
+                    if let Some(other) = self.edges.get(&(b, a)).cloned() {
+                        if !bad.contains(&other) && self.in_circumcircle(other, self.pts[pt]) {
+                            bad.insert(other);
+                            queue.push(other);
+                        }
+                    }
+                }
+            }
+
+            // Replace the bad triangles with a fan from the new point to the edges of the hole
+            let mut boundary = Vec::new();
+            for idx in &bad {
+                let tri = self.triangles[*idx].unwrap();
+                for i in 0..3 {
+                    let (a, b) = (tri[i], tri[(i + 1) % 3]);
+                    if !self
+                        .edges
+                        .get(&(b, a))
+                        .map(|t| bad.contains(t))
+                        .unwrap_or(false)
+                    {
+                        boundary.push((a, b));
+                    }
+                }
+            }
+            for idx in bad {
+                self.remove(idx);
+            }
+            for (a, b) in boundary {
+                last = self.add([a, b, pt]);
+            }
+        }
+    }
+
+    // Walk towards the point from some starting triangle
+    fn locate(&self, start: usize, pt: Pt2D) -> usize {
+        let mut current = if self.triangles[start].is_some() {
+            start
+        } else {
+            self.any_triangle()
+        };
+        // Walking can cycle in degenerate cases, so give up eventually and check everything
+        for _ in 0..self.triangles.len() {
+            let tri = self.triangles[current].unwrap();
+            let crossing = (0..3).find_map(|i| {
+                let (a, b) = (tri[i], tri[(i + 1) % 3]);
+                if orient(self.pts[a], self.pts[b], pt) < 0.0 {
+                    self.edges.get(&(b, a)).cloned()
+                } else {
+                    None
+                }
+            });
+            match crossing {
+                Some(next) => {
+                    current = next;
+                }
+                None => {
+                    return current;
+                }
+            }
+        }
+        self.triangles
+            .iter()
+            .position(|tri| {
+                tri.map(|tri| {
+                    (0..3).all(|i| orient(self.pts[tri[i]], self.pts[tri[(i + 1) % 3]], pt) >= 0.0)
+                })
+                .unwrap_or(false)
+            })
+            .unwrap_or(current)
+    }
+
+    fn any_triangle(&self) -> usize {
+        self.triangles
+            .iter()
+            .rposition(|tri| tri.is_some())
+            .unwrap()
+    }
+
+    fn add(&mut self, tri: Triangle) -> usize {
+        let idx = self.triangles.len();
+        for i in 0..3 {
+            self.edges.insert((tri[i], tri[(i + 1) % 3]), idx);
+        }
+        self.triangles.push(Some(tri));
+        idx
+    }
+
+    fn remove(&mut self, idx: usize) {
+        let tri = self.triangles[idx].take().unwrap();
+        for i in 0..3 {
+            self.edges.remove(&(tri[i], tri[(i + 1) % 3]));
+        }
+    }
+
+    fn in_circumcircle(&self, idx: usize, pt: Pt2D) -> bool {
+        let tri = self.triangles[idx].unwrap();
+        let [a, b, c] = tri.map(|v| (self.pts[v].x() - pt.x(), self.pts[v].y() - pt.y()));
+        let det = (a.0 * a.0 + a.1 * a.1) * (b.0 * c.1 - c.0 * b.1)
+            - (b.0 * b.0 + b.1 * b.1) * (a.0 * c.1 - c.0 * a.1)
+            + (c.0 * c.0 + c.1 * c.1) * (a.0 * b.1 - b.0 * a.1);
+        det > 0.0
+    }
+
+    fn circumradius(&self, tri: &Triangle) -> f64 {
+        let [a, b, c] = tri.map(|v| self.pts[v]);
+        let area = orient(a, b, c).abs() / 2.0;
+        if area < f64::EPSILON {
+            return f64::MAX;
+        }
+        let lengths =
+            a.dist_to(b).inner_meters() * b.dist_to(c).inner_meters() * c.dist_to(a).inner_meters();
+        lengths / (4.0 * area)
+    }
+}
+
+// Positive if c is to the left of the line from a to b
+fn orient(a: Pt2D, b: Pt2D, c: Pt2D) -> f64 {
+    (b.x() - a.x()) * (c.y() - a.y()) - (b.y() - a.y()) * (c.x() - a.x())
+}
+
+// Positive for counter-clockwise rings. The first point isn't repeated at the end.
+fn signed_area(pts: &[Pt2D]) -> f64 {
+    let mut sum = 0.0;
+    for (i, pt1) in pts.iter().enumerate() {
+        let pt2 = pts[(i + 1) % pts.len()];
+        sum += pt1.x() * pt2.y() - pt2.x() * pt1.y();
+    }
+    sum / 2.0
+}
+
+fn ring_contains_pt(pts: &[Pt2D], pt: Pt2D) -> bool {
+    let mut inside = false;
+    for pair in pts.windows(2) {
+        let (pt1, pt2) = (pair[0], pair[1]);
+        if (pt1.y() > pt.y()) != (pt2.y() > pt.y())
+            && pt.x() < pt1.x() + (pt.y() - pt1.y()) / (pt2.y() - pt1.y()) * (pt2.x() - pt1.x())
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+// Join directed edges into loops. Where an outline touches itself at one point, that point has
+// several outgoing edges; split those into separate loops, so no loop visits a point twice.
+fn chain_rings(mut next_pts: HashMap<usize, Vec<usize>>) -> Vec<Vec<usize>> {
+    let mut rings = Vec::new();
+    while let Some(start) = next_pts.keys().next().cloned() {
+        let mut path = vec![start];
+        loop {
+            let current = *path.last().unwrap();
+            let next = match next_pts.get_mut(&current).and_then(|list| list.pop()) {
+                Some(next) => next,
+                // A dangling edge; this shouldn't happen with a proper triangulation
+                None => break,
+            };
+            if next_pts.get(&current).map(|list| list.is_empty()) == Some(true) {
+                next_pts.remove(&current);
+            }
+            if let Some(pos) = path.iter().position(|v| *v == next) {
+                rings.push(path.split_off(pos));
+                if path.is_empty() {
+                    break;
+                }
+            }
+            path.push(next);
+        }
+    }
+    rings.retain(|ring| ring.len() >= 3);
+    rings
+}
+
+#[cfg(test)]
+mod tests {
+    use geom::{Distance, Pt2D};
+
+    use super::alpha_shape;
+
+    #[test]
+    fn test_alpha_shape() {
+        // Two dense clusters of points, far apart
+        let mut pts = Vec::new();
+        for x in 0..10 {
+            for y in 0..10 {
+                pts.push(Pt2D::new(x as f64 * 10.0, y as f64 * 10.0));
+                pts.push(Pt2D::new(1000.0 + x as f64 * 10.0, y as f64 * 10.0));
+            }
+        }
+        let polygons = alpha_shape(pts.clone(), Distance::meters(20.0));
+        assert_eq!(polygons.len(), 2);
+        for p in &polygons {
+            assert!((p.area() - 8100.0).abs() < 1.0);
+        }
+
+        // A huge alpha is just the convex hull
+        let polygons = alpha_shape(pts, Distance::meters(100_000.0));
+        assert_eq!(polygons.len(), 1);
+        assert!((polygons[0].area() - 1090.0 * 90.0).abs() < 1.0);
+    }
+}
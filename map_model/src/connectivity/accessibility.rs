@@ -0,0 +1,139 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use petgraph::graphmap::DiGraphMap;
+
+use crate::pathfind::WalkingNode;
+use crate::{AccessibilityBarrier, BuildingID, IntersectionID, LaneType, Map, RoadID};
+
+/// Compares where people walking and wheelchair users can get to. Unlike a route from one place,
+/// this looks at the whole pedestrian network: the largest connected part of it is the "main"
+/// network, and any building cut off from that is a gap.
+pub struct WheelchairAccess {
+    /// Roads whose sidewalks or paths wheelchair users can't use, and why
+    pub barriers: BTreeMap<RoadID, AccessibilityBarrier>,
+    /// Crosswalks at these ends of roads have a raised kerb
+    pub blocked_crosswalks: BTreeSet<(RoadID, IntersectionID)>,
+    /// Buildings that people walking can reach from the main network, but wheelchair users can't
+    pub unreachable: BTreeSet<BuildingID>,
+    /// How many buildings wheelchair users can reach from the main network
+    pub num_reachable: usize,
+}
+
+impl WheelchairAccess {
+    pub fn new(map: &Map) -> WheelchairAccess {
+        let mut barriers = BTreeMap::new();
+        let mut blocked_crosswalks = BTreeSet::new();
+        for r in map.all_roads() {
+            if !r.lanes.iter().any(|l| l.is_walkable()) {
+                continue;
+            }
+            if let Some(barrier) = r.wheelchair_barrier() {
+                barriers.insert(r.id, barrier);
+            }
+            for i in r.endpoints() {
+                if r.raised_kerb_near(i) {
+                    blocked_crosswalks.insert((r.id, i));
+                }
+            }
+        }
+
+        let walking = main_network(map, false);
+        let wheelchair = main_network(map, true);
+        WheelchairAccess {
+            barriers,
+            blocked_crosswalks,
+            num_reachable: wheelchair.len(),
+            unreachable: walking.difference(&wheelchair).cloned().collect(),
+        }
+    }
+}
+
+/// The buildings in the largest connected part of the pedestrian network. Shoulders count as
+/// walkable, like the default `WalkingOptions`.
+fn main_network(map: &Map, wheelchair: bool) -> BTreeSet<BuildingID> {
+    let mut graph: DiGraphMap<WalkingNode, ()> = DiGraphMap::new();
+    let mut connect = |n1: WalkingNode, n2: WalkingNode| {
+        graph.add_edge(n1, n2, ());
+        graph.add_edge(n2, n1, ());
+    };
+    let passable = |r: RoadID| !wheelchair || map.get_r(r).wheelchair_barrier().is_none();
+
+    // Where each building joins the network. Buildings on an impassable sidewalk are left out.
+    let mut bldg_nodes: HashMap<BuildingID, WalkingNode> = HashMap::new();
+    for b in map.all_buildings() {
+        let lane = map.get_l(b.sidewalk());
+        if passable(lane.id.road) {
+            bldg_nodes.insert(
+                b.id,
+                WalkingNode::SidewalkEndpoint(lane.get_directed_parent(), false),
+            );
+        }
+    }
+
+    for road in map.all_roads() {
+        if !passable(road.id) {
+            continue;
+        }
+        let mut sides = Vec::new();
+        for l in &road.lanes {
+            if l.is_walkable() || l.lane_type == LaneType::Shoulder {
+                let dr = l.get_directed_parent();
+                connect(
+                    WalkingNode::SidewalkEndpoint(dr, false),
+                    WalkingNode::SidewalkEndpoint(dr, true),
+                );
+                sides.push(dr);
+            }
+        }
+
+        // Mid-block crossings, or anywhere in a shared space
+        let can_cross = road.shared_space
+            || road
+                .mid_block_crossings()
+                .any(|c| !wheelchair || !road.raised_kerb_at(c.dist));
+        if sides.len() == 2 && can_cross {
+            connect(
+                WalkingNode::SidewalkEndpoint(sides[0], false),
+                WalkingNode::SidewalkEndpoint(sides[1], false),
+            );
+        }
+    }
+
+    for turn in map.all_turns() {
+        if !turn.between_sidewalks() {
+            continue;
+        }
+        let src = map.get_l(turn.id.src);
+        let dst = map.get_l(turn.id.dst);
+        if wheelchair
+            && turn.turn_type.pedestrian_crossing()
+            && map.get_r(src.id.road).raised_kerb_near(turn.id.parent)
+        {
+            continue;
+        }
+        connect(
+            WalkingNode::SidewalkEndpoint(src.get_directed_parent(), src.dst_i == turn.id.parent),
+            WalkingNode::SidewalkEndpoint(dst.get_directed_parent(), dst.dst_i == turn.id.parent),
+        );
+    }
+
+    // Every building's node must be in the graph, even if nothing else connects to it
+    for node in bldg_nodes.values() {
+        graph.add_node(*node);
+    }
+
+    let mut node_to_bldgs: HashMap<WalkingNode, Vec<BuildingID>> = HashMap::new();
+    for (b, node) in bldg_nodes {
+        node_to_bldgs.entry(node).or_insert_with(Vec::new).push(b);
+    }
+    petgraph::algo::kosaraju_scc(&graph)
+        .into_iter()
+        .map(|component| {
+            component
+                .into_iter()
+                .flat_map(|node| node_to_bldgs.remove(&node).unwrap_or_default())
+                .collect::<BTreeSet<_>>()
+        })
+        .max_by_key(|bldgs| bldgs.len())
+        .unwrap_or_default()
+}
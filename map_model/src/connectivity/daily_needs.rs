@@ -0,0 +1,167 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+
+use geom::Duration;
+
+use crate::connectivity::{all_vehicle_costs_from, all_walking_costs_from, Spot, WalkingOptions};
+use crate::{AmenityType, AreaType, BuildingID, BuildingType, Map, PathConstraints};
+
+/// The everyday places a "15-minute city" should put within reach of every home.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DailyNeed {
+    Groceries,
+    School,
+    Healthcare,
+    Park,
+}
+
+impl DailyNeed {
+    pub fn all() -> Vec<DailyNeed> {
+        vec![
+            DailyNeed::Groceries,
+            DailyNeed::School,
+            DailyNeed::Healthcare,
+            DailyNeed::Park,
+        ]
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            DailyNeed::Groceries => "groceries",
+            DailyNeed::School => "school",
+            DailyNeed::Healthcare => "healthcare",
+            DailyNeed::Park => "park",
+        }
+    }
+
+    /// The categories of businesses that satisfy this need
+    pub fn amenity_types(self) -> Vec<AmenityType> {
+        match self {
+            DailyNeed::Groceries => vec![AmenityType::Supermarket, AmenityType::ConvenienceStore],
+            DailyNeed::School => vec![AmenityType::School],
+            DailyNeed::Healthcare => vec![AmenityType::Medical],
+            DailyNeed::Park => vec![AmenityType::GreenSpace, AmenityType::Playground],
+        }
+    }
+
+    /// Everywhere this need is satisfied. Parks are usually areas, not buildings, so they also
+    /// start from the roads meeting at any intersection inside a park.
+    fn spots(self, map: &Map, constraints: PathConstraints) -> Vec<Spot> {
+        let amenity_types = self.amenity_types();
+        let mut spots: Vec<Spot> = map
+            .all_buildings()
+            .iter()
+            .filter(|b| amenity_types.iter().any(|at| b.has_amenity(*at)))
+            .map(|b| Spot::Building(b.id))
+            .collect();
+
+        if self == DailyNeed::Park {
+            for area in map.all_areas() {
+                if area.area_type != AreaType::Park {
+                    continue;
+                }
+                let bounds = area.polygon.get_bounds();
+                for i in map.all_intersections() {
+                    let pt = i.polygon.center();
+                    if !bounds.contains(pt) || !area.polygon.contains_pt(pt) {
+                        continue;
+                    }
+                    for r in &i.roads {
+                        for lane in &map.get_r(*r).lanes {
+                            let usable = if constraints == PathConstraints::Pedestrian {
+                                lane.is_walkable()
+                            } else {
+                                constraints.can_use(lane, map)
+                            };
+                            if usable {
+                                spots.push(Spot::DirectedRoad(lane.get_directed_parent()));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        spots
+    }
+}
+
+/// For every building, how long it takes to reach the nearest place satisfying each daily need.
+pub struct DailyNeedsAccess {
+    pub constraints: PathConstraints,
+    pub time_limit: Duration,
+    /// Needs that can't be reached within the time limit are omitted.
+    pub time_per_building: HashMap<BuildingID, BTreeMap<DailyNeed, Duration>>,
+}
+
+impl DailyNeedsAccess {
+    /// Walking (with the default `WalkingOptions`), biking, and driving are supported; transit
+    /// isn't. The map's current edits are used, so new crossings or roads change the results.
+    pub fn new(
+        map: &Map,
+        constraints: PathConstraints,
+        time_limit: Duration,
+    ) -> Result<DailyNeedsAccess> {
+        if matches!(constraints, PathConstraints::Bus | PathConstraints::Train) {
+            bail!("Access to daily needs by {:?} isn't supported", constraints);
+        }
+
+        let mut time_per_building: HashMap<BuildingID, BTreeMap<DailyNeed, Duration>> =
+            HashMap::new();
+        for need in DailyNeed::all() {
+            let spots = need.spots(map, constraints);
+            if spots.is_empty() {
+                continue;
+            }
+            let costs = match constraints {
+                PathConstraints::Pedestrian => {
+                    all_walking_costs_from(map, spots, time_limit, WalkingOptions::default())
+                }
+                _ => all_vehicle_costs_from(map, spots, time_limit, constraints),
+            };
+            for (b, cost) in costs {
+                time_per_building.entry(b).or_default().insert(need, cost);
+            }
+        }
+        Ok(DailyNeedsAccess {
+            constraints,
+            time_limit,
+            time_per_building,
+        })
+    }
+
+    /// The fraction of daily needs reachable from a building, from 0 to 1.
+    pub fn score(&self, b: BuildingID) -> f64 {
+        let reachable = self
+            .time_per_building
+            .get(&b)
+            .map(|needs| needs.len())
+            .unwrap_or(0);
+        (reachable as f64) / (DailyNeed::all().len() as f64)
+    }
+
+    /// Averages the score of the buildings given, weighted by how many people live in each.
+    /// Returns `None` if nobody lives there.
+    pub fn average_score<I: IntoIterator<Item = BuildingID>>(
+        &self,
+        map: &Map,
+        buildings: I,
+    ) -> Option<f64> {
+        let mut total = 0.0;
+        let mut people = 0;
+        for b in buildings {
+            let n = match map.get_b(b).bldg_type {
+                BuildingType::Residential { num_residents, .. } => num_residents,
+                BuildingType::ResidentialCommercial(num_residents, _) => num_residents,
+                BuildingType::Commercial(_) | BuildingType::Empty => 0,
+            };
+            total += (n as f64) * self.score(b);
+            people += n;
+        }
+        if people == 0 {
+            None
+        } else {
+            Some(total / (people as f64))
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::Result;
+
+use geom::{Distance, Duration, Polygon};
+
+use crate::connectivity::{all_vehicle_costs_from, all_walking_costs_from, Spot, WalkingOptions};
+use crate::{concave_hull, BuildingID, Map, PathConstraints, RoadID};
+
+/// Everywhere reachable from some starting points by one mode, within a time budget.
+pub struct Isochrone {
+    pub constraints: PathConstraints,
+    pub time_limit: Duration,
+    /// How long it takes to reach each building. Buildings farther than the time limit are
+    /// omitted.
+    pub time_to_reach_building: HashMap<BuildingID, Duration>,
+    /// The roads that any reachable building is attached to
+    pub roads: BTreeSet<RoadID>,
+}
+
+impl Isochrone {
+    /// Walking uses the default `WalkingOptions`. Buses and trains aren't supported, and return an
+    /// error.
+    pub fn new(
+        map: &Map,
+        starts: Vec<Spot>,
+        constraints: PathConstraints,
+        time_limit: Duration,
+    ) -> Result<Isochrone> {
+        let time_to_reach_building = match constraints {
+            PathConstraints::Pedestrian => {
+                all_walking_costs_from(map, starts, time_limit, WalkingOptions::default())
+            }
+            PathConstraints::Car | PathConstraints::Bike => {
+                all_vehicle_costs_from(map, starts, time_limit, constraints)
+            }
+            PathConstraints::Bus | PathConstraints::Train => {
+                bail!("Isochrones for {:?} aren't supported", constraints)
+            }
+        };
+        Ok(Isochrone::from_costs(
+            map,
+            constraints,
+            time_limit,
+            time_to_reach_building,
+        ))
+    }
+
+    pub fn walking(
+        map: &Map,
+        starts: Vec<Spot>,
+        time_limit: Duration,
+        opts: WalkingOptions,
+    ) -> Isochrone {
+        let time_to_reach_building = all_walking_costs_from(map, starts, time_limit, opts);
+        Isochrone::from_costs(
+            map,
+            PathConstraints::Pedestrian,
+            time_limit,
+            time_to_reach_building,
+        )
+    }
+
+    fn from_costs(
+        map: &Map,
+        constraints: PathConstraints,
+        time_limit: Duration,
+        time_to_reach_building: HashMap<BuildingID, Duration>,
+    ) -> Isochrone {
+        let roads = time_to_reach_building
+            .keys()
+            .map(|b| map.get_b(*b).sidewalk_pos.lane().road)
+            .collect();
+        Isochrone {
+            constraints,
+            time_limit,
+            time_to_reach_building,
+            roads,
+        }
+    }
+
+    /// For each threshold (which should be no more than the time limit), returns polygons
+    /// tightly covering the buildings reachable within that much time. Gaps between buildings
+    /// wider than a street, like parks and water, aren't covered.
+    pub fn polygons(&self, map: &Map, thresholds: &[Duration]) -> Vec<Vec<Polygon>> {
+        thresholds
+            .iter()
+            .map(|threshold| {
+                let mut pts = Vec::new();
+                for (b, cost) in &self.time_to_reach_building {
+                    if cost <= threshold {
+                        pts.extend(
+                            map.get_b(*b)
+                                .polygon
+                                .get_outer_ring()
+                                .points()
+                                .iter()
+                                .cloned(),
+                        );
+                    }
+                }
+                concave_hull::alpha_shape(pts, ALPHA)
+            })
+            .collect()
+    }
+}
+
+/// Big enough to span a typical street between buildings on either side
+const ALPHA: Distance = Distance::const_meters(50.0);
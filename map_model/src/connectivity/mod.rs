@@ -1,17 +1,26 @@
 // TODO Possibly these should be methods on Map.
 
 use serde::{Deserialize, Serialize};
-use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
 
 use petgraph::graphmap::DiGraphMap;
 
 use abstutil::PriorityQueueItem;
-use geom::Duration;
+use geom::{Distance, Duration};
 
+pub use self::accessibility::WheelchairAccess;
+pub use self::daily_needs::{DailyNeed, DailyNeedsAccess};
+pub use self::isochrone::Isochrone;
 pub use self::walking::{all_walking_costs_from, WalkingOptions};
 pub use crate::pathfind::{vehicle_cost, WalkingNode};
-use crate::{BuildingID, DirectedRoadID, IntersectionID, LaneID, Map, PathConstraints};
-
+use crate::{
+    BuildingID, DirectedRoadID, Direction, IntersectionID, LaneID, LevelOfTrafficStress, Map,
+    PathConstraints, RoadID,
+};
+
+mod accessibility;
+mod daily_needs;
+mod isochrone;
 mod walking;
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -150,3 +159,52 @@ pub fn all_vehicle_costs_from(
     }
     results
 }
+
+/// Group the roads comfortable for cycling into islands connected to each other. A road belongs
+/// to the low-stress network if its level of traffic stress in either direction is no more than
+/// `tolerance`. The islands are sorted from longest to shortest total length.
+pub fn find_low_stress_islands(
+    map: &Map,
+    tolerance: LevelOfTrafficStress,
+) -> Vec<BTreeSet<RoadID>> {
+    let mut low_stress = BTreeSet::new();
+    for r in map.all_roads() {
+        for dir in [Direction::Fwd, Direction::Back] {
+            if let Some(lts) = r.level_of_traffic_stress(map, dir, None) {
+                if lts <= tolerance {
+                    low_stress.insert(r.id);
+                }
+            }
+        }
+    }
+
+    let mut islands = Vec::new();
+    let mut visited = HashSet::new();
+    for start in &low_stress {
+        if visited.contains(start) {
+            continue;
+        }
+        let mut island = BTreeSet::new();
+        let mut queue = vec![*start];
+        while let Some(r) = queue.pop() {
+            if !visited.insert(r) {
+                continue;
+            }
+            island.insert(r);
+            let road = map.get_r(r);
+            for i in [road.src_i, road.dst_i] {
+                for next in &map.get_i(i).roads {
+                    if low_stress.contains(next) && !visited.contains(next) {
+                        queue.push(*next);
+                    }
+                }
+            }
+        }
+        let length = island
+            .iter()
+            .fold(Distance::ZERO, |sum, r| sum + map.get_r(*r).length());
+        islands.push((length, island));
+    }
+    islands.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+    islands.into_iter().map(|(_, island)| island).collect()
+}
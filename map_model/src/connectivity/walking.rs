@@ -5,13 +5,16 @@ use geom::{Duration, Speed};
 
 use crate::connectivity::Spot;
 use crate::pathfind::{zone_cost, WalkingNode};
-use crate::{BuildingID, Lane, LaneType, Map, PathConstraints, PathStep};
+use crate::{BuildingID, DirectedRoadID, Lane, LaneType, Map, PathConstraints, PathStep};
 
 #[derive(Clone)]
 pub struct WalkingOptions {
     /// If true, allow walking on shoulders.
     pub allow_shoulders: bool,
     pub walking_speed: Speed,
+    /// If true, avoid steps, steep grades, rough surfaces, and crossings without dropped kerbs,
+    /// for wheelchair users and others with limited mobility.
+    pub wheelchair: bool,
 }
 
 impl WalkingOptions {
@@ -19,6 +22,7 @@ impl WalkingOptions {
         WalkingOptions {
             allow_shoulders: true,
             walking_speed: WalkingOptions::default_speed(),
+            wheelchair: false,
         }
     }
 
@@ -128,8 +132,9 @@ pub fn all_walking_costs_from(
             _ => unreachable!(),
         };
         let lane = map.get_l(r.must_get_sidewalk(map));
+        let passable = !opts.wheelchair || map.get_r(r.road).wheelchair_barrier().is_none();
         // Cross the lane
-        if opts.allow_shoulders || lane.lane_type != LaneType::Shoulder {
+        if (opts.allow_shoulders || lane.lane_type != LaneType::Shoulder) && passable {
             let sidewalk_len = lane.length();
             let step = if is_dst_i {
                 PathStep::ContraflowLane(lane.id)
@@ -156,7 +161,7 @@ pub fn all_walking_costs_from(
                     };
                     let bldg_cost = current.cost + dist_to_bldg / speed;
                     if bldg_cost <= time_limit {
-                        results.insert(*b, bldg_cost);
+                        insert_min(&mut results, *b, bldg_cost);
                     }
                 }
 
@@ -165,12 +170,111 @@ pub fn all_walking_costs_from(
                     value: cross_to_node,
                 });
             }
+
+            // Mid-block crossings lead straight to the sidewalk on the other side of the road, the
+            // same as in pedestrian pathfinding. In a shared space, people can cross anywhere.
+            let road = map.get_r(r.road);
+            let other_side = DirectedRoadID {
+                road: r.road,
+                dir: r.dir.opposite(),
+            };
+            let other_side_walkable = (road.mid_block_crossings().next().is_some()
+                || road.shared_space)
+                && road
+                    .children(other_side.dir)
+                    .into_iter()
+                    .filter(|(_, lt)| lt.is_walkable())
+                    .count()
+                    == 1;
+            let other_lane = if other_side_walkable {
+                Some(map.get_l(other_side.must_get_sidewalk(map)))
+            } else {
+                None
+            }
+            .filter(|l| opts.allow_shoulders || l.lane_type != LaneType::Shoulder);
+            if let Some(other_lane) = other_lane {
+                // Crossings are positioned along the center of the road, as a fraction of its
+                // length. Also track how long people expect to wait there.
+                let mut crossing_pcts: Vec<(f64, Duration)> = road
+                    .mid_block_crossings()
+                    .filter(|crossing| !opts.wheelchair || !road.raised_kerb_at(crossing.dist))
+                    .map(|crossing| (crossing.dist / road.length(), crossing.expected_wait()))
+                    .collect();
+                if road.shared_space {
+                    // Head straight across to each building
+                    for b in sidewalk_to_bldgs.get(other_lane.id) {
+                        let pct = map.get_b(*b).sidewalk_pos.dist_along() / other_lane.length();
+                        crossing_pcts.push((
+                            if other_lane.dst_i == road.dst_i {
+                                pct
+                            } else {
+                                1.0 - pct
+                            },
+                            Duration::ZERO,
+                        ));
+                    }
+                }
+                for (pct, wait) in crossing_pcts {
+                    // Sidewalks point in their own direction
+                    let along_here = lane.length()
+                        * if lane.dst_i == road.dst_i {
+                            pct
+                        } else {
+                            1.0 - pct
+                        };
+                    let along_there = other_lane.length()
+                        * if other_lane.dst_i == road.dst_i {
+                            pct
+                        } else {
+                            1.0 - pct
+                        };
+                    let walk_here = if is_dst_i {
+                        sidewalk_len - along_here
+                    } else {
+                        along_here
+                    };
+                    let cost_at_crossing = current.cost
+                        + walk_here / speed
+                        + wait
+                        + road.get_width() / opts.walking_speed;
+                    if cost_at_crossing > time_limit {
+                        continue;
+                    }
+
+                    for b in sidewalk_to_bldgs.get(other_lane.id) {
+                        let bldg_dist_along = map.get_b(*b).sidewalk_pos.dist_along();
+                        let dist_to_bldg = if bldg_dist_along > along_there {
+                            bldg_dist_along - along_there
+                        } else {
+                            along_there - bldg_dist_along
+                        };
+                        let bldg_cost = cost_at_crossing + dist_to_bldg / speed;
+                        if bldg_cost <= time_limit {
+                            insert_min(&mut results, *b, bldg_cost);
+                        }
+                    }
+                    queue.push(PriorityQueueItem {
+                        cost: cost_at_crossing + along_there / speed,
+                        value: WalkingNode::SidewalkEndpoint(other_side, false),
+                    });
+                    queue.push(PriorityQueueItem {
+                        cost: cost_at_crossing + (other_lane.length() - along_there) / speed,
+                        value: WalkingNode::SidewalkEndpoint(other_side, true),
+                    });
+                }
+            }
         }
         // All turns from the lane
         for turn in map.get_turns_for(lane.id, PathConstraints::Pedestrian) {
             if (turn.id.parent == lane.dst_i) != is_dst_i {
                 continue;
             }
+            if opts.wheelchair
+                && turn.turn_type.pedestrian_crossing()
+                && map.get_parent(turn.id.src).raised_kerb_near(turn.id.parent)
+            {
+                continue;
+            }
             queue.push(PriorityQueueItem {
                 cost: current.cost
                     + turn.geom.length()
@@ -190,3 +294,10 @@ pub fn all_walking_costs_from(
 
     results
 }
+
+fn insert_min(results: &mut HashMap<BuildingID, Duration>, b: BuildingID, cost: Duration) {
+    let entry = results.entry(b).or_insert(cost);
+    if cost < *entry {
+        *entry = cost;
+    }
+}
@@ -150,6 +150,8 @@ impl Map {
             .extend(more_changed_intersections);
 
         self.recalculate_road_to_buildings();
+        // Road edits can change the shape of a roundabout
+        self.recalculate_roundabouts();
 
         effects
     }
@@ -157,12 +159,30 @@ impl Map {
     /// This can expensive, so don't constantly do it while editing in the UI. But this must happen
     /// before the simulation resumes.
     pub fn recalculate_pathfinding_after_edits(&mut self, timer: &mut Timer) {
+        self.update_pathfinding_after_edits(false, timer);
+    }
+
+    /// Like `recalculate_pathfinding_after_edits`, but much faster for small edits. Modes affected
+    /// by the edits fall back to slower queries, until `recalculate_pathfinding_after_edits` is
+    /// called. Use this when the map is about to be queried a few times, not before running a
+    /// full simulation.
+    pub fn quickly_recalculate_pathfinding_after_edits(&mut self, timer: &mut Timer) {
+        self.update_pathfinding_after_edits(true, timer);
+    }
+
+    fn update_pathfinding_after_edits(&mut self, quick: bool, timer: &mut Timer) {
         if !self.pathfinder_dirty {
+            // A previous quick update might've left some modes using the slower fallback
+            if !quick && self.pathfinder.is_stale() {
+                let mut pathfinder = std::mem::replace(&mut self.pathfinder, Pathfinder::empty());
+                pathfinder.apply_edits(self, false, timer);
+                self.pathfinder = pathfinder;
+            }
             return;
         }
 
         let mut pathfinder = std::mem::replace(&mut self.pathfinder, Pathfinder::empty());
-        pathfinder.apply_edits(self, timer);
+        pathfinder.apply_edits(self, quick, timer);
         self.pathfinder = pathfinder;
 
         // Also recompute blackholes. This is cheap enough to do from scratch.
@@ -203,6 +223,9 @@ impl EditCmd {
                 road.access_restrictions = new.access_restrictions.clone();
                 road.modal_filter = new.modal_filter.clone();
                 road.crossings = new.crossings.clone();
+                road.parking_restrictions = new.parking_restrictions.clone();
+                road.bus_priority = new.bus_priority.clone();
+                road.shared_space = new.shared_space;
                 road.turn_restrictions = new.turn_restrictions.clone();
                 road.complicated_turn_restrictions = new.complicated_turn_restrictions.clone();
 
@@ -273,6 +296,14 @@ impl EditCmd {
             EditCmd::ChangeRouteSchedule { id, new, .. } => {
                 map.transit_routes[id.0].spawn_times = new.clone();
             }
+            EditCmd::ChangeRouteFare { id, new, .. } => {
+                map.transit_routes[id.0].fare = new.clone();
+            }
+            EditCmd::Group { cmds, .. } => {
+                for cmd in cmds {
+                    cmd.apply(effects, map);
+                }
+            }
         }
     }
 
@@ -293,6 +324,15 @@ impl EditCmd {
                 old: new,
                 new: old,
             },
+            EditCmd::ChangeRouteFare { id, old, new } => EditCmd::ChangeRouteFare {
+                id,
+                old: new,
+                new: old,
+            },
+            EditCmd::Group { description, cmds } => EditCmd::Group {
+                description,
+                cmds: cmds.into_iter().rev().map(|cmd| cmd.undo()).collect(),
+            },
         }
     }
 }
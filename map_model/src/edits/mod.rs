@@ -8,18 +8,21 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use abstutil::Timer;
-use geom::{Speed, Time};
+use geom::{Distance, Speed, Time};
 use osm2streets::{get_lane_specs_ltr, RestrictionType};
 
-pub use self::perma::PermanentMapEdits;
+pub use self::osm_export::{NewBarrier, OsmChanges, RemovedBarrier};
+pub use self::perma::{MigrationReport, PermanentEditCmd, PermanentMapEdits};
 use crate::{
-    AccessRestrictions, ControlStopSign, ControlTrafficSignal, Crossing, DiagonalFilter,
-    IntersectionControl, IntersectionID, LaneID, LaneSpec, Map, MapConfig, ParkingLotID, Road,
-    RoadFilter, RoadID, TransitRouteID, TurnID, TurnType,
+    osm, AccessRestrictions, BusPriority, ControlStopSign, ControlTrafficSignal, Crossing,
+    DiagonalFilter, IntersectionControl, IntersectionID, LaneID, LaneSpec, LaneType, Map,
+    MapConfig, ParkingLotID, ParkingRestriction, Road, RoadFilter, RoadID, TransitFare,
+    TransitRouteID, TurnID, TurnType,
 };
 
 mod apply;
 mod compat;
+mod osm_export;
 mod perma;
 pub mod perma_traffic_signal;
 
@@ -41,6 +44,10 @@ pub struct MapEdits {
     /// proposals." They require a description and may have a link to a write-up.
     pub proposal_description: Vec<String>,
     pub proposal_link: Option<String>,
+    /// Who made these edits, if they chose to say
+    pub author: Option<String>,
+    /// When these edits were first created, like "2023-12-31"
+    pub created: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -60,6 +67,17 @@ pub enum EditCmd {
         old: Vec<Time>,
         new: Vec<Time>,
     },
+    ChangeRouteFare {
+        id: TransitRouteID,
+        old: TransitFare,
+        new: TransitFare,
+    },
+    /// Many changes made at once, like setting the speed limit across an area. They're undone
+    /// together.
+    Group {
+        description: String,
+        cmds: Vec<EditCmd>,
+    },
 }
 
 pub struct EditEffects {
@@ -80,6 +98,12 @@ pub struct EditRoad {
     pub access_restrictions: AccessRestrictions,
     pub modal_filter: Option<RoadFilter>,
     pub crossings: Vec<Crossing>,
+    #[serde(default)]
+    pub parking_restrictions: Vec<ParkingRestriction>,
+    #[serde(default)]
+    pub bus_priority: Vec<BusPriority>,
+    #[serde(default)]
+    pub shared_space: bool,
     pub turn_restrictions: Vec<(RestrictionType, RoadID)>,
     pub complicated_turn_restrictions: Vec<(RoadID, RoadID)>,
 }
@@ -110,8 +134,10 @@ impl EditRoad {
             access_restrictions: r.access_restrictions_from_osm(),
             // TODO Port logic/existing_filters.rs here?
             modal_filter: None,
-            // TODO From crossing_nodes?
-            crossings: Vec::new(),
+            crossings: r.crossings_from_osm(),
+            parking_restrictions: Vec::new(),
+            bus_priority: Vec::new(),
+            shared_space: r.shared_space_from_osm(),
             // TODO - review this. When editing turn restrictions, within the LTN tool we do not
             // use `get_orig_from_osm()`. The `EditRoad` is populated `map.get_r_edit()`.
             // Therefore we just create empty vecs here for now.
@@ -121,6 +147,73 @@ impl EditRoad {
         }
     }
 
+    pub fn total_width(&self) -> Distance {
+        self.lanes_ltr.iter().map(|spec| spec.width).sum()
+    }
+
+    /// Describes any problems fitting these lanes into a cross-section of the given width: lanes
+    /// too narrow to be usable, or lanes not fitting in the space at all.
+    pub fn validate_cross_section(&self, available: Distance) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (idx, spec) in self.lanes_ltr.iter().enumerate() {
+            let min = min_lane_width(spec.lt);
+            if spec.width < min {
+                problems.push(format!(
+                    "Lane {} ({}) is narrower than the minimum {}",
+                    idx + 1,
+                    spec.lt.short_name(),
+                    min
+                ));
+            }
+        }
+        let total = self.total_width();
+        if total > available {
+            problems.push(format!(
+                "The lanes need {} more than the {} available",
+                total - available,
+                available
+            ));
+        }
+        problems
+    }
+
+    /// Narrow general traffic and parking lanes (but never below their minimum width) until all
+    /// lanes fit in the available width. The lane at index `keep` isn't touched. Returns false if
+    /// the lanes still don't fit.
+    pub fn fit_to_width(&mut self, available: Distance, keep: Option<usize>) -> bool {
+        let excess = self.total_width() - available;
+        if excess <= Distance::ZERO {
+            return true;
+        }
+
+        let mut slack = Vec::new();
+        for (idx, spec) in self.lanes_ltr.iter().enumerate() {
+            if Some(idx) == keep
+                || !matches!(
+                    spec.lt,
+                    LaneType::Driving | LaneType::Bus | LaneType::Parking
+                )
+            {
+                continue;
+            }
+            let extra = spec.width - min_lane_width(spec.lt);
+            if extra > Distance::ZERO {
+                slack.push((idx, extra));
+            }
+        }
+        let total_slack: Distance = slack.iter().map(|(_, extra)| *extra).sum();
+        if total_slack == Distance::ZERO {
+            return false;
+        }
+
+        // Take from every lane in proportion to how much it can give
+        let fraction = (excess / total_slack).min(1.0);
+        for (idx, extra) in slack {
+            self.lanes_ltr[idx].width -= extra * fraction;
+        }
+        excess <= total_slack
+    }
+
     fn diff(&self, other: &EditRoad) -> Vec<String> {
         #![allow(clippy::comparison_chain)]
         let mut lt = 0;
@@ -166,10 +259,34 @@ impl EditRoad {
         if self.crossings != other.crossings {
             changes.push("crossings".to_string());
         }
+        if self.parking_restrictions != other.parking_restrictions {
+            changes.push("parking restrictions".to_string());
+        }
+        if self.bus_priority != other.bus_priority {
+            changes.push("bus priority".to_string());
+        }
+        if self.shared_space != other.shared_space {
+            changes.push(if self.shared_space {
+                "shared space".to_string()
+            } else {
+                "no longer a shared space".to_string()
+            });
+        }
         changes
     }
 }
 
+/// The narrowest a lane of some type can reasonably be, based loosely on NACTO guidance.
+fn min_lane_width(lt: LaneType) -> Distance {
+    match lt {
+        LaneType::Driving | LaneType::Bus => Distance::meters(2.7),
+        LaneType::Parking => Distance::meters(2.0),
+        LaneType::Biking => Distance::meters(1.2),
+        LaneType::Sidewalk => Distance::meters(1.5),
+        _ => Distance::ZERO,
+    }
+}
+
 impl EditIntersection {
     fn diff(&self, other: &EditIntersection) -> Vec<String> {
         let mut changes = Vec::new();
@@ -193,6 +310,8 @@ impl MapEdits {
             edits_name: "TODO temporary".to_string(),
             proposal_description: Vec::new(),
             proposal_link: None,
+            author: None,
+            created: None,
             commands: Vec::new(),
 
             original_roads: BTreeMap::new(),
@@ -205,6 +324,18 @@ impl MapEdits {
     /// match the current map. If the resulting edits are totally empty, consider that a failure --
     /// the edits likely don't cover this map at all.
     pub fn load_from_file(map: &Map, path: String, timer: &mut Timer) -> Result<MapEdits> {
+        let (edits, report) = MapEdits::load_and_migrate_from_file(map, path, timer)?;
+        report.log();
+        Ok(edits)
+    }
+
+    /// Like `load_from_file`, but also describes how edits made against an older version of the
+    /// map were matched up to this one, and which of them couldn't be.
+    pub fn load_and_migrate_from_file(
+        map: &Map,
+        path: String,
+        timer: &mut Timer,
+    ) -> Result<(MapEdits, MigrationReport)> {
         let perma = match abstio::maybe_read_json::<PermanentMapEdits>(path.clone(), timer) {
             Ok(perma) => perma,
             Err(_) => {
@@ -226,11 +357,11 @@ impl MapEdits {
             );
         }
 
-        let edits = perma.into_edits_permissive(map);
+        let (edits, report) = perma.migrate(map);
         if edits.commands.is_empty() {
             bail!("None of the edits apply to this map");
         }
-        Ok(edits)
+        Ok((edits, report))
     }
 
     /// Load map edits from the given JSON bytes. Strip out any commands that're broken because
@@ -270,7 +401,7 @@ impl MapEdits {
         self.original_intersections.clear();
         self.changed_routes.clear();
 
-        for cmd in &self.commands {
+        for cmd in self.commands.iter().flat_map(|cmd| cmd.flatten()) {
             match cmd {
                 EditCmd::ChangeRoad { r, ref old, .. } => {
                     if !self.original_roads.contains_key(r) {
@@ -282,9 +413,10 @@ impl MapEdits {
                         self.original_intersections.insert(*i, old.clone());
                     }
                 }
-                EditCmd::ChangeRouteSchedule { id, .. } => {
+                EditCmd::ChangeRouteSchedule { id, .. } | EditCmd::ChangeRouteFare { id, .. } => {
                     self.changed_routes.insert(*id);
                 }
+                EditCmd::Group { .. } => unreachable!(),
             }
         }
 
@@ -294,7 +426,7 @@ impl MapEdits {
             .retain(|i, orig| map.get_i_edit(*i) != orig.clone());
         self.changed_routes.retain(|br| {
             let r = map.get_tr(*br);
-            r.spawn_times != r.orig_spawn_times
+            r.spawn_times != r.orig_spawn_times || r.fare != r.orig_fare
         });
     }
 
@@ -316,11 +448,20 @@ impl MapEdits {
         }
         for r in &self.changed_routes {
             let r = map.get_tr(*r);
-            self.commands.push(EditCmd::ChangeRouteSchedule {
-                id: r.id,
-                new: r.spawn_times.clone(),
-                old: r.orig_spawn_times.clone(),
-            });
+            if r.spawn_times != r.orig_spawn_times {
+                self.commands.push(EditCmd::ChangeRouteSchedule {
+                    id: r.id,
+                    new: r.spawn_times.clone(),
+                    old: r.orig_spawn_times.clone(),
+                });
+            }
+            if r.fare != r.orig_fare {
+                self.commands.push(EditCmd::ChangeRouteFare {
+                    id: r.id,
+                    new: r.fare.clone(),
+                    old: r.orig_fare.clone(),
+                });
+            }
         }
     }
 
@@ -336,6 +477,9 @@ impl MapEdits {
                 || r.access_restrictions != orig.access_restrictions
                 || r.modal_filter != orig.modal_filter
                 || r.crossings != orig.crossings
+                || r.parking_restrictions != orig.parking_restrictions
+                || r.bus_priority != orig.bus_priority
+                || r.shared_space != orig.shared_space
                 // If a lane was added or deleted, figuring out if any were modified is kind of
                 // unclear -- just mark the entire road.
                 || r.lanes.len() != orig.lanes_ltr.len()
@@ -416,14 +560,31 @@ impl EditCmd {
             EditCmd::ChangeRouteSchedule { id, .. } => {
                 format!("reschedule route {}", map.get_tr(*id).short_name)
             }
+            EditCmd::ChangeRouteFare { id, old, new } => {
+                details.push(format!("{} -> {}", old.describe(), new.describe()));
+                format!("change fare for route {}", map.get_tr(*id).short_name)
+            }
+            EditCmd::Group { description, cmds } => {
+                details.push(format!("{} changes", cmds.len()));
+                description.clone()
+            }
         };
         (summary, details)
     }
+
+    /// Expands groups into the individual commands they contain.
+    pub fn flatten(&self) -> Vec<&EditCmd> {
+        match self {
+            EditCmd::Group { cmds, .. } => cmds.iter().flat_map(|cmd| cmd.flatten()).collect(),
+            _ => vec![self],
+        }
+    }
 }
 
 impl Map {
     pub fn new_edits(&self) -> MapEdits {
         let mut edits = MapEdits::new();
+        edits.created = abstutil::today();
 
         // Automatically find a new filename
         let mut i = 1;
@@ -441,6 +602,22 @@ impl Map {
         &self.edits
     }
 
+    /// A checksum of the parts of the map that edits refer to. If the map is imported again and
+    /// this changes, edits made against the old version might need to be migrated.
+    pub fn get_basemap_checksum(&self) -> String {
+        let mut context = md5::Context::new();
+        for r in self.all_roads() {
+            context.consume(r.orig_id.to_string());
+            for l in &r.lanes {
+                context.consume(format!("{:?}{:?}", l.lane_type, l.dir));
+            }
+        }
+        for i in self.all_intersections() {
+            context.consume(i.orig_id.to_string());
+        }
+        format!("{:x}", context.compute())
+    }
+
     pub fn unsaved_edits(&self) -> bool {
         self.edits.edits_name.starts_with("Untitled Proposal") && !self.edits.commands.is_empty()
     }
@@ -453,6 +630,9 @@ impl Map {
             access_restrictions: r.access_restrictions.clone(),
             modal_filter: r.modal_filter.clone(),
             crossings: r.crossings.clone(),
+            parking_restrictions: r.parking_restrictions.clone(),
+            bus_priority: r.bus_priority.clone(),
+            shared_space: r.shared_space,
             turn_restrictions: r.turn_restrictions.clone(),
             complicated_turn_restrictions: r.complicated_turn_restrictions.clone(),
         }
@@ -500,6 +680,53 @@ impl Map {
         EditCmd::ChangeIntersection { i, old, new }
     }
 
+    /// Exports the current timing of every traffic signal in the map.
+    pub fn export_traffic_signals(&self) -> Vec<perma_traffic_signal::TrafficSignal> {
+        self.all_intersections()
+            .iter()
+            .filter(|i| i.is_traffic_signal())
+            .map(|i| self.get_traffic_signal(i.id).export(self))
+            .collect()
+    }
+
+    /// Matches imported traffic signals to existing signalized intersections, producing a command
+    /// to change each one. Signals that can't be matched or don't fit the intersection are
+    /// returned as errors instead.
+    pub fn import_traffic_signals(
+        &self,
+        signals: Vec<perma_traffic_signal::TrafficSignal>,
+    ) -> (Vec<EditCmd>, Vec<String>) {
+        let mut cmds = Vec::new();
+        let mut errors = Vec::new();
+        for ts in signals {
+            let osm_id = ts.intersection_osm_node_id;
+            let result = self
+                .find_i_by_osm_id(osm::NodeID(osm_id))
+                .and_then(|i| {
+                    if self.get_i(i).is_traffic_signal() {
+                        Ok(i)
+                    } else {
+                        bail!("{} isn't a traffic signal", i)
+                    }
+                })
+                .and_then(|i| {
+                    ControlTrafficSignal::import(ts.clone(), i, self)?;
+                    Ok(i)
+                });
+            match result {
+                Ok(i) => {
+                    cmds.push(self.edit_intersection_cmd(i, |new| {
+                        new.control = EditIntersectionControl::TrafficSignal(ts);
+                    }));
+                }
+                Err(err) => {
+                    errors.push(format!("OSM node {}: {}", osm_id, err));
+                }
+            }
+        }
+        (cmds, errors)
+    }
+
     pub fn save_edits(&self) {
         // Don't overwrite the current edits with the compressed first. Otherwise, undo/redo order
         // in the UI gets messed up.
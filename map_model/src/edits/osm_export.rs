@@ -0,0 +1,404 @@
+//! Express map edits as changes to OpenStreetMap, so that proposals prototyped here can be taken
+//! back to OSM or used by other tools consuming OSM data.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use abstutil::Tags;
+use geom::{LonLat, Speed};
+use osm2streets::get_lane_specs_ltr;
+
+use crate::{
+    osm, Direction, DrivingSide, EditRoad, FilterType, LaneSpec, LaneType, Map, PathConstraints,
+    RoadID,
+};
+
+/// The result of expressing map edits in OSM terms
+pub struct OsmChanges {
+    /// The complete, new set of tags for every modified way
+    pub ways: BTreeMap<osm::WayID, Tags>,
+    /// New barrier nodes to insert into existing ways
+    pub new_barriers: Vec<NewBarrier>,
+    /// Existing barrier nodes to delete
+    pub removed_barriers: Vec<RemovedBarrier>,
+    /// Edits that couldn't be expressed, or might need manual review
+    pub warnings: Vec<String>,
+}
+
+/// A modal filter, expressed as a new OSM node along a way
+pub struct NewBarrier {
+    pub way: osm::WayID,
+    pub pt: LonLat,
+    pub tags: Tags,
+}
+
+/// A modal filter that was removed or moved. The OSM node isn't known here, so the barrier node on
+/// the way closest to this point should be deleted.
+pub struct RemovedBarrier {
+    pub way: osm::WayID,
+    pub pt: LonLat,
+}
+
+impl Map {
+    /// Describe the current edits to roads as changes to OSM ways and nodes. Only changes to
+    /// lanes, speed limits, access restrictions, and modal filters are handled.
+    ///
+    /// Edits only partly covering an OSM way can't be expressed by changing the way's tags; the
+    /// way would need to be split first. These ways are skipped and mentioned in the warnings.
+    pub fn edits_to_osm_changes(&self) -> OsmChanges {
+        let mut changes = OsmChanges {
+            ways: BTreeMap::new(),
+            new_barriers: Vec::new(),
+            removed_barriers: Vec::new(),
+            warnings: Vec::new(),
+        };
+
+        let mut new_tags_per_way: BTreeMap<osm::WayID, Vec<(RoadID, Tags)>> = BTreeMap::new();
+        for (r, orig) in &self.edits.original_roads {
+            let road = self.get_r(*r);
+            let way = road.orig_id.osm_way_id;
+            if way.0 < 0 {
+                changes
+                    .warnings
+                    .push(format!("{} isn't from OSM, so its edits are skipped", r));
+                continue;
+            }
+            let new = self.get_r_edit(*r);
+            let tags = self.road_edits_to_osm_tags(*r, orig, &new, &mut changes.warnings);
+
+            if new.modal_filter != orig.modal_filter {
+                if let Some(ref filter) = new.modal_filter {
+                    if let Some(tags) = barrier_tags(filter.filter_type) {
+                        if let Ok((pt, _)) = road.center_pts.dist_along(filter.dist) {
+                            changes.new_barriers.push(NewBarrier {
+                                way,
+                                pt: pt.to_gps(self.get_gps_bounds()),
+                                tags,
+                            });
+                        }
+                    } else {
+                        changes.warnings.push(format!(
+                            "{} has a {:?} filter, which has no OSM equivalent",
+                            r, filter.filter_type
+                        ));
+                    }
+                }
+                if let Some(ref filter) = orig.modal_filter {
+                    if barrier_tags(filter.filter_type).is_some() {
+                        if let Ok((pt, _)) = road.center_pts.dist_along(filter.dist) {
+                            changes.removed_barriers.push(RemovedBarrier {
+                                way,
+                                pt: pt.to_gps(self.get_gps_bounds()),
+                            });
+                        }
+                    }
+                }
+            }
+
+            new_tags_per_way.entry(way).or_default().push((*r, tags));
+        }
+
+        // A way may be split into many roads. All of them need the same tags.
+        let mut roads_per_way: BTreeMap<osm::WayID, Vec<RoadID>> = BTreeMap::new();
+        for road in self.all_roads() {
+            roads_per_way
+                .entry(road.orig_id.osm_way_id)
+                .or_default()
+                .push(road.id);
+        }
+        let mut skipped = BTreeSet::new();
+        for (way, edited) in new_tags_per_way {
+            let tags = edited[0].1.clone();
+            let consistent = roads_per_way[&way].iter().all(|r| {
+                edited
+                    .iter()
+                    .find(|(edited_r, _)| edited_r == r)
+                    .map(|(_, t)| t)
+                    .cloned()
+                    .unwrap_or_else(|| exportable_tags(&self.get_r(*r).osm_tags))
+                    == tags
+            });
+            if !consistent {
+                changes.warnings.push(format!(
+                    "{} is only partly edited, or its pieces were edited differently. Split the way in OSM first.",
+                    way
+                ));
+                skipped.insert(way);
+                continue;
+            }
+            if tags != exportable_tags(&self.get_r(edited[0].0).osm_tags) {
+                changes.ways.insert(way, tags);
+            }
+        }
+        changes.new_barriers.retain(|b| !skipped.contains(&b.way));
+        changes
+            .removed_barriers
+            .retain(|b| !skipped.contains(&b.way));
+
+        changes
+    }
+
+    fn road_edits_to_osm_tags(
+        &self,
+        r: RoadID,
+        orig: &EditRoad,
+        new: &EditRoad,
+        warnings: &mut Vec<String>,
+    ) -> Tags {
+        let road = self.get_r(r);
+        let mut tags = exportable_tags(&road.osm_tags);
+
+        if new.lanes_ltr != orig.lanes_ltr {
+            lanes_to_osm_tags(&new.lanes_ltr, self.get_config().driving_side, &mut tags);
+
+            // Make sure other OSM tools will interpret the tags the same way
+            let lane_types = |lanes: Vec<LaneSpec>| -> Vec<(LaneType, Direction)> {
+                lanes
+                    .into_iter()
+                    .filter(|spec| !matches!(spec.lt, LaneType::Buffer(_)))
+                    .map(|spec| (spec.lt, spec.dir))
+                    .collect()
+            };
+            if lane_types(get_lane_specs_ltr(&tags, self.get_config()))
+                != lane_types(new.lanes_ltr.clone())
+            {
+                warnings.push(format!(
+                    "The lanes of {} can't be expressed exactly in OSM tags; review them",
+                    r
+                ));
+            }
+        }
+
+        if new.speed_limit != orig.speed_limit {
+            let mph = road
+                .osm_tags
+                .get("maxspeed")
+                .map(|x| x.ends_with("mph"))
+                .unwrap_or(false);
+            tags.insert("maxspeed", speed_to_osm(new.speed_limit, mph));
+        }
+
+        if new.shared_space != orig.shared_space {
+            tags.insert("shared_space", if new.shared_space { "yes" } else { "no" });
+        }
+
+        if new.access_restrictions != orig.access_restrictions {
+            let allowed = new.access_restrictions.allow_through_traffic;
+            for (constraints, key) in [
+                (PathConstraints::Car, "motor_vehicle"),
+                (PathConstraints::Bike, "bicycle"),
+                (PathConstraints::Pedestrian, "foot"),
+            ] {
+                if allowed.contains(constraints) {
+                    if tags.is_any(key, vec!["private", "destination", "no"]) {
+                        tags.remove(key);
+                    }
+                } else if !tags.is_any(key, vec!["private", "destination", "no"]) {
+                    tags.insert(key, "destination");
+                }
+            }
+        }
+
+        if new.access_restrictions.no_hgv != orig.access_restrictions.no_hgv {
+            if new.access_restrictions.no_hgv {
+                tags.insert("hgv", "no");
+            } else {
+                tags.remove("hgv");
+            }
+        }
+
+        if new.parking_restrictions != orig.parking_restrictions
+            || new.bus_priority != orig.bus_priority
+            || new.turn_restrictions != orig.turn_restrictions
+            || new.complicated_turn_restrictions != orig.complicated_turn_restrictions
+            || new.crossings != orig.crossings
+        {
+            warnings.push(format!(
+                "Parking, bus priority, turn restriction, and crossing edits on {} aren't exported",
+                r
+            ));
+        }
+
+        tags
+    }
+}
+
+/// Remove internal tags added during import
+fn exportable_tags(tags: &Tags) -> Tags {
+    let mut result = Tags::empty();
+    for (k, v) in tags.inner() {
+        if !k.starts_with("abst:") {
+            result.insert(k, v);
+        }
+    }
+    result
+}
+
+fn lanes_to_osm_tags(lanes: &[LaneSpec], driving_side: DrivingSide, tags: &mut Tags) {
+    for key in [
+        "lanes",
+        "lanes:forward",
+        "lanes:backward",
+        "lanes:both_ways",
+        "turn:lanes:both_ways",
+        "oneway",
+        "sidewalk",
+        "sidewalk:left",
+        "sidewalk:right",
+        "sidewalk:both",
+        "busway",
+        "busway:left",
+        "busway:right",
+        "busway:both",
+        "parking:lane:left",
+        "parking:lane:right",
+        "parking:lane:both",
+        "parking_lane_both",
+    ] {
+        tags.remove(key);
+    }
+    for side in ["", ":left", ":right", ":both"] {
+        tags.remove(&format!("cycleway{}", side));
+        tags.remove(&format!("cycleway{}:oneway", side));
+    }
+
+    let count = |lt: LaneType, dir: Direction| {
+        lanes
+            .iter()
+            .filter(|spec| spec.lt == lt && spec.dir == dir)
+            .count()
+    };
+    let fwd = count(LaneType::Driving, Direction::Fwd) + count(LaneType::Bus, Direction::Fwd);
+    let back = count(LaneType::Driving, Direction::Back) + count(LaneType::Bus, Direction::Back);
+    if fwd + back > 0 {
+        tags.insert("lanes", (fwd + back).to_string());
+    }
+    if back == 0 && fwd > 0 {
+        tags.insert("oneway", "yes");
+    } else if fwd == 0 && back > 0 {
+        tags.insert("oneway", "-1");
+    } else if fwd > 0 && back > 0 {
+        tags.insert("lanes:forward", fwd.to_string());
+        tags.insert("lanes:backward", back.to_string());
+    }
+    if lanes.iter().any(|spec| spec.lt == LaneType::SharedLeftTurn) {
+        tags.insert("lanes:both_ways", "1");
+        tags.insert("turn:lanes:both_ways", "left");
+    }
+
+    // Per side of the road, the value for sidewalks, bike lanes, bus lanes, and parking
+    let mut sidewalk = [false, false];
+    let mut cycleway: [Option<(&str, Option<&str>)>; 2] = [None, None];
+    let mut busway = [false, false];
+    let mut parking = [false, false];
+    for (idx, spec) in lanes.iter().enumerate() {
+        let side = lane_side(idx, lanes.len(), spec.dir, driving_side);
+        match spec.lt {
+            LaneType::Sidewalk => {
+                sidewalk[side] = true;
+            }
+            LaneType::Biking => {
+                let separated = [idx.checked_sub(1), Some(idx + 1)]
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|i| lanes.get(i))
+                    .any(|spec| matches!(spec.lt, LaneType::Buffer(_)));
+                // Is the bike lane going the usual direction for its side of the road?
+                let usual_dir = if (side == RIGHT) == (driving_side == DrivingSide::Right) {
+                    Direction::Fwd
+                } else {
+                    Direction::Back
+                };
+                let oneway = if spec.dir == usual_dir {
+                    None
+                } else if spec.dir == Direction::Fwd {
+                    Some("yes")
+                } else {
+                    Some("-1")
+                };
+                cycleway[side] = Some((if separated { "track" } else { "lane" }, oneway));
+            }
+            LaneType::Bus => {
+                busway[side] = true;
+            }
+            LaneType::Parking => {
+                parking[side] = true;
+            }
+            _ => {}
+        }
+    }
+
+    tags.insert(
+        "sidewalk",
+        match sidewalk {
+            [true, true] => "both",
+            [true, false] => "left",
+            [false, true] => "right",
+            [false, false] => "no",
+        },
+    );
+    for (side, name) in [(LEFT, "left"), (RIGHT, "right")] {
+        if let Some((value, oneway)) = cycleway[side] {
+            tags.insert(format!("cycleway:{}", name), value);
+            if let Some(oneway) = oneway {
+                tags.insert(format!("cycleway:{}:oneway", name), oneway);
+            }
+        }
+        if busway[side] {
+            tags.insert(format!("busway:{}", name), "lane");
+        }
+    }
+    if cycleway == [None, None] {
+        tags.insert("cycleway:both", "no");
+    }
+    if parking == [false, false] {
+        tags.insert("parking:lane:both", "no");
+    } else {
+        for (side, name) in [(LEFT, "left"), (RIGHT, "right")] {
+            tags.insert(
+                format!("parking:lane:{}", name),
+                if parking[side] { "parallel" } else { "no" },
+            );
+        }
+    }
+}
+
+const LEFT: usize = 0;
+const RIGHT: usize = 1;
+
+/// Which side of the road is a lane on? A lane in the exact middle is placed by its direction.
+fn lane_side(idx: usize, num_lanes: usize, dir: Direction, driving_side: DrivingSide) -> usize {
+    if 2 * idx + 1 < num_lanes {
+        LEFT
+    } else if 2 * idx + 1 > num_lanes {
+        RIGHT
+    } else if (dir == Direction::Fwd) == (driving_side == DrivingSide::Right) {
+        RIGHT
+    } else {
+        LEFT
+    }
+}
+
+fn speed_to_osm(speed: Speed, mph: bool) -> String {
+    if mph {
+        format!("{} mph", speed.to_miles_per_hour().round())
+    } else {
+        format!("{}", (speed.inner_meters_per_second() * 3.6).round())
+    }
+}
+
+fn barrier_tags(filter_type: FilterType) -> Option<Tags> {
+    let mut tags = Tags::empty();
+    match filter_type {
+        FilterType::WalkCycleOnly | FilterType::NoEntry => {
+            tags.insert("barrier", "bollard");
+        }
+        FilterType::BusGate => {
+            tags.insert("barrier", "bus_trap");
+        }
+        // This is a time-based restriction, not a physical barrier
+        FilterType::SchoolStreet => {
+            return None;
+        }
+    }
+    Some(tags)
+}
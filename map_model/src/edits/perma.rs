@@ -10,7 +10,8 @@ use geom::Time;
 use super::perma_traffic_signal;
 use crate::edits::{EditCmd, EditIntersection, EditIntersectionControl, EditRoad, MapEdits};
 use crate::{
-    osm, ControlStopSign, DiagonalFilter, IntersectionID, Map, MovementID, OriginalRoad, TurnType,
+    osm, ControlStopSign, DiagonalFilter, IntersectionID, Map, MovementID, OriginalRoad, Road,
+    TransitFare, TurnType,
 };
 
 // Manually change this to attempt to preserve edits after major OSM updates.
@@ -30,6 +31,55 @@ pub struct PermanentMapEdits {
     pub proposal_description: Vec<String>,
     /// The link is optional even for proposals
     pub proposal_link: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    /// When the edits were first created, like "2023-12-31"
+    #[serde(default)]
+    pub created: Option<String>,
+    /// `Map::get_basemap_checksum` when the edits were last saved. If the map has been imported
+    /// again since then, the edits have to be migrated.
+    #[serde(default)]
+    pub basemap_checksum: Option<String>,
+}
+
+/// Describes what happened when edits made against one version of a map were loaded into another.
+#[derive(Default)]
+pub struct MigrationReport {
+    /// The map was imported again since the edits were saved
+    pub basemap_changed: bool,
+    /// Edits that still apply, but to something that changed in the new map
+    pub rebound: Vec<String>,
+    /// Edits that couldn't be matched to anything in the new map, and were dropped
+    pub dropped: Vec<String>,
+}
+
+impl MigrationReport {
+    pub fn is_clean(&self) -> bool {
+        self.rebound.is_empty() && self.dropped.is_empty()
+    }
+
+    /// Describe every conflict, one per line
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if self.basemap_changed {
+            lines.push("The map has been updated since these edits were made.".to_string());
+        }
+        for x in &self.rebound {
+            lines.push(format!("Adjusted: {}", x));
+        }
+        for x in &self.dropped {
+            lines.push(format!("Dropped: {}", x));
+        }
+        lines
+    }
+
+    pub(crate) fn log(&self) {
+        if !self.is_clean() {
+            for line in self.describe() {
+                warn!("{}", line);
+            }
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -74,6 +124,15 @@ pub enum PermanentEditCmd {
         old: Vec<Time>,
         new: Vec<Time>,
     },
+    ChangeRouteFare {
+        gtfs_id: String,
+        old: TransitFare,
+        new: TransitFare,
+    },
+    Group {
+        description: String,
+        cmds: Vec<PermanentEditCmd>,
+    },
 }
 
 impl EditCmd {
@@ -96,6 +155,15 @@ impl EditCmd {
                     new: new.clone(),
                 }
             }
+            EditCmd::ChangeRouteFare { id, old, new } => PermanentEditCmd::ChangeRouteFare {
+                gtfs_id: map.get_tr(*id).gtfs_id.clone(),
+                old: old.clone(),
+                new: new.clone(),
+            },
+            EditCmd::Group { description, cmds } => PermanentEditCmd::Group {
+                description: description.clone(),
+                cmds: cmds.iter().map(|cmd| cmd.to_perma(map)).collect(),
+            },
         }
     }
 }
@@ -146,7 +214,106 @@ impl PermanentEditCmd {
                     .ok_or_else(|| anyhow!("can't find {}", gtfs_id))?;
                 Ok(EditCmd::ChangeRouteSchedule { id, old, new })
             }
+            PermanentEditCmd::ChangeRouteFare { gtfs_id, old, new } => {
+                let id = map
+                    .find_tr_by_gtfs(&gtfs_id)
+                    .ok_or_else(|| anyhow!("can't find {}", gtfs_id))?;
+                Ok(EditCmd::ChangeRouteFare { id, old, new })
+            }
+            PermanentEditCmd::Group { description, cmds } => Ok(EditCmd::Group {
+                description,
+                cmds: cmds
+                    .into_iter()
+                    .map(|cmd| cmd.into_cmd(map))
+                    .collect::<Result<Vec<EditCmd>>>()?,
+            }),
+        }
+    }
+
+    /// Like `into_cmd`, but if this refers to a road that's changed in the map, try to apply the
+    /// edit to the road anyway. If that happens, describes what changed.
+    fn rebind(self, map: &Map) -> Result<(EditCmd, Option<String>)> {
+        let (r, new, old) = match self {
+            PermanentEditCmd::ChangeRoad { r, new, old } => (r, new, old),
+            PermanentEditCmd::Group { description, cmds } => {
+                // Keep whatever part of the group still applies
+                let mut rebound = Vec::new();
+                let mut notes = Vec::new();
+                for cmd in cmds {
+                    match cmd.rebind(map) {
+                        Ok((cmd, note)) => {
+                            rebound.push(cmd);
+                            notes.extend(note);
+                        }
+                        Err(err) => {
+                            notes.push(format!("part of \"{}\" was dropped: {}", description, err));
+                        }
+                    }
+                }
+                if rebound.is_empty() {
+                    bail!("none of \"{}\" applies to this map", description);
+                }
+                let note = if notes.is_empty() {
+                    None
+                } else {
+                    Some(notes.join("; "))
+                };
+                return Ok((
+                    EditCmd::Group {
+                        description,
+                        cmds: rebound,
+                    },
+                    note,
+                ));
+            }
+            _ => {
+                return self.into_cmd(map).map(|cmd| (cmd, None));
+            }
+        };
+
+        let mut notes = Vec::new();
+        let id = match map.find_r_by_osm_id(r) {
+            Ok(id) => id,
+            Err(err) => {
+                // If the way was split or its endpoints moved, there might still be exactly one
+                // road segment from it
+                let candidates: Vec<&Road> = map
+                    .all_roads()
+                    .iter()
+                    .filter(|road| road.orig_id.osm_way_id == r.osm_way_id)
+                    .collect();
+                if candidates.len() != 1 {
+                    return Err(err);
+                }
+                notes.push(format!(
+                    "{} now ends at different nodes, {}",
+                    r, candidates[0].orig_id
+                ));
+                candidates[0].id
+            }
+        };
+
+        let current = EditRoad::get_orig_from_osm(map.get_r(id), map.get_config());
+        if current.lanes_ltr != old.lanes_ltr {
+            notes.push(format!(
+                "the lanes of {} changed in the map, but the edited lanes replace them",
+                r
+            ));
         }
+        let note = if notes.is_empty() {
+            None
+        } else {
+            Some(notes.join("; "))
+        };
+        // Note we change 'old' to match the current basemap
+        Ok((
+            EditCmd::ChangeRoad {
+                r: id,
+                new,
+                old: current,
+            },
+            note,
+        ))
     }
 }
 
@@ -160,6 +327,9 @@ impl MapEdits {
             version: 13,
             proposal_description: self.proposal_description.clone(),
             proposal_link: self.proposal_link.clone(),
+            author: self.author.clone(),
+            created: self.created.clone(),
+            basemap_checksum: Some(map.get_basemap_checksum()),
             commands: self.commands.iter().map(|cmd| cmd.to_perma(map)).collect(),
         }
     }
@@ -173,6 +343,8 @@ impl PermanentMapEdits {
             edits_name: self.edits_name,
             proposal_description: self.proposal_description,
             proposal_link: self.proposal_link,
+            author: self.author,
+            created: self.created,
             commands: self
                 .commands
                 .into_iter()
@@ -190,28 +362,50 @@ impl PermanentMapEdits {
     /// Transform permanent edits to MapEdits, looking up the map IDs by the hopefully stabler OSM
     /// IDs. Strip out commands that're broken, but log warnings.
     pub fn into_edits_permissive(self, map: &Map) -> MapEdits {
+        let (edits, report) = self.migrate(map);
+        report.log();
+        edits
+    }
+
+    /// Transform permanent edits to MapEdits, even if the map has been imported again since the
+    /// edits were made. Road edits are matched to the new map where possible, and commands that
+    /// can't be matched are stripped out. Everything that happened is described in the report.
+    pub fn migrate(self, map: &Map) -> (MapEdits, MigrationReport) {
+        let mut report = MigrationReport {
+            basemap_changed: self
+                .basemap_checksum
+                .as_ref()
+                .map(|checksum| *checksum != map.get_basemap_checksum())
+                .unwrap_or(false),
+            ..Default::default()
+        };
+        let mut commands = Vec::new();
+        for cmd in self.commands {
+            match cmd.rebind(map) {
+                Ok((cmd, note)) => {
+                    commands.push(cmd);
+                    report.rebound.extend(note);
+                }
+                Err(err) => {
+                    report.dropped.push(err.to_string());
+                }
+            }
+        }
+
         let mut edits = MapEdits {
             edits_name: self.edits_name,
             proposal_description: self.proposal_description,
             proposal_link: self.proposal_link,
-            commands: self
-                .commands
-                .into_iter()
-                .filter_map(|cmd| match cmd.into_cmd(map) {
-                    Ok(cmd) => Some(cmd),
-                    Err(err) => {
-                        warn!("Skipping broken command: {}", err);
-                        None
-                    }
-                })
-                .collect(),
+            author: self.author,
+            created: self.created,
+            commands,
 
             original_roads: BTreeMap::new(),
             original_intersections: BTreeMap::new(),
             changed_routes: BTreeSet::new(),
         };
         edits.update_derived(map);
-        edits
+        (edits, report)
     }
 
     /// Get the human-friendly of these edits. If they have a description, the first line is the
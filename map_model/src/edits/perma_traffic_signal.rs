@@ -1,8 +1,28 @@
 //! A representation of traffic signal configuration that references OpenStreetMap IDs and is
 //! hopefully robust to minor edits over time.
+//!
+//! Besides the JSON form of these structs, signal timing can also be exchanged as a flat CSV file,
+//! loosely modeled after the phasing tables in Synchro's UTDF format. Each row describes one turn
+//! during one stage:
+//!
+//! | column | meaning |
+//! |--------|---------|
+//! | `intersection_osm_node_id` | identifies the traffic signal |
+//! | `plan_start_time_seconds`, `offset_seconds` | from the `Plan` |
+//! | `stage` | which stage, starting from 1 |
+//! | `duration_seconds` | for fixed stages, or the minimum duration for variable stages |
+//! | `delay_seconds`, `additional_seconds` | both 0 for fixed stages |
+//! | `priority` | `protected` or `permitted` |
+//! | `from_osm_way_id`, `from_osm_node1`, `from_osm_node2`, `from_is_forwards` | `Turn::from` |
+//! | `to_osm_way_id`, `to_osm_node1`, `to_osm_node2`, `to_is_forwards` | `Turn::to` |
+//! | `turn_intersection_osm_node_id`, `is_crosswalk` | the rest of the `Turn` |
+//!
+//! Stages without any turns can't be expressed in this format.
 
 use std::collections::BTreeSet;
+use std::io::Cursor;
 
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -94,3 +114,233 @@ pub struct DirectedRoad {
     /// https://wiki.openstreetmap.org/wiki/Forward_%26_backward,_left_%26_right for details.
     pub is_forwards: bool,
 }
+
+/// Flattens traffic signals into the CSV format described at the top of this module.
+pub fn to_csv(signals: &[TrafficSignal]) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for ts in signals {
+        for plan in &ts.plans {
+            for (idx, stage) in plan.stages.iter().enumerate() {
+                let (duration, delay, additional) = match stage.stage_type {
+                    StageType::Fixed(d) => (d, 0, 0),
+                    StageType::Variable(min, delay, additional) => (min, delay, additional),
+                };
+                for (priority, turns) in [
+                    ("protected", &stage.protected_turns),
+                    ("permitted", &stage.permitted_turns),
+                ] {
+                    for turn in turns {
+                        writer.serialize(CsvRecord {
+                            intersection_osm_node_id: ts.intersection_osm_node_id,
+                            plan_start_time_seconds: plan.start_time_seconds,
+                            offset_seconds: plan.offset_seconds,
+                            stage: idx + 1,
+                            duration_seconds: duration,
+                            delay_seconds: delay,
+                            additional_seconds: additional,
+                            priority: priority.to_string(),
+                            from_osm_way_id: turn.from.osm_way_id,
+                            from_osm_node1: turn.from.osm_node1,
+                            from_osm_node2: turn.from.osm_node2,
+                            from_is_forwards: turn.from.is_forwards,
+                            to_osm_way_id: turn.to.osm_way_id,
+                            to_osm_node1: turn.to.osm_node1,
+                            to_osm_node2: turn.to.osm_node2,
+                            to_is_forwards: turn.to.is_forwards,
+                            turn_intersection_osm_node_id: turn.intersection_osm_node_id,
+                            is_crosswalk: turn.is_crosswalk,
+                        })?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+/// Parses traffic signals from the CSV format described at the top of this module. Rows for the
+/// same signal and plan don't have to be adjacent, but stages must be numbered consecutively.
+pub fn from_csv(bytes: &[u8]) -> Result<Vec<TrafficSignal>> {
+    let mut signals: Vec<TrafficSignal> = Vec::new();
+    for rec in csv::Reader::from_reader(Cursor::new(bytes)).deserialize() {
+        let rec: CsvRecord = rec?;
+        let turn = Turn {
+            from: DirectedRoad {
+                osm_way_id: rec.from_osm_way_id,
+                osm_node1: rec.from_osm_node1,
+                osm_node2: rec.from_osm_node2,
+                is_forwards: rec.from_is_forwards,
+            },
+            to: DirectedRoad {
+                osm_way_id: rec.to_osm_way_id,
+                osm_node1: rec.to_osm_node1,
+                osm_node2: rec.to_osm_node2,
+                is_forwards: rec.to_is_forwards,
+            },
+            intersection_osm_node_id: rec.turn_intersection_osm_node_id,
+            is_crosswalk: rec.is_crosswalk,
+        };
+        let stage_type = if rec.delay_seconds == 0 && rec.additional_seconds == 0 {
+            StageType::Fixed(rec.duration_seconds)
+        } else {
+            StageType::Variable(
+                rec.duration_seconds,
+                rec.delay_seconds,
+                rec.additional_seconds,
+            )
+        };
+
+        let ts_idx = match signals
+            .iter()
+            .position(|ts| ts.intersection_osm_node_id == rec.intersection_osm_node_id)
+        {
+            Some(idx) => idx,
+            None => {
+                signals.push(TrafficSignal {
+                    intersection_osm_node_id: rec.intersection_osm_node_id,
+                    plans: Vec::new(),
+                });
+                signals.len() - 1
+            }
+        };
+        let plans = &mut signals[ts_idx].plans;
+        let plan_idx = match plans
+            .iter()
+            .position(|p| p.start_time_seconds == rec.plan_start_time_seconds)
+        {
+            Some(idx) => idx,
+            None => {
+                plans.push(Plan {
+                    start_time_seconds: rec.plan_start_time_seconds,
+                    stages: Vec::new(),
+                    offset_seconds: rec.offset_seconds,
+                });
+                plans.len() - 1
+            }
+        };
+        let plan = &mut plans[plan_idx];
+        if plan.offset_seconds != rec.offset_seconds {
+            bail!(
+                "signal {} has different offsets for the plan starting at {}s",
+                rec.intersection_osm_node_id,
+                rec.plan_start_time_seconds
+            );
+        }
+
+        if rec.stage == 0 || rec.stage > plan.stages.len() + 1 {
+            bail!(
+                "signal {} is missing stages before stage {}",
+                rec.intersection_osm_node_id,
+                rec.stage
+            );
+        }
+        if rec.stage == plan.stages.len() + 1 {
+            plan.stages.push(Stage {
+                protected_turns: BTreeSet::new(),
+                permitted_turns: BTreeSet::new(),
+                stage_type: stage_type.clone(),
+            });
+        }
+        let stage = &mut plan.stages[rec.stage - 1];
+        if stage.stage_type != stage_type {
+            bail!(
+                "signal {} has different timing for stage {}",
+                rec.intersection_osm_node_id,
+                rec.stage
+            );
+        }
+        match rec.priority.as_str() {
+            "protected" => {
+                stage.protected_turns.insert(turn);
+            }
+            "permitted" => {
+                stage.permitted_turns.insert(turn);
+            }
+            x => bail!("unknown priority {}", x),
+        }
+    }
+
+    for ts in &mut signals {
+        ts.plans.sort_by_key(|p| p.start_time_seconds);
+    }
+    Ok(signals)
+}
+
+#[derive(Serialize, Deserialize)]
+struct CsvRecord {
+    intersection_osm_node_id: i64,
+    plan_start_time_seconds: usize,
+    offset_seconds: usize,
+    stage: usize,
+    duration_seconds: usize,
+    delay_seconds: usize,
+    additional_seconds: usize,
+    priority: String,
+    from_osm_way_id: i64,
+    from_osm_node1: i64,
+    from_osm_node2: i64,
+    from_is_forwards: bool,
+    to_osm_way_id: i64,
+    to_osm_node1: i64,
+    to_osm_node2: i64,
+    to_is_forwards: bool,
+    turn_intersection_osm_node_id: i64,
+    is_crosswalk: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_round_trip() {
+        let turn = |from_way, to_way, is_crosswalk| Turn {
+            from: DirectedRoad {
+                osm_way_id: from_way,
+                osm_node1: 1,
+                osm_node2: 2,
+                is_forwards: true,
+            },
+            to: DirectedRoad {
+                osm_way_id: to_way,
+                osm_node1: 2,
+                osm_node2: 3,
+                is_forwards: false,
+            },
+            intersection_osm_node_id: 2,
+            is_crosswalk,
+        };
+        let signals = vec![TrafficSignal {
+            intersection_osm_node_id: 2,
+            plans: vec![
+                Plan {
+                    start_time_seconds: 0,
+                    stages: vec![
+                        Stage {
+                            protected_turns: vec![turn(10, 11, false)].into_iter().collect(),
+                            permitted_turns: vec![turn(10, 12, false)].into_iter().collect(),
+                            stage_type: StageType::Fixed(30),
+                        },
+                        Stage {
+                            protected_turns: vec![turn(11, 11, true)].into_iter().collect(),
+                            permitted_turns: BTreeSet::new(),
+                            stage_type: StageType::Variable(10, 5, 20),
+                        },
+                    ],
+                    offset_seconds: 0,
+                },
+                Plan {
+                    start_time_seconds: 3600 * 7,
+                    stages: vec![Stage {
+                        protected_turns: vec![turn(10, 11, false)].into_iter().collect(),
+                        permitted_turns: BTreeSet::new(),
+                        stage_type: StageType::Fixed(45),
+                    }],
+                    offset_seconds: 12,
+                },
+            ],
+        }];
+        let csv = to_csv(&signals).unwrap();
+        assert_eq!(from_csv(csv.as_bytes()).unwrap(), signals);
+    }
+}
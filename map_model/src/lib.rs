@@ -28,7 +28,7 @@ extern crate anyhow;
 #[macro_use]
 extern crate log;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, RwLock};
 
 use popgetter::CensusZone;
@@ -36,7 +36,8 @@ use serde::{Deserialize, Serialize};
 
 use abstio::MapName;
 use abstutil::{
-    deserialize_btreemap, deserialize_multimap, serialize_btreemap, serialize_multimap, MultiMap,
+    deserialize_btreemap, deserialize_multimap, serialize_btreemap, serialize_multimap,
+    FormatVersion, MultiMap,
 };
 use geom::{Bounds, FindClosest, GPSBounds, Polygon};
 pub use osm2streets::{
@@ -44,12 +45,13 @@ pub use osm2streets::{
     LaneType, MapConfig, NamePerLanguage, RestrictionType, NORMAL_LANE_THICKNESS,
     SIDEWALK_THICKNESS,
 };
-pub use raw_map::{Amenity, AmenityType, AreaType, CrossingType, ExtraPOI, ExtraPOIType};
+pub use raw_map::{Amenity, AmenityType, AreaType, CrossingType, ExtraPOI, ExtraPOIType, LandUse};
 
 pub use crate::city::City;
 pub use crate::edits::{
-    EditCmd, EditEffects, EditIntersection, EditIntersectionControl, EditRoad, MapEdits,
-    PermanentMapEdits,
+    perma_traffic_signal, EditCmd, EditEffects, EditIntersection, EditIntersectionControl,
+    EditRoad, MapEdits, MigrationReport, NewBarrier, OsmChanges, PermanentEditCmd,
+    PermanentMapEdits, RemovedBarrier,
 };
 
 pub use crate::make::RawToMapOptions;
@@ -61,11 +63,17 @@ pub use crate::objects::modal_filter::{DiagonalFilter, FilterType, RoadFilter};
 pub use crate::objects::movement::{CompressedMovementID, Movement, MovementID};
 pub use crate::objects::parking_lot::{ParkingLot, ParkingLotID};
 pub use crate::objects::road::{
-    Crossing, DirectedRoadID, OriginalRoad, Road, RoadID, RoadSideID, SideOfRoad,
+    shared_space_speed_limit, AccessibilityBarrier, BusPriority, Crossing, CurbUse, DirectedRoadID,
+    LevelOfTrafficStress, OriginalRoad, ParkingRestriction, Road, RoadID, RoadSideID, SideOfRoad,
+    MAX_CROSSWALK_SETBACK, MAX_WHEELCHAIR_INCLINE,
 };
+pub use crate::objects::roundabout::{Roundabout, RoundaboutID};
 pub use crate::objects::stop_signs::{ControlStopSign, RoadWithStopSign};
 pub use crate::objects::traffic_signals::{ControlTrafficSignal, Stage, StageType};
-pub use crate::objects::transit::{TransitRoute, TransitRouteID, TransitStop, TransitStopID};
+pub use crate::objects::transit::{
+    describe_cents, FareStructure, TransitFare, TransitRoute, TransitRouteID, TransitStop,
+    TransitStopID,
+};
 pub use crate::objects::turn::{Turn, TurnID, TurnPriority, TurnType};
 pub use crate::objects::zone::{AccessRestrictions, Zone};
 pub use crate::pathfind::uber_turns::{IntersectionCluster, UberTurn};
@@ -77,23 +85,33 @@ pub use crate::traversable::{Position, Traversable, MAX_BIKE_SPEED, MAX_WALKING_
 pub use map::turn_type_from_angles;
 
 mod city;
+pub mod concave_hull;
 pub mod connectivity;
 mod edits;
 mod make;
 mod map;
 mod objects;
 mod pathfind;
+pub mod polygon_ops;
 mod traversable;
 
+/// Bump this whenever the binary layout of `Map` or anything inside it changes, like adding a field
+/// to `Road`. Maps built with a different version can't be loaded, and must be imported again.
+pub const MAP_FORMAT_VERSION: u32 = 3;
+
 // The map used by the simulation and UI. This struct is declared here so that the rest of the
 // crate can reach into private fields.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Map {
+    /// Must come first
+    format_version: FormatVersion<MAP_FORMAT_VERSION>,
     roads: Vec<Road>,
     intersections: Vec<Intersection>,
     #[serde(skip_serializing, skip_deserializing)]
     intersection_quad_tree: Arc<RwLock<Option<FindClosest<IntersectionID>>>>,
     buildings: Vec<Building>,
+    #[serde(skip_serializing, skip_deserializing)]
+    building_quad_tree: Arc<RwLock<Option<FindClosest<BuildingID>>>>,
     #[serde(
         serialize_with = "serialize_btreemap",
         deserialize_with = "deserialize_btreemap"
@@ -134,4 +152,8 @@ pub struct Map {
     edits_generation: usize,
     #[serde(skip_serializing, skip_deserializing)]
     road_to_buildings: MultiMap<RoadID, BuildingID>,
+    #[serde(skip_serializing, skip_deserializing)]
+    roundabouts: Vec<Roundabout>,
+    #[serde(skip_serializing, skip_deserializing)]
+    roundabout_per_intersection: HashMap<IntersectionID, RoundaboutID>,
 }
@@ -3,31 +3,60 @@ use geom::{Distance, FindClosest};
 
 use crate::{Road, RoadID};
 
-/// Look for roads underneath bridges, then lower their z-order. OSM tags bridges and tunnels, but
-/// not the roads that pass under bridges.
+/// Fix up the z-order of bridges, tunnels, and the roads crossing them. OSM tags bridges and
+/// tunnels, but usually omits `layer` for them, and never tags the roads passing under bridges or
+/// over tunnels.
 pub fn find_bridges(roads: &mut Vec<Road>, timer: &mut Timer) {
+    // A bridge or tunnel without an explicit layer is implicitly one level up or down
+    for r in roads.iter_mut() {
+        if r.zorder == 0 && !r.osm_tags.contains_key("layer") {
+            if r.is_bridge() {
+                r.zorder = 1;
+            } else if r.is_tunnel() {
+                r.zorder = -1;
+            }
+        }
+    }
+
     let mut closest: FindClosest<RoadID> = FindClosest::new();
     let mut bridges = Vec::new();
+    let mut tunnels = Vec::new();
     for r in roads.iter() {
         closest.add(r.id, r.center_pts.points());
-        if r.osm_tags.contains_key("bridge") {
+        if r.is_bridge() {
             bridges.push(r.id);
+        } else if r.is_tunnel() {
+            tunnels.push(r.id);
         }
     }
 
-    timer.start_iter("find roads underneath bridge", bridges.len());
-    for bridge in bridges {
+    timer.start_iter(
+        "find roads crossing bridges and tunnels",
+        bridges.len() + tunnels.len(),
+    );
+    for (id, is_bridge) in bridges
+        .into_iter()
+        .map(|r| (r, true))
+        .chain(tunnels.into_iter().map(|r| (r, false)))
+    {
         timer.next();
-        let bridge_pts = roads[bridge.0].center_pts.clone();
-        for (r, _, _) in closest.all_close_pts(bridge_pts.middle(), Distance::meters(500.0)) {
-            #[allow(clippy::collapsible_if)]
-            if bridge != r
-                && bridge_pts != roads[r.0].center_pts
-                && bridge_pts.intersection(&roads[r.0].center_pts).is_some()
+        let pts = roads[id.0].center_pts.clone();
+        let zorder = roads[id.0].zorder;
+        for (r, _, _) in closest.all_close_pts(pts.middle(), Distance::meters(500.0)) {
+            let other = &roads[r.0];
+            if id == r
+                || other.is_bridge()
+                || other.is_tunnel()
+                || pts == other.center_pts
+                || pts.intersection(&other.center_pts).is_none()
             {
-                if roads[r.0].zorder == 0 {
-                    roads[r.0].zorder = -1;
-                }
+                continue;
+            }
+            // If the layers are explicitly tagged wrong, move the other road out of the way
+            if is_bridge && other.zorder >= zorder {
+                roads[r.0].zorder = zorder - 1;
+            } else if !is_bridge && other.zorder <= zorder {
+                roads[r.0].zorder = zorder + 1;
             }
         }
     }
@@ -4,9 +4,10 @@ use rand::{Rng, SeedableRng};
 use rand_xorshift::XorShiftRng;
 
 use abstutil::{Tags, Timer};
-use geom::{Distance, HashablePt2D, Line};
-use raw_map::RawBuilding;
+use geom::{Distance, HashablePt2D, Line, Polygon};
+use raw_map::{LandUse, RawBuilding};
 
+use crate::make::land_use::infer_land_use;
 use crate::make::{match_points_to_lanes, trim_path};
 use crate::{
     osm, Amenity, Building, BuildingID, BuildingType, LaneID, Map, NamePerLanguage,
@@ -16,6 +17,7 @@ use crate::{
 /// Finalize importing of buildings, mostly by matching them to the nearest sidewalk.
 pub fn make_all_buildings(
     input: &BTreeMap<osm::OsmID, RawBuilding>,
+    landuse: &[(Polygon, LandUse)],
     map: &Map,
     keep_bldg_tags: bool,
     timer: &mut Timer,
@@ -43,6 +45,8 @@ pub fn make_all_buildings(
         timer,
     );
 
+    let inferred_land_use = infer_land_use(input, landuse, timer);
+
     let mut results = Vec::new();
     timer.start_iter("match buildings to sidewalks", center_per_bldg.len());
     for (orig_id, bldg_center) in center_per_bldg {
@@ -69,6 +73,7 @@ pub fn make_all_buildings(
                 .get("building:levels")
                 .and_then(|x| x.parse::<f64>().ok())
                 .unwrap_or(1.0);
+            let inferred = inferred_land_use.get(&orig_id).cloned();
 
             results.push(Building {
                 id,
@@ -95,8 +100,10 @@ pub fn make_all_buildings(
                     &b.amenities,
                     levels,
                     b.polygon.area(),
+                    inferred,
                     &mut rng,
                 ),
+                inferred_land_use: inferred,
                 parking: if let Some(n) = b.public_garage_name.clone() {
                     OffstreetParking::PublicGarage(n, b.num_parking_spots)
                 } else {
@@ -144,6 +151,7 @@ fn classify_bldg(
     amenities: &[Amenity],
     levels: f64,
     ground_area_sq_meters: f64,
+    inferred: Option<LandUse>,
     rng: &mut XorShiftRng,
 ) -> BuildingType {
     // used: top values from https://taginfo.openstreetmap.org/keys/building#values (>100k uses)
@@ -222,7 +230,23 @@ fn classify_bldg(
         //  - regional/cultural norms
         residents = (area_sq_meters / 10.0) as usize;
     } else {
-        residents = rng.gen_range(0..2);
+        match inferred {
+            // Workplaces per square meter are just as rough as above. Warehouses and factories
+            // have far fewer workers than offices.
+            Some(LandUse::Commercial) => {
+                return BuildingType::Commercial((area_sq_meters / 10.0) as usize);
+            }
+            Some(LandUse::Industrial) => {
+                return BuildingType::Commercial((area_sq_meters / 50.0) as usize);
+            }
+            // A large residential building is probably an apartment block
+            Some(LandUse::Residential) if ground_area_sq_meters >= 300.0 => {
+                residents = (area_sq_meters / 10.0) as usize;
+            }
+            _ => {
+                residents = rng.gen_range(0..2);
+            }
+        }
     }
 
     if commercial && workers == 0 {
@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+
+use abstutil::Timer;
+use geom::{Bounds, Distance, FindClosest, Polygon};
+use raw_map::{LandUse, RawBuilding};
+
+use crate::osm;
+
+/// Count the amenities within this distance of a building
+const POI_RADIUS: Distance = Distance::const_meters(100.0);
+/// With at least this many amenities nearby, an untagged building is probably commercial too
+const COMMERCIAL_NUM_POIS: usize = 5;
+/// Untagged buildings with a footprint this large and few amenities nearby are usually
+/// warehouses or factories
+const INDUSTRIAL_FOOTPRINT_SQ_METERS: f64 = 2000.0;
+
+/// Many buildings in OSM are just tagged `building=yes`, with no amenities. Guess what they're used
+/// for, so that trips aren't only attracted to the few buildings that are tagged. In order, the
+/// evidence used is:
+///
+/// 1) a containing residential, commercial, or industrial landuse area
+/// 2) many amenities in nearby buildings
+/// 3) the footprint size
+pub fn infer_land_use(
+    input: &BTreeMap<osm::OsmID, RawBuilding>,
+    landuse: &[(Polygon, LandUse)],
+    timer: &mut Timer,
+) -> BTreeMap<osm::OsmID, LandUse> {
+    timer.start("infer land use of untagged buildings");
+    // Landuse polygons can be huge and detailed, so check the bounding box first
+    let landuse: Vec<(Bounds, &Polygon, LandUse)> = landuse
+        .iter()
+        .map(|(polygon, lu)| (polygon.get_bounds(), polygon, *lu))
+        .collect();
+
+    let mut pois: FindClosest<osm::OsmID> = FindClosest::new();
+    for (id, b) in input {
+        if !b.amenities.is_empty() {
+            pois.add(*id, &[b.polygon.center()]);
+        }
+    }
+
+    let mut results = BTreeMap::new();
+    timer.start_iter("classify untagged buildings", input.len());
+    for (id, b) in input {
+        timer.next();
+        if !b.osm_tags.is("building", "yes")
+            || b.osm_tags.contains_key("building:use")
+            || !b.amenities.is_empty()
+        {
+            continue;
+        }
+
+        let center = b.polygon.center();
+        if let Some((_, _, lu)) = landuse
+            .iter()
+            .find(|(bounds, polygon, _)| bounds.contains(center) && polygon.contains_pt(center))
+        {
+            results.insert(*id, *lu);
+            continue;
+        }
+
+        let num_pois: usize = pois
+            .all_close_pts(center, POI_RADIUS)
+            .into_iter()
+            .map(|(other, _, _)| input[&other].amenities.len())
+            .sum();
+        let lu = if num_pois >= COMMERCIAL_NUM_POIS {
+            LandUse::Commercial
+        } else if b.polygon.area() >= INDUSTRIAL_FOOTPRINT_SQ_METERS {
+            LandUse::Industrial
+        } else {
+            LandUse::Residential
+        };
+        results.insert(*id, lu);
+    }
+    timer.stop("infer land use of untagged buildings");
+    results
+}
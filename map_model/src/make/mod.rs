@@ -6,7 +6,7 @@ use std::sync::{Arc, RwLock};
 
 use structopt::StructOpt;
 
-use abstutil::{MultiMap, Tags, Timer};
+use abstutil::{FormatVersion, MultiMap, Tags, Timer};
 use geom::{
     Distance, FindClosest, HashablePt2D, Line, PolyLine, Polygon, Pt2D, Speed, EPSILON_DIST,
 };
@@ -23,7 +23,9 @@ use crate::{
 
 mod bridges;
 mod buildings;
+mod land_use;
 mod parking_lots;
+mod smoothing;
 pub mod traffic_signals;
 pub mod transit;
 pub mod turns;
@@ -39,6 +41,10 @@ pub struct RawToMapOptions {
     /// Preserve all OSM tags for buildings, increasing the final file size substantially.
     #[structopt(long)]
     pub keep_bldg_tags: bool,
+    /// Fit curves to gentle bends in road geometry, instead of keeping the straight segments
+    /// between OSM nodes. This makes curved roads and the turns onto them look more natural.
+    #[structopt(long)]
+    pub smooth_curves: bool,
 }
 
 impl Map {
@@ -47,9 +53,11 @@ impl Map {
             .apply_transformations(Transformation::abstreet(), timer);
 
         let mut map = Map {
+            format_version: FormatVersion,
             roads: Vec::new(),
             intersections: Vec::new(),
             intersection_quad_tree: Arc::new(RwLock::new(None)),
+            building_quad_tree: Arc::new(RwLock::new(None)),
             buildings: Vec::new(),
             transit_stops: BTreeMap::new(),
             transit_routes: Vec::new(),
@@ -72,6 +80,8 @@ impl Map {
             edits: MapEdits::new(),
             edits_generation: 0,
             road_to_buildings: MultiMap::new(),
+            roundabouts: Vec::new(),
+            roundabout_per_intersection: HashMap::new(),
         };
         map.edits = map.new_edits();
 
@@ -124,10 +134,30 @@ impl Map {
             let i1 = intersection_id_mapping[&r.src_i];
             let i2 = intersection_id_mapping[&r.dst_i];
 
+            let mut untrimmed_center_pts =
+                r.get_untrimmed_center_line(raw.streets.config.driving_side);
+            let mut center_pts = r.center_line.clone();
+            if opts.smooth_curves {
+                untrimmed_center_pts =
+                    smoothing::smooth_curves(&untrimmed_center_pts, r.trim_start, r.trim_end);
+                // Smoothing leaves the trimmed ends alone, so the road still meets the
+                // intersection polygons exactly
+                if let Some(pl) = osm2streets::Road::trim_polyline_both_ends(
+                    untrimmed_center_pts.clone(),
+                    r.trim_start,
+                    r.trim_end,
+                ) {
+                    center_pts = pl;
+                } else {
+                    untrimmed_center_pts =
+                        r.get_untrimmed_center_line(raw.streets.config.driving_side);
+                }
+            }
+
             let extra = &raw.extra_road_data[&r.id];
-            let barrier_nodes = snap_nodes_to_line(&extra.barrier_nodes, &r.center_line);
-            let crossing_nodes =
-                snap_nodes_with_data_to_line(&extra.crossing_nodes, &r.center_line);
+            let barrier_nodes = snap_nodes_to_line(&extra.barrier_nodes, &center_pts);
+            let crossing_nodes = snap_nodes_with_data_to_line(&extra.crossing_nodes, &center_pts);
+            let raised_kerbs = snap_nodes_to_line(&extra.raised_kerbs, &center_pts);
 
             // TODO Hack. Roads and intersections each may have ZERO or more OSM IDs.
             let orig_id = OriginalRoad {
@@ -177,8 +207,8 @@ impl Map {
                     })
                     .collect(),
                 lanes: Vec::new(),
-                center_pts: r.center_line.clone(),
-                untrimmed_center_pts: r.get_untrimmed_center_line(raw.streets.config.driving_side),
+                center_pts,
+                untrimmed_center_pts,
                 trim_start: r.trim_start,
                 trim_end: r.trim_end,
                 src_i: i1,
@@ -193,10 +223,20 @@ impl Map {
                 modal_filter: None,
                 barrier_nodes,
                 crossing_nodes,
+                raised_kerbs,
                 crossings: Vec::new(),
+                parking_restrictions: Vec::new(),
+                bus_priority: Vec::new(),
+                shared_space: false,
             };
+            if extra.private_gate && !road.osm_tags.contains_key("motor_vehicle") {
+                // A gate anywhere along the road keeps through traffic off all of it
+                road.osm_tags.insert("motor_vehicle", "private");
+            }
             road.speed_limit = road.speed_limit_from_osm();
             road.access_restrictions = road.access_restrictions_from_osm();
+            road.shared_space = road.shared_space_from_osm();
+            road.crossings = road.crossings_from_osm();
 
             road.recreate_lanes(r.lane_specs_ltr.clone());
             for lane in &road.lanes {
@@ -242,6 +282,9 @@ impl Map {
             }
         }
 
+        // Turns depend on the layers of roads
+        bridges::find_bridges(&mut map.roads, timer);
+
         let mut all_turns = Vec::new();
         let mut connectivity_problems = 0;
         for i in &map.intersections {
@@ -280,8 +323,13 @@ impl Map {
         }
         timer.stop("find blackholes");
 
-        map.buildings =
-            buildings::make_all_buildings(&raw.buildings, &map, opts.keep_bldg_tags, timer);
+        map.buildings = buildings::make_all_buildings(
+            &raw.buildings,
+            &raw.landuse,
+            &map,
+            opts.keep_bldg_tags,
+            timer,
+        );
 
         map.parking_lots = parking_lots::make_all_parking_lots(
             &raw.parking_lots,
@@ -302,9 +350,9 @@ impl Map {
             });
         }
 
-        bridges::find_bridges(&mut map.roads, timer);
-
         map.recalculate_all_movements(timer);
+        // Stop signs depend on these
+        map.recalculate_roundabouts();
 
         let mut stop_signs: BTreeMap<IntersectionID, ControlStopSign> = BTreeMap::new();
         let mut traffic_signals: BTreeMap<IntersectionID, ControlTrafficSignal> = BTreeMap::new();
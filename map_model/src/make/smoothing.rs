@@ -0,0 +1,110 @@
+use lyon::geom::{Point, QuadraticBezierSegment};
+
+use geom::{Distance, PolyLine, Pt2D};
+
+/// Bends sharper than this are probably real corners, not a curve drawn with a few OSM nodes, so
+/// they're left alone.
+const MAX_DEFLECTION_DEGREES: f64 = 60.0;
+/// Bends gentler than this aren't worth adding points for.
+const MIN_DEFLECTION_DEGREES: f64 = 1.0;
+/// The most a corner can be cut back along each segment to fit a curve
+const MAX_CUTBACK: Distance = Distance::const_meters(30.0);
+/// Roughly how many degrees of turning each piece of a curve covers
+const DEGREES_PER_PIECE: f64 = 5.0;
+
+/// OSM ways approximate curves with a handful of nodes, so imported roads look like obvious
+/// polylines. This replaces each gentle bend with a quadratic bezier curve, using the original
+/// vertex as the control point. Each curve only uses up to half of the segments on either side,
+/// so neighboring curves never overlap, and the first and last segments keep their direction.
+///
+/// Nothing within `keep_start` of the start or `keep_end` of the end changes, so trimming the
+/// result by those distances lands on the same points as trimming the original.
+pub fn smooth_curves(pl: &PolyLine, keep_start: Distance, keep_end: Distance) -> PolyLine {
+    let pts = pl.points();
+    if pts.len() < 3 {
+        return pl.clone();
+    }
+    let keep_end = pl.length() - keep_end;
+
+    let mut result = vec![pts[0]];
+    let mut dist_so_far = pts[0].dist_to(pts[1]);
+    for idx in 1..pts.len() - 1 {
+        let (prev, pt, next) = (pts[idx - 1], pts[idx], pts[idx + 1]);
+        let len_before = prev.dist_to(pt);
+        let len_after = pt.dist_to(next);
+        let cutback = (len_before / 2.0)
+            .min(len_after / 2.0)
+            .min(dist_so_far - keep_start)
+            .min(keep_end - dist_so_far)
+            .min(MAX_CUTBACK);
+        dist_so_far += len_after;
+
+        let deflection = prev
+            .angle_to(pt)
+            .simple_shortest_rotation_towards(pt.angle_to(next))
+            .abs();
+        if cutback <= geom::EPSILON_DIST
+            || !(MIN_DEFLECTION_DEGREES..=MAX_DEFLECTION_DEGREES).contains(&deflection)
+        {
+            result.push(pt);
+            continue;
+        }
+
+        let curve = QuadraticBezierSegment {
+            from: to_pt(pt.project_away(cutback, pt.angle_to(prev))),
+            ctrl: to_pt(pt),
+            to: to_pt(pt.project_away(cutback, pt.angle_to(next))),
+        };
+        let pieces = (deflection / DEGREES_PER_PIECE).ceil().max(2.0) as usize;
+        for i in 0..=pieces {
+            result.push(from_pt(curve.sample((i as f64) / (pieces as f64))));
+        }
+    }
+    result.push(*pts.last().unwrap());
+
+    // If something degenerate happened, just keep the original
+    PolyLine::deduping_new(result).unwrap_or_else(|_| pl.clone())
+}
+
+fn to_pt(pt: Pt2D) -> Point<f64> {
+    Point::new(pt.x(), pt.y())
+}
+
+fn from_pt(pt: Point<f64>) -> Pt2D {
+    Pt2D::new(pt.x, pt.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use geom::{Distance, PolyLine, Pt2D};
+
+    use super::smooth_curves;
+
+    #[test]
+    fn test_smooth_curves() {
+        let gentle = PolyLine::must_new(vec![
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(100.0, 0.0),
+            Pt2D::new(200.0, 30.0),
+        ]);
+        let smoothed = smooth_curves(&gentle, Distance::meters(10.0), Distance::meters(10.0));
+        assert!(smoothed.points().len() > 3);
+        assert_eq!(smoothed.first_pt(), gentle.first_pt());
+        assert_eq!(smoothed.last_pt(), gentle.last_pt());
+        // Cutting the corner makes the road shorter
+        assert!(smoothed.length() < gentle.length());
+
+        // A right angle is a real corner
+        let corner = PolyLine::must_new(vec![
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(100.0, 0.0),
+            Pt2D::new(100.0, 100.0),
+        ]);
+        let smoothed = smooth_curves(&corner, Distance::ZERO, Distance::ZERO);
+        assert_eq!(smoothed.points().len(), 3);
+
+        // The bend is inside the part that'll be trimmed off
+        let smoothed = smooth_curves(&gentle, Distance::meters(120.0), Distance::ZERO);
+        assert_eq!(smoothed.points().len(), 3);
+    }
+}
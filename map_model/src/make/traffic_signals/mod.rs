@@ -24,7 +24,11 @@ pub fn get_possible_policies(map: &Map, id: IntersectionID) -> Vec<(String, Cont
     let i = map.get_i(id);
 
     // As long as we're using silly heuristics for these by default, prefer shorter cycle
-    // length.
+    // length. But when left turns have their own lane, give them a protected stage; there's room
+    // for them to wait without blocking through traffic.
+    if let Some(ts) = four_way_protected_turn_lanes(map, i) {
+        results.push(("two-stage with protected turn lanes".to_string(), ts));
+    }
     if let Some(ts) = four_way_two_stage(map, i) {
         results.push(("two-stage".to_string(), ts));
     }
@@ -262,6 +266,77 @@ fn four_way_two_stage(map: &Map, i: &Intersection) -> Option<ControlTrafficSigna
     Some(ts)
 }
 
+/// Like `four_way_two_stage`, but roads with a dedicated left turn lane get a leading protected
+/// left before the rest of their movements. Returns `None` if no road has one.
+fn four_way_protected_turn_lanes(map: &Map, i: &Intersection) -> Option<ControlTrafficSignal> {
+    let roads = i.get_sorted_incoming_roads(map);
+    if roads.len() != 4 {
+        return None;
+    }
+
+    // make_stages flips left and right when driving on the left, but we need to look for the real
+    // turn type here.
+    let hard_turn = if map.config.driving_side == DrivingSide::Right {
+        TurnType::Left
+    } else {
+        TurnType::Right
+    };
+    let with_turn_lane: Vec<RoadID> = roads
+        .iter()
+        .filter(|r| {
+            i.incoming_lanes.iter().any(|l| {
+                let lane = map.get_l(*l);
+                lane.id.road == **r && lane.dedicated_turn_type(map) == Some(hard_turn)
+            })
+        })
+        .cloned()
+        .collect();
+    if with_turn_lane.is_empty() {
+        return None;
+    }
+
+    // Just to refer to these easily, label with directions. Imagine an axis-aligned four-way.
+    let (north, west, south, east) = (roads[0], roads[1], roads[2], roads[3]);
+    let ns_turn_lanes: Vec<RoadID> = vec![north, south]
+        .into_iter()
+        .filter(|r| with_turn_lane.contains(r))
+        .collect();
+    let ew_turn_lanes: Vec<RoadID> = vec![east, west]
+        .into_iter()
+        .filter(|r| with_turn_lane.contains(r))
+        .collect();
+
+    let mut stage_specs = Vec::new();
+    if !ns_turn_lanes.is_empty() {
+        stage_specs.push(vec![
+            (ns_turn_lanes.clone(), TurnType::Left, PROTECTED),
+            (ns_turn_lanes, TurnType::Right, YIELD),
+        ]);
+    }
+    stage_specs.push(vec![
+        (vec![north, south], TurnType::Straight, PROTECTED),
+        (vec![north, south], TurnType::Right, YIELD),
+        (vec![north, south], TurnType::Left, YIELD),
+        (vec![east, west], TurnType::Right, YIELD),
+    ]);
+    if !ew_turn_lanes.is_empty() {
+        stage_specs.push(vec![
+            (ew_turn_lanes.clone(), TurnType::Left, PROTECTED),
+            (ew_turn_lanes, TurnType::Right, YIELD),
+        ]);
+    }
+    stage_specs.push(vec![
+        (vec![east, west], TurnType::Straight, PROTECTED),
+        (vec![east, west], TurnType::Right, YIELD),
+        (vec![east, west], TurnType::Left, YIELD),
+        (vec![north, south], TurnType::Right, YIELD),
+    ]);
+
+    let mut ts = new(i.id);
+    make_stages(&mut ts, &map.config, i, stage_specs);
+    Some(ts)
+}
+
 fn all_walk_all_yield(i: &Intersection) -> ControlTrafficSignal {
     let mut ts = new(i.id);
 
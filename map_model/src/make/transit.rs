@@ -8,7 +8,7 @@ use raw_map::{RawMap, RawTransitRoute, RawTransitStop, RawTransitType};
 
 use crate::make::match_points_to_lanes;
 use crate::{
-    LaneID, Map, PathConstraints, Position, TransitRoute, TransitRouteID, TransitStop,
+    LaneID, Map, PathConstraints, Position, TransitFare, TransitRoute, TransitRouteID, TransitStop,
     TransitStopID,
 };
 
@@ -219,10 +219,14 @@ fn create_route(
         }
     };
 
-    // TODO This'll come from the RawTransitRoute eventually. For now, every 30 minutes.
-    let spawn_times: Vec<Time> = (0..48)
-        .map(|i| Time::START_OF_DAY + (i as f64) * Duration::minutes(30))
-        .collect();
+    // If GTFS didn't describe the schedule, just run every 30 minutes.
+    let spawn_times: Vec<Time> = if route.spawn_times.is_empty() {
+        (0..48)
+            .map(|i| Time::START_OF_DAY + (i as f64) * Duration::minutes(30))
+            .collect()
+    } else {
+        route.spawn_times.clone()
+    };
 
     let result = TransitRoute {
         id: TransitRouteID(map.transit_routes.len()),
@@ -238,6 +242,9 @@ fn create_route(
         },
         spawn_times: spawn_times.clone(),
         orig_spawn_times: spawn_times,
+        // GTFS fares aren't imported yet
+        fare: TransitFare::free(),
+        orig_fare: TransitFare::free(),
     };
 
     // Check that the paths are valid
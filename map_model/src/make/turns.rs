@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 use anyhow::Result;
 use lyon::geom::{CubicBezierSegment, Point, QuadraticBezierSegment};
@@ -22,9 +22,15 @@ pub fn make_all_turns(map: &Map, i: &Intersection) -> Vec<Turn> {
     let unique_turns = ensure_unique(raw_turns);
     // Never allow turns that go against road-level turn restrictions; that upstream OSM data is
     // usually not extremely broken.
+    let through_layers = layers_passing_through(map, i);
     let all_turns: Vec<Turn> = unique_turns
         .into_iter()
         .filter(|t| t.permitted_by_road(i, map))
+        .filter(|t| {
+            let z1 = map.get_parent(t.id.src).zorder;
+            let z2 = map.get_parent(t.id.dst).zorder;
+            z1 == z2 || !(through_layers.contains(&z1) || through_layers.contains(&z2))
+        })
         .collect();
 
     // Try to use turn lane tags...
@@ -69,6 +75,24 @@ pub fn make_all_turns(map: &Map, i: &Intersection) -> Vec<Turn> {
     }
 }
 
+/// OSM sometimes connects roads on different layers at a node that's really in the middle of a
+/// bridge or tunnel. When a bridge or tunnel continues through an intersection, it's physically
+/// separated from the roads on other layers there, so turns between them shouldn't exist.
+fn layers_passing_through(map: &Map, i: &Intersection) -> BTreeSet<isize> {
+    let mut count_per_layer: BTreeMap<isize, usize> = BTreeMap::new();
+    for r in &i.roads {
+        let road = map.get_r(*r);
+        if road.is_bridge() || road.is_tunnel() {
+            *count_per_layer.entry(road.zorder).or_insert(0) += 1;
+        }
+    }
+    count_per_layer
+        .into_iter()
+        .filter(|(_, count)| *count >= 2)
+        .map(|(z, _)| z)
+        .collect()
+}
+
 fn ensure_unique(turns: Vec<Turn>) -> Vec<Turn> {
     let mut ids = HashSet::new();
     let mut keep: Vec<Turn> = Vec::new();
@@ -145,12 +169,12 @@ fn make_vehicle_turns(i: &Intersection, map: &Map) -> Vec<Turn> {
     let is_deadend = i.is_deadend_for_driving(map);
     for src in &i.incoming_lanes {
         let src = map.get_l(*src);
-        if !src.lane_type.is_for_moving_vehicles() {
+        if !src.may_carry_traffic(map) {
             continue;
         }
         for dst in &i.outgoing_lanes {
             let dst = map.get_l(*dst);
-            if !dst.lane_type.is_for_moving_vehicles() {
+            if !dst.may_carry_traffic(map) {
                 continue;
             }
             // Only allow U-turns at deadends
@@ -139,8 +139,8 @@ pub fn make_walking_turns(map: &Map, i: &Intersection) -> Vec<Turn> {
 /// Filter out crosswalks on really short roads. In reality, these roads are usually located within
 /// an intersection, which isn't a valid place for a pedestrian crossing.
 ///
-/// And if the road is marked as having no crosswalks at an end, downgrade them to unmarked
-/// crossings.
+/// And if the road is marked as having no crosswalks at an end (and no crossing has been added
+/// there), downgrade them to unmarked crossings.
 pub fn filter_turns(mut input: Vec<Turn>, map: &Map, i: &Intersection) -> Vec<Turn> {
     for r in &i.roads {
         if map.get_r(*r).is_extremely_short() {
@@ -153,11 +153,12 @@ pub fn filter_turns(mut input: Vec<Turn>, map: &Map, i: &Intersection) -> Vec<Tu
     for turn in &mut input {
         if let Some(dr) = turn.crosswalk_over_road(map) {
             let road = map.get_r(dr.road);
+            // A crossing added by map edits near this end also counts
             let keep = if dr.dir == Direction::Fwd {
                 road.crosswalk_forward
             } else {
                 road.crosswalk_backward
-            };
+            } || road.has_crossing_near(i.id);
             if !keep {
                 turn.turn_type = TurnType::UnmarkedCrossing;
             }
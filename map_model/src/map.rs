@@ -10,7 +10,7 @@ use petgraph::graphmap::{DiGraphMap, UnGraphMap};
 use popgetter::CensusZone;
 
 use abstio::{CityName, MapName};
-use abstutil::{prettyprint_usize, serialized_size_bytes, MultiMap, Tags, Timer};
+use abstutil::{FormatVersion, MemoryReport, MultiMap, Tags, Timer};
 use geom::{
     Angle, Bounds, Distance, Duration, FindClosest, GPSBounds, LonLat, PolyLine, Polygon, Pt2D,
     Ring, Time,
@@ -23,7 +23,7 @@ use crate::{
     DrivingSide, ExtraPOI, Intersection, IntersectionControl, IntersectionID, IntersectionKind,
     Lane, LaneID, LaneType, Map, MapConfig, MapEdits, Movement, MovementID, OffstreetParking,
     OriginalRoad, ParkingLot, ParkingLotID, Path, PathConstraints, PathRequest, PathV2, Pathfinder,
-    PathfinderCaching, Position, Road, RoadFilter, RoadID, RoutingParams, TransitRoute,
+    PathfinderCaching, Position, Road, RoadFilter, RoadID, Roundabout, RoutingParams, TransitRoute,
     TransitRouteID, TransitStop, TransitStopID, Turn, TurnID, TurnType, Zone,
 };
 
@@ -74,79 +74,64 @@ impl Map {
 
         self.edits = self.new_edits();
         self.recalculate_road_to_buildings();
+        self.recalculate_roundabouts();
         self.recalculate_all_movements(timer);
 
         // Enable to work on shrinking map file sizes. Never run this on the web though --
         // trying to serialize fast_paths in wasm melts the browser, because the usize<->u32
         // translation there isn't meant to run on wasm.
         if cfg!(not(target_arch = "wasm32")) && false {
-            let mut costs = vec![
-                (
-                    "roads",
-                    self.roads.len(),
-                    serialized_size_bytes(&self.roads),
-                ),
-                (
-                    "intersections",
-                    self.intersections.len(),
-                    serialized_size_bytes(&self.intersections),
-                ),
-                (
-                    "buildings",
-                    self.buildings.len(),
-                    serialized_size_bytes(&self.buildings),
-                ),
-                (
-                    "areas",
-                    self.areas.len(),
-                    serialized_size_bytes(&self.areas),
-                ),
-                (
-                    "parking lots",
-                    self.parking_lots.len(),
-                    serialized_size_bytes(&self.parking_lots),
-                ),
-                (
-                    "zones",
-                    self.zones.len(),
-                    serialized_size_bytes(&self.zones),
-                ),
-                (
-                    "census_zones",
-                    self.census_zones.len(),
-                    serialized_size_bytes(&self.census_zones),
-                ),
-                (
-                    "extra_pois",
-                    self.extra_pois.len(),
-                    serialized_size_bytes(&self.extra_pois),
-                ),
-                ("pathfinder", 1, serialized_size_bytes(&self.pathfinder)),
-            ];
-            costs.sort_by_key(|(_, _, bytes)| *bytes);
-            costs.reverse();
-
-            info!(
-                "Total map size: {} bytes",
-                prettyprint_usize(serialized_size_bytes(self))
-            );
-            for (name, number, bytes) in costs {
-                info!(
-                    "- {} {}: {} bytes",
-                    prettyprint_usize(number),
-                    name,
-                    prettyprint_usize(bytes)
-                );
+            for line in self.memory_report().describe() {
+                info!("{}", line);
             }
         }
     }
 
+    /// Estimates how much memory each part of the map uses. Pathfinding graphs are reported
+    /// separately from the rest of the map.
+    pub fn memory_report(&self) -> MemoryReport {
+        let mut report = MemoryReport::new();
+        report.measure("map", "roads", self.roads.len(), &self.roads);
+        report.measure(
+            "map",
+            "intersections",
+            self.intersections.len(),
+            &self.intersections,
+        );
+        report.measure("map", "buildings", self.buildings.len(), &self.buildings);
+        report.measure("map", "areas", self.areas.len(), &self.areas);
+        report.measure(
+            "map",
+            "parking lots",
+            self.parking_lots.len(),
+            &self.parking_lots,
+        );
+        report.measure("map", "zones", self.zones.len(), &self.zones);
+        report.measure(
+            "map",
+            "census zones",
+            self.census_zones.len(),
+            &self.census_zones,
+        );
+        report.measure("map", "extra POIs", self.extra_pois.len(), &self.extra_pois);
+        report.measure(
+            "map",
+            "transit routes",
+            self.transit_routes.len(),
+            &self.transit_routes,
+        );
+        self.pathfinder.measure_memory(&mut report);
+        report
+    }
+
     /// Just for temporary std::mem::replace tricks.
     pub fn blank() -> Map {
         Map {
+            format_version: FormatVersion,
             roads: Vec::new(),
             intersections: Vec::new(),
             intersection_quad_tree: Arc::new(RwLock::new(None)),
+            building_quad_tree: Arc::new(RwLock::new(None)),
             buildings: Vec::new(),
             transit_stops: BTreeMap::new(),
             transit_routes: Vec::new(),
@@ -175,6 +160,8 @@ impl Map {
             edits: MapEdits::new(),
             edits_generation: 0,
             road_to_buildings: MultiMap::new(),
+            roundabouts: Vec::new(),
+            roundabout_per_intersection: HashMap::new(),
         }
     }
 
@@ -245,6 +232,74 @@ impl Map {
         )
     }
 
+    /// A one-lane roundabout with a road entering from the west and leaving to the east, for
+    /// testing how traffic enters it.
+    pub fn tiny_roundabout() -> Self {
+        let mut raw = RawMap::blank(MapName::blank());
+
+        raw.streets.boundary_polygon = Polygon::rectangle(300.0, 300.0);
+        raw.streets
+            .gps_bounds
+            .update(LonLat::new(-122.453224, 47.723277));
+        raw.streets
+            .gps_bounds
+            .update(LonLat::new(-122.240505, 47.495342));
+
+        let mut add_intersection = |pt: Pt2D, kind: IntersectionKind| {
+            let control = if kind == IntersectionKind::MapEdge {
+                IntersectionControl::Uncontrolled
+            } else {
+                IntersectionControl::Signed
+            };
+            let id = raw
+                .streets
+                .insert_intersection(Vec::new(), pt, kind, control);
+            raw.elevation_per_intersection.insert(id, Distance::ZERO);
+            (id, pt)
+        };
+        let west = add_intersection(Pt2D::new(0.0, 150.0), IntersectionKind::MapEdge);
+        let east = add_intersection(Pt2D::new(300.0, 150.0), IntersectionKind::MapEdge);
+        let ring1 = add_intersection(Pt2D::new(100.0, 150.0), IntersectionKind::Intersection);
+        let ring2 = add_intersection(Pt2D::new(200.0, 150.0), IntersectionKind::Intersection);
+        let ring3 = add_intersection(Pt2D::new(150.0, 236.6), IntersectionKind::Intersection);
+
+        let mut street = Tags::empty();
+        street.insert("highway", "residential");
+        street.insert("lanes", "2");
+        let mut ring = Tags::empty();
+        ring.insert("highway", "residential");
+        ring.insert("junction", "roundabout");
+        ring.insert("oneway", "yes");
+        ring.insert("lanes", "1");
+
+        for (src, dst, tags) in [
+            (west, ring1, &street),
+            (ring2, east, &street),
+            (ring1, ring2, &ring),
+            (ring2, ring3, &ring),
+            (ring3, ring1, &ring),
+        ] {
+            let road_id = raw.streets.next_road_id();
+            raw.streets.insert_road(osm2streets::Road::new(
+                road_id,
+                Vec::new(),
+                src.0,
+                dst.0,
+                PolyLine::must_new(vec![src.1, dst.1]),
+                tags.clone(),
+                &raw.streets.config,
+            ));
+            raw.extra_road_data
+                .insert(road_id, raw_map::ExtraRoadData::default());
+        }
+
+        Self::create_from_raw(
+            raw,
+            crate::RawToMapOptions::default(),
+            &mut Timer::throwaway(),
+        )
+    }
+
     pub fn all_roads(&self) -> &Vec<Road> {
         &self.roads
     }
@@ -334,6 +389,17 @@ impl Map {
         self.transit_stops.get(&id)
     }
 
+    pub fn all_roundabouts(&self) -> &Vec<Roundabout> {
+        &self.roundabouts
+    }
+
+    /// If the intersection is part of a roundabout, returns the whole roundabout.
+    pub fn get_roundabout(&self, i: IntersectionID) -> Option<&Roundabout> {
+        self.roundabout_per_intersection
+            .get(&i)
+            .map(|id| &self.roundabouts[id.0])
+    }
+
     pub fn maybe_get_stop_sign(&self, id: IntersectionID) -> Option<&ControlStopSign> {
         self.stop_signs.get(&id)
     }
@@ -756,6 +822,34 @@ impl Map {
         *quad_tree = Some(quad);
     }
 
+    fn populate_building_quad_tree(&self) {
+        let mut quad_tree = self.building_quad_tree.write().unwrap();
+        if quad_tree.is_some() {
+            return;
+        }
+
+        let mut quad: FindClosest<BuildingID> = FindClosest::new();
+        for b in self.all_buildings() {
+            quad.add(b.id, &[b.label_center]);
+        }
+        *quad_tree = Some(quad);
+    }
+
+    /// Finds every building whose label center is within `radius` of a point, closest first.
+    pub fn find_buildings_near(&self, pt: Pt2D, radius: Distance) -> Vec<(BuildingID, Distance)> {
+        self.populate_building_quad_tree();
+        let quad_tree = self.building_quad_tree.read().unwrap();
+        let mut results: Vec<(BuildingID, Distance)> = quad_tree
+            .as_ref()
+            .unwrap()
+            .all_close_pts(pt, radius)
+            .into_iter()
+            .map(|(b, _, dist)| (b, dist))
+            .collect();
+        results.sort_by_key(|(b, dist)| (*dist, *b));
+        results
+    }
+
     pub fn localise_lon_lat_to_map(&self, point: LonLat) -> Pt2D {
         point.to_pt(&self.gps_bounds)
     }
@@ -969,6 +1063,15 @@ impl Map {
         self.road_to_buildings = mapping;
     }
 
+    pub(crate) fn recalculate_roundabouts(&mut self) {
+        self.roundabouts = Roundabout::find_all(self);
+        self.roundabout_per_intersection = self
+            .roundabouts
+            .iter()
+            .flat_map(|r| r.members.iter().map(move |i| (*i, r.id)))
+            .collect();
+    }
+
     pub(crate) fn recalculate_all_movements(&mut self, timer: &mut Timer) {
         let movements = timer.parallelize(
             "generate movements",
@@ -1042,6 +1145,7 @@ impl Map {
     /// Modifies the map in-place, removing buildings.
     pub fn minify_buildings(&mut self, timer: &mut Timer) {
         self.buildings.clear();
+        *self.building_quad_tree.write().unwrap() = None;
 
         // We only need the CHs for driving.
         self.pathfinder = Pathfinder::new_limited(
@@ -1086,6 +1190,163 @@ impl Map {
         geom::geometries_with_properties_to_geojson(pairs)
     }
 
+    /// Export roads, lanes, intersections, buildings, and transit to GeoJSON with detailed
+    /// properties, transforming to WGS84. Unlike `export_geometry`, this is meant for presenting
+    /// the processed map in other GIS tools.
+    pub fn export_to_geojson(&self) -> geojson::GeoJson {
+        let mut pairs = Vec::new();
+        let gps_bounds = Some(self.get_gps_bounds());
+
+        for r in self.all_roads() {
+            let mut props = serde_json::Map::new();
+            props.insert("type".to_string(), "road".into());
+            props.insert("id".to_string(), r.id.0.into());
+            props.insert("osm_way_id".to_string(), r.orig_id.osm_way_id.0.into());
+            props.insert("name".to_string(), r.get_name(None).into());
+            if let Some(highway) = r.osm_tags.get(osm::HIGHWAY) {
+                props.insert("highway".to_string(), highway.clone().into());
+            }
+            props.insert(
+                "speed_limit_kph".to_string(),
+                (r.speed_limit.inner_meters_per_second() * 3.6)
+                    .round()
+                    .into(),
+            );
+            props.insert("width_m".to_string(), r.get_width().inner_meters().into());
+            props.insert("zorder".to_string(), r.zorder.into());
+            props.insert("private".to_string(), r.is_private().into());
+            props.insert(
+                "lanes".to_string(),
+                r.lanes
+                    .iter()
+                    .map(|l| format!("{:?} {:?}", l.lane_type, l.dir))
+                    .collect::<Vec<_>>()
+                    .into(),
+            );
+            if let Some(ref filter) = r.modal_filter {
+                props.insert(
+                    "modal_filter".to_string(),
+                    format!("{:?}", filter.filter_type).into(),
+                );
+            }
+            pairs.push((r.center_pts.to_geojson(gps_bounds), props));
+
+            for (idx, l) in r.lanes.iter().enumerate() {
+                let mut props = serde_json::Map::new();
+                props.insert("type".to_string(), "lane".into());
+                props.insert("id".to_string(), l.id.to_string().into());
+                props.insert("road".to_string(), r.id.0.into());
+                props.insert("index".to_string(), idx.into());
+                props.insert("lane_type".to_string(), format!("{:?}", l.lane_type).into());
+                props.insert("direction".to_string(), format!("{:?}", l.dir).into());
+                props.insert("width_m".to_string(), l.width.inner_meters().into());
+                pairs.push((l.get_thick_polygon().to_geojson(gps_bounds), props));
+            }
+        }
+
+        for i in self.all_intersections() {
+            let mut props = serde_json::Map::new();
+            props.insert("type".to_string(), "intersection".into());
+            props.insert("id".to_string(), i.id.0.into());
+            props.insert("osm_node_id".to_string(), i.orig_id.0.into());
+            props.insert("kind".to_string(), format!("{:?}", i.kind).into());
+            props.insert("control".to_string(), format!("{:?}", i.control).into());
+            props.insert("elevation_m".to_string(), i.elevation.inner_meters().into());
+            if let Some(ref filter) = i.modal_filter {
+                props.insert(
+                    "modal_filter".to_string(),
+                    format!("{:?}", filter.filter_type).into(),
+                );
+            }
+            pairs.push((i.polygon.to_geojson(gps_bounds), props));
+        }
+
+        for b in self.all_buildings() {
+            let mut props = serde_json::Map::new();
+            props.insert("type".to_string(), "building".into());
+            props.insert("id".to_string(), b.id.0.into());
+            props.insert("osm_id".to_string(), b.orig_id.to_string().into());
+            props.insert("address".to_string(), b.address.clone().into());
+            if let Some(ref names) = b.name {
+                props.insert("name".to_string(), names.get(None).to_string().into());
+            }
+            props.insert("levels".to_string(), b.levels.into());
+            let (residents, workers) = match b.bldg_type {
+                BuildingType::Residential { num_residents, .. } => (num_residents, 0),
+                BuildingType::ResidentialCommercial(residents, workers) => (residents, workers),
+                BuildingType::Commercial(workers) => (0, workers),
+                BuildingType::Empty => (0, 0),
+            };
+            props.insert("residents".to_string(), residents.into());
+            props.insert("workers".to_string(), workers.into());
+            props.insert(
+                "amenities".to_string(),
+                b.amenities
+                    .iter()
+                    .map(|a| a.amenity_type.clone())
+                    .collect::<Vec<_>>()
+                    .into(),
+            );
+            pairs.push((b.polygon.to_geojson(gps_bounds), props));
+        }
+
+        for ts in self.all_transit_stops().values() {
+            let mut props = serde_json::Map::new();
+            props.insert("type".to_string(), "transit stop".into());
+            props.insert("id".to_string(), ts.id.to_string().into());
+            props.insert("name".to_string(), ts.name.clone().into());
+            props.insert("gtfs_id".to_string(), ts.gtfs_id.clone().into());
+            props.insert("train".to_string(), ts.is_train_stop.into());
+            let pt = ts.sidewalk_pos.pt(self).to_gps(self.get_gps_bounds());
+            pairs.push((
+                geojson::Geometry::new(geojson::Value::Point(vec![pt.x(), pt.y()])),
+                props,
+            ));
+        }
+
+        for tr in self.all_transit_routes() {
+            let lines: Vec<Vec<Vec<f64>>> = match tr.all_paths(self) {
+                Ok(paths) => paths
+                    .into_iter()
+                    .filter_map(|path| path.trace(self))
+                    .map(|pl| {
+                        self.get_gps_bounds()
+                            .convert_back(pl.points())
+                            .into_iter()
+                            .map(|gps| vec![gps.x(), gps.y()])
+                            .collect()
+                    })
+                    .collect(),
+                Err(err) => {
+                    warn!("Not exporting the path of {}: {}", tr.long_name, err);
+                    continue;
+                }
+            };
+            let mut props = serde_json::Map::new();
+            props.insert("type".to_string(), "transit route".into());
+            props.insert("id".to_string(), tr.id.to_string().into());
+            props.insert("short_name".to_string(), tr.short_name.clone().into());
+            props.insert("long_name".to_string(), tr.long_name.clone().into());
+            props.insert("gtfs_id".to_string(), tr.gtfs_id.clone().into());
+            props.insert("mode".to_string(), format!("{:?}", tr.route_type).into());
+            props.insert(
+                "stops".to_string(),
+                tr.stops
+                    .iter()
+                    .map(|ts| ts.to_string())
+                    .collect::<Vec<_>>()
+                    .into(),
+            );
+            props.insert("trips_per_day".to_string(), tr.spawn_times.len().into());
+            pairs.push((
+                geojson::Geometry::new(geojson::Value::MultiLineString(lines)),
+                props,
+            ));
+        }
+
+        geom::geometries_with_properties_to_geojson(pairs)
+    }
+
     /// What're the names of bus routes along a road? Note this is best effort, not robust to edits
     /// or transformations.
     pub fn get_bus_routes_on_road(&self, r: RoadID) -> &BTreeSet<String> {
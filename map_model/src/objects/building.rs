@@ -6,7 +6,9 @@ use serde::{Deserialize, Serialize};
 use abstutil::{deserialize_usize, serialize_usize, Tags};
 use geom::{Distance, PolyLine, Polygon, Pt2D};
 
-use crate::{osm, Amenity, AmenityType, LaneID, Map, NamePerLanguage, PathConstraints, Position};
+use crate::{
+    osm, Amenity, AmenityType, LandUse, LaneID, Map, NamePerLanguage, PathConstraints, Position,
+};
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct BuildingID(
@@ -38,6 +40,9 @@ pub struct Building {
     pub label_center: Pt2D,
     pub amenities: Vec<Amenity>,
     pub bldg_type: BuildingType,
+    /// Most buildings in OSM don't say what they're used for. For those, this is a guess based on
+    /// the surrounding landuse, nearby amenities, and footprint size, which `bldg_type` reflects.
+    pub inferred_land_use: Option<LandUse>,
     pub parking: OffstreetParking,
     /// Depending on options while importing, these might be empty, to save file space.
     pub osm_tags: Tags,
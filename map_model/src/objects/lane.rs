@@ -182,6 +182,13 @@ impl Lane {
         self.lane_type == LaneType::Parking
     }
 
+    /// Can vehicles ever move along this lane? Besides normal lanes for moving vehicles, this
+    /// includes parking lanes with a clearway, which traffic may use while the clearway is active.
+    pub fn may_carry_traffic(&self, map: &Map) -> bool {
+        self.lane_type.is_for_moving_vehicles()
+            || (self.is_parking() && map.get_r(self.id.road).has_clearway(self.dir))
+    }
+
     pub fn is_light_rail(&self) -> bool {
         self.lane_type == LaneType::LightRail
     }
@@ -229,6 +236,55 @@ impl Lane {
         }
     }
 
+    /// If every vehicle turn from this driving lane has the same type, but some other driving lane
+    /// going the same way serves different turns, then this is a dedicated turn lane (or a turn
+    /// pocket). Returns that type. U-turns count as left turns (or right turns, when driving on the
+    /// left).
+    pub fn dedicated_turn_type(&self, map: &Map) -> Option<TurnType> {
+        if !self.is_driving() {
+            return None;
+        }
+        let types = self.vehicle_turn_types(map);
+        if types.len() != 1 {
+            return None;
+        }
+        let turn_type = *types.iter().next().unwrap();
+        if turn_type == TurnType::Straight {
+            return None;
+        }
+        let road = map.get_r(self.id.road);
+        for (l, lt) in road.children(self.dir) {
+            if l != self.id
+                && lt == LaneType::Driving
+                && map.get_l(l).vehicle_turn_types(map) != types
+            {
+                return Some(turn_type);
+            }
+        }
+        None
+    }
+
+    /// The types of vehicle turns starting from this lane, counting U-turns like
+    /// `dedicated_turn_type`.
+    pub fn vehicle_turn_types(&self, map: &Map) -> BTreeSet<TurnType> {
+        let uturn_as = if map.get_config().driving_side == DrivingSide::Right {
+            TurnType::Left
+        } else {
+            TurnType::Right
+        };
+        map.get_turns_from_lane(self.id)
+            .into_iter()
+            .filter(|t| !t.turn_type.pedestrian_crossing())
+            .map(|t| {
+                if t.turn_type == TurnType::UTurn {
+                    uturn_as
+                } else {
+                    t.turn_type
+                }
+            })
+            .collect()
+    }
+
     /// Returns the set of allowed turn types, based on individual turn lane restrictions. `None`
     /// means all turn types are allowed.
     ///
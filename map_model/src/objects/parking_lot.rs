@@ -40,8 +40,8 @@ pub struct ParkingLot {
 
     /// Goes from the lot to the driving lane
     pub driveway_line: PolyLine,
-    /// Guaranteed to be at least 7m (MAX_CAR_LENGTH + a little buffer) away from both ends of the
-    /// lane, to prevent various headaches
+    /// Guaranteed to be at least 7m (the longest car + a little buffer) away from both ends of the
+    /// lane, to prevent various headaches. Longer vehicles don't use lots.
     pub driving_pos: Position,
 
     /// Lot to sidewalk
@@ -6,7 +6,7 @@ use enumset::EnumSet;
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_usize, serialize_usize, Tags};
-use geom::{Distance, PolyLine, Polygon, Speed};
+use geom::{Distance, Duration, PolyLine, Polygon, Speed, Time};
 
 use crate::{
     osm, AccessRestrictions, CommonEndpoint, CrossingType, Direction, DrivingSide, IntersectionID,
@@ -205,8 +205,18 @@ pub struct Road {
     /// Some kind of crossing this distance along center_pts.
     // TODO Just use Crossing directly?
     pub crossing_nodes: Vec<(Distance, CrossingType)>,
+    /// A kerb without a dropped section for wheelchairs this distance along center_pts
+    pub raised_kerbs: Vec<Distance>,
     /// Sorted by increasing distance
     pub crossings: Vec<Crossing>,
+    /// Curb regulations along one side of this road, like clearways and loading bays
+    pub parking_restrictions: Vec<ParkingRestriction>,
+    /// Rules for the bus lanes along one side of this road, and queue jumps where they meet a
+    /// traffic signal. Sides without an entry have full-time bus lanes and no queue jump.
+    pub bus_priority: Vec<BusPriority>,
+    /// A woonerf, living street, or school street: pedestrians have priority and may use the full
+    /// width, and vehicles may only enter at walking pace
+    pub shared_space: bool,
 }
 
 impl Road {
@@ -454,6 +464,19 @@ impl Road {
         self.osm_tags.is(osm::HIGHWAY, "service")
     }
 
+    pub fn is_bridge(&self) -> bool {
+        self.osm_tags.contains_key("bridge") && !self.osm_tags.is("bridge", "no")
+    }
+
+    /// Building passages are tagged as tunnels, but they're usually at ground level, so they
+    /// don't count.
+    pub fn is_tunnel(&self) -> bool {
+        self.osm_tags.contains_key("tunnel")
+            && !self
+                .osm_tags
+                .is_any("tunnel", vec!["no", "building_passage"])
+    }
+
     pub fn is_cycleway(&self) -> bool {
         let mut bike = false;
         for lane in &self.lanes {
@@ -491,24 +514,122 @@ impl Road {
         }
     }
 
+    /// The curb regulations in effect on this side of the road at this time
+    pub fn active_parking_restrictions(
+        &self,
+        dir: Direction,
+        time: Time,
+    ) -> impl Iterator<Item = &ParkingRestriction> {
+        self.parking_restrictions
+            .iter()
+            .filter(move |r| r.dir == dir && r.is_active(time))
+    }
+
+    /// Is there a full-length clearway on this side of the road at any time of day?
+    pub fn has_clearway(&self, dir: Direction) -> bool {
+        self.parking_restrictions
+            .iter()
+            .any(|r| r.dir == dir && r.is_clearway())
+    }
+
+    /// May moving traffic use the parking lanes on this side of the road at this time?
+    pub fn is_clearway_active(&self, dir: Direction, time: Time) -> bool {
+        self.active_parking_restrictions(dir, time)
+            .any(|r| r.is_clearway())
+    }
+
+    /// May only buses use the bus lanes on this side of the road at this time? Outside their
+    /// hours, bus lanes are open to general traffic.
+    pub fn is_bus_lane_active(&self, dir: Direction, time: Time) -> bool {
+        match self.bus_priority.iter().find(|bp| bp.dir == dir) {
+            Some(bp) => bp.is_active(time),
+            None => true,
+        }
+    }
+
+    /// How long buses on this side of the road get a green light before general traffic, when
+    /// approaching a traffic signal.
+    pub fn bus_queue_jump(&self, dir: Direction) -> Duration {
+        self.bus_priority
+            .iter()
+            .find(|bp| bp.dir == dir)
+            .map(|bp| bp.queue_jump)
+            .unwrap_or(Duration::ZERO)
+    }
+
+    /// The fastest vehicles may go along this road. In a shared space, that's walking pace,
+    /// whatever the posted limit is.
+    pub fn vehicle_speed_limit(&self) -> Speed {
+        if self.shared_space {
+            self.speed_limit.min(shared_space_speed_limit())
+        } else {
+            self.speed_limit
+        }
+    }
+
+    pub(crate) fn shared_space_from_osm(&self) -> bool {
+        if self.osm_tags.is("shared_space", "no") {
+            return false;
+        }
+        self.osm_tags.is(osm::HIGHWAY, "living_street") || self.osm_tags.is("shared_space", "yes")
+    }
+
     pub fn is_private(&self) -> bool {
-        self.access_restrictions != AccessRestrictions::new() && !self.is_light_rail()
+        self.access_restrictions.allow_through_traffic != EnumSet::all() && !self.is_light_rail()
     }
 
     pub(crate) fn access_restrictions_from_osm(&self) -> AccessRestrictions {
-        let allow_through_traffic = if self.osm_tags.is("access", "private") {
-            EnumSet::new()
-        } else if self.osm_tags.is(osm::HIGHWAY, "living_street") {
+        // See https://wiki.openstreetmap.org/wiki/Key:hgv
+        let no_hgv = self.osm_tags.is_any("hgv", vec!["no", "delivery"]);
+
+        if self.osm_tags.is(osm::HIGHWAY, "living_street") {
             let mut allow = PathConstraints::Pedestrian | PathConstraints::Bike;
             if self.osm_tags.is("psv", "yes") || self.osm_tags.is("bus", "yes") {
                 allow |= PathConstraints::Bus;
             }
-            allow
-        } else {
-            EnumSet::all()
-        };
+            return AccessRestrictions {
+                allow_through_traffic: allow,
+                no_hgv,
+            };
+        }
+
+        // The most specific access key present wins. See
+        // https://wiki.openstreetmap.org/wiki/Key:access#Transport_mode_restrictions.
+        let mut allow_through_traffic = EnumSet::new();
+        for (constraints, keys) in [
+            (PathConstraints::Pedestrian, vec!["foot"]),
+            (PathConstraints::Bike, vec!["bicycle", "vehicle"]),
+            (PathConstraints::Car, vec!["motor_vehicle", "vehicle"]),
+            (
+                PathConstraints::Bus,
+                vec!["bus", "psv", "motor_vehicle", "vehicle"],
+            ),
+            (PathConstraints::Train, vec![]),
+        ] {
+            // Only restrictions meaning "no through traffic" count here. "no" for one mode
+            // usually just reflects the type of road, which the lane types already capture, but a
+            // general "access=no" is common on private driveways.
+            let no_through = |v: &str| {
+                matches!(
+                    v,
+                    "private" | "destination" | "delivery" | "customers" | "permit"
+                )
+            };
+            let restricted = match keys.into_iter().find_map(|key| self.osm_tags.get(key)) {
+                Some(v) => no_through(v.as_str()),
+                None => self
+                    .osm_tags
+                    .get("access")
+                    .map(|v| v == "no" || no_through(v.as_str()))
+                    .unwrap_or(false),
+            };
+            if !restricted {
+                allow_through_traffic |= constraints;
+            }
+        }
         AccessRestrictions {
             allow_through_traffic,
+            no_hgv,
         }
     }
 
@@ -532,6 +653,155 @@ impl Road {
         self.length() < Distance::meters(2.0)
     }
 
+    /// Is there a crossing close enough to this end of the road to act as the intersection's
+    /// crosswalk? Crossings farther away are mid-block.
+    pub fn has_crossing_near(&self, i: IntersectionID) -> bool {
+        self.crossings
+            .iter()
+            .any(|c| self.dist_from_end(i, c.dist) <= MAX_CROSSWALK_SETBACK)
+    }
+
+    /// The crossings tagged in OpenStreetMap, sorted by distance.
+    pub fn crossings_from_osm(&self) -> Vec<Crossing> {
+        let mut crossings: Vec<Crossing> = self
+            .crossing_nodes
+            .iter()
+            .map(|(dist, kind)| Crossing {
+                kind: *kind,
+                dist: *dist,
+            })
+            .collect();
+        crossings.sort_by_key(|c| c.dist);
+        crossings
+    }
+
+    /// Crossings that aren't close enough to either end to act as an intersection's crosswalk.
+    /// Pedestrians use these to get between the sidewalks on both sides of the road.
+    pub fn mid_block_crossings(&self) -> impl Iterator<Item = &Crossing> {
+        self.crossings.iter().filter(move |c| {
+            self.dist_from_end(self.src_i, c.dist) > MAX_CROSSWALK_SETBACK
+                && self.dist_from_end(self.dst_i, c.dist) > MAX_CROSSWALK_SETBACK
+        })
+    }
+
+    /// Somebody starts on one sidewalk at the `from` end of this road, crosses mid-block, then
+    /// walks along the sidewalk on the other side to the `to` end. Which crossing is quickest,
+    /// counting the expected wait to cross? Pathfinding and the simulation both use this, so they
+    /// agree about where people cross.
+    pub fn best_mid_block_crossing(
+        &self,
+        from: IntersectionID,
+        to: IntersectionID,
+    ) -> Option<&Crossing> {
+        let cost = |c: &Crossing| {
+            (self.dist_from_end(from, c.dist) + self.dist_from_end(to, c.dist))
+                / crate::MAX_WALKING_SPEED
+                + c.expected_wait()
+        };
+        self.mid_block_crossings()
+            .min_by(|c1, c2| cost(c1).partial_cmp(&cost(c2)).unwrap())
+    }
+
+    /// How far along one of this road's lanes is a crossing? Crossings are positioned along the
+    /// center of the road, so this scales to the lane's length and direction.
+    pub fn crossing_dist_along_lane(&self, crossing: &Crossing, lane: &Lane) -> Distance {
+        let pct = crossing.dist / self.length();
+        if lane.dir == Direction::Fwd {
+            pct * lane.length()
+        } else {
+            (1.0 - pct) * lane.length()
+        }
+    }
+
+    fn dist_from_end(&self, i: IntersectionID, dist: Distance) -> Distance {
+        if i == self.src_i {
+            dist
+        } else {
+            self.length() - dist
+        }
+    }
+
+    /// Why wheelchair users can't move along this road's sidewalks or path, if they can't. An
+    /// explicit `wheelchair` tag overrides everything else.
+    pub fn wheelchair_barrier(&self) -> Option<AccessibilityBarrier> {
+        if self
+            .osm_tags
+            .is_any("wheelchair", vec!["yes", "designated"])
+        {
+            return None;
+        }
+        if self.osm_tags.is("wheelchair", "no") {
+            return Some(AccessibilityBarrier::TaggedInaccessible);
+        }
+        if self.osm_tags.is(osm::HIGHWAY, "steps") {
+            return Some(AccessibilityBarrier::Steps);
+        }
+        // A separately mapped crossing with a raised kerb at either end
+        if self.is_footway() && !self.raised_kerbs.is_empty() {
+            return Some(AccessibilityBarrier::RaisedKerb);
+        }
+        if self.steepest_incline() > MAX_WHEELCHAIR_INCLINE {
+            return Some(AccessibilityBarrier::SteepIncline);
+        }
+
+        // TODO The two sides of a road could have different surfaces
+        let surface = if self.is_footway() {
+            self.osm_tags.get("surface")
+        } else {
+            [
+                "sidewalk:surface",
+                "sidewalk:both:surface",
+                "sidewalk:left:surface",
+                "sidewalk:right:surface",
+            ]
+            .into_iter()
+            .find_map(|key| self.osm_tags.get(key))
+        };
+        if surface
+            .map(|x| ROUGH_SURFACES.contains(&x.as_str()))
+            .unwrap_or(false)
+        {
+            return Some(AccessibilityBarrier::RoughSurface);
+        }
+        None
+    }
+
+    /// The steeper of the incline from elevation data and any `incline` tag, ignoring direction
+    pub fn steepest_incline(&self) -> f64 {
+        let tagged = self
+            .osm_tags
+            .get("incline")
+            .and_then(|x| x.strip_suffix('%'))
+            .and_then(|x| x.trim().parse::<f64>().ok())
+            .map(|pct| pct.abs() / 100.0)
+            .unwrap_or(0.0);
+        self.percent_incline.abs().max(tagged)
+    }
+
+    /// Does a raised kerb stop wheelchair users from using the crosswalk at this end of the road?
+    pub fn raised_kerb_near(&self, i: IntersectionID) -> bool {
+        let len = self.length();
+        self.raised_kerbs.iter().any(|dist| {
+            if i == self.src_i {
+                *dist <= MAX_CROSSWALK_SETBACK
+            } else {
+                len - *dist <= MAX_CROSSWALK_SETBACK
+            }
+        })
+    }
+
+    /// Does a raised kerb stop wheelchair users from using a mid-block crossing this far along?
+    pub fn raised_kerb_at(&self, dist: Distance) -> bool {
+        self.raised_kerbs.iter().any(|kerb| {
+            let gap = if *kerb > dist {
+                *kerb - dist
+            } else {
+                dist - *kerb
+            };
+            gap <= KERB_NEAR_CROSSING
+        })
+    }
+
     /// Get the DirectedRoadID pointing to the intersection. Panics if the intersection isn't an
     /// endpoint.
     pub fn directed_id_from(&self, i: IntersectionID) -> DirectedRoadID {
@@ -617,28 +887,111 @@ impl Road {
         panic!("{} doesn't contain both {} and {}", self.id, l1, l2);
     }
 
-    /// A simple classification of if the directed road is stressful or not for cycling. Arterial
-    /// roads without a bike lane match this. Why arterial, instead of looking at speed limits?
-    /// Even on arterial roads with official speed limits lowered, in practice vehicles still
-    /// travel at the speed suggested by the design of the road.
+    /// A simple classification of if the directed road is stressful or not for cycling: level of
+    /// traffic stress 3 or 4, without knowing traffic volumes. Speed limits are used, but in
+    /// practice vehicles on arterial roads still travel at the speed suggested by the design of the
+    /// road, so those are also treated as busy.
     // TODO Should elevation matter or not? Flat high-speed roads are still terrifying, but there's
     // something about slogging up (or flying down!) a pothole-filled road inches from cars.
     pub fn high_stress_for_bikes(&self, map: &Map, dir: Direction) -> bool {
-        let mut bike_lanes = false;
-        let mut can_use = false;
-        // Can a bike even use it, or is it a highway?
-        for l in &self.lanes {
-            if l.lane_type == LaneType::Biking && l.dir == dir {
-                bike_lanes = true;
+        self.level_of_traffic_stress(map, dir, None) > Some(LevelOfTrafficStress::LTS2)
+    }
+
+    /// Classifies how stressful it is to cycle along this road in one direction, following a
+    /// simplified version of the Mineta Transportation Institute criteria. Returns `None` if bikes
+    /// can't travel this way. `vehicles_per_day` can come from counts or a simulation; without
+    /// it, local roads are assumed to be quiet and everything else busy.
+    pub fn level_of_traffic_stress(
+        &self,
+        map: &Map,
+        dir: Direction,
+        vehicles_per_day: Option<usize>,
+    ) -> Option<LevelOfTrafficStress> {
+        if !self
+            .lanes
+            .iter()
+            .any(|l| l.dir == dir && PathConstraints::Bike.can_use(l, map))
+        {
+            return None;
+        }
+        // Roads without any motor traffic, like cycleways and shared paths, or where it's limited
+        // to walking pace
+        if self.shared_space || !self.lanes.iter().any(|l| l.is_driving() || l.is_bus()) {
+            return Some(LevelOfTrafficStress::LTS1);
+        }
+
+        let lanes_this_way = self
+            .lanes
+            .iter()
+            .filter(|l| l.dir == dir && (l.is_driving() || l.is_bus()))
+            .count();
+        // Leave some slack, so common limits in km/h fall in the expected bucket. 50 km/h is 31
+        // mph.
+        let speed = self.speed_limit.to_miles_per_hour();
+        let at_most = |mph: f64| speed <= mph + 1.5;
+        // 0 is quiet, 1 moderate, 2 busy
+        let volume = match vehicles_per_day {
+            Some(n) if n <= 1000 => 0,
+            Some(n) if n <= 3000 => 1,
+            Some(_) => 2,
+            None => {
+                if self.get_rank() == osm::RoadRank::Local {
+                    0
+                } else {
+                    2
+                }
             }
-            if PathConstraints::Bike.can_use(l, map) {
-                can_use = true;
+        };
+
+        let mut bike_lane = false;
+        let mut protected = false;
+        let mut next_to_parking = false;
+        for (idx, l) in self.lanes.iter().enumerate() {
+            if l.lane_type != LaneType::Biking || l.dir != dir {
+                continue;
+            }
+            bike_lane = true;
+            for neighbor in [idx.checked_sub(1), Some(idx + 1)]
+                .into_iter()
+                .flatten()
+                .filter_map(|i| self.lanes.get(i))
+            {
+                if matches!(neighbor.lane_type, LaneType::Buffer(_)) {
+                    protected = true;
+                } else if neighbor.is_parking() {
+                    next_to_parking = true;
+                }
             }
         }
-        if !can_use || bike_lanes {
-            return false;
-        }
-        self.get_rank() != osm::RoadRank::Local
+
+        use LevelOfTrafficStress::*;
+        Some(if protected {
+            LTS1
+        } else if bike_lane {
+            if lanes_this_way <= 1 && at_most(30.0) {
+                if next_to_parking {
+                    LTS2
+                } else {
+                    LTS1
+                }
+            } else if lanes_this_way <= 2 && at_most(35.0) {
+                LTS2
+            } else if at_most(40.0) {
+                LTS3
+            } else {
+                LTS4
+            }
+        } else if lanes_this_way <= 1 && at_most(25.0) {
+            [LTS1, LTS2, LTS3][volume]
+        } else if lanes_this_way <= 1 && at_most(30.0) {
+            [LTS2, LTS3, LTS3][volume]
+        } else if lanes_this_way <= 1 && at_most(35.0) {
+            LTS3
+        } else if lanes_this_way <= 2 && at_most(25.0) {
+            LTS3
+        } else {
+            LTS4
+        })
     }
 
     pub fn oneway_for_driving(&self) -> Option<Direction> {
@@ -734,8 +1087,274 @@ impl OriginalRoad {
     }
 }
 
+/// How comfortable a road is to cycle along, from 1 (suitable for children) to 4 (only for the
+/// most confident people).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum LevelOfTrafficStress {
+    /// Separated from traffic, or a very quiet street
+    LTS1,
+    /// Most adults are comfortable here
+    LTS2,
+    /// Confident people who cycle regularly will use this
+    LTS3,
+    /// Fast or busy traffic without any separation
+    LTS4,
+}
+
+impl LevelOfTrafficStress {
+    pub fn all() -> Vec<LevelOfTrafficStress> {
+        vec![
+            LevelOfTrafficStress::LTS1,
+            LevelOfTrafficStress::LTS2,
+            LevelOfTrafficStress::LTS3,
+            LevelOfTrafficStress::LTS4,
+        ]
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            LevelOfTrafficStress::LTS1 => "LTS 1 (suitable for children)",
+            LevelOfTrafficStress::LTS2 => "LTS 2 (most adults)",
+            LevelOfTrafficStress::LTS3 => "LTS 3 (confident cyclists)",
+            LevelOfTrafficStress::LTS4 => "LTS 4 (very few people)",
+        }
+    }
+}
+
+/// Something stopping wheelchair users, and others with limited mobility, from using a path that
+/// people walking can use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum AccessibilityBarrier {
+    Steps,
+    /// Steeper than `MAX_WHEELCHAIR_INCLINE`
+    SteepIncline,
+    /// Gravel, grass, cobblestones, and the like
+    RoughSurface,
+    /// A kerb at a crossing that isn't dropped
+    RaisedKerb,
+    /// Tagged `wheelchair=no` in OSM
+    TaggedInaccessible,
+}
+
+impl AccessibilityBarrier {
+    pub fn all() -> Vec<AccessibilityBarrier> {
+        vec![
+            AccessibilityBarrier::Steps,
+            AccessibilityBarrier::SteepIncline,
+            AccessibilityBarrier::RoughSurface,
+            AccessibilityBarrier::RaisedKerb,
+            AccessibilityBarrier::TaggedInaccessible,
+        ]
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            AccessibilityBarrier::Steps => "steps",
+            AccessibilityBarrier::SteepIncline => "steep incline",
+            AccessibilityBarrier::RoughSurface => "rough surface",
+            AccessibilityBarrier::RaisedKerb => "raised kerb",
+            AccessibilityBarrier::TaggedInaccessible => "tagged as inaccessible",
+        }
+    }
+}
+
+/// The steepest grade most wheelchair users can manage. Ramps are limited to 1:12.
+pub const MAX_WHEELCHAIR_INCLINE: f64 = 0.083;
+/// A raised kerb this close to a mid-block crossing blocks it
+const KERB_NEAR_CROSSING: Distance = Distance::const_meters(5.0);
+/// Values of OSM's `surface` tag that wheelchairs can't reliably use
+const ROUGH_SURFACES: [&str; 14] = [
+    "cobblestone",
+    "unhewn_cobblestone",
+    "gravel",
+    "pebblestone",
+    "rock",
+    "stepping_stones",
+    "sand",
+    "grass",
+    "grass_paver",
+    "dirt",
+    "earth",
+    "ground",
+    "mud",
+    "woodchips",
+];
+
+/// A crossing at most this far from an intersection is treated as that intersection's crosswalk.
+pub const MAX_CROSSWALK_SETBACK: Distance = Distance::const_meters(15.0);
+
+/// Signalized mid-block crossings run on a fixed cycle, starting with a walk phase.
+const SIGNALIZED_CROSSING_CYCLE: Duration = Duration::const_seconds(90.0);
+/// How long pedestrians may start crossing at the beginning of each signal cycle.
+const SIGNALIZED_CROSSING_WALK: Duration = Duration::const_seconds(15.0);
+
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Crossing {
     pub kind: CrossingType,
     pub dist: Distance,
 }
+
+impl Crossing {
+    /// How long does somebody arriving at this crossing at `now` wait for the signal? Always zero
+    /// for unsignalized crossings, where pedestrians instead wait for a gap in traffic.
+    pub fn wait_for_signal(&self, now: Time) -> Duration {
+        if self.kind == CrossingType::Unsignalized {
+            return Duration::ZERO;
+        }
+        let into_cycle =
+            Duration::seconds(now.inner_seconds() % SIGNALIZED_CROSSING_CYCLE.inner_seconds());
+        if into_cycle < SIGNALIZED_CROSSING_WALK {
+            Duration::ZERO
+        } else {
+            SIGNALIZED_CROSSING_CYCLE - into_cycle
+        }
+    }
+
+    /// On average, how long does somebody arriving at a random time wait to cross? Used for
+    /// pathfinding, which doesn't know about the signal's phase or about traffic.
+    pub fn expected_wait(&self) -> Duration {
+        match self.kind {
+            CrossingType::Signalized => {
+                let red = SIGNALIZED_CROSSING_CYCLE - SIGNALIZED_CROSSING_WALK;
+                (red / SIGNALIZED_CROSSING_CYCLE) * (red / 2.0)
+            }
+            CrossingType::Unsignalized => Duration::ZERO,
+        }
+    }
+}
+
+/// A curb regulation along one side of a road (a blockface) during some time window -- for
+/// example, a peak-hour clearway or a loading bay. Outside the window, the parking lanes behave
+/// normally.
+///
+/// During the window, the simulation keeps the curb clear. A clearway covering the whole
+/// blockface also opens the parking lane to moving traffic, like bus lanes outside their hours.
+/// Turns to and from the lane always exist, but pathfinding doesn't know about clearways; vehicles
+/// only move into the lane opportunistically while it's active.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ParkingRestriction {
+    /// Applies to all parking lanes on this side of the road
+    pub dir: Direction,
+    pub start: Time,
+    pub end: Time,
+    /// Who may use the curb during the window. Older JSON edits only had clearways.
+    #[serde(default)]
+    pub kind: CurbUse,
+    /// The (start, end) distance along the parking lane covered by this regulation. If None, the
+    /// entire blockface is covered.
+    #[serde(default)]
+    pub span: Option<(Distance, Distance)>,
+}
+
+impl ParkingRestriction {
+    /// Restrictions repeat daily, so simulations running past midnight are handled.
+    pub fn is_active(&self, time: Time) -> bool {
+        let time = time_of_day(time);
+        self.start <= time && time < self.end
+    }
+
+    /// Does this regulation open the whole parking lane to moving traffic?
+    pub fn is_clearway(&self) -> bool {
+        self.kind == CurbUse::NoStopping && self.span.is_none()
+    }
+
+    /// Does this regulation cover any part of the curb between these distances along the parking
+    /// lane?
+    pub fn covers(&self, start: Distance, end: Distance) -> bool {
+        match self.span {
+            Some((from, to)) => start < to && from < end,
+            None => true,
+        }
+    }
+}
+
+/// Who may stop along a stretch of curb while a regulation is active
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum CurbUse {
+    /// Nobody may stop, not even to load, like a peak-hour clearway
+    #[default]
+    NoStopping,
+    /// General parking isn't allowed, but goods vehicles may load and disabled permit holders may
+    /// park
+    NoParking,
+    /// Only goods vehicles may stop, to load and unload
+    Loading,
+    /// Only vehicles with a disabled parking permit may park
+    Disabled,
+}
+
+impl CurbUse {
+    pub fn all() -> Vec<CurbUse> {
+        vec![
+            CurbUse::NoStopping,
+            CurbUse::NoParking,
+            CurbUse::Loading,
+            CurbUse::Disabled,
+        ]
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            CurbUse::NoStopping => "no stopping",
+            CurbUse::NoParking => "no parking",
+            CurbUse::Loading => "loading only",
+            CurbUse::Disabled => "disabled parking only",
+        }
+    }
+
+    /// May a vehicle park here?
+    pub fn allows(self, goods_vehicle: bool, disabled_permit: bool) -> bool {
+        match self {
+            CurbUse::NoStopping => false,
+            CurbUse::NoParking => goods_vehicle || disabled_permit,
+            CurbUse::Loading => goods_vehicle,
+            CurbUse::Disabled => disabled_permit,
+        }
+    }
+
+    /// When two regulations overlap, a vehicle must obey both.
+    pub fn combine(self, other: CurbUse) -> CurbUse {
+        if self == other || other == CurbUse::NoParking {
+            self
+        } else if self == CurbUse::NoParking {
+            other
+        } else {
+            CurbUse::NoStopping
+        }
+    }
+}
+
+/// Bus priority measures along one side of a road.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct BusPriority {
+    /// Applies to all bus lanes on this side of the road
+    pub dir: Direction,
+    /// Time windows when the bus lanes are reserved for buses. If this is empty, they always are.
+    pub hours: Vec<(Time, Time)>,
+    /// When a traffic signal at the end of this side of the road turns green, buses get this long
+    /// before general traffic may go. Zero means there's no queue jump.
+    pub queue_jump: Duration,
+}
+
+impl BusPriority {
+    /// Like parking restrictions, the hours repeat daily.
+    pub fn is_active(&self, time: Time) -> bool {
+        if self.hours.is_empty() {
+            return true;
+        }
+        let time = time_of_day(time);
+        self.hours
+            .iter()
+            .any(|(start, end)| *start <= time && time < *end)
+    }
+}
+
+fn time_of_day(time: Time) -> Time {
+    Time::START_OF_DAY
+        + Duration::seconds(time.inner_seconds() % Duration::hours(24).inner_seconds())
+}
+
+/// What counts as walking pace for vehicles in a shared space
+pub fn shared_space_speed_limit() -> Speed {
+    Speed::miles_per_hour(5.0)
+}
@@ -0,0 +1,132 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use geom::{Polygon, Pt2D, Ring};
+
+use crate::{polygon_ops, IntersectionID, Map, Road, RoadID, TurnID};
+
+/// OSM maps a roundabout as a ring of one-way roads tagged `junction=roundabout`, with an
+/// intersection everywhere another road joins it. This groups those pieces back together, so the
+/// roundabout can be treated as one junction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Roundabout {
+    pub id: RoundaboutID,
+    /// The intersections along the ring
+    pub members: BTreeSet<IntersectionID>,
+    /// The roads forming the ring itself
+    pub ring: BTreeSet<RoadID>,
+    /// Covers the ring, the intersections along it, and the central island
+    pub polygon: Polygon,
+}
+
+/// Roundabouts are found again whenever the map is loaded or edited, so these IDs aren't stable
+/// across edits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RoundaboutID(pub usize);
+
+impl fmt::Display for RoundaboutID {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Roundabout #{}", self.0)
+    }
+}
+
+impl Roundabout {
+    /// Roundabouts only partly inside the map are skipped.
+    pub(crate) fn find_all(map: &Map) -> Vec<Roundabout> {
+        let mut results = Vec::new();
+        let mut seen = BTreeSet::new();
+        for start in map.all_roads() {
+            if !is_ring_road(start) || seen.contains(&start.id) {
+                continue;
+            }
+
+            // Walk around the ring in the direction of travel
+            let mut ring = vec![start.id];
+            let mut members = vec![start.dst_i];
+            let mut pts = start.center_pts.points().clone();
+            let mut closed = false;
+            loop {
+                let i = *members.last().unwrap();
+                if i == start.src_i {
+                    closed = true;
+                    break;
+                }
+                let next = map.get_i(i).roads.iter().map(|r| map.get_r(*r)).find(|r| {
+                    is_ring_road(r)
+                        && r.src_i == i
+                        && !ring.contains(&r.id)
+                        && !seen.contains(&r.id)
+                });
+                match next {
+                    Some(r) => {
+                        ring.push(r.id);
+                        members.push(r.dst_i);
+                        pts.extend(r.center_pts.points().iter().cloned());
+                    }
+                    None => break,
+                }
+            }
+            seen.extend(ring.iter().cloned());
+            if !closed {
+                continue;
+            }
+
+            let polygon = make_polygon(map, &ring, &members, pts);
+            results.push(Roundabout {
+                id: RoundaboutID(results.len()),
+                members: members.into_iter().collect(),
+                ring: ring.into_iter().collect(),
+                polygon,
+            });
+        }
+        results
+    }
+
+    /// Does this turn enter the roundabout from outside?
+    pub fn is_entry(&self, turn: TurnID) -> bool {
+        !self.ring.contains(&turn.src.road) && self.ring.contains(&turn.dst.road)
+    }
+
+    /// Does this turn continue around the roundabout?
+    pub fn is_circulating(&self, turn: TurnID) -> bool {
+        self.ring.contains(&turn.src.road) && self.ring.contains(&turn.dst.road)
+    }
+}
+
+fn is_ring_road(r: &Road) -> bool {
+    r.osm_tags.is("junction", "roundabout") || r.osm_tags.is("junction", "circular")
+}
+
+fn make_polygon(
+    map: &Map,
+    ring: &[RoadID],
+    members: &[IntersectionID],
+    mut center_pts: Vec<Pt2D>,
+) -> Polygon {
+    let mut polygons: Vec<Polygon> = ring
+        .iter()
+        .map(|r| map.get_r(*r).get_thick_polygon())
+        .chain(members.iter().map(|i| map.get_i(*i).polygon.clone()))
+        .collect();
+    // The center line around the ring covers the island in the middle
+    center_pts.push(center_pts[0]);
+    if let Ok(island) = Ring::deduping_new(center_pts) {
+        polygons.push(island.into_polygon());
+    }
+
+    let fallback = map.get_i(members[0]).polygon.clone();
+    match polygon_ops::union(polygons) {
+        // If the pieces don't all touch, use the biggest
+        Ok(list) => list
+            .into_iter()
+            .max_by(|p1, p2| p1.area().partial_cmp(&p2.area()).unwrap())
+            .unwrap_or(fallback),
+        Err(err) => {
+            warn!(
+                "Couldn't make a polygon for the roundabout at {}: {}",
+                members[0], err
+            );
+            fallback
+        }
+    }
+}
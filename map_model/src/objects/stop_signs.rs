@@ -93,6 +93,14 @@ impl ControlStopSign {
             }
         }
 
+        // Entering a roundabout means yielding to traffic already going around it
+        if let Some(roundabout) = map.get_roundabout(id) {
+            for (r, cfg) in ss.roads.iter_mut() {
+                cfg.must_stop = !roundabout.ring.contains(r);
+            }
+            return ss;
+        }
+
         // Degenerate roads and deadends don't need any stop signs. But be careful with
         // roundabouts; we want it to be lower priority to enter a roundabout than continue through
         // it.
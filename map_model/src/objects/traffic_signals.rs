@@ -8,7 +8,8 @@ use geom::{Distance, Duration, Speed};
 use crate::edits::perma_traffic_signal;
 use crate::make::traffic_signals::get_possible_policies;
 use crate::{
-    Intersection, IntersectionID, Map, Movement, MovementID, RoadID, TurnID, TurnPriority,
+    DrivingSide, Intersection, IntersectionID, Map, Movement, MovementID, RoadID, TurnID,
+    TurnPriority, TurnType,
 };
 
 // The pace to use for crosswalk pace in m/s
@@ -327,6 +328,36 @@ impl Stage {
             self.yield_movements.insert(g.id);
         }
     }
+
+    /// Makes every hard turn allowed during this stage protected or permissive. Hard turns cross
+    /// oncoming traffic -- left turns when driving on the right, right turns otherwise. Hard turns
+    /// that would conflict with something else already protected stay permissive.
+    pub fn set_hard_turn_phasing(
+        &mut self,
+        i: &Intersection,
+        driving_side: DrivingSide,
+        protected: bool,
+    ) {
+        let hard_turn = if driving_side == DrivingSide::Right {
+            TurnType::Left
+        } else {
+            TurnType::Right
+        };
+        for movement in i.movements.values() {
+            if movement.turn_type != hard_turn
+                || self.get_priority_of_movement(movement.id) == TurnPriority::Banned
+            {
+                continue;
+            }
+            self.protected_movements.remove(&movement.id);
+            if protected && self.could_be_protected(movement.id, i) {
+                self.edit_movement(movement, TurnPriority::Protected);
+            } else {
+                self.edit_movement(movement, TurnPriority::Yield);
+            }
+        }
+    }
+
     pub fn enforce_minimum_crosswalk_time(&mut self, movement: &Movement) {
         // Round up to an int, because it is exported as a usize
         let time = Duration::seconds(
@@ -404,7 +435,7 @@ impl ControlTrafficSignal {
         }
     }
 
-    pub(crate) fn import(
+    pub fn import(
         mut raw: perma_traffic_signal::TrafficSignal,
         id: IntersectionID,
         map: &Map,
@@ -69,6 +69,9 @@ pub struct TransitRoute {
     /// Explicitly store whatever the original was, since this can't be reconstructed without side
     /// input.
     pub orig_spawn_times: Vec<Time>,
+    pub fare: TransitFare,
+    /// Like orig_spawn_times, whatever fare the route was imported with
+    pub orig_fare: TransitFare,
 }
 
 impl TransitRoute {
@@ -146,3 +149,100 @@ impl TransitRoute {
         }
     }
 }
+
+/// What riding a transit route costs. All amounts are in cents, or the smallest unit of the local
+/// currency.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TransitFare {
+    pub structure: FareStructure,
+    /// Once somebody has paid this much for rides in one day, their later rides are free.
+    pub daily_cap: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum FareStructure {
+    Free,
+    /// Every ride costs the same
+    Flat(usize),
+    /// Each stop belongs to a numbered zone, and a ride costs `base` plus `per_zone` for every zone
+    /// boundary crossed. `zones` has one entry per stop along the route; stops without an entry are
+    /// in zone 0.
+    Zonal {
+        base: usize,
+        per_zone: usize,
+        zones: Vec<usize>,
+    },
+}
+
+impl TransitFare {
+    pub fn free() -> TransitFare {
+        TransitFare {
+            structure: FareStructure::Free,
+            daily_cap: None,
+        }
+    }
+
+    /// The price of riding `route` from `stop1` to `stop2`, ignoring any daily cap. If there's no
+    /// `stop2`, the rider leaves the map on the vehicle, so they're charged to the last stop.
+    pub fn price(
+        &self,
+        route: &TransitRoute,
+        stop1: TransitStopID,
+        stop2: Option<TransitStopID>,
+    ) -> usize {
+        match self.structure {
+            FareStructure::Free => 0,
+            FareStructure::Flat(price) => price,
+            FareStructure::Zonal {
+                base,
+                per_zone,
+                ref zones,
+            } => {
+                let zone = |stop: Option<TransitStopID>| {
+                    let idx = match stop {
+                        Some(stop) => route.stops.iter().position(|s| *s == stop),
+                        None => route.stops.len().checked_sub(1),
+                    };
+                    idx.and_then(|idx| zones.get(idx)).cloned().unwrap_or(0)
+                };
+                let (z1, z2) = (zone(Some(stop1)), zone(stop2));
+                base + per_zone * (z1.max(z2) - z1.min(z2))
+            }
+        }
+    }
+
+    /// What a typical ride costs, for rough estimates that don't know the exact stops. Zonal fares
+    /// are assumed to cross one boundary.
+    pub fn typical_price(&self) -> usize {
+        let price = match self.structure {
+            FareStructure::Free => 0,
+            FareStructure::Flat(price) => price,
+            FareStructure::Zonal { base, per_zone, .. } => base + per_zone,
+        };
+        match self.daily_cap {
+            Some(cap) => price.min(cap),
+            None => price,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let mut result = match self.structure {
+            FareStructure::Free => "free".to_string(),
+            FareStructure::Flat(price) => format!("{} per ride", describe_cents(price)),
+            FareStructure::Zonal { base, per_zone, .. } => format!(
+                "{} plus {} per zone crossed",
+                describe_cents(base),
+                describe_cents(per_zone)
+            ),
+        };
+        if let Some(cap) = self.daily_cap {
+            result = format!("{}, capped at {} per day", result, describe_cents(cap));
+        }
+        result
+    }
+}
+
+/// Formats an amount in cents, like "2.75"
+pub fn describe_cents(cents: usize) -> String {
+    format!("{}.{:02}", cents / 100, cents % 100)
+}
@@ -15,12 +15,17 @@ use crate::{CommonEndpoint, IntersectionID, Map, PathConstraints, RoadID};
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct AccessRestrictions {
     pub allow_through_traffic: EnumSet<PathConstraints>,
+    /// Trucks can't use this road at all. Unlike through traffic, this doesn't form a zone; trucks
+    /// just route around these roads.
+    #[serde(default)]
+    pub no_hgv: bool,
 }
 
 impl AccessRestrictions {
     pub fn new() -> AccessRestrictions {
         AccessRestrictions {
             allow_through_traffic: EnumSet::all(),
+            no_hgv: false,
         }
     }
 }
@@ -73,7 +78,9 @@ fn floodfill(map: &Map, start: RoadID) -> Option<Zone> {
         members.insert(current);
         for r in map.get_next_roads(current) {
             let r = map.get_r(r);
-            if r.access_restrictions == match_constraints {
+            if r.access_restrictions.allow_through_traffic
+                == match_constraints.allow_through_traffic
+            {
                 queue.push(r.id);
             } else {
                 // TODO Handle other cases
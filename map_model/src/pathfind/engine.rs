@@ -126,6 +126,80 @@ impl PathfindEngine {
     }
 }
 
+/// Remembers enough about how a PathfindEngine was built to cheaply update it after map edits.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct EngineUpdates {
+    /// A hash of the input graph's edges, to detect when edits don't affect this graph at all
+    fingerprint: u64,
+    /// After a quick update, the engine temporarily uses Dijkstra. This holds the contraction
+    /// hierarchy it replaced, along with the fingerprint of the graph it was built from. If the
+    /// edits are undone, it's restored as-is; otherwise it seeds the node ordering when
+    /// rebuilding.
+    stale_ch: Option<(u64, PathfindEngine)>,
+}
+
+impl EngineUpdates {
+    pub fn new(input_graph: &InputGraph) -> EngineUpdates {
+        EngineUpdates {
+            fingerprint: fingerprint(input_graph),
+            stale_ch: None,
+        }
+    }
+
+    /// Update the engine after map edits. If the edits didn't change the input graph, this does
+    /// nothing. Rebuilding a contraction hierarchy is slow, so when `quick` is true, this instead
+    /// switches to Dijkstra. Queries are slower, but correct. A later call with `quick` false
+    /// rebuilds the contraction hierarchy.
+    pub fn apply(&mut self, engine: &mut PathfindEngine, input_graph: InputGraph, quick: bool) {
+        let fingerprint = fingerprint(&input_graph);
+        if self.stale_ch.as_ref().map(|(f, _)| *f) == Some(fingerprint) {
+            *engine = self.stale_ch.take().unwrap().1;
+            self.fingerprint = fingerprint;
+            return;
+        }
+        if self.fingerprint == fingerprint && (quick || self.stale_ch.is_none()) {
+            return;
+        }
+        let prev_fingerprint = std::mem::replace(&mut self.fingerprint, fingerprint);
+
+        if quick && (self.stale_ch.is_some() || !engine.is_dijkstra()) {
+            let prev = std::mem::replace(engine, CreateEngine::Dijkstra.create(input_graph));
+            if self.stale_ch.is_none() {
+                self.stale_ch = Some((prev_fingerprint, prev));
+            }
+            return;
+        }
+
+        // The nodes never change, so the previous contraction hierarchy's node ordering can be
+        // reused. Contracting in a fixed order is deterministic, and the order only affects how
+        // fast queries are, not their results, so this matches building from scratch.
+        *engine = match self.stale_ch.take() {
+            Some((_, ch)) => ch.reuse_ordering().create(input_graph),
+            None => engine.reuse_ordering().create(input_graph),
+        };
+    }
+
+    /// Is the engine temporarily using Dijkstra after a quick update?
+    pub fn is_stale(&self) -> bool {
+        self.stale_ch.is_some()
+    }
+}
+
+/// FNV-1a over the edges. This is saved with the map, so unlike `DefaultHasher`, it has to stay
+/// the same across builds.
+fn fingerprint(input_graph: &InputGraph) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for edge in input_graph.get_edges() {
+        for x in [edge.from, edge.to, edge.weight] {
+            for byte in (x as u64).to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+    }
+    hash
+}
+
 pub enum CreateEngine<'a> {
     Dijkstra,
     CH,
@@ -181,3 +255,90 @@ impl<'a> CreateEngine<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A small grid with some one-way differences in cost. `extra` makes one column of edges more
+    // expensive, like an edit would.
+    fn grid(extra: usize) -> InputGraph {
+        let width = 6;
+        let id = |x: usize, y: usize| y * width + x;
+        let mut graph = InputGraph::new();
+        for y in 0..width {
+            for x in 0..width {
+                let cost = 1 + (x * 7 + y * 3) % 5;
+                if x + 1 < width {
+                    graph.add_edge(id(x, y), id(x + 1, y), cost);
+                    graph.add_edge(id(x + 1, y), id(x, y), cost + 1);
+                }
+                if y + 1 < width {
+                    let cost_down = if x == 2 { cost + extra } else { cost };
+                    graph.add_edge(id(x, y), id(x, y + 1), cost_down);
+                    graph.add_edge(id(x, y + 1), id(x, y), cost);
+                }
+            }
+        }
+        graph.freeze();
+        graph
+    }
+
+    fn all_costs(engine: &PathfindEngine) -> Vec<Option<usize>> {
+        let mut costs = Vec::new();
+        for start in 0..36 {
+            for end in 0..36 {
+                costs.push(engine.calculate_path(start, end).map(|(cost, _)| cost));
+            }
+        }
+        costs
+    }
+
+    fn ch_graph(engine: &PathfindEngine) -> String {
+        match engine {
+            PathfindEngine::CH { ref graph, .. } => serde_json::to_string(graph).unwrap(),
+            _ => panic!("not a contraction hierarchy"),
+        }
+    }
+
+    #[test]
+    fn test_reuse_ordering() {
+        let before = CreateEngine::CH.create(grid(0));
+
+        let edited1 = before.reuse_ordering().create(grid(20));
+        let edited2 = before.reuse_ordering().create(grid(20));
+        assert_eq!(ch_graph(&edited1), ch_graph(&edited2));
+
+        let from_scratch = CreateEngine::CH.create(grid(20));
+        assert_eq!(all_costs(&edited1), all_costs(&from_scratch));
+    }
+
+    #[test]
+    fn test_quick_updates() {
+        let mut engine = CreateEngine::CH.create(grid(0));
+        let mut updates = EngineUpdates::new(&grid(0));
+        let original = ch_graph(&engine);
+
+        // The same graph doesn't change anything
+        updates.apply(&mut engine, grid(0), true);
+        assert!(!engine.is_dijkstra());
+
+        updates.apply(&mut engine, grid(20), true);
+        assert!(engine.is_dijkstra());
+        assert!(updates.is_stale());
+        assert_eq!(
+            all_costs(&engine),
+            all_costs(&CreateEngine::CH.create(grid(20)))
+        );
+
+        // Undoing the edit restores the old contraction hierarchy
+        updates.apply(&mut engine, grid(0), true);
+        assert!(!updates.is_stale());
+        assert_eq!(ch_graph(&engine), original);
+
+        updates.apply(&mut engine, grid(20), true);
+        updates.apply(&mut engine, grid(20), false);
+        assert!(!engine.is_dijkstra());
+        assert!(!updates.is_stale());
+    }
+}
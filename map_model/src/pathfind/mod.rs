@@ -1,11 +1,11 @@
 //! Everything related to pathfinding through a map for different types of agents.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 use enumset::EnumSetType;
 use serde::{Deserialize, Serialize};
 
-use geom::Duration;
+use geom::{Duration, Polygon};
 
 pub use self::engine::CreateEngine;
 pub use self::pathfinder::{Pathfinder, PathfinderCache, PathfinderCaching};
@@ -13,7 +13,9 @@ pub use self::v1::{Path, PathRequest, PathStep};
 pub use self::v2::{PathStepV2, PathV2};
 pub use self::vehicles::vehicle_cost;
 pub use self::walking::WalkingNode;
-use crate::{osm, Lane, LaneID, LaneType, Map, MovementID, Road, RoadID, TurnType};
+use crate::{
+    osm, Lane, LaneID, LaneType, LevelOfTrafficStress, Map, MovementID, Road, RoadID, TurnType,
+};
 
 mod engine;
 mod node_map;
@@ -176,13 +178,21 @@ pub struct RoutingParams {
     pub driving_lane_penalty: f64,
 
     // For bike routing.
-    // "Steep" is a fixed threshold of 8% incline, uphill only. Multiply by the base cost. (Note
-    // that cost already includes a reduction of speed to account for the incline -- this is a
-    // further "delay" on top of that!)
-    // TODO But even steeper roads matter more!
+    // Multiply the base cost of uphill roads by this penalty at an 8% incline, scaling it
+    // proportionally for other grades and ignoring anything under 3%. (Note that cost already
+    // includes a reduction of speed to account for the incline -- this is a further "delay" on top
+    // of that!)
     pub avoid_steep_incline_penalty: f64,
-    // If the road is `high_stress_for_bikes`, multiply by the base cost.
+    // If the road's level of traffic stress is higher than `stress_tolerance`, multiply by the
+    // base cost.
     pub avoid_high_stress: f64,
+    #[serde(default = "default_stress_tolerance")]
+    pub stress_tolerance: LevelOfTrafficStress,
+
+    /// For cars and buses. Multiply the base cost of shared spaces by this penalty, so they're
+    /// only used to reach somewhere inside them.
+    #[serde(default = "default_shared_space_penalty")]
+    pub shared_space_penalty: f64,
 
     /// When crossing an arterial or highway road, multiply the base cost by this penalty. When
     /// greater than 1, this will encourage routes to use local roads more.
@@ -198,6 +208,11 @@ pub struct RoutingParams {
     /// Don't allow movements between these roads at all. Only affects vehicle routing, not
     /// pedestrian.
     pub avoid_movements_between: BTreeSet<(RoadID, RoadID)>,
+
+    /// Multiply the base cost of crossing these roads by some penalty. Only affects vehicle
+    /// routing, not pedestrian.
+    #[serde(default)]
+    pub road_penalties: BTreeMap<RoadID, f64>,
 }
 
 impl Default for RoutingParams {
@@ -213,16 +228,53 @@ impl Default for RoutingParams {
 
             avoid_steep_incline_penalty: 1.0,
             avoid_high_stress: 1.0,
+            stress_tolerance: default_stress_tolerance(),
+
+            shared_space_penalty: default_shared_space_penalty(),
 
             main_road_penalty: 1.0,
 
             avoid_roads: BTreeSet::new(),
             avoid_movements_between: BTreeSet::new(),
             only_use_roads: BTreeSet::new(),
+            road_penalties: BTreeMap::new(),
         }
     }
 }
 
+// Most adults are comfortable up to here
+fn default_stress_tolerance() -> LevelOfTrafficStress {
+    LevelOfTrafficStress::LTS2
+}
+
+fn default_shared_space_penalty() -> f64 {
+    10.0
+}
+
+impl RoutingParams {
+    /// Don't allow routes through any road with its midpoint inside this region, unless the route
+    /// starts or ends there. Useful for asking how to get somewhere without cutting through a
+    /// neighborhood.
+    pub fn avoid_region(&mut self, map: &Map, region: &Polygon) {
+        self.avoid_roads.extend(roads_in_region(map, region));
+    }
+
+    /// Multiply the cost of crossing any road with its midpoint inside this region.
+    pub fn penalize_region(&mut self, map: &Map, region: &Polygon, penalty: f64) {
+        for r in roads_in_region(map, region) {
+            self.road_penalties.insert(r, penalty);
+        }
+    }
+}
+
+fn roads_in_region(map: &Map, region: &Polygon) -> Vec<RoadID> {
+    map.all_roads()
+        .iter()
+        .filter(|r| region.contains_pt(r.center_pts.middle()))
+        .map(|r| r.id)
+        .collect()
+}
+
 pub fn round(cost: Duration) -> usize {
     // Round up! 0 cost edges are ignored
     (cost.inner_seconds().round() as usize).max(1)
@@ -4,7 +4,7 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use thread_local::ThreadLocal;
 
-use abstutil::{Timer, VecMap};
+use abstutil::{MemoryReport, Timer, VecMap};
 use geom::Duration;
 
 use crate::pathfind::engine::CreateEngine;
@@ -63,6 +63,21 @@ impl Clone for Pathfinder {
 }
 
 impl Pathfinder {
+    /// Contraction hierarchies for each mode are usually the biggest part of a map in memory.
+    pub(crate) fn measure_memory(&self, report: &mut MemoryReport) {
+        report.measure("pathfinding", "car graph", 1, &self.car_graph);
+        report.measure("pathfinding", "bike graph", 1, &self.bike_graph);
+        report.measure("pathfinding", "bus graph", 1, &self.bus_graph);
+        report.measure("pathfinding", "train graph", 1, &self.train_graph);
+        report.measure("pathfinding", "walking graph", 1, &self.walking_graph);
+        report.measure(
+            "pathfinding",
+            "walking with transit graph",
+            1,
+            &self.walking_with_transit_graph,
+        );
+    }
+
     /// Quickly create an invalid pathfinder, just to make borrow checking / initialization order
     /// work.
     pub fn empty() -> Pathfinder {
@@ -84,39 +99,40 @@ impl Pathfinder {
         engine: &CreateEngine,
         timer: &mut Timer,
     ) -> Pathfinder {
-        timer.start("prepare pathfinding for cars");
-        let car_graph = VehiclePathfinder::new(map, PathConstraints::Car, &params, engine);
-        timer.stop("prepare pathfinding for cars");
-
-        // The edge weights for bikes are so different from the driving graph that reusing the node
-        // ordering actually hurts!
-        timer.start("prepare pathfinding for bikes");
-        let bike_graph = VehiclePathfinder::new(map, PathConstraints::Bike, &params, engine);
-        timer.stop("prepare pathfinding for bikes");
-
-        timer.start("prepare pathfinding for buses");
-        let bus_graph = VehiclePathfinder::new(
-            map,
-            PathConstraints::Bus,
-            &params,
-            &car_graph.engine.reuse_ordering(),
-        );
-        timer.stop("prepare pathfinding for buses");
-
-        // Light rail networks are absolutely tiny; using a contraction hierarchy for them is
-        // overkill. And in fact, it costs a bit of memory and file size, so don't do it!
-        timer.start("prepare pathfinding for trains");
-        let train_graph = VehiclePathfinder::new(
-            map,
-            PathConstraints::Train,
-            &params,
-            &CreateEngine::Dijkstra,
-        );
-        timer.stop("prepare pathfinding for trains");
-
-        timer.start("prepare pathfinding for pedestrians");
-        let walking_graph = SidewalkPathfinder::new(map, None, engine);
-        timer.stop("prepare pathfinding for pedestrians");
+        // Each mode's graph is independent, except buses reuse the node ordering from cars, so
+        // build them at the same time. Contracting the graphs is most of the time spent importing
+        // a map.
+        timer.start("prepare pathfinding for all modes");
+        #[cfg(not(target_arch = "wasm32"))]
+        let (car_graph, bike_graph, bus_graph, train_graph, walking_graph) =
+            std::thread::scope(|s| {
+                let bike = s.spawn(|| Pathfinder::bike_graph(map, &params, engine));
+                let train = s.spawn(|| Pathfinder::train_graph(map, &params));
+                let walking = s.spawn(|| SidewalkPathfinder::new(map, None, engine));
+                let car_graph = VehiclePathfinder::new(map, PathConstraints::Car, &params, engine);
+                let bus_graph = Pathfinder::bus_graph(map, &params, &car_graph);
+                (
+                    car_graph,
+                    bike.join().unwrap(),
+                    bus_graph,
+                    train.join().unwrap(),
+                    walking.join().unwrap(),
+                )
+            });
+        // No threads in wasm
+        #[cfg(target_arch = "wasm32")]
+        let (car_graph, bike_graph, bus_graph, train_graph, walking_graph) = {
+            let car_graph = VehiclePathfinder::new(map, PathConstraints::Car, &params, engine);
+            let bus_graph = Pathfinder::bus_graph(map, &params, &car_graph);
+            (
+                car_graph,
+                Pathfinder::bike_graph(map, &params, engine),
+                bus_graph,
+                Pathfinder::train_graph(map, &params),
+                SidewalkPathfinder::new(map, None, engine),
+            )
+        };
+        timer.stop("prepare pathfinding for all modes");
 
         // Transit routes haven't been created yet, so defer this step
         let walking_with_transit_graph = SidewalkPathfinder::empty();
@@ -134,6 +150,31 @@ impl Pathfinder {
         }
     }
 
+    // The edge weights for bikes are so different from the driving graph that reusing the node
+    // ordering actually hurts!
+    fn bike_graph(map: &Map, params: &RoutingParams, engine: &CreateEngine) -> VehiclePathfinder {
+        VehiclePathfinder::new(map, PathConstraints::Bike, params, engine)
+    }
+
+    fn bus_graph(
+        map: &Map,
+        params: &RoutingParams,
+        car_graph: &VehiclePathfinder,
+    ) -> VehiclePathfinder {
+        VehiclePathfinder::new(
+            map,
+            PathConstraints::Bus,
+            params,
+            &car_graph.engine.reuse_ordering(),
+        )
+    }
+
+    // Light rail networks are absolutely tiny; using a contraction hierarchy for them is overkill.
+    // And in fact, it costs a bit of memory and file size, so don't do it!
+    fn train_graph(map: &Map, params: &RoutingParams) -> VehiclePathfinder {
+        VehiclePathfinder::new(map, PathConstraints::Train, params, &CreateEngine::Dijkstra)
+    }
+
     /// Create a new Pathfinder with custom routing params that can only serve some modes. Fast to
     /// create, slow to use.
     pub fn new_dijkstra(
@@ -297,31 +338,65 @@ impl Pathfinder {
             .should_use_transit(map, start, end)
     }
 
-    pub(crate) fn apply_edits(&mut self, map: &Map, timer: &mut Timer) {
+    /// Are any modes temporarily using slower queries after a quick update?
+    pub(crate) fn is_stale(&self) -> bool {
+        self.car_graph.is_stale()
+            || self.bike_graph.is_stale()
+            || self.bus_graph.is_stale()
+            || self.train_graph.is_stale()
+            || self.walking_graph.is_stale()
+            || self.walking_with_transit_graph.is_stale()
+    }
+
+    /// Update a pathfinder created with `new_ch` or `new_dijkstra` after map edits, using new
+    /// routing params. Modes that the edits and params don't affect are left alone, and the others
+    /// reuse their previous node ordering, which is much faster than creating a new pathfinder.
+    pub fn apply_edits_with_params(&mut self, map: &Map, params: RoutingParams, timer: &mut Timer) {
+        for graph in [
+            &mut self.car_graph,
+            &mut self.bike_graph,
+            &mut self.bus_graph,
+            &mut self.train_graph,
+        ] {
+            graph.set_params(&params);
+        }
+        self.params = params;
+        self.apply_edits(map, false, timer);
+    }
+
+    /// When `quick` is true, any modes affected by the edits switch to slower Dijkstra queries,
+    /// instead of rebuilding contraction hierarchies.
+    pub(crate) fn apply_edits(&mut self, map: &Map, quick: bool, timer: &mut Timer) {
         timer.start("apply edits to car pathfinding");
-        self.car_graph.apply_edits(map);
+        self.car_graph.apply_edits(map, quick);
         timer.stop("apply edits to car pathfinding");
 
         timer.start("apply edits to bike pathfinding");
-        self.bike_graph.apply_edits(map);
+        self.bike_graph.apply_edits(map, quick);
         timer.stop("apply edits to bike pathfinding");
 
         timer.start("apply edits to bus pathfinding");
-        self.bus_graph.apply_edits(map);
+        self.bus_graph.apply_edits(map, quick);
         timer.stop("apply edits to bus pathfinding");
 
         timer.start("apply edits to train pathfinding");
-        self.train_graph.apply_edits(map);
+        self.train_graph.apply_edits(map, quick);
         timer.stop("apply edits to train pathfinding");
 
         timer.start("apply edits to pedestrian pathfinding");
-        self.walking_graph.apply_edits(map, None);
+        self.walking_graph.apply_edits(map, None, quick);
         timer.stop("apply edits to pedestrian pathfinding");
 
         timer.start("apply edits to pedestrian using transit pathfinding");
-        self.walking_with_transit_graph
-            .apply_edits(map, Some((&self.bus_graph, &self.train_graph)));
+        self.walking_with_transit_graph.apply_edits(
+            map,
+            Some((&self.bus_graph, &self.train_graph)),
+            quick,
+        );
         timer.stop("apply edits to pedestrian using transit pathfinding");
+
+        // Pathfinders with custom params were built for the old map
+        self.cached_alternatives = ThreadLocal::new();
     }
 }
 
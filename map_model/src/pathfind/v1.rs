@@ -8,8 +8,8 @@ use abstutil::prettyprint_usize;
 use geom::{Distance, Duration, PolyLine, Polygon, Ring, Speed, EPSILON_DIST};
 
 use crate::{
-    BuildingID, DirectedRoadID, LaneID, Map, PathConstraints, Position, RoadID, Traversable,
-    TurnID, UberTurn,
+    BuildingID, Crossing, DirectedRoadID, LaneID, Map, PathConstraints, Position, RoadID,
+    Traversable, TurnID, UberTurn,
 };
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -40,6 +40,36 @@ impl PathStep {
         self.as_traversable().as_turn()
     }
 
+    /// Pedestrians cross a road mid-block by following one sidewalk, then immediately the other
+    /// sidewalk of the same road. If that's what happens between this step and the next, returns
+    /// the crossing used and how far along each sidewalk it is.
+    pub fn mid_block_crossing_to<'a>(
+        &self,
+        next: PathStep,
+        map: &'a Map,
+    ) -> Option<(&'a Crossing, Distance, Distance)> {
+        let (l1, from) = match *self {
+            PathStep::Lane(l) => (map.get_l(l), map.get_l(l).src_i),
+            PathStep::ContraflowLane(l) => (map.get_l(l), map.get_l(l).dst_i),
+            PathStep::Turn(_) | PathStep::ContraflowTurn(_) => return None,
+        };
+        let (l2, to) = match next {
+            PathStep::Lane(l) => (map.get_l(l), map.get_l(l).dst_i),
+            PathStep::ContraflowLane(l) => (map.get_l(l), map.get_l(l).src_i),
+            PathStep::Turn(_) | PathStep::ContraflowTurn(_) => return None,
+        };
+        if l1.id.road != l2.id.road || l1.id == l2.id {
+            return None;
+        }
+        let road = map.get_r(l1.id.road);
+        let crossing = road.best_mid_block_crossing(from, to)?;
+        Some((
+            crossing,
+            road.crossing_dist_along_lane(crossing, l1),
+            road.crossing_dist_along_lane(crossing, l2),
+        ))
+    }
+
     // start is relative to the start of the actual geometry -- so from the lane's real start for
     // ContraflowLane.
     fn exact_slice(
@@ -138,7 +138,7 @@ impl PathV2 {
                 PathStepV2::Along(dr) | PathStepV2::Contraflow(dr) => {
                     let road = map.get_r(dr.road);
                     dist = road.length();
-                    speed = road.speed_limit;
+                    speed = road.vehicle_speed_limit();
 
                     if let Some(penalty) = main_road_penalty {
                         if road.get_rank() != osm::RoadRank::Local {
@@ -151,8 +151,8 @@ impl PathV2 {
                         dist = movement.geom.length();
                         speed = map
                             .get_r(m.from.road)
-                            .speed_limit
-                            .min(map.get_r(m.to.road).speed_limit);
+                            .vehicle_speed_limit()
+                            .min(map.get_r(m.to.road).vehicle_speed_limit());
                     } else {
                         // Assume it's a SharedSidewalkCorner and just skip
                         continue;
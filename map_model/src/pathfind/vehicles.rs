@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize};
 use abstutil::MultiMap;
 use geom::Duration;
 
-use crate::pathfind::engine::{CreateEngine, PathfindEngine};
+use crate::pathfind::engine::{CreateEngine, EngineUpdates, PathfindEngine};
 use crate::pathfind::node_map::{deserialize_nodemap, NodeMap};
 use crate::pathfind::uber_turns::{IntersectionCluster, UberTurnV2};
 use crate::pathfind::zone_cost;
@@ -26,6 +26,7 @@ pub struct VehiclePathfinder {
     constraints: PathConstraints,
     params: RoutingParams,
     pub engine: PathfindEngine,
+    updates: EngineUpdates,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
@@ -42,6 +43,7 @@ impl VehiclePathfinder {
             constraints: PathConstraints::Car,
             params: RoutingParams::default(),
             engine: PathfindEngine::Empty,
+            updates: EngineUpdates::default(),
         }
     }
 
@@ -76,6 +78,7 @@ impl VehiclePathfinder {
         }
 
         let input_graph = make_input_graph(constraints, &nodes, &uber_turns, params, map);
+        let updates = EngineUpdates::new(&input_graph);
         let engine = engine.create(input_graph);
 
         VehiclePathfinder {
@@ -84,6 +87,7 @@ impl VehiclePathfinder {
             constraints,
             params: params.clone(),
             engine,
+            updates,
         }
     }
 
@@ -137,15 +141,21 @@ impl VehiclePathfinder {
         Some(PathV2::from_roads(road_steps, req, cost, uber_turns, map))
     }
 
-    pub fn apply_edits(&mut self, map: &Map) {
+    pub fn is_stale(&self) -> bool {
+        self.updates.is_stale()
+    }
+
+    pub fn set_params(&mut self, params: &RoutingParams) {
+        self.params = params.clone();
+    }
+
+    pub fn apply_edits(&mut self, map: &Map, quick: bool) {
         if matches!(self.engine, PathfindEngine::Empty) {
             return;
         }
 
         // The NodeMap is just all roads and uber-turns -- it won't change. So we can also reuse
         // the node ordering.
-        // TODO Make sure the result of this is deterministic and equivalent to computing from
-        // scratch.
         let input_graph = make_input_graph(
             self.constraints,
             &self.nodes,
@@ -153,8 +163,7 @@ impl VehiclePathfinder {
             &self.params,
             map,
         );
-        let engine = self.engine.reuse_ordering().create(input_graph);
-        self.engine = engine;
+        self.updates.apply(&mut self.engine, input_graph, quick);
     }
 
     pub fn all_costs_from(&self, start: Position, map: &Map) -> HashMap<DirectedRoadID, Duration> {
@@ -360,18 +369,24 @@ pub fn vehicle_cost(
         } else {
             -road.percent_incline
         };
-        if percent_incline >= 0.08 {
-            multiplier *= params.avoid_steep_incline_penalty;
+        // Gentle slopes barely matter, but the penalty keeps growing for steeper climbs. At the
+        // 8% "steep" threshold, the full penalty applies.
+        if percent_incline >= 0.03 {
+            multiplier *= 1.0 + (params.avoid_steep_incline_penalty - 1.0) * percent_incline / 0.08;
         }
     }
 
     if constraints == PathConstraints::Bike
         && (params.avoid_high_stress - 1.0).abs() > f64::EPSILON
-        && road.high_stress_for_bikes(map, dr.dir)
+        && road.level_of_traffic_stress(map, dr.dir, None) > Some(params.stress_tolerance)
     {
         multiplier *= params.avoid_high_stress;
     }
 
+    if road.shared_space && matches!(constraints, PathConstraints::Car | PathConstraints::Bus) {
+        multiplier *= params.shared_space_penalty;
+    }
+
     let mut extra = zone_cost(mvmnt, constraints, map);
     // Penalize unprotected turns at a stop sign from smaller to larger roads.
     if map.is_unprotected_turn(dr.road, mvmnt.to.road, movement.turn_type) {
@@ -384,5 +399,9 @@ pub fn vehicle_cost(
         multiplier *= params.main_road_penalty;
     }
 
+    if let Some(penalty) = params.road_penalties.get(&dr.road) {
+        multiplier *= penalty;
+    }
+
     Some(multiplier * base + extra)
 }
@@ -7,14 +7,15 @@ use serde::{Deserialize, Serialize};
 
 use geom::{Distance, Duration};
 
-use crate::pathfind::engine::{CreateEngine, PathfindEngine};
+use crate::pathfind::engine::{CreateEngine, EngineUpdates, PathfindEngine};
 use crate::pathfind::node_map::{deserialize_nodemap, NodeMap};
 use crate::pathfind::vehicles::VehiclePathfinder;
 use crate::pathfind::zone_cost;
 use crate::pathfind::{round, unround};
 use crate::{
-    DirectedRoadID, IntersectionID, Map, PathConstraints, PathRequest, PathStep, PathStepV2,
-    PathV2, Position, TransitRoute, TransitRouteID, TransitStopID, TurnType,
+    Crossing, DirectedRoadID, Direction, IntersectionID, Lane, Map, PathConstraints, PathRequest,
+    PathStep, PathStepV2, PathV2, Position, Road, TransitRoute, TransitRouteID, TransitStopID,
+    TurnType,
 };
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -23,6 +24,7 @@ pub struct SidewalkPathfinder {
     nodes: NodeMap<WalkingNode>,
     use_transit: bool,
     engine: PathfindEngine,
+    updates: EngineUpdates,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Hash, Serialize, Deserialize)]
@@ -60,6 +62,7 @@ impl SidewalkPathfinder {
             nodes: NodeMap::new(),
             use_transit: false,
             engine: PathfindEngine::Empty,
+            updates: EngineUpdates::default(),
         }
     }
 
@@ -90,27 +93,33 @@ impl SidewalkPathfinder {
         }
 
         let input_graph = make_input_graph(&nodes, use_transit, map);
+        let updates = EngineUpdates::new(&input_graph);
         let engine = engine.create(input_graph);
 
         SidewalkPathfinder {
             nodes,
             use_transit: use_transit.is_some(),
             engine,
+            updates,
         }
     }
 
+    pub fn is_stale(&self) -> bool {
+        self.updates.is_stale()
+    }
+
     pub fn apply_edits(
         &mut self,
         map: &Map,
         use_transit: Option<(&VehiclePathfinder, &VehiclePathfinder)>,
+        quick: bool,
     ) {
         if matches!(self.engine, PathfindEngine::Empty) {
             return;
         }
 
         let input_graph = make_input_graph(&self.nodes, use_transit, map);
-        let engine = self.engine.reuse_ordering().create(input_graph);
-        self.engine = engine;
+        self.updates.apply(&mut self.engine, input_graph, quick);
     }
 
     pub fn pathfind(&self, req: PathRequest, map: &Map) -> Option<PathV2> {
@@ -285,8 +294,9 @@ fn make_input_graph(
             ] {
                 let mut cost =
                     l.length() / step.max_speed_along(max_speed, PathConstraints::Pedestrian, map);
-                // TODO Tune this penalty, along with many others.
-                if l.is_shoulder() {
+                // TODO Tune this penalty, along with many others. In a shared space, people walk
+                // down the middle of the street anyway.
+                if l.is_shoulder() && !map.get_r(l.id.road).shared_space {
                     cost = 2.0 * cost;
                 }
                 input_graph.add_edge(pair.0, pair.1, round(cost));
@@ -311,7 +321,10 @@ fn make_input_graph(
                 / PathStep::Turn(t.id).max_speed_along(max_speed, PathConstraints::Pedestrian, map)
                 + zone_cost(t.id.to_movement(map), PathConstraints::Pedestrian, map);
 
-            if t.turn_type == TurnType::UnmarkedCrossing {
+            // People cross a shared space freely
+            if t.turn_type == TurnType::UnmarkedCrossing
+                && !(src.id.road == dst.id.road && map.get_r(src.id.road).shared_space)
+            {
                 // TODO Add to RoutingParams
                 cost = 3.0 * cost;
             }
@@ -321,6 +334,33 @@ fn make_input_graph(
         }
     }
 
+    // Mid-block crossings link the sidewalks on both sides of a road. Add an edge for every
+    // combination of ends, using the best crossing for each.
+    for r in map.all_roads() {
+        let (fwd, back) = match sidewalks_on_both_sides(r) {
+            Some(pair) => pair,
+            None => continue,
+        };
+        for (l1, l2) in [(fwd, back), (back, fwd)] {
+            for from in [r.src_i, r.dst_i] {
+                for to in [r.src_i, r.dst_i] {
+                    if let Some(crossing) = r.best_mid_block_crossing(from, to) {
+                        let n1 = nodes.get(WalkingNode::SidewalkEndpoint(
+                            l1.get_directed_parent(),
+                            l1.dst_i == from,
+                        ));
+                        let n2 = nodes.get(WalkingNode::SidewalkEndpoint(
+                            l2.get_directed_parent(),
+                            l2.dst_i == to,
+                        ));
+                        let cost = mid_block_crossing_cost(map, r, crossing, l1, from, l2, to);
+                        input_graph.add_edge(n1, n2, round(cost));
+                    }
+                }
+            }
+        }
+    }
+
     if let Some(graphs) = use_transit {
         transit_input_graph(&mut input_graph, nodes, map, graphs.0, graphs.1);
     }
@@ -330,6 +370,58 @@ fn make_input_graph(
     input_graph
 }
 
+/// Mid-block crossings need exactly one sidewalk or shoulder on each side of the road.
+fn sidewalks_on_both_sides(r: &Road) -> Option<(&Lane, &Lane)> {
+    let mut fwd = Vec::new();
+    let mut back = Vec::new();
+    for l in &r.lanes {
+        if l.is_walkable() {
+            if l.dir == Direction::Fwd {
+                fwd.push(l);
+            } else {
+                back.push(l);
+            }
+        }
+    }
+    if fwd.len() == 1 && back.len() == 1 {
+        Some((fwd[0], back[0]))
+    } else {
+        None
+    }
+}
+
+/// Walk along `l1` from its `from` end to the crossing, wait for a chance to cross the road, then
+/// walk along `l2` to its `to` end.
+fn mid_block_crossing_cost(
+    map: &Map,
+    r: &Road,
+    crossing: &Crossing,
+    l1: &Lane,
+    from: IntersectionID,
+    l2: &Lane,
+    to: IntersectionID,
+) -> Duration {
+    let max_speed = Some(crate::MAX_WALKING_SPEED);
+
+    let dist1 = r.crossing_dist_along_lane(crossing, l1);
+    let (step1, len1) = if l1.src_i == from {
+        (PathStep::Lane(l1.id), dist1)
+    } else {
+        (PathStep::ContraflowLane(l1.id), l1.length() - dist1)
+    };
+    let dist2 = r.crossing_dist_along_lane(crossing, l2);
+    let (step2, len2) = if l2.dst_i == to {
+        (PathStep::Lane(l2.id), l2.length() - dist2)
+    } else {
+        (PathStep::ContraflowLane(l2.id), dist2)
+    };
+
+    len1 / step1.max_speed_along(max_speed, PathConstraints::Pedestrian, map)
+        + r.get_width() / crate::MAX_WALKING_SPEED
+        + len2 / step2.max_speed_along(max_speed, PathConstraints::Pedestrian, map)
+        + crossing.expected_wait()
+}
+
 fn transit_input_graph(
     input_graph: &mut InputGraph,
     nodes: &NodeMap<WalkingNode>,
@@ -426,8 +518,8 @@ fn walking_path_to_steps(path: Vec<WalkingNode>, map: &Map) -> Vec<PathStepV2> {
             WalkingNode::RideTransit(_) => unreachable!(),
             WalkingNode::LeaveMap(_) => unreachable!(),
         };
-        let r2 = match pair[1] {
-            WalkingNode::SidewalkEndpoint(r, _) => r,
+        let (r2, r2_endpt) = match pair[1] {
+            WalkingNode::SidewalkEndpoint(r, endpt) => (r, endpt),
             WalkingNode::RideTransit(_) => unreachable!(),
             WalkingNode::LeaveMap(_) => unreachable!(),
         };
@@ -438,6 +530,20 @@ fn walking_path_to_steps(path: Vec<WalkingNode>, map: &Map) -> Vec<PathStepV2> {
             } else {
                 steps.push(PathStepV2::Along(r1));
             }
+        } else if r1.road == r2.road {
+            // A mid-block crossing. Walk partway along one sidewalk, cross, then continue along
+            // the other. There's no step for the crossing itself; two consecutive sidewalks of the
+            // same road mean the crossing from Road::best_mid_block_crossing is used.
+            if r1_endpt {
+                steps.push(PathStepV2::Contraflow(r1));
+            } else {
+                steps.push(PathStepV2::Along(r1));
+            }
+            if r2_endpt {
+                steps.push(PathStepV2::Along(r2));
+            } else {
+                steps.push(PathStepV2::Contraflow(r2));
+            }
         } else {
             let i = if r1_endpt {
                 r1.dst_i(map)
@@ -0,0 +1,71 @@
+//! Boolean operations on polygons. `geom` provides the underlying operations, but callers each
+//! had to deal with degenerate input, tiny slivers in the output, and failures on their own. These
+//! wrappers handle all of that the same way everywhere.
+
+use std::panic;
+
+use anyhow::Result;
+
+use geom::Polygon;
+
+/// Pieces of output smaller than this many square meters are just precision noise, so they're
+/// dropped.
+const MIN_AREA: f64 = 0.01;
+
+/// Merge overlapping or touching polygons. The result has one polygon per disconnected piece.
+pub fn union(polygons: Vec<Polygon>) -> Result<Vec<Polygon>> {
+    if polygons.len() <= 1 {
+        return Ok(polygons);
+    }
+    let multipolygon = catch(move || Ok(Polygon::union_all_into_multipolygon(polygons)))?;
+    Ok(clean(
+        multipolygon
+            .0
+            .into_iter()
+            .filter_map(|p| Polygon::try_from(p).ok())
+            .collect(),
+    ))
+}
+
+/// The areas covered by both polygons
+pub fn intersection(p1: &Polygon, p2: &Polygon) -> Result<Vec<Polygon>> {
+    catch(|| p1.intersection(p2)).map(clean)
+}
+
+/// The areas covered by the first polygon, but not the second
+pub fn difference(p1: &Polygon, p2: &Polygon) -> Result<Vec<Polygon>> {
+    catch(|| p1.difference(p2)).map(clean)
+}
+
+/// Clip each polygon to a boundary. If clipping one fails, it's kept as it is, since that's
+/// usually better for drawing than losing it.
+pub fn clip_all(polygons: Vec<Polygon>, boundary: &Polygon) -> Vec<Polygon> {
+    let mut results = Vec::new();
+    for p in polygons {
+        match intersection(&p, boundary) {
+            Ok(list) => {
+                results.extend(list);
+            }
+            Err(err) => {
+                warn!("Couldn't clip polygon to boundary: {}", err);
+                results.push(p);
+            }
+        }
+    }
+    results
+}
+
+fn clean(polygons: Vec<Polygon>) -> Vec<Polygon> {
+    polygons
+        .into_iter()
+        .filter(|p| p.area() >= MIN_AREA)
+        .collect()
+}
+
+// The underlying geometry library can panic on degenerate input, instead of returning an error
+fn catch<T, F: FnOnce() -> Result<T>>(f: F) -> Result<T> {
+    match panic::catch_unwind(panic::AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(_) => bail!("Polygon operation crashed on degenerate input"),
+    }
+}
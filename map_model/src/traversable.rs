@@ -218,7 +218,7 @@ impl Traversable {
         } else {
             debug_assert!(max_speed_on_flat_ground.is_none());
             // Incline doesn't affect cars, buses, or trains
-            road.speed_limit
+            road.vehicle_speed_limit()
         };
 
         let speed = if let Some(s) = max_speed_on_flat_ground {
@@ -240,8 +240,8 @@ impl Traversable {
         // TODO Ignore elevation on turns?
         let base = map
             .get_r(mvmnt.from.road)
-            .speed_limit
-            .min(map.get_r(mvmnt.to.road).speed_limit);
+            .vehicle_speed_limit()
+            .min(map.get_r(mvmnt.to.road).vehicle_speed_limit());
         if let Some(s) = max_speed_on_flat_ground {
             base.min(s)
         } else {
@@ -9,7 +9,7 @@
 //! These types form a pipeline:
 //!
 //! 1) For a given map, find some census data that describes how many people live in different
-//!    areas of the city. (CensusArea)
+//!    areas of the city. (CensusArea, loaded from some PopulationSource)
 //! 2) Take the CensusAreas and turn them into individual CensusPersons, by randomly choosing a
 //!    specific building on the map as their home, and assigning specific attributes based on the
 //!    census data's distribution.
@@ -30,12 +30,15 @@ use map_model::{BuildingID, Map};
 use synthpop::Scenario;
 
 pub use self::distribute_people::distribute_population_to_homes;
+pub use self::import_census::UsCensus;
+pub use self::sources::{EurostatGrid, GeoJsonAreas, PopulationSource, WorldPopRaster};
 
 mod activities;
 mod distribute_people;
 mod import_census;
 mod make_person;
 pub mod od;
+mod sources;
 
 /// Represents aggregate demographic data for some part of a city. These could be census tracts or
 /// blocks, depending what data we find. All of the areas should roughly partition the map -- we
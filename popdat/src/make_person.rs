@@ -283,8 +283,8 @@ fn pick_mode(
     if rng.gen_bool(0.005) {
         return TripMode::Bike;
     }
-    // Try transit if available, or fallback to walking
-    if rng.gen_bool(0.3) {
+    // Try transit if available, or fallback to walking. Expensive fares push people to drive.
+    if rng.gen_bool(0.3 * synthpop::make::transit_fare_multiplier(map)) {
         return TripMode::Transit;
     }
 
@@ -307,7 +307,9 @@ fn create_zones(
                     zone.workplaces.push((b.id, b.amenities.len()));
                 }
                 BuildingType::Commercial(_) => {
-                    zone.workplaces.push((b.id, b.amenities.len()));
+                    // Offices, warehouses, and untagged buildings inferred to be commercial have
+                    // no amenities, but are still workplaces.
+                    zone.workplaces.push((b.id, b.amenities.len().max(1)));
                 }
                 BuildingType::Empty => {}
             }
@@ -0,0 +1,436 @@
+//! Different places publish population data in different formats. Each `PopulationSource` turns
+//! one of them into `CensusArea`s.
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+use geo::{Intersects, MapCoordsInPlace};
+
+use geom::{GPSBounds, LonLat, Polygon};
+
+use crate::CensusArea;
+
+/// Something describing how many people live in different parts of a map.
+pub trait PopulationSource {
+    fn describe(&self) -> String;
+
+    /// Find all areas overlapping the map. The map area and bounds are in map-space.
+    fn load<'a>(
+        &'a self,
+        map_area: &'a Polygon,
+        bounds: &'a GPSBounds,
+    ) -> BoxFuture<'a, Result<Vec<CensusArea>>>;
+}
+
+impl dyn PopulationSource {
+    /// Guess the type of source from a local file's extension: `.geojson` for `GeoJsonAreas`,
+    /// `.csv` for `EurostatGrid`, and `.asc` for `WorldPopRaster`.
+    pub fn from_path(path: String) -> Result<Box<dyn PopulationSource + Send + Sync>> {
+        if path.ends_with(".geojson") {
+            Ok(Box::new(GeoJsonAreas {
+                path,
+                population_property: "population".to_string(),
+            }))
+        } else if path.ends_with(".csv") {
+            Ok(Box::new(EurostatGrid {
+                path,
+                population_column: "TOT_P".to_string(),
+            }))
+        } else if path.ends_with(".asc") {
+            Ok(Box::new(WorldPopRaster {
+                path,
+                block_size: 10,
+            }))
+        } else {
+            bail!("Unknown population data format for {}", path)
+        }
+    }
+}
+
+/// Polygons with a population property, like UK census output areas. The polygons must be in
+/// WGS84.
+pub struct GeoJsonAreas {
+    pub path: String,
+    pub population_property: String,
+}
+
+impl PopulationSource for GeoJsonAreas {
+    fn describe(&self) -> String {
+        format!("areas from {}", self.path)
+    }
+
+    fn load<'a>(
+        &'a self,
+        map_area: &'a Polygon,
+        bounds: &'a GPSBounds,
+    ) -> BoxFuture<'a, Result<Vec<CensusArea>>> {
+        Box::pin(async move {
+            let map_area = map_area_to_gps(map_area, bounds);
+            let geojson: geojson::GeoJson =
+                String::from_utf8(abstio::slurp_file(&self.path)?)?.parse()?;
+            let collection = geojson::FeatureCollection::try_from(geojson)?;
+
+            let mut results = Vec::new();
+            for feature in collection.features {
+                let population = match feature
+                    .property(&self.population_property)
+                    .and_then(|x| x.as_f64().or_else(|| x.as_str()?.parse::<f64>().ok()))
+                {
+                    Some(x) => x.round() as usize,
+                    None => {
+                        warn!("skipping feature with missing {}", self.population_property);
+                        continue;
+                    }
+                };
+                let geometry = match feature.geometry {
+                    Some(geometry) => geometry,
+                    None => continue,
+                };
+                let polygons: Vec<geo::Polygon> = match geo::Geometry::try_from(geometry)? {
+                    geo::Geometry::Polygon(p) => vec![p],
+                    geo::Geometry::MultiPolygon(mp) => mp.0,
+                    _ => {
+                        warn!("skipping unexpected geometry");
+                        continue;
+                    }
+                };
+                // Split the population between parts of a multipolygon by area
+                let total_area: f64 = polygons.iter().map(geo::Area::unsigned_area).sum();
+                if total_area == 0.0 {
+                    warn!("skipping feature with no area");
+                    continue;
+                }
+                for polygon in polygons {
+                    let pct = geo::Area::unsigned_area(&polygon) / total_area;
+                    results.extend(to_census_area(
+                        polygon,
+                        (pct * population as f64).round() as usize,
+                        &map_area,
+                        bounds,
+                    ));
+                }
+            }
+            Ok(results)
+        })
+    }
+}
+
+/// The Eurostat GEOSTAT population grid (https://ec.europa.eu/eurostat/web/gisco/geodata/grids),
+/// as CSV. Cells are identified by a `GRD_ID` in the ETRS89-LAEA projection (EPSG:3035), like
+/// `1kmN2689E4337` or `CRS3035RES1000mN2689000E4337000`.
+pub struct EurostatGrid {
+    pub path: String,
+    /// `TOT_P` in the 2011 grid, `T` in 2021
+    pub population_column: String,
+}
+
+impl PopulationSource for EurostatGrid {
+    fn describe(&self) -> String {
+        format!("Eurostat grid from {}", self.path)
+    }
+
+    fn load<'a>(
+        &'a self,
+        map_area: &'a Polygon,
+        bounds: &'a GPSBounds,
+    ) -> BoxFuture<'a, Result<Vec<CensusArea>>> {
+        Box::pin(async move {
+            let map_area = map_area_to_gps(map_area, bounds);
+            let mut reader = csv::Reader::from_reader(fs_err::File::open(&self.path)?);
+            let headers = reader.headers()?.clone();
+            let id_idx = headers
+                .iter()
+                .position(|x| x == "GRD_ID")
+                .ok_or_else(|| anyhow!("{} has no GRD_ID column", self.path))?;
+            let pop_idx = headers
+                .iter()
+                .position(|x| x == self.population_column)
+                .ok_or_else(|| anyhow!("{} has no {} column", self.path, self.population_column))?;
+
+            let mut results = Vec::new();
+            for rec in reader.records() {
+                let rec = rec?;
+                let (x, y, size) = parse_grid_id(&rec[id_idx])?;
+                let population = rec[pop_idx].parse::<f64>()?.round() as usize;
+                if population == 0 {
+                    continue;
+                }
+                let corners = [(x, y), (x + size, y), (x + size, y + size), (x, y + size)];
+                let mut ring: Vec<geo::Coord> = corners
+                    .iter()
+                    .map(|(x, y)| {
+                        let gps = laea_to_wgs84(*x, *y);
+                        geo::Coord {
+                            x: gps.x(),
+                            y: gps.y(),
+                        }
+                    })
+                    .collect();
+                ring.push(ring[0]);
+                results.extend(to_census_area(
+                    geo::Polygon::new(ring.into(), Vec::new()),
+                    population,
+                    &map_area,
+                    bounds,
+                ));
+            }
+            Ok(results)
+        })
+    }
+}
+
+/// A WorldPop (https://www.worldpop.org) people-per-cell raster in WGS84, converted to an ESRI ASCII
+/// grid, with something like `gdal_translate -of AAIGrid input.tif output.asc`.
+pub struct WorldPopRaster {
+    pub path: String,
+    /// Cells are tiny and often fractional, so group this many cells in each direction into one
+    /// area
+    pub block_size: usize,
+}
+
+impl PopulationSource for WorldPopRaster {
+    fn describe(&self) -> String {
+        format!("WorldPop raster from {}", self.path)
+    }
+
+    fn load<'a>(
+        &'a self,
+        map_area: &'a Polygon,
+        bounds: &'a GPSBounds,
+    ) -> BoxFuture<'a, Result<Vec<CensusArea>>> {
+        Box::pin(async move {
+            let map_area = map_area_to_gps(map_area, bounds);
+            let contents = String::from_utf8(abstio::slurp_file(&self.path)?)?;
+            let blocks = parse_ascii_grid(&contents, self.block_size)
+                .map_err(|err| anyhow!("can't read {}: {}", self.path, err))?;
+
+            let mut results = Vec::new();
+            for (rect, population) in blocks {
+                results.extend(to_census_area(
+                    rect.to_polygon(),
+                    population.round() as usize,
+                    &map_area,
+                    bounds,
+                ));
+            }
+            Ok(results)
+        })
+    }
+}
+
+/// Parses an ESRI ASCII grid in WGS84, summing cells into square blocks of `block_size` cells.
+/// Returns the area and total of every block with something in it.
+fn parse_ascii_grid(contents: &str, block_size: usize) -> Result<Vec<(geo::Rect, f64)>> {
+    let mut lines = contents.lines();
+
+    let mut header = std::collections::HashMap::new();
+    for _ in 0..6 {
+        let line = lines.next().ok_or_else(|| anyhow!("incomplete header"))?;
+        let mut parts = line.split_whitespace();
+        if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+            header.insert(key.to_lowercase(), value.parse::<f64>()?);
+        }
+    }
+    let get = |key: &str| {
+        header
+            .get(key)
+            .cloned()
+            .ok_or_else(|| anyhow!("{} is missing from the header", key))
+    };
+    let ncols = get("ncols")? as usize;
+    let nrows = get("nrows")? as usize;
+    let xll = get("xllcorner")?;
+    let yll = get("yllcorner")?;
+    let cellsize = get("cellsize")?;
+    let nodata = header.get("nodata_value").cloned();
+
+    // Sum the cells into blocks. Rows go from north to south.
+    let block = block_size.max(1);
+    let mut per_block: std::collections::BTreeMap<(usize, usize), f64> =
+        std::collections::BTreeMap::new();
+    for (row, line) in lines.take(nrows).enumerate() {
+        for (col, value) in line.split_whitespace().take(ncols).enumerate() {
+            let value = value.parse::<f64>()?;
+            if Some(value) == nodata || value <= 0.0 {
+                continue;
+            }
+            *per_block.entry((row / block, col / block)).or_insert(0.0) += value;
+        }
+    }
+
+    let block_degrees = cellsize * block as f64;
+    let top = yll + cellsize * nrows as f64;
+    Ok(per_block
+        .into_iter()
+        .map(|((row, col), total)| {
+            let x1 = xll + block_degrees * col as f64;
+            let y2 = top - block_degrees * row as f64;
+            let rect = geo::Rect::new(
+                geo::Coord {
+                    x: x1,
+                    y: y2 - block_degrees,
+                },
+                geo::Coord {
+                    x: x1 + block_degrees,
+                    y: y2,
+                },
+            );
+            (rect, total)
+        })
+        .collect())
+}
+
+pub(crate) fn map_area_to_gps(map_area: &Polygon, bounds: &GPSBounds) -> geo::Polygon {
+    let mut polygon: geo::Polygon = map_area.clone().into();
+    polygon.map_coords_in_place(|c| {
+        let projected = geom::Pt2D::new(c.x, c.y).to_gps(bounds);
+        (projected.x(), projected.y()).into()
+    });
+    polygon
+}
+
+/// Transforms an area in WGS84 to map-space, if it overlaps the map.
+fn to_census_area(
+    mut polygon: geo::Polygon,
+    population: usize,
+    map_area: &geo::Polygon,
+    bounds: &GPSBounds,
+) -> Option<CensusArea> {
+    if population == 0 || !polygon.intersects(map_area) {
+        return None;
+    }
+    polygon.map_coords_in_place(|c| LonLat::new(c.x, c.y).to_pt(bounds).into());
+    Some(CensusArea {
+        polygon,
+        population,
+    })
+}
+
+/// Returns the southwest corner and size of a grid cell, in meters.
+fn parse_grid_id(id: &str) -> Result<(f64, f64, f64)> {
+    let n = id
+        .rfind('N')
+        .ok_or_else(|| anyhow!("can't parse grid ID {}", id))?;
+    let e = id
+        .rfind('E')
+        .ok_or_else(|| anyhow!("can't parse grid ID {}", id))?;
+    let y = id[n + 1..e].parse::<f64>()?;
+    let x = id[e + 1..].parse::<f64>()?;
+    if let Some(res) = id.strip_prefix("CRS3035RES") {
+        // CRS3035RES1000mN2689000E4337000, in meters
+        let size = res[..res.find('m').unwrap_or(0)].parse::<f64>()?;
+        Ok((x, y, size))
+    } else {
+        // 1kmN2689E4337, in units of the cell size
+        let size = id[..n]
+            .strip_suffix("km")
+            .map(|x| x.parse::<f64>().map(|km| km * 1000.0))
+            .or_else(|| id[..n].strip_suffix('m').map(|x| x.parse::<f64>()))
+            .ok_or_else(|| anyhow!("can't parse grid ID {}", id))??;
+        Ok((x * size, y * size, size))
+    }
+}
+
+/// Inverts the Lambert azimuthal equal-area projection used by Eurostat (EPSG:3035), following
+/// the formulas in IOGP Guidance Note 7-2.
+fn laea_to_wgs84(x: f64, y: f64) -> LonLat {
+    // GRS80 ellipsoid and the EPSG:3035 origin
+    let a = 6_378_137.0;
+    let e2: f64 = 0.006_694_380_022_90;
+    let e = e2.sqrt();
+    let lat0 = 52.0_f64.to_radians();
+    let lon0 = 10.0_f64.to_radians();
+    let (false_easting, false_northing) = (4_321_000.0, 3_210_000.0);
+
+    let q = |sin_lat: f64| {
+        (1.0 - e2)
+            * (sin_lat / (1.0 - e2 * sin_lat * sin_lat)
+                - (1.0 / (2.0 * e)) * ((1.0 - e * sin_lat) / (1.0 + e * sin_lat)).ln())
+    };
+    let qp = q(1.0);
+    let beta0 = (q(lat0.sin()) / qp).asin();
+    let rq = a * (qp / 2.0).sqrt();
+    let d = a * (lat0.cos() / (1.0 - e2 * lat0.sin().powi(2)).sqrt()) / (rq * beta0.cos());
+
+    let dx = x - false_easting;
+    let dy = y - false_northing;
+    let rho = ((dx / d).powi(2) + (d * dy).powi(2)).sqrt();
+    if rho == 0.0 {
+        return LonLat::new(lon0.to_degrees(), lat0.to_degrees());
+    }
+    let c = 2.0 * (rho / (2.0 * rq)).asin();
+    let beta = (c.cos() * beta0.sin() + d * dy * c.sin() * beta0.cos() / rho).asin();
+    let lat = beta
+        + (e2 / 3.0 + 31.0 * e2.powi(2) / 180.0 + 517.0 * e2.powi(3) / 5040.0) * (2.0 * beta).sin()
+        + (23.0 * e2.powi(2) / 360.0 + 251.0 * e2.powi(3) / 3780.0) * (4.0 * beta).sin()
+        + (761.0 * e2.powi(3) / 45360.0) * (6.0 * beta).sin();
+    let lon = lon0
+        + (dx * c.sin())
+            .atan2(d * rho * beta0.cos() * c.cos() - d * d * dy * beta0.sin() * c.sin());
+    LonLat::new(lon.to_degrees(), lat.to_degrees())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_grid_id() {
+        let expected = (4_337_000.0, 2_689_000.0, 1000.0);
+        assert_eq!(parse_grid_id("1kmN2689E4337").unwrap(), expected);
+        assert_eq!(
+            parse_grid_id("CRS3035RES1000mN2689000E4337000").unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_grid_id("100mN26890E43370").unwrap(),
+            (4_337_000.0, 2_689_000.0, 100.0)
+        );
+        assert!(parse_grid_id("N2689E4337").is_err());
+        assert!(parse_grid_id("1kmE4337").is_err());
+    }
+
+    #[test]
+    fn test_laea_to_wgs84() {
+        // The worked example for EPSG:3035 from IOGP Guidance Note 7-2
+        let pt = laea_to_wgs84(3_962_799.45, 2_999_718.85);
+        assert!((pt.x() - 5.0).abs() < 1e-6, "longitude {}", pt.x());
+        assert!((pt.y() - 50.0).abs() < 1e-6, "latitude {}", pt.y());
+
+        // The projection's origin
+        let pt = laea_to_wgs84(4_321_000.0, 3_210_000.0);
+        assert!((pt.x() - 10.0).abs() < 1e-9 && (pt.y() - 52.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_ascii_grid() {
+        let contents = "ncols 4
+nrows 2
+xllcorner 10.0
+yllcorner 50.0
+cellsize 0.5
+NODATA_value -9999
+1.5 2.5 -9999 0
+3 -9999 4 0.25
+";
+        let blocks = parse_ascii_grid(contents, 2).unwrap();
+        assert_eq!(blocks.len(), 2);
+
+        // The western block covers all 4 cells there, with one missing
+        let (rect, total) = blocks[0];
+        assert_eq!(total, 7.0);
+        assert_eq!((rect.min().x, rect.min().y), (10.0, 50.0));
+        assert_eq!((rect.max().x, rect.max().y), (11.0, 51.0));
+
+        let (rect, total) = blocks[1];
+        assert_eq!(total, 4.25);
+        assert_eq!((rect.min().x, rect.min().y), (11.0, 50.0));
+
+        // Without grouping, each cell is its own block, with the first row to the north
+        let blocks = parse_ascii_grid(contents, 1).unwrap();
+        assert_eq!(blocks.len(), 5);
+        assert_eq!(blocks[0].0.min().y, 50.5);
+        assert_eq!(blocks[0].1, 1.5);
+
+        assert!(parse_ascii_grid("ncols 4\nnrows 2\n", 1).is_err());
+    }
+}
@@ -10,17 +10,23 @@ use serde::{Deserialize, Serialize};
 
 use abstio::{CityName, MapName};
 use abstutil::{
-    deserialize_btreemap, deserialize_multimap, serialize_btreemap, serialize_multimap, MultiMap,
-    Tags,
+    deserialize_btreemap, deserialize_multimap, serialize_btreemap, serialize_multimap,
+    FormatVersion, MultiMap, Tags,
 };
-use geom::{Distance, PolyLine, Polygon, Pt2D};
+use geom::{Distance, PolyLine, Polygon, Pt2D, Time};
 
-pub use self::types::{Amenity, AmenityType, AreaType};
+pub use self::types::{Amenity, AmenityType, AreaType, LandUse};
 
 mod types;
 
+/// Bump this whenever the binary layout of `RawMap` or anything inside it changes. Raw maps with a
+/// different version can't be loaded, and must be imported again.
+pub const RAW_MAP_FORMAT_VERSION: u32 = 1;
+
 #[derive(Serialize, Deserialize)]
 pub struct RawMap {
+    /// Must come first
+    pub format_version: FormatVersion<RAW_MAP_FORMAT_VERSION>,
     pub name: MapName,
     pub streets: StreetNetwork,
     #[serde(
@@ -33,6 +39,8 @@ pub struct RawMap {
     pub parking_aisles: Vec<(osm::WayID, Vec<Pt2D>)>,
     pub transit_routes: Vec<RawTransitRoute>,
     pub census_zones: Vec<(Polygon, CensusZone)>,
+    /// Residential, commercial, and industrial landuse areas, only used to classify buildings
+    pub landuse: Vec<(Polygon, LandUse)>,
     #[serde(
         serialize_with = "serialize_btreemap",
         deserialize_with = "deserialize_btreemap"
@@ -70,6 +78,7 @@ pub struct RawMap {
 impl RawMap {
     pub fn blank(name: MapName) -> RawMap {
         RawMap {
+            format_version: FormatVersion,
             name,
             streets: StreetNetwork::blank(),
             buildings: BTreeMap::new(),
@@ -78,6 +87,7 @@ impl RawMap {
             parking_aisles: Vec::new(),
             transit_routes: Vec::new(),
             census_zones: Vec::new(),
+            landuse: Vec::new(),
             transit_stops: BTreeMap::new(),
             bus_routes_on_roads: MultiMap::new(),
             osm_tags: BTreeMap::new(),
@@ -136,7 +146,9 @@ pub struct RawTransitRoute {
     /// Entries into transit_stops
     pub stops: Vec<String>,
     pub route_type: RawTransitType,
-    // TODO Schedule
+    /// When vehicles depart from the first stop, sorted. If empty, the schedule is unknown.
+    #[serde(default)]
+    pub spawn_times: Vec<Time>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -176,6 +188,11 @@ pub struct ExtraRoadData {
     pub barrier_nodes: Vec<Pt2D>,
     /// Crossing nodes along this road's original center line.
     pub crossing_nodes: Vec<(Pt2D, CrossingType)>,
+    /// Raised kerbs along this road's original center line, without a dropped section for
+    /// wheelchairs. These're usually tagged on crossings.
+    pub raised_kerbs: Vec<Pt2D>,
+    /// Is there a gate closed to the public somewhere along this road?
+    pub private_gate: bool,
 }
 
 impl ExtraRoadData {
@@ -187,6 +204,8 @@ impl ExtraRoadData {
             crosswalk_backward: true,
             barrier_nodes: Vec::new(),
             crossing_nodes: Vec::new(),
+            raised_kerbs: Vec::new(),
+            private_gate: false,
         }
     }
 }
@@ -202,4 +221,6 @@ pub struct ExtraPOI {
 pub enum ExtraPOIType {
     LondonUndergroundStation(String),
     NationalRailStation(String),
+    /// A bike rack or shelter, with how many bikes it holds
+    BicycleParking(usize),
 }
@@ -180,6 +180,26 @@ impl AmenityType {
     }
 }
 
+/// What a patch of land is mainly used for, according to OSM `landuse` tags. Used to guess what
+/// untagged buildings are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LandUse {
+    Residential,
+    Commercial,
+    Industrial,
+}
+
+impl LandUse {
+    pub fn from_tags(tags: &Tags) -> Option<LandUse> {
+        match tags.get("landuse")?.as_str() {
+            "residential" => Some(LandUse::Residential),
+            "commercial" | "retail" => Some(LandUse::Commercial),
+            "industrial" | "port" => Some(LandUse::Industrial),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum AreaType {
     Park,
@@ -1,4 +1,5 @@
-use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::fmt::Write;
 
 use serde::{Deserialize, Serialize};
@@ -6,11 +7,12 @@ use serde::{Deserialize, Serialize};
 use abstutil::Counter;
 use geom::{Duration, Pt2D, Time};
 use map_model::{
-    CompressedMovementID, IntersectionID, LaneID, Map, MovementID, ParkingLotID, Path, PathRequest,
-    RoadID, TransitRouteID, TransitStopID, Traversable, TurnID,
+    BuildingID, CompressedMovementID, IntersectionID, LaneID, Map, MovementID, ParkingLotID, Path,
+    PathRequest, RoadID, TransitRouteID, TransitStopID, Traversable, TurnID,
 };
-use synthpop::TripMode;
+use synthpop::{TrafficCounts, TripMode};
 
+use crate::analytics_spill::{IntersectionDelays, SpilledAnalytics, TripLog};
 use crate::{AgentID, AgentType, AlertLocation, CarID, Event, ParkingSpot, TripID, TripPhaseType};
 
 /// As a simulation runs, different pieces emit Events. The Analytics object listens to these,
@@ -39,6 +41,8 @@ pub struct Analytics {
     /// For each passenger boarding, how long did they wait at the stop?
     pub passengers_boarding: BTreeMap<TransitStopID, Vec<(Time, TransitRouteID, Duration)>>,
     pub passengers_alighting: BTreeMap<TransitStopID, Vec<(Time, TransitRouteID)>>,
+    /// For each passenger boarding, the fare they paid in cents. Free rides are recorded as 0.
+    pub transit_fares: BTreeMap<TransitRouteID, Vec<(Time, usize)>>,
 
     pub started_trips: BTreeMap<TripID, Time>,
     /// Finish time, ID, mode, trip duration if successful (or None if cancelled)
@@ -54,14 +58,34 @@ pub struct Analytics {
     /// Only for traffic signals. The u8 is the movement index from a CompressedMovementID.
     pub intersection_delays: BTreeMap<IntersectionID, Vec<(u8, Time, Duration, AgentType)>>,
 
+    /// For each trip, where it lost time waiting at intersections or queueing on roads.
+    /// Consecutive delays with the same cause are merged.
+    pub trip_delays: BTreeMap<TripID, Vec<(TripDelayCause, Duration)>>,
+
     /// Per parking lane or lot, when does a spot become filled (true) or free (false)
     pub parking_lane_changes: BTreeMap<LaneID, Vec<(Time, bool)>>,
     pub parking_lot_changes: BTreeMap<ParkingLotID, Vec<(Time, bool)>>,
+    /// When a driver found a spot, the building they were trying to park near, and how long they
+    /// spent looking
+    pub parking_searches: Vec<(Time, BuildingID, Duration)>,
+    /// When a cyclist locked their bike, the building they were trying to reach, and the rack they
+    /// used (None if they parked informally). Only recorded when bike parking is modeled.
+    pub bike_parking: Vec<(Time, BuildingID, Option<BuildingID>)>,
 
     pub(crate) alerts: Vec<(Time, AlertLocation, String)>,
+    /// How many alerts have been raised in total, even after they're handled. Not saved with the
+    /// rest of the results, so this restarts from 0 when loading a savestate.
+    #[serde(skip)]
+    pub num_alerts: usize,
 
     /// For benchmarking, we may want to disable collecting data.
     record_anything: bool,
+
+    /// If enabled, older parts of `trip_log`, `intersection_delays`, and the parking changes are
+    /// periodically moved to disk. Use the methods that read these back (like `full_trip_log`),
+    /// instead of the fields directly.
+    #[serde(skip)]
+    spilled: Option<SpilledAnalytics>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -161,15 +185,155 @@ impl Analytics {
             bus_arrivals: Vec::new(),
             passengers_boarding: BTreeMap::new(),
             passengers_alighting: BTreeMap::new(),
+            transit_fares: BTreeMap::new(),
             started_trips: BTreeMap::new(),
             finished_trips: Vec::new(),
             problems_per_trip: BTreeMap::new(),
             trip_log: Vec::new(),
             intersection_delays: BTreeMap::new(),
+            trip_delays: BTreeMap::new(),
             parking_lane_changes: BTreeMap::new(),
             parking_lot_changes: BTreeMap::new(),
+            parking_searches: Vec::new(),
+            bike_parking: Vec::new(),
             alerts: Vec::new(),
+            num_alerts: 0,
             record_anything,
+            spilled: None,
+        }
+    }
+
+    pub(crate) fn enable_spilling(&mut self, spilled: SpilledAnalytics) {
+        self.spilled = Some(spilled);
+    }
+
+    /// Switch to the recording options of another instance, like a fresh one after loading a
+    /// savestate.
+    pub(crate) fn keep_options_from(&mut self, other: Analytics) {
+        self.record_anything = other.record_anything;
+        self.spilled = other.spilled;
+    }
+
+    /// If it's time, move the time-series that grow over the whole run to disk
+    pub(crate) fn maybe_spill(&mut self, now: Time) {
+        if let Some(ref mut spilled) = self.spilled {
+            if spilled.is_due(now) {
+                spilled.flush(
+                    now,
+                    std::mem::take(&mut self.trip_log),
+                    std::mem::take(&mut self.intersection_delays),
+                    std::mem::take(&mut self.parking_lane_changes),
+                    std::mem::take(&mut self.parking_lot_changes),
+                );
+            }
+        }
+    }
+
+    /// Read everything moved to disk back into memory. Spilling continues afterwards.
+    pub(crate) fn unspill(&mut self) {
+        let spilled = match self.spilled {
+            Some(ref mut spilled) if !spilled.is_empty() => spilled,
+            _ => {
+                return;
+            }
+        };
+
+        let mut trip_log = spilled.read_trip_log(|_| true);
+        trip_log.append(&mut self.trip_log);
+        self.trip_log = trip_log;
+
+        self.intersection_delays = concat_lists(
+            spilled.read_intersection_delays(None),
+            std::mem::take(&mut self.intersection_delays),
+        );
+        self.parking_lane_changes = concat_lists(
+            spilled.read_parking_lane_changes(None),
+            std::mem::take(&mut self.parking_lane_changes),
+        );
+        self.parking_lot_changes = concat_lists(
+            spilled.read_parking_lot_changes(None),
+            std::mem::take(&mut self.parking_lot_changes),
+        );
+
+        spilled.forget_chunks();
+    }
+
+    /// Where older analytics have been moved to disk, if anything has been.
+    pub(crate) fn spilled_chunks(&self) -> Option<(String, Vec<Time>)> {
+        self.spilled
+            .as_ref()
+            .filter(|spilled| !spilled.is_empty())
+            .map(|spilled| spilled.chunks())
+    }
+
+    /// Read back analytics that another instance moved to disk, so this one is self-contained.
+    pub(crate) fn read_back_spilled(&mut self, dir: String, chunks: Vec<Time>) {
+        let spilled = self
+            .spilled
+            .replace(SpilledAnalytics::from_chunks(dir, chunks));
+        self.unspill();
+        self.spilled = spilled;
+    }
+
+    /// Every trip phase change, including anything moved to disk.
+    pub fn full_trip_log(&self) -> Cow<TripLog> {
+        match self.spilled {
+            Some(ref spilled) if !spilled.is_empty() => {
+                let mut log = spilled.read_trip_log(|_| true);
+                log.extend(self.trip_log.iter().cloned());
+                Cow::Owned(log)
+            }
+            _ => Cow::Borrowed(&self.trip_log),
+        }
+    }
+
+    /// Like `full_trip_log`, but may skip entries for other trips
+    fn trip_log_for(&self, trip: TripID) -> Cow<TripLog> {
+        match self.spilled {
+            Some(ref spilled) if !spilled.is_empty() => {
+                let mut log = spilled.read_trip_log(|id| id == trip);
+                log.extend(self.trip_log.iter().filter(|x| x.1 == trip).cloned());
+                Cow::Owned(log)
+            }
+            _ => Cow::Borrowed(&self.trip_log),
+        }
+    }
+
+    /// Delays measured at one traffic signal, including anything moved to disk.
+    pub fn intersection_delays_for(
+        &self,
+        i: IntersectionID,
+    ) -> Cow<[(u8, Time, Duration, AgentType)]> {
+        match self.spilled {
+            Some(ref spilled) if !spilled.is_empty() => {
+                let mut list = spilled
+                    .read_intersection_delays(Some(i))
+                    .remove(&i)
+                    .unwrap_or_default();
+                list.extend(
+                    self.intersection_delays
+                        .get(&i)
+                        .into_iter()
+                        .flatten()
+                        .cloned(),
+                );
+                Cow::Owned(list)
+            }
+            _ => match self.intersection_delays.get(&i) {
+                Some(list) => Cow::Borrowed(list.as_slice()),
+                None => Cow::Owned(Vec::new()),
+            },
+        }
+    }
+
+    /// Delays measured at all traffic signals, including anything moved to disk.
+    pub fn all_intersection_delays(&self) -> Cow<IntersectionDelays> {
+        match self.spilled {
+            Some(ref spilled) if !spilled.is_empty() => Cow::Owned(concat_lists(
+                spilled.read_intersection_delays(None),
+                self.intersection_delays.clone(),
+            )),
+            _ => Cow::Borrowed(&self.intersection_delays),
         }
     }
 
@@ -245,6 +409,12 @@ impl Analytics {
                 .or_insert_with(Vec::new)
                 .push((time, route));
         }
+        if let Event::TransitFarePaid(_, route, fare) = ev {
+            self.transit_fares
+                .entry(route)
+                .or_insert_with(Vec::new)
+                .push((time, fare));
+        }
 
         // Started trips
         if let Event::TripPhaseStarting(id, _, _, _) = ev {
@@ -274,6 +444,13 @@ impl Analytics {
                 // Don't record for riders
                 AgentID::BusPassenger(_, _) => Duration::hours(24),
             };
+            if !matches!(agent, AgentID::BusPassenger(_, _)) {
+                self.record_trip_delay(
+                    trip_id,
+                    TripDelayCause::Intersection(turn_id.parent),
+                    delay,
+                );
+            }
             if delay > threshold {
                 self.problems_per_trip
                     .entry(trip_id)
@@ -290,6 +467,13 @@ impl Analytics {
                     .push((compressed.idx, time, delay, agent.to_type()));
             }
         }
+        if let Event::QueueDelayMeasured(trip_id, on, delay) = ev {
+            let cause = match on {
+                Traversable::Lane(l) => TripDelayCause::Queueing(l.road),
+                Traversable::Turn(t) => TripDelayCause::Intersection(t.parent),
+            };
+            self.record_trip_delay(trip_id, cause, delay);
+        }
 
         // Parking spot changes
         if let Event::CarReachedParkingSpot(_, spot) = ev {
@@ -319,6 +503,13 @@ impl Analytics {
             }
         }
 
+        if let Event::ParkingSearchFinished(_, b, searching) = ev {
+            self.parking_searches.push((time, b, searching));
+        }
+        if let Event::BikeParked(_, b, rack) = ev {
+            self.bike_parking.push((time, b, rack));
+        }
+
         // Safety metrics
         if let Event::AgentEntersTraversable(a, Some(trip), Traversable::Turn(t), _) = ev {
             if a.to_type() == AgentType::Bike && map.get_i(t.parent).roads.len() > 4 {
@@ -360,6 +551,7 @@ impl Analytics {
             }
             Event::Alert(loc, msg) => {
                 self.alerts.push((time, loc, msg));
+                self.num_alerts += 1;
             }
             Event::ProblemEncountered(trip, problem) => {
                 self.problems_per_trip
@@ -384,6 +576,30 @@ impl Analytics {
     // TODO If these ever need to be speeded up, just cache the histogram and index in the events
     // list.
 
+    /// Summarize how many agents of some types have crossed every road and intersection so far, in
+    /// the same form as counts observed in the real world or produced by other models.
+    pub fn traffic_counts(
+        &self,
+        map: &Map,
+        description: String,
+        agent_types: &BTreeSet<AgentType>,
+    ) -> TrafficCounts {
+        let mut counts = TrafficCounts {
+            map: map.get_name().clone(),
+            description,
+            per_road: self.road_thruput.all_total_counts(agent_types),
+            per_intersection: self.intersection_thruput.all_total_counts(agent_types),
+        };
+        // Explicitly include 0 for everything, so comparisons work
+        for r in map.all_roads() {
+            counts.per_road.add(r.id, 0);
+        }
+        for i in map.all_intersections() {
+            counts.per_intersection.add(i.id, 0);
+        }
+        counts
+    }
+
     /// Ignores the current time. Returns None for cancelled trips.
     pub fn finished_trip_time(&self, trip: TripID) -> Option<Duration> {
         // TODO This is so inefficient!
@@ -425,11 +641,55 @@ impl Analytics {
         results
     }
 
+    /// Consecutive delays with the same cause are merged, so a long wait at one intersection stays
+    /// one entry.
+    fn record_trip_delay(&mut self, trip: TripID, cause: TripDelayCause, delay: Duration) {
+        if delay == Duration::ZERO {
+            return;
+        }
+        let delays = self.trip_delays.entry(trip).or_insert_with(Vec::new);
+        if let Some((last_cause, total)) = delays.last_mut() {
+            if *last_cause == cause {
+                *total += delay;
+                return;
+            }
+        }
+        delays.push((cause, delay));
+    }
+
+    /// Everywhere one trip lost time, summed per cause. Waiting for transit comes from the trip's
+    /// phases.
+    pub fn trip_delay_causes(&self, trip: TripID) -> Vec<(TripDelayCause, Duration)> {
+        let mut per_cause: BTreeMap<TripDelayCause, Duration> = BTreeMap::new();
+        for (cause, delay) in self.trip_delays.get(&trip).into_iter().flatten() {
+            *per_cause.entry(*cause).or_insert(Duration::ZERO) += *delay;
+        }
+
+        let mut waiting_since: Option<(Time, TransitStopID)> = None;
+        for (t, id, _, phase_type) in self.trip_log_for(trip).iter() {
+            if *id != trip {
+                continue;
+            }
+            if let Some((start, stop)) = waiting_since.take() {
+                *per_cause
+                    .entry(TripDelayCause::WaitingForTransit(stop))
+                    .or_insert(Duration::ZERO) += *t - start;
+            }
+            if let TripPhaseType::WaitingForBus(_, stop) = phase_type {
+                waiting_since = Some((*t, *stop));
+            }
+        }
+
+        let mut delays: Vec<(TripDelayCause, Duration)> = per_cause.into_iter().collect();
+        delays.sort_by_key(|(_, dt)| std::cmp::Reverse(*dt));
+        delays
+    }
+
     /// If calling on prebaked Analytics, be careful to pass in an unedited map, to match how the
     /// simulation was originally run. Otherwise the paths may be nonsense.
     pub fn get_trip_phases(&self, trip: TripID, map: &Map) -> Vec<TripPhase> {
         let mut phases: Vec<TripPhase> = Vec::new();
-        for (t, id, maybe_req, phase_type) in &self.trip_log {
+        for (t, id, maybe_req, phase_type) in self.trip_log_for(trip).iter() {
             if *id != trip {
                 continue;
             }
@@ -452,7 +712,7 @@ impl Analytics {
 
     pub fn get_all_trip_phases(&self) -> BTreeMap<TripID, Vec<TripPhase>> {
         let mut trips = BTreeMap::new();
-        for (t, id, maybe_req, phase_type) in &self.trip_log {
+        for (t, id, maybe_req, phase_type) in self.full_trip_log().iter() {
             let phases: &mut Vec<TripPhase> = trips.entry(*id).or_insert_with(Vec::new);
             if let Some(ref mut last) = phases.last_mut() {
                 last.end_time = Some(*t);
@@ -526,10 +786,24 @@ impl Analytics {
         l: LaneID,
         capacity: usize,
     ) -> Vec<(Time, usize)> {
-        if let Some(changes) = self.parking_lane_changes.get(&l) {
-            Analytics::parking_spot_availability(now, changes, capacity)
-        } else {
+        let changes = match self.spilled {
+            Some(ref spilled) if !spilled.is_empty() => {
+                let mut changes = spilled
+                    .read_parking_lane_changes(Some(l))
+                    .remove(&l)
+                    .unwrap_or_default();
+                changes.extend(self.parking_lane_changes.get(&l).into_iter().flatten());
+                Cow::<[(Time, bool)]>::Owned(changes)
+            }
+            _ => match self.parking_lane_changes.get(&l) {
+                Some(changes) => Cow::Borrowed(changes.as_slice()),
+                None => Cow::Owned(Vec::new()),
+            },
+        };
+        if changes.is_empty() {
             vec![(Time::START_OF_DAY, capacity), (now, capacity)]
+        } else {
+            Analytics::parking_spot_availability(now, &changes, capacity)
         }
     }
     pub fn parking_lot_availability(
@@ -538,10 +812,24 @@ impl Analytics {
         pl: ParkingLotID,
         capacity: usize,
     ) -> Vec<(Time, usize)> {
-        if let Some(changes) = self.parking_lot_changes.get(&pl) {
-            Analytics::parking_spot_availability(now, changes, capacity)
-        } else {
+        let changes = match self.spilled {
+            Some(ref spilled) if !spilled.is_empty() => {
+                let mut changes = spilled
+                    .read_parking_lot_changes(Some(pl))
+                    .remove(&pl)
+                    .unwrap_or_default();
+                changes.extend(self.parking_lot_changes.get(&pl).into_iter().flatten());
+                Cow::<[(Time, bool)]>::Owned(changes)
+            }
+            _ => match self.parking_lot_changes.get(&pl) {
+                Some(changes) => Cow::Borrowed(changes.as_slice()),
+                None => Cow::Owned(Vec::new()),
+            },
+        };
+        if changes.is_empty() {
             vec![(Time::START_OF_DAY, capacity), (now, capacity)]
+        } else {
+            Analytics::parking_spot_availability(now, &changes, capacity)
         }
     }
 
@@ -579,6 +867,130 @@ impl Analytics {
         pts
     }
 
+    /// For drivers that found a spot by `now`, the total time spent searching and the number of
+    /// searches, grouped by the road of the building they were trying to reach.
+    pub fn parking_search_per_road(
+        &self,
+        now: Time,
+        map: &Map,
+    ) -> BTreeMap<RoadID, (Duration, usize)> {
+        let mut per_road: BTreeMap<RoadID, (Duration, usize)> = BTreeMap::new();
+        for (t, b, searching) in &self.parking_searches {
+            if *t > now {
+                break;
+            }
+            let entry = per_road
+                .entry(map.get_b(*b).sidewalk_pos.lane().road)
+                .or_insert((Duration::ZERO, 0));
+            entry.0 += *searching;
+            entry.1 += 1;
+        }
+        per_road
+    }
+
+    /// For cyclists arriving by `now`, grouped by the road of their destination: how many arrived,
+    /// how many had to use a rack near a different building, and how many found no free rack
+    /// nearby at all. The last two are unmet demand for bike parking.
+    pub fn bike_parking_per_road(
+        &self,
+        now: Time,
+        map: &Map,
+    ) -> BTreeMap<RoadID, BikeParkingDemand> {
+        let mut per_road: BTreeMap<RoadID, BikeParkingDemand> = BTreeMap::new();
+        for (t, b, rack) in &self.bike_parking {
+            if *t > now {
+                break;
+            }
+            let entry = per_road
+                .entry(map.get_b(*b).sidewalk_pos.lane().road)
+                .or_default();
+            entry.arrivals += 1;
+            match rack {
+                Some(r) if r == b => {}
+                Some(_) => {
+                    entry.used_other_rack += 1;
+                }
+                None => {
+                    entry.parked_informally += 1;
+                }
+            }
+        }
+        per_road
+    }
+
+    /// For every route, the number of riders boarding and the total fare revenue (in cents) by
+    /// `now`.
+    pub fn ridership_and_revenue(&self, now: Time) -> BTreeMap<TransitRouteID, (usize, usize)> {
+        let mut per_route = BTreeMap::new();
+        for (route, fares) in &self.transit_fares {
+            let mut riders = 0;
+            let mut revenue = 0;
+            for (t, fare) in fares {
+                if *t > now {
+                    break;
+                }
+                riders += 1;
+                revenue += *fare;
+            }
+            per_route.insert(*route, (riders, revenue));
+        }
+        per_route
+    }
+
+    /// For one route, the number of riders and fare revenue (in cents) during each hour of the day
+    /// up to `now`.
+    pub fn hourly_ridership_and_revenue(
+        &self,
+        now: Time,
+        route: TransitRouteID,
+    ) -> Vec<(usize, usize)> {
+        let mut per_hour = vec![(0, 0); now.get_hours() + 1];
+        for (t, fare) in self.transit_fares.get(&route).into_iter().flatten() {
+            if *t > now {
+                break;
+            }
+            let entry = &mut per_hour[t.get_hours()];
+            entry.0 += 1;
+            entry.1 += *fare;
+        }
+        per_hour
+    }
+
+    /// For one route, how long each bus that finished the route by `now` took to get from the
+    /// first stop to the last, indexed by when it finished.
+    pub fn bus_journey_times(
+        &self,
+        now: Time,
+        route: TransitRouteID,
+        map: &Map,
+    ) -> Vec<(Time, Duration)> {
+        let stops = &map.get_tr(route).stops;
+        let (first_stop, last_stop) = match (stops.first(), stops.last()) {
+            (Some(first), Some(last)) if first != last => (*first, *last),
+            _ => {
+                return Vec::new();
+            }
+        };
+        let mut started: HashMap<CarID, Time> = HashMap::new();
+        let mut results = Vec::new();
+        for (t, bus, r, stop) in &self.bus_arrivals {
+            if *t > now {
+                break;
+            }
+            if *r != route {
+                continue;
+            }
+            if *stop == first_stop {
+                started.insert(*bus, *t);
+            } else if *stop == last_stop {
+                if let Some(start) = started.remove(bus) {
+                    results.push((*t, *t - start));
+                }
+            }
+        }
+        results
+    }
+
     pub fn problems_per_intersection(
         &self,
         now: Time,
@@ -695,6 +1107,50 @@ impl Default for Analytics {
     }
 }
 
+/// Where an agent lost time during a trip
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum TripDelayCause {
+    /// Waiting to start a turn, whether for a signal, a stop sign, or a gap in traffic
+    Intersection(IntersectionID),
+    /// Stuck behind other vehicles on a road
+    Queueing(RoadID),
+    /// Waiting at a stop for a bus or train
+    WaitingForTransit(TransitStopID),
+}
+
+/// Explains why a finished trip took longer than it would have with nobody else around.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TripDelayBreakdown {
+    pub total_time: Duration,
+    /// How long the trip would take at the speed limit, with no other agents around
+    pub free_flow_time: Duration,
+    /// Sorted with the biggest delay first
+    pub delays: Vec<(TripDelayCause, Duration)>,
+    /// Time lost that isn't explained by any specific delay, like following slow vehicles or
+    /// taking a different route than the free-flow one
+    pub other: Duration,
+}
+
+impl TripDelayBreakdown {
+    pub fn new(
+        total_time: Duration,
+        free_flow_time: Duration,
+        delays: Vec<(TripDelayCause, Duration)>,
+    ) -> TripDelayBreakdown {
+        let explained = delays.iter().fold(free_flow_time, |sum, (_, dt)| sum + *dt);
+        TripDelayBreakdown {
+            total_time,
+            free_flow_time,
+            delays,
+            other: if total_time > explained {
+                total_time - explained
+            } else {
+                Duration::ZERO
+            },
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct TripPhase {
     pub start_time: Time,
@@ -704,6 +1160,32 @@ pub struct TripPhase {
     pub phase_type: TripPhaseType,
 }
 
+/// See `Analytics::bike_parking_per_road`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BikeParkingDemand {
+    pub arrivals: usize,
+    pub used_other_rack: usize,
+    pub parked_informally: usize,
+}
+
+impl BikeParkingDemand {
+    /// Cyclists who couldn't park at their destination
+    pub fn unmet(&self) -> usize {
+        self.used_other_rack + self.parked_informally
+    }
+}
+
+/// Appends each list in `after` to the matching list in `before`
+fn concat_lists<K: Ord, V>(
+    mut before: BTreeMap<K, Vec<V>>,
+    after: BTreeMap<K, Vec<V>>,
+) -> BTreeMap<K, Vec<V>> {
+    for (k, mut list) in after {
+        before.entry(k).or_default().append(&mut list);
+    }
+    before
+}
+
 /// See https://github.com/a-b-street/abstreet/issues/85
 #[derive(Clone, Serialize, Deserialize)]
 pub struct TimeSeriesCount<X: Ord + Clone> {
@@ -0,0 +1,253 @@
+//! Over a long run, the time-series in `Analytics` grow without bound -- every trip phase, every
+//! delay at a traffic signal, every car parking. For multi-day or region-scale runs, these can be
+//! periodically moved out of memory into files, then read back only when something asks for them.
+//!
+//! Each flush writes one file per series, covering everything recorded since the previous flush.
+//! Files are columnar: each field is stored as its own vector, rather than a vector of tuples.
+
+use std::collections::BTreeMap;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use abstio::MapName;
+use abstutil::Timer;
+use geom::{Duration, Time};
+use map_model::{IntersectionID, LaneID, ParkingLotID, PathRequest};
+
+use crate::{AgentType, TripID, TripPhaseType};
+
+/// Tracks analytics that've been written to disk. This only lives in memory; if the simulation is
+/// saved, everything is read back first. Checkpoints just refer to the chunks instead.
+#[derive(Clone)]
+pub(crate) struct SpilledAnalytics {
+    dir: String,
+    period: Duration,
+    next_flush: Time,
+    /// The time each chunk was written, in order. This identifies the chunk's files.
+    chunks: Vec<Time>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct TripLogColumns {
+    time: Vec<Time>,
+    trip: Vec<TripID>,
+    req: Vec<Option<PathRequest>>,
+    phase: Vec<TripPhaseType>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct DelayColumns {
+    intersection: Vec<IntersectionID>,
+    movement: Vec<u8>,
+    time: Vec<Time>,
+    delay: Vec<Duration>,
+    agent_type: Vec<AgentType>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ParkingColumns<K> {
+    id: Vec<K>,
+    time: Vec<Time>,
+    filled: Vec<bool>,
+}
+
+pub(crate) type TripLog = Vec<(Time, TripID, Option<PathRequest>, TripPhaseType)>;
+pub(crate) type IntersectionDelays = BTreeMap<IntersectionID, Vec<(u8, Time, Duration, AgentType)>>;
+
+impl SpilledAnalytics {
+    pub fn new(
+        map_name: &MapName,
+        edits_name: &str,
+        run_name: &str,
+        period: Duration,
+    ) -> SpilledAnalytics {
+        let dir = abstio::path_spilled_analytics(map_name, edits_name, run_name);
+        info!(
+            "Moving analytics to {} every {} of simulated time",
+            dir, period
+        );
+        SpilledAnalytics {
+            dir,
+            period,
+            next_flush: Time::START_OF_DAY + period,
+            chunks: Vec::new(),
+        }
+    }
+
+    /// Only for reading back chunks written by another instance, like when loading a checkpoint.
+    pub fn from_chunks(dir: String, chunks: Vec<Time>) -> SpilledAnalytics {
+        SpilledAnalytics {
+            dir,
+            period: Duration::ZERO,
+            next_flush: Time::START_OF_DAY,
+            chunks,
+        }
+    }
+
+    /// The directory and the chunks written there so far
+    pub fn chunks(&self) -> (String, Vec<Time>) {
+        (self.dir.clone(), self.chunks.clone())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// After everything's been read back into memory, stop reading the files
+    pub fn forget_chunks(&mut self) {
+        self.chunks.clear();
+    }
+
+    pub fn is_due(&self, now: Time) -> bool {
+        now >= self.next_flush
+    }
+
+    /// Writes everything passed in as a new chunk. The caller should then drop it from memory.
+    pub fn flush(
+        &mut self,
+        now: Time,
+        trip_log: TripLog,
+        intersection_delays: IntersectionDelays,
+        parking_lane_changes: BTreeMap<LaneID, Vec<(Time, bool)>>,
+        parking_lot_changes: BTreeMap<ParkingLotID, Vec<(Time, bool)>>,
+    ) {
+        let mut log = TripLogColumns::default();
+        for (time, trip, req, phase) in trip_log {
+            log.time.push(time);
+            log.trip.push(trip);
+            log.req.push(req);
+            log.phase.push(phase);
+        }
+
+        let mut delays = DelayColumns::default();
+        for (i, list) in intersection_delays {
+            for (movement, time, delay, agent_type) in list {
+                delays.intersection.push(i);
+                delays.movement.push(movement);
+                delays.time.push(time);
+                delays.delay.push(delay);
+                delays.agent_type.push(agent_type);
+            }
+        }
+
+        abstio::write_binary(self.path("trip_log", now), &log);
+        abstio::write_binary(self.path("intersection_delays", now), &delays);
+        abstio::write_binary(
+            self.path("parking_lane_changes", now),
+            &parking_columns(parking_lane_changes),
+        );
+        abstio::write_binary(
+            self.path("parking_lot_changes", now),
+            &parking_columns(parking_lot_changes),
+        );
+
+        self.chunks.push(now);
+        self.next_flush = now + self.period;
+    }
+
+    /// Reads back every trip log entry matching the filter, in order
+    pub fn read_trip_log<F: Fn(TripID) -> bool>(&self, filter: F) -> TripLog {
+        let mut results = Vec::new();
+        for cols in self.read_all::<TripLogColumns>("trip_log") {
+            for (((time, trip), req), phase) in cols
+                .time
+                .into_iter()
+                .zip(cols.trip)
+                .zip(cols.req)
+                .zip(cols.phase)
+            {
+                if filter(trip) {
+                    results.push((time, trip, req, phase));
+                }
+            }
+        }
+        results
+    }
+
+    /// Reads back intersection delays, either for one intersection or all of them
+    pub fn read_intersection_delays(&self, only: Option<IntersectionID>) -> IntersectionDelays {
+        let mut results: IntersectionDelays = BTreeMap::new();
+        for cols in self.read_all::<DelayColumns>("intersection_delays") {
+            for i in 0..cols.intersection.len() {
+                if only.map(|id| id != cols.intersection[i]).unwrap_or(false) {
+                    continue;
+                }
+                results.entry(cols.intersection[i]).or_default().push((
+                    cols.movement[i],
+                    cols.time[i],
+                    cols.delay[i],
+                    cols.agent_type[i],
+                ));
+            }
+        }
+        results
+    }
+
+    pub fn read_parking_lane_changes(
+        &self,
+        only: Option<LaneID>,
+    ) -> BTreeMap<LaneID, Vec<(Time, bool)>> {
+        self.read_parking("parking_lane_changes", only)
+    }
+
+    pub fn read_parking_lot_changes(
+        &self,
+        only: Option<ParkingLotID>,
+    ) -> BTreeMap<ParkingLotID, Vec<(Time, bool)>> {
+        self.read_parking("parking_lot_changes", only)
+    }
+
+    fn read_parking<K: Copy + Ord + DeserializeOwned>(
+        &self,
+        series: &str,
+        only: Option<K>,
+    ) -> BTreeMap<K, Vec<(Time, bool)>> {
+        let mut results: BTreeMap<K, Vec<(Time, bool)>> = BTreeMap::new();
+        for cols in self.read_all::<ParkingColumns<K>>(series) {
+            for i in 0..cols.id.len() {
+                if only.map(|id| id != cols.id[i]).unwrap_or(false) {
+                    continue;
+                }
+                results
+                    .entry(cols.id[i])
+                    .or_default()
+                    .push((cols.time[i], cols.filled[i]));
+            }
+        }
+        results
+    }
+
+    fn read_all<T: DeserializeOwned>(&self, series: &str) -> Vec<T> {
+        let mut results = Vec::new();
+        for time in &self.chunks {
+            let path = self.path(series, *time);
+            match abstio::maybe_read_binary(path.clone(), &mut Timer::throwaway()) {
+                Ok(cols) => results.push(cols),
+                // The files are only written by us, so something else must've deleted them
+                Err(err) => error!("Couldn't read spilled analytics from {}: {}", path, err),
+            }
+        }
+        results
+    }
+
+    fn path(&self, series: &str, time: Time) -> String {
+        format!("{}/{}_{}.bin", self.dir, series, time.as_filename())
+    }
+}
+
+fn parking_columns<K: Copy>(changes: BTreeMap<K, Vec<(Time, bool)>>) -> ParkingColumns<K> {
+    let mut cols = ParkingColumns {
+        id: Vec::new(),
+        time: Vec::new(),
+        filled: Vec::new(),
+    };
+    for (id, list) in changes {
+        for (time, filled) in list {
+            cols.id.push(id);
+            cols.time.push(time);
+            cols.filled.push(filled);
+        }
+    }
+    cols
+}
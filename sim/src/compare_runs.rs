@@ -0,0 +1,213 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use geom::{Duration, Time};
+use map_model::{IntersectionID, Map, RoadID};
+use synthpop::TripMode;
+
+use crate::{AgentType, Analytics, TripID};
+
+/// Compares the results of two simulation runs that have already finished, without having to run
+/// both at the same time like the A/B mode does. Each run is just its Analytics, saved to
+/// `abstio::path_run_analytics` or prebaked.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RunComparison {
+    /// Per road, (before, after) throughput
+    pub roads: BTreeMap<RoadID, (usize, usize)>,
+    /// Per intersection, (before, after) throughput
+    pub intersections: BTreeMap<IntersectionID, (usize, usize)>,
+    /// Per traffic signal, (before, after) mean delay of everybody crossing it. Only signals
+    /// crossed in both runs are included.
+    pub intersection_delays: BTreeMap<IntersectionID, (Duration, Duration)>,
+    /// Trips that finished in both runs: (before, after) duration
+    pub trips: Vec<(TripID, TripMode, Duration, Duration)>,
+}
+
+/// Statistics about how a set of values changed between two runs. The deltas are after - before,
+/// so for durations, negative means faster.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DeltaSummary {
+    pub count: usize,
+    pub mean: f64,
+    pub p10: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub num_increased: usize,
+    pub num_decreased: usize,
+}
+
+impl RunComparison {
+    /// Both runs should come from the same map and scenario; otherwise trip IDs won't line up.
+    pub fn new(before: &Analytics, after: &Analytics, agent_types: &BTreeSet<AgentType>) -> Self {
+        let roads = before
+            .road_thruput
+            .all_total_counts(agent_types)
+            .compare(after.road_thruput.all_total_counts(agent_types))
+            .into_iter()
+            .map(|(r, a, b)| (r, (a, b)))
+            .collect();
+        let intersections = before
+            .intersection_thruput
+            .all_total_counts(agent_types)
+            .compare(after.intersection_thruput.all_total_counts(agent_types))
+            .into_iter()
+            .map(|(i, a, b)| (i, (a, b)))
+            .collect();
+
+        let mean_delays_before = mean_delays(before, agent_types);
+        let mut mean_delays_after = mean_delays(after, agent_types);
+        let mut intersection_delays = BTreeMap::new();
+        for (i, dt1) in mean_delays_before {
+            if let Some(dt2) = mean_delays_after.remove(&i) {
+                intersection_delays.insert(i, (dt1, dt2));
+            }
+        }
+
+        // Include every trip, even ones finishing after midnight
+        let end = [before, after]
+            .into_iter()
+            .filter_map(|analytics| analytics.finished_trips.last().map(|(t, _, _, _)| *t))
+            .max()
+            .unwrap_or(Time::START_OF_DAY);
+        let trips = after
+            .both_finished_trips(end, before)
+            .into_iter()
+            .map(|(id, dt1, dt2, mode)| (id, mode, dt1, dt2))
+            .collect();
+
+        RunComparison {
+            roads,
+            intersections,
+            intersection_delays,
+            trips,
+        }
+    }
+
+    /// How trip durations changed, in seconds, optionally only for one mode
+    pub fn trip_summary(&self, mode: Option<TripMode>) -> Option<DeltaSummary> {
+        DeltaSummary::new(
+            self.trips
+                .iter()
+                .filter(|(_, m, _, _)| mode.map(|mode| mode == *m).unwrap_or(true))
+                .map(|(_, _, before, after)| (*after - *before).inner_seconds())
+                .collect(),
+        )
+    }
+
+    /// How road throughput changed
+    pub fn road_summary(&self) -> Option<DeltaSummary> {
+        DeltaSummary::new(
+            self.roads
+                .values()
+                .map(|(before, after)| (*after as f64) - (*before as f64))
+                .collect(),
+        )
+    }
+
+    /// How intersection throughput changed
+    pub fn intersection_summary(&self) -> Option<DeltaSummary> {
+        DeltaSummary::new(
+            self.intersections
+                .values()
+                .map(|(before, after)| (*after as f64) - (*before as f64))
+                .collect(),
+        )
+    }
+
+    /// How mean delay at traffic signals changed, in seconds
+    pub fn intersection_delay_summary(&self) -> Option<DeltaSummary> {
+        DeltaSummary::new(
+            self.intersection_delays
+                .values()
+                .map(|(before, after)| (*after - *before).inner_seconds())
+                .collect(),
+        )
+    }
+
+    /// The roads whose throughput changed the most, in either direction
+    pub fn biggest_road_changes(&self, n: usize) -> Vec<(RoadID, usize, usize)> {
+        let mut changes: Vec<(RoadID, usize, usize)> = self
+            .roads
+            .iter()
+            .filter(|(_, (before, after))| before != after)
+            .map(|(r, (before, after))| (*r, *before, *after))
+            .collect();
+        changes.sort_by_key(|(_, before, after)| {
+            std::cmp::Reverse((*after as isize - *before as isize).abs())
+        });
+        changes.truncate(n);
+        changes
+    }
+
+    /// Drop roads and intersections that don't exist on this map, in case one of the runs was on
+    /// a map with different edits.
+    pub fn retain_existing(&mut self, map: &Map) {
+        self.roads.retain(|r, _| r.0 < map.all_roads().len());
+        self.intersections
+            .retain(|i, _| i.0 < map.all_intersections().len());
+        self.intersection_delays
+            .retain(|i, _| i.0 < map.all_intersections().len());
+    }
+}
+
+fn mean_delays(
+    analytics: &Analytics,
+    agent_types: &BTreeSet<AgentType>,
+) -> BTreeMap<IntersectionID, Duration> {
+    let mut results = BTreeMap::new();
+    for (i, delays) in analytics.all_intersection_delays().iter() {
+        let mut sum = Duration::ZERO;
+        let mut cnt = 0;
+        for (_, _, dt, agent_type) in delays {
+            if agent_types.contains(agent_type) {
+                sum += *dt;
+                cnt += 1;
+            }
+        }
+        if cnt > 0 {
+            results.insert(*i, sum / (cnt as f64));
+        }
+    }
+    results
+}
+
+impl DeltaSummary {
+    /// None if there are no values
+    pub fn new(mut deltas: Vec<f64>) -> Option<DeltaSummary> {
+        if deltas.is_empty() {
+            return None;
+        }
+        deltas.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let count = deltas.len();
+        let percentile = |p: usize| deltas[((count - 1) * p) / 100];
+        Some(DeltaSummary {
+            count,
+            mean: deltas.iter().sum::<f64>() / (count as f64),
+            p10: percentile(10),
+            p50: percentile(50),
+            p90: percentile(90),
+            num_increased: deltas.iter().filter(|x| **x > 0.0).count(),
+            num_decreased: deltas.iter().filter(|x| **x < 0.0).count(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeltaSummary;
+
+    #[test]
+    fn test_delta_summary() {
+        assert_eq!(DeltaSummary::new(Vec::new()), None);
+
+        let summary = DeltaSummary::new((-5..=5).map(|x| x as f64).collect()).unwrap();
+        assert_eq!(summary.count, 11);
+        assert_eq!(summary.mean, 0.0);
+        assert_eq!(summary.p10, -4.0);
+        assert_eq!(summary.p50, 0.0);
+        assert_eq!(summary.p90, 4.0);
+        assert_eq!(summary.num_increased, 5);
+        assert_eq!(summary.num_decreased, 5);
+    }
+}
@@ -27,6 +27,8 @@ pub enum Event {
     /// How long waiting at the stop?
     PassengerBoardsTransit(PersonID, CarID, TransitRouteID, TransitStopID, Duration),
     PassengerAlightsTransit(PersonID, CarID, TransitRouteID, TransitStopID),
+    /// In cents. May be zero for free rides or when a daily cap has been reached.
+    TransitFarePaid(PersonID, TransitRouteID, usize),
 
     PersonEntersBuilding(PersonID, BuildingID),
     PersonLeavesBuilding(PersonID, BuildingID),
@@ -35,8 +37,14 @@ pub enum Event {
     PersonEntersMap(PersonID, AgentID, IntersectionID),
 
     PedReachedParkingSpot(PedestrianID, ParkingSpot),
+    /// A driver found a spot after searching for this long, trying to park near the building
+    ParkingSearchFinished(CarID, BuildingID, Duration),
 
     BikeStoppedAtSidewalk(CarID, LaneID),
+    /// A cyclist locked their bike, trying to reach the building. The second building is the rack
+    /// they used, or None if every rack nearby was full and they parked informally. Only happens
+    /// with `SimOptions::model_bike_parking`.
+    BikeParked(CarID, BuildingID, Option<BuildingID>),
 
     ProblemEncountered(TripID, Problem),
 
@@ -45,6 +53,8 @@ pub enum Event {
     AgentEntersTraversable(AgentID, Option<TripID>, Traversable, Option<usize>),
     /// TripID, TurnID (Where the delay was encountered), Time spent waiting at that turn
     IntersectionDelayMeasured(TripID, TurnID, AgentID, Duration),
+    /// A vehicle was stuck behind others on this Traversable for some time
+    QueueDelayMeasured(TripID, Traversable, Duration),
 
     TripFinished {
         trip: TripID,
@@ -79,6 +89,8 @@ pub enum TripPhaseType {
     WaitingForBus(TransitRouteID, TransitStopID),
     /// What stop did they board at?
     RidingBus(TransitRouteID, TransitStopID, CarID),
+    WaitingForMicrotransit,
+    RidingMicrotransit(CarID),
     Cancelled,
     Finished,
     DelayedStart,
@@ -97,6 +109,8 @@ impl TripPhaseType {
             TripPhaseType::RidingBus(r, _, _) => {
                 format!("Riding route {}", map.get_tr(r).long_name)
             }
+            TripPhaseType::WaitingForMicrotransit => "Waiting to be picked up".to_string(),
+            TripPhaseType::RidingMicrotransit(_) => "Riding microtransit".to_string(),
             TripPhaseType::Cancelled => "Trip was cancelled due to some bug".to_string(),
             TripPhaseType::Finished => "Trip finished".to_string(),
             TripPhaseType::DelayedStart => "Delayed by a previous trip taking too long".to_string(),
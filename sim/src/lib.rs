@@ -27,16 +27,19 @@ use map_model::{
     BuildingID, IntersectionID, LaneID, Map, ParkingLotID, Path, PathConstraints, Position,
     TransitRouteID, TransitStopID,
 };
-use synthpop::TripEndpoint;
+use synthpop::{TripEndpoint, VehicleClass};
 
 pub use crate::render::{
     CarStatus, DrawCarInput, DrawPedCrowdInput, DrawPedestrianInput, Intent, PedCrowdLocation,
     UnzoomedAgent,
 };
 
-pub use self::analytics::{Analytics, Problem, ProblemType, SlidingWindow, TripPhase};
-pub(crate) use self::events::Event;
-pub use self::events::{AlertLocation, TripPhaseType};
+pub use self::analytics::{
+    Analytics, BikeParkingDemand, Problem, ProblemType, SlidingWindow, TripDelayBreakdown,
+    TripDelayCause, TripPhase,
+};
+pub use self::compare_runs::{DeltaSummary, RunComparison};
+pub use self::events::{AlertLocation, Event, TripPhaseType};
 pub use self::make::SimFlags;
 pub(crate) use self::make::{StartTripArgs, TripSpec};
 pub(crate) use self::mechanics::{
@@ -47,35 +50,46 @@ pub use self::prebake::PrebakeSummary;
 pub(crate) use self::recorder::TrafficRecorder;
 pub(crate) use self::router::{ActionAtEnd, Router};
 pub(crate) use self::scheduler::{Command, Scheduler};
+pub use self::scheduler::{ModelCommand, ModelScheduler};
+pub use self::secondary_model::SecondaryModel;
+pub(crate) use self::sim::ProbeState;
 pub use self::sim::{
-    count_parked_cars_per_bldg, rand_dist, AgentProperties, AlertHandler, DelayCause, Sim,
-    SimCallback, SimOptions,
+    count_parked_cars_per_bldg, rand_dist, AgentDecision, AgentDiff, AgentProperties, AlertHandler,
+    DecisionType, DelayCause, GridlockReport, Sim, SimCallback, SimDiff, SimOptions,
 };
 pub(crate) use self::transit::TransitSimState;
 pub use self::trips::{CommutersVehiclesCounts, Person, PersonState, TripInfo, TripResult};
 pub(crate) use self::trips::{TripLeg, TripManager};
+pub use self::weather::{Precipitation, Season, Weather};
 pub use synthpop::make::{fork_rng, BorderSpawnOverTime, ScenarioGenerator, SpawnOverTime};
 
 mod analytics;
+mod analytics_spill;
+mod compare_runs;
 mod events;
 mod make;
 mod mechanics;
+mod microtransit;
 mod pandemic;
 pub mod prebake;
 mod recorder;
 mod render;
 mod router;
 mod scheduler;
+mod secondary_model;
 mod sim;
 mod transit;
 mod trips;
+mod weather;
 
 // http://pccsc.net/bicycle-parking-info/ says 68 inches, which is 1.73m
 pub(crate) const BIKE_LENGTH: Distance = Distance::const_meters(1.8);
 pub(crate) const MIN_CAR_LENGTH: Distance = Distance::const_meters(4.5);
+/// The longest car. Trucks can be longer; see `VehicleClass::length_range`.
 pub(crate) const MAX_CAR_LENGTH: Distance = Distance::const_meters(6.5);
 // Note this is more than MAX_CAR_LENGTH
 pub(crate) const BUS_LENGTH: Distance = Distance::const_meters(12.5);
+pub(crate) const MINIBUS_LENGTH: Distance = Distance::const_meters(7.0);
 pub(crate) const LIGHT_RAIL_LENGTH: Distance = Distance::const_meters(60.0);
 
 /// At all speeds (including at rest), cars must be at least this far apart, measured from front of
@@ -319,6 +333,10 @@ pub struct Vehicle {
     pub vehicle_type: VehicleType,
     pub length: Distance,
     pub max_speed: Option<Speed>,
+    /// Only set for VehicleType::Car
+    pub class: Option<VehicleClass>,
+    /// May this vehicle park in disabled bays?
+    pub disabled_permit: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -326,6 +344,9 @@ pub struct VehicleSpec {
     pub vehicle_type: VehicleType,
     pub length: Distance,
     pub max_speed: Option<Speed>,
+    /// Only set for VehicleType::Car
+    pub class: Option<VehicleClass>,
+    pub disabled_permit: bool,
 }
 
 impl VehicleSpec {
@@ -337,6 +358,8 @@ impl VehicleSpec {
             vehicle_type: self.vehicle_type,
             length: self.length,
             max_speed: self.max_speed,
+            class: self.class,
+            disabled_permit: self.disabled_permit,
         }
     }
 }
@@ -4,6 +4,7 @@ use rand_xorshift::XorShiftRng;
 use structopt::StructOpt;
 
 use abstio::MapName;
+use geom::Time;
 use map_model::{Map, MapEdits};
 use synthpop::{Scenario, ScenarioModifier};
 
@@ -32,6 +33,11 @@ pub struct SimFlags {
     // TODO default_value can only handle strings, so copying SimFlags::RNG_SEED
     #[structopt(long, default_value = "42")]
     pub rng_seed: u64,
+    /// When loading a scenario, start the simulation at this time of day, instead of simulating
+    /// everything from midnight. Resumes from a savestate if there is one, or otherwise
+    /// approximates the traffic from recent trips.
+    #[structopt(long, parse(try_from_str = Time::parse))]
+    pub start_time: Option<Time>,
     #[structopt(flatten)]
     pub opts: SimOptions,
 }
@@ -61,6 +67,7 @@ impl SimFlags {
             load: MapName::seattle("montlake").path(),
             scenario_modifiers: Vec::new(),
             rng_seed: SimFlags::RNG_SEED,
+            start_time: None,
             opts: SimOptions::new(run_name),
         }
     }
@@ -115,7 +122,11 @@ impl SimFlags {
                 opts.run_name = scenario.scenario_name.clone();
             }
             let mut sim = Sim::new(&map, opts);
-            sim.instantiate(&scenario, &map, &mut rng, timer);
+            if let Some(start) = self.start_time {
+                sim.warm_start(&scenario, &map, &mut rng, start, timer);
+            } else {
+                sim.instantiate(&scenario, &map, &mut rng, timer);
+            }
 
             (map, sim, rng)
         } else if self.load.contains("/raw_maps/") || self.load.contains("/maps/") {
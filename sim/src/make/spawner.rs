@@ -15,6 +15,8 @@ use crate::{CarID, DrivingGoal, SidewalkSpot, TripLeg, VehicleType, SPAWN_DIST};
 pub(crate) struct StartTripArgs {
     pub retry_if_no_room: bool,
     pub use_vehicle: Option<CarID>,
+    /// Transit trips book a ride with on-demand vehicles instead of using fixed routes
+    pub use_microtransit: bool,
 }
 
 // TODO Some of these fields are unused now that we separately pass TripEndpoint
@@ -55,6 +57,8 @@ pub(crate) enum TripSpec {
         stop1: TransitStopID,
         maybe_stop2: Option<TransitStopID>,
     },
+    /// Wait outside the building to be picked up, and get dropped off right at the destination
+    UsingMicrotransit { start: BuildingID, goal: BuildingID },
 }
 
 impl TripSpec {
@@ -201,6 +205,9 @@ impl TripSpec {
                     legs = vec![TripLeg::Walk(walk_to), TripLeg::RideBus(*route, None)];
                 }
             }
+            TripSpec::UsingMicrotransit { goal, .. } => {
+                legs.push(TripLeg::RideMicrotransit(*goal));
+            }
         };
 
         (self, legs)
@@ -214,6 +221,7 @@ impl TripSpec {
         mode: TripMode,
         use_vehicle: Option<CarID>,
         retry_if_no_room: bool,
+        use_microtransit: bool,
         map: &Map,
     ) -> Result<TripSpec> {
         Ok(match mode {
@@ -270,6 +278,19 @@ impl TripSpec {
                 goal: end_sidewalk_spot(to, map)?,
             },
             TripMode::Transit => {
+                if use_microtransit {
+                    if let (TripEndpoint::Building(start), TripEndpoint::Building(goal)) =
+                        (from, to)
+                    {
+                        // Both ends have to be reachable by vehicles
+                        if map.get_b(start).driving_connection(map).is_some()
+                            && map.get_b(goal).driving_connection(map).is_some()
+                        {
+                            return Ok(TripSpec::UsingMicrotransit { start, goal });
+                        }
+                    }
+                }
+
                 let start = start_sidewalk_spot(from, map)?;
                 let goal = end_sidewalk_spot(to, map)?;
                 if let Some((stop1, maybe_stop2, route)) =
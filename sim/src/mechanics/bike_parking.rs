@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use abstutil::{deserialize_btreemap, serialize_btreemap};
+use geom::{Distance, FindClosest};
+use map_model::{BuildingID, ExtraPOIType, Map};
+
+use crate::CarID;
+
+/// Cyclists won't walk further than this from a rack to their destination. If every rack closer
+/// than this is full, they lock their bike to whatever's outside.
+const MAX_WALK_FROM_RACK: Distance = Distance::const_meters(300.0);
+
+/// Tracks bike racks imported from OSM and how full they are. Only used with
+/// `SimOptions::model_bike_parking`; otherwise, bikes are left right outside every building
+/// without any capacity limits.
+///
+/// Racks are snapped to the nearest building, and bikes are locked at that building's biking
+/// connection, so each building acts as a rack with some capacity.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct BikeParkingSimState {
+    capacity: BTreeMap<BuildingID, usize>,
+    occupied: BTreeMap<BuildingID, usize>,
+    /// Which rack each parked bike is using. A bike heading somewhere already counts as parked
+    /// there.
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    parked: BTreeMap<CarID, BuildingID>,
+}
+
+impl BikeParkingSimState {
+    pub fn new(map: &Map) -> BikeParkingSimState {
+        let mut closest: FindClosest<BuildingID> = FindClosest::new();
+        for b in map.all_buildings() {
+            closest.add_polygon(b.id, &b.polygon);
+        }
+
+        let mut capacity = BTreeMap::new();
+        for poi in map.all_extra_pois() {
+            if let ExtraPOIType::BicycleParking(spots) = poi.kind {
+                if let Some((b, _)) = closest.closest_pt(poi.pt, MAX_WALK_FROM_RACK) {
+                    if map.get_b(b).biking_connection(map).is_some() {
+                        *capacity.entry(b).or_insert(0) += spots;
+                    }
+                }
+            }
+        }
+
+        BikeParkingSimState {
+            capacity,
+            occupied: BTreeMap::new(),
+            parked: BTreeMap::new(),
+        }
+    }
+
+    /// Finds the closest rack with a free spot near the building and reserves it. Returns `None`
+    /// if there's nowhere to park formally.
+    pub fn reserve(&mut self, bike: CarID, near: BuildingID, map: &Map) -> Option<BuildingID> {
+        let pt = map.get_b(near).label_center;
+        let (rack, _) = map
+            .find_buildings_near(pt, MAX_WALK_FROM_RACK)
+            .into_iter()
+            .find(|(b, _)| {
+                self.capacity
+                    .get(b)
+                    .map(|capacity| self.occupied.get(b).cloned().unwrap_or(0) < *capacity)
+                    .unwrap_or(false)
+            })?;
+        *self.occupied.entry(rack).or_insert(0) += 1;
+        self.parked.insert(bike, rack);
+        Some(rack)
+    }
+
+    /// When a bike leaves, free up its spot and return the rack where the trip should start. If
+    /// the rack is far from where the trip starts, the bike must've been moved some other way, so
+    /// just start from the building instead.
+    pub fn release(&mut self, bike: CarID, start: BuildingID, map: &Map) -> Option<BuildingID> {
+        let rack = self.parked.remove(&bike)?;
+        *self.occupied.get_mut(&rack).unwrap() -= 1;
+        if map
+            .get_b(start)
+            .label_center
+            .dist_to(map.get_b(rack).label_center)
+            <= MAX_WALK_FROM_RACK
+        {
+            Some(rack)
+        } else {
+            None
+        }
+    }
+
+    /// Frees the spot a bike was heading to, when it won't get there after all.
+    pub fn cancel(&mut self, bike: CarID) {
+        if let Some(rack) = self.parked.remove(&bike) {
+            *self.occupied.get_mut(&rack).unwrap() -= 1;
+        }
+    }
+
+    pub fn get_rack(&self, bike: CarID) -> Option<BuildingID> {
+        self.parked.get(&bike).cloned()
+    }
+
+    /// Every rack, with its (occupied, capacity)
+    pub fn all_racks(&self) -> BTreeMap<BuildingID, (usize, usize)> {
+        self.capacity
+            .iter()
+            .map(|(b, capacity)| (*b, (self.occupied.get(b).cloned().unwrap_or(0), *capacity)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VehicleType;
+
+    fn bike(id: usize) -> CarID {
+        CarID {
+            id,
+            vehicle_type: VehicleType::Bike,
+        }
+    }
+
+    /// The only building has a rack with room for one bike
+    fn setup() -> (Map, BuildingID, BikeParkingSimState) {
+        let map = Map::almost_blank();
+        let b = map.all_buildings()[0].id;
+        let mut capacity = BTreeMap::new();
+        capacity.insert(b, 1);
+        let state = BikeParkingSimState {
+            capacity,
+            occupied: BTreeMap::new(),
+            parked: BTreeMap::new(),
+        };
+        (map, b, state)
+    }
+
+    #[test]
+    fn test_reserve_until_full() {
+        let (map, b, mut state) = setup();
+        assert_eq!(state.reserve(bike(0), b, &map), Some(b));
+        assert_eq!(state.get_rack(bike(0)), Some(b));
+        // The rack is full, so the second cyclist leaves their bike outside the building
+        assert_eq!(state.reserve(bike(1), b, &map), None);
+        assert_eq!(state.get_rack(bike(1)), None);
+        assert_eq!(state.all_racks()[&b], (1, 1));
+    }
+
+    #[test]
+    fn test_release_frees_the_spot() {
+        let (map, b, mut state) = setup();
+        state.reserve(bike(0), b, &map);
+        assert_eq!(state.release(bike(0), b, &map), Some(b));
+        assert_eq!(state.all_racks()[&b], (0, 1));
+        assert_eq!(state.reserve(bike(1), b, &map), Some(b));
+
+        // A bike that was left outside a building starts from there
+        assert_eq!(state.release(bike(2), b, &map), None);
+        assert_eq!(state.all_racks()[&b], (1, 1));
+    }
+
+    #[test]
+    fn test_cancel_frees_the_spot() {
+        let (map, b, mut state) = setup();
+        state.reserve(bike(0), b, &map);
+        state.cancel(bike(0));
+        assert_eq!(state.get_rack(bike(0)), None);
+        assert_eq!(state.all_racks()[&b], (0, 1));
+        assert_eq!(state.reserve(bike(1), b, &map), Some(b));
+
+        // Cancelling a bike without a spot doesn't free anybody else's
+        state.cancel(bike(2));
+        assert_eq!(state.all_racks()[&b], (1, 1));
+    }
+}
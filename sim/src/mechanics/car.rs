@@ -2,14 +2,25 @@ use std::collections::{BTreeSet, VecDeque};
 
 use serde::{Deserialize, Serialize};
 
-use geom::{Distance, Duration, PolyLine, Time, EPSILON_DIST};
+use geom::{Distance, Duration, PolyLine, Speed, Time, EPSILON_DIST};
 use map_model::{Direction, LaneID, Map, Traversable};
 
 use crate::{
-    CarID, CarStatus, DistanceInterval, DrawCarInput, Intent, ParkingSpot, PersonID, Router,
+    CarID, CarStatus, DistanceInterval, DrawCarInput, Event, Intent, ParkingSpot, PersonID, Router,
     TimeInterval, TransitSimState, TripID, Vehicle, VehicleType,
 };
 
+/// Things besides the path itself that change how fast vehicles move, from `SimOptions`
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub(crate) struct DrivingConditions {
+    /// Narrow lanes slow vehicles down
+    pub lane_widths: bool,
+    /// Multiplies the speed of every vehicle, because of the weather
+    pub weather_speed: f64,
+    /// Multiplies the usable length of every lane, because of the weather
+    pub lane_capacity: f64,
+}
+
 /// Represents a single vehicle. Note "car" is a misnomer; it could also be a bus or bike.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub(crate) struct Car {
@@ -33,7 +44,13 @@ pub(crate) struct Car {
 
 impl Car {
     /// Assumes the current head of the path is the thing to cross.
-    pub fn crossing_state(&self, start_dist: Distance, start_time: Time, map: &Map) -> CarState {
+    pub fn crossing_state(
+        &self,
+        start_dist: Distance,
+        start_time: Time,
+        map: &Map,
+        conditions: DrivingConditions,
+    ) -> CarState {
         let end_dist = if self.router.last_step() {
             self.router.get_end_dist()
         } else {
@@ -47,14 +64,29 @@ impl Car {
         }
 
         let dist_int = DistanceInterval::new_driving(start_dist, end_dist);
-        self.crossing_state_with_end_dist(dist_int, start_time, map)
+        self.crossing_state_with_end_dist(dist_int, start_time, map, conditions)
     }
 
+    /// Narrow lanes or the weather may slow the vehicle down, depending on `conditions`.
     pub fn crossing_state_with_end_dist(
         &self,
         dist_int: DistanceInterval,
         start_time: Time,
         map: &Map,
+        conditions: DrivingConditions,
+    ) -> CarState {
+        self.crossing_state_along(self.router.head(), dist_int, start_time, map, conditions)
+    }
+
+    /// Like `crossing_state_with_end_dist`, but uses the width of `on`, which might not be the
+    /// current head yet.
+    pub fn crossing_state_along(
+        &self,
+        on: Traversable,
+        dist_int: DistanceInterval,
+        start_time: Time,
+        map: &Map,
+        conditions: DrivingConditions,
     ) -> CarState {
         let (speed, percent_incline) = self
             .router
@@ -65,7 +97,18 @@ impl Car {
                 self.vehicle.vehicle_type.to_constraints(),
                 map,
             );
-        let dt = (dist_int.end - dist_int.start) / speed;
+        let mut speed_penalty = conditions.weather_speed;
+        if conditions.lane_widths {
+            speed_penalty *= narrow_lane_penalty(map, on, self.vehicle.vehicle_type);
+        }
+        let speed = speed_penalty * speed;
+        let dist = dist_int.end - dist_int.start;
+        let dt = match self.vehicle.class {
+            Some(class) if self.state.is_stopped() => {
+                time_from_rest(dist, speed, class.acceleration())
+            }
+            _ => dist / speed,
+        };
         CarState::Crossing {
             time_int: TimeInterval::new(start_time, start_time + dt),
             dist_int,
@@ -296,11 +339,10 @@ impl Car {
             label: if self.vehicle.vehicle_type == VehicleType::Bus
                 || self.vehicle.vehicle_type == VehicleType::Train
             {
-                Some(
-                    map.get_tr(transit.bus_route(self.vehicle.id))
-                        .short_name
-                        .clone(),
-                )
+                // Microtransit vehicles don't have a route
+                transit
+                    .bus_route(self.vehicle.id)
+                    .map(|r| map.get_tr(r).short_name.clone())
             } else {
                 None
             },
@@ -309,6 +351,18 @@ impl Car {
         }
     }
 
+    /// The car was stuck in a queue on the current step of its path since `blocked_since`, but can
+    /// move again. Accumulate the blocked time and record where it was lost.
+    pub fn stop_queueing(&mut self, now: Time, blocked_since: Time, events: &mut Vec<Event>) {
+        let delay = now - blocked_since;
+        self.total_blocked_time += delay;
+        if let Some((trip, _)) = self.trip_and_person {
+            if delay > Duration::ZERO {
+                events.push(Event::QueueDelayMeasured(trip, self.router.head(), delay));
+            }
+        }
+    }
+
     pub fn is_parking(&self) -> bool {
         if let CarState::Parking(_, _, _) = self.state {
             return true;
@@ -354,6 +408,18 @@ pub(crate) enum CarState {
 }
 
 impl CarState {
+    /// Is the vehicle standing still, so it has to accelerate once it moves again?
+    pub fn is_stopped(&self) -> bool {
+        match self {
+            CarState::Crossing { .. } | CarState::ChangingLanes { .. } => false,
+            CarState::Queued { .. }
+            | CarState::WaitingToAdvance { .. }
+            | CarState::Unparking { .. }
+            | CarState::Parking(_, _, _)
+            | CarState::IdlingAtStop(_, _) => true,
+        }
+    }
+
     pub fn get_end_time(&self) -> Time {
         match self {
             CarState::Crossing { ref time_int, .. } => time_int.end,
@@ -375,3 +441,47 @@ impl CarState {
         }
     }
 }
+
+/// The widths that vehicles of each type need to travel comfortably, and the narrowest lane they
+/// can squeeze through at all
+pub(crate) fn comfortable_and_min_width(vehicle_type: VehicleType) -> (Distance, Distance) {
+    // Roughly based on https://nacto.org/publication/urban-street-design-guide/street-design-elements/lane-width/
+    // and typical cycle lane guidance
+    match vehicle_type {
+        VehicleType::Car => (Distance::meters(3.0), Distance::meters(2.2)),
+        VehicleType::Bus | VehicleType::Train => (Distance::meters(3.3), Distance::meters(2.7)),
+        VehicleType::Bike => (Distance::meters(1.5), Distance::meters(0.9)),
+    }
+}
+
+/// How long does it take to cover some distance from a standstill, accelerating at a constant
+/// rate (in m/s^2) up to a cruising speed?
+fn time_from_rest(dist: Distance, speed: Speed, acceleration: f64) -> Duration {
+    let v = speed.inner_meters_per_second();
+    let d = dist.inner_meters();
+    let accel_dist = v * v / (2.0 * acceleration);
+    if d >= accel_dist {
+        Duration::seconds(d / v + v / (2.0 * acceleration))
+    } else {
+        Duration::seconds((2.0 * d / acceleration).sqrt())
+    }
+}
+
+/// Drivers slow down in lanes narrower than they're comfortable with, down to half speed in the
+/// narrowest lanes. Turns aren't affected.
+fn narrow_lane_penalty(map: &Map, traversable: Traversable, vehicle_type: VehicleType) -> f64 {
+    let width = match traversable {
+        Traversable::Lane(l) => map.get_l(l).width,
+        Traversable::Turn(_) => {
+            return 1.0;
+        }
+    };
+    let (comfortable, min) = comfortable_and_min_width(vehicle_type);
+    if width >= comfortable {
+        return 1.0;
+    }
+    if width <= min {
+        return 0.5;
+    }
+    0.5 + 0.5 * (width - min).inner_meters() / (comfortable - min).inner_meters()
+}
@@ -1,19 +1,21 @@
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_hashmap, serialize_hashmap, FixedMap, IndexableKey};
 use geom::{Distance, Duration, PolyLine, Time};
 use map_model::{DrivingSide, IntersectionID, LaneID, Map, Path, PathStep, Position, Traversable};
 
-use crate::mechanics::car::{Car, CarState};
+use crate::mechanics::car::{comfortable_and_min_width, Car, CarState, DrivingConditions};
 use crate::mechanics::queue::{Queue, QueueEntry, Queued};
-use crate::sim::Ctx;
+use crate::sim::{CheckpointEntries, Ctx};
 use crate::{
-    ActionAtEnd, AgentID, AgentProperties, CarID, CarStatus, Command, CreateCar, DelayCause,
-    DistanceInterval, DrawCarInput, Event, IntersectionSimState, ParkedCar, ParkingSim,
-    ParkingSpot, PersonID, Problem, SimOptions, TimeInterval, TransitSimState, TripID, TripManager,
-    UnzoomedAgent, Vehicle, VehicleType, WalkingSimState, FOLLOWING_DISTANCE, MAX_CAR_LENGTH,
+    ActionAtEnd, AgentDiff, AgentID, AgentProperties, CarID, CarStatus, Command, CreateCar,
+    DelayCause, DistanceInterval, DrawCarInput, Event, IntersectionSimState, ParkedCar, ParkingSim,
+    ParkingSpot, PersonID, ProbeState, Problem, SimOptions, TimeInterval, TransitSimState, TripID,
+    TripManager, UnzoomedAgent, Vehicle, VehicleType, WalkingSimState, FOLLOWING_DISTANCE,
+    MAX_CAR_LENGTH,
 };
 
 const TIME_TO_WAIT_AT_BUS_STOP: Duration = Duration::const_seconds(10.0);
@@ -45,6 +47,8 @@ pub(crate) struct DrivingSimState {
 
     recalc_lanechanging: bool,
     handle_uber_turns: bool,
+    conditions: DrivingConditions,
+    cruise_for_parking: bool,
 
     time_to_unpark_onstreet: Duration,
     time_to_park_onstreet: Duration,
@@ -61,6 +65,18 @@ impl DrivingSimState {
             events: Vec::new(),
             recalc_lanechanging: !opts.dont_recalc_lanechanging,
             handle_uber_turns: !opts.dont_handle_uber_turns,
+            conditions: DrivingConditions {
+                lane_widths: opts.lane_widths_affect_driving,
+                weather_speed: opts
+                    .weather
+                    .map(|w| w.vehicle_speed_factor())
+                    .unwrap_or(1.0),
+                lane_capacity: opts
+                    .weather
+                    .map(|w| w.lane_capacity_factor())
+                    .unwrap_or(1.0),
+            },
+            cruise_for_parking: opts.cruise_for_parking,
             waiting_to_spawn: BTreeMap::new(),
 
             time_to_unpark_onstreet: Duration::seconds(10.0),
@@ -74,14 +90,14 @@ impl DrivingSimState {
         }
 
         for l in map.all_lanes() {
-            if l.lane_type.is_for_moving_vehicles() {
-                let q = Queue::new(Traversable::Lane(l.id), map);
+            if l.may_carry_traffic(map) {
+                let q = Queue::new(Traversable::Lane(l.id), map, sim.conditions.lane_capacity);
                 sim.queues.insert(q.id, q);
             }
         }
         for t in map.all_turns() {
             if !t.between_sidewalks() {
-                let q = Queue::new(Traversable::Turn(t.id), map);
+                let q = Queue::new(Traversable::Turn(t.id), map, sim.conditions.lane_capacity);
                 sim.queues.insert(q.id, q);
             }
         }
@@ -89,6 +105,15 @@ impl DrivingSimState {
         sim
     }
 
+    /// Switch to the behavior options of another instance, like a fresh one after loading a
+    /// savestate.
+    pub fn keep_options_from(&mut self, other: &DrivingSimState) {
+        self.recalc_lanechanging = other.recalc_lanechanging;
+        self.handle_uber_turns = other.handle_uber_turns;
+        self.conditions.lane_widths = other.conditions.lane_widths;
+        self.cruise_for_parking = other.cruise_for_parking;
+    }
+
     /// None if it worked, otherwise returns the CreateCar unmodified for possible retry.
     pub fn start_car_on_lane(
         &mut self,
@@ -123,15 +148,18 @@ impl DrivingSimState {
         let mut blocked_starts: Vec<(Position, usize)> = Vec::new();
         for lane in params.router.get_path().get_blocked_starts() {
             // This buffer makes sure other vehicles can enter the queue behind a blockage very
-            // close to the start of the lane and not spillover.
+            // close to the start of the lane and not spillover. A long vehicle's own
+            // blockage also has to fit on the lane.
             let pos = match params
                 .router
                 .get_path()
                 .get_req()
                 .start
                 .equiv_pos(lane, ctx.map)
-                .buffer_dist(MAX_CAR_LENGTH + FOLLOWING_DISTANCE, ctx.map)
-            {
+                .buffer_dist(
+                    params.vehicle.length.max(MAX_CAR_LENGTH) + FOLLOWING_DISTANCE,
+                    ctx.map,
+                ) {
                 Some(pos) => pos,
                 None => {
                     // TODO Loss of some simulation realism. We could also ban this upfront in
@@ -233,10 +261,12 @@ impl DrivingSimState {
                 // Have to do this early
                 if car.router.last_step() {
                     match car.router.maybe_handle_end(
+                        now,
                         start_dist,
                         &car.vehicle,
                         ctx.parking,
                         ctx.map,
+                        self.cruise_for_parking,
                         car.trip_and_person,
                         &mut self.events,
                     ) {
@@ -266,7 +296,7 @@ impl DrivingSimState {
                     }
                 }
 
-                car.state = car.crossing_state(start_dist, now, ctx.map);
+                car.state = car.crossing_state(start_dist, now, ctx.map, self.conditions);
                 start_crossing = true;
             }
             ctx.scheduler
@@ -399,6 +429,7 @@ impl DrivingSimState {
                             &self.queues,
                             ctx.map,
                             self.handle_uber_turns,
+                            now,
                         );
                     }
                     ctx.scheduler.push(now, Command::UpdateCar(car.vehicle.id));
@@ -418,7 +449,8 @@ impl DrivingSimState {
                         ));
                     }
 
-                    if let Some(target_lane) = self.pick_overtaking_lane(car, ctx.map) {
+                    if let Some(target_lane) = self.pick_overtaking_lane(car, slow_leader, ctx.map)
+                    {
                         // We need the current position of the car to see if lane-changing is
                         // actually feasible right now, so record our intention and trigger
                         // update_car_with_distances.
@@ -470,15 +502,17 @@ impl DrivingSimState {
                     // the next loop will pick that up. Just trigger the side effect of choosing an
                     // end_dist.
                     car.router.maybe_handle_end(
+                        now,
                         front,
                         &car.vehicle,
                         ctx.parking,
                         ctx.map,
+                        self.cruise_for_parking,
                         car.trip_and_person,
                         &mut self.events,
                     );
                 }
-                car.state = car.crossing_state(front, now, ctx.map);
+                car.state = car.crossing_state(front, now, ctx.map, self.conditions);
                 ctx.scheduler
                     .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
                 self.new_crossing_state(ctx, car);
@@ -525,14 +559,16 @@ impl DrivingSimState {
                 // way, until laggy_head is None.
 
                 let last_step = car.router.advance(
+                    now,
                     &car.vehicle,
                     ctx.parking,
                     ctx.map,
+                    self.cruise_for_parking,
                     car.trip_and_person,
                     &mut self.events,
                 );
                 car.total_blocked_time += now - blocked_since;
-                car.state = car.crossing_state(Distance::ZERO, now, ctx.map);
+                car.state = car.crossing_state(Distance::ZERO, now, ctx.map, self.conditions);
                 ctx.scheduler
                     .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
                 self.events.push(Event::AgentEntersTraversable(
@@ -540,7 +576,7 @@ impl DrivingSimState {
                     car.trip_and_person.map(|(t, _)| t),
                     goto,
                     if car.vehicle.vehicle_type.is_transit() {
-                        Some(transit.get_passengers(car.vehicle.id).len())
+                        Some(transit.num_passengers(car.vehicle.id))
                     } else {
                         None
                     },
@@ -560,6 +596,7 @@ impl DrivingSimState {
                         ),
                         now,
                         ctx.map,
+                        self.conditions,
                     )
                     .get_end_time(),
                     Command::UpdateLaggyHead(car.vehicle.id),
@@ -639,15 +676,17 @@ impl DrivingSimState {
                 }
 
                 match car.router.maybe_handle_end(
+                    now,
                     our_dist,
                     &car.vehicle,
                     ctx.parking,
                     ctx.map,
+                    self.cruise_for_parking,
                     car.trip_and_person,
                     &mut self.events,
                 ) {
                     Some(ActionAtEnd::VanishAtBorder(i)) => {
-                        car.total_blocked_time += now - blocked_since;
+                        car.stop_queueing(now, blocked_since, &mut self.events);
                         // Don't do this for buses
                         if car.trip_and_person.is_some() {
                             trips.car_or_bike_reached_border(
@@ -662,7 +701,7 @@ impl DrivingSimState {
                         false
                     }
                     Some(ActionAtEnd::GiveUpOnParking) => {
-                        car.total_blocked_time += now - blocked_since;
+                        car.stop_queueing(now, blocked_since, &mut self.events);
                         trips.cancel_trip(
                             now,
                             car.trip_and_person.unwrap().0,
@@ -675,7 +714,7 @@ impl DrivingSimState {
                         false
                     }
                     Some(ActionAtEnd::StartParking(spot)) => {
-                        car.total_blocked_time += now - blocked_since;
+                        car.stop_queueing(now, blocked_since, &mut self.events);
                         let delay = match spot {
                             ParkingSpot::Onstreet(_, _) => self.time_to_park_onstreet,
                             ParkingSpot::Offstreet(_, _) | ParkingSpot::Lot(_, _) => {
@@ -693,15 +732,15 @@ impl DrivingSimState {
                         true
                     }
                     Some(ActionAtEnd::GotoLaneEnd) => {
-                        car.total_blocked_time += now - blocked_since;
-                        car.state = car.crossing_state(our_dist, now, ctx.map);
+                        car.stop_queueing(now, blocked_since, &mut self.events);
+                        car.state = car.crossing_state(our_dist, now, ctx.map, self.conditions);
                         ctx.scheduler
                             .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
                         self.new_crossing_state(ctx, car);
                         true
                     }
                     Some(ActionAtEnd::StopBiking(bike_rack)) => {
-                        car.total_blocked_time += now - blocked_since;
+                        car.stop_queueing(now, blocked_since, &mut self.events);
                         trips.bike_reached_end(
                             now,
                             car.vehicle.id,
@@ -713,7 +752,7 @@ impl DrivingSimState {
                         false
                     }
                     Some(ActionAtEnd::BusAtStop) => {
-                        car.total_blocked_time += now - blocked_since;
+                        car.stop_queueing(now, blocked_since, &mut self.events);
                         if transit.bus_arrived_at_stop(now, car.vehicle.id, trips, walking, ctx) {
                             car.state = CarState::IdlingAtStop(
                                 our_dist,
@@ -737,7 +776,7 @@ impl DrivingSimState {
                         // to be slower otherwise. :(
                         /*
                         // If this car wasn't blocked at all, when would it reach its goal?
-                        let ideal_end_time = match car.crossing_state(our_dist, now, map) {
+                        let ideal_end_time = match car.crossing_state(our_dist, now, map, self.conditions) {
                             CarState::Crossing { time_int, .. } => time_int.end,
                             _ => unreachable!(),
                         };
@@ -773,10 +812,16 @@ impl DrivingSimState {
                 false
             }
             CarState::IdlingAtStop(dist, _) => {
-                car.router = transit.bus_departed_from_stop(car.vehicle.id, ctx.map);
+                car.router = match transit.bus_departed_from_stop(now, car.vehicle.id, trips, ctx) {
+                    Some(router) => router,
+                    None => {
+                        // Vanishing in place
+                        return false;
+                    }
+                };
                 self.events
                     .push(Event::PathAmended(car.router.get_path().clone()));
-                car.state = car.crossing_state(dist, now, ctx.map);
+                car.state = car.crossing_state(dist, now, ctx.map, self.conditions);
                 ctx.scheduler
                     .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
                 self.new_crossing_state(ctx, car);
@@ -899,8 +944,9 @@ impl DrivingSimState {
                     // behind us? !follower.router.last_step()
 
                     // Prevent them from jumping forwards.
-                    follower.total_blocked_time += now - blocked_since;
-                    follower.state = follower.crossing_state(follower_dist, now, ctx.map);
+                    follower.stop_queueing(now, blocked_since, &mut self.events);
+                    follower.state =
+                        follower.crossing_state(follower_dist, now, ctx.map, self.conditions);
                     ctx.scheduler.update(
                         follower.state.get_end_time(),
                         Command::UpdateCar(follower_id),
@@ -912,7 +958,8 @@ impl DrivingSimState {
                     // If the follower was still Crossing, they might not've been blocked by the
                     // leader yet. But recalculating their Crossing state isn't necessarily a no-op
                     // -- this could prevent them from suddenly warping past a blockage.
-                    follower.state = follower.crossing_state(follower_dist, now, ctx.map);
+                    follower.state =
+                        follower.crossing_state(follower_dist, now, ctx.map, self.conditions);
                     ctx.scheduler.update(
                         follower.state.get_end_time(),
                         Command::UpdateCar(follower_id),
@@ -927,21 +974,21 @@ impl DrivingSimState {
                     // This is a fun case -- something stopped blocking somebody that was in the
                     // process of lane-changing! Similar to the Crossing case above, we just have
                     // to update the distance/time intervals, but otherwise leave them in the
-                    // middle of their lane-changing. It's guaranteed that lc_time will continue to
-                    // finish before the new time interval, because there's no possible way
-                    // recalculating this crossing state here will speed things up from the
-                    // original estimate.
+                    // middle of their lane-changing. If they started lane-changing from a stop,
+                    // the original estimate included accelerating, so recalculating here might
+                    // speed things up. Make sure lc_time still finishes first.
                     let (new_time, new_dist) = match follower.crossing_state_with_end_dist(
                         DistanceInterval::new_driving(follower_dist, ctx.map.get_l(to).length()),
                         now,
                         ctx.map,
+                        self.conditions,
                     ) {
                         CarState::Crossing {
                             time_int, dist_int, ..
                         } => (time_int, dist_int),
                         _ => unreachable!(),
                     };
-                    assert!(new_time.end >= lc_time.end);
+                    let new_time = TimeInterval::new(new_time.start, new_time.end.max(lc_time.end));
                     follower.state = CarState::ChangingLanes {
                         from,
                         to,
@@ -1006,6 +1053,7 @@ impl DrivingSimState {
                     ),
                     now,
                     ctx.map,
+                    self.conditions,
                 )
                 .get_end_time();
             // Sometimes due to rounding, retry_at will be exactly time, but we really need to
@@ -1079,6 +1127,7 @@ impl DrivingSimState {
                                             &self.queues,
                                             ctx.map,
                                             self.handle_uber_turns,
+                                            now,
                                         );
                                     }
                                     ctx.scheduler
@@ -1108,8 +1157,10 @@ impl DrivingSimState {
     /// - The lane must be in the same direction as the current; no support for crossing the road's
     ///   yellow line yet.
     /// - Prefer passing on the left (for DrivingSide::Right)
+    /// - If lane widths matter, don't squeeze past a cyclist using a lane too narrow for the
+    ///   overtaking vehicle
     /// For now, just pick one candidate lane, even if both might be usable.
-    fn pick_overtaking_lane(&self, car: &Car, map: &Map) -> Option<LaneID> {
+    fn pick_overtaking_lane(&self, car: &Car, slow_leader: CarID, map: &Map) -> Option<LaneID> {
         // Don't overtake in the middle of a turn!
         let current_lane = map.get_l(car.router.head().maybe_lane()?);
         let road = map.get_parent(current_lane.id);
@@ -1142,6 +1193,12 @@ impl DrivingSimState {
             {
                 continue;
             }
+            if self.conditions.lane_widths
+                && slow_leader.vehicle_type == VehicleType::Bike
+                && target_lane.width < comfortable_and_min_width(car.vehicle.vehicle_type).0
+            {
+                continue;
+            }
             // Is this other lane compatible with the path? We won't make any attempts to return to the
             // original lane after changing.
             if !car
@@ -1182,10 +1239,12 @@ impl DrivingSimState {
 
         // Calculate the crossing state in the target queue. Pass in the DistanceInterval
         // explicitly, because we haven't modified the route yet.
-        let (new_time, new_dist) = match car.crossing_state_with_end_dist(
+        let (new_time, new_dist) = match car.crossing_state_along(
+            Traversable::Lane(target_lane),
             DistanceInterval::new_driving(front_target_queue, ctx.map.get_l(target_lane).length()),
             now,
             ctx.map,
+            self.conditions,
         ) {
             CarState::Crossing {
                 time_int, dist_int, ..
@@ -1256,7 +1315,7 @@ impl DrivingSimState {
         // Calculate all queues that should exist now.
         let mut new_queues = HashSet::new();
         for l in map.all_lanes() {
-            if l.lane_type.is_for_moving_vehicles() {
+            if l.may_carry_traffic(map) {
                 new_queues.insert(Traversable::Lane(l.id));
             }
         }
@@ -1287,7 +1346,8 @@ impl DrivingSimState {
 
         // Create any new queues
         for key in new_queues {
-            self.queues.insert(key, Queue::new(key, map));
+            self.queues
+                .insert(key, Queue::new(key, map, self.conditions.lane_capacity));
         }
     }
 
@@ -1304,8 +1364,107 @@ impl DrivingSimState {
     }
 }
 
+// Checkpoints
+impl DrivingSimState {
+    /// For incremental savestates, serialize each car and queue separately from everything else.
+    pub(crate) fn split_for_checkpoint(
+        &mut self,
+    ) -> (Vec<u8>, CheckpointEntries, CheckpointEntries) {
+        // Keep the capacity, so the rest serializes the same after joining the cars back in
+        let cars = std::mem::replace(
+            &mut self.cars,
+            FixedMap::with_capacity(self.cars.capacity()),
+        );
+        let queues = std::mem::take(&mut self.queues);
+        let rest = abstutil::to_binary(self);
+
+        let car_entries = cars
+            .values()
+            .map(|car| {
+                (
+                    abstutil::to_binary(&car.vehicle.id),
+                    abstutil::to_binary(car),
+                )
+            })
+            .collect();
+        let queue_entries = queues
+            .iter()
+            .map(|(id, queue)| (abstutil::to_binary(id), abstutil::to_binary(queue)))
+            .collect();
+
+        self.cars = cars;
+        self.queues = queues;
+        (rest, car_entries, queue_entries)
+    }
+
+    /// The inverse of `split_for_checkpoint`.
+    pub(crate) fn join_from_checkpoint(
+        rest: &[u8],
+        cars: CheckpointEntries,
+        queues: CheckpointEntries,
+    ) -> Result<DrivingSimState> {
+        let mut state: DrivingSimState = abstutil::from_binary(rest)?;
+        for (id, car) in cars {
+            state
+                .cars
+                .insert(abstutil::from_binary(&id)?, abstutil::from_binary(&car)?);
+        }
+        for (id, queue) in queues {
+            state
+                .queues
+                .insert(abstutil::from_binary(&id)?, abstutil::from_binary(&queue)?);
+        }
+        Ok(state)
+    }
+}
+
 // Queries
 impl DrivingSimState {
+    /// Find the cars and queues that differ from another simulation.
+    pub fn diff(
+        &self,
+        other: &DrivingSimState,
+    ) -> (BTreeMap<CarID, AgentDiff>, BTreeSet<Traversable>) {
+        let mut cars = BTreeMap::new();
+        for car in self.cars.values() {
+            let id = car.vehicle.id;
+            match other.cars.get(&id) {
+                Some(other_car) => {
+                    if abstutil::to_binary(car) != abstutil::to_binary(other_car) {
+                        cars.insert(id, AgentDiff::StateDiffers);
+                    }
+                }
+                None => {
+                    cars.insert(id, AgentDiff::OnlyInFirst);
+                }
+            }
+        }
+        for car in other.cars.values() {
+            if !self.cars.contains_key(&car.vehicle.id) {
+                cars.insert(car.vehicle.id, AgentDiff::OnlyInSecond);
+            }
+        }
+
+        let mut queues = BTreeSet::new();
+        for (id, queue) in &self.queues {
+            let same = other
+                .queues
+                .get(id)
+                .map(|q| abstutil::to_binary(q) == abstutil::to_binary(queue))
+                .unwrap_or(false);
+            if !same {
+                queues.insert(*id);
+            }
+        }
+        for id in other.queues.keys() {
+            if !self.queues.contains_key(id) {
+                queues.insert(*id);
+            }
+        }
+
+        (cars, queues)
+    }
+
     /// Note the ordering of results is non-deterministic!
     pub fn get_unzoomed_agents(&self, now: Time, map: &Map) -> Vec<UnzoomedAgent> {
         let mut result = Vec::new();
@@ -1380,6 +1539,54 @@ impl DrivingSimState {
         result
     }
 
+    /// What a traced car is doing right now.
+    pub fn probe_car(&self, id: CarID) -> Option<ProbeState> {
+        let car = self.cars.get(&id)?;
+        let (activity, speed) = match car.state {
+            CarState::Crossing {
+                ref time_int,
+                ref dist_int,
+                ..
+            } => ("moving".to_string(), ProbeState::speed(time_int, dist_int)),
+            CarState::ChangingLanes {
+                from,
+                to,
+                ref new_time,
+                ref new_dist,
+                ..
+            } => (
+                format!("changing lanes from {} to {}", from, to),
+                ProbeState::speed(new_time, new_dist),
+            ),
+            CarState::Queued {
+                want_to_change_lanes,
+                ..
+            } => (
+                match want_to_change_lanes {
+                    Some(l) => format!("queued, wanting to change to {}", l),
+                    None => "queued".to_string(),
+                },
+                None,
+            ),
+            CarState::WaitingToAdvance { .. } => (
+                match car.router.maybe_next() {
+                    Some(Traversable::Turn(t)) => format!("waiting to start {}", t),
+                    _ => "waiting to advance".to_string(),
+                },
+                None,
+            ),
+            CarState::Unparking { ref spot, .. } => (format!("leaving {:?}", spot), None),
+            CarState::Parking(_, ref spot, _) => (format!("parking at {:?}", spot), None),
+            CarState::IdlingAtStop(_, _) => ("idling at a stop".to_string(), None),
+        };
+        Some(ProbeState::new(
+            car.router.head(),
+            activity,
+            speed,
+            car.router.get_path().get_steps().iter().cloned(),
+        ))
+    }
+
     /// This is about as expensive as get_draw_cars_on.
     pub fn get_single_draw_car(
         &self,
@@ -1522,6 +1729,10 @@ impl DrivingSimState {
         let car = self.cars.get(&id)?;
         Some(car.router.get_path())
     }
+    pub fn cruising_for_parking(&self) -> bool {
+        self.cruise_for_parking
+    }
+
     pub fn get_all_driving_paths(&self) -> Vec<&Path> {
         self.cars
             .values()
@@ -1601,6 +1812,29 @@ impl DrivingSimState {
         }
     }
 
+    /// Is any vehicle on this lane over some point, or within `gap` of reaching it? Pedestrians
+    /// waiting at an unsignalized mid-block crossing use this to find a gap in traffic.
+    pub fn vehicle_approaching(
+        &self,
+        now: Time,
+        lane: LaneID,
+        dist: Distance,
+        gap: Distance,
+    ) -> bool {
+        self.queues
+            .get(&Traversable::Lane(lane))
+            .map(|q| {
+                q.get_car_positions(now, &self.cars, &self.queues)
+                    .into_iter()
+                    .any(|entry| {
+                        matches!(entry.member, Queued::Vehicle(_))
+                            && entry.back <= dist
+                            && entry.front >= dist - gap
+                    })
+            })
+            .unwrap_or(false)
+    }
+
     pub fn debug_queue_lengths(&self, l: LaneID) -> Option<(Distance, Distance)> {
         let queue = self.queues.get(&Traversable::Lane(l))?;
         Some((queue.reserved_length, queue.geom_len))
@@ -5,8 +5,8 @@ use serde::{Deserialize, Serialize};
 use abstutil::{deserialize_btreemap, prettyprint_usize, serialize_btreemap, FixedMap};
 use geom::{Duration, Time};
 use map_model::{
-    ControlStopSign, ControlTrafficSignal, Intersection, IntersectionID, LaneID, Map, StageType,
-    Traversable, TurnID, TurnPriority, TurnType, UberTurn,
+    ControlStopSign, ControlTrafficSignal, Intersection, IntersectionID, LaneID, Map, Roundabout,
+    StageType, Traversable, TurnID, TurnPriority, TurnType, UberTurn,
 };
 
 use crate::mechanics::car::{Car, CarState};
@@ -16,13 +16,16 @@ use crate::{
 };
 
 const WAIT_AT_STOP_SIGN: Duration = Duration::const_seconds(0.5);
+// Vehicles entering a roundabout need at least this much time before the next vehicle going
+// around arrives
+const ROUNDABOUT_ENTRY_GAP: Duration = Duration::const_seconds(3.0);
 const WAIT_BEFORE_YIELD_AT_TRAFFIC_SIGNAL: Duration = Duration::const_seconds(0.2);
 
 /// Manages conflicts at intersections. When an agent has reached the end of a lane, they call
 /// maybe_start_turn to make a Request. Based on the intersection type (stop sign, traffic signal,
-/// or a "freeform policy"), the Request gets queued or immediately accepted. When agents finish
-/// turns or when some time passes (for traffic signals), the intersection also gets a chance to
-/// react, maybe granting one of the pending requests.
+/// roundabout, or a "freeform policy"), the Request gets queued or immediately accepted. When
+/// agents finish turns or when some time passes (for traffic signals), the intersection also gets
+/// a chance to react, maybe granting one of the pending requests.
 ///
 /// Most of the complexity comes from attempting to workaround
 /// <https://a-b-street.github.io/docs/tech/trafficsim/gridlock.html>.
@@ -34,6 +37,7 @@ pub(crate) struct IntersectionSimState {
     break_turn_conflict_cycles: bool,
     handle_uber_turns: bool,
     disable_turn_conflicts: bool,
+    permissive_turn_critical_gap: Duration,
     // (x, y) means x is blocked by y. It's a many-to-many relationship. TODO Better data
     // structure.
     blocked_by: BTreeSet<(CarID, CarID)>,
@@ -97,6 +101,29 @@ struct Request {
     turn: TurnID,
 }
 
+impl State {
+    /// Gap acceptance: is a conflicting vehicle arriving within `gap`? If so, the request should
+    /// wait. Check again once they should've arrived, in case they don't actually come.
+    fn wait_for_gap<F: Fn(&Request) -> bool>(
+        &self,
+        req: &Request,
+        now: Time,
+        gap: Duration,
+        conflicts: F,
+        scheduler: Option<&mut Scheduler>,
+    ) -> bool {
+        for (other, eta) in self.leader_eta.values() {
+            if *eta > now && *eta < now + gap && conflicts(other) {
+                if let Some(s) = scheduler {
+                    s.update(*eta, Command::update_agent(req.agent));
+                }
+                return true;
+            }
+        }
+        false
+    }
+}
+
 // Mutations
 impl IntersectionSimState {
     pub fn new(map: &Map, scheduler: &mut Scheduler, opts: &SimOptions) -> IntersectionSimState {
@@ -107,6 +134,7 @@ impl IntersectionSimState {
             break_turn_conflict_cycles: !opts.dont_break_turn_conflict_cycles,
             handle_uber_turns: !opts.dont_handle_uber_turns,
             disable_turn_conflicts: opts.disable_turn_conflicts,
+            permissive_turn_critical_gap: Duration::seconds(opts.permissive_turn_critical_gap),
             blocked_by: BTreeSet::new(),
             events: Vec::new(),
 
@@ -140,6 +168,17 @@ impl IntersectionSimState {
         sim
     }
 
+    /// Switch to the policy options of another instance, like a fresh one after loading a
+    /// savestate.
+    pub fn keep_options_from(&mut self, other: &IntersectionSimState) {
+        self.use_freeform_policy_everywhere = other.use_freeform_policy_everywhere;
+        self.dont_block_the_box = other.dont_block_the_box;
+        self.break_turn_conflict_cycles = other.break_turn_conflict_cycles;
+        self.handle_uber_turns = other.handle_uber_turns;
+        self.disable_turn_conflicts = other.disable_turn_conflicts;
+        self.permissive_turn_critical_gap = other.permissive_turn_critical_gap;
+    }
+
     pub fn turn_finished(
         &mut self,
         now: Time,
@@ -251,6 +290,15 @@ impl IntersectionSimState {
                     }
                 }
             }
+        } else if let Some(roundabout) = map.get_roundabout(i) {
+            // Traffic going around has priority over everybody else
+            for (req, _, _) in all {
+                if roundabout.is_circulating(req.turn) {
+                    protected.push(req);
+                } else {
+                    yielding.push(req);
+                }
+            }
         } else if let Some(sign) = map.maybe_get_stop_sign(i) {
             for (req, _, _) in all {
                 match sign.get_priority(req.turn, map) {
@@ -475,6 +523,8 @@ impl IntersectionSimState {
             true
         } else if let Some(signal) = map.maybe_get_traffic_signal(turn.parent) {
             self.traffic_signal_policy(&req, map, signal, speed, now, Some(scheduler))
+        } else if let Some(roundabout) = map.get_roundabout(turn.parent) {
+            self.roundabout_policy(&req, map, roundabout, now, scheduler)
         } else if let Some(sign) = map.maybe_get_stop_sign(turn.parent) {
             self.stop_sign_policy(&req, map, sign, speed, now, scheduler)
         } else {
@@ -915,6 +965,47 @@ impl IntersectionSimState {
         true
     }
 
+    /// All of the intersections around a roundabout act as one junction, instead of a cluster of
+    /// stop signs. Vehicles entering don't stop first; they just wait for a gap in the traffic
+    /// going around. Everybody else only has to avoid turns already in progress.
+    fn roundabout_policy(
+        &self,
+        req: &Request,
+        map: &Map,
+        roundabout: &Roundabout,
+        now: Time,
+        scheduler: &mut Scheduler,
+    ) -> bool {
+        if !req.agent.is_pedestrian() && roundabout.is_entry(req.turn) {
+            return self.roundabout_entry_policy(req, map, roundabout, now, scheduler);
+        }
+        true
+    }
+
+    fn roundabout_entry_policy(
+        &self,
+        req: &Request,
+        map: &Map,
+        roundabout: &Roundabout,
+        now: Time,
+        scheduler: &mut Scheduler,
+    ) -> bool {
+        let state = &self.state[&req.turn.parent];
+        let our_turn = map.get_t(req.turn);
+        let conflicts = |other: &Request| {
+            roundabout.is_circulating(other.turn) && our_turn.conflicts_with(map.get_t(other.turn))
+        };
+
+        // Somebody already going around is waiting to continue. They have priority, so they'll
+        // be woken up first, and we'll be woken up after their turn finishes.
+        if state.waiting.keys().any(&conflicts) {
+            return false;
+        }
+
+        // Somebody going around is about to arrive, so the gap isn't big enough
+        !state.wait_for_gap(req, now, ROUNDABOUT_ENTRY_GAP, conflicts, Some(scheduler))
+    }
+
     fn traffic_signal_policy(
         &mut self,
         req: &Request,
@@ -939,6 +1030,29 @@ impl IntersectionSimState {
             return false;
         }
 
+        // A queue jump holds general traffic back at the start of a stage, so buses waiting in
+        // the bus lane get through first. Extended stages are well past their start.
+        if !req.agent.is_pedestrian() && signal_state.extensions_count == 0 {
+            let src = map.get_l(req.turn.src);
+            let queue_jump = map.get_r(src.id.road).bus_queue_jump(src.dir);
+            let since_green = full_stage_duration - remaining_stage_time;
+            let prev_stage = &signal.stages
+                [(signal_state.current_stage + signal.stages.len() - 1) % signal.stages.len()];
+            if !src.is_bus()
+                && since_green < queue_jump
+                && prev_stage.get_priority_of_turn(req.turn, map.get_i(state.id))
+                    == TurnPriority::Banned
+            {
+                if let Some(s) = scheduler {
+                    s.push(
+                        now + (queue_jump - since_green),
+                        Command::update_agent(req.agent),
+                    );
+                }
+                return false;
+            }
+        }
+
         if our_priority == TurnPriority::Yield
             && now < our_time + WAIT_BEFORE_YIELD_AT_TRAFFIC_SIGNAL
         {
@@ -958,8 +1072,24 @@ impl IntersectionSimState {
         // Priority vehicles getting scheduled first just requires a little tweak in
         // update_intersection.
 
-        // TODO Make sure we can optimistically finish this turn before an approaching
-        // higher-priority vehicle wants to begin.
+        // Gap acceptance: a vehicle yielding only goes if the next conflicting vehicle with
+        // protected priority is far enough away.
+        if our_priority == TurnPriority::Yield && !req.agent.is_pedestrian() {
+            let i = map.get_i(state.id);
+            let conflicts = |other: &Request| {
+                stage.get_priority_of_turn(other.turn, i) == TurnPriority::Protected
+                    && turn.conflicts_with(map.get_t(other.turn))
+            };
+            if state.wait_for_gap(
+                req,
+                now,
+                self.permissive_turn_critical_gap,
+                conflicts,
+                scheduler,
+            ) {
+                return false;
+            }
+        }
 
         // Optimistically if nobody else is in the way, this is how long it'll take to finish the
         // turn. Don't start the turn if we won't finish by the time the light changes. If we get
@@ -1173,3 +1303,122 @@ fn allow_block_the_box(i: &Intersection) -> bool {
     }
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use map_model::Turn;
+
+    use super::*;
+    use crate::VehicleType;
+
+    fn car(id: usize) -> AgentID {
+        AgentID::Car(CarID {
+            id,
+            vehicle_type: VehicleType::Car,
+        })
+    }
+
+    /// Where traffic enters the roundabout, finds the entry turn and a turn going around that
+    /// conflicts with it.
+    fn setup() -> (Map, IntersectionSimState, Scheduler, TurnID, TurnID) {
+        let map = Map::tiny_roundabout();
+        let roundabout = &map.all_roundabouts()[0];
+        let (entry, circulating) = roundabout
+            .members
+            .iter()
+            .find_map(|i| {
+                let turns: Vec<&Turn> = map
+                    .get_i(*i)
+                    .turns
+                    .iter()
+                    .filter(|t| map.get_l(t.id.src).is_driving())
+                    .collect();
+                let entry = turns.iter().find(|t| roundabout.is_entry(t.id))?;
+                let circulating = turns
+                    .iter()
+                    .find(|t| roundabout.is_circulating(t.id) && t.conflicts_with(entry))?;
+                Some((entry.id, circulating.id))
+            })
+            .expect("no entry into the roundabout");
+
+        let mut scheduler = Scheduler::new();
+        let state = IntersectionSimState::new(&map, &mut scheduler, &SimOptions::default());
+        (map, state, scheduler, entry, circulating)
+    }
+
+    fn enter(
+        state: &IntersectionSimState,
+        map: &Map,
+        scheduler: &mut Scheduler,
+        entry: TurnID,
+        now: Time,
+    ) -> bool {
+        let req = Request {
+            agent: car(0),
+            turn: entry,
+        };
+        state.roundabout_policy(
+            &req,
+            map,
+            map.get_roundabout(entry.parent).unwrap(),
+            now,
+            scheduler,
+        )
+    }
+
+    #[test]
+    fn test_enter_empty_roundabout() {
+        let (map, state, mut scheduler, entry, _) = setup();
+        assert!(enter(
+            &state,
+            &map,
+            &mut scheduler,
+            entry,
+            Time::START_OF_DAY
+        ));
+    }
+
+    #[test]
+    fn test_yield_to_traffic_waiting_to_go_around() {
+        let (map, mut state, mut scheduler, entry, circulating) = setup();
+        let now = Time::START_OF_DAY;
+        state.state.get_mut(&entry.parent).unwrap().waiting.insert(
+            Request {
+                agent: car(1),
+                turn: circulating,
+            },
+            (now, false),
+        );
+        assert!(!enter(&state, &map, &mut scheduler, entry, now));
+    }
+
+    #[test]
+    fn test_wait_for_a_gap_in_traffic_going_around() {
+        let (map, mut state, mut scheduler, entry, circulating) = setup();
+        let now = Time::START_OF_DAY;
+        let other = Request {
+            agent: car(1),
+            turn: circulating,
+        };
+
+        // Too close to pull out in front of. Check again once they should've arrived.
+        let eta = now + Duration::seconds(1.0);
+        state
+            .state
+            .get_mut(&entry.parent)
+            .unwrap()
+            .leader_eta
+            .insert(circulating.src, (other.clone(), eta));
+        assert!(!enter(&state, &map, &mut scheduler, entry, now));
+        assert_eq!(scheduler.peek_next_time(), Some(eta));
+
+        // Far enough away
+        state
+            .state
+            .get_mut(&entry.parent)
+            .unwrap()
+            .leader_eta
+            .insert(circulating.src, (other, now + Duration::seconds(10.0)));
+        assert!(enter(&state, &map, &mut scheduler, entry, now));
+    }
+}
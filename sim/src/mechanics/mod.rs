@@ -1,9 +1,11 @@
+pub(crate) use self::bike_parking::BikeParkingSimState;
 pub(crate) use self::driving::DrivingSimState;
 pub(crate) use self::intersection::IntersectionSimState;
 pub(crate) use self::parking::{ParkingSim, ParkingSimState};
 pub(crate) use self::queue::Queue;
 pub(crate) use self::walking::WalkingSimState;
 
+mod bike_parking;
 mod car;
 mod driving;
 mod intersection;
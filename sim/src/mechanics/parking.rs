@@ -1,4 +1,4 @@
-use std::collections::{hash_map::Entry, BTreeMap, BTreeSet, BinaryHeap, HashMap};
+use std::collections::{hash_map::Entry, BTreeMap, BTreeSet, BinaryHeap, HashMap, VecDeque};
 
 use enum_dispatch::enum_dispatch;
 use rand::{Rng, SeedableRng};
@@ -9,11 +9,12 @@ use abstutil::{
     deserialize_btreemap, deserialize_multimap, serialize_btreemap, serialize_multimap, MultiMap,
     Timer,
 };
-use geom::{Distance, PolyLine, Pt2D};
+use geom::{Distance, PolyLine, Pt2D, Time};
 use map_model::{
-    BuildingID, Lane, LaneID, LaneType, Map, OffstreetParking, ParkingLotID, PathConstraints,
-    PathStep, Position, Traversable, TurnID,
+    BuildingID, CurbUse, Lane, LaneID, LaneType, Map, OffstreetParking, ParkingLotID,
+    PathConstraints, PathStep, Position, Traversable, TurnID,
 };
+use synthpop::VehicleClass;
 
 use crate::{CarID, CarStatus, DrawCarInput, Event, ParkedCar, ParkingSpot, PersonID, Vehicle};
 
@@ -40,7 +41,11 @@ pub trait ParkingSim {
     /// There's no DrawCarInput for cars parked offstreet, so we need this.
     fn canonical_pt(&self, id: CarID, map: &Map) -> Option<Pt2D>;
     fn get_all_draw_cars(&self, map: &Map) -> Vec<DrawCarInput>;
+    /// Nobody is parked in or heading to this spot, and stopping there isn't banned right now.
+    /// Curb regulations may still keep some vehicles out; see `may_park`.
     fn is_free(&self, spot: ParkingSpot) -> bool;
+    /// Do the curb regulations active right now allow this vehicle to park in this spot?
+    fn may_park(&self, spot: ParkingSpot, vehicle: &Vehicle) -> bool;
     fn get_car_at_spot(&self, spot: ParkingSpot) -> Option<&ParkedCar>;
     /// The vehicle's front is currently at the given driving_pos. Returns all valid spots and their
     /// driving position.
@@ -72,6 +77,9 @@ pub trait ParkingSim {
         target: BuildingID,
         map: &Map,
     ) -> Option<(Vec<PathStep>, ParkingSpot, Position)>;
+    /// Apply the curb regulations active at this time. Cars parked where they're no longer allowed
+    /// are towed to the nearest unregulated spot. Returns the number of towed cars.
+    fn update_parking_restrictions(&mut self, now: Time, map: &Map) -> usize;
     fn collect_events(&mut self) -> Vec<Event>;
     fn all_parked_car_positions(&self, map: &Map) -> Vec<(Position, PersonID)>;
     fn bldg_to_parked_cars(&self, b: BuildingID) -> Vec<CarID>;
@@ -147,6 +155,13 @@ pub struct NormalParkingSimState {
     )]
     driving_to_lots: MultiMap<LaneID, ParkingLotID>,
 
+    // On-street spots with a curb regulation currently active
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    curb_regulations: BTreeMap<ParkingSpot, CurbUse>,
+
     events: Vec<Event>,
 }
 
@@ -164,6 +179,8 @@ impl NormalParkingSimState {
             num_spots_per_lot: BTreeMap::new(),
             driving_to_lots: MultiMap::new(),
 
+            curb_regulations: BTreeMap::new(),
+
             events: Vec::new(),
         };
         for l in map.all_lanes() {
@@ -211,6 +228,38 @@ impl NormalParkingSimState {
 
         sim
     }
+
+    /// Search outwards from a driving lane for the closest free on-street or parking lot spot.
+    fn find_tow_destination(&self, start: LaneID, map: &Map) -> Option<ParkingSpot> {
+        // Don't tow cars across the whole map
+        let max_lanes = 100;
+
+        let mut visited = BTreeSet::new();
+        visited.insert(start);
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        while let Some(current) = queue.pop_front() {
+            for l in self.driving_to_parking_lanes.get(current) {
+                if let Some(spot) = self.get_free_onstreet_spots(*l).into_iter().next() {
+                    return Some(spot);
+                }
+            }
+            for pl in self.driving_to_lots.get(current) {
+                if let Some(spot) = self.get_free_lot_spots(*pl).into_iter().next() {
+                    return Some(spot);
+                }
+            }
+            if visited.len() >= max_lanes {
+                continue;
+            }
+            for turn in map.get_turns_for(current, PathConstraints::Car) {
+                if visited.insert(turn.id.dst) {
+                    queue.push_back(turn.id.dst);
+                }
+            }
+        }
+        None
+    }
 }
 
 impl ParkingSim for NormalParkingSimState {
@@ -227,6 +276,14 @@ impl ParkingSim for NormalParkingSimState {
         self.driving_to_offstreet = new.driving_to_offstreet;
         self.num_spots_per_lot = new.num_spots_per_lot;
         self.driving_to_lots = new.driving_to_lots;
+        let onstreet_lanes = &self.onstreet_lanes;
+        self.curb_regulations.retain(|spot, _| match spot {
+            ParkingSpot::Onstreet(l, idx) => onstreet_lanes
+                .get(l)
+                .map(|lane| *idx < lane.spot_dist_along.len())
+                .unwrap_or(false),
+            _ => false,
+        });
 
         // For every spot filled or reserved before, make sure that same spot still exists. If not,
         // evict that car.
@@ -258,7 +315,8 @@ impl ParkingSim for NormalParkingSimState {
         let mut spots: Vec<ParkingSpot> = Vec::new();
         if let Some(lane) = self.onstreet_lanes.get(&l) {
             for spot in lane.spots() {
-                if self.is_free(spot) {
+                // Without knowing the vehicle, skip any spot with a curb regulation
+                if self.is_free(spot) && !self.curb_regulations.contains_key(&spot) {
                     spots.push(spot);
                 }
             }
@@ -431,9 +489,22 @@ impl ParkingSim for NormalParkingSimState {
     }
 
     fn is_free(&self, spot: ParkingSpot) -> bool {
+        if self.curb_regulations.get(&spot) == Some(&CurbUse::NoStopping) {
+            return false;
+        }
         !self.occupants.contains_key(&spot) && !self.reserved_spots.contains_key(&spot)
     }
 
+    fn may_park(&self, spot: ParkingSpot, vehicle: &Vehicle) -> bool {
+        match self.curb_regulations.get(&spot) {
+            Some(kind) => kind.allows(
+                vehicle.class == Some(VehicleClass::Truck),
+                vehicle.disabled_permit,
+            ),
+            None => true,
+        }
+    }
+
     fn get_car_at_spot(&self, spot: ParkingSpot) -> Option<&ParkedCar> {
         let car = self.occupants.get(&spot)?;
         Some(&self.parked_cars[car])
@@ -450,9 +521,16 @@ impl ParkingSim for NormalParkingSimState {
     ) -> Vec<(ParkingSpot, Position)> {
         let mut candidates = Vec::new();
 
+        // Long vehicles like trucks don't fit everywhere
+        let fits_onstreet = vehicle.length <= map.get_config().street_parking_spot_length;
+        // Lots are laid out for cars
+        let fits_lot = vehicle.length <= VehicleClass::Car.length_range().1;
+
         for l in self.driving_to_parking_lanes.get(driving_pos.lane()) {
             for spot in self.onstreet_lanes[l].spots() {
-                if self.is_free(spot)
+                if fits_onstreet
+                    && self.is_free(spot)
+                    && self.may_park(spot, vehicle)
                     && driving_pos.dist_along()
                         <= self.spot_to_driving_pos(spot, vehicle, map).dist_along()
                 {
@@ -479,7 +557,7 @@ impl ParkingSim for NormalParkingSimState {
 
         for pl in self.driving_to_lots.get(driving_pos.lane()) {
             let lot_dist = map.get_pl(*pl).driving_pos.dist_along();
-            if driving_pos.dist_along() < lot_dist {
+            if fits_lot && driving_pos.dist_along() < lot_dist {
                 for idx in 0..self.num_spots_per_lot[pl] {
                     let spot = ParkingSpot::Lot(*pl, idx);
                     if self.is_free(spot) {
@@ -608,6 +686,11 @@ impl ParkingSim for NormalParkingSimState {
                 }
             }
             for turn in map.get_turns_for(current, PathConstraints::Car) {
+                if let Some(class) = vehicle.class {
+                    if !class.can_use_road(map.get_r(turn.id.dst.road)) {
+                        continue;
+                    }
+                }
                 if let Entry::Vacant(e) = backrefs.entry(turn.id.dst) {
                     let dist_this_step = turn.geom.length() + map.get_l(current).length();
                     // When vehicles search away from the first lane for a spot, don't all go in
@@ -628,6 +711,60 @@ impl ParkingSim for NormalParkingSimState {
         None
     }
 
+    fn update_parking_restrictions(&mut self, now: Time, map: &Map) -> usize {
+        let spot_length = map.get_config().street_parking_spot_length;
+        let mut curb_regulations = BTreeMap::new();
+        for (l, lane) in &self.onstreet_lanes {
+            let parking_lane = map.get_l(*l);
+            for restriction in map
+                .get_parent(*l)
+                .active_parking_restrictions(parking_lane.dir, now)
+            {
+                for (idx, front) in lane.spot_dist_along.iter().enumerate() {
+                    if !restriction.covers(*front - spot_length, *front) {
+                        continue;
+                    }
+                    let spot = ParkingSpot::Onstreet(*l, idx);
+                    let kind = match curb_regulations.get(&spot) {
+                        Some(existing) => restriction.kind.combine(*existing),
+                        None => restriction.kind,
+                    };
+                    curb_regulations.insert(spot, kind);
+                }
+            }
+        }
+        self.curb_regulations = curb_regulations;
+
+        // Cars in the middle of parking in a restricted spot already reserved it; let them finish.
+        let mut num_towed = 0;
+        for spot in self.curb_regulations.keys().cloned().collect::<Vec<_>>() {
+            let car = if let Some(car) = self.occupants.get(&spot) {
+                *car
+            } else {
+                continue;
+            };
+            if self.may_park(spot, &self.parked_cars[&car].vehicle) {
+                continue;
+            }
+            let l = match spot {
+                ParkingSpot::Onstreet(l, _) => l,
+                _ => unreachable!(),
+            };
+            let driving_lane = self.onstreet_lanes[&l].driving_lane;
+            if let Some(new_spot) = self.find_tow_destination(driving_lane, map) {
+                let mut p = self.parked_cars[&car].clone();
+                self.remove_parked_car(p.clone());
+                self.reserve_spot(new_spot, car);
+                p.spot = new_spot;
+                self.add_parked_car(p);
+                num_towed += 1;
+            } else {
+                warn!("Nowhere to tow {} from restricted parking on {}", car, l);
+            }
+        }
+        num_towed
+    }
+
     fn collect_events(&mut self) -> Vec<Event> {
         std::mem::take(&mut self.events)
     }
@@ -924,6 +1061,10 @@ impl ParkingSim for InfiniteParkingSimState {
         !self.occupants.contains_key(&spot) && !self.reserved_spots.contains_key(&spot)
     }
 
+    fn may_park(&self, _: ParkingSpot, _: &Vehicle) -> bool {
+        true
+    }
+
     fn get_car_at_spot(&self, spot: ParkingSpot) -> Option<&ParkedCar> {
         let car = self.occupants.get(&spot)?;
         Some(&self.parked_cars[car])
@@ -1029,6 +1170,11 @@ impl ParkingSim for InfiniteParkingSimState {
                 }
             }
             for turn in map.get_turns_for(current, PathConstraints::Car) {
+                if let Some(class) = vehicle.class {
+                    if !class.can_use_road(map.get_r(turn.id.dst.road)) {
+                        continue;
+                    }
+                }
                 if let Entry::Vacant(e) = backrefs.entry(turn.id.dst) {
                     let dist_this_step = turn.geom.length() + map.get_l(current).length();
                     e.insert(turn.id);
@@ -1041,6 +1187,11 @@ impl ParkingSim for InfiniteParkingSimState {
         None
     }
 
+    fn update_parking_restrictions(&mut self, _: Time, _: &Map) -> usize {
+        // On-street parking is ignored entirely
+        0
+    }
+
     fn collect_events(&mut self) -> Vec<Event> {
         std::mem::take(&mut self.events)
     }
@@ -1070,3 +1221,101 @@ impl ParkingSim for InfiniteParkingSimState {
         cars
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VehicleType;
+
+    fn car(id: usize, disabled_permit: bool) -> Vehicle {
+        Vehicle {
+            id: CarID {
+                id,
+                vehicle_type: VehicleType::Car,
+            },
+            owner: None,
+            vehicle_type: VehicleType::Car,
+            length: Distance::meters(4.5),
+            max_speed: None,
+            class: Some(VehicleClass::Car),
+            disabled_permit,
+        }
+    }
+
+    /// The blank map has no parking lanes, so attach a made-up one with three spots to a real
+    /// driving lane. Only the driving lane is ever looked up in the map.
+    fn setup() -> (Map, LaneID, NormalParkingSimState) {
+        let map = Map::almost_blank();
+        let mut state = NormalParkingSimState::new(&map, &mut Timer::throwaway());
+        let driving_lane = map
+            .all_lanes()
+            .find(|l| l.lane_type == LaneType::Driving)
+            .unwrap()
+            .id;
+        let road = driving_lane.road;
+        let parking_lane = LaneID { road, offset: 100 };
+        state.onstreet_lanes.insert(
+            parking_lane,
+            ParkingLane {
+                parking_lane,
+                driving_lane,
+                sidewalk: LaneID { road, offset: 101 },
+                spot_dist_along: vec![
+                    Distance::meters(16.0),
+                    Distance::meters(24.0),
+                    Distance::meters(32.0),
+                ],
+            },
+        );
+        state
+            .driving_to_parking_lanes
+            .insert(driving_lane, parking_lane);
+        (map, parking_lane, state)
+    }
+
+    #[test]
+    fn test_disabled_spot_needs_permit() {
+        let (_, l, mut state) = setup();
+        let spot = ParkingSpot::Onstreet(l, 0);
+        state.curb_regulations.insert(spot, CurbUse::Disabled);
+
+        assert!(state.is_free(spot));
+        assert!(!state.may_park(spot, &car(0, false)));
+        assert!(state.may_park(spot, &car(1, true)));
+        // Unregulated spots are open to everybody
+        assert!(state.may_park(ParkingSpot::Onstreet(l, 1), &car(0, false)));
+    }
+
+    #[test]
+    fn test_no_stopping_spot_is_never_free() {
+        let (_, l, mut state) = setup();
+        let spot = ParkingSpot::Onstreet(l, 0);
+        state.curb_regulations.insert(spot, CurbUse::NoStopping);
+
+        assert!(!state.is_free(spot));
+        assert!(!state.may_park(spot, &car(0, true)));
+    }
+
+    #[test]
+    fn test_tow_destination_skips_regulated_spots() {
+        let (map, l, mut state) = setup();
+        let driving_lane = state.onstreet_lanes[&l].driving_lane;
+        state
+            .curb_regulations
+            .insert(ParkingSpot::Onstreet(l, 0), CurbUse::Disabled);
+        state
+            .curb_regulations
+            .insert(ParkingSpot::Onstreet(l, 1), CurbUse::Loading);
+
+        assert_eq!(
+            state.find_tow_destination(driving_lane, &map),
+            Some(ParkingSpot::Onstreet(l, 2))
+        );
+
+        // With every spot regulated, there's nowhere nearby to tow to
+        state
+            .curb_regulations
+            .insert(ParkingSpot::Onstreet(l, 2), CurbUse::NoParking);
+        assert_eq!(state.find_tow_destination(driving_lane, &map), None);
+    }
+}
@@ -36,6 +36,9 @@ pub(crate) struct Queue {
 
     /// How long the lane or turn physically is.
     pub geom_len: Distance,
+    /// How much of the length vehicles can fill before no more may enter. This is less than
+    /// geom_len in bad weather, when drivers leave bigger gaps.
+    capacity_len: Distance,
     /// When a car's turn is accepted, reserve the vehicle length + FOLLOWING_DISTANCE for the
     /// target lane. When the car completely leaves (stops being the laggy_head), free up that
     /// space. To prevent blocking the box for possibly scary amounts of time, allocate some of
@@ -74,12 +77,15 @@ pub struct QueueEntry {
 }
 
 impl Queue {
-    pub fn new(id: Traversable, map: &Map) -> Queue {
+    /// `capacity_factor` scales how much of the length is usable; see `capacity_len`.
+    pub fn new(id: Traversable, map: &Map, capacity_factor: f64) -> Queue {
+        let geom_len = id.get_polyline(map).length();
         Queue {
             id,
             members: VecDeque::new(),
             laggy_head: None,
-            geom_len: id.get_polyline(map).length(),
+            geom_len,
+            capacity_len: capacity_factor * geom_len,
             reserved_length: Distance::ZERO,
         }
     }
@@ -400,7 +406,7 @@ impl Queue {
     /// Can a car start a turn for this queue?
     pub fn room_for_car(&self, car: &Car) -> bool {
         self.reserved_length == Distance::ZERO
-            || self.reserved_length + car.vehicle.length + FOLLOWING_DISTANCE < self.geom_len
+            || self.reserved_length + car.vehicle.length + FOLLOWING_DISTANCE < self.capacity_len
     }
 
     /// Once a car has fully exited a queue, free up the space it was reserving.
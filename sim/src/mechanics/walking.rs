@@ -1,25 +1,30 @@
 use std::collections::{BTreeMap, BTreeSet};
 
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_multimap, serialize_multimap, FixedMap, IndexableKey, MultiMap};
 use geom::{Distance, Duration, Line, PolyLine, Speed, Time};
 use map_model::{
-    BuildingID, DrivingSide, IntersectionID, Map, ParkingLotID, Path, PathConstraints, PathStep,
-    RoadID, TransitRouteID, Traversable,
+    BuildingID, CrossingType, DrivingSide, IntersectionID, Map, ParkingLotID, Path,
+    PathConstraints, PathStep, RoadID, TransitRouteID, Traversable,
 };
 
-use crate::sim::Ctx;
+use crate::sim::{CheckpointEntries, Ctx};
 use crate::{
-    pedestrian_body_radius, AgentID, AgentProperties, Command, CommutersVehiclesCounts,
-    CreatePedestrian, DistanceInterval, DrawPedCrowdInput, DrawPedestrianInput, Event, Intent,
-    IntersectionSimState, ParkedCar, ParkingSpot, PedCrowdLocation, PedestrianID, PersonID,
-    Problem, Scheduler, SidewalkPOI, SidewalkSpot, TimeInterval, TransitSimState, TripID,
-    TripManager, UnzoomedAgent,
+    pedestrian_body_radius, AgentDiff, AgentID, AgentProperties, Command, CommutersVehiclesCounts,
+    CreatePedestrian, DistanceInterval, DrawPedCrowdInput, DrawPedestrianInput, DrivingSimState,
+    Event, Intent, IntersectionSimState, ParkedCar, ParkingSpot, PedCrowdLocation, PedestrianID,
+    PersonID, ProbeState, Problem, Scheduler, SidewalkPOI, SidewalkSpot, TimeInterval,
+    TransitSimState, TripID, TripManager, UnzoomedAgent,
 };
 
 const TIME_TO_START_BIKING: Duration = Duration::const_seconds(30.0);
 const TIME_TO_FINISH_BIKING: Duration = Duration::const_seconds(45.0);
+/// At an unsignalized mid-block crossing, pedestrians wait until no vehicle is this close.
+const CROSSING_GAP: Distance = Distance::const_meters(30.0);
+/// How often somebody waiting for a gap in traffic looks again.
+const CROSSING_GAP_RETRY: Duration = Duration::const_seconds(2.0);
 
 /// Simulates pedestrians. Unlike vehicles, pedestrians can move bidirectionally on sidewalks and
 /// just "ghost" through each other. There's no queueing or slowdown when many people are
@@ -134,6 +139,7 @@ impl WalkingSimState {
         ctx: &mut Ctx,
         trips: &mut TripManager,
         transit: &mut TransitSimState,
+        driving: &DrivingSimState,
     ) {
         let ped = self.peds.get_mut(&id).unwrap();
         match ped.state {
@@ -220,6 +226,14 @@ impl WalkingSimState {
                         SidewalkPOI::SuddenlyAppear => unreachable!(),
                         SidewalkPOI::DeferredParkingSpot => unreachable!(),
                     }
+                } else if ped
+                    .path
+                    .current_step()
+                    .mid_block_crossing_to(ped.path.next_step(), ctx.map)
+                    .is_some()
+                {
+                    ped.state = PedState::WaitingToCrossRoad(dist_int.end, now);
+                    ped.maybe_cross_road(now, ctx.map, ctx.scheduler, driving);
                 } else {
                     if let PathStep::Turn(t) | PathStep::ContraflowTurn(t) = ped.path.current_step()
                     {
@@ -270,6 +284,36 @@ impl WalkingSimState {
                     ));
                 }
             }
+            PedState::WaitingToCrossRoad(_, _) => {
+                ped.maybe_cross_road(now, ctx.map, ctx.scheduler, driving);
+            }
+            PedState::CrossingRoad(_, _, _) => {
+                self.peds_per_traversable
+                    .remove(ped.path.current_step().as_traversable(), ped.id);
+                let (_, _, start_dist) = ped
+                    .path
+                    .current_step()
+                    .mid_block_crossing_to(ped.path.next_step(), ctx.map)
+                    .unwrap();
+                ped.path.shift(ctx.map);
+                ped.state = ped.crossing_state(
+                    &self.peds_per_traversable,
+                    start_dist,
+                    now,
+                    ctx.map,
+                    &mut self.events,
+                );
+                self.peds_per_traversable
+                    .insert(ped.path.current_step().as_traversable(), ped.id);
+                self.events.push(Event::AgentEntersTraversable(
+                    AgentID::Pedestrian(ped.id),
+                    Some(ped.trip),
+                    ped.path.current_step().as_traversable(),
+                    None,
+                ));
+                ctx.scheduler
+                    .push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
+            }
             PedState::LeavingBuilding(b, _) => {
                 ped.state = ped.crossing_state(
                     &self.peds_per_traversable,
@@ -410,6 +454,8 @@ impl WalkingSimState {
             | PedState::FinishingBiking(_, _, _) => Distance::ZERO,
             // In all of these cases, we haven't shifted the PathStep that led us to this state yet
             PedState::WaitingToTurn(_, _)
+            | PedState::WaitingToCrossRoad(_, _)
+            | PedState::CrossingRoad(_, _, _)
             | PedState::EnteringBuilding(_, _)
             | PedState::EnteringParkingLot(_, _)
             | PedState::StartingToBike(_, _, _)
@@ -439,11 +485,105 @@ impl WalkingSimState {
         p.path.trace_from_start(map, dist)
     }
 
+    /// What a traced pedestrian is doing right now.
+    pub fn probe_ped(&self, id: PedestrianID) -> Option<ProbeState> {
+        let p = self.peds.get(&id)?;
+        let (activity, speed) = match p.state {
+            PedState::Crossing {
+                ref dist_int,
+                ref time_int,
+                ..
+            } => ("walking".to_string(), ProbeState::speed(time_int, dist_int)),
+            PedState::WaitingToTurn(_, _) => (
+                match p.path.maybe_next_step() {
+                    Some(PathStep::Turn(t)) | Some(PathStep::ContraflowTurn(t)) => {
+                        format!("waiting to start {}", t)
+                    }
+                    _ => "waiting to advance".to_string(),
+                },
+                None,
+            ),
+            PedState::WaitingToCrossRoad(_, _) => (
+                format!("waiting to cross {}", p.path.current_step().as_lane().road),
+                None,
+            ),
+            PedState::CrossingRoad(_, _, _) => (
+                format!("crossing {}", p.path.current_step().as_lane().road),
+                None,
+            ),
+            PedState::LeavingBuilding(b, _) => (format!("leaving {}", b), None),
+            PedState::EnteringBuilding(b, _) => (format!("entering {}", b), None),
+            PedState::LeavingParkingLot(pl, _) => (format!("leaving {}", pl), None),
+            PedState::EnteringParkingLot(pl, _) => (format!("entering {}", pl), None),
+            PedState::StartingToBike(_, _, _) => ("getting on a bike".to_string(), None),
+            PedState::FinishingBiking(_, _, _) => ("getting off a bike".to_string(), None),
+            PedState::WaitingForBus(r, _) => (format!("waiting for {}", r), None),
+        };
+        Some(ProbeState::new(
+            p.path.current_step().as_traversable(),
+            activity,
+            speed,
+            p.path.get_steps().iter().cloned(),
+        ))
+    }
+
     pub fn get_path(&self, id: PedestrianID) -> Option<&Path> {
         let p = self.peds.get(&id)?;
         Some(&p.path)
     }
 
+    /// Find the pedestrians that differ from another simulation.
+    /// For incremental savestates, serialize each pedestrian separately from everything else.
+    pub(crate) fn split_for_checkpoint(&mut self) -> (Vec<u8>, CheckpointEntries) {
+        let peds = std::mem::replace(
+            &mut self.peds,
+            FixedMap::with_capacity(self.peds.capacity()),
+        );
+        let rest = abstutil::to_binary(self);
+        let entries = peds
+            .values()
+            .map(|ped| (abstutil::to_binary(&ped.id), abstutil::to_binary(ped)))
+            .collect();
+        self.peds = peds;
+        (rest, entries)
+    }
+
+    /// The inverse of `split_for_checkpoint`.
+    pub(crate) fn join_from_checkpoint(
+        rest: &[u8],
+        peds: CheckpointEntries,
+    ) -> Result<WalkingSimState> {
+        let mut state: WalkingSimState = abstutil::from_binary(rest)?;
+        for (id, ped) in peds {
+            state
+                .peds
+                .insert(abstutil::from_binary(&id)?, abstutil::from_binary(&ped)?);
+        }
+        Ok(state)
+    }
+
+    pub fn diff(&self, other: &WalkingSimState) -> BTreeMap<PedestrianID, AgentDiff> {
+        let mut peds = BTreeMap::new();
+        for ped in self.peds.values() {
+            match other.peds.get(&ped.id) {
+                Some(other_ped) => {
+                    if abstutil::to_binary(ped) != abstutil::to_binary(other_ped) {
+                        peds.insert(ped.id, AgentDiff::StateDiffers);
+                    }
+                }
+                None => {
+                    peds.insert(ped.id, AgentDiff::OnlyInFirst);
+                }
+            }
+        }
+        for ped in other.peds.values() {
+            if !self.peds.contains_key(&ped.id) {
+                peds.insert(ped.id, AgentDiff::OnlyInSecond);
+            }
+        }
+        peds
+    }
+
     pub fn get_unzoomed_agents(&self, now: Time, map: &Map) -> Vec<UnzoomedAgent> {
         let mut peds = Vec::new();
 
@@ -508,7 +648,9 @@ impl WalkingSimState {
                 }
                 PedState::StartingToBike(_, _, _)
                 | PedState::FinishingBiking(_, _, _)
-                | PedState::WaitingForBus(_, _) => {
+                | PedState::WaitingForBus(_, _)
+                | PedState::WaitingToCrossRoad(_, _)
+                | PedState::CrossingRoad(_, _, _) => {
                     // The backwards half of the sidewalk is closer to the road.
                     backwards.push((*id, dist));
                 }
@@ -683,6 +825,12 @@ impl Pedestrian {
     ) -> PedState {
         let end_dist = if self.path.is_last_step() {
             self.goal.sidewalk_pos.dist_along()
+        } else if let Some((_, dist, _)) = self
+            .path
+            .current_step()
+            .mid_block_crossing_to(self.path.next_step(), map)
+        {
+            dist
         } else {
             // TODO PathStep should have a end_dist... or end_pos
             match self.path.current_step() {
@@ -729,7 +877,9 @@ impl Pedestrian {
                 ref time_int,
                 ..
             } => dist_int.lerp(time_int.percent(now)),
-            PedState::WaitingToTurn(dist, _) => dist,
+            PedState::WaitingToTurn(dist, _)
+            | PedState::WaitingToCrossRoad(dist, _)
+            | PedState::CrossingRoad(dist, _, _) => dist,
             PedState::LeavingBuilding(b, _) | PedState::EnteringBuilding(b, _) => {
                 map.get_b(b).sidewalk_pos.dist_along()
             }
@@ -797,6 +947,18 @@ impl Pedestrian {
                     facing,
                 )
             }
+            PedState::WaitingToCrossRoad(dist, _) => {
+                let (pos, facing) = on.get_polyline(map).dist_along(dist).expect(&err);
+                (
+                    pos.project_away(project_away, facing.rotate_degs(angle_offset)),
+                    facing,
+                )
+            }
+            PedState::CrossingRoad(_, ref line, ref time_int) => (
+                line.percent_along(time_int.percent(now))
+                    .unwrap_or_else(|_| line.pt1()),
+                line.angle(),
+            ),
             PedState::LeavingBuilding(b, ref time_int) => {
                 let pl = &map.get_b(b).driveway_geom;
                 // If we're on some tiny line and percent_along fails, just fall back to to some
@@ -872,6 +1034,65 @@ impl Pedestrian {
         }
     }
 
+    /// Waiting at a mid-block crossing. At a signalized crossing, wait for the walk phase. At an
+    /// unsignalized one, wait for a gap in traffic; vehicles don't yield.
+    fn maybe_cross_road(
+        &mut self,
+        now: Time,
+        map: &Map,
+        scheduler: &mut Scheduler,
+        driving: &DrivingSimState,
+    ) {
+        let blocked_since = match self.state {
+            PedState::WaitingToCrossRoad(_, blocked_since) => blocked_since,
+            _ => unreachable!(),
+        };
+        let (crossing, dist1, dist2) = self
+            .path
+            .current_step()
+            .mid_block_crossing_to(self.path.next_step(), map)
+            .unwrap();
+
+        let wait = crossing.wait_for_signal(now);
+        if wait > Duration::ZERO {
+            scheduler.push(now + wait, Command::UpdatePed(self.id));
+            return;
+        }
+        let road = map.get_r(self.path.current_step().as_lane().road);
+        if crossing.kind == CrossingType::Unsignalized
+            && road.lanes.iter().any(|l| {
+                l.lane_type.is_for_moving_vehicles()
+                    && driving.vehicle_approaching(
+                        now,
+                        l.id,
+                        road.crossing_dist_along_lane(crossing, l),
+                        CROSSING_GAP,
+                    )
+            })
+        {
+            scheduler.push(now + CROSSING_GAP_RETRY, Command::UpdatePed(self.id));
+            return;
+        }
+
+        self.total_blocked_time += now - blocked_since;
+        let line = Line::must_new(
+            map.get_l(self.path.current_step().as_lane())
+                .lane_center_pts
+                .must_dist_along(dist1)
+                .0,
+            map.get_l(self.path.next_step().as_lane())
+                .lane_center_pts
+                .must_dist_along(dist2)
+                .0,
+        );
+        self.state = PedState::CrossingRoad(
+            dist1,
+            line.clone(),
+            TimeInterval::new(now, now + line.length() / self.speed),
+        );
+        scheduler.push(self.state.get_end_time(), Command::UpdatePed(self.id));
+    }
+
     // True if we successfully continued to the next step of our path
     fn maybe_transition(
         &mut self,
@@ -929,6 +1150,11 @@ enum PedState {
     },
     /// The Distance is either 0 or the current traversable's length. The Time is blocked_since.
     WaitingToTurn(Distance, Time),
+    /// Waiting at a mid-block crossing, at some distance along the current sidewalk. The Time is
+    /// blocked_since.
+    WaitingToCrossRoad(Distance, Time),
+    /// Crossing the road mid-block, starting from some distance along the current sidewalk
+    CrossingRoad(Distance, Line, TimeInterval),
     LeavingBuilding(BuildingID, TimeInterval),
     EnteringBuilding(BuildingID, TimeInterval),
     LeavingParkingLot(ParkingLotID, TimeInterval),
@@ -943,6 +1169,8 @@ impl PedState {
         match self {
             PedState::Crossing { ref time_int, .. } => time_int.end,
             PedState::WaitingToTurn(_, _) => unreachable!(),
+            PedState::WaitingToCrossRoad(_, _) => unreachable!(),
+            PedState::CrossingRoad(_, _, ref time_int) => time_int.end,
             PedState::LeavingBuilding(_, ref time_int) => time_int.end,
             PedState::EnteringBuilding(_, ref time_int) => time_int.end,
             PedState::LeavingParkingLot(_, ref time_int) => time_int.end,
@@ -956,6 +1184,7 @@ impl PedState {
     fn time_spent_waiting(&self, now: Time) -> Duration {
         match self {
             PedState::WaitingToTurn(_, blocked_since)
+            | PedState::WaitingToCrossRoad(_, blocked_since)
             | PedState::WaitingForBus(_, blocked_since) => now - *blocked_since,
             _ => Duration::ZERO,
         }
@@ -0,0 +1,670 @@
+//! Demand-responsive transit, or microtransit: a fleet of minibuses without fixed routes. People
+//! book a ride between two buildings, requests made around the same time are assigned to vehicles
+//! together, and each vehicle visits its pickups and dropoffs in whatever order adds the least
+//! distance.
+//!
+//! Vehicles wait at a depot until they're needed, then drive from there, and return once they have
+//! nobody left to serve. While at the depot, they're not simulated at all.
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use geom::{Distance, Duration, Time};
+use map_model::{BuildingID, Map, Path, PathConstraints, PathRequest, Position};
+
+use crate::sim::Ctx;
+use crate::{
+    CarID, Command, CreateCar, Event, PersonID, Router, TripID, TripManager, TripPhaseType,
+    VehicleSpec, VehicleType, MINIBUS_LENGTH,
+};
+
+/// Requests made within this long of each other are assigned to vehicles together, so nearby
+/// riders can share a vehicle.
+const BATCH_WINDOW: Duration = Duration::const_seconds(60.0);
+/// If no vehicle can fit somebody in for this long, give up on their trip.
+const MAX_WAIT: Duration = Duration::const_seconds(3600.0);
+/// Don't make riders travel more than this many times the direct distance...
+const MAX_DETOUR_FACTOR: f64 = 2.0;
+/// ... unless the detour is short anyway.
+const MIN_DETOUR: Distance = Distance::const_meters(1000.0);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct Request {
+    trip: TripID,
+    person: PersonID,
+    pickup: Position,
+    dropoff: Position,
+    requested_at: Time,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+enum StopKind {
+    Pickup,
+    Dropoff,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct Stop {
+    kind: StopKind,
+    request: Request,
+}
+
+impl Stop {
+    fn pos(&self) -> Position {
+        match self.kind {
+            StopKind::Pickup => self.request.pickup,
+            StopKind::Dropoff => self.request.dropoff,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+enum VehicleState {
+    AtDepot,
+    /// Heading to the first of the remaining stops
+    DrivingToStop,
+    AtStop(Position),
+    ReturningToDepot,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct Vehicle {
+    depot: Position,
+    /// Only set while the vehicle is out of the depot
+    car: Option<CarID>,
+    state: VehicleState,
+    /// In the order they'll be visited
+    stops: VecDeque<Stop>,
+    passengers: Vec<Request>,
+}
+
+/// Dispatches a fleet of on-demand minibuses. The vehicles themselves are buses in the driving
+/// simulation, following one path at a time, so TransitSimState hands their callbacks over to
+/// this.
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct MicrotransitSimState {
+    vehicles: Vec<Vehicle>,
+    capacity: usize,
+    /// Requests that haven't been assigned to a vehicle yet
+    pending: Vec<Request>,
+    dispatch_scheduled: bool,
+}
+
+impl MicrotransitSimState {
+    pub fn new(map: &Map, fleet: usize, capacity: usize) -> MicrotransitSimState {
+        // Scatter depots among buildings that vehicles can reach
+        let candidates: Vec<Position> = map
+            .all_buildings()
+            .iter()
+            .filter_map(|b| b.driving_connection(map).map(|(pos, _)| pos))
+            .collect();
+        let mut vehicles = Vec::new();
+        if !candidates.is_empty() {
+            for idx in 0..fleet {
+                vehicles.push(Vehicle {
+                    depot: candidates[idx * candidates.len() / fleet],
+                    car: None,
+                    state: VehicleState::AtDepot,
+                    stops: VecDeque::new(),
+                    passengers: Vec::new(),
+                });
+            }
+        }
+
+        MicrotransitSimState {
+            vehicles,
+            capacity,
+            pending: Vec::new(),
+            dispatch_scheduled: false,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.vehicles.is_empty()
+    }
+
+    pub fn same_fleet(&self, other: &MicrotransitSimState) -> bool {
+        self.vehicles.len() == other.vehicles.len() && self.capacity == other.capacity
+    }
+
+    pub fn owns(&self, car: CarID) -> bool {
+        self.vehicles.iter().any(|v| v.car == Some(car))
+    }
+
+    fn vehicle_idx(&self, car: CarID) -> usize {
+        self.vehicles
+            .iter()
+            .position(|v| v.car == Some(car))
+            .unwrap()
+    }
+
+    /// Somebody is waiting outside of `from` to be picked up. They won't be assigned a vehicle
+    /// until the next dispatch. If vehicles can't reach either building, the trip is cancelled.
+    pub fn request_ride(
+        &mut self,
+        now: Time,
+        trip: TripID,
+        person: PersonID,
+        from: BuildingID,
+        to: BuildingID,
+        trips: &mut TripManager,
+        ctx: &mut Ctx,
+    ) {
+        let (pickup, dropoff) = match (
+            ctx.map.get_b(from).driving_connection(ctx.map),
+            ctx.map.get_b(to).driving_connection(ctx.map),
+        ) {
+            (Some((pickup, _)), Some((dropoff, _))) => (pickup, dropoff),
+            _ => {
+                trips.cancel_microtransit_trip(
+                    now,
+                    trip,
+                    None,
+                    format!(
+                        "microtransit vehicles can't reach {} or {} from the street",
+                        from, to
+                    ),
+                    ctx,
+                );
+                return;
+            }
+        };
+        self.pending.push(Request {
+            trip,
+            person,
+            pickup,
+            dropoff,
+            requested_at: now,
+        });
+        if !self.dispatch_scheduled {
+            self.dispatch_scheduled = true;
+            ctx.scheduler
+                .push(now + BATCH_WINDOW, Command::DispatchMicrotransit);
+        }
+    }
+
+    /// Assign every pending request to the vehicle where it adds the least distance, then send out
+    /// any vehicles from the depot that now have work.
+    pub fn dispatch(&mut self, now: Time, trips: &mut TripManager, ctx: &mut Ctx) {
+        self.dispatch_scheduled = false;
+
+        for req in std::mem::take(&mut self.pending) {
+            if let Some((idx, stops)) = self.best_insertion(&req, ctx.map) {
+                self.vehicles[idx].stops = stops;
+            } else if now - req.requested_at >= MAX_WAIT {
+                trips.cancel_microtransit_trip(
+                    now,
+                    req.trip,
+                    None,
+                    "no microtransit vehicle could pick them up".to_string(),
+                    ctx,
+                );
+            } else {
+                self.pending.push(req);
+            }
+        }
+
+        for idx in 0..self.vehicles.len() {
+            if self.vehicles[idx].state != VehicleState::AtDepot
+                || self.vehicles[idx].stops.is_empty()
+            {
+                continue;
+            }
+            let depot = self.vehicles[idx].depot;
+            if let Some(path) = self.route_to_next_stop(idx, depot, now, trips, ctx) {
+                let vehicle = VehicleSpec {
+                    vehicle_type: VehicleType::Bus,
+                    length: MINIBUS_LENGTH,
+                    max_speed: None,
+                    class: None,
+                    disabled_permit: false,
+                }
+                .make(
+                    CarID {
+                        id: trips.new_car_id(),
+                        vehicle_type: VehicleType::Bus,
+                    },
+                    None,
+                );
+                self.vehicles[idx].car = Some(vehicle.id);
+                self.vehicles[idx].state = VehicleState::DrivingToStop;
+                ctx.scheduler.push(
+                    now,
+                    Command::SpawnCar(
+                        CreateCar {
+                            router: Router::follow_bus_route(vehicle.id, path),
+                            vehicle,
+                            maybe_parked_car: None,
+                            trip_and_person: None,
+                            maybe_route: None,
+                        },
+                        true,
+                    ),
+                );
+            }
+        }
+
+        if !self.pending.is_empty() {
+            self.dispatch_scheduled = true;
+            ctx.scheduler
+                .push(now + BATCH_WINDOW, Command::DispatchMicrotransit);
+        }
+    }
+
+    /// If true, the vehicle is idling at a stop. If false, it's back at the depot and should
+    /// vanish.
+    pub fn vehicle_arrived(
+        &mut self,
+        now: Time,
+        car: CarID,
+        trips: &mut TripManager,
+        ctx: &mut Ctx,
+        events: &mut Vec<Event>,
+    ) -> bool {
+        let idx = self.vehicle_idx(car);
+        match self.vehicles[idx].state {
+            VehicleState::DrivingToStop => {
+                let here = self.vehicles[idx].stops[0].pos();
+                self.vehicles[idx].state = VehicleState::AtStop(here);
+                self.serve_stops(idx, now, here, trips, ctx, events);
+                true
+            }
+            VehicleState::ReturningToDepot => {
+                let vehicle = &mut self.vehicles[idx];
+                if vehicle.stops.is_empty() {
+                    vehicle.car = None;
+                    vehicle.state = VehicleState::AtDepot;
+                    false
+                } else {
+                    // Somebody booked a ride while the vehicle was on its way back
+                    vehicle.state = VehicleState::AtStop(vehicle.depot);
+                    true
+                }
+            }
+            VehicleState::AtDepot | VehicleState::AtStop(_) => unreachable!(),
+        }
+    }
+
+    /// Returns the path to the next stop, or back to the depot. None means the vehicle should
+    /// vanish where it is.
+    pub fn vehicle_departed(
+        &mut self,
+        now: Time,
+        car: CarID,
+        trips: &mut TripManager,
+        ctx: &mut Ctx,
+        events: &mut Vec<Event>,
+    ) -> Option<Router> {
+        let idx = self.vehicle_idx(car);
+        let here = match self.vehicles[idx].state {
+            VehicleState::AtStop(pos) => pos,
+            _ => unreachable!(),
+        };
+        // Requests assigned while the vehicle was idling might start right here
+        self.serve_stops(idx, now, here, trips, ctx, events);
+
+        if let Some(path) = self.route_to_next_stop(idx, here, now, trips, ctx) {
+            self.vehicles[idx].state = VehicleState::DrivingToStop;
+            return Some(Router::follow_bus_route(car, path));
+        }
+
+        let vehicle = &mut self.vehicles[idx];
+        if here != vehicle.depot {
+            if let Ok(path) = ctx.map.pathfind(PathRequest::vehicle(
+                here,
+                vehicle.depot,
+                PathConstraints::Bus,
+            )) {
+                vehicle.state = VehicleState::ReturningToDepot;
+                return Some(Router::follow_bus_route(car, path));
+            }
+        }
+        // Already at the depot, or it's unreachable. Either way, pretend the vehicle is back.
+        vehicle.car = None;
+        vehicle.state = VehicleState::AtDepot;
+        None
+    }
+
+    /// Drop off and pick up everybody at the front of the vehicle's schedule who's at `here`.
+    fn serve_stops(
+        &mut self,
+        idx: usize,
+        now: Time,
+        here: Position,
+        trips: &mut TripManager,
+        ctx: &mut Ctx,
+        events: &mut Vec<Event>,
+    ) {
+        let vehicle = &mut self.vehicles[idx];
+        let car = vehicle.car.unwrap();
+        while vehicle
+            .stops
+            .front()
+            .map(|stop| stop.pos() == here)
+            .unwrap_or(false)
+        {
+            let stop = vehicle.stops.pop_front().unwrap();
+            let req = stop.request;
+            match stop.kind {
+                StopKind::Pickup => {
+                    trips.person_boarded_microtransit(req.trip, car, now - req.requested_at);
+                    events.push(Event::TripPhaseStarting(
+                        req.trip,
+                        req.person,
+                        None,
+                        TripPhaseType::RidingMicrotransit(car),
+                    ));
+                    vehicle.passengers.push(req);
+                }
+                StopKind::Dropoff => {
+                    vehicle.passengers.retain(|r| r.trip != req.trip);
+                    trips.person_left_microtransit(now, req.person, car, ctx);
+                }
+            }
+        }
+    }
+
+    /// Find a path from `from` to the vehicle's next stop. If a stop can't be reached, give up on
+    /// that rider's trip and try the next stop. Returns None once no stops are left.
+    fn route_to_next_stop(
+        &mut self,
+        idx: usize,
+        from: Position,
+        now: Time,
+        trips: &mut TripManager,
+        ctx: &mut Ctx,
+    ) -> Option<Path> {
+        loop {
+            let vehicle = &mut self.vehicles[idx];
+            let stop = vehicle.stops.front()?;
+            let req = PathRequest::vehicle(from, stop.pos(), PathConstraints::Bus);
+            match ctx.map.pathfind(req) {
+                Ok(path) => {
+                    return Some(path);
+                }
+                Err(err) => {
+                    let trip = stop.request.trip;
+                    let on_board = vehicle.passengers.iter().any(|r| r.trip == trip);
+                    vehicle.stops.retain(|s| s.request.trip != trip);
+                    vehicle.passengers.retain(|r| r.trip != trip);
+                    trips.cancel_microtransit_trip(
+                        now,
+                        trip,
+                        if on_board { vehicle.car } else { None },
+                        format!("microtransit vehicle couldn't reach their stop: {}", err),
+                        ctx,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Try inserting the pickup and dropoff at every point in every vehicle's schedule, and pick
+    /// the cheapest feasible option. Distances are estimated as straight lines, since pathfinding
+    /// for every combination would be too slow.
+    fn best_insertion(&self, req: &Request, map: &Map) -> Option<(usize, VecDeque<Stop>)> {
+        let mut best: Option<(f64, usize, VecDeque<Stop>)> = None;
+        for (idx, vehicle) in self.vehicles.iter().enumerate() {
+            // Where does the vehicle's schedule start, and how many stops can't be rearranged?
+            let (start, fixed) = match vehicle.state {
+                VehicleState::AtDepot | VehicleState::ReturningToDepot => (vehicle.depot, 0),
+                VehicleState::AtStop(pos) => (pos, 0),
+                VehicleState::DrivingToStop => (vehicle.stops[0].pos(), 1),
+            };
+            let current = schedule_length(start, &vehicle.stops, map).inner_meters();
+            let num_stops = vehicle.stops.len();
+            for i in fixed..=num_stops {
+                for j in i..=num_stops {
+                    let mut stops = vehicle.stops.clone();
+                    stops.insert(
+                        i,
+                        Stop {
+                            kind: StopKind::Pickup,
+                            request: req.clone(),
+                        },
+                    );
+                    stops.insert(
+                        j + 1,
+                        Stop {
+                            kind: StopKind::Dropoff,
+                            request: req.clone(),
+                        },
+                    );
+                    if !self.is_feasible(vehicle.passengers.len(), start, &stops, map) {
+                        continue;
+                    }
+                    let cost = schedule_length(start, &stops, map).inner_meters() - current;
+                    if best.as_ref().map(|(c, _, _)| cost < *c).unwrap_or(true) {
+                        best = Some((cost, idx, stops));
+                    }
+                }
+            }
+        }
+        best.map(|(_, idx, stops)| (idx, stops))
+    }
+
+    /// The vehicle never carries more than its capacity, and nobody not yet picked up has to
+    /// detour too much.
+    fn is_feasible(
+        &self,
+        on_board: usize,
+        start: Position,
+        stops: &VecDeque<Stop>,
+        map: &Map,
+    ) -> bool {
+        let mut load = on_board;
+        let mut dist = Distance::ZERO;
+        let mut prev = start.pt(map);
+        let mut picked_up_at: Vec<(TripID, Distance)> = Vec::new();
+        for stop in stops {
+            let pt = stop.pos().pt(map);
+            dist += prev.dist_to(pt);
+            prev = pt;
+            match stop.kind {
+                StopKind::Pickup => {
+                    load += 1;
+                    if load > self.capacity {
+                        return false;
+                    }
+                    picked_up_at.push((stop.request.trip, dist));
+                }
+                StopKind::Dropoff => {
+                    load -= 1;
+                    if let Some((_, start_dist)) = picked_up_at
+                        .iter()
+                        .find(|(trip, _)| *trip == stop.request.trip)
+                    {
+                        let direct = stop
+                            .request
+                            .pickup
+                            .pt(map)
+                            .dist_to(stop.request.dropoff.pt(map));
+                        if dist - *start_dist > direct * MAX_DETOUR_FACTOR + MIN_DETOUR {
+                            return false;
+                        }
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    pub fn num_passengers(&self, car: CarID) -> usize {
+        self.vehicles[self.vehicle_idx(car)].passengers.len()
+    }
+
+    pub fn get_passengers(&self, car: CarID) -> Vec<PersonID> {
+        self.vehicles[self.vehicle_idx(car)]
+            .passengers
+            .iter()
+            .map(|r| r.person)
+            .collect()
+    }
+
+    pub fn active_vehicles(&self) -> Vec<CarID> {
+        self.vehicles.iter().filter_map(|v| v.car).collect()
+    }
+
+    /// Everybody waiting to be picked up, whether or not they've been assigned a vehicle yet
+    pub fn num_waiting(&self) -> usize {
+        self.pending.len()
+            + self
+                .vehicles
+                .iter()
+                .flat_map(|v| v.stops.iter())
+                .filter(|s| s.kind == StopKind::Pickup)
+                .count()
+    }
+}
+
+fn schedule_length(start: Position, stops: &VecDeque<Stop>, map: &Map) -> Distance {
+    let mut dist = Distance::ZERO;
+    let mut prev = start.pt(map);
+    for stop in stops {
+        let pt = stop.pos().pt(map);
+        dist += prev.dist_to(pt);
+        prev = pt;
+    }
+    dist
+}
+
+#[cfg(test)]
+mod tests {
+    use map_model::LaneID;
+
+    use super::*;
+
+    /// Positions along one driving lane of a tiny map, `fraction` of the way along it
+    fn pos(map: &Map, lane: LaneID, fraction: f64) -> Position {
+        Position::new(lane, fraction * map.get_l(lane).length())
+    }
+
+    fn request(id: usize, pickup: Position, dropoff: Position) -> Request {
+        Request {
+            trip: TripID(id),
+            person: PersonID(id),
+            pickup,
+            dropoff,
+            requested_at: Time::START_OF_DAY,
+        }
+    }
+
+    fn stop(kind: StopKind, request: &Request) -> Stop {
+        Stop {
+            kind,
+            request: request.clone(),
+        }
+    }
+
+    fn setup(capacity: usize) -> (Map, LaneID, MicrotransitSimState) {
+        let map = Map::almost_blank();
+        let lane = map
+            .all_lanes()
+            .find(|l| l.is_driving())
+            .expect("the map has a driving lane")
+            .id;
+        let state = MicrotransitSimState {
+            vehicles: vec![Vehicle {
+                depot: pos(&map, lane, 0.0),
+                car: None,
+                state: VehicleState::AtDepot,
+                stops: VecDeque::new(),
+                passengers: Vec::new(),
+            }],
+            capacity,
+            pending: Vec::new(),
+            dispatch_scheduled: false,
+        };
+        (map, lane, state)
+    }
+
+    fn kinds(stops: &VecDeque<Stop>) -> Vec<(StopKind, TripID)> {
+        stops.iter().map(|s| (s.kind, s.request.trip)).collect()
+    }
+
+    #[test]
+    fn test_is_feasible_respects_capacity() {
+        let (map, lane, mut state) = setup(1);
+        let start = pos(&map, lane, 0.0);
+        let first = request(0, pos(&map, lane, 0.2), pos(&map, lane, 0.6));
+        let second = request(1, pos(&map, lane, 0.4), pos(&map, lane, 0.8));
+
+        let overlapping: VecDeque<Stop> = vec![
+            stop(StopKind::Pickup, &first),
+            stop(StopKind::Pickup, &second),
+            stop(StopKind::Dropoff, &first),
+            stop(StopKind::Dropoff, &second),
+        ]
+        .into();
+        let one_at_a_time: VecDeque<Stop> = vec![
+            stop(StopKind::Pickup, &first),
+            stop(StopKind::Dropoff, &first),
+            stop(StopKind::Pickup, &second),
+            stop(StopKind::Dropoff, &second),
+        ]
+        .into();
+
+        assert!(!state.is_feasible(0, start, &overlapping, &map));
+        assert!(state.is_feasible(0, start, &one_at_a_time, &map));
+        // Somebody already on board counts against the capacity
+        assert!(!state.is_feasible(1, start, &one_at_a_time, &map));
+
+        state.capacity = 2;
+        assert!(state.is_feasible(0, start, &overlapping, &map));
+    }
+
+    #[test]
+    fn test_best_insertion_into_empty_vehicle() {
+        let (map, lane, state) = setup(1);
+        let req = request(0, pos(&map, lane, 0.3), pos(&map, lane, 0.7));
+        let (idx, stops) = state.best_insertion(&req, &map).unwrap();
+        assert_eq!(idx, 0);
+        assert_eq!(
+            kinds(&stops),
+            vec![
+                (StopKind::Pickup, TripID(0)),
+                (StopKind::Dropoff, TripID(0))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_best_insertion_shares_and_keeps_the_current_stop() {
+        let (map, lane, mut state) = setup(2);
+        let first = request(0, pos(&map, lane, 0.5), pos(&map, lane, 0.9));
+        state.vehicles[0].state = VehicleState::DrivingToStop;
+        state.vehicles[0].stops = vec![
+            stop(StopKind::Pickup, &first),
+            stop(StopKind::Dropoff, &first),
+        ]
+        .into();
+
+        // Picking up the second rider first would be shorter, but the vehicle is already heading to
+        // the first pickup, so the second rider shares the rest of the trip.
+        let second = request(1, pos(&map, lane, 0.1), pos(&map, lane, 0.8));
+        let (_, stops) = state.best_insertion(&second, &map).unwrap();
+        assert_eq!(
+            kinds(&stops),
+            vec![
+                (StopKind::Pickup, TripID(0)),
+                (StopKind::Pickup, TripID(1)),
+                (StopKind::Dropoff, TripID(1)),
+                (StopKind::Dropoff, TripID(0)),
+            ]
+        );
+
+        // With room for only one rider, the second has to wait for the first to be dropped off
+        state.capacity = 1;
+        let (_, stops) = state.best_insertion(&second, &map).unwrap();
+        assert_eq!(
+            kinds(&stops),
+            vec![
+                (StopKind::Pickup, TripID(0)),
+                (StopKind::Dropoff, TripID(0)),
+                (StopKind::Pickup, TripID(1)),
+                (StopKind::Dropoff, TripID(1)),
+            ]
+        );
+    }
+}
@@ -6,7 +6,7 @@ use std::ops;
 
 use anyhow::Result;
 
-pub use model::{Cmd, PandemicModel};
+pub use model::{Cmd, PandemicModel, MODEL_NAME};
 use rand::Rng;
 use rand_distr::{Distribution, Exp, Normal};
 use rand_xorshift::XorShiftRng;
@@ -8,7 +8,9 @@ use geom::{Duration, Time};
 use map_model::{BuildingID, TransitStopID};
 
 use crate::pandemic::{AnyTime, State};
-use crate::{CarID, Event, Person, PersonID, Scheduler, TripPhaseType};
+use crate::{
+    CarID, Event, ModelCommand, ModelScheduler, Person, PersonID, SecondaryModel, TripPhaseType,
+};
 
 // TODO This does not model transmission by surfaces; only person-to-person.
 // TODO If two people are in the same shared space indefinitely and neither leaves, we don't model
@@ -27,7 +29,11 @@ pub struct PandemicModel {
     initialized: bool,
 }
 
-// You can schedule callbacks in the future by doing scheduler.push(future time, one of these)
+/// Identifies this model's commands in the scheduler
+pub const MODEL_NAME: &str = "pandemic";
+
+// You can schedule callbacks in the future by doing scheduler.push(future time,
+// ModelCommand::new(MODEL_NAME, key, &one of these))
 #[derive(Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Debug)]
 pub enum Cmd {
     BecomeHospitalized(PersonID),
@@ -57,39 +63,6 @@ impl PandemicModel {
         }
     }
 
-    // Sorry, initialization order of simulations is still a bit messy. This'll be called at
-    // Time::START_OF_DAY after all of the people have been created from a Scenario.
-    pub(crate) fn initialize(&mut self, population: &[Person], _scheduler: &mut Scheduler) {
-        assert!(!self.initialized);
-        self.initialized = true;
-
-        // Seed initially infected people.
-        // TODO the intial time is not well set. it should start "before"
-        // the beginning of the day. Also
-        for p in population {
-            let state = State::new(0.5, 0.5);
-            let state = if self.rng.gen_bool(State::ini_exposed_ratio()) {
-                let next_state = state
-                    .start(
-                        AnyTime::from(Time::START_OF_DAY),
-                        Duration::seconds(std::f64::MAX),
-                        &mut self.rng,
-                    )
-                    .unwrap();
-                if self.rng.gen_bool(State::ini_infectious_ratio()) {
-                    next_state
-                        .next_default(AnyTime::from(Time::START_OF_DAY), &mut self.rng)
-                        .unwrap()
-                } else {
-                    next_state
-                }
-            } else {
-                state
-            };
-            self.pop.insert(p.id, state);
-        }
-    }
-
     pub fn count_sane(&self) -> usize {
         self.pop
             .iter()
@@ -138,70 +111,6 @@ impl PandemicModel {
             + self.count_dead()
     }
 
-    pub(crate) fn handle_event(&mut self, now: Time, ev: &Event, scheduler: &mut Scheduler) {
-        assert!(self.initialized);
-
-        match ev {
-            Event::PersonEntersBuilding(person, bldg) => {
-                self.bldgs.person_enters_space(now, *person, *bldg);
-            }
-            Event::PersonLeavesBuilding(person, bldg) => {
-                if let Some(others) = self.bldgs.person_leaves_space(now, *person, *bldg) {
-                    self.transmission(now, *person, others, scheduler);
-                } else {
-                    panic!("{} left {}, but they weren't inside", person, bldg);
-                }
-            }
-            Event::TripPhaseStarting(_, p, _, tpt) => {
-                let person = *p;
-                match tpt {
-                    TripPhaseType::WaitingForBus(_, stop) => {
-                        self.bus_stops.person_enters_space(now, person, *stop);
-                    }
-                    TripPhaseType::RidingBus(_, stop, bus) => {
-                        let others = self
-                            .bus_stops
-                            .person_leaves_space(now, person, *stop)
-                            .unwrap();
-                        self.transmission(now, person, others, scheduler);
-
-                        self.buses.person_enters_space(now, person, *bus);
-                        self.person_to_bus.insert(person, *bus);
-                    }
-                    TripPhaseType::Walking => {
-                        // A person can start walking for many reasons, but the only possible state
-                        // transition after riding a bus is walking, so use this to detect the end
-                        // of a bus ride.
-                        if let Some(car) = self.person_to_bus.remove(&person) {
-                            let others = self.buses.person_leaves_space(now, person, car).unwrap();
-                            self.transmission(now, person, others, scheduler);
-                        }
-                    }
-                    _ => {
-                        self.transition(now, person, scheduler);
-                    }
-                }
-            }
-            _ => {}
-        }
-    }
-
-    pub(crate) fn handle_cmd(&mut self, _now: Time, cmd: Cmd, _scheduler: &mut Scheduler) {
-        assert!(self.initialized);
-
-        // TODO Here we might enforce policies. Like severe -> become hospitalized
-        // Symptomatic -> stay quaratined, and/or track contacts to quarantine them too (or test
-        // them)
-        match cmd {
-            Cmd::BecomeHospitalized(_person) => {
-                // self.hospitalized.insert(person);
-            }
-            Cmd::BecomeQuarantined(_person) => {
-                // self.quarantined.insert(person);
-            }
-        }
-    }
-
     pub fn get_time(&self, person: PersonID) -> Option<Time> {
         match self.pop.get(&person) {
             Some(state) => state.get_time(),
@@ -258,7 +167,7 @@ impl PandemicModel {
         now: Time,
         person: PersonID,
         other_occupants: Vec<(PersonID, Duration)>,
-        scheduler: &mut Scheduler,
+        scheduler: &mut ModelScheduler,
     ) {
         // person has spent some duration in the same space as other people. Does transmission
         // occur?
@@ -270,7 +179,7 @@ impl PandemicModel {
     }
 
     // transition from a state to another without interaction with others
-    fn transition(&mut self, now: Time, person: PersonID, _scheduler: &mut Scheduler) {
+    fn transition(&mut self, now: Time, person: PersonID, _scheduler: &mut ModelScheduler) {
         let state = self.pop.remove(&person).unwrap();
         let state = state.next(AnyTime::from(now), &mut self.rng).unwrap();
         self.pop.insert(person, state);
@@ -278,7 +187,11 @@ impl PandemicModel {
         // if self.rng.gen_bool(0.1) {
         //     scheduler.push(
         //         now + self.rand_duration(Duration::hours(1), Duration::hours(3)),
-        //         Command::Pandemic(Cmd::BecomeHospitalized(person)),
+        //         ModelCommand::new(
+        //             MODEL_NAME,
+        //             person.to_string(),
+        //             &Cmd::BecomeHospitalized(person),
+        //         ),
         //     );
         // }
     }
@@ -288,7 +201,7 @@ impl PandemicModel {
         now: Time,
         overlap: Duration,
         person: PersonID,
-        _scheduler: &mut Scheduler,
+        _scheduler: &mut ModelScheduler,
     ) {
         #![allow(clippy::float_cmp)] // false positive
                                      // When people become exposed
@@ -305,12 +218,130 @@ impl PandemicModel {
         // if self.rng.gen_bool(0.1) {
         //     scheduler.push(
         //         now + self.rand_duration(Duration::hours(1), Duration::hours(3)),
-        //         Command::Pandemic(Cmd::BecomeHospitalized(person)),
+        //         ModelCommand::new(
+        //             MODEL_NAME,
+        //             person.to_string(),
+        //             &Cmd::BecomeHospitalized(person),
+        //         ),
         //     );
         // }
     }
 }
 
+impl SecondaryModel for PandemicModel {
+    fn name(&self) -> &str {
+        MODEL_NAME
+    }
+
+    // Sorry, initialization order of simulations is still a bit messy. This'll be called at
+    // Time::START_OF_DAY after all of the people have been created from a Scenario.
+    fn initialize(&mut self, population: &[Person], _scheduler: &mut ModelScheduler) {
+        assert!(!self.initialized);
+        self.initialized = true;
+
+        // Seed initially infected people.
+        // TODO the intial time is not well set. it should start "before"
+        // the beginning of the day. Also
+        for p in population {
+            let state = State::new(0.5, 0.5);
+            let state = if self.rng.gen_bool(State::ini_exposed_ratio()) {
+                let next_state = state
+                    .start(
+                        AnyTime::from(Time::START_OF_DAY),
+                        Duration::seconds(std::f64::MAX),
+                        &mut self.rng,
+                    )
+                    .unwrap();
+                if self.rng.gen_bool(State::ini_infectious_ratio()) {
+                    next_state
+                        .next_default(AnyTime::from(Time::START_OF_DAY), &mut self.rng)
+                        .unwrap()
+                } else {
+                    next_state
+                }
+            } else {
+                state
+            };
+            self.pop.insert(p.id, state);
+        }
+    }
+
+    fn handle_event(&mut self, now: Time, ev: &Event, scheduler: &mut ModelScheduler) {
+        assert!(self.initialized);
+
+        match ev {
+            Event::PersonEntersBuilding(person, bldg) => {
+                self.bldgs.person_enters_space(now, *person, *bldg);
+            }
+            Event::PersonLeavesBuilding(person, bldg) => {
+                if let Some(others) = self.bldgs.person_leaves_space(now, *person, *bldg) {
+                    self.transmission(now, *person, others, scheduler);
+                } else {
+                    panic!("{} left {}, but they weren't inside", person, bldg);
+                }
+            }
+            Event::TripPhaseStarting(_, p, _, tpt) => {
+                let person = *p;
+                match tpt {
+                    TripPhaseType::WaitingForBus(_, stop) => {
+                        self.bus_stops.person_enters_space(now, person, *stop);
+                    }
+                    TripPhaseType::RidingBus(_, stop, bus) => {
+                        let others = self
+                            .bus_stops
+                            .person_leaves_space(now, person, *stop)
+                            .unwrap();
+                        self.transmission(now, person, others, scheduler);
+
+                        self.buses.person_enters_space(now, person, *bus);
+                        self.person_to_bus.insert(person, *bus);
+                    }
+                    TripPhaseType::Walking => {
+                        // A person can start walking for many reasons, but the only possible state
+                        // transition after riding a bus is walking, so use this to detect the end
+                        // of a bus ride.
+                        if let Some(car) = self.person_to_bus.remove(&person) {
+                            let others = self.buses.person_leaves_space(now, person, car).unwrap();
+                            self.transmission(now, person, others, scheduler);
+                        }
+                    }
+                    _ => {
+                        self.transition(now, person, scheduler);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_cmd(&mut self, _now: Time, cmd: ModelCommand, _scheduler: &mut ModelScheduler) {
+        assert!(self.initialized);
+        let cmd: Cmd = match cmd.decode() {
+            Ok(cmd) => cmd,
+            Err(err) => {
+                error!("Pandemic model got a broken command {:?}: {}", cmd, err);
+                return;
+            }
+        };
+
+        // TODO Here we might enforce policies. Like severe -> become hospitalized
+        // Symptomatic -> stay quaratined, and/or track contacts to quarantine them too (or test
+        // them)
+        match cmd {
+            Cmd::BecomeHospitalized(_person) => {
+                // self.hospitalized.insert(person);
+            }
+            Cmd::BecomeQuarantined(_person) => {
+                // self.quarantined.insert(person);
+            }
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn SecondaryModel> {
+        Box::new(self.clone())
+    }
+}
+
 #[derive(Clone)]
 struct SharedSpace<T: Ord> {
     // Since when has a person been in some shared space?
@@ -1,8 +1,9 @@
 use std::collections::BTreeSet;
 
+use abstutil::FormatVersion;
 use geom::Time;
 use map_model::{IntersectionID, LaneID, Map, PathStep, Position, Traversable};
-use synthpop::{IndividTrip, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose};
+use synthpop::{FleetMix, IndividTrip, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose};
 
 use crate::{AgentID, CarID, DrivingSimState, Event, TripID, VehicleType};
 
@@ -99,6 +100,7 @@ impl TrafficRecorder {
 
     pub fn save(mut self, map: &Map) {
         Scenario {
+            format_version: FormatVersion,
             scenario_name: "recorded".to_string(),
             map_name: map.get_name().clone(),
             people: self
@@ -110,6 +112,7 @@ impl TrafficRecorder {
                 })
                 .collect::<Vec<_>>(),
             only_seed_buses: None,
+            fleet_mix: FleetMix::default(),
         }
         .save();
     }
@@ -3,12 +3,14 @@
 
 use std::collections::HashMap;
 
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
 
-use geom::Distance;
+use geom::{Distance, Time};
 use map_model::{
     BuildingID, IntersectionID, LaneID, Map, Path, PathConstraints, PathRequest, PathStep,
-    Position, Traversable, Turn, TurnID,
+    Position, Traversable, Turn, TurnID, TurnType,
 };
 
 use crate::mechanics::Queue;
@@ -17,6 +19,10 @@ use crate::{
     TripID, TripPhaseType, Vehicle, VehicleType,
 };
 
+/// When cruising for parking, drivers give up after checking this many lanes and head to the
+/// closest free spot they can find, like a garage they know about.
+const MAX_LANES_TO_CRUISE: usize = 30;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub(crate) struct Router {
     /// Front is always the current step
@@ -43,7 +49,10 @@ enum Goal {
         spot: Option<(ParkingSpot, Distance)>,
         /// No parking available at all!
         stuck_end_dist: Option<Distance>,
-        started_looking: bool,
+        /// When the driver reached the destination's lane and started looking for a spot
+        started_looking: Option<Time>,
+        /// How many lanes the driver has circled through without seeing a free spot
+        lanes_cruised: usize,
     },
     EndAtBorder {
         end_dist: Distance,
@@ -78,7 +87,8 @@ impl Router {
                 target: bldg,
                 spot: None,
                 stuck_end_dist: None,
-                started_looking: false,
+                started_looking: None,
+                lanes_cruised: 0,
             },
             owner,
         }
@@ -140,9 +150,11 @@ impl Router {
     /// Returns the step just finished
     pub fn advance(
         &mut self,
+        now: Time,
         vehicle: &Vehicle,
         parking: &ParkingSimState,
         map: &Map,
+        cruise_for_parking: bool,
         trip_and_person: Option<(TripID, PersonID)>,
         events: &mut Vec<Event>,
     ) -> Traversable {
@@ -150,10 +162,12 @@ impl Router {
         if self.last_step() {
             // Do this to trigger the side-effect of looking for parking.
             self.maybe_handle_end(
+                now,
                 Distance::ZERO,
                 vehicle,
                 parking,
                 map,
+                cruise_for_parking,
                 trip_and_person,
                 events,
             );
@@ -175,12 +189,17 @@ impl Router {
 
     /// Called when the car is Queued at the last step, or when they initially advance to the last
     /// step.
+    ///
+    /// If `cruise_for_parking` is true, drivers only know about free spots on the lane they're
+    /// currently on. Otherwise, they magically know where the nearest free spot is.
     pub fn maybe_handle_end(
         &mut self,
+        now: Time,
         front: Distance,
         vehicle: &Vehicle,
         parking: &ParkingSimState,
         map: &Map,
+        cruise_for_parking: bool,
         // TODO Not so nice to plumb all of this here
         trip_and_person: Option<(TripID, PersonID)>,
         events: &mut Vec<Event>,
@@ -200,6 +219,7 @@ impl Router {
                 ref mut stuck_end_dist,
                 target,
                 ref mut started_looking,
+                ref mut lanes_cruised,
             } => {
                 if let Some(d) = stuck_end_dist {
                     if *d == front {
@@ -210,11 +230,13 @@ impl Router {
                 }
 
                 let need_new_spot = match spot {
-                    Some((s, _)) => !parking.is_free(*s),
+                    Some((s, _)) => !parking.is_free(*s) || !parking.may_park(*s, vehicle),
                     None => true,
                 };
                 if need_new_spot {
-                    *started_looking = true;
+                    if started_looking.is_none() {
+                        *started_looking = Some(now);
+                    }
                     let current_lane = self.path.current_step().as_lane();
                     let candidates = parking.get_all_free_spots(
                         Position::new(current_lane, front),
@@ -259,6 +281,25 @@ impl Router {
                         assert!(new_pos.dist_along() >= front);
                         *spot = Some((new_spot, new_pos.dist_along()));
                     } else {
+                        if cruise_for_parking && *lanes_cruised < MAX_LANES_TO_CRUISE {
+                            if let Some(new_path_steps) = cruise_to_next_lane(
+                                current_lane,
+                                vehicle,
+                                target,
+                                *lanes_cruised,
+                                map,
+                            ) {
+                                *lanes_cruised += 1;
+                                for step in new_path_steps {
+                                    self.path.add(step, map);
+                                }
+                                // We'll look again when we reach the next lane
+                                *spot = None;
+                                events.push(Event::PathAmended(self.path.clone()));
+                                return Some(ActionAtEnd::GotoLaneEnd);
+                            }
+                        }
+
                         if let Some((new_path_steps, new_spot, new_pos)) =
                             parking.path_to_free_parking_spot(current_lane, vehicle, target, map)
                         {
@@ -299,6 +340,13 @@ impl Router {
                 }
 
                 if spot.unwrap().1 == front {
+                    if let Some(started) = started_looking {
+                        events.push(Event::ParkingSearchFinished(
+                            vehicle.id,
+                            target,
+                            now - *started,
+                        ));
+                    }
                     Some(ActionAtEnd::StartParking(spot.unwrap().0))
                 } else {
                     None
@@ -326,6 +374,7 @@ impl Router {
         queues: &HashMap<Traversable, Queue>,
         map: &Map,
         handle_uber_turns: bool,
+        now: Time,
     ) {
         // if we're already in the uber-turn, we're committed, but if we're about to enter one, lock
         // in the best path through it now.
@@ -353,9 +402,24 @@ impl Router {
             let next_parent = map.get_l(next_lane).src_i;
             let constraints = self.owner.vehicle_type.to_constraints();
 
-            let compute_cost = |turn1: &Turn, lane: LaneID| {
-                let (lt, lc, mut slow_lane) = turn1.penalty(constraints, map);
-                let (vehicles, mut bike) = queues[&Traversable::Lane(lane)].target_lane_penalty();
+            let compute_cost = |turn1: &Turn, lane: LaneID, turn2: &Turn| {
+                let (mut lt, lc, mut slow_lane) = turn1.penalty(constraints, map);
+                if constraints == PathConstraints::Car
+                    && map.get_l(lane).is_bus()
+                    && !parent.is_bus_lane_active(map.get_l(lane).dir, now)
+                {
+                    // Outside its hours, a bus lane is just another general purpose lane
+                    lt = 0;
+                }
+                let (mut vehicles, mut bike) =
+                    queues[&Traversable::Lane(lane)].target_lane_penalty();
+                // If we're going straight, a lane shared with turning vehicles is worse; they may
+                // block it while waiting for a gap. Count it like one more vehicle in line.
+                if turn2.turn_type == TurnType::Straight
+                    && map.get_l(lane).vehicle_turn_types(map).len() > 1
+                {
+                    vehicles += 1;
+                }
 
                 // The magic happens here. We have different penalties:
                 //
@@ -365,7 +429,8 @@ impl Router {
                 //    we're another bike, the speed difference won't matter.
                 // 3) IF we're a bike, are we headed to something other than the slow (rightmost in
                 //    the US) lane?
-                // 4) Are there lots of vehicles stacked up in one lane?
+                // 4) Are there lots of vehicles stacked up in one lane? (Or turning vehicles that
+                //    might block us?)
                 // 5) Are we changing lanes?
                 //
                 // A linear combination of these penalties is hard to reason about. We mostly
@@ -387,7 +452,16 @@ impl Router {
             let best = parent
                 .lanes
                 .iter()
-                .filter(|l| l.dir == dir && constraints.can_use(l, map))
+                .filter(|l| {
+                    l.dir == dir
+                        && (constraints.can_use(l, map)
+                            || (constraints == PathConstraints::Car
+                                && l.is_bus()
+                                && !parent.is_bus_lane_active(l.dir, now))
+                            || (constraints == PathConstraints::Car
+                                && l.is_parking()
+                                && parent.is_clearway_active(l.dir, now)))
+                })
                 .filter_map(|l| {
                     // Make sure we can go from this lane to next_lane.
 
@@ -408,7 +482,7 @@ impl Router {
                     Some((turn1, l.id, turn2))
                 })
                 .map(|(turn1, l, turn2)| {
-                    let cost = compute_cost(turn1, l);
+                    let cost = compute_cost(turn1, l, turn2);
                     if turn1.id == current_turn {
                         original_cost = Some(cost);
                     }
@@ -487,7 +561,7 @@ impl Router {
         match self.goal {
             Goal::ParkNearBuilding {
                 started_looking, ..
-            } => started_looking,
+            } => started_looking.is_some(),
             _ => false,
         }
     }
@@ -499,3 +573,49 @@ impl Router {
         }
     }
 }
+
+/// A driver without knowledge of free spots further away picks a nearby lane to check next,
+/// returning the turn and lane to add to their path. They tend to stay close to their destination,
+/// but don't all take the same loop.
+fn cruise_to_next_lane(
+    current: LaneID,
+    vehicle: &Vehicle,
+    target: BuildingID,
+    lanes_cruised: usize,
+    map: &Map,
+) -> Option<Vec<PathStep>> {
+    let target_pt = map.get_b(target).polygon.center();
+    // Deterministic across runs, but different between vehicles and each lane they check
+    let mut rng = XorShiftRng::seed_from_u64(
+        (vehicle.id.id + current.encode_u32() as usize + lanes_cruised) as u64,
+    );
+
+    let mut candidates: Vec<&Turn> = map
+        .get_turns_for(current, PathConstraints::Car)
+        .into_iter()
+        .filter(|t| {
+            vehicle
+                .class
+                .map(|class| class.can_use_road(map.get_r(t.id.dst.road)))
+                .unwrap_or(true)
+        })
+        .collect();
+    // Only turn around when there's no other choice
+    if candidates.iter().any(|t| t.turn_type != TurnType::UTurn) {
+        candidates.retain(|t| t.turn_type != TurnType::UTurn);
+    }
+
+    let turn = candidates
+        .into_iter()
+        .map(|t| {
+            let dist = map
+                .get_l(t.id.dst)
+                .lane_center_pts
+                .middle()
+                .dist_to(target_pt);
+            (rng.gen_range(0.5..1.5) * dist, t.id)
+        })
+        .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap().then(a.1.cmp(&b.1)))?
+        .1;
+    Some(vec![PathStep::Turn(turn), PathStep::Lane(turn.dst)])
+}
@@ -1,14 +1,16 @@
 use std::collections::hash_map::Entry;
 use std::collections::{BinaryHeap, HashMap};
 
+use anyhow::Result;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
 use abstutil::{Counter, PriorityQueueItem};
 use geom::{Duration, Histogram, Time};
-use map_model::{IntersectionID, TransitRouteID};
+use map_model::{BuildingID, IntersectionID, TransitRouteID};
 
 use crate::{
-    pandemic, AgentID, CarID, CreateCar, CreatePedestrian, PedestrianID, StartTripArgs, TripID,
+    AgentID, CarID, CreateCar, CreatePedestrian, PedestrianID, PersonID, StartTripArgs, TripID,
 };
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -23,9 +25,18 @@ pub(crate) enum Command {
     UpdatePed(PedestrianID),
     UpdateIntersection(IntersectionID),
     Callback(Duration),
-    Pandemic(pandemic::Cmd),
+    /// Handled by a secondary model, like the pandemic model or one registered with
+    /// `Sim::register_model`
+    Model(ModelCommand),
     /// The Time is redundant, just used to dedupe commands
     StartBus(TransitRouteID, Time),
+    /// Some parking restriction starts or ends now
+    UpdateParkingRestrictions,
+    CheckForGridlock,
+    /// Somebody starting a trip books a microtransit ride between two buildings
+    RequestMicrotransit(TripID, PersonID, BuildingID, BuildingID),
+    /// Assign pending microtransit requests to vehicles
+    DispatchMicrotransit,
 }
 
 impl Command {
@@ -47,8 +58,14 @@ impl Command {
             Command::UpdatePed(id) => CommandType::Ped(*id),
             Command::UpdateIntersection(id) => CommandType::Intersection(*id),
             Command::Callback(_) => CommandType::Callback,
-            Command::Pandemic(ref p) => CommandType::Pandemic(p.clone()),
+            Command::Model(ref cmd) => {
+                CommandType::Model(cmd.priority, cmd.model.clone(), cmd.key.clone())
+            }
             Command::StartBus(r, t) => CommandType::StartBus(*r, *t),
+            Command::UpdateParkingRestrictions => CommandType::ParkingRestrictions,
+            Command::CheckForGridlock => CommandType::Gridlock,
+            Command::RequestMicrotransit(trip, _, _, _) => CommandType::MicrotransitRequest(*trip),
+            Command::DispatchMicrotransit => CommandType::Microtransit,
         }
     }
 
@@ -62,8 +79,13 @@ impl Command {
             Command::UpdatePed(_) => SimpleCommandType::Ped,
             Command::UpdateIntersection(_) => SimpleCommandType::Intersection,
             Command::Callback(_) => SimpleCommandType::Callback,
-            Command::Pandemic(_) => SimpleCommandType::Pandemic,
+            Command::Model(_) => SimpleCommandType::Model,
             Command::StartBus(_, _) => SimpleCommandType::StartBus,
+            Command::UpdateParkingRestrictions => SimpleCommandType::ParkingRestrictions,
+            Command::CheckForGridlock => SimpleCommandType::Gridlock,
+            Command::RequestMicrotransit(_, _, _, _) | Command::DispatchMicrotransit => {
+                SimpleCommandType::Microtransit
+            }
         }
     }
 }
@@ -78,8 +100,13 @@ enum CommandType {
     Ped(PedestrianID),
     Intersection(IntersectionID),
     Callback,
-    Pandemic(pandemic::Cmd),
+    /// Among commands at the same time, higher priorities go first
+    Model(u8, String, String),
     StartBus(TransitRouteID, Time),
+    ParkingRestrictions,
+    Gridlock,
+    MicrotransitRequest(TripID),
+    Microtransit,
 }
 
 /// A more compressed form of CommandType, just used for keeping stats on event processing.
@@ -91,8 +118,11 @@ enum SimpleCommandType {
     Ped,
     Intersection,
     Callback,
-    Pandemic,
+    Model,
     StartBus,
+    ParkingRestrictions,
+    Gridlock,
+    Microtransit,
 }
 
 /// The priority queue driving the discrete event simulation. Different pieces of the simulation
@@ -227,3 +257,70 @@ impl Scheduler {
         stats
     }
 }
+
+/// A command scheduled by a secondary model. The simulation doesn't interpret the payload; it just
+/// hands the command back to the model named here at the scheduled time.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Debug)]
+pub struct ModelCommand {
+    /// Which model handles this command. See `SecondaryModel::name`.
+    pub model: String,
+    /// Only one command per model, key, and priority may be scheduled at a time. Pushing the same
+    /// one again is a bug; use `ModelScheduler::update` to reschedule it.
+    pub key: String,
+    /// Among model commands scheduled for the same time, higher priorities are handled first.
+    /// Defaults to 0.
+    pub priority: u8,
+    /// Model-specific data
+    pub payload: Vec<u8>,
+}
+
+impl ModelCommand {
+    /// Serializes the payload in a compact binary format
+    pub fn new<T: Serialize>(model: &str, key: String, payload: &T) -> ModelCommand {
+        ModelCommand {
+            model: model.to_string(),
+            key,
+            priority: 0,
+            payload: abstutil::to_binary(payload),
+        }
+    }
+
+    pub fn priority(mut self, priority: u8) -> ModelCommand {
+        self.priority = priority;
+        self
+    }
+
+    /// Deserializes a payload created by `new`
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T> {
+        abstutil::from_binary(&self.payload)
+    }
+}
+
+/// Lets secondary models schedule their own commands in the simulation's priority queue, without
+/// access to any of the simulation's internal commands.
+pub struct ModelScheduler<'a> {
+    scheduler: &'a mut Scheduler,
+}
+
+impl<'a> ModelScheduler<'a> {
+    pub(crate) fn new(scheduler: &'a mut Scheduler) -> ModelScheduler<'a> {
+        ModelScheduler { scheduler }
+    }
+
+    /// Schedule a new command. Panics if the time is in the past, or if a command with the same
+    /// model, key, and priority is already scheduled.
+    pub fn push(&mut self, time: Time, cmd: ModelCommand) {
+        self.scheduler.push(time, Command::Model(cmd));
+    }
+
+    /// Schedule a command, replacing the time of an existing command with the same model, key, and
+    /// priority. The payload must match the existing command.
+    pub fn update(&mut self, time: Time, cmd: ModelCommand) {
+        self.scheduler.update(time, Command::Model(cmd));
+    }
+
+    /// Cancel a command with the same model, key, and priority, if one is scheduled
+    pub fn cancel(&mut self, cmd: ModelCommand) {
+        self.scheduler.cancel(Command::Model(cmd));
+    }
+}
@@ -0,0 +1,110 @@
+use geom::Time;
+
+use crate::{Event, ModelCommand, ModelScheduler, Person};
+
+/// A model running alongside the traffic simulation, like the pandemic model. It observes every
+/// event the simulation produces and can schedule its own commands, without the simulation having
+/// to know anything about it. Register one with `Sim::register_model`.
+///
+/// Models aren't included in savestates. After loading one, register the models again; any of
+/// their commands that were still scheduled will be handed to the new instances.
+pub trait SecondaryModel: downcast_rs::Downcast + Send {
+    /// Must be unique among all registered models. Commands with this `ModelCommand::model` are
+    /// routed here.
+    fn name(&self) -> &str;
+
+    /// Called once after a scenario is instantiated, if the model was registered before then
+    fn initialize(&mut self, _population: &[Person], _scheduler: &mut ModelScheduler) {}
+
+    /// Called for every event, at the time it happens
+    fn handle_event(&mut self, _now: Time, _ev: &Event, _scheduler: &mut ModelScheduler) {}
+
+    /// Called when one of this model's commands is due
+    fn handle_cmd(&mut self, now: Time, cmd: ModelCommand, scheduler: &mut ModelScheduler);
+
+    /// Models have to be copied when the simulation is
+    fn clone_box(&self) -> Box<dyn SecondaryModel>;
+}
+downcast_rs::impl_downcast!(SecondaryModel);
+
+impl Clone for Box<dyn SecondaryModel> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    use abstutil::Timer;
+    use geom::Duration;
+    use map_model::Map;
+    use synthpop::Scenario;
+
+    use super::*;
+    use crate::{Sim, SimOptions};
+
+    /// Schedules a countdown when the scenario starts, then each command schedules the next
+    #[derive(Clone, Default)]
+    struct Countdown {
+        received: Vec<(Time, usize)>,
+    }
+
+    impl SecondaryModel for Countdown {
+        fn name(&self) -> &str {
+            "countdown"
+        }
+
+        fn initialize(&mut self, _: &[Person], scheduler: &mut ModelScheduler) {
+            scheduler.push(
+                Time::START_OF_DAY + Duration::minutes(5),
+                ModelCommand::new("countdown", "tick".to_string(), &3_usize),
+            );
+        }
+
+        fn handle_cmd(&mut self, now: Time, cmd: ModelCommand, scheduler: &mut ModelScheduler) {
+            let left: usize = cmd.decode().unwrap();
+            self.received.push((now, left));
+            if left > 1 {
+                scheduler.push(
+                    now + Duration::minutes(5),
+                    ModelCommand::new("countdown", "tick".to_string(), &(left - 1)),
+                );
+            }
+        }
+
+        fn clone_box(&self) -> Box<dyn SecondaryModel> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_registered_model_receives_commands() {
+        let map = Map::blank();
+        let mut sim = Sim::new(&map, SimOptions::default());
+        sim.register_model(Box::new(Countdown::default()));
+        sim.instantiate(
+            &Scenario::empty(&map, "countdown"),
+            &map,
+            &mut XorShiftRng::seed_from_u64(42),
+            &mut Timer::throwaway(),
+        );
+        sim.timed_step(&map, Duration::hours(1), &mut None, &mut Timer::throwaway());
+
+        let model = sim
+            .get_model("countdown")
+            .unwrap()
+            .downcast_ref::<Countdown>()
+            .unwrap();
+        assert_eq!(
+            model.received,
+            vec![
+                (Time::START_OF_DAY + Duration::minutes(5), 3),
+                (Time::START_OF_DAY + Duration::minutes(10), 2),
+                (Time::START_OF_DAY + Duration::minutes(15), 1),
+            ]
+        );
+    }
+}
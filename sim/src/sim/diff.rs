@@ -0,0 +1,408 @@
+//! Tools for comparing two simulations and storing compact incremental savestates. Both work by
+//! serializing each piece of the simulation separately.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{Hash, Hasher};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use abstutil::Timer;
+use geom::Time;
+use map_model::Traversable;
+
+use crate::{AgentID, DrivingSimState, Sim, WalkingSimState};
+
+/// Describes how two simulations differ. This is useful for debugging nondeterminism: run the same
+/// scenario twice, save at the same time, and diff the results.
+#[derive(Serialize)]
+pub struct SimDiff {
+    /// The times of both simulations, if they differ
+    pub time: Option<(Time, Time)>,
+    /// Which top-level pieces of the simulation serialize differently
+    pub components: Vec<&'static str>,
+    pub agents: BTreeMap<AgentID, AgentDiff>,
+    /// Lanes and turns whose queue of vehicles differs
+    pub queues: BTreeSet<Traversable>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum AgentDiff {
+    OnlyInFirst,
+    OnlyInSecond,
+    StateDiffers,
+}
+
+impl SimDiff {
+    pub fn is_empty(&self) -> bool {
+        self.time.is_none() && self.components.is_empty()
+    }
+
+    pub fn describe(&self) -> Vec<String> {
+        if self.is_empty() {
+            return vec!["The simulations are identical".to_string()];
+        }
+        let mut lines = Vec::new();
+        if let Some((t1, t2)) = self.time {
+            lines.push(format!("Time differs: {} vs {}", t1, t2));
+        }
+        lines.push(format!("Differing pieces: {}", self.components.join(", ")));
+        lines.push(format!("{} agents differ", self.agents.len()));
+        for (id, diff) in self.agents.iter().take(10) {
+            lines.push(format!("- {}: {:?}", id, diff));
+        }
+        lines.push(format!("{} queues differ", self.queues.len()));
+        for id in self.queues.iter().take(10) {
+            lines.push(format!("- {:?}", id));
+        }
+        lines
+    }
+}
+
+/// An incremental savestate, only storing what changed since a full savestate. On large maps,
+/// much of the state (parking, intersections, transit) changes slowly, and most agents and queues
+/// haven't changed between two checkpoints, so these are much smaller and faster to write.
+#[derive(Serialize, Deserialize)]
+struct SimCheckpoint {
+    /// The path to the full savestate this is relative to
+    base: String,
+    time: Time,
+    step_count: usize,
+    /// Pieces of the simulation that changed, stored whole
+    changed: BTreeMap<String, Vec<u8>>,
+    /// Cars, queues, and pedestrians are stored individually, only if they changed
+    entries: BTreeMap<String, EntriesDelta>,
+    /// Analytics that were already moved to disk aren't copied; this refers to those files.
+    spilled_analytics: Option<(String, Vec<Time>)>,
+}
+
+/// Individually serialized entries of something, like cars, keyed by their serialized ID.
+pub(crate) type CheckpointEntries = BTreeMap<Vec<u8>, Vec<u8>>;
+
+#[derive(Default, Serialize, Deserialize)]
+struct EntriesDelta {
+    /// New entries, or ones that changed since the full savestate
+    changed: CheckpointEntries,
+    removed: Vec<Vec<u8>>,
+}
+
+/// Remembers what the last full savestate looked like, without keeping a copy of it around.
+#[derive(Clone)]
+pub(crate) struct CheckpointBase {
+    path: String,
+    hashes: BTreeMap<&'static str, u64>,
+    entry_hashes: BTreeMap<&'static str, BTreeMap<Vec<u8>, u64>>,
+}
+
+impl CheckpointBase {
+    fn new(path: String, sim: &mut Sim) -> CheckpointBase {
+        let (pieces, entries) = sim.checkpoint_pieces();
+        CheckpointBase {
+            path,
+            hashes: pieces
+                .into_iter()
+                .map(|(name, bytes)| (name, hash(&bytes)))
+                .collect(),
+            entry_hashes: entries
+                .into_iter()
+                .map(|(name, entries)| {
+                    (
+                        name,
+                        entries
+                            .into_iter()
+                            .map(|(id, bytes)| (id, hash(&bytes)))
+                            .collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Sim {
+    /// Compare this simulation to another one, describing which agents and queues differ.
+    pub fn diff(&self, other: &Sim) -> SimDiff {
+        let other_components: BTreeMap<&'static str, Vec<u8>> =
+            other.components().into_iter().collect();
+        let mut components = Vec::new();
+        for (name, bytes) in self.components() {
+            if other_components[name] != bytes {
+                components.push(name);
+            }
+        }
+
+        let mut agents = BTreeMap::new();
+        let (cars, queues) = self.driving.diff(&other.driving);
+        for (id, diff) in cars {
+            agents.insert(AgentID::Car(id), diff);
+        }
+        for (id, diff) in self.walking.diff(&other.walking) {
+            agents.insert(AgentID::Pedestrian(id), diff);
+        }
+
+        SimDiff {
+            time: if self.time == other.time {
+                None
+            } else {
+                Some((self.time, other.time))
+            },
+            components,
+            agents,
+            queues,
+        }
+    }
+
+    /// Write a savestate and return its path. The first call writes a full savestate; later calls
+    /// only write what changed since then, unless `force_full` is set.
+    pub fn save_checkpoint(&mut self, force_full: bool) -> String {
+        if let Some(base) = self.checkpoint_base.take().filter(|_| !force_full) {
+            let checkpoint = self.make_checkpoint(&base);
+            self.checkpoint_base = Some(base);
+            let path = self.save_path(self.time).replace(".bin", "_checkpoint.bin");
+            abstio::write_binary(path.clone(), &checkpoint);
+            return path;
+        }
+
+        let path = self.save();
+        self.checkpoint_base = Some(CheckpointBase::new(path.clone(), self));
+        path
+    }
+
+    fn make_checkpoint(&mut self, base: &CheckpointBase) -> SimCheckpoint {
+        let (pieces, all_entries) = self.checkpoint_pieces();
+
+        let mut changed = BTreeMap::new();
+        for (name, bytes) in pieces {
+            if base.hashes.get(name) != Some(&hash(&bytes)) {
+                changed.insert(name.to_string(), bytes);
+            }
+        }
+
+        let mut entries = BTreeMap::new();
+        for (name, current) in all_entries {
+            let base_hashes = &base.entry_hashes[name];
+            let mut delta = EntriesDelta::default();
+            for id in base_hashes.keys() {
+                if !current.contains_key(id) {
+                    delta.removed.push(id.clone());
+                }
+            }
+            for (id, bytes) in current {
+                if base_hashes.get(&id) != Some(&hash(&bytes)) {
+                    delta.changed.insert(id, bytes);
+                }
+            }
+            entries.insert(name.to_string(), delta);
+        }
+
+        SimCheckpoint {
+            base: base.path.clone(),
+            time: self.time,
+            step_count: self.step_count,
+            changed,
+            entries,
+            spilled_analytics: self.analytics.spilled_chunks(),
+        }
+    }
+
+    pub(crate) fn is_checkpoint(path: &str) -> bool {
+        path.ends_with("_checkpoint.bin")
+    }
+
+    pub(crate) fn load_checkpoint(path: String, timer: &mut Timer) -> Result<Sim> {
+        let checkpoint: SimCheckpoint = abstio::maybe_read_binary(path, timer)?;
+        let base: Sim = abstio::maybe_read_binary(checkpoint.base.clone(), timer)?;
+        Sim::apply_checkpoint(base, checkpoint)
+    }
+
+    fn apply_checkpoint(mut sim: Sim, mut checkpoint: SimCheckpoint) -> Result<Sim> {
+        sim.time = checkpoint.time;
+        sim.step_count = checkpoint.step_count;
+
+        let (driving, walking, mut all_entries) = sim.split_agents();
+        for (name, delta) in checkpoint.entries {
+            let entries = match all_entries.get_mut(name.as_str()) {
+                Some(entries) => entries,
+                None => bail!("Unknown entries in the simulation {}", name),
+            };
+            for id in delta.removed {
+                entries.remove(&id);
+            }
+            entries.extend(delta.changed);
+        }
+
+        let driving = checkpoint.changed.remove("driving").unwrap_or(driving);
+        sim.driving = DrivingSimState::join_from_checkpoint(
+            &driving,
+            all_entries.remove("cars").unwrap(),
+            all_entries.remove("queues").unwrap(),
+        )?;
+        let walking = checkpoint.changed.remove("walking").unwrap_or(walking);
+        sim.walking =
+            WalkingSimState::join_from_checkpoint(&walking, all_entries.remove("peds").unwrap())?;
+
+        for (name, bytes) in checkpoint.changed {
+            sim.set_component(&name, &bytes)?;
+        }
+        if let Some((dir, chunks)) = checkpoint.spilled_analytics {
+            sim.analytics.read_back_spilled(dir, chunks);
+        }
+        Ok(sim)
+    }
+
+    /// Serialize each piece of the simulation separately.
+    fn components(&self) -> Vec<(&'static str, Vec<u8>)> {
+        let mut pieces = vec![
+            ("driving", abstutil::to_binary(&self.driving)),
+            ("walking", abstutil::to_binary(&self.walking)),
+        ];
+        pieces.extend(self.other_components());
+        pieces
+    }
+
+    /// Like `components`, but cars, queues, and pedestrians are serialized individually, and left
+    /// out of the driving and walking pieces.
+    fn checkpoint_pieces(
+        &mut self,
+    ) -> (
+        Vec<(&'static str, Vec<u8>)>,
+        BTreeMap<&'static str, CheckpointEntries>,
+    ) {
+        let (driving, walking, entries) = self.split_agents();
+        let mut pieces = vec![("driving", driving), ("walking", walking)];
+        pieces.extend(self.other_components());
+        (pieces, entries)
+    }
+
+    /// Returns the rest of the driving and walking state, then the individual entries.
+    fn split_agents(&mut self) -> (Vec<u8>, Vec<u8>, BTreeMap<&'static str, CheckpointEntries>) {
+        let (driving, cars, queues) = self.driving.split_for_checkpoint();
+        let (walking, peds) = self.walking.split_for_checkpoint();
+        let mut entries = BTreeMap::new();
+        entries.insert("cars", cars);
+        entries.insert("queues", queues);
+        entries.insert("peds", peds);
+        (driving, walking, entries)
+    }
+
+    fn other_components(&self) -> Vec<(&'static str, Vec<u8>)> {
+        vec![
+            ("parking", abstutil::to_binary(&self.parking)),
+            ("intersections", abstutil::to_binary(&self.intersections)),
+            ("transit", abstutil::to_binary(&self.transit)),
+            ("trips", abstutil::to_binary(&self.trips)),
+            ("scheduler", abstutil::to_binary(&self.scheduler)),
+            ("analytics", abstutil::to_binary(&self.analytics)),
+            ("weather", abstutil::to_binary(&self.weather)),
+            (
+                "gridlock_watchdog",
+                abstutil::to_binary(&self.gridlock_watchdog),
+            ),
+            (
+                "highlighted_people",
+                abstutil::to_binary(&self.highlighted_people),
+            ),
+        ]
+    }
+
+    fn set_component(&mut self, name: &str, bytes: &[u8]) -> Result<()> {
+        match name {
+            "parking" => self.parking = abstutil::from_binary(bytes)?,
+            "intersections" => self.intersections = abstutil::from_binary(bytes)?,
+            "transit" => self.transit = abstutil::from_binary(bytes)?,
+            "trips" => self.trips = abstutil::from_binary(bytes)?,
+            "scheduler" => self.scheduler = abstutil::from_binary(bytes)?,
+            "analytics" => self.analytics = abstutil::from_binary(bytes)?,
+            "weather" => self.weather = abstutil::from_binary(bytes)?,
+            "gridlock_watchdog" => self.gridlock_watchdog = abstutil::from_binary(bytes)?,
+            "highlighted_people" => self.highlighted_people = abstutil::from_binary(bytes)?,
+            x => bail!("Unknown piece of the simulation {}", x),
+        }
+        Ok(())
+    }
+}
+
+fn hash(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    use geom::Duration;
+    use map_model::Map;
+    use synthpop::{IndividTrip, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose};
+
+    use super::*;
+    use crate::SimOptions;
+
+    #[test]
+    fn test_checkpoint_matches_original() {
+        let map = Map::almost_blank();
+        let border1 = TripEndpoint::Border(map.all_intersections()[0].id);
+        let border2 = TripEndpoint::Border(map.all_intersections()[1].id);
+        let bldg = TripEndpoint::Building(map.all_buildings()[0].id);
+
+        let mut scenario = Scenario::empty(&map, "checkpoints");
+        for i in 0..10 {
+            let depart = Time::START_OF_DAY + Duration::seconds(10.0 * (i as f64));
+            scenario.people.push(PersonSpec {
+                orig_id: None,
+                trips: vec![IndividTrip::new(
+                    depart,
+                    TripPurpose::Work,
+                    border1,
+                    border2,
+                    TripMode::Drive,
+                )],
+            });
+            scenario.people.push(PersonSpec {
+                orig_id: None,
+                trips: vec![IndividTrip::new(
+                    depart,
+                    TripPurpose::Shopping,
+                    bldg,
+                    border2,
+                    TripMode::Walk,
+                )],
+            });
+        }
+
+        let mut sim = Sim::new(&map, SimOptions::new("checkpoints"));
+        sim.instantiate(
+            &scenario,
+            &map,
+            &mut XorShiftRng::seed_from_u64(42),
+            &mut Timer::throwaway(),
+        );
+        sim.timed_step(
+            &map,
+            Duration::seconds(30.0),
+            &mut None,
+            &mut Timer::throwaway(),
+        );
+
+        // Stands in for writing the full savestate and reading it back
+        let base = sim.clone();
+        let checkpoint_base = CheckpointBase::new(String::new(), &mut sim);
+
+        sim.timed_step(
+            &map,
+            Duration::seconds(30.0),
+            &mut None,
+            &mut Timer::throwaway(),
+        );
+        let checkpoint = sim.make_checkpoint(&checkpoint_base);
+        assert!(!checkpoint.entries["cars"].changed.is_empty());
+        assert!(!checkpoint.changed.contains_key("weather"));
+
+        let loaded = Sim::apply_checkpoint(base, checkpoint).unwrap();
+        let diff = sim.diff(&loaded);
+        assert!(diff.is_empty(), "{:?}", diff.describe());
+    }
+}
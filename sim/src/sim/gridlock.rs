@@ -0,0 +1,353 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use abstutil::Counter;
+use geom::{Duration, Time};
+use map_model::{IntersectionID, Map, Traversable};
+
+use crate::{AgentID, AlertLocation, Command, DelayCause, Event, Scheduler, Sim};
+
+/// How often the watchdog checks for progress
+const CHECK_FREQUENCY: Duration = Duration::const_seconds(60.0);
+
+/// Notices when no vehicle has entered a new lane or turn for a while, even though some agents are
+/// stuck waiting. Pedestrians are ignored, since they rarely cause gridlock and could otherwise
+/// hide it.
+///
+/// Checks stop while nobody is active or after a stall has been reported, and resume when a trip
+/// starts or a vehicle moves again.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct GridlockWatchdog {
+    threshold: Duration,
+    last_progress: Time,
+    /// Only report each stall once
+    reported: bool,
+    latest_report: Option<GridlockReport>,
+    /// Is a CheckForGridlock command pending?
+    scheduled: bool,
+}
+
+impl GridlockWatchdog {
+    pub fn new(threshold: Duration) -> GridlockWatchdog {
+        GridlockWatchdog {
+            threshold,
+            last_progress: Time::START_OF_DAY,
+            reported: false,
+            latest_report: None,
+            scheduled: false,
+        }
+    }
+
+    pub fn handle_event(&mut self, now: Time, ev: &Event, scheduler: &mut Scheduler) {
+        if let Event::AgentEntersTraversable(AgentID::Car(_), _, _, _) = ev {
+            self.last_progress = now;
+            self.reported = false;
+            self.wake(now, scheduler);
+        }
+    }
+
+    /// Start checking again, if the watchdog went idle. Progress is measured from now.
+    pub fn wake(&mut self, now: Time, scheduler: &mut Scheduler) {
+        if !self.scheduled {
+            self.last_progress = now;
+            self.schedule_check(now, scheduler);
+        }
+    }
+
+    fn schedule_check(&mut self, now: Time, scheduler: &mut Scheduler) {
+        self.scheduled = true;
+        scheduler.push(now + CHECK_FREQUENCY, Command::CheckForGridlock);
+    }
+}
+
+/// Describes gridlock, for debugging and bug reports.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GridlockReport {
+    pub time: Time,
+    /// How long since any vehicle last entered a new lane or turn
+    pub stalled_for: Duration,
+    /// How many agents are waiting on something
+    pub num_blocked: usize,
+    /// Agents waiting on each other in a cycle, if there is one. Each agent is blocked by the next
+    /// one, and the last by the first. When there are multiple cycles, this is the one involving
+    /// whoever has been waiting longest.
+    pub cycle: Vec<AgentID>,
+    /// The lanes and turns where agents in the cycle are stuck
+    pub queues: Vec<Traversable>,
+    /// The intersections that the cycle passes through
+    pub intersections: Vec<IntersectionID>,
+    /// Intersections that are ultimately responsible for the most blocked agents, with that count
+    pub worst_intersections: Vec<(IntersectionID, usize)>,
+}
+
+impl GridlockReport {
+    /// A one-line summary
+    pub fn describe(&self) -> String {
+        if self.cycle.is_empty() {
+            format!(
+                "No vehicles have moved for {}; {} agents are blocked, but no cycle was found",
+                self.stalled_for, self.num_blocked
+            )
+        } else {
+            format!(
+                "No vehicles have moved for {}; {} agents are blocked, including a cycle of {} \
+                 agents through {} intersections",
+                self.stalled_for,
+                self.num_blocked,
+                self.cycle.len(),
+                self.intersections.len()
+            )
+        }
+    }
+
+    /// The best place to look first
+    pub fn focus(&self) -> Option<IntersectionID> {
+        self.intersections
+            .first()
+            .cloned()
+            .or_else(|| self.worst_intersections.first().map(|(i, _)| *i))
+    }
+}
+
+impl Sim {
+    /// Explain why agents are currently stuck. Returns None if nobody is blocked.
+    pub fn diagnose_gridlock(&self, map: &Map) -> Option<GridlockReport> {
+        let graph = self.get_blocked_by_graph(map);
+        if graph.is_empty() {
+            return None;
+        }
+
+        let mut worst = Counter::new();
+        for start in graph.keys() {
+            if let Some(i) = root_intersection(&graph, *start) {
+                worst.inc(i);
+            }
+        }
+
+        let cycle = find_cycle(&graph);
+        let mut queues = Vec::new();
+        let mut intersections = Vec::new();
+        for agent in &cycle {
+            let on = match agent {
+                AgentID::Car(c) => self.get_draw_car(*c, map).map(|d| d.on),
+                AgentID::Pedestrian(p) => self.get_draw_ped(*p, map).map(|d| d.on),
+                AgentID::BusPassenger(_, _) => None,
+            };
+            if let Some(on) = on {
+                if !queues.contains(&on) {
+                    queues.push(on);
+                }
+                let i = match on {
+                    Traversable::Lane(l) => map.get_l(l).dst_i,
+                    Traversable::Turn(t) => t.parent,
+                };
+                if !intersections.contains(&i) {
+                    intersections.push(i);
+                }
+            }
+        }
+
+        let stalled_for = match self.gridlock_watchdog {
+            Some(ref watchdog) => self.time - watchdog.last_progress,
+            None => Duration::ZERO,
+        };
+        Some(GridlockReport {
+            time: self.time,
+            stalled_for,
+            num_blocked: graph.len(),
+            cycle,
+            queues,
+            intersections,
+            worst_intersections: worst.highest_n(5),
+        })
+    }
+
+    /// The most recent report from the gridlock watchdog, if it's enabled and has noticed
+    /// anything
+    pub fn get_gridlock_report(&self) -> Option<&GridlockReport> {
+        self.gridlock_watchdog
+            .as_ref()
+            .and_then(|w| w.latest_report.as_ref())
+    }
+
+    /// Use a different watchdog setting, like after loading a savestate. Progress is measured
+    /// from now.
+    pub(crate) fn replace_gridlock_watchdog(&mut self, watchdog: Option<GridlockWatchdog>) {
+        // A check from the savestate may already be pending
+        let scheduled = self
+            .gridlock_watchdog
+            .as_ref()
+            .map(|w| w.scheduled)
+            .unwrap_or(false);
+        self.gridlock_watchdog = watchdog.map(|mut w| {
+            w.last_progress = self.time;
+            w.scheduled = scheduled;
+            w
+        });
+        if let Some(ref mut w) = self.gridlock_watchdog {
+            w.wake(self.time, &mut self.scheduler);
+        }
+    }
+
+    pub(crate) fn check_for_gridlock(&mut self, map: &Map) -> Option<Event> {
+        let watchdog = self.gridlock_watchdog.as_mut()?;
+        watchdog.scheduled = false;
+        // Don't reschedule. Starting a trip or moving a vehicle will wake the watchdog up again.
+        if watchdog.reported || self.trips.num_active_agents() == 0 {
+            return None;
+        }
+        if self.time - watchdog.last_progress < watchdog.threshold {
+            watchdog.schedule_check(self.time, &mut self.scheduler);
+            return None;
+        }
+
+        let report = match self.diagnose_gridlock(map) {
+            Some(report) => report,
+            None => {
+                // Nobody is moving, but nobody is stuck either
+                let watchdog = self.gridlock_watchdog.as_mut().unwrap();
+                watchdog.last_progress = self.time;
+                watchdog.schedule_check(self.time, &mut self.scheduler);
+                return None;
+            }
+        };
+
+        let path = format!(
+            "{}/gridlock_{}.json",
+            self.save_dir(),
+            self.time.as_filename()
+        );
+        abstio::write_json(path.clone(), &report);
+        let msg = format!("{}. Details in {}", report.describe(), path);
+        let loc = match report.focus() {
+            Some(i) => AlertLocation::Intersection(i),
+            None => AlertLocation::Nil,
+        };
+
+        let watchdog = self.gridlock_watchdog.as_mut().unwrap();
+        watchdog.reported = true;
+        watchdog.latest_report = Some(report);
+        Some(Event::Alert(loc, msg))
+    }
+}
+
+/// Follow the chain of agents waiting on each other, returning the intersection at the end, if
+/// that's what's responsible.
+fn root_intersection(
+    graph: &BTreeMap<AgentID, (Duration, DelayCause)>,
+    start: AgentID,
+) -> Option<IntersectionID> {
+    let mut seen = HashSet::new();
+    let mut current = start;
+    loop {
+        if !seen.insert(current) {
+            return None;
+        }
+        match graph.get(&current) {
+            Some((_, DelayCause::Agent(a))) => {
+                current = *a;
+            }
+            Some((_, DelayCause::Intersection(i))) => {
+                return Some(*i);
+            }
+            None => {
+                return None;
+            }
+        }
+    }
+}
+
+/// Each agent is blocked by at most one other agent, so just walk from each agent, starting with
+/// the one waiting longest, until reaching an agent already seen.
+fn find_cycle(graph: &BTreeMap<AgentID, (Duration, DelayCause)>) -> Vec<AgentID> {
+    let mut starts: Vec<(Duration, AgentID)> = graph
+        .iter()
+        .map(|(agent, (delay, _))| (*delay, *agent))
+        .collect();
+    starts.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap().then(b.1.cmp(&a.1)));
+
+    let mut visited: HashSet<AgentID> = HashSet::new();
+    for (_, start) in starts {
+        // Position of each agent along the current walk
+        let mut path: HashMap<AgentID, usize> = HashMap::new();
+        let mut order = Vec::new();
+        let mut current = start;
+        loop {
+            if let Some(idx) = path.get(&current) {
+                return order.split_off(*idx);
+            }
+            if !visited.insert(current) {
+                break;
+            }
+            path.insert(current, order.len());
+            order.push(current);
+            match graph.get(&current) {
+                Some((_, DelayCause::Agent(a))) => {
+                    current = *a;
+                }
+                _ => break,
+            }
+        }
+    }
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use map_model::{LaneID, RoadID};
+
+    use super::*;
+    use crate::{CarID, SimOptions, VehicleType};
+
+    fn car_moves() -> Event {
+        Event::AgentEntersTraversable(
+            AgentID::Car(CarID {
+                id: 0,
+                vehicle_type: VehicleType::Car,
+            }),
+            None,
+            Traversable::Lane(LaneID {
+                road: RoadID(0),
+                offset: 0,
+            }),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_progress_wakes_watchdog() {
+        let mut scheduler = Scheduler::new();
+        let mut watchdog = GridlockWatchdog::new(Duration::minutes(10));
+        let now = Time::START_OF_DAY + Duration::hours(1);
+        watchdog.reported = true;
+
+        watchdog.handle_event(now, &car_moves(), &mut scheduler);
+        assert!(watchdog.scheduled);
+        assert!(!watchdog.reported);
+        assert_eq!(watchdog.last_progress, now);
+        assert_eq!(scheduler.peek_next_time(), Some(now + CHECK_FREQUENCY));
+
+        // More progress doesn't schedule a second check
+        let later = now + Duration::seconds(5.0);
+        watchdog.handle_event(later, &car_moves(), &mut scheduler);
+        assert_eq!(watchdog.last_progress, later);
+        assert_eq!(scheduler.peek_next_time(), Some(now + CHECK_FREQUENCY));
+    }
+
+    #[test]
+    fn test_idle_watchdog_stops_checking() {
+        let map = Map::almost_blank();
+        let mut opts = SimOptions::default();
+        opts.gridlock_watchdog = Some(Duration::minutes(10));
+        let mut sim = Sim::new(&map, opts);
+        sim.gridlock_watchdog
+            .as_mut()
+            .unwrap()
+            .wake(sim.time, &mut sim.scheduler);
+
+        // Nobody is around, so the check doesn't reschedule itself
+        assert_eq!(sim.check_for_gridlock(&map), None);
+        assert!(!sim.gridlock_watchdog.as_ref().unwrap().scheduled);
+    }
+}
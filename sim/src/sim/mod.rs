@@ -1,6 +1,6 @@
 // This file has a jumbled mess of queries, setup, and mutating methods.
 
-use std::collections::{BTreeSet, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 use anyhow::Result;
 use instant::Instant;
@@ -10,25 +10,38 @@ use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
 
 use abstio::{CityName, MapName};
-use abstutil::{prettyprint_usize, serialized_size_bytes, Timer};
+use abstutil::{MemoryReport, Timer};
 use geom::{Distance, Duration, Speed, Time};
 use map_model::{
     BuildingID, IntersectionID, LaneID, Map, ParkingLotID, Path, PathConstraints, PathRequest,
-    Position, TransitRoute, Traversable,
+    Position, RoadID, TransitRoute, Traversable,
 };
-use synthpop::OrigPersonID;
-
+use synthpop::{OrigPersonID, VehicleClass};
+
+use self::diff::CheckpointBase;
+pub(crate) use self::diff::CheckpointEntries;
+pub use self::diff::{AgentDiff, SimDiff};
+pub use self::gridlock::GridlockReport;
+use self::gridlock::GridlockWatchdog;
+use self::probe::AgentProbe;
+pub(crate) use self::probe::ProbeState;
+pub use self::probe::{AgentDecision, DecisionType};
 pub use self::queries::{AgentProperties, DelayCause};
 // TODO Super weird for both of these to wind up here
 pub use self::scenario::{count_parked_cars_per_bldg, rand_dist};
+use crate::analytics_spill::SpilledAnalytics;
 use crate::{
     AgentID, AlertLocation, Analytics, CarID, Command, CreateCar, DrivingSimState, Event,
-    IntersectionSimState, PandemicModel, ParkedCar, ParkingSim, ParkingSimState, ParkingSpot,
-    Person, PersonID, Router, Scheduler, SidewalkPOI, SidewalkSpot, StartTripArgs, TrafficRecorder,
-    TransitSimState, TripID, TripInfo, TripManager, TripPhaseType, Vehicle, VehicleSpec,
-    VehicleType, WalkingSimState, BUS_LENGTH, LIGHT_RAIL_LENGTH, MIN_CAR_LENGTH,
+    IntersectionSimState, ModelScheduler, PandemicModel, ParkedCar, ParkingSim, ParkingSimState,
+    ParkingSpot, Person, PersonID, Router, Scheduler, SecondaryModel, SidewalkPOI, SidewalkSpot,
+    StartTripArgs, TrafficRecorder, TransitSimState, TripID, TripInfo, TripManager, TripPhaseType,
+    Vehicle, VehicleSpec, VehicleType, WalkingSimState, Weather, BUS_LENGTH, LIGHT_RAIL_LENGTH,
+    MIN_CAR_LENGTH,
 };
 
+mod diff;
+mod gridlock;
+mod probe;
 mod queries;
 mod scenario;
 
@@ -44,10 +57,12 @@ pub struct Sim {
     intersections: IntersectionSimState,
     transit: TransitSimState,
     trips: TripManager,
+    /// Registered by callers; see SecondaryModel. Keyed by name.
     #[serde(skip_serializing, skip_deserializing)]
-    pandemic: Option<PandemicModel>,
+    models: BTreeMap<String, Box<dyn SecondaryModel>>,
     scheduler: Scheduler,
     time: Time,
+    weather: Option<Weather>,
 
     // These're needed to load from a savestate.
     pub(crate) map_name: MapName,
@@ -59,12 +74,22 @@ pub struct Sim {
     highlighted_people: Option<BTreeSet<PersonID>>,
 
     analytics: Analytics,
+    gridlock_watchdog: Option<GridlockWatchdog>,
     // This is created interactively, and there's no reason to preserve one for savestates.
     #[serde(skip_serializing, skip_deserializing)]
     recorder: Option<TrafficRecorder>,
 
     #[serde(skip_serializing, skip_deserializing)]
     alerts: AlertHandler,
+
+    /// The last full savestate written by save_checkpoint
+    #[serde(skip_serializing, skip_deserializing)]
+    checkpoint_base: Option<CheckpointBase>,
+
+    /// Decision logs for agents being traced. These're for debugging a live session, so they
+    /// aren't saved.
+    #[serde(skip_serializing, skip_deserializing)]
+    probe: AgentProbe,
 }
 
 pub(crate) struct Ctx<'a> {
@@ -105,6 +130,39 @@ pub struct SimOptions {
     /// red lights after starting.
     #[structopt(long)]
     pub dont_handle_uber_turns: bool,
+    /// Slow vehicles down in lanes narrower than they need, and prevent them from squeezing past
+    /// cyclists through narrow lanes. Otherwise, lane widths only affect rendering.
+    #[structopt(long)]
+    pub lane_widths_affect_driving: bool,
+    /// Drivers only notice free parking spots on the lane they're driving along, so they circle
+    /// around their destination until they find one. Otherwise, they know where the nearest free
+    /// spot is.
+    #[structopt(long)]
+    pub cruise_for_parking: bool,
+    /// Run a fleet of this many on-demand minibuses. Public transit trips between two buildings
+    /// book a ride with one of them, instead of using fixed bus routes.
+    #[structopt(long, default_value = "0")]
+    pub microtransit_fleet: usize,
+    /// How many riders each microtransit vehicle can carry at once.
+    #[structopt(long, default_value = "8")]
+    pub microtransit_capacity: usize,
+    /// Make cyclists prefer routes with gentler climbs. Multiplies the cost of uphill roads,
+    /// growing with the grade; 1.0 only accounts for cyclists slowing down on hills.
+    #[structopt(long, default_value = "1.0")]
+    pub bikes_avoid_hills: f64,
+    /// At traffic signals, vehicles making a permissive turn only start once the next vehicle
+    /// approaching on a conflicting, protected movement is at least this many seconds away.
+    #[structopt(long, default_value = "4.5")]
+    pub permissive_turn_critical_gap: f64,
+    /// Cyclists lock their bikes at racks imported from OSM, walking from the closest one with a
+    /// free spot. If there's none nearby, they park informally, which counts as unmet demand.
+    /// Otherwise, bikes are left right outside every building.
+    #[structopt(long)]
+    pub model_bike_parking: bool,
+    /// Simulate rain, snow, or a darker time of year, like "snow,winter". Fewer people walk or
+    /// cycle, especially after dark, and vehicles drive slower and fit fewer per lane.
+    #[structopt(long, parse(try_from_str = Weather::parse))]
+    pub weather: Option<Weather>,
     /// Enable an experimental SEIR pandemic model. This requires an RNG seed, which can be the
     /// same or different from the one used for the rest of the simulation.
     #[structopt(long, parse(try_from_str = parse_rng))]
@@ -127,6 +185,15 @@ pub struct SimOptions {
     /// quickly.
     #[structopt(long)]
     pub skip_analytics: bool,
+    /// If no vehicle moves onto a new lane or turn for this long while some agents are blocked,
+    /// raise an alert and save a diagnosis of the gridlock. For example, 0:10:00 for 10 minutes.
+    #[structopt(long, parse(try_from_str = Duration::parse))]
+    pub gridlock_watchdog: Option<Duration>,
+    /// For long runs, bound memory use by moving the trip log, delays at traffic signals, and
+    /// parking changes to disk after this much simulated time, repeatedly. For example, 1:00:00
+    /// for every hour. Dashboards read the files back when needed, which is slower.
+    #[structopt(long, parse(try_from_str = Duration::parse))]
+    pub spill_analytics: Option<Duration>,
 }
 
 impl SimOptions {
@@ -138,11 +205,21 @@ impl SimOptions {
             dont_recalc_lanechanging: false,
             dont_break_turn_conflict_cycles: false,
             dont_handle_uber_turns: false,
+            lane_widths_affect_driving: false,
+            cruise_for_parking: false,
+            microtransit_fleet: 0,
+            microtransit_capacity: 8,
+            bikes_avoid_hills: 1.0,
+            permissive_turn_critical_gap: 4.5,
+            model_bike_parking: false,
+            weather: None,
             enable_pandemic_model: None,
             alerts: AlertHandler::Print,
             infinite_parking: false,
             disable_turn_conflicts: false,
             skip_analytics: false,
+            gridlock_watchdog: None,
+            spill_analytics: None,
         }
     }
 }
@@ -206,16 +283,17 @@ impl Sim {
             opts.allow_block_the_box = true;
         }
 
-        Sim {
+        let mut sim = Sim {
             driving: DrivingSimState::new(map, &opts),
             parking: ParkingSimState::new(map, opts.infinite_parking, &mut timer),
             walking: WalkingSimState::new(),
             intersections: IntersectionSimState::new(map, &mut scheduler, &opts),
-            transit: TransitSimState::new(map),
-            trips: TripManager::new(),
-            pandemic: opts.enable_pandemic_model.map(PandemicModel::new),
+            transit: TransitSimState::new(map, &opts),
+            trips: TripManager::new(map, &opts),
+            models: BTreeMap::new(),
             scheduler,
             time: Time::START_OF_DAY,
+            weather: opts.weather,
 
             map_name: map.get_name().clone(),
             edits_name: map.get_edits().edits_name.clone(),
@@ -225,8 +303,24 @@ impl Sim {
             alerts: opts.alerts,
 
             analytics: Analytics::new(!opts.skip_analytics),
+            gridlock_watchdog: opts.gridlock_watchdog.map(GridlockWatchdog::new),
             recorder: None,
+            checkpoint_base: None,
+            probe: AgentProbe::default(),
+        };
+        if let Some(rng) = opts.enable_pandemic_model {
+            sim.register_model(Box::new(PandemicModel::new(rng)));
+        }
+        sim.update_parking_restrictions(map);
+        if let Some(period) = opts.spill_analytics {
+            sim.analytics.enable_spilling(SpilledAnalytics::new(
+                &sim.map_name,
+                &sim.edits_name,
+                &sim.run_name,
+                period,
+            ));
         }
+        sim
     }
 
     pub(crate) fn spawn_trips(
@@ -249,8 +343,11 @@ impl Sim {
             }
         }
 
-        if let Some(ref mut m) = self.pandemic {
-            m.initialize(self.trips.get_all_people(), &mut self.scheduler);
+        for m in self.models.values_mut() {
+            m.initialize(
+                self.trips.get_all_people(),
+                &mut ModelScheduler::new(&mut self.scheduler),
+            );
         }
 
         self.dispatch_events(Vec::new(), map);
@@ -287,6 +384,8 @@ impl Sim {
             vehicle_type: VehicleType::Car,
             length: MIN_CAR_LENGTH,
             max_speed: None,
+            class: Some(VehicleClass::Car),
+            disabled_permit: false,
         };
         let driving_lane = map.find_driving_lane_near_building(b);
 
@@ -334,6 +433,42 @@ impl Sim {
         }
     }
 
+    fn update_parking_restrictions(&mut self, map: &Map) {
+        let num_towed = self.parking.update_parking_restrictions(self.time, map);
+        if num_towed > 0 {
+            info!(
+                "At {}, towed {} cars away from restricted parking",
+                self.time,
+                prettyprint_usize(num_towed)
+            );
+        }
+
+        // Restrictions repeat daily. Find the next time any of them starts or ends.
+        let day = Duration::hours(24);
+        let midnight = Time::START_OF_DAY
+            + Duration::seconds(
+                (self.time.inner_seconds() / day.inner_seconds()).floor() * day.inner_seconds(),
+            );
+        let mut next: Option<Time> = None;
+        for r in map.all_roads() {
+            for restriction in &r.parking_restrictions {
+                for t in [restriction.start, restriction.end] {
+                    let offset = t - Time::START_OF_DAY;
+                    for candidate in [midnight + offset, midnight + day + offset] {
+                        if candidate > self.time && next.map(|n| candidate < n).unwrap_or(true) {
+                            next = Some(candidate);
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(t) = next {
+            self.scheduler.update(t, Command::UpdateParkingRestrictions);
+        } else {
+            self.scheduler.cancel(Command::UpdateParkingRestrictions);
+        }
+    }
+
     fn start_bus(&mut self, route: &TransitRoute, map: &Map) {
         // Spawn one bus for the first leg.
         let path = self.transit.create_empty_route(route, map);
@@ -349,6 +484,8 @@ impl Sim {
             vehicle_type,
             length,
             max_speed: None,
+            class: None,
+            disabled_permit: false,
         }
         .make(
             CarID {
@@ -446,6 +583,9 @@ impl Sim {
         match cmd {
             Command::StartTrip(id, args) => {
                 self.trips.start_trip(self.time, id, args, &mut ctx);
+                if let Some(ref mut w) = self.gridlock_watchdog {
+                    w.wake(self.time, ctx.scheduler);
+                }
             }
             Command::SpawnCar(create_car, retry_if_no_room) => {
                 // If this SpawnCar is being retried and the map was live-edited since the first
@@ -612,6 +752,7 @@ impl Sim {
                     &mut ctx,
                     &mut self.trips,
                     &mut self.transit,
+                    &self.driving,
                 );
             }
             Command::UpdateIntersection(i) => {
@@ -625,15 +766,41 @@ impl Sim {
                     halt = true;
                 }
             }
-            Command::Pandemic(cmd) => {
-                self.pandemic
-                    .as_mut()
-                    .unwrap()
-                    .handle_cmd(self.time, cmd, &mut self.scheduler);
+            Command::Model(cmd) => {
+                let mut scheduler = ModelScheduler::new(&mut self.scheduler);
+                if let Some(m) = self.models.get_mut(&cmd.model) {
+                    m.handle_cmd(self.time, cmd, &mut scheduler);
+                } else {
+                    warn!(
+                        "No secondary model named {} is registered, dropping {:?}",
+                        cmd.model, cmd
+                    );
+                }
             }
             Command::StartBus(r, _) => {
                 self.start_bus(map.get_tr(r), map);
             }
+            Command::UpdateParkingRestrictions => {
+                self.update_parking_restrictions(map);
+            }
+            Command::CheckForGridlock => {
+                events.extend(self.check_for_gridlock(map));
+            }
+            Command::RequestMicrotransit(trip, person, from, to) => {
+                self.transit.request_microtransit(
+                    self.time,
+                    trip,
+                    person,
+                    from,
+                    to,
+                    &mut self.trips,
+                    &mut ctx,
+                );
+            }
+            Command::DispatchMicrotransit => {
+                self.transit
+                    .dispatch_microtransit(self.time, &mut self.trips, &mut ctx);
+            }
         }
 
         // Record events at precisely the time they occur.
@@ -650,15 +817,28 @@ impl Sim {
         events.extend(self.intersections.collect_events());
         events.extend(self.parking.collect_events());
         for ev in events {
-            if let Some(ref mut m) = self.pandemic {
-                m.handle_event(self.time, &ev, &mut self.scheduler);
+            for m in self.models.values_mut() {
+                m.handle_event(
+                    self.time,
+                    &ev,
+                    &mut ModelScheduler::new(&mut self.scheduler),
+                );
             }
             if let Some(ref mut r) = self.recorder {
                 r.handle_event(self.time, &ev, map, &self.driving);
             }
+            if let Some(ref mut w) = self.gridlock_watchdog {
+                w.handle_event(self.time, &ev, &mut self.scheduler);
+            }
+            self.probe.handle_event(self.time, &ev);
 
             self.analytics.event(ev, self.time, map);
         }
+        self.analytics.maybe_spill(self.time);
+
+        if !self.probe.is_empty() {
+            self.update_probe();
+        }
     }
 
     pub fn timed_step(
@@ -780,42 +960,41 @@ impl Sim {
     pub fn save(&mut self) -> String {
         if false {
             println!("sim savestate breakdown:");
-            println!(
-                "- driving: {} bytes",
-                prettyprint_usize(serialized_size_bytes(&self.driving))
-            );
-            println!(
-                "- parking: {} bytes",
-                prettyprint_usize(serialized_size_bytes(&self.parking))
-            );
-            println!(
-                "- walking: {} bytes",
-                prettyprint_usize(serialized_size_bytes(&self.walking))
-            );
-            println!(
-                "- intersections: {} bytes",
-                prettyprint_usize(serialized_size_bytes(&self.intersections))
-            );
-            println!(
-                "- transit: {} bytes",
-                prettyprint_usize(serialized_size_bytes(&self.transit))
-            );
-            println!(
-                "- trips: {} bytes",
-                prettyprint_usize(serialized_size_bytes(&self.trips))
-            );
-            println!(
-                "- scheduler: {} bytes",
-                prettyprint_usize(serialized_size_bytes(&self.scheduler))
-            );
+            for line in self.memory_report().describe() {
+                println!("{}", line);
+            }
         }
 
+        // Savestates have to be self-contained
+        self.analytics.unspill();
+
         let path = self.save_path(self.time);
         abstio::write_binary(path.clone(), self);
 
         path
     }
 
+    /// Estimates how much memory agent state and analytics use. Combine with
+    /// `Map::memory_report` for the full picture.
+    pub fn memory_report(&self) -> MemoryReport {
+        let mut report = MemoryReport::new();
+        let num_agents = self.num_active_agents();
+        report.measure("agents", "driving", num_agents, &self.driving);
+        report.measure("agents", "parking", 1, &self.parking);
+        report.measure("agents", "walking", num_agents, &self.walking);
+        report.measure("agents", "intersections", 1, &self.intersections);
+        report.measure("agents", "transit", 1, &self.transit);
+        report.measure(
+            "agents",
+            "people and trips",
+            self.trips.get_all_people().len(),
+            &self.trips,
+        );
+        report.measure("agents", "scheduler", 1, &self.scheduler);
+        report.measure("analytics", "analytics", 1, &self.analytics);
+        report
+    }
+
     pub fn find_previous_savestate(&self, base_time: Time) -> Option<String> {
         abstio::find_prev_file(self.save_path(base_time))
     }
@@ -824,9 +1003,36 @@ impl Sim {
         abstio::find_next_file(self.save_path(base_time))
     }
 
+    /// Loads either a full savestate or an incremental checkpoint.
     pub fn load_savestate(path: String, timer: &mut Timer) -> Result<Sim> {
+        if Sim::is_checkpoint(&path) {
+            return Sim::load_checkpoint(path, timer);
+        }
         abstio::maybe_read_binary(path, timer)
     }
+
+    /// True if this fresh `Sim` can resume from a savestate. Some options shape the state itself,
+    /// so they can't change partway through: the weather affects who travels how, and the others
+    /// change where vehicles park or which vehicles exist.
+    pub(crate) fn can_resume_from(&self, savestate: &Sim) -> bool {
+        self.weather == savestate.weather
+            && self.parking.is_infinite() == savestate.parking.is_infinite()
+            && self.trips.models_bike_parking() == savestate.trips.models_bike_parking()
+            && self.transit.same_microtransit_fleet(&savestate.transit)
+    }
+
+    /// Replace this fresh `Sim` with a savestate, keeping the options this one was created with.
+    pub(crate) fn resume_from(&mut self, savestate: Sim) {
+        let fresh = std::mem::replace(self, savestate);
+        self.driving.keep_options_from(&fresh.driving);
+        self.intersections.keep_options_from(&fresh.intersections);
+        self.trips.keep_options_from(&fresh.trips);
+        self.analytics.keep_options_from(fresh.analytics);
+        self.replace_gridlock_watchdog(fresh.gridlock_watchdog);
+        self.run_name = fresh.run_name;
+        self.models = fresh.models;
+        self.alerts = fresh.alerts;
+    }
 }
 
 // Live edits
@@ -887,6 +1093,8 @@ impl Sim {
 
         self.driving.handle_live_edits(map);
         self.intersections.handle_live_edits(map);
+        // The edits might've changed when parking is restricted
+        self.update_parking_restrictions(map);
 
         (num_trips_cancelled, num_parked_cars)
     }
@@ -910,20 +1118,32 @@ impl Sim {
                     closed_intersections.insert(*i);
                 }
             }
+            // Pedestrians crossing mid-block need the crossing to stay put
+            let edited_crossings: HashSet<RoadID> = map
+                .get_edits()
+                .original_roads
+                .iter()
+                .filter(|(r, orig)| map.get_r(**r).crossings != orig.crossings)
+                .map(|(r, _)| *r)
+                .collect();
             for (a, trip) in self.trips.active_agents_and_trips() {
                 if let Some(path) = self.get_path(*a) {
-                    if path
-                        .get_steps()
-                        .iter()
-                        .any(|step| match step.as_traversable() {
-                            Traversable::Lane(l) => edited_lanes.contains(&l),
-                            Traversable::Turn(t) => {
-                                closed_intersections.contains(&t.parent)
-                                    || edited_lanes.contains(&t.src)
-                                    || edited_lanes.contains(&t.dst)
+                    let steps = path.get_steps();
+                    if steps.iter().any(|step| match step.as_traversable() {
+                        Traversable::Lane(l) => edited_lanes.contains(&l),
+                        Traversable::Turn(t) => {
+                            closed_intersections.contains(&t.parent)
+                                || edited_lanes.contains(&t.src)
+                                || edited_lanes.contains(&t.dst)
+                        }
+                    }) || steps.iter().zip(steps.iter().skip(1)).any(|(s1, s2)| {
+                        match (s1.as_traversable(), s2.as_traversable()) {
+                            (Traversable::Lane(l1), Traversable::Lane(l2)) => {
+                                l1.road == l2.road && edited_crossings.contains(&l1.road)
                             }
-                        })
-                    {
+                            _ => false,
+                        }
+                    }) {
                         affected.insert((*a, *trip));
                     }
                 }
@@ -1009,6 +1229,37 @@ impl Sim {
     }
 }
 
+// Secondary models
+impl Sim {
+    /// Run a model alongside the simulation from now on. Panics if a model with the same name is
+    /// already registered.
+    pub fn register_model(&mut self, model: Box<dyn SecondaryModel>) {
+        let name = model.name().to_string();
+        assert!(
+            !self.models.contains_key(&name),
+            "A secondary model named {} is already registered",
+            name
+        );
+        self.models.insert(name, model);
+    }
+
+    pub fn unregister_model(&mut self, name: &str) -> Option<Box<dyn SecondaryModel>> {
+        self.models.remove(name)
+    }
+
+    /// Use `downcast_ref` on the result to access the concrete model
+    pub fn get_model(&self, name: &str) -> Option<&dyn SecondaryModel> {
+        self.models.get(name).map(|m| m.as_ref())
+    }
+
+    pub fn get_model_mut(&mut self, name: &str) -> Option<&mut dyn SecondaryModel> {
+        match self.models.get_mut(name) {
+            Some(m) => Some(m.as_mut()),
+            None => None,
+        }
+    }
+}
+
 // Recording traffic
 impl Sim {
     pub fn record_traffic_for(&mut self, intersections: BTreeSet<IntersectionID>) {
@@ -0,0 +1,284 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use geom::{Speed, Time};
+use map_model::{PathStep, Traversable};
+
+use crate::{AgentID, DistanceInterval, Event, PersonID, Sim, TimeInterval};
+
+/// How many upcoming steps of a path to watch for changes
+const UPCOMING_STEPS: usize = 5;
+/// Ignore speed changes smaller than this; they're just rounding
+const SPEED_EPSILON: f64 = 0.1;
+
+/// Records every decision a few chosen agents make, to help answer "why did this car do that?"
+/// without adding print statements to the simulation. Tracing is opt-in, since it checks each
+/// traced agent after every step.
+#[derive(Clone, Default)]
+pub(crate) struct AgentProbe {
+    traces: BTreeMap<AgentID, AgentTrace>,
+}
+
+#[derive(Clone)]
+struct AgentTrace {
+    person: Option<PersonID>,
+    /// Keep recording? Finished traces stay around to be viewed or exported.
+    active: bool,
+    last_state: Option<ProbeState>,
+    decisions: Vec<AgentDecision>,
+}
+
+/// What an agent is doing at one moment. The probe notices decisions by comparing these.
+#[derive(Clone, PartialEq)]
+pub(crate) struct ProbeState {
+    pub on: Traversable,
+    /// Like "moving" or "queued"
+    pub activity: String,
+    /// Only known while the agent is moving
+    pub speed: Option<Speed>,
+    /// The next few steps of the path, starting with the current one
+    pub upcoming: Vec<PathStep>,
+}
+
+impl ProbeState {
+    pub fn new(
+        on: Traversable,
+        activity: String,
+        speed: Option<Speed>,
+        steps: impl Iterator<Item = PathStep>,
+    ) -> ProbeState {
+        ProbeState {
+            on,
+            activity,
+            speed,
+            upcoming: steps.take(UPCOMING_STEPS).collect(),
+        }
+    }
+
+    /// The speed of an agent covering a distance interval over a time interval
+    pub(crate) fn speed(time_int: &TimeInterval, dist_int: &DistanceInterval) -> Option<Speed> {
+        let dt = time_int.end - time_int.start;
+        if dt.inner_seconds() <= 0.0 {
+            return None;
+        }
+        Some(Speed::meters_per_second(
+            dist_int.length().inner_meters() / dt.inner_seconds(),
+        ))
+    }
+}
+
+/// Something a traced agent did.
+#[derive(Clone, Debug, Serialize)]
+pub struct AgentDecision {
+    pub time: Time,
+    pub decision_type: DecisionType,
+    pub description: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum DecisionType {
+    /// The planned path changed, other than by following it
+    Route,
+    /// Entered a lane or turn
+    EnterQueue,
+    /// Waited for somebody or something at an intersection
+    Yield,
+    Speed,
+    /// Anything else, like starting to park
+    Activity,
+}
+
+impl DecisionType {
+    pub fn describe(self) -> &'static str {
+        match self {
+            DecisionType::Route => "route",
+            DecisionType::EnterQueue => "enter",
+            DecisionType::Yield => "yield",
+            DecisionType::Speed => "speed",
+            DecisionType::Activity => "activity",
+        }
+    }
+}
+
+impl AgentTrace {
+    fn record(&mut self, time: Time, decision_type: DecisionType, description: String) {
+        self.decisions.push(AgentDecision {
+            time,
+            decision_type,
+            description,
+        });
+    }
+
+    fn compare(&mut self, time: Time, state: ProbeState) {
+        let last = match self.last_state.take() {
+            Some(last) => last,
+            None => {
+                self.record(
+                    time,
+                    DecisionType::Activity,
+                    format!("appeared on {:?}, {}", state.on, state.activity),
+                );
+                self.last_state = Some(state);
+                return;
+            }
+        };
+
+        if last.on != state.on {
+            self.record(
+                time,
+                DecisionType::EnterQueue,
+                format!("entered {:?}", state.on),
+            );
+        }
+        if rerouted(&last.upcoming, &state.upcoming) {
+            self.record(
+                time,
+                DecisionType::Route,
+                format!("now planning to take {:?}", state.upcoming),
+            );
+        }
+        if last.activity != state.activity {
+            let decision_type =
+                if state.activity.starts_with("waiting") || state.activity.starts_with("queued") {
+                    DecisionType::Yield
+                } else {
+                    DecisionType::Activity
+                };
+            self.record(time, decision_type, state.activity.clone());
+        }
+        if let Some(speed) = state.speed {
+            let changed = match last.speed {
+                Some(prev) => {
+                    (speed.inner_meters_per_second() - prev.inner_meters_per_second()).abs()
+                        > SPEED_EPSILON
+                }
+                None => true,
+            };
+            if changed {
+                self.record(
+                    time,
+                    DecisionType::Speed,
+                    format!("moving at {:.1} m/s", speed.inner_meters_per_second()),
+                );
+            }
+        }
+        self.last_state = Some(state);
+    }
+}
+
+/// Did the path change, besides advancing along it?
+fn rerouted(old: &[PathStep], new: &[PathStep]) -> bool {
+    let first = match new.first() {
+        Some(first) => first,
+        None => {
+            return false;
+        }
+    };
+    match old.iter().position(|step| step == first) {
+        Some(idx) => old[idx..].iter().zip(new).any(|(a, b)| a != b),
+        // The agent might've moved past everything we were watching in one step, or changed
+        // lanes. Either way, the new steps are worth recording.
+        None => !old.is_empty(),
+    }
+}
+
+impl AgentProbe {
+    pub fn is_empty(&self) -> bool {
+        self.traces.values().all(|trace| !trace.active)
+    }
+
+    pub fn handle_event(&mut self, now: Time, ev: &Event) {
+        if let Event::IntersectionDelayMeasured(_, turn, agent, delay) = ev {
+            if let Some(trace) = self.traces.get_mut(agent) {
+                if trace.active {
+                    trace.record(
+                        now,
+                        DecisionType::Yield,
+                        format!("waited {} before starting {}", delay, turn),
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl Sim {
+    /// Start recording every decision this agent makes. Buses and pedestrians can be traced, but
+    /// not transit passengers.
+    pub fn start_tracing_agent(&mut self, id: AgentID) {
+        let person = self.agent_to_person(id);
+        let trace = self.probe.traces.entry(id).or_insert_with(|| AgentTrace {
+            person,
+            active: true,
+            last_state: None,
+            decisions: Vec::new(),
+        });
+        trace.active = true;
+        self.update_probe();
+    }
+
+    /// Stop recording an agent, but keep everything recorded so far.
+    pub fn stop_tracing_agent(&mut self, id: AgentID) {
+        if let Some(trace) = self.probe.traces.get_mut(&id) {
+            trace.active = false;
+        }
+    }
+
+    pub fn is_tracing_agent(&self, id: AgentID) -> bool {
+        self.probe
+            .traces
+            .get(&id)
+            .map(|trace| trace.active)
+            .unwrap_or(false)
+    }
+
+    /// Everything a traced agent has done, in order.
+    pub fn get_agent_trace(&self, id: AgentID) -> Option<&Vec<AgentDecision>> {
+        self.probe.traces.get(&id).map(|trace| &trace.decisions)
+    }
+
+    /// All agents that have been traced for a person, including ones that have already finished
+    /// their trip.
+    pub fn traced_agents_for_person(&self, person: PersonID) -> Vec<AgentID> {
+        self.probe
+            .traces
+            .iter()
+            .filter(|(_, trace)| trace.person == Some(person))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Everything a traced agent has done, as JSON.
+    pub fn agent_trace_json(&self, id: AgentID) -> Option<String> {
+        self.get_agent_trace(id).map(abstutil::to_json)
+    }
+
+    pub(crate) fn update_probe(&mut self) {
+        let now = self.time;
+        for (id, trace) in &mut self.probe.traces {
+            if !trace.active {
+                continue;
+            }
+            let state = match id {
+                AgentID::Car(car) => self.driving.probe_car(*car),
+                AgentID::Pedestrian(ped) => self.walking.probe_ped(*ped),
+                AgentID::BusPassenger(_, _) => None,
+            };
+            match state {
+                Some(state) => trace.compare(now, state),
+                None => {
+                    // The agent finished this leg of its trip or left the map. A car can be
+                    // traced again when its owner drives it later.
+                    if trace.last_state.take().is_some() {
+                        trace.record(
+                            now,
+                            DecisionType::Activity,
+                            "left the simulation".to_string(),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -14,9 +14,10 @@ use synthpop::{OrigPersonID, Scenario, TripMode};
 
 use crate::analytics::SlidingWindow;
 use crate::{
-    AgentID, AgentType, Analytics, CarID, CommutersVehiclesCounts, DrawCarInput, DrawPedCrowdInput,
-    DrawPedestrianInput, PandemicModel, ParkedCar, ParkingSim, PedestrianID, Person, PersonID,
-    PersonState, Sim, TripEndpoint, TripID, TripInfo, TripResult, UnzoomedAgent, VehicleType,
+    pandemic, AgentID, AgentType, Analytics, CarID, CommutersVehiclesCounts, DrawCarInput,
+    DrawPedCrowdInput, DrawPedestrianInput, PandemicModel, ParkedCar, ParkingSim, PedestrianID,
+    Person, PersonID, PersonState, Sim, TripDelayBreakdown, TripEndpoint, TripID, TripInfo,
+    TripResult, UnzoomedAgent, VehicleType, Weather,
 };
 
 // TODO Many of these just delegate to an inner piece. This is unorganized and hard to maintain.
@@ -97,14 +98,19 @@ impl Sim {
     }
 
     pub fn num_transit_passengers(&self, car: CarID) -> usize {
-        self.transit.get_passengers(car).len()
+        self.transit.num_passengers(car)
+    }
+
+    /// How many people have booked a microtransit ride and haven't been picked up yet
+    pub fn num_waiting_for_microtransit(&self) -> usize {
+        self.transit.num_waiting_for_microtransit()
     }
 
     pub fn bus_route_id(&self, maybe_bus: CarID) -> Option<TransitRouteID> {
         if maybe_bus.vehicle_type == VehicleType::Bus
             || maybe_bus.vehicle_type == VehicleType::Train
         {
-            Some(self.transit.bus_route(maybe_bus))
+            self.transit.bus_route(maybe_bus)
         } else {
             None
         }
@@ -313,6 +319,20 @@ impl Sim {
         &self.analytics
     }
 
+    /// For a finished trip, break down where time was lost compared to a free-flow estimate of the
+    /// same trip.
+    pub fn explain_trip_delay(&self, map: &Map, id: TripID) -> Result<TripDelayBreakdown> {
+        let (total_time, _, _) = self
+            .finished_trip_details(id)
+            .ok_or_else(|| anyhow!("{} hasn't finished", id))?;
+        let free_flow_time = self.get_trip_time_lower_bound(map, id)?;
+        Ok(TripDelayBreakdown::new(
+            total_time,
+            free_flow_time,
+            self.analytics.trip_delay_causes(id),
+        ))
+    }
+
     /// For intersections with an agent waiting beyond some threshold, return when they started
     /// waiting. Sorted by earliest waiting (likely the root cause of gridlock).
     pub fn delayed_intersections(&self, threshold: Duration) -> Vec<(IntersectionID, Time)> {
@@ -325,7 +345,8 @@ impl Sim {
     }
 
     pub fn get_pandemic_model(&self) -> Option<&PandemicModel> {
-        self.pandemic.as_ref()
+        self.get_model(pandemic::MODEL_NAME)?
+            .downcast_ref::<PandemicModel>()
     }
 
     pub fn get_end_of_day(&self) -> Time {
@@ -394,6 +415,32 @@ impl Sim {
         self.parking.is_infinite()
     }
 
+    /// Do drivers have to circle around to find a free parking spot?
+    pub fn cruising_for_parking(&self) -> bool {
+        self.driving.cruising_for_parking()
+    }
+
+    /// The weather being simulated, if any. Without this, it's a dry day with no effects.
+    pub fn get_weather(&self) -> Option<Weather> {
+        self.weather
+    }
+
+    /// Is it currently dark? Always false without `SimOptions::weather`.
+    pub fn is_dark(&self) -> bool {
+        self.weather.map(|w| w.is_dark(self.time)).unwrap_or(false)
+    }
+
+    /// Do cyclists have to find a bike rack with free space?
+    pub fn modeling_bike_parking(&self) -> bool {
+        self.trips.all_bike_racks().is_some()
+    }
+
+    /// Every bike rack, keyed by the building it's near, with its (occupied, capacity). Empty
+    /// unless `SimOptions::model_bike_parking` is on.
+    pub fn all_bike_racks(&self) -> BTreeMap<BuildingID, (usize, usize)> {
+        self.trips.all_bike_racks().unwrap_or_default()
+    }
+
     pub fn all_waiting_people(&self) -> BTreeMap<PersonID, Duration> {
         let mut delays = BTreeMap::new();
         self.walking.all_waiting_people(self.time, &mut delays);
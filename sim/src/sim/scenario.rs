@@ -5,16 +5,19 @@ use rand::{Rng, SeedableRng};
 use rand_xorshift::XorShiftRng;
 
 use abstutil::{prettyprint_usize, Counter, Timer};
-use geom::{Distance, Speed};
+use geom::{Distance, Duration, Speed, Time};
 use map_model::{BuildingID, Map, OffstreetParking, RoadID};
 use synthpop::make::fork_rng;
-use synthpop::{PersonSpec, Scenario, TripEndpoint, TripMode};
+use synthpop::{FleetMix, PersonSpec, Scenario, TripEndpoint, TripMode, VehicleClass};
 
 use crate::{
     ParkingSpot, Sim, StartTripArgs, TripInfo, Vehicle, VehicleSpec, VehicleType, BIKE_LENGTH,
-    MAX_CAR_LENGTH, MIN_CAR_LENGTH,
 };
 
+/// When there's no savestate to warm start from, how much traffic to simulate before the start
+/// time, so queues and congestion have a chance to build up
+const WARM_START_PERIOD: Duration = Duration::const_seconds(3600.0);
+
 impl Sim {
     pub fn instantiate(
         &mut self,
@@ -26,6 +29,67 @@ impl Sim {
         self.instantiate_without_retries(scenario, map, rng, true, timer);
     }
 
+    /// Start the simulation partway through the day, without simulating everything before then.
+    /// This must be called on a fresh `Sim`.
+    ///
+    /// If there's a savestate for this scenario (with the same edits) from `start` or earlier,
+    /// resume from it, keeping the options this `Sim` was created with. Savestates made with
+    /// different weather, parking, or microtransit options can't be used. Otherwise, approximate
+    /// the traffic at `start` from demand: skip trips departing more than `WARM_START_PERIOD`
+    /// earlier, and only simulate that last period. In that case, people and trips are numbered
+    /// differently than in a full run, so comparisons to prebaked results don't line up. The
+    /// approximation isn't saved, so it can't be mistaken for a real savestate later.
+    pub fn warm_start(
+        &mut self,
+        scenario: &Scenario,
+        map: &Map,
+        rng: &mut XorShiftRng,
+        start: Time,
+        timer: &mut Timer,
+    ) {
+        self.set_run_name(scenario.scenario_name.clone());
+
+        let exact = self.save_path(start);
+        let cached = if abstio::file_exists(&exact) {
+            Some(exact)
+        } else {
+            self.find_previous_savestate(start)
+        };
+        if let Some(path) = cached {
+            match Sim::load_savestate(path.clone(), timer) {
+                Ok(sim) if self.can_resume_from(&sim) => {
+                    info!("Warm starting from {}", path);
+                    self.resume_from(sim);
+                    self.timed_step(map, start - self.time, &mut None, timer);
+                    return;
+                }
+                Ok(_) => {
+                    warn!(
+                        "Savestate {} was simulated with different weather, parking, or \
+                         microtransit options",
+                        path
+                    );
+                }
+                Err(err) => {
+                    warn!("Couldn't load savestate {}: {}", path, err);
+                }
+            }
+        }
+
+        let warmup_start = if start - Time::START_OF_DAY > WARM_START_PERIOD {
+            start - WARM_START_PERIOD
+        } else {
+            Time::START_OF_DAY
+        };
+        info!(
+            "No savestate for {} at or before {}, so approximating traffic from trips after {}",
+            scenario.scenario_name, start, warmup_start
+        );
+        let scenario = scenario.clone().remove_trips_before(warmup_start);
+        self.instantiate(&scenario, map, rng, timer);
+        self.timed_step(map, start - self.time, &mut None, timer);
+    }
+
     /// If retry_if_no_room is false, any vehicles that fail to spawn because of something else in
     /// the way will just wind up as cancelled trips.
     pub fn instantiate_without_retries(
@@ -39,6 +103,16 @@ impl Sim {
         // Any case where map edits could change the calls to the RNG, we have to fork.
         self.set_run_name(scenario.scenario_name.clone());
 
+        let adjusted;
+        let scenario = if let Some(weather) = self.weather {
+            let mut copy = scenario.clone();
+            weather.adjust_mode_shares(&mut copy);
+            adjusted = copy;
+            &adjusted
+        } else {
+            scenario
+        };
+
         timer.start(format!("Instantiating {}", scenario.scenario_name));
 
         if let Some(ref routes) = scenario.only_seed_buses {
@@ -54,6 +128,7 @@ impl Sim {
             }
         }
 
+        let use_microtransit = self.transit.microtransit_enabled();
         timer.start_iter("trips for People", scenario.people.len());
         let mut parked_cars: Vec<(Vehicle, BuildingID)> = Vec::new();
         let mut schedule_trips = Vec::new();
@@ -65,8 +140,12 @@ impl Sim {
             }
 
             let (vehicle_specs, cars_initially_parked_at, vehicle_foreach_trip) =
-                get_vehicles(p, rng);
-            let person = self.new_person(p.orig_id, rand_ped_speed(rng), vehicle_specs);
+                get_vehicles(p, &scenario.fleet_mix, rng);
+            let mut ped_speed = rand_ped_speed(rng);
+            if let Some(weather) = self.weather {
+                ped_speed = weather.walking_speed_factor() * ped_speed;
+            }
+            let person = self.new_person(p.orig_id, ped_speed, vehicle_specs);
             for (idx, b) in cars_initially_parked_at {
                 parked_cars.push((person.vehicles[idx].clone(), b));
             }
@@ -89,6 +168,7 @@ impl Sim {
                     StartTripArgs {
                         retry_if_no_room,
                         use_vehicle: maybe_idx.map(|idx| person.vehicles[idx].id),
+                        use_microtransit,
                     },
                 ));
             }
@@ -105,6 +185,7 @@ impl Sim {
 
 fn get_vehicles(
     person: &PersonSpec,
+    fleet_mix: &FleetMix,
     rng: &mut XorShiftRng,
 ) -> (
     Vec<VehicleSpec>,
@@ -146,7 +227,10 @@ fn get_vehicles(
                 } else {
                     // Need a new car, starting in the right spot
                     let idx = vehicle_specs.len();
-                    vehicle_specs.push(rand_car(rng));
+                    let class = fleet_mix.pick(rng);
+                    let mut spec = rand_car(class, rng);
+                    spec.disabled_permit = fleet_mix.pick_disabled_permit(rng);
+                    vehicle_specs.push(spec);
                     if let Some(b) = need_parked_at {
                         cars_initially_parked_at.push((idx, b));
                     }
@@ -188,12 +272,15 @@ fn get_vehicles(
     )
 }
 
-fn rand_car(rng: &mut XorShiftRng) -> VehicleSpec {
-    let length = rand_dist(rng, MIN_CAR_LENGTH, MAX_CAR_LENGTH);
+fn rand_car(class: VehicleClass, rng: &mut XorShiftRng) -> VehicleSpec {
+    let (min, max) = class.length_range();
+    let length = rand_dist(rng, min, max);
     VehicleSpec {
         vehicle_type: VehicleType::Car,
         length,
-        max_speed: None,
+        max_speed: class.max_speed(),
+        class: Some(class),
+        disabled_permit: false,
     }
 }
 
@@ -207,6 +294,8 @@ fn rand_bike(rng: &mut XorShiftRng) -> VehicleSpec {
         vehicle_type: VehicleType::Bike,
         length: BIKE_LENGTH,
         max_speed,
+        class: None,
+        disabled_permit: false,
     }
 }
 
@@ -356,7 +445,7 @@ pub fn count_parked_cars_per_bldg(scenario: &Scenario) -> Counter<BuildingID> {
     // Pass in a dummy RNG
     let mut rng = XorShiftRng::seed_from_u64(0);
     for p in &scenario.people {
-        let (_, cars_initially_parked_at, _) = get_vehicles(p, &mut rng);
+        let (_, cars_initially_parked_at, _) = get_vehicles(p, &scenario.fleet_mix, &mut rng);
         for (_, b) in cars_initially_parked_at {
             per_bldg.inc(b);
         }
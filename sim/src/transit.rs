@@ -4,12 +4,13 @@ use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_btreemap, serialize_btreemap};
 use geom::Time;
-use map_model::{Map, Path, TransitRoute, TransitRouteID, TransitStopID};
+use map_model::{BuildingID, Map, Path, TransitRoute, TransitRouteID, TransitStopID};
 
+use crate::microtransit::MicrotransitSimState;
 use crate::sim::Ctx;
 use crate::{
-    AgentID, CarID, DrivingSimState, Event, PedestrianID, PersonID, Router, TripID, TripManager,
-    TripPhaseType, UnzoomedAgent, VehicleType, WalkingSimState,
+    AgentID, CarID, DrivingSimState, Event, PedestrianID, PersonID, Router, SimOptions, TripID,
+    TripManager, TripPhaseType, UnzoomedAgent, VehicleType, WalkingSimState,
 };
 
 // These index stops along a route, not stops along a single sidewalk.
@@ -63,12 +64,20 @@ pub(crate) struct TransitSimState {
     )]
     peds_waiting:
         BTreeMap<TransitStopID, Vec<(PedestrianID, TransitRouteID, Option<TransitStopID>, Time)>>,
+    /// On-demand vehicles without a route are handled separately
+    microtransit: MicrotransitSimState,
+    /// How much has each person paid for rides so far, for applying daily fare caps
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    fares_paid: BTreeMap<PersonID, usize>,
 
     events: Vec<Event>,
 }
 
 impl TransitSimState {
-    pub fn new(map: &Map) -> TransitSimState {
+    pub fn new(map: &Map, opts: &SimOptions) -> TransitSimState {
         // Keep this filled out always so get_passengers can return &Vec without a hassle
         let mut peds_waiting = BTreeMap::new();
         for ts in map.all_transit_stops().keys() {
@@ -79,6 +88,12 @@ impl TransitSimState {
             buses: BTreeMap::new(),
             routes: BTreeMap::new(),
             peds_waiting,
+            microtransit: MicrotransitSimState::new(
+                map,
+                opts.microtransit_fleet,
+                opts.microtransit_capacity,
+            ),
+            fares_paid: BTreeMap::new(),
             events: Vec::new(),
         }
     }
@@ -131,6 +146,12 @@ impl TransitSimState {
         walking: &mut WalkingSimState,
         ctx: &mut Ctx,
     ) -> bool {
+        if self.microtransit.owns(id) {
+            return self
+                .microtransit
+                .vehicle_arrived(now, id, trips, ctx, &mut self.events);
+        }
+
         let bus = self.buses.get_mut(&id).unwrap();
         match bus.state {
             BusState::DrivingToStop(stop_idx) => {
@@ -173,6 +194,14 @@ impl TransitSimState {
                             stop1,
                             now - started_waiting,
                         ));
+                        pay_fare(
+                            &mut self.fares_paid,
+                            &mut self.events,
+                            person,
+                            ctx.map.get_tr(route),
+                            stop1,
+                            maybe_stop2,
+                        );
                         // TODO Recording the PathRequest for the passenger is actually hard. We
                         // don't want to route directly between their first and last stop, because
                         // there might be a much shorter path there. Should we record a leg per leg
@@ -214,7 +243,20 @@ impl TransitSimState {
         }
     }
 
-    pub fn bus_departed_from_stop(&mut self, id: CarID, _: &Map) -> Router {
+    /// If None, the vehicle has nowhere else to go and should vanish.
+    pub fn bus_departed_from_stop(
+        &mut self,
+        now: Time,
+        id: CarID,
+        trips: &mut TripManager,
+        ctx: &mut Ctx,
+    ) -> Option<Router> {
+        if self.microtransit.owns(id) {
+            return self
+                .microtransit
+                .vehicle_departed(now, id, trips, ctx, &mut self.events);
+        }
+
         let bus = self.buses.get_mut(&id).unwrap();
         let route = self.routes.get_mut(&bus.route).unwrap();
         match bus.state {
@@ -230,7 +272,10 @@ impl TransitSimState {
                 } else {
                     bus.state = BusState::DrivingToStop(stop_idx + 1);
                 }
-                Router::follow_bus_route(id, route.paths[stop_idx + 1].clone())
+                Some(Router::follow_bus_route(
+                    id,
+                    route.paths[stop_idx + 1].clone(),
+                ))
             }
             BusState::DrivingToStop(_) | BusState::DrivingOffMap | BusState::Finished => {
                 unreachable!()
@@ -248,7 +293,7 @@ impl TransitSimState {
         stop1: TransitStopID,
         route_id: TransitRouteID,
         maybe_stop2: Option<TransitStopID>,
-        _: &Map,
+        map: &Map,
     ) -> Option<CarID> {
         assert!(Some(stop1) != maybe_stop2);
         if let Some(route) = self.routes.get(&route_id) {
@@ -260,6 +305,14 @@ impl TransitSimState {
                             .unwrap()
                             .passengers
                             .push((person, maybe_stop2));
+                        pay_fare(
+                            &mut self.fares_paid,
+                            &mut self.events,
+                            person,
+                            map.get_tr(route_id),
+                            stop1,
+                            maybe_stop2,
+                        );
                         // TODO Same problem as elsewhere with recording the PathRequest
                         self.events.push(Event::TripPhaseStarting(
                             trip,
@@ -285,16 +338,47 @@ impl TransitSimState {
         None
     }
 
+    pub fn microtransit_enabled(&self) -> bool {
+        self.microtransit.is_enabled()
+    }
+
+    pub fn same_microtransit_fleet(&self, other: &TransitSimState) -> bool {
+        self.microtransit.same_fleet(&other.microtransit)
+    }
+
+    pub fn request_microtransit(
+        &mut self,
+        now: Time,
+        trip: TripID,
+        person: PersonID,
+        from: BuildingID,
+        to: BuildingID,
+        trips: &mut TripManager,
+        ctx: &mut Ctx,
+    ) {
+        self.microtransit
+            .request_ride(now, trip, person, from, to, trips, ctx);
+    }
+
+    pub fn dispatch_microtransit(&mut self, now: Time, trips: &mut TripManager, ctx: &mut Ctx) {
+        self.microtransit.dispatch(now, trips, ctx);
+    }
+
     pub fn collect_events(&mut self) -> Vec<Event> {
         self.events.drain(..).collect()
     }
 
-    pub fn get_passengers(&self, bus: CarID) -> &Vec<(PersonID, Option<TransitStopID>)> {
-        &self.buses[&bus].passengers
+    pub fn num_passengers(&self, bus: CarID) -> usize {
+        if self.microtransit.owns(bus) {
+            self.microtransit.num_passengers(bus)
+        } else {
+            self.buses[&bus].passengers.len()
+        }
     }
 
-    pub fn bus_route(&self, bus: CarID) -> TransitRouteID {
-        self.buses[&bus].route
+    /// None for microtransit vehicles
+    pub fn bus_route(&self, bus: CarID) -> Option<TransitRouteID> {
+        self.buses.get(&bus).map(|b| b.route)
     }
 
     /// also stop idx that the bus is coming from
@@ -325,7 +409,7 @@ impl TransitSimState {
 
     /// (buses, trains)
     pub fn active_vehicles(&self) -> (usize, usize) {
-        let mut buses = 0;
+        let mut buses = self.microtransit.active_vehicles().len();
         let mut trains = 0;
         for r in self.routes.values() {
             let len = r.active_vehicles.len();
@@ -340,6 +424,11 @@ impl TransitSimState {
         (buses, trains)
     }
 
+    /// People waiting to be picked up by microtransit
+    pub fn num_waiting_for_microtransit(&self) -> usize {
+        self.microtransit.num_waiting()
+    }
+
     pub fn get_people_waiting_at_stop(
         &self,
         at: TransitStopID,
@@ -354,24 +443,32 @@ impl TransitSimState {
         map: &Map,
     ) -> Vec<UnzoomedAgent> {
         let mut results = Vec::new();
-        for (bus_id, bus) in &self.buses {
-            if bus.passengers.is_empty() {
+        let mut all_passengers: Vec<(CarID, Vec<PersonID>)> = self
+            .buses
+            .iter()
+            .map(|(id, bus)| (*id, bus.passengers.iter().map(|(p, _)| *p).collect()))
+            .collect();
+        for id in self.microtransit.active_vehicles() {
+            all_passengers.push((id, self.microtransit.get_passengers(id)));
+        }
+        for (bus_id, passengers) in all_passengers {
+            if passengers.is_empty() {
                 continue;
             }
-            let pos = if let Some(input) = driving.get_single_draw_car(*bus_id, now, map, self) {
+            let pos = if let Some(input) = driving.get_single_draw_car(bus_id, now, map, self) {
                 input.body.last_pt()
             } else {
                 panic!(
                     "At {}, bus {} can't be drawn, yet it has passengers {:?}",
-                    now, bus_id, bus.passengers
+                    now, bus_id, passengers
                 );
             };
-            for (person, _) in &bus.passengers {
-                let agent = AgentID::BusPassenger(*person, *bus_id);
+            for person in passengers {
+                let agent = AgentID::BusPassenger(person, bus_id);
                 results.push(UnzoomedAgent {
                     id: agent,
                     pos,
-                    person: Some(*person),
+                    person: Some(person),
                     parking: false,
                 });
             }
@@ -379,3 +476,22 @@ impl TransitSimState {
         results
     }
 }
+
+/// Charge somebody for boarding a route. If the route has a daily cap, they never pay more than
+/// that in total for the day, counting what they've paid on any route.
+fn pay_fare(
+    fares_paid: &mut BTreeMap<PersonID, usize>,
+    events: &mut Vec<Event>,
+    person: PersonID,
+    route: &TransitRoute,
+    stop1: TransitStopID,
+    maybe_stop2: Option<TransitStopID>,
+) {
+    let paid_so_far = fares_paid.entry(person).or_insert(0);
+    let mut fare = route.fare.price(route, stop1, maybe_stop2);
+    if let Some(cap) = route.fare.daily_cap {
+        fare = fare.min(cap.saturating_sub(*paid_so_far));
+    }
+    *paid_so_far += fare;
+    events.push(Event::TransitFarePaid(person, route.id, fare));
+}
@@ -5,19 +5,20 @@ use serde::{Deserialize, Serialize};
 use abstutil::{deserialize_btreemap, serialize_btreemap, Counter};
 use geom::{Distance, Duration, Speed, Time};
 use map_model::{
-    BuildingID, IntersectionID, Map, PathConstraints, PathRequest, Position, TransitRouteID,
-    TransitStopID,
+    BuildingID, IntersectionID, Map, Path, PathConstraints, PathRequest, PathfinderCaching,
+    Position, TransitRouteID, TransitStopID,
 };
 use synthpop::{
     IndividTrip, OrigPersonID, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose,
 };
 
+use crate::mechanics::BikeParkingSimState;
 use crate::sim::Ctx;
 use crate::{
     AgentID, AgentType, AlertLocation, CarID, Command, CreateCar, CreatePedestrian, DrivingGoal,
     Event, ParkedCar, ParkingSim, ParkingSpot, PedestrianID, PersonID, SidewalkPOI, SidewalkSpot,
-    StartTripArgs, TransitSimState, TripID, TripPhaseType, TripSpec, Vehicle, VehicleSpec,
-    VehicleType, WalkingSimState,
+    SimOptions, StartTripArgs, TransitSimState, TripID, TripPhaseType, TripSpec, Vehicle,
+    VehicleSpec, VehicleType, WalkingSimState,
 };
 
 /// Manages people, each of which executes some trips through the day. Each trip is further broken
@@ -39,23 +40,43 @@ pub(crate) struct TripManager {
     unfinished_trips: usize,
 
     car_id_counter: usize,
+    /// See `SimOptions::bikes_avoid_hills`
+    bikes_avoid_hills: f64,
+    /// See `SimOptions::model_bike_parking`
+    bike_parking: Option<BikeParkingSimState>,
 
     events: Vec<Event>,
 }
 
 // Initialization
 impl TripManager {
-    pub fn new() -> TripManager {
+    pub fn new(map: &Map, opts: &SimOptions) -> TripManager {
         TripManager {
             trips: Vec::new(),
             people: Vec::new(),
             active_trip_mode: BTreeMap::new(),
             unfinished_trips: 0,
             car_id_counter: 0,
+            bikes_avoid_hills: opts.bikes_avoid_hills,
+            bike_parking: if opts.model_bike_parking {
+                Some(BikeParkingSimState::new(map))
+            } else {
+                None
+            },
             events: Vec::new(),
         }
     }
 
+    /// Switch to the routing options of another instance, like a fresh one after loading a
+    /// savestate.
+    pub fn keep_options_from(&mut self, other: &TripManager) {
+        self.bikes_avoid_hills = other.bikes_avoid_hills;
+    }
+
+    pub fn models_bike_parking(&self) -> bool {
+        self.bike_parking.is_some()
+    }
+
     // TODO assert the specs are correct yo
     pub fn new_person(
         &mut self,
@@ -166,6 +187,7 @@ impl TripManager {
             info.mode,
             args.use_vehicle,
             args.retry_if_no_room,
+            args.use_microtransit,
             ctx.map,
         ) {
             Ok(spec) => spec,
@@ -208,7 +230,7 @@ impl TripManager {
                 );
                 let person = person.id;
 
-                match ctx.map.pathfind(req) {
+                match pathfind_for_vehicle(ctx.map, req, &vehicle, self.bikes_avoid_hills) {
                     Ok(path) => {
                         let router = goal.make_router(vehicle.id, path, ctx.map);
                         ctx.scheduler.push(
@@ -329,11 +351,17 @@ impl TripManager {
                     }
                 }
             }
-            TripSpec::UsingBike { start, .. } => {
+            TripSpec::UsingBike { start, bike, .. } => {
                 assert_eq!(person.state, PersonState::Inside(start));
                 person.state = PersonState::Trip(trip);
 
-                if let Some(walk_to) = SidewalkSpot::bike_rack(start, ctx.map) {
+                // The bike might've been locked at a rack somewhere else
+                let rack = self
+                    .bike_parking
+                    .as_mut()
+                    .and_then(|bike_parking| bike_parking.release(bike, start, ctx.map))
+                    .unwrap_or(start);
+                if let Some(walk_to) = SidewalkSpot::bike_rack(rack, ctx.map) {
                     let req = PathRequest::walking(
                         SidewalkSpot::building(start, ctx.map).sidewalk_pos,
                         walk_to.sidewalk_pos,
@@ -432,6 +460,23 @@ impl TripManager {
                     }
                 }
             }
+            TripSpec::UsingMicrotransit { start, goal } => {
+                assert_eq!(person.state, PersonState::Inside(start));
+                person.state = PersonState::Trip(trip);
+
+                self.events
+                    .push(Event::PersonLeavesBuilding(person.id, start));
+                self.events.push(Event::TripPhaseStarting(
+                    trip,
+                    person.id,
+                    None,
+                    TripPhaseType::WaitingForMicrotransit,
+                ));
+                ctx.scheduler.push(
+                    now,
+                    Command::RequestMicrotransit(trip, person.id, start, goal),
+                );
+            }
         }
     }
 
@@ -547,7 +592,7 @@ impl TripManager {
 
         let person = trip.person;
         let trip = trip.id;
-        match ctx.map.pathfind(req) {
+        match pathfind_for_vehicle(ctx.map, req, &parked_car.vehicle, self.bikes_avoid_hills) {
             Ok(path) => {
                 let router = drive_to.make_router(parked_car.vehicle.id, path, ctx.map);
                 ctx.scheduler.push(
@@ -593,7 +638,16 @@ impl TripManager {
             _ => unreachable!(),
         };
 
-        let end = if let Some(end) = drive_to.goal_pos(PathConstraints::Bike, ctx.map) {
+        // If bike parking is modeled, the cyclist might have to lock up somewhere else, then walk
+        // to their destination
+        let park_at = match (self.bike_parking.as_mut(), &drive_to) {
+            (Some(bike_parking), DrivingGoal::ParkNear(b)) => {
+                DrivingGoal::ParkNear(bike_parking.reserve(bike, *b, ctx.map).unwrap_or(*b))
+            }
+            _ => drive_to.clone(),
+        };
+
+        let end = if let Some(end) = park_at.goal_pos(PathConstraints::Bike, ctx.map) {
             end
         } else {
             let trip = trip.id;
@@ -615,9 +669,13 @@ impl TripManager {
                 req.start.lane()
             ))
         } else {
-            ctx.map
-                .pathfind(req)
-                .map(|path| drive_to.make_router(bike, path, ctx.map))
+            pathfind_for_vehicle(
+                ctx.map,
+                req,
+                &self.people[trip.person.0].get_vehicle(bike),
+                self.bikes_avoid_hills,
+            )
+            .map(|path| park_at.make_router(bike, path, ctx.map))
         };
         match maybe_router {
             Ok(router) => {
@@ -659,8 +717,12 @@ impl TripManager {
         trip.total_distance += distance_crossed;
 
         match trip.legs.pop_front() {
-            Some(TripLeg::Drive(c, DrivingGoal::ParkNear(_))) => {
+            Some(TripLeg::Drive(c, DrivingGoal::ParkNear(b))) => {
                 assert_eq!(c, bike);
+                if let Some(ref bike_parking) = self.bike_parking {
+                    self.events
+                        .push(Event::BikeParked(bike, b, bike_parking.get_rack(bike)));
+                }
             }
             _ => unreachable!(),
         };
@@ -795,6 +857,39 @@ impl TripManager {
         self.spawn_ped(now, id, start, ctx);
     }
 
+    /// The person was waiting outside their building, so no distance was crossed before boarding.
+    pub fn person_boarded_microtransit(&mut self, trip: TripID, vehicle: CarID, waited: Duration) {
+        let trip = &mut self.trips[trip.0];
+        trip.total_blocked_time += waited;
+        self.active_trip_mode
+            .insert(AgentID::BusPassenger(trip.person, vehicle), trip.id);
+        self.people[trip.person.0].on_bus = Some(vehicle);
+    }
+
+    pub fn person_left_microtransit(
+        &mut self,
+        now: Time,
+        person: PersonID,
+        vehicle: CarID,
+        ctx: &mut Ctx,
+    ) {
+        let trip = &mut self.trips[self
+            .active_trip_mode
+            .remove(&AgentID::BusPassenger(person, vehicle))
+            .unwrap()
+            .0];
+        let bldg = match trip.legs.pop_front().unwrap() {
+            TripLeg::RideMicrotransit(b) => b,
+            _ => unreachable!(),
+        };
+        self.people[person.0].on_bus.take().unwrap();
+        self.people[person.0].state = PersonState::Inside(bldg);
+        self.events.push(Event::PersonEntersBuilding(person, bldg));
+
+        let id = trip.id;
+        self.trip_finished(now, id, ctx);
+    }
+
     pub fn ped_reached_border(
         &mut self,
         now: Time,
@@ -1064,9 +1159,40 @@ impl TripManager {
             }
         }
 
+        // A cyclist who never reached their rack doesn't need the spot anymore
+        if let (Some(TripLeg::Drive(c, _)), Some(bike_parking)) =
+            (self.trips[id.0].legs.front(), self.bike_parking.as_mut())
+        {
+            if c.vehicle_type == VehicleType::Bike {
+                bike_parking.cancel(*c);
+            }
+        }
+
         self.start_delayed_trip(now, person, ctx);
     }
 
+    /// Give up on somebody's microtransit ride. If they're already riding `vehicle`, they're taken
+    /// off of it first.
+    pub fn cancel_microtransit_trip(
+        &mut self,
+        now: Time,
+        id: TripID,
+        vehicle: Option<CarID>,
+        reason: String,
+        ctx: &mut Ctx,
+    ) {
+        if let Some(vehicle) = vehicle {
+            let person = self.trips[id.0].person;
+            assert_eq!(
+                self.active_trip_mode
+                    .remove(&AgentID::BusPassenger(person, vehicle)),
+                Some(id)
+            );
+            self.people[person.0].on_bus.take().unwrap();
+        }
+        self.cancel_trip(now, id, reason, None, ctx);
+    }
+
     pub fn trip_abruptly_cancelled(&mut self, trip: TripID, agent: AgentID) {
         assert_eq!(self.active_trip_mode.remove(&agent), Some(trip));
     }
@@ -1105,6 +1231,11 @@ impl TripManager {
             TripLeg::Walk(_) => AgentID::Pedestrian(person.ped),
             TripLeg::Drive(c, _) => AgentID::Car(*c),
             TripLeg::RideBus(_, _) => AgentID::BusPassenger(person.id, person.on_bus.unwrap()),
+            TripLeg::RideMicrotransit(_) => match person.on_bus {
+                Some(vehicle) => AgentID::BusPassenger(person.id, vehicle),
+                // Still waiting to be picked up
+                None => return TripResult::ModeChange,
+            },
         };
         if self.active_trip_mode.get(&a) == Some(&id) {
             TripResult::Ok(a)
@@ -1246,6 +1377,10 @@ impl TripManager {
     pub fn get_person(&self, p: PersonID) -> Option<&Person> {
         self.people.get(p.0)
     }
+    pub fn all_bike_racks(&self) -> Option<BTreeMap<BuildingID, (usize, usize)>> {
+        self.bike_parking.as_ref().map(|x| x.all_racks())
+    }
+
     pub fn get_all_people(&self) -> &Vec<Person> {
         &self.people
     }
@@ -1352,6 +1487,8 @@ pub(crate) enum TripLeg {
     Drive(CarID, DrivingGoal),
     /// Maybe get off at a stop, maybe ride off-map
     RideBus(TransitRouteID, Option<TransitStopID>),
+    /// Ride an on-demand vehicle right to this building
+    RideMicrotransit(BuildingID),
 }
 
 pub enum TripResult<T> {
@@ -1428,3 +1565,25 @@ pub struct CommutersVehiclesCounts {
     pub bus_riders: usize,
     pub train_riders: usize,
 }
+
+/// Like `Map::pathfind`, but some classes of vehicle have to avoid certain roads, and cyclists may
+/// avoid hills. Many vehicles share each set of restrictions, so the map caches a contraction
+/// hierarchy for them. When the router later searches for parking, it avoids the same roads.
+fn pathfind_for_vehicle(
+    map: &Map,
+    req: PathRequest,
+    vehicle: &Vehicle,
+    bikes_avoid_hills: f64,
+) -> anyhow::Result<Path> {
+    let mut params = map.routing_params().clone();
+    if let Some(class) = vehicle.class {
+        params.avoid_roads = class.restricted_roads(map);
+    }
+    if vehicle.vehicle_type == VehicleType::Bike {
+        params.avoid_steep_incline_penalty = bikes_avoid_hills;
+    }
+    if &params == map.routing_params() {
+        return map.pathfind(req);
+    }
+    map.pathfind_with_params(req, &params, PathfinderCaching::CacheCH)
+}
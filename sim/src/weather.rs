@@ -0,0 +1,339 @@
+//! Weather and daylight change how people travel. In rain, snow, or the dark, fewer people walk or
+//! cycle, everyone walks a bit slower, and drivers slow down and leave bigger gaps. This lets
+//! proposals for active travel be evaluated against winter conditions, not just a dry summer day.
+//!
+//! The factors here are rough, taken from the range reported in studies of weather and travel
+//! behavior. They're a starting point for sensitivity testing, not a calibrated model.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use geom::{Duration, Time};
+use map_model::BuildingID;
+use synthpop::{PersonSpec, Scenario, TripEndpoint, TripMode};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Precipitation {
+    Dry,
+    Rain,
+    Snow,
+}
+
+/// The time of year determines when it gets dark
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Season {
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+/// Conditions for the whole simulated day
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Weather {
+    pub precipitation: Precipitation,
+    pub season: Season,
+}
+
+impl Weather {
+    /// Parses something like "snow,winter" or "rain". The season defaults to summer, and
+    /// precipitation to dry.
+    pub fn parse(x: &str) -> Result<Weather> {
+        let mut weather = Weather {
+            precipitation: Precipitation::Dry,
+            season: Season::Summer,
+        };
+        for part in x.split(',') {
+            match part.trim() {
+                "dry" => weather.precipitation = Precipitation::Dry,
+                "rain" => weather.precipitation = Precipitation::Rain,
+                "snow" => weather.precipitation = Precipitation::Snow,
+                "spring" => weather.season = Season::Spring,
+                "summer" => weather.season = Season::Summer,
+                "autumn" | "fall" => weather.season = Season::Autumn,
+                "winter" => weather.season = Season::Winter,
+                _ => bail!(
+                    "Bad --weather={}. Must be some of dry|rain|snow and \
+                     spring|summer|autumn|winter, separated by a comma",
+                    x
+                ),
+            }
+        }
+        Ok(weather)
+    }
+
+    /// Approximate sunrise and sunset for a mid-latitude city, like Seattle or London
+    pub fn sunrise_and_sunset(&self) -> (Time, Time) {
+        let (sunrise, sunset) = match self.season {
+            Season::Spring | Season::Autumn => (7.0, 19.0),
+            Season::Summer => (5.25, 21.0),
+            Season::Winter => (8.0, 16.5),
+        };
+        (
+            Time::START_OF_DAY + Duration::seconds(sunrise * 3600.0),
+            Time::START_OF_DAY + Duration::seconds(sunset * 3600.0),
+        )
+    }
+
+    /// Is it dark at this time? Simulations spanning multiple days repeat the same daylight.
+    pub fn is_dark(&self, now: Time) -> bool {
+        let (sunrise, sunset) = self.sunrise_and_sunset();
+        let time_of_day = Time::START_OF_DAY
+            + Duration::seconds((now - Time::START_OF_DAY).inner_seconds() % (24.0 * 3600.0));
+        time_of_day < sunrise || time_of_day >= sunset
+    }
+
+    /// Multiplies the speed of every vehicle
+    pub fn vehicle_speed_factor(&self) -> f64 {
+        match self.precipitation {
+            Precipitation::Dry => 1.0,
+            Precipitation::Rain => 0.9,
+            Precipitation::Snow => 0.7,
+        }
+    }
+
+    /// Multiplies how fast people walk, on slippery or wet sidewalks
+    pub fn walking_speed_factor(&self) -> f64 {
+        match self.precipitation {
+            Precipitation::Dry => 1.0,
+            Precipitation::Rain => 0.95,
+            Precipitation::Snow => 0.85,
+        }
+    }
+
+    /// Drivers leave bigger gaps on slippery roads, so fewer vehicles fit on each lane before it
+    /// counts as full. This multiplies the usable length of every lane.
+    pub fn lane_capacity_factor(&self) -> f64 {
+        match self.precipitation {
+            Precipitation::Dry => 1.0,
+            Precipitation::Rain => 0.9,
+            Precipitation::Snow => 0.75,
+        }
+    }
+
+    /// The fraction of trips using this mode that still happen using it. The rest switch to
+    /// another mode.
+    pub fn mode_share_factor(&self, mode: TripMode, depart: Time) -> f64 {
+        let dark = self.is_dark(depart);
+        match mode {
+            TripMode::Walk => {
+                let weather = match self.precipitation {
+                    Precipitation::Dry => 1.0,
+                    Precipitation::Rain => 0.85,
+                    Precipitation::Snow => 0.75,
+                };
+                weather * if dark { 0.9 } else { 1.0 }
+            }
+            TripMode::Bike => {
+                let weather = match self.precipitation {
+                    Precipitation::Dry => 1.0,
+                    Precipitation::Rain => 0.6,
+                    Precipitation::Snow => 0.3,
+                };
+                let cold = if self.season == Season::Winter {
+                    0.8
+                } else {
+                    1.0
+                };
+                weather * cold * if dark { 0.8 } else { 1.0 }
+            }
+            TripMode::Transit | TripMode::Drive => 1.0,
+        }
+    }
+
+    /// Moves some walking and cycling trips to other modes. People who'd walk take transit instead,
+    /// which still falls back to walking when there's no useful route. People who'd cycle drive
+    /// if their car is parked where the trip starts; otherwise they take transit too. Only people
+    /// who drive somewhere during the day own a car.
+    ///
+    /// Who switches is decided by a hash of their index and trip, rather than the RNG, so that the
+    /// same trips switch across runs and with different map edits. Switched trips are marked as
+    /// modified.
+    pub fn adjust_mode_shares(&self, scenario: &mut Scenario) {
+        for (person_idx, person) in scenario.people.iter_mut().enumerate() {
+            let mut cars = initial_car_locations(person);
+            // Cars somebody took instead of cycling. They'll drive those back, instead of cycling.
+            let mut borrowed = vec![false; cars.len()];
+
+            for (trip_idx, trip) in person.trips.iter_mut().enumerate() {
+                let origin = match trip.origin {
+                    TripEndpoint::Building(b) => Some(b),
+                    _ => None,
+                };
+                let destination = match trip.destination {
+                    TripEndpoint::Building(b) => Some(b),
+                    _ => None,
+                };
+                let car_here = cars.iter().position(|at| *at == origin);
+
+                let keep = self.mode_share_factor(trip.mode, trip.depart);
+                // A cheap, stable value in [0, 1)
+                let x = ((person_idx * 7919 + trip_idx * 104729) % 1000) as f64 / 1000.0;
+                let switch = x >= keep;
+
+                match trip.mode {
+                    TripMode::Drive => {
+                        if let Some(idx) = car_here {
+                            cars[idx] = destination;
+                            borrowed[idx] = false;
+                        }
+                    }
+                    TripMode::Bike => {
+                        let must_return_car = car_here.map(|idx| borrowed[idx]).unwrap_or(false);
+                        if !switch && !must_return_car {
+                            continue;
+                        }
+                        match car_here {
+                            // Only cars parked in a building count; a border means the person is
+                            // already off the map
+                            Some(idx) if origin.is_some() => {
+                                trip.mode = TripMode::Drive;
+                                cars[idx] = destination;
+                                borrowed[idx] = true;
+                            }
+                            _ => {
+                                trip.mode = TripMode::Transit;
+                            }
+                        }
+                        trip.modified = true;
+                    }
+                    TripMode::Walk => {
+                        if switch {
+                            trip.mode = TripMode::Transit;
+                            trip.modified = true;
+                        }
+                    }
+                    TripMode::Transit => {}
+                }
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{}, {}",
+            match self.precipitation {
+                Precipitation::Dry => "dry",
+                Precipitation::Rain => "rain",
+                Precipitation::Snow => "snow",
+            },
+            match self.season {
+                Season::Spring => "spring",
+                Season::Summer => "summer",
+                Season::Autumn => "autumn",
+                Season::Winter => "winter",
+            }
+        )
+    }
+}
+
+/// Where each of somebody's cars is parked at the start of the day, following the same rules as
+/// instantiating the scenario: a car appears wherever a driving trip starts without one already
+/// being there. `None` means off the map.
+fn initial_car_locations(person: &PersonSpec) -> Vec<Option<BuildingID>> {
+    let mut initial = Vec::new();
+    let mut current: Vec<Option<BuildingID>> = Vec::new();
+    for trip in &person.trips {
+        if trip.mode != TripMode::Drive {
+            continue;
+        }
+        let origin = match trip.origin {
+            TripEndpoint::Building(b) => Some(b),
+            _ => None,
+        };
+        let destination = match trip.destination {
+            TripEndpoint::Building(b) => Some(b),
+            _ => None,
+        };
+        let idx = if let Some(idx) = current.iter().position(|at| *at == origin) {
+            idx
+        } else {
+            initial.push(origin);
+            current.push(origin);
+            current.len() - 1
+        };
+        current[idx] = destination;
+    }
+    initial
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let weather = Weather::parse("snow,winter").unwrap();
+        assert_eq!(weather.precipitation, Precipitation::Snow);
+        assert_eq!(weather.season, Season::Winter);
+        assert_eq!(Weather::parse("rain").unwrap().season, Season::Summer);
+        assert!(Weather::parse("hail").is_err());
+    }
+
+    #[test]
+    fn test_is_dark() {
+        let weather = Weather::parse("winter").unwrap();
+        assert!(weather.is_dark(Time::START_OF_DAY + Duration::hours(7)));
+        assert!(!weather.is_dark(Time::START_OF_DAY + Duration::hours(12)));
+        assert!(weather.is_dark(Time::START_OF_DAY + Duration::hours(17)));
+        // The next day
+        assert!(!weather.is_dark(Time::START_OF_DAY + Duration::hours(36)));
+    }
+
+    #[test]
+    fn test_only_car_owners_switch_to_driving() {
+        use synthpop::{IndividTrip, TripPurpose};
+
+        let home = TripEndpoint::Building(BuildingID(0));
+        let work = TripEndpoint::Building(BuildingID(1));
+        let shop = TripEndpoint::Building(BuildingID(2));
+        let trip = |hour: usize, from: TripEndpoint, to: TripEndpoint, mode: TripMode| {
+            IndividTrip::new(
+                Time::START_OF_DAY + Duration::hours(hour),
+                TripPurpose::Work,
+                from,
+                to,
+                mode,
+            )
+        };
+        let mut scenario = Scenario {
+            scenario_name: "test".to_string(),
+            map_name: abstio::MapName::blank(),
+            people: Vec::new(),
+            only_seed_buses: None,
+            fleet_mix: Default::default(),
+        };
+        for i in 0..40 {
+            let mut trips = vec![
+                trip(10, home, work, TripMode::Bike),
+                trip(13, work, home, TripMode::Bike),
+            ];
+            // Every other person drives to the shops in the evening, so they own a car
+            if i % 2 == 1 {
+                trips.push(trip(18, home, shop, TripMode::Drive));
+                trips.push(trip(19, shop, home, TripMode::Drive));
+            }
+            scenario.people.push(PersonSpec {
+                orig_id: None,
+                trips,
+            });
+        }
+
+        Weather::parse("snow,winter")
+            .unwrap()
+            .adjust_mode_shares(&mut scenario);
+
+        let mut num_drove = 0;
+        for (idx, person) in scenario.people.iter().enumerate() {
+            let modes: Vec<TripMode> = person.trips.iter().map(|t| t.mode).collect();
+            if idx % 2 == 0 {
+                assert!(!modes.contains(&TripMode::Drive));
+            } else if modes[0] == TripMode::Drive {
+                // They have to bring the car back home for the evening
+                assert_eq!(modes[1], TripMode::Drive);
+                num_drove += 1;
+            }
+        }
+        assert!(num_drove > 0);
+    }
+}
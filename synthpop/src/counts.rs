@@ -1,9 +1,12 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use abstio::MapName;
 use abstutil::{prettyprint_usize, Counter, Timer};
 use geom::Distance;
-use map_model::{IntersectionID, Map, PathRequest, PathStepV2, PathV2, Pathfinder, RoadID};
+use map_model::{osm, IntersectionID, Map, PathRequest, PathStepV2, PathV2, Pathfinder, RoadID};
 
 /// This represents the number of vehicles (or trips, or something else) crossing roads and
 /// intersections over some span of time. The data could represent real observations or something
@@ -104,6 +107,109 @@ impl TrafficCounts {
         }
     }
 
+    /// Import aggregate link volumes from SUMO's edgeData output
+    /// (https://sumo.dlr.de/docs/Simulation/Output/Lane-_or_Edge-based_Traffic_Measures.html). The
+    /// SUMO network must have been built by `netconvert` from the same OSM data, so that edge IDs
+    /// like `-123456#2` encode the OSM way. Vehicles entering each edge are summed over all
+    /// intervals.
+    ///
+    /// SUMO and A/B Street split OSM ways at different points, so each road gets the busiest SUMO
+    /// edge of its way, summed over both directions.
+    pub fn import_sumo_edgedata(map: &Map, description: String, xml: &str) -> Result<Self> {
+        let doc = roxmltree::Document::parse(xml)?;
+        // Per (OSM way, forwards, SUMO segment)
+        let mut per_edge: Counter<(osm::WayID, bool, String)> = Counter::new();
+        for node in doc.descendants() {
+            if !node.has_tag_name("edge") {
+                continue;
+            }
+            let id = node
+                .attribute("id")
+                .ok_or_else(|| anyhow!("<edge> missing id"))?;
+            // Internal edges within junctions start with ':'
+            if id.starts_with(':') {
+                continue;
+            }
+            let (forwards, id) = match id.strip_prefix('-') {
+                Some(rest) => (false, rest),
+                None => (true, id),
+            };
+            let (way, segment) = id.split_once('#').unwrap_or((id, "0"));
+            let way = match way.parse::<i64>() {
+                Ok(way) => osm::WayID(way),
+                Err(_) => {
+                    warn!(
+                        "Skipping SUMO edge {}, which doesn't reference an OSM way",
+                        id
+                    );
+                    continue;
+                }
+            };
+            // Volumes may be fractional when SUMO scales demand
+            let entered = node
+                .attribute("entered")
+                .or_else(|| node.attribute("departed"))
+                .unwrap_or("0")
+                .parse::<f64>()?;
+            per_edge.add(
+                (way, forwards, segment.to_string()),
+                entered.round() as usize,
+            );
+        }
+
+        let mut per_way_direction: BTreeMap<(osm::WayID, bool), usize> = BTreeMap::new();
+        for ((way, forwards, _), cnt) in per_edge.consume() {
+            let max = per_way_direction.entry((way, forwards)).or_insert(0);
+            *max = (*max).max(cnt);
+        }
+        let mut per_way = Counter::new();
+        for ((way, _), cnt) in per_way_direction {
+            per_way.add(way, cnt);
+        }
+        Ok(Self::from_osm_way_counts(map, description, per_way))
+    }
+
+    /// Import a generic CSV file of link volumes, with an `osm_way_id` and `count` column. Every
+    /// road belonging to the OSM way gets the count. Rows for the same way are summed, so
+    /// per-direction or per-hour data is fine.
+    pub fn import_link_volumes_csv(map: &Map, description: String, csv: &[u8]) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct Record {
+            osm_way_id: i64,
+            count: f64,
+        }
+
+        let mut per_way = Counter::new();
+        for rec in csv::Reader::from_reader(csv).deserialize() {
+            let rec: Record = rec?;
+            per_way.add(osm::WayID(rec.osm_way_id), rec.count.round() as usize);
+        }
+        Ok(Self::from_osm_way_counts(map, description, per_way))
+    }
+
+    fn from_osm_way_counts(map: &Map, description: String, per_way: Counter<osm::WayID>) -> Self {
+        let mut counts = Self {
+            map: map.get_name().clone(),
+            description,
+            per_road: Counter::new(),
+            per_intersection: Counter::new(),
+        };
+        let mut matched = 0;
+        for r in map.all_roads() {
+            let cnt = per_way.get(r.orig_id.osm_way_id);
+            if cnt > 0 {
+                matched += 1;
+            }
+            counts.per_road.add(r.id, cnt);
+        }
+        info!(
+            "Matched counts from {} OSM ways to {} roads",
+            prettyprint_usize(per_way.borrow().len()),
+            prettyprint_usize(matched)
+        );
+        counts
+    }
+
     /// Print a comparison of counts. Only look at roads/intersections in `self`.
     pub fn quickly_compare(&self, other: &TrafficCounts) {
         // TODO Easy ASCII art table without huge dependencies?
@@ -0,0 +1,152 @@
+use std::collections::BTreeSet;
+
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use geom::{Distance, Speed};
+use map_model::{Map, Road, RoadID};
+
+/// Private vehicles come in different classes, with different sizes and capabilities. Buses and
+/// trains are transit, so they're described separately.
+///
+/// Classes differ by length, top speed, acceleration, and which roads they can use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum VehicleClass {
+    Car,
+    Motorcycle,
+    Truck,
+}
+
+impl VehicleClass {
+    pub fn all() -> Vec<VehicleClass> {
+        vec![
+            VehicleClass::Car,
+            VehicleClass::Motorcycle,
+            VehicleClass::Truck,
+        ]
+    }
+
+    pub fn noun(self) -> &'static str {
+        match self {
+            VehicleClass::Car => "car",
+            VehicleClass::Motorcycle => "motorcycle",
+            VehicleClass::Truck => "truck",
+        }
+    }
+
+    /// The (shortest, longest) vehicle of this class. Trucks are limited to rigid goods vehicles
+    /// that fit in a regular on-street parking spot.
+    pub fn length_range(self) -> (Distance, Distance) {
+        match self {
+            VehicleClass::Car => (Distance::meters(4.5), Distance::meters(6.5)),
+            VehicleClass::Motorcycle => (Distance::meters(2.0), Distance::meters(2.5)),
+            VehicleClass::Truck => (Distance::meters(7.0), Distance::meters(8.0)),
+        }
+    }
+
+    /// Vehicles of this class never go faster than this, even when the speed limit is higher.
+    /// None means they can go at the speed limit.
+    pub fn max_speed(self) -> Option<Speed> {
+        match self {
+            VehicleClass::Car | VehicleClass::Motorcycle => None,
+            VehicleClass::Truck => Some(Speed::miles_per_hour(55.0)),
+        }
+    }
+
+    /// How quickly vehicles of this class pull away from a stop, in m/s^2. The simulation
+    /// doesn't model braking; vehicles stop instantly.
+    pub fn acceleration(self) -> f64 {
+        match self {
+            VehicleClass::Car => 2.5,
+            VehicleClass::Motorcycle => 3.5,
+            VehicleClass::Truck => 1.0,
+        }
+    }
+
+    /// Can this class of vehicle drive along the road at all? This only checks restrictions
+    /// specific to the class, not the lane types.
+    pub fn can_use_road(self, road: &Road) -> bool {
+        match self {
+            VehicleClass::Car | VehicleClass::Motorcycle => true,
+            VehicleClass::Truck => !road.access_restrictions.no_hgv,
+        }
+    }
+
+    /// All roads that vehicles of this class must avoid when routing
+    pub fn restricted_roads(self, map: &Map) -> BTreeSet<RoadID> {
+        if self != VehicleClass::Truck {
+            return BTreeSet::new();
+        }
+        map.all_roads()
+            .iter()
+            .filter(|r| !self.can_use_road(r))
+            .map(|r| r.id)
+            .collect()
+    }
+}
+
+/// How common each class of vehicle is among people driving in a scenario. The weights are
+/// relative, so percentages work, but they don't need to sum to 100. If empty, everybody drives a
+/// car.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct FleetMix {
+    pub weights: Vec<(VehicleClass, usize)>,
+    /// The percent of cars whose drivers hold a disabled parking permit, letting them use disabled
+    /// bays
+    #[serde(default)]
+    pub disabled_permit_pct: usize,
+}
+
+impl FleetMix {
+    /// Randomly pick a class. Doesn't touch the RNG when there's only one choice, so adding this
+    /// to older scenarios doesn't change their results.
+    pub fn pick(&self, rng: &mut XorShiftRng) -> VehicleClass {
+        let total: usize = self.weights.iter().map(|(_, w)| *w).sum();
+        if self.weights.len() <= 1 || total == 0 {
+            return self
+                .weights
+                .first()
+                .map(|(class, _)| *class)
+                .unwrap_or(VehicleClass::Car);
+        }
+
+        let mut x = rng.gen_range(0..total);
+        for (class, weight) in &self.weights {
+            if x < *weight {
+                return *class;
+            }
+            x -= weight;
+        }
+        self.weights.last().unwrap().0
+    }
+
+    /// Does the driver of a new car hold a disabled parking permit? Like `pick`, this doesn't touch
+    /// the RNG unless some drivers do.
+    pub fn pick_disabled_permit(&self, rng: &mut XorShiftRng) -> bool {
+        if self.disabled_permit_pct == 0 {
+            return false;
+        }
+        rng.gen_range(0..100) < self.disabled_permit_pct
+    }
+
+    pub fn describe(&self) -> String {
+        let mut description = if self.weights.is_empty() {
+            "only cars".to_string()
+        } else {
+            let total: usize = self.weights.iter().map(|(_, w)| *w).sum();
+            self.weights
+                .iter()
+                .map(|(class, weight)| format!("{}% {}", 100 * weight / total.max(1), class.noun()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        if self.disabled_permit_pct > 0 {
+            description.push_str(&format!(
+                ", {}% with disabled parking permits",
+                self.disabled_permit_pct
+            ));
+        }
+        description
+    }
+}
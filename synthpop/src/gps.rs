@@ -0,0 +1,438 @@
+//! Turn GPS traces, like the ones collected by some travel surveys, into trips. A trace is split
+//! wherever somebody stays in one spot for a while, and the mode of each trip is guessed from its
+//! speed and the roads it follows.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+
+use geom::{Distance, Duration, FindClosest, LonLat, Pt2D, Time};
+use map_model::{Map, RoadID};
+
+use crate::{ExternalPerson, ExternalTrip, ExternalTripEndpoint, TripMode, TripPurpose};
+
+/// Somebody staying within this distance of one spot isn't travelling
+const STAY_RADIUS: Distance = Distance::const_meters(100.0);
+/// Shorter trips are usually GPS noise around a stop
+const MIN_TRIP_LENGTH: Distance = Distance::const_meters(200.0);
+/// Points implying a jump faster than this (in m/s) are GPS errors
+const MAX_PLAUSIBLE_SPEED: f64 = 60.0;
+/// Only match points to roads this close
+const MAX_MATCH_DIST: Distance = Distance::const_meters(30.0);
+/// Thresholds (in m/s) on the 85th percentile speed of a trip, to guess the mode
+const MAX_WALKING_SPEED: f64 = 2.8;
+const MAX_BIKING_SPEED: f64 = 8.3;
+
+/// One person's GPS trace over a single day
+pub struct GpsTrace {
+    pub id: String,
+    /// In time order
+    pub points: Vec<(Time, LonLat)>,
+}
+
+impl GpsTrace {
+    /// Each `<trk>` in a GPX file is a different person. Points without a `<time>` are skipped.
+    /// Timestamps in UTC are shifted by `utc_offset` to get local time.
+    pub fn parse_gpx(xml: &str, utc_offset: Duration) -> Result<Vec<GpsTrace>> {
+        let doc = roxmltree::Document::parse(xml)?;
+        let mut traces = Vec::new();
+        // GPX uses a default namespace, so just compare the local names
+        let is = |node: &roxmltree::Node, name: &str| {
+            node.is_element() && node.tag_name().name() == name
+        };
+        for (idx, trk) in doc.descendants().filter(|n| is(n, "trk")).enumerate() {
+            let id = trk
+                .children()
+                .find(|n| is(n, "name"))
+                .and_then(|n| n.text())
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| format!("track {}", idx));
+            let mut raw = Vec::new();
+            for pt in trk.descendants().filter(|n| is(n, "trkpt")) {
+                let lat = pt
+                    .attribute("lat")
+                    .ok_or_else(|| anyhow!("<trkpt> missing lat"))?
+                    .parse::<f64>()?;
+                let lon = pt
+                    .attribute("lon")
+                    .ok_or_else(|| anyhow!("<trkpt> missing lon"))?
+                    .parse::<f64>()?;
+                if let Some(time) = pt.children().find(|n| is(n, "time")).and_then(|n| n.text()) {
+                    raw.push((time.to_string(), LonLat::new(lon, lat)));
+                }
+            }
+            traces.push(GpsTrace::new(id, raw, utc_offset)?);
+        }
+        Ok(traces)
+    }
+
+    /// Expects a FeatureCollection. LineStrings need a `coordTimes` property with a timestamp per
+    /// point (the output of tools like togeojson), and Points need a `time` property. Features
+    /// with the same `person` property are combined into one trace; otherwise each feature is a
+    /// different person.
+    pub fn parse_geojson(raw: &str, utc_offset: Duration) -> Result<Vec<GpsTrace>> {
+        let collection = geojson::FeatureCollection::try_from(raw.parse::<geojson::GeoJson>()?)?;
+        let mut per_person: BTreeMap<String, Vec<(String, LonLat)>> = BTreeMap::new();
+        for (idx, feature) in collection.features.into_iter().enumerate() {
+            let id = match feature.property("person") {
+                Some(value) => value
+                    .as_str()
+                    .map(|x| x.to_string())
+                    .unwrap_or_else(|| value.to_string()),
+                None => format!("feature {}", idx),
+            };
+            let raw = per_person.entry(id).or_insert_with(Vec::new);
+            match feature.geometry.as_ref().map(|g| &g.value) {
+                Some(geojson::Value::Point(pt)) => {
+                    if let Some(time) = feature.property("time").and_then(|x| x.as_str()) {
+                        raw.push((time.to_string(), LonLat::new(pt[0], pt[1])));
+                    }
+                }
+                Some(geojson::Value::LineString(pts)) => {
+                    let times = feature
+                        .property("coordTimes")
+                        .and_then(|x| x.as_array())
+                        .ok_or_else(|| anyhow!("LineString feature {} has no coordTimes", idx))?;
+                    if times.len() != pts.len() {
+                        bail!(
+                            "LineString feature {} has {} points, but {} coordTimes",
+                            idx,
+                            pts.len(),
+                            times.len()
+                        );
+                    }
+                    for (pt, time) in pts.iter().zip(times) {
+                        if let Some(time) = time.as_str() {
+                            raw.push((time.to_string(), LonLat::new(pt[0], pt[1])));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut traces = Vec::new();
+        for (id, raw) in per_person {
+            traces.push(GpsTrace::new(id, raw, utc_offset)?);
+        }
+        Ok(traces)
+    }
+
+    /// Parses ISO 8601 timestamps, keeping only points from the day with the most points, since a
+    /// scenario only covers one day.
+    fn new(id: String, raw: Vec<(String, LonLat)>, utc_offset: Duration) -> Result<GpsTrace> {
+        let mut per_day: BTreeMap<i64, Vec<(Time, LonLat)>> = BTreeMap::new();
+        for (timestamp, gps) in raw {
+            let (day, time) = parse_timestamp(&timestamp, utc_offset)
+                .ok_or_else(|| anyhow!("Can't parse timestamp {}", timestamp))?;
+            per_day
+                .entry(day)
+                .or_insert_with(Vec::new)
+                .push((time, gps));
+        }
+        let total: usize = per_day.values().map(|pts| pts.len()).sum();
+        // Ties go to the earliest day
+        let mut points = per_day
+            .into_values()
+            .rev()
+            .max_by_key(|pts| pts.len())
+            .unwrap_or_default();
+        if points.len() < total {
+            warn!(
+                "Skipping {} points from {} that aren't from the same day as the rest",
+                total - points.len(),
+                id
+            );
+        }
+        points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        Ok(GpsTrace { id, points })
+    }
+
+    /// Split the trace into trips wherever the person stays within a small area for at least
+    /// `min_dwell`. The endpoints of each trip are snapped to buildings later, by
+    /// `ExternalPerson::import`. Trips returning to where the trace started are assumed to go
+    /// home; the purpose of other trips isn't known.
+    pub fn to_person(
+        &self,
+        map: &Map,
+        roads: &FindClosest<RoadID>,
+        min_dwell: Duration,
+    ) -> ExternalPerson {
+        let points = self.without_jumps(map);
+        let mut trips = Vec::new();
+        if points.len() < 2 {
+            return ExternalPerson { trips };
+        }
+
+        // Where the person stayed, as (arrival, departure) indices into points. The start and end
+        // of the trace count too.
+        let mut stays = find_stays(&points, min_dwell);
+        if stays
+            .first()
+            .map(|(arrive, _)| *arrive != 0)
+            .unwrap_or(true)
+        {
+            stays.insert(0, (0, 0));
+        }
+        let last = points.len() - 1;
+        if stays
+            .last()
+            .map(|(_, depart)| *depart != last)
+            .unwrap_or(true)
+        {
+            stays.push((last, last));
+        }
+
+        let home = points[0].gps;
+        for pair in stays.windows(2) {
+            let (_, depart) = pair[0];
+            let (arrive, _) = pair[1];
+            let segment = &points[depart..=arrive];
+            let length = segment
+                .windows(2)
+                .map(|pts| pts[0].gps.fast_dist(pts[1].gps))
+                .fold(Distance::ZERO, |a, b| a + b);
+            if length < MIN_TRIP_LENGTH {
+                continue;
+            }
+
+            // Describe each stay by a point in the middle of it
+            let origin = points[(pair[0].0 + pair[0].1) / 2].gps;
+            let destination = points[(pair[1].0 + pair[1].1) / 2].gps;
+            trips.push(ExternalTrip {
+                departure: points[depart].time,
+                origin: ExternalTripEndpoint::Position(origin),
+                destination: ExternalTripEndpoint::Position(destination),
+                mode: infer_mode(segment, map, roads),
+                purpose: if destination.fast_dist(home) <= STAY_RADIUS {
+                    TripPurpose::Home
+                } else {
+                    TripPurpose::PersonalBusiness
+                },
+            });
+        }
+        ExternalPerson { trips }
+    }
+
+    /// Drop points implying an impossibly fast jump from the previous point.
+    fn without_jumps(&self, map: &Map) -> Vec<Point> {
+        let mut points: Vec<Point> = Vec::new();
+        for (time, gps) in &self.points {
+            if let Some(prev) = points.last() {
+                let dt = (*time - prev.time).inner_seconds();
+                let dist = prev.gps.fast_dist(*gps).inner_meters();
+                if dt <= 0.0 || dist / dt > MAX_PLAUSIBLE_SPEED {
+                    continue;
+                }
+            }
+            points.push(Point {
+                time: *time,
+                gps: *gps,
+                pt: gps.to_pt(map.get_gps_bounds()),
+            });
+        }
+        points
+    }
+}
+
+/// Everything needed to match points to roads
+pub fn closest_roads(map: &Map) -> FindClosest<RoadID> {
+    let mut closest = FindClosest::new();
+    for r in map.all_roads() {
+        closest.add(r.id, r.center_pts.points());
+    }
+    closest
+}
+
+struct Point {
+    time: Time,
+    gps: LonLat,
+    pt: Pt2D,
+}
+
+/// Based on "Mining user similarity based on location history" (Li et al, 2008). Returns
+/// (arrival, departure) indices.
+fn find_stays(points: &[Point], min_dwell: Duration) -> Vec<(usize, usize)> {
+    let mut stays = Vec::new();
+    let mut i = 0;
+    while i < points.len() {
+        let mut j = i + 1;
+        while j < points.len() && points[i].gps.fast_dist(points[j].gps) <= STAY_RADIUS {
+            j += 1;
+        }
+        if points[j - 1].time - points[i].time >= min_dwell {
+            stays.push((i, j - 1));
+            i = j;
+        } else {
+            i += 1;
+        }
+    }
+    stays
+}
+
+fn infer_mode(segment: &[Point], map: &Map, roads: &FindClosest<RoadID>) -> TripMode {
+    // Which sort of roads does the trip follow?
+    let mut matched = 0;
+    let mut on_rail = 0;
+    let mut car_free = 0;
+    for pt in segment {
+        if let Some((r, _)) = roads.closest_pt(pt.pt, MAX_MATCH_DIST) {
+            matched += 1;
+            let road = map.get_r(r);
+            if road.is_light_rail() {
+                on_rail += 1;
+            } else if !road.is_driveable() {
+                car_free += 1;
+            }
+        }
+    }
+    if matched > 0 && 2 * on_rail > matched {
+        return TripMode::Transit;
+    }
+
+    // Use a high percentile, so waiting at junctions doesn't matter
+    let mut speeds: Vec<f64> = segment
+        .windows(2)
+        .filter_map(|pts| {
+            let dt = (pts[1].time - pts[0].time).inner_seconds();
+            if dt > 0.0 {
+                Some(pts[0].gps.fast_dist(pts[1].gps).inner_meters() / dt)
+            } else {
+                None
+            }
+        })
+        .collect();
+    speeds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let speed = speeds.get(speeds.len() * 85 / 100).cloned().unwrap_or(0.0);
+
+    if speed <= MAX_WALKING_SPEED {
+        TripMode::Walk
+    } else if speed <= MAX_BIKING_SPEED || (matched > 0 && 2 * car_free > matched) {
+        TripMode::Bike
+    } else {
+        TripMode::Drive
+    }
+}
+
+/// Returns the local day, counted from 1970-01-01, and the local time of day from something like
+/// `2023-05-04T08:12:33Z` or `2023-05-04T08:12:33.250+01:00`. A timestamp with a numeric offset is
+/// already in local time. `utc_offset` is added to timestamps in UTC, ending in `Z` or without a
+/// zone. That can move the time into the previous or next day.
+fn parse_timestamp(input: &str, utc_offset: Duration) -> Option<(i64, Time)> {
+    let (date, rest) = input.trim().split_once('T')?;
+    let date: Vec<i64> = date
+        .split('-')
+        .map(|x| x.parse::<i64>())
+        .collect::<Result<_, _>>()
+        .ok()?;
+    if date.len() != 3 {
+        return None;
+    }
+
+    let clock: String = rest
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == ':' || *c == '.')
+        .collect();
+    let parts: Vec<&str> = clock.split(':').collect();
+    if parts.len() != 3 {
+        return None;
+    }
+    let hours = parts[0].parse::<f64>().ok()?;
+    let minutes = parts[1].parse::<f64>().ok()?;
+    let seconds = parts[2].parse::<f64>().ok()?;
+
+    let offset = match &rest[clock.len()..] {
+        "" | "Z" => utc_offset.inner_seconds(),
+        zone => {
+            // +hh:mm, +hhmm, or +hh. Just check it's well-formed.
+            let sign = zone.chars().next()?;
+            let digits: String = zone[1..].chars().filter(|c| *c != ':').collect();
+            if (sign != '+' && sign != '-')
+                || !(digits.len() == 2 || digits.len() == 4)
+                || !digits.chars().all(|c| c.is_ascii_digit())
+            {
+                return None;
+            }
+            0.0
+        }
+    };
+
+    // Work in signed seconds, since the offset may be negative
+    let total = 3600.0 * hours + 60.0 * minutes + seconds + offset;
+    let days = (total / 86400.0).floor();
+    let day = days_since_epoch(date[0], date[1], date[2]) + days as i64;
+    Some((
+        day,
+        Time::START_OF_DAY + Duration::seconds(total - 86400.0 * days),
+    ))
+}
+
+/// From http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_since_epoch(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str, utc_offset_hours: f64) -> (i64, Time) {
+        parse_timestamp(input, Duration::seconds(3600.0 * utc_offset_hours)).unwrap()
+    }
+
+    fn hms(hours: usize, minutes: usize) -> Time {
+        Time::START_OF_DAY + Duration::hours(hours) + Duration::minutes(minutes)
+    }
+
+    #[test]
+    fn test_utc() {
+        let day = days_since_epoch(2023, 5, 4);
+        assert_eq!(parse("2023-05-04T08:12:00Z", 0.0), (day, hms(8, 12)));
+        assert_eq!(parse("2023-05-04T08:12:00", 1.0), (day, hms(9, 12)));
+        assert_eq!(days_since_epoch(1970, 1, 1), 0);
+        assert_eq!(days_since_epoch(2000, 3, 1), 11017);
+    }
+
+    #[test]
+    fn test_negative_offset() {
+        let day = days_since_epoch(2023, 5, 4);
+        assert_eq!(parse("2023-05-04T15:30:00Z", -7.0), (day, hms(8, 30)));
+        // Early in the morning in UTC is the previous evening in Seattle
+        assert_eq!(parse("2023-05-04T02:00:00Z", -7.0), (day - 1, hms(19, 0)));
+        // Across a month boundary
+        assert_eq!(
+            parse("2023-06-01T03:00:00Z", -7.0),
+            (days_since_epoch(2023, 5, 31), hms(20, 0))
+        );
+    }
+
+    #[test]
+    fn test_explicit_zone() {
+        let day = days_since_epoch(2023, 5, 4);
+        // The offset from the command line only applies to UTC
+        assert_eq!(parse("2023-05-04T08:12:00+01:00", -7.0), (day, hms(8, 12)));
+        assert_eq!(parse("2023-05-04T08:12:00.250-0700", 3.0).0, day);
+        assert!(parse_timestamp("2023-05-04T08:12:00 PST", Duration::ZERO).is_none());
+    }
+
+    #[test]
+    fn test_points_before_midnight() {
+        let pt = LonLat::new(-122.3, 47.6);
+        let raw = vec![
+            ("2023-05-04T06:50:00Z".to_string(), pt),
+            ("2023-05-04T15:00:00Z".to_string(), pt),
+            ("2023-05-04T16:00:00Z".to_string(), pt),
+        ];
+        // 06:50 UTC is 23:50 the previous day in Seattle, so it's dropped instead of panicking
+        let trace = GpsTrace::new("x".to_string(), raw, Duration::seconds(-7.0 * 3600.0)).unwrap();
+        assert_eq!(
+            trace.points.iter().map(|(t, _)| *t).collect::<Vec<_>>(),
+            vec![hms(8, 0), hms(9, 0)]
+        );
+    }
+}
@@ -19,14 +19,19 @@ pub use self::borders::{MapBorder, MapBorders};
 pub use self::counts::TrafficCounts;
 pub use self::endpoint::TripEndpoint;
 pub use self::external::{ExternalPerson, ExternalTrip, ExternalTripEndpoint};
+pub use self::fleet::{FleetMix, VehicleClass};
+pub use self::gps::{closest_roads, GpsTrace};
 pub use self::modifier::ScenarioModifier;
-pub use self::scenario::{IndividTrip, PersonSpec, Scenario, TripPurpose};
+pub use self::scenario::{IndividTrip, PersonSpec, Scenario, TripPurpose, SCENARIO_FORMAT_VERSION};
 
 mod borders;
 mod counts;
 mod endpoint;
 mod external;
+mod fleet;
+mod gps;
 pub mod make;
+mod matsim;
 mod modifier;
 mod scenario;
 
@@ -14,7 +14,7 @@ use map_model::{BuildingID, BuildingType, Map, PathConstraints, PathRequest};
 
 use crate::{IndividTrip, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose};
 
-use crate::make::{fork_rng, ScenarioGenerator};
+use crate::make::{fork_rng, transit_fare_multiplier, ScenarioGenerator};
 
 impl ScenarioGenerator {
     /// Designed in https://github.com/a-b-street/abstreet/issues/154
@@ -233,7 +233,7 @@ fn create_prole(
             // TODO If home or work is in an access-restricted zone (like a living street),
             // then probably don't drive there. Actually, it depends on the specific tagging;
             // access=no in the US usually means a gated community.
-            select_trip_mode(dist, rng)
+            select_trip_mode(dist, map, rng)
         }
         // if you exit or leave the map, we assume driving
         _ => TripMode::Drive,
@@ -276,7 +276,7 @@ fn create_prole(
     })
 }
 
-fn select_trip_mode(distance: Distance, rng: &mut XorShiftRng) -> TripMode {
+fn select_trip_mode(distance: Distance, map: &Map, rng: &mut XorShiftRng) -> TripMode {
     // TODO Make this probabilistic
     // for example probability of walking currently has massive differences
     // at thresholds, it would be nicer to change this gradually
@@ -306,8 +306,8 @@ fn select_trip_mode(distance: Distance, rng: &mut XorShiftRng) -> TripMode {
     if rng.gen_bool(0.005) {
         return TripMode::Bike;
     }
-    // Try transit if available, or fallback to walking
-    if rng.gen_bool(0.3) {
+    // Try transit if available, or fallback to walking. Expensive fares push people to drive.
+    if rng.gen_bool(0.3 * transit_fare_multiplier(map)) {
         return TripMode::Transit;
     }
 
@@ -0,0 +1,127 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use geom::{Distance, Duration, Speed, Time};
+use map_model::{BuildingID, Map};
+
+use crate::{IndividTrip, PersonSpec, TripEndpoint, TripMode, TripPurpose};
+
+/// A large event at one building, like a game at a stadium or a concert. Attendees come from homes
+/// all over the map, arrive in the hours before the event starts, and mostly leave together when
+/// it ends.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct EventSpec {
+    pub venue: BuildingID,
+    pub attendance: usize,
+    pub start: Time,
+    pub end: Time,
+    /// Attendees arrive over this long before the start, with most arriving close to it
+    pub arrival_spread: Duration,
+    /// Attendees leave over this long after the end, with most leaving right away
+    pub departure_spread: Duration,
+    /// Relative weights of how attendees travel. Everybody goes home the same way they came.
+    pub mode_split: Vec<(TripMode, usize)>,
+}
+
+impl EventSpec {
+    /// Generate a round trip for every attendee.
+    pub fn generate(&self, map: &Map, rng: &mut XorShiftRng) -> Vec<PersonSpec> {
+        let mut homes: Vec<BuildingID> = map
+            .all_buildings()
+            .iter()
+            .filter(|b| b.id != self.venue && b.bldg_type.has_residents())
+            .map(|b| b.id)
+            .collect();
+        if homes.is_empty() {
+            homes = map
+                .all_buildings()
+                .iter()
+                .filter(|b| b.id != self.venue)
+                .map(|b| b.id)
+                .collect();
+        }
+        let total_weight: usize = self.mode_split.iter().map(|(_, w)| *w).sum();
+        if homes.is_empty() || total_weight == 0 {
+            warn!("Can't generate trips for an event at {}", self.venue);
+            return Vec::new();
+        }
+        let venue_pt = map.get_b(self.venue).polygon.center();
+
+        let mut people = Vec::new();
+        for _ in 0..self.attendance {
+            let home = *homes.choose(rng).unwrap();
+            let mode = pick_mode(&self.mode_split, total_weight, rng);
+
+            // Squaring a uniform sample bunches people up near the start and end of the event
+            let arrive = self
+                .start
+                .clamped_sub(self.arrival_spread * rng.gen::<f64>().powi(2));
+            let depart = arrive.clamped_sub(estimated_travel_time(
+                map.get_b(home).polygon.center().dist_to(venue_pt),
+                mode,
+            ));
+            let leave = self.end + self.departure_spread * rng.gen::<f64>().powi(2);
+
+            people.push(PersonSpec {
+                orig_id: None,
+                trips: vec![
+                    IndividTrip::new(
+                        depart,
+                        TripPurpose::Recreation,
+                        TripEndpoint::Building(home),
+                        TripEndpoint::Building(self.venue),
+                        mode,
+                    ),
+                    IndividTrip::new(
+                        leave,
+                        TripPurpose::Home,
+                        TripEndpoint::Building(self.venue),
+                        TripEndpoint::Building(home),
+                        mode,
+                    ),
+                ],
+            });
+        }
+        people
+    }
+
+    pub fn describe(&self, map: &Map) -> String {
+        format!(
+            "an event at {} for {} people, from {} to {}",
+            map.get_b(self.venue).address,
+            abstutil::prettyprint_usize(self.attendance),
+            self.start.ampm_tostring(),
+            self.end.ampm_tostring()
+        )
+    }
+}
+
+fn pick_mode(
+    mode_split: &[(TripMode, usize)],
+    total_weight: usize,
+    rng: &mut XorShiftRng,
+) -> TripMode {
+    let mut x = rng.gen_range(0..total_weight);
+    for (mode, weight) in mode_split {
+        if x < *weight {
+            return *mode;
+        }
+        x -= weight;
+    }
+    mode_split.last().unwrap().0
+}
+
+/// A rough guess of how long it takes to travel a straight-line distance, so attendees leave home
+/// early enough to make it before the event starts
+fn estimated_travel_time(dist: Distance, mode: TripMode) -> Duration {
+    let speed = match mode {
+        TripMode::Walk => Speed::meters_per_second(1.34),
+        TripMode::Bike => Speed::miles_per_hour(10.0),
+        TripMode::Transit => Speed::miles_per_hour(12.0),
+        TripMode::Drive => Speed::miles_per_hour(20.0),
+    };
+    // Routes aren't straight lines
+    1.5 * (dist / speed)
+}
@@ -3,9 +3,13 @@
 use rand::{RngCore, SeedableRng};
 use rand_xorshift::XorShiftRng;
 
+use map_model::Map;
+
+pub use self::event::EventSpec;
 pub use self::generator::{BorderSpawnOverTime, ScenarioGenerator, SpawnOverTime};
 
 mod activity_model;
+mod event;
 mod generator;
 
 /// Need to explain this trick -- basically keeps consistency between two different simulations when
@@ -13,3 +17,19 @@ mod generator;
 pub fn fork_rng(base_rng: &mut XorShiftRng) -> XorShiftRng {
     XorShiftRng::seed_from_u64(base_rng.next_u64())
 }
+
+/// Scales down the base probability of choosing transit when riding costs money. Free transit (the
+/// default, since fares aren't imported yet) leaves the probability unchanged, and every $10 of a
+/// typical fare cuts it by a factor of e.
+pub fn transit_fare_multiplier(map: &Map) -> f64 {
+    let routes = map.all_transit_routes();
+    if routes.is_empty() {
+        return 1.0;
+    }
+    let avg_cents = routes
+        .iter()
+        .map(|r| r.fare.typical_price() as f64)
+        .sum::<f64>()
+        / (routes.len() as f64);
+    (-avg_cents / 1000.0).exp()
+}
@@ -0,0 +1,285 @@
+//! Import travel demand from MATSim population files
+//! (https://www.matsim.org/files/dtd/population_v6.dtd).
+
+use anyhow::Result;
+
+use geom::{Duration, LonLat, Time};
+
+use crate::{ExternalPerson, ExternalTrip, ExternalTripEndpoint, TripMode, TripPurpose};
+
+impl ExternalPerson {
+    /// Parse the selected plan of every person in a MATSim population XML file. Each leg between
+    /// two activities becomes a trip. Activity coordinates must be WGS84 longitude/latitude;
+    /// MATSim scenarios usually use a projected coordinate system, so transform the file first.
+    /// Activities without coordinates and legs using unknown modes are skipped.
+    pub fn parse_matsim_plans(xml: &str) -> Result<Vec<ExternalPerson>> {
+        let doc = roxmltree::Document::parse(xml)?;
+        let mut people = Vec::new();
+        for person in doc.descendants().filter(|n| n.has_tag_name("person")) {
+            let plans: Vec<_> = person
+                .children()
+                .filter(|n| n.has_tag_name("plan"))
+                .collect();
+            // When there's no selected plan, just use the first
+            let plan = match plans
+                .iter()
+                .find(|plan| plan.attribute("selected") == Some("yes"))
+                .or_else(|| plans.get(0))
+            {
+                Some(plan) => plan,
+                None => continue,
+            };
+
+            let mut trips = Vec::new();
+            let mut prev_activity: Option<Activity> = None;
+            // Routed plans split one trip into several legs, with "interaction" activities
+            // between them.
+            let mut legs: Vec<(Option<Time>, Option<TripMode>)> = Vec::new();
+            for node in plan.children() {
+                if node.has_tag_name("act") || node.has_tag_name("activity") {
+                    if node
+                        .attribute("type")
+                        .map(|x| x.ends_with("interaction"))
+                        .unwrap_or(false)
+                    {
+                        continue;
+                    }
+                    let activity = Activity::parse(node)?;
+                    // A trip's main mode is the "biggest" one used; walking to a bus stop or
+                    // parked car shouldn't count
+                    let mode = legs.iter().filter_map(|(_, mode)| *mode).max();
+                    let dep_time = legs.get(0).and_then(|(time, _)| *time);
+                    if let (Some(prev), Some(mode)) = (prev_activity.as_ref(), mode) {
+                        if let (Some(origin), Some(destination), Some(departure)) =
+                            (prev.pos, activity.pos, dep_time.or_else(|| prev.end_time()))
+                        {
+                            trips.push(ExternalTrip {
+                                departure,
+                                origin: ExternalTripEndpoint::Position(origin),
+                                destination: ExternalTripEndpoint::Position(destination),
+                                mode,
+                                purpose: activity.purpose,
+                            });
+                        }
+                    }
+                    legs.clear();
+                    prev_activity = Some(activity);
+                } else if node.has_tag_name("leg") {
+                    let dep_time = node.attribute("dep_time").map(parse_time).transpose()?;
+                    let mode = node.attribute("mode").and_then(parse_mode);
+                    legs.push((dep_time, mode));
+                }
+            }
+            if !trips.is_empty() {
+                people.push(ExternalPerson { trips });
+            }
+        }
+        Ok(people)
+    }
+}
+
+struct Activity {
+    pos: Option<LonLat>,
+    purpose: TripPurpose,
+    start_time: Option<Time>,
+    end_time: Option<Time>,
+    duration: Option<Duration>,
+}
+
+impl Activity {
+    fn parse(node: roxmltree::Node) -> Result<Activity> {
+        let pos = match (node.attribute("x"), node.attribute("y")) {
+            (Some(x), Some(y)) => {
+                let (lon, lat) = (x.parse::<f64>()?, y.parse::<f64>()?);
+                if !(-180.0..=180.0).contains(&lon) || !(-90.0..=90.0).contains(&lat) {
+                    bail!(
+                        "Activity at ({}, {}) isn't in WGS84; reproject the population file",
+                        x,
+                        y
+                    );
+                }
+                Some(LonLat::new(lon, lat))
+            }
+            _ => None,
+        };
+        Ok(Activity {
+            pos,
+            purpose: parse_purpose(node.attribute("type").unwrap_or("")),
+            start_time: node.attribute("start_time").map(parse_time).transpose()?,
+            end_time: node.attribute("end_time").map(parse_time).transpose()?,
+            duration: node
+                .attribute("max_dur")
+                .or_else(|| node.attribute("dur"))
+                .map(|x| parse_time(x).map(|t| t - Time::START_OF_DAY))
+                .transpose()?,
+        })
+    }
+
+    fn end_time(&self) -> Option<Time> {
+        self.end_time
+            .or_else(|| Some(self.start_time? + self.duration?))
+    }
+}
+
+/// Times look like 07:30:00, and may exceed 24 hours.
+fn parse_time(input: &str) -> Result<Time> {
+    let parts = input
+        .split(':')
+        .map(|x| x.parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()?;
+    if parts.len() != 3 {
+        bail!("Unknown time format {}", input);
+    }
+    Ok(Time::START_OF_DAY + Duration::seconds(parts[0] * 3600.0 + parts[1] * 60.0 + parts[2]))
+}
+
+fn parse_mode(mode: &str) -> Option<TripMode> {
+    match mode {
+        "walk" | "transit_walk" | "non_network_walk" => Some(TripMode::Walk),
+        "bike" | "bicycle" => Some(TripMode::Bike),
+        "pt" | "bus" | "tram" | "rail" | "train" | "subway" => Some(TripMode::Transit),
+        // Passengers are modelled as drivers, since A/B Street doesn't simulate carpooling
+        "car" | "ride" | "car_passenger" => Some(TripMode::Drive),
+        _ => {
+            warn!("Skipping leg with unknown mode {}", mode);
+            None
+        }
+    }
+}
+
+/// Activity types are scenario-specific. Some are single letters, and many have a duration suffix
+/// like `work_3600`, so just look at the start.
+fn parse_purpose(activity: &str) -> TripPurpose {
+    let activity = activity.to_lowercase();
+    let starts = |prefixes: &[&str]| prefixes.iter().any(|x| activity.starts_with(x));
+    if activity == "h" || starts(&["home"]) {
+        TripPurpose::Home
+    } else if activity == "w" || starts(&["work"]) {
+        TripPurpose::Work
+    } else if activity == "e" || starts(&["edu", "school", "univ", "kindergarten"]) {
+        TripPurpose::School
+    } else if activity == "s" || starts(&["shop"]) {
+        TripPurpose::Shopping
+    } else if activity == "l" || starts(&["leisure", "recreation", "sport"]) {
+        TripPurpose::Recreation
+    } else if starts(&["visit", "social"]) {
+        TripPurpose::Social
+    } else if starts(&["dining", "meal", "restaurant", "eat"]) {
+        TripPurpose::Meal
+    } else if starts(&["health", "medical", "doctor"]) {
+        TripPurpose::Medical
+    } else if starts(&["escort", "pickup", "dropoff"]) {
+        TripPurpose::Escort
+    } else {
+        TripPurpose::PersonalBusiness
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLANS: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<!DOCTYPE population SYSTEM "http://www.matsim.org/files/dtd/population_v6.dtd">
+<population>
+    <person id="1">
+        <plan selected="no">
+            <activity type="home" x="-122.30" y="47.60" end_time="09:00:00" />
+            <leg mode="bike" />
+            <activity type="work" x="-122.31" y="47.61" />
+        </plan>
+        <plan selected="yes">
+            <activity type="home" x="-122.30" y="47.60" end_time="07:30:00" />
+            <leg mode="walk" dep_time="07:30:00" />
+            <activity type="car interaction" x="-122.30" y="47.60" max_dur="00:00:00" />
+            <leg mode="car" dep_time="07:32:00" />
+            <activity type="car interaction" x="-122.31" y="47.61" max_dur="00:00:00" />
+            <leg mode="walk" dep_time="07:50:00" />
+            <activity type="work_3600" x="-122.31" y="47.61" end_time="17:00:00" />
+            <leg mode="bike" />
+            <activity type="s" x="-122.32" y="47.62" start_time="17:20:00" max_dur="00:30:00" />
+            <leg mode="pt" />
+            <activity type="home" x="-122.30" y="47.60" end_time="25:00:00" />
+            <leg mode="walk" dep_time="25:15:00" />
+            <activity type="leisure" x="-122.29" y="47.59" />
+            <leg mode="hovercraft" dep_time="26:00:00" />
+            <activity type="home" x="-122.30" y="47.60" />
+        </plan>
+    </person>
+    <person id="2">
+        <plan>
+            <activity type="home" link="1" end_time="08:00:00" />
+            <leg mode="car" />
+            <activity type="work" link="2" />
+        </plan>
+    </person>
+</population>"#;
+
+    fn hms(hours: usize, minutes: usize) -> Time {
+        Time::START_OF_DAY + Duration::hours(hours) + Duration::minutes(minutes)
+    }
+
+    fn pos(endpoint: &ExternalTripEndpoint) -> LonLat {
+        match endpoint {
+            ExternalTripEndpoint::Position(pt) => *pt,
+            ExternalTripEndpoint::TripEndpoint(_) => panic!("expected a position"),
+        }
+    }
+
+    #[test]
+    fn test_parse_plans() {
+        let people = ExternalPerson::parse_matsim_plans(PLANS).unwrap();
+        // The second person's activities don't have coordinates
+        assert_eq!(people.len(), 1);
+        let trips = &people[0].trips;
+
+        // Only the selected plan is used, and the leg with an unknown mode is skipped
+        assert_eq!(
+            trips.iter().map(|t| t.mode).collect::<Vec<_>>(),
+            vec![
+                TripMode::Drive,
+                TripMode::Bike,
+                TripMode::Transit,
+                TripMode::Walk
+            ]
+        );
+        // Departure times come from the first leg, then the previous activity's end time, then
+        // its start time plus duration
+        assert_eq!(
+            trips.iter().map(|t| t.departure).collect::<Vec<_>>(),
+            vec![hms(7, 30), hms(17, 0), hms(17, 50), hms(25, 15)]
+        );
+
+        // The walking legs to and from the car are folded into one trip
+        assert_eq!(pos(&trips[0].origin), LonLat::new(-122.30, 47.60));
+        assert_eq!(pos(&trips[0].destination), LonLat::new(-122.31, 47.61));
+        assert_eq!(pos(&trips[1].destination), LonLat::new(-122.32, 47.62));
+        assert_eq!(pos(&trips[3].destination), LonLat::new(-122.29, 47.59));
+
+        assert!(matches!(trips[0].purpose, TripPurpose::Work));
+        assert!(matches!(trips[1].purpose, TripPurpose::Shopping));
+        assert!(matches!(trips[2].purpose, TripPurpose::Home));
+        assert!(matches!(trips[3].purpose, TripPurpose::Recreation));
+    }
+
+    #[test]
+    fn test_projected_coordinates() {
+        let xml = r#"<population><person id="1"><plan>
+            <activity type="home" x="552000.0" y="5272000.0" end_time="08:00:00" />
+            <leg mode="car" />
+            <activity type="work" x="553000.0" y="5273000.0" />
+        </plan></person></population>"#;
+        assert!(ExternalPerson::parse_matsim_plans(xml).is_err());
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(
+            parse_time("07:30:15").unwrap(),
+            hms(7, 30) + Duration::seconds(15.0)
+        );
+        // After midnight on the next day
+        assert_eq!(parse_time("27:05:00").unwrap(), hms(27, 5));
+        assert!(parse_time("07:30").is_err());
+    }
+}
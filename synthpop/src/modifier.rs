@@ -10,7 +10,8 @@ use abstutil::Timer;
 use geom::{Duration, Time};
 use map_model::Map;
 
-use crate::{Scenario, TripMode};
+use crate::make::EventSpec;
+use crate::{FleetMix, Scenario, TripMode};
 
 /// Transforms an existing Scenario before instantiating it.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
@@ -29,6 +30,10 @@ pub enum ScenarioModifier {
     },
     /// Scenario name
     AddExtraTrips(String),
+    /// Replace the classes of vehicle people drive
+    SetFleetMix(FleetMix),
+    /// Add a surge of trips to and from a large event
+    AddEvent(EventSpec),
 }
 
 impl ScenarioModifier {
@@ -96,6 +101,19 @@ impl ScenarioModifier {
                 }
                 s
             }
+            ScenarioModifier::SetFleetMix(fleet_mix) => {
+                s.fleet_mix = fleet_mix.clone();
+                s
+            }
+            ScenarioModifier::AddEvent(event) => {
+                for mut p in event.generate(map, rng) {
+                    for trip in &mut p.trips {
+                        trip.modified = true;
+                    }
+                    s.people.push(p);
+                }
+                s
+            }
         }
     }
 
@@ -124,6 +142,15 @@ impl ScenarioModifier {
                 to_mode.map(|m| m.verb())
             ),
             ScenarioModifier::AddExtraTrips(name) => format!("Add extra trips from {}", name),
+            ScenarioModifier::SetFleetMix(fleet_mix) => {
+                format!("people drive {}", fleet_mix.describe())
+            }
+            ScenarioModifier::AddEvent(event) => format!(
+                "add an event for {} people from {} to {}",
+                abstutil::prettyprint_usize(event.attendance),
+                event.start.ampm_tostring(),
+                event.end.ampm_tostring()
+            ),
         }
     }
 }
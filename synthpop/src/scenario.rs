@@ -5,21 +5,30 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use abstio::{CityName, MapName};
-use abstutil::prettyprint_usize;
+use abstutil::{prettyprint_usize, FormatVersion};
 use geom::Time;
 use map_model::Map;
 
-use crate::{OrigPersonID, TripEndpoint, TripMode};
+use crate::{FleetMix, OrigPersonID, TripEndpoint, TripMode};
+
+/// Bump this whenever the binary layout of `Scenario` or anything inside it changes, like adding a
+/// field to `FleetMix`. Scenarios with a different version can't be loaded, and must be imported
+/// again.
+pub const SCENARIO_FORMAT_VERSION: u32 = 1;
 
 /// A Scenario describes all the input to a simulation. Usually a scenario covers one day.
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Scenario {
+    /// Must come first
+    pub format_version: FormatVersion<SCENARIO_FORMAT_VERSION>,
     pub scenario_name: String,
     pub map_name: MapName,
 
     pub people: Vec<PersonSpec>,
     /// None means seed all buses. Otherwise the route name must be present here.
     pub only_seed_buses: Option<BTreeSet<String>>,
+    /// Which classes of vehicle people drive
+    pub fleet_mix: FleetMix,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -113,10 +122,12 @@ impl Scenario {
 
     pub fn empty(map: &Map, name: &str) -> Scenario {
         Scenario {
+            format_version: FormatVersion,
             scenario_name: name.to_string(),
             map_name: map.get_name().clone(),
             people: Vec::new(),
             only_seed_buses: Some(BTreeSet::new()),
+            fleet_mix: FleetMix::default(),
         }
     }
 
@@ -139,6 +150,16 @@ impl Scenario {
         self
     }
 
+    /// Skip every trip departing before some time. People start their remaining day from wherever
+    /// their next trip begins, and anybody with nothing left to do is removed.
+    pub fn remove_trips_before(mut self, time: Time) -> Scenario {
+        for person in &mut self.people {
+            person.trips.retain(|trip| trip.depart >= time);
+        }
+        self.people.retain(|person| !person.trips.is_empty());
+        self
+    }
+
     pub fn all_trips(&self) -> impl Iterator<Item = &IndividTrip> {
         self.people.iter().flat_map(|p| p.trips.iter())
     }
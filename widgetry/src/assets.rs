@@ -8,6 +8,7 @@ use usvg_text_layout::fontdb;
 use geom::Bounds;
 
 use crate::text::Font;
+use crate::tools::Translations;
 use crate::{text, EventCtx, GeomBatch, GfxCtx, Prerender, Style};
 
 // TODO We don't need refcell maybe? Can we take &mut Assets?
@@ -20,6 +21,7 @@ pub struct Assets {
     font_to_id: HashMap<Font, fontdb::ID>,
     extra_fonts: RefCell<HashSet<String>>,
     pub(crate) style: RefCell<Style>,
+    pub(crate) translations: RefCell<Translations>,
     pub(crate) fontdb: RefCell<fontdb::Database>,
     pub read_svg: Box<dyn Fn(&str) -> Vec<u8>>,
     base_url: Option<String>,
@@ -78,6 +80,7 @@ impl Assets {
             extra_fonts: RefCell::new(HashSet::new()),
             fontdb: RefCell::new(fontdb),
             style: RefCell::new(style),
+            translations: RefCell::new(Translations::english()),
             base_url,
             are_gzipped,
             read_svg,
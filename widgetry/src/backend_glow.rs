@@ -172,11 +172,16 @@ pub struct Drawable {
     elem_buffer: Buffer,
     num_indices: i32,
     gl: Rc<glow::Context>,
+    // Shared with PrerenderInnards, to track how much is still on the GPU
+    num_bytes: usize,
+    gpu_bytes_in_use: Rc<Cell<usize>>,
 }
 
 impl Drop for Drawable {
     #[inline]
     fn drop(&mut self) {
+        self.gpu_bytes_in_use
+            .set(self.gpu_bytes_in_use.get() - self.num_bytes);
         self.elem_buffer.destroy(&self.gl);
         self.vert_buffer.destroy(&self.gl);
         self.vert_array.destroy(&self.gl);
@@ -271,6 +276,7 @@ pub struct PrerenderInnards {
     // TODO Prerender doesn't know what things are temporary and permanent. Could make the API more
     // detailed.
     pub total_bytes_uploaded: Cell<usize>,
+    pub gpu_bytes_in_use: Rc<Cell<usize>>,
 }
 
 impl PrerenderInnards {
@@ -286,6 +292,7 @@ impl PrerenderInnards {
             program,
             window_adapter,
             total_bytes_uploaded: Cell::new(0),
+            gpu_bytes_in_use: Rc::new(Cell::new(0)),
         }
     }
 
@@ -367,13 +374,14 @@ impl PrerenderInnards {
         };
         let num_indices = indices.len() as i32;
 
+        let num_bytes =
+            std::mem::size_of_val(vertices.as_slice()) + std::mem::size_of_val(indices.as_slice());
         if permanent {
-            /*self.total_bytes_uploaded.set(
-                self.total_bytes_uploaded.get()
-                    + vertex_buffer.get_size()
-                    + index_buffer.get_size(),
-            );*/
+            self.total_bytes_uploaded
+                .set(self.total_bytes_uploaded.get() + num_bytes);
         }
+        self.gpu_bytes_in_use
+            .set(self.gpu_bytes_in_use.get() + num_bytes);
 
         Drawable {
             vert_array,
@@ -381,6 +389,8 @@ impl PrerenderInnards {
             elem_buffer,
             num_indices,
             gl: self.gl.clone(),
+            num_bytes,
+            gpu_bytes_in_use: self.gpu_bytes_in_use.clone(),
         }
     }
 
@@ -1,10 +1,12 @@
 use std::cell::RefCell;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 
+use instant::Instant;
 use serde::{Deserialize, Serialize};
 
 use geom::{Bounds, Pt2D};
 
+use crate::widgets::{load_panel_layouts, PanelLayout};
 use crate::{Key, ScreenDims, ScreenPt, ScreenRectangle, UpdateType, UserInput};
 
 // Click and release counts as a normal click, not a drag, if the distance between click and
@@ -15,6 +17,9 @@ const PAN_SPEED: f64 = 15.0;
 
 const PANNING_THRESHOLD: f64 = 25.0;
 
+// Tooltips that follow the mouse only appear once the cursor rests this long.
+const TOOLTIP_DELAY: instant::Duration = instant::Duration::from_millis(400);
+
 pub struct Canvas {
     // All of these f64's are in screen-space, so do NOT use Pt2D.
     // Public for saving/loading... should probably do better
@@ -25,10 +30,17 @@ pub struct Canvas {
     // TODO Should this become Option<ScreenPt>?
     pub(crate) cursor: ScreenPt,
     pub(crate) window_has_cursor: bool,
+    pub(crate) cursor_moved_at: Instant,
+    // In "what's this?" mode, tooltips appear immediately, and clicking buttons just explains
+    // them.
+    pub(crate) whats_this: bool,
 
     // Only for drags starting on the map. Only used to pan the map. (Last event, original)
     pub(crate) drag_canvas_from: Option<(ScreenPt, ScreenPt)>,
     drag_just_ended: bool,
+    // Did the current drag include pinching to zoom? If so, releasing isn't a click, no matter
+    // how little the cursor moved.
+    pinched: bool,
 
     pub window_width: f64,
     pub window_height: f64,
@@ -42,6 +54,14 @@ pub struct Canvas {
 
     // Kind of just widgetry state awkwardly stuck here...
     pub(crate) keys_held: HashSet<Key>,
+    // How the player arranged dockable panels, keyed by ID. Loaded once and saved whenever it
+    // changes.
+    pub(crate) panel_layouts: BTreeMap<String, PanelLayout>,
+    // The one widget on the whole screen with keyboard focus, as (panel ID, widget name). Like
+    // focus_owned_by, the panel has to renew this during every event, or it expires.
+    pub(crate) keyboard_focus: Option<(usize, String)>,
+    pub(crate) keyboard_focus_renewed: bool,
+    pub(crate) next_panel_id: usize,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -83,9 +103,12 @@ impl Canvas {
 
             cursor: ScreenPt::new(0.0, 0.0),
             window_has_cursor: true,
+            cursor_moved_at: Instant::now(),
+            whats_this: false,
 
             drag_canvas_from: None,
             drag_just_ended: false,
+            pinched: false,
 
             window_width: initial_dims.width,
             window_height: initial_dims.height,
@@ -96,6 +119,33 @@ impl Canvas {
             covered_areas: RefCell::new(Vec::new()),
 
             keys_held: HashSet::new(),
+            panel_layouts: load_panel_layouts(),
+            keyboard_focus: None,
+            keyboard_focus_renewed: false,
+            next_panel_id: 0,
+        }
+    }
+
+    /// Is the player exploring the UI in "what's this?" mode? Toggled with Shift+F1.
+    pub fn is_whats_this_mode(&self) -> bool {
+        self.whats_this
+    }
+
+    pub fn set_whats_this_mode(&mut self, enabled: bool) {
+        self.whats_this = enabled;
+    }
+
+    /// Should tooltips following the mouse be drawn yet?
+    pub(crate) fn tooltip_ready(&self) -> bool {
+        self.whats_this || self.cursor_moved_at.elapsed() >= TOOLTIP_DELAY
+    }
+
+    /// When a tooltip following the mouse will be ready, if it isn't yet
+    pub(crate) fn tooltip_deadline(&self) -> Option<Instant> {
+        if self.tooltip_ready() {
+            None
+        } else {
+            Some(self.cursor_moved_at + TOOLTIP_DELAY)
         }
     }
 
@@ -172,6 +222,16 @@ impl Canvas {
             }
         }
 
+        // Like dragging, pinching that starts on the map can continue off of it
+        if let Some(ratio) = input.get_pinch() {
+            if self.drag_canvas_from.is_some() || self.get_cursor_in_map_space().is_some() {
+                self.zoom_by(ratio, self.cursor);
+                if self.drag_canvas_from.is_some() {
+                    self.pinched = true;
+                }
+            }
+        }
+
         // If we start the drag on the map and move the mouse off the map, keep dragging.
         if let Some((click, orig)) = self.drag_canvas_from {
             let pt = self.get_cursor();
@@ -182,9 +242,10 @@ impl Canvas {
             if input.left_mouse_button_released() {
                 let (_, orig) = self.drag_canvas_from.take().unwrap();
                 let dist = ((pt.x - orig.x).powi(2) + (pt.y - orig.y).powi(2)).sqrt();
-                if dist > DRAG_THRESHOLD {
+                if dist > DRAG_THRESHOLD || self.pinched {
                     self.drag_just_ended = true;
                 }
+                self.pinched = false;
             }
         } else if self.drag_just_ended {
             self.drag_just_ended = false;
@@ -219,12 +280,21 @@ impl Canvas {
     }
 
     pub fn zoom(&mut self, delta: f64, focus: ScreenPt) {
+        let old_zoom = self.cam_zoom;
+        let new_zoom = 1.1_f64
+            .powf(old_zoom.log(1.1) + delta * (self.settings.canvas_scroll_speed as f64 / 10.0));
+        self.set_zoom_around(new_zoom, focus);
+    }
+
+    /// Multiply the zoom by some ratio, keeping the focus point fixed. Used for pinching to zoom.
+    pub fn zoom_by(&mut self, ratio: f64, focus: ScreenPt) {
+        self.set_zoom_around(self.cam_zoom * ratio, focus);
+    }
+
+    fn set_zoom_around(&mut self, new_zoom: f64, focus: ScreenPt) {
         let old_zoom = self.cam_zoom;
         // By popular request, some limits ;)
-        self.cam_zoom = 1.1_f64
-            .powf(old_zoom.log(1.1) + delta * (self.settings.canvas_scroll_speed as f64 / 10.0))
-            .max(self.min_zoom())
-            .min(self.max_zoom());
+        self.cam_zoom = new_zoom.max(self.min_zoom()).min(self.max_zoom());
 
         // Make screen_to_map of the focus point still point to the same thing after
         // zooming.
@@ -371,7 +441,7 @@ impl Canvas {
     pub(crate) fn is_dragging(&self) -> bool {
         // This could be called before or after handle_event. So we need to repeat the threshold
         // check here! Alternatively, we could this upfront in runner.
-        if self.drag_just_ended {
+        if self.drag_just_ended || self.pinched {
             return true;
         }
         if let Some((_, orig)) = self.drag_canvas_from {
@@ -165,6 +165,43 @@ impl Color {
     pub fn invert(self) -> Color {
         Color::rgba_f(1.0 - self.r, 1.0 - self.g, 1.0 - self.b, self.a)
     }
+
+    /// Hue is in degrees, from 0 to 360. Saturation and value are from 0 to 1.
+    pub fn hsv(hue: f64, saturation: f64, value: f64) -> Color {
+        let hue = hue.rem_euclid(360.0) / 60.0;
+        let chroma = value * saturation;
+        let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+        let (r, g, b) = match hue as usize {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = value - chroma;
+        Color::rgb_f((r + m) as f32, (g + m) as f32, (b + m) as f32)
+    }
+
+    /// Returns (hue, saturation, value), ignoring alpha. See `Color::hsv`.
+    pub fn to_hsv(self) -> (f64, f64, f64) {
+        let (r, g, b) = (self.r as f64, self.g as f64, self.b as f64);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        let saturation = if max == 0.0 { 0.0 } else { delta / max };
+        (hue, saturation, max)
+    }
 }
 
 // https://developer.mozilla.org/en-US/docs/Web/CSS/linear-gradient is the best reference I've
@@ -251,3 +288,34 @@ impl std::convert::From<Texture> for Fill {
         Fill::Texture(texture)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Color;
+
+    #[test]
+    fn test_hsv_round_trip() {
+        for color in [
+            Color::BLACK,
+            Color::WHITE,
+            Color::RED,
+            Color::CYAN,
+            Color::PURPLE,
+            Color::ORANGE,
+            Color::hex("#5B5B5B"),
+        ] {
+            let (h, s, v) = color.to_hsv();
+            let actual = Color::hsv(h, s, v);
+            for (x, y) in [
+                (color.r, actual.r),
+                (color.g, actual.g),
+                (color.b, actual.b),
+            ] {
+                if (x - y).abs() > 0.0001 {
+                    panic!("{} became {}", color, actual);
+                }
+            }
+        }
+        assert_eq!(Color::hsv(120.0, 1.0, 1.0), Color::GREEN);
+    }
+}
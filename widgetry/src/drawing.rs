@@ -158,8 +158,11 @@ impl<'a> GfxCtx<'a> {
 
     // Canvas stuff.
 
-    /// Draw a tooltip where the mouse is
+    /// Draw a tooltip where the mouse is, once the mouse has rested for a moment
     pub fn draw_mouse_tooltip(&mut self, txt: Text) {
+        if !self.canvas.tooltip_ready() {
+            return;
+        }
         self.draw_tooltip_at(
             txt,
             ScreenPt::new(self.canvas.cursor.x, self.canvas.cursor.y + 20.0),
@@ -272,6 +275,12 @@ impl Prerender {
         self.inner.total_bytes_uploaded.get()
     }
 
+    /// How much geometry currently uploaded to the GPU takes, including temporary uploads, but
+    /// not textures. Freed when the `Drawable` is dropped.
+    pub fn get_gpu_bytes_in_use(&self) -> usize {
+        self.inner.gpu_bytes_in_use.get()
+    }
+
     fn actually_upload(&self, permanent: bool, batch: GeomBatch) -> Drawable {
         self.num_uploads.set(self.num_uploads.get() + 1);
         self.inner.actually_upload(permanent, batch)
@@ -1,4 +1,5 @@
 use instant::Instant;
+use serde::{Deserialize, Serialize};
 use winit::event::{
     ElementState, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent,
 };
@@ -11,9 +12,9 @@ use crate::{EventCtx, Line, ScreenDims, ScreenPt, TextSpan};
 // Ideally the delay would be a little more tolerant - e.g. 500ms, but because we don't actually
 // have a way to indicate that a single click was handled (and thus *shouldn't* be counted as part of a double click)
 // it's too easy to have false positives.
-const MAX_DOUBLE_CLICK_DURATION: instant::Duration = instant::Duration::from_millis(300);
+pub(crate) const MAX_DOUBLE_CLICK_DURATION: instant::Duration = instant::Duration::from_millis(300);
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Event {
     // Used to initialize the application and also to recalculate menu state when some other event
     // is used.
@@ -45,6 +46,9 @@ pub enum Event {
     WindowLostCursor,
     WindowGainedCursor,
     MouseWheelScroll(f64, f64),
+    /// Two fingers on a touchscreen moved apart (more than 1) or together (less than 1) by this
+    /// ratio. The cursor is between the fingers.
+    Pinch(f64),
     WindowResized(ScreenDims),
 }
 
@@ -135,7 +139,7 @@ impl Event {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
 pub enum Key {
     // Case is unspecified.
     // TODO Would be cool to represent A and UpperA, but then release semantics get weird... hold
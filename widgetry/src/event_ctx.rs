@@ -5,6 +5,7 @@ use instant::Instant;
 use abstutil::{elapsed_seconds, Timer, TimerSink};
 use geom::{Percent, Polygon};
 
+use crate::tools::Translations;
 use crate::{
     svg, Canvas, CanvasSettings, Color, Drawable, Event, GeomBatch, GfxCtx, HorizontalAlignment,
     Key, Line, Panel, PanelDims, Prerender, ScreenDims, Style, Text, UserInput, VerticalAlignment,
@@ -21,6 +22,10 @@ pub enum UpdateType {
         zoom: f64,
         dims: ScreenDims,
     },
+    /// Draw what's currently on the screen, then save it as a PNG file.
+    ScreenCaptureViewport {
+        filename: String,
+    },
 }
 
 pub struct EventCtx<'a> {
@@ -160,6 +165,26 @@ impl<'a> EventCtx<'a> {
         *self.style = style;
     }
 
+    /// Look up a UI string in the current language. `english` is used if the current catalog
+    /// doesn't have `id`.
+    pub fn tr(&self, id: &str, english: &str) -> String {
+        self.prerender.assets.translations.borrow().get(id, english)
+    }
+
+    /// Like `tr`, but fills in `{ $name }` placeholders.
+    pub fn tr_args(&self, id: &str, english: &str, args: &[(&str, String)]) -> String {
+        self.prerender
+            .assets
+            .translations
+            .borrow()
+            .get_args(id, english, args)
+    }
+
+    /// Panels built after this will use the new language.
+    pub fn set_translations(&mut self, translations: Translations) {
+        *self.prerender.assets.translations.borrow_mut() = translations;
+    }
+
     pub fn make_loading_screen(&mut self, txt: Text) -> Panel {
         let border = Color::hex("#F4DA22");
         let (label, bytes) = crate::include_labeled_bytes!("../icons/loading.svg");
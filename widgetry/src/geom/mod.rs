@@ -344,6 +344,8 @@ pub enum RewriteColor {
     ChangeAll(Color),
     /// Change the alpha value of all colors to this value.
     ChangeAlpha(f32),
+    /// Multiply the alpha value of all colors by this factor.
+    MultiplyAlpha(f32),
     /// Convert all colors to greyscale.
     MakeGrayscale,
 }
@@ -373,6 +375,7 @@ impl RewriteColor {
                 }
             }
             RewriteColor::ChangeAlpha(alpha) => c.alpha(*alpha),
+            RewriteColor::MultiplyAlpha(factor) => c.multiply_alpha(*factor),
             RewriteColor::MakeGrayscale => {
                 let avg = (c.r + c.g + c.b) / 3.0;
                 Color::grey(avg).alpha(c.a)
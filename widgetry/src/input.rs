@@ -105,6 +105,14 @@ impl UserInput {
         None
     }
 
+    /// How much did the user pinch to zoom on a touchscreen? More than 1 means zooming in.
+    pub fn get_pinch(&self) -> Option<f64> {
+        if let Event::Pinch(ratio) = self.event {
+            return Some(ratio);
+        }
+        None
+    }
+
     pub fn is_window_resized(&self) -> bool {
         matches!(self.event, Event::WindowResized(_))
     }
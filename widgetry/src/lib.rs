@@ -5,14 +5,17 @@
 //! TODO inline pictures of some of these
 //!
 //! * [`Autocomplete`] - select predefined value by combining text entry with menus
+//! * [`BoxPlot`] - compare the spread of values in several groups
 //! * [`Button`] - clickable buttons with keybindings and tooltips
 //! * [`Toggle`] - checkboxes, switches, and other toggles
+//! * [`ColorPicker`] - pick a color from a palette, or by hue, saturation, and value
 //! * [`CompareTimes`] - a scatter plot specialized for comparing times
 //! * [`DragDrop`] - a reorderable row of draggable cards
 //! * [`DrawWithTooltips`] - draw static geometry, with mouse tooltips in certain regions
 //! * [`Dropdown`] - a button that expands into a menu
 //! * [`FanChart`] - visualize a range of values over time
 //! * [`Filler`] - just carve out space in the layout for something else
+//! * [`HistogramPlot`] - visualize the distribution of one variable
 //! * [`JustDraw`] (argh private) - just draw text, `GeomBatch`es, SVGs
 //! * [`LinePlot`] - visualize 2 variables with a line plot
 //! * [`Menu`] - select something from a menu, with keybindings
@@ -20,7 +23,9 @@
 //! * [`ScatterPlot`] - visualize 2 variables with a scatter plot
 //! * [`Slider`] - horizontal and vertical sliders
 //! * [`Spinner`] - numeric input with up/down buttons
-//! * [`table::Table`] - rows and columns, supporting filtering and pagination
+//! * [`StackedAreaPlot`] - visualize how a total splits into parts over time
+//! * [`table::Table`] - rows and columns, supporting sorting, filtering, searching, and pagination
+//! * [`TextArea`] - multi-line text entry
 //! * [`TextBox`] - single line text entry
 
 //#![warn(missing_docs)]
@@ -44,6 +49,7 @@ pub use crate::geom::geom_batch_stack::{
 };
 pub use crate::geom::{GeomBatch, RewriteColor};
 pub use crate::input::UserInput;
+pub use crate::replay::Replay;
 pub use crate::runner::{run, Settings};
 pub use crate::screen_geom::{ScreenDims, ScreenPt, ScreenRectangle};
 pub use crate::style::{ButtonStyle, OutlineStyle, Style};
@@ -51,13 +57,16 @@ pub use crate::text::{Font, Line, Text, TextExt, TextSpan};
 pub use crate::tools::warper::Warper;
 pub use crate::tools::Cached;
 pub use crate::widgets::autocomplete::Autocomplete;
+pub use crate::widgets::box_plot::{BoxGroup, BoxPlot};
 pub(crate) use crate::widgets::button::Button;
 pub use crate::widgets::button::ButtonBuilder;
+pub use crate::widgets::color_picker::ColorPicker;
 pub use crate::widgets::compare_times::CompareTimes;
 pub use crate::widgets::drag_drop::DragDrop;
 pub(crate) use crate::widgets::dropdown::Dropdown;
 pub use crate::widgets::fan_chart::FanChart;
 pub use crate::widgets::filler::Filler;
+pub use crate::widgets::histogram_plot::HistogramPlot;
 pub use crate::widgets::image::{Image, ImageSource};
 pub use crate::widgets::just_draw::DrawWithTooltips;
 pub(crate) use crate::widgets::just_draw::{DeferDraw, JustDraw};
@@ -67,16 +76,18 @@ pub use crate::widgets::persistent_split::PersistentSplit;
 pub use crate::widgets::plots::{PlotOptions, Series};
 pub use crate::widgets::scatter_plot::ScatterPlot;
 pub use crate::widgets::slider::Slider;
-pub use crate::widgets::spinner::{RoundedF64, Spinner};
+pub use crate::widgets::spinner::{RoundedF64, Spinner, SpinnerUnits};
+pub use crate::widgets::stacked_area_plot::StackedAreaPlot;
 pub use crate::widgets::stash::Stash;
 pub use crate::widgets::table;
 pub use crate::widgets::tabs::TabController;
+pub use crate::widgets::text_area::TextArea;
 pub use crate::widgets::text_box::TextBox;
 pub use crate::widgets::toggle::Toggle;
 pub use crate::widgets::DEFAULT_CORNER_RADIUS;
 pub use crate::widgets::{
-    ClickOutcome, CornerRounding, EdgeInsets, Outcome, Panel, PanelBuilder, PanelDims, Widget,
-    WidgetImpl, WidgetOutput,
+    AccessibleNode, AccessibleRole, ClickOutcome, CornerRounding, EdgeInsets, Outcome, Panel,
+    PanelBuilder, PanelDims, Widget, WidgetImpl, WidgetOutput,
 };
 
 mod app_state;
@@ -95,12 +106,14 @@ mod event_ctx;
 mod geom;
 mod input;
 pub mod mapspace;
+mod replay;
 mod runner;
 mod screen_geom;
 mod style;
 mod svg;
 mod text;
 pub mod tools;
+mod touch;
 mod widgets;
 
 mod backend {
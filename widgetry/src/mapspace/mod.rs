@@ -1,9 +1,13 @@
+mod spatial_index;
+mod tiles;
 mod unzoomed;
 mod world;
 
 use geom::Polygon;
 
 use crate::{Drawable, EventCtx, Fill, GeomBatch, GfxCtx, RewriteColor};
+pub use spatial_index::SpatialIndex;
+pub use tiles::TiledDrawable;
 pub use unzoomed::{DrawCustomUnzoomedShapes, DrawUnzoomedShapes, PerZoom};
 pub use world::{DummyID, ObjectID, World, WorldOutcome};
 
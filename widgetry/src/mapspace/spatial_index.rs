@@ -0,0 +1,467 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use geom::{Bounds, Distance, Pt2D};
+
+/// How many entries fit in one node before it splits
+const MAX_ENTRIES: usize = 16;
+
+/// An R-tree, indexing objects by their bounding box. Use this to quickly find objects near a
+/// point, overlapping some area, or closest to somewhere, instead of scanning through everything.
+///
+/// Objects can be bulk-loaded upfront, which produces a better tree, and later inserted or
+/// removed one at a time.
+pub struct SpatialIndex<T: Copy + Eq + Hash> {
+    root: Node<T>,
+    rect_per_object: HashMap<T, Rect>,
+}
+
+enum Node<T> {
+    Leaf(Vec<(Rect, T)>),
+    Internal(Vec<(Rect, Node<T>)>),
+}
+
+// Like Bounds, but cheap to copy around
+#[derive(Clone, Copy)]
+struct Rect {
+    min_x: f64,
+    min_y: f64,
+    max_x: f64,
+    max_y: f64,
+}
+
+impl<T: Copy + Eq + Hash> SpatialIndex<T> {
+    pub fn new() -> SpatialIndex<T> {
+        SpatialIndex {
+            root: Node::Leaf(Vec::new()),
+            rect_per_object: HashMap::new(),
+        }
+    }
+
+    /// Build an index of many objects at once. If an object appears more than once, only the
+    /// last bounds are used.
+    pub fn bulk_load(objects: Vec<(T, Bounds)>) -> SpatialIndex<T> {
+        let mut rect_per_object = HashMap::new();
+        for (id, bounds) in objects {
+            rect_per_object.insert(id, Rect::from(&bounds));
+        }
+        let entries: Vec<(Rect, T)> = rect_per_object
+            .iter()
+            .map(|(id, rect)| (*rect, *id))
+            .collect();
+
+        // Sort-Tile-Recursive packing, one level at a time
+        let root = if entries.len() <= MAX_ENTRIES {
+            Node::Leaf(entries)
+        } else {
+            let mut level: Vec<(Rect, Node<T>)> = pack(entries)
+                .into_iter()
+                .map(|group| (bounds_of(&group), Node::Leaf(group)))
+                .collect();
+            while level.len() > MAX_ENTRIES {
+                level = pack(level)
+                    .into_iter()
+                    .map(|group| (bounds_of(&group), Node::Internal(group)))
+                    .collect();
+            }
+            Node::Internal(level)
+        };
+        SpatialIndex {
+            root,
+            rect_per_object,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rect_per_object.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rect_per_object.is_empty()
+    }
+
+    pub fn contains(&self, id: T) -> bool {
+        self.rect_per_object.contains_key(&id)
+    }
+
+    /// Adds an object to the index. If it's already present, its bounds are updated.
+    pub fn insert(&mut self, id: T, bounds: Bounds) {
+        self.remove(id);
+        let rect = Rect::from(&bounds);
+        self.rect_per_object.insert(id, rect);
+        if let Some(sibling) = self.root.insert(rect, id) {
+            let old_root = std::mem::replace(&mut self.root, Node::Leaf(Vec::new()));
+            self.root = Node::Internal(vec![
+                (old_root.bounds(), old_root),
+                (sibling.bounds(), sibling),
+            ]);
+        }
+    }
+
+    /// Removes an object from the index. Returns false if it wasn't present.
+    pub fn remove(&mut self, id: T) -> bool {
+        let rect = if let Some(rect) = self.rect_per_object.remove(&id) {
+            rect
+        } else {
+            return false;
+        };
+        assert!(
+            self.root.remove(&rect, id),
+            "SpatialIndex lost track of an object"
+        );
+        // Shrink the tree when the root only has one child left
+        loop {
+            match self.root {
+                Node::Internal(ref mut children) if children.len() <= 1 => {
+                    self.root = children
+                        .pop()
+                        .map(|(_, node)| node)
+                        .unwrap_or_else(|| Node::Leaf(Vec::new()));
+                }
+                _ => break,
+            }
+        }
+        true
+    }
+
+    /// Returns every object whose bounding box overlaps the given bounds.
+    pub fn query_bounds(&self, bounds: &Bounds) -> Vec<T> {
+        let mut results = Vec::new();
+        let bounds = Rect::from(bounds);
+        self.root
+            .query(&|rect: &Rect| rect.overlaps(&bounds), &mut results);
+        results
+    }
+
+    /// Returns every object whose bounding box contains the point.
+    pub fn query_point(&self, pt: Pt2D) -> Vec<T> {
+        let mut results = Vec::new();
+        self.root
+            .query(&|rect: &Rect| rect.contains(pt), &mut results);
+        results
+    }
+
+    /// Returns every object whose bounding box is within some distance of the point.
+    pub fn query_radius(&self, pt: Pt2D, radius: Distance) -> Vec<T> {
+        let mut results = Vec::new();
+        let radius = radius.inner_meters();
+        self.root
+            .query(&|rect: &Rect| rect.dist_to(pt) <= radius, &mut results);
+        results
+    }
+
+    /// Finds the closest object to a point, within some maximum distance. Bounding boxes are used
+    /// to skip most objects, then `exact_dist` measures the real distance to the remaining
+    /// candidates. `exact_dist` must never be less than the distance to the object's bounding
+    /// box; the distance to anything inside the box satisfies this.
+    pub fn nearest<F: Fn(T) -> Distance>(
+        &self,
+        pt: Pt2D,
+        max_dist: Distance,
+        exact_dist: F,
+    ) -> Option<(T, Distance)> {
+        let mut best = None;
+        let mut limit = max_dist.inner_meters();
+        self.root.nearest(pt, &exact_dist, &mut limit, &mut best);
+        best.map(|id| (id, Distance::meters(limit)))
+    }
+}
+
+impl<T: Copy + Eq + Hash> Default for SpatialIndex<T> {
+    fn default() -> Self {
+        SpatialIndex::new()
+    }
+}
+
+impl<T: Copy + PartialEq> Node<T> {
+    fn bounds(&self) -> Rect {
+        match self {
+            Node::Leaf(entries) => bounds_of(entries),
+            Node::Internal(children) => bounds_of(children),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            Node::Leaf(entries) => entries.is_empty(),
+            Node::Internal(children) => children.is_empty(),
+        }
+    }
+
+    // If this node overflows, it splits, and the new sibling is returned.
+    fn insert(&mut self, rect: Rect, id: T) -> Option<Node<T>> {
+        match self {
+            Node::Leaf(entries) => {
+                entries.push((rect, id));
+                if entries.len() > MAX_ENTRIES {
+                    return Some(Node::Leaf(split(entries)));
+                }
+            }
+            Node::Internal(children) => {
+                // Descend into the child that grows the least to fit this
+                let idx = (0..children.len())
+                    .min_by(|a, b| {
+                        let key = |idx: usize| {
+                            let child = children[idx].0;
+                            let area = child.area();
+                            (child.union(&rect).area() - area, area)
+                        };
+                        key(*a).partial_cmp(&key(*b)).unwrap()
+                    })
+                    .unwrap();
+                let (ref mut child_bounds, ref mut child) = children[idx];
+                *child_bounds = child_bounds.union(&rect);
+                if let Some(sibling) = child.insert(rect, id) {
+                    *child_bounds = child.bounds();
+                    children.push((sibling.bounds(), sibling));
+                    if children.len() > MAX_ENTRIES {
+                        return Some(Node::Internal(split(children)));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // Returns true if the object was found and removed.
+    fn remove(&mut self, rect: &Rect, id: T) -> bool {
+        match self {
+            Node::Leaf(entries) => {
+                if let Some(idx) = entries.iter().position(|(_, x)| *x == id) {
+                    entries.remove(idx);
+                    return true;
+                }
+                false
+            }
+            Node::Internal(children) => {
+                for idx in 0..children.len() {
+                    if !children[idx].0.overlaps(rect) || !children[idx].1.remove(rect, id) {
+                        continue;
+                    }
+                    // Nodes are allowed to be underfull, but not empty
+                    if children[idx].1.is_empty() {
+                        children.remove(idx);
+                    } else {
+                        children[idx].0 = children[idx].1.bounds();
+                    }
+                    return true;
+                }
+                false
+            }
+        }
+    }
+
+    fn query<F: Fn(&Rect) -> bool>(&self, matches: &F, results: &mut Vec<T>) {
+        match self {
+            Node::Leaf(entries) => {
+                for (rect, id) in entries {
+                    if matches(rect) {
+                        results.push(*id);
+                    }
+                }
+            }
+            Node::Internal(children) => {
+                for (rect, child) in children {
+                    if matches(rect) {
+                        child.query(matches, results);
+                    }
+                }
+            }
+        }
+    }
+
+    fn nearest<F: Fn(T) -> Distance>(
+        &self,
+        pt: Pt2D,
+        exact_dist: &F,
+        limit: &mut f64,
+        best: &mut Option<T>,
+    ) {
+        match self {
+            Node::Leaf(entries) => {
+                for (rect, id) in entries {
+                    if rect.dist_to(pt) > *limit {
+                        continue;
+                    }
+                    let dist = exact_dist(*id).inner_meters();
+                    if dist <= *limit {
+                        *limit = dist;
+                        *best = Some(*id);
+                    }
+                }
+            }
+            Node::Internal(children) => {
+                // Visit the closest children first, so the limit shrinks quickly
+                let mut order: Vec<(f64, &Node<T>)> = children
+                    .iter()
+                    .map(|(rect, child)| (rect.dist_to(pt), child))
+                    .collect();
+                order.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+                for (dist, child) in order {
+                    if dist > *limit {
+                        break;
+                    }
+                    child.nearest(pt, exact_dist, limit, best);
+                }
+            }
+        }
+    }
+}
+
+impl Rect {
+    fn from(b: &Bounds) -> Rect {
+        Rect {
+            min_x: b.min_x,
+            min_y: b.min_y,
+            max_x: b.max_x,
+            max_y: b.max_y,
+        }
+    }
+
+    fn empty() -> Rect {
+        Rect {
+            min_x: f64::MAX,
+            min_y: f64::MAX,
+            max_x: f64::MIN,
+            max_y: f64::MIN,
+        }
+    }
+
+    fn union(&self, other: &Rect) -> Rect {
+        Rect {
+            min_x: self.min_x.min(other.min_x),
+            min_y: self.min_y.min(other.min_y),
+            max_x: self.max_x.max(other.max_x),
+            max_y: self.max_y.max(other.max_y),
+        }
+    }
+
+    fn area(&self) -> f64 {
+        (self.max_x - self.min_x) * (self.max_y - self.min_y)
+    }
+
+    fn center(&self) -> (f64, f64) {
+        (
+            (self.min_x + self.max_x) / 2.0,
+            (self.min_y + self.max_y) / 2.0,
+        )
+    }
+
+    fn overlaps(&self, other: &Rect) -> bool {
+        self.min_x <= other.max_x
+            && other.min_x <= self.max_x
+            && self.min_y <= other.max_y
+            && other.min_y <= self.max_y
+    }
+
+    fn contains(&self, pt: Pt2D) -> bool {
+        pt.x() >= self.min_x && pt.x() <= self.max_x && pt.y() >= self.min_y && pt.y() <= self.max_y
+    }
+
+    // 0 if the point is inside
+    fn dist_to(&self, pt: Pt2D) -> f64 {
+        let dx = (self.min_x - pt.x()).max(pt.x() - self.max_x).max(0.0);
+        let dy = (self.min_y - pt.y()).max(pt.y() - self.max_y).max(0.0);
+        dx.hypot(dy)
+    }
+}
+
+// Split off the upper half of the entries, along whichever axis they're most spread out.
+fn split<E>(entries: &mut Vec<(Rect, E)>) -> Vec<(Rect, E)> {
+    let total = bounds_of(entries);
+    if total.max_x - total.min_x >= total.max_y - total.min_y {
+        entries.sort_by(|a, b| a.0.center().0.partial_cmp(&b.0.center().0).unwrap());
+    } else {
+        entries.sort_by(|a, b| a.0.center().1.partial_cmp(&b.0.center().1).unwrap());
+    }
+    entries.split_off(entries.len() / 2)
+}
+
+// Group entries into nodes of nearby entries, by slicing into vertical strips, then chunking each
+// strip from bottom to top.
+fn pack<E>(mut entries: Vec<(Rect, E)>) -> Vec<Vec<(Rect, E)>> {
+    let num_nodes = (entries.len() + MAX_ENTRIES - 1) / MAX_ENTRIES;
+    let num_strips = (num_nodes as f64).sqrt().ceil() as usize;
+    let per_strip = num_strips * MAX_ENTRIES;
+
+    entries.sort_by(|a, b| a.0.center().0.partial_cmp(&b.0.center().0).unwrap());
+    let mut groups = Vec::new();
+    while !entries.is_empty() {
+        let rest = entries.split_off(per_strip.min(entries.len()));
+        let mut strip = std::mem::replace(&mut entries, rest);
+        strip.sort_by(|a, b| a.0.center().1.partial_cmp(&b.0.center().1).unwrap());
+        while !strip.is_empty() {
+            let rest = strip.split_off(MAX_ENTRIES.min(strip.len()));
+            groups.push(std::mem::replace(&mut strip, rest));
+        }
+    }
+    groups
+}
+
+fn bounds_of<E>(entries: &[(Rect, E)]) -> Rect {
+    entries
+        .iter()
+        .fold(Rect::empty(), |acc, (rect, _)| acc.union(rect))
+}
+
+#[cfg(test)]
+mod tests {
+    use geom::{Bounds, Distance, Pt2D};
+
+    use super::SpatialIndex;
+
+    fn square(x: f64, y: f64) -> Bounds {
+        Bounds::from(&[Pt2D::new(x, y), Pt2D::new(x + 1.0, y + 1.0)])
+    }
+
+    #[test]
+    fn test_spatial_index() {
+        let mut objects = Vec::new();
+        for x in 0..30 {
+            for y in 0..30 {
+                objects.push(((x, y), square(10.0 * x as f64, 10.0 * y as f64)));
+            }
+        }
+        let mut index = SpatialIndex::bulk_load(objects);
+        assert_eq!(index.len(), 900);
+
+        assert_eq!(index.query_point(Pt2D::new(50.5, 70.5)), vec![(5, 7)]);
+        assert!(index.query_point(Pt2D::new(55.0, 75.0)).is_empty());
+
+        let mut found = index.query_bounds(&Bounds::from(&[
+            Pt2D::new(15.0, 15.0),
+            Pt2D::new(30.5, 20.5),
+        ]));
+        found.sort();
+        assert_eq!(found, vec![(2, 2), (3, 2)]);
+
+        let mut found = index.query_radius(Pt2D::new(105.0, 100.5), Distance::meters(4.5));
+        found.sort();
+        assert_eq!(found, vec![(10, 10)]);
+
+        let center =
+            |(x, y): (usize, usize)| Pt2D::new(10.0 * x as f64 + 0.5, 10.0 * y as f64 + 0.5);
+        let pt = Pt2D::new(123.0, 87.0);
+        let (id, _) = index
+            .nearest(pt, Distance::meters(100.0), |id| center(id).dist_to(pt))
+            .unwrap();
+        assert_eq!(id, (12, 9));
+
+        // Incremental changes
+        assert!(index.remove((12, 9)));
+        assert!(!index.remove((12, 9)));
+        let (id, _) = index
+            .nearest(pt, Distance::meters(100.0), |id| center(id).dist_to(pt))
+            .unwrap();
+        assert_ne!(id, (12, 9));
+        index.insert((100, 100), square(122.5, 86.5));
+        assert_eq!(index.query_point(pt), vec![(100, 100)]);
+        for x in 0..30 {
+            for y in 0..30 {
+                index.remove((x, y));
+            }
+        }
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.query_point(pt), vec![(100, 100)]);
+    }
+}
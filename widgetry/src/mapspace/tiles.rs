@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+
+use geom::Bounds;
+
+use crate::{Drawable, EventCtx, GeomBatch, GfxCtx};
+
+/// Tiles at the most detailed level are this many meters wide. Each coarser level's tiles are
+/// `LEVEL_FACTOR` times wider.
+const TILE_SIZE: f64 = 2000.0;
+const LEVEL_FACTOR: f64 = 4.0;
+/// Switch to a coarser level once tiles would be drawn narrower than this many pixels, to bound
+/// how many tiles are drawn at once.
+const MIN_TILE_PIXELS: f64 = 512.0;
+
+/// Draws a large amount of map-space geometry, like every road in a huge map, efficiently.
+///
+/// The geometry is split into tiles, so only the tiles on screen are drawn. Zooming out switches
+/// to coarser levels of detail with bigger tiles. These are drawn from separate, simpler geometry
+/// -- without outlines or icons, for example -- and skip polygons that'd be smaller than a pixel,
+/// so the detailed geometry is only uploaded for the most zoomed-in level. Everything is uploaded
+/// once upfront; panning and zooming never upload again.
+pub struct TiledDrawable {
+    // From most to least detailed
+    levels: Vec<Level>,
+    num_layers: usize,
+}
+
+struct Level {
+    // This level is used when zoomed in at least this much
+    min_zoom: f64,
+    tiles: Vec<Tile>,
+}
+
+struct Tile {
+    // Polygons belong to the tile containing their center, so this can spill past the tile's
+    // nominal square
+    bounds: Bounds,
+    // One per layer
+    layers: Vec<Option<Drawable>>,
+}
+
+impl TiledDrawable {
+    /// The layers are drawn in order. Within one layer, tiles may be drawn in any order, so
+    /// anything that must be drawn on top of something else should be in a later layer.
+    /// `coarse_layers` are drawn instead of `layers` when zoomed out, and must have the same
+    /// number of layers. Detail that isn't visible when zoomed out should be left out of them.
+    pub fn new(
+        ctx: &EventCtx,
+        layers: Vec<GeomBatch>,
+        coarse_layers: Vec<GeomBatch>,
+    ) -> TiledDrawable {
+        assert_eq!(layers.len(), coarse_layers.len());
+        let mut bounds = Bounds::new();
+        for batch in layers.iter().chain(coarse_layers.iter()) {
+            if !batch.is_empty() {
+                bounds.union(batch.get_bounds());
+            }
+        }
+        let mut tiled = TiledDrawable {
+            levels: Vec::new(),
+            num_layers: layers.len(),
+        };
+        if layers
+            .iter()
+            .chain(coarse_layers.iter())
+            .all(|batch| batch.is_empty())
+        {
+            return tiled;
+        }
+
+        let mut tile_size = TILE_SIZE;
+        loop {
+            let covers_everything = tile_size >= bounds.width().max(bounds.height());
+            // Polygons smaller than a pixel at the most zoomed-in this level is used don't need
+            // to be drawn.
+            let min_polygon_size = match tiled.levels.last() {
+                Some(finer) => 1.0 / finer.min_zoom,
+                None => 0.0,
+            };
+            tiled.levels.push(Level::new(
+                ctx,
+                if tiled.levels.is_empty() {
+                    &layers
+                } else {
+                    &coarse_layers
+                },
+                &bounds,
+                tile_size,
+                min_polygon_size,
+                // The coarsest level handles anything zoomed out further
+                if covers_everything {
+                    0.0
+                } else {
+                    MIN_TILE_PIXELS / tile_size
+                },
+            ));
+            if covers_everything {
+                break;
+            }
+            tile_size *= LEVEL_FACTOR;
+        }
+        tiled
+    }
+
+    /// Draw everything visible on the screen
+    pub fn draw(&self, g: &mut GfxCtx) {
+        let bounds = g.get_screen_bounds();
+        let zoom = g.canvas.cam_zoom;
+        self.draw_within(g, &bounds, zoom);
+    }
+
+    /// Draw everything within some map-space bounds, at the detail needed for a zoom level. Use
+    /// this instead of `draw` after `GfxCtx::fork`, like for a minimap.
+    pub fn draw_within(&self, g: &mut GfxCtx, bounds: &Bounds, zoom: f64) {
+        let level = if let Some(level) = self.levels.iter().find(|l| zoom >= l.min_zoom) {
+            level
+        } else {
+            return;
+        };
+        let visible: Vec<&Tile> = level
+            .tiles
+            .iter()
+            .filter(|tile| overlaps(&tile.bounds, bounds))
+            .collect();
+        for layer in 0..self.num_layers {
+            for tile in &visible {
+                if let Some(ref draw) = tile.layers[layer] {
+                    g.redraw(draw);
+                }
+            }
+        }
+    }
+}
+
+impl Level {
+    fn new(
+        ctx: &EventCtx,
+        layers: &[GeomBatch],
+        map_bounds: &Bounds,
+        tile_size: f64,
+        min_polygon_size: f64,
+        min_zoom: f64,
+    ) -> Level {
+        let mut tiles: BTreeMap<(usize, usize), (Bounds, Vec<GeomBatch>)> = BTreeMap::new();
+        for (layer, batch) in layers.iter().enumerate() {
+            for (fill, polygon, z) in &batch.list {
+                let bounds = polygon.get_bounds();
+                if bounds.width().max(bounds.height()) < min_polygon_size {
+                    continue;
+                }
+                let center = bounds.center();
+                let key = (
+                    ((center.x() - map_bounds.min_x) / tile_size).max(0.0) as usize,
+                    ((center.y() - map_bounds.min_y) / tile_size).max(0.0) as usize,
+                );
+                let (tile_bounds, batches) = tiles.entry(key).or_insert_with(|| {
+                    (
+                        Bounds::new(),
+                        std::iter::repeat_with(GeomBatch::new)
+                            .take(layers.len())
+                            .collect(),
+                    )
+                });
+                tile_bounds.union(bounds);
+                batches[layer].push_with_z(fill.clone(), polygon.clone(), *z);
+            }
+        }
+
+        Level {
+            min_zoom,
+            tiles: tiles
+                .into_values()
+                .map(|(bounds, batches)| Tile {
+                    bounds,
+                    layers: batches
+                        .into_iter()
+                        .map(|batch| {
+                            if batch.is_empty() {
+                                None
+                            } else {
+                                Some(ctx.upload(batch))
+                            }
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+fn overlaps(b1: &Bounds, b2: &Bounds) -> bool {
+    b1.min_x <= b2.max_x && b2.min_x <= b1.max_x && b1.min_y <= b2.max_y && b2.min_y <= b1.max_y
+}
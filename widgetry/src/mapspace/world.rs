@@ -1,11 +1,11 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::hash::Hash;
 
-use geom::{Bounds, Circle, Distance, Polygon, Pt2D, QuadTree};
+use geom::{Bounds, Distance, Polygon, Pt2D};
 
-use crate::mapspace::{ToggleZoomed, ToggleZoomedBuilder};
-use crate::{Color, Drawable, EventCtx, GeomBatch, GfxCtx, MultiKey, RewriteColor, Text};
+use crate::mapspace::{SpatialIndex, ToggleZoomed, ToggleZoomedBuilder};
+use crate::{Color, Drawable, EventCtx, GeomBatch, GfxCtx, Key, MultiKey, RewriteColor, Text};
 
 // TODO Tests...
 // - start drag in screenspace, release in map
@@ -18,7 +18,7 @@ use crate::{Color, Drawable, EventCtx, GeomBatch, GfxCtx, MultiKey, RewriteColor
 pub struct World<ID: ObjectID> {
     // TODO Hashing may be too slow in some cases
     objects: HashMap<ID, Object<ID>>,
-    quadtree: QuadTree<ID>,
+    index: SpatialIndex<ID>,
 
     draw_master_batches: Vec<ToggleZoomed>,
 
@@ -28,6 +28,11 @@ pub struct World<ID: ObjectID> {
     // If we're currently dragging, where was the cursor during the last movement, and has the
     // cursor moved since starting the drag?
     dragging_from: Option<(Pt2D, bool)>,
+
+    // Holding this key while dragging draws a selection box
+    box_select_key: Option<Key>,
+    // The corner where the selection box started, and the cursor's current position
+    box_selecting: Option<(Pt2D, Pt2D)>,
 }
 
 /// The result of a `World` handling an event
@@ -55,6 +60,9 @@ pub enum WorldOutcome<ID: ObjectID> {
     /// and we immediately wind up on another road beneath, we don't detect this and start showing
     /// road points.
     HoverChanged(Option<ID>, Option<ID>),
+    /// The user finished dragging a selection box. Every hoverable object whose hitbox touches the
+    /// box is included. Only happens after `enable_box_selection`.
+    BoxSelected(HashSet<ID>),
     /// Nothing interesting happened
     Nothing,
 }
@@ -92,6 +100,15 @@ impl<I: ObjectID> WorldOutcome<I> {
                 };
                 Some(WorldOutcome::HoverChanged(before, after))
             }
+            WorldOutcome::BoxSelected(ids) => {
+                // Keep the objects that f knows about, unless there are none
+                let ids: HashSet<O> = ids.into_iter().filter_map(f).collect();
+                if ids.is_empty() {
+                    None
+                } else {
+                    Some(WorldOutcome::BoxSelected(ids))
+                }
+            }
             WorldOutcome::Nothing => Some(WorldOutcome::Nothing),
         }
     }
@@ -307,7 +324,7 @@ impl<'a, ID: ObjectID> ObjectBuilder<'a, ID> {
     pub fn build(mut self, ctx: &EventCtx) {
         assert!(!self.hitboxes.is_empty(), "didn't specify hitbox");
         let bounds = Bounds::from_polygons(&self.hitboxes);
-        self.world.quadtree.insert_with_box(self.id, bounds);
+        self.world.index.insert(self.id, bounds);
 
         self.world.objects.insert(
             self.id,
@@ -356,16 +373,30 @@ impl<ID: ObjectID> World<ID> {
     pub fn new() -> World<ID> {
         World {
             objects: HashMap::new(),
-            quadtree: QuadTree::new(),
+            index: SpatialIndex::new(),
 
             draw_master_batches: Vec::new(),
 
             hovering: None,
             draw_hovering: None,
             dragging_from: None,
+
+            box_select_key: None,
+            box_selecting: None,
         }
     }
 
+    /// Let the user select many objects at once by holding a key, then clicking and dragging a
+    /// box. `WorldOutcome::BoxSelected` will be fired when they release the mouse.
+    pub fn enable_box_selection(&mut self, key: Key) {
+        self.box_select_key = Some(key);
+    }
+
+    /// Is the user currently dragging a selection box?
+    pub fn is_box_selecting(&self) -> bool {
+        self.box_selecting.is_some()
+    }
+
     /// Start adding an object to the `World`. The caller should specify the object with methods on
     /// `ObjectBuilder`, then call `build`.
     pub fn add(&mut self, id: ID) -> ObjectBuilder<'_, ID> {
@@ -403,11 +434,7 @@ impl<ID: ObjectID> World<ID> {
     /// `event`. This may be called while the object is being hovered on or dragged.
     pub fn delete_before_replacement(&mut self, id: ID) {
         if self.objects.remove(&id).is_some() {
-            if self.quadtree.remove(id).is_none() {
-                // This can happen for objects that're out-of-bounds. One example is intersections
-                // in map_editor.
-                warn!("{:?} wasn't in the quadtree", id);
-            }
+            self.index.remove(id);
         } else {
             panic!("Can't delete {:?}; it's not in the World", id);
         }
@@ -424,11 +451,7 @@ impl<ID: ObjectID> World<ID> {
         }
 
         if self.objects.remove(&id).is_some() {
-            if self.quadtree.remove(id).is_none() {
-                // This can happen for objects that're out-of-bounds. One example is intersections
-                // in map_editor.
-                warn!("{:?} wasn't in the quadtree", id);
-            }
+            self.index.remove(id);
         }
     }
 
@@ -525,6 +548,24 @@ impl<ID: ObjectID> World<ID> {
             return WorldOutcome::Nothing;
         }
 
+        if let Some((start, current)) = self.box_selecting {
+            if ctx.input.left_mouse_button_released() {
+                self.box_selecting = None;
+                return WorldOutcome::BoxSelected(self.objects_in_box(start, current));
+            }
+            // Like dragging an object, allow zooming but not panning
+            if let Some((_, dy)) = ctx.input.get_mouse_scroll() {
+                ctx.canvas.zoom(dy, ctx.canvas.get_cursor());
+            }
+            if ctx.redo_mouseover() {
+                // If the cursor leaves the map, keep the box as it was
+                if let Some(cursor) = ctx.canvas.get_cursor_in_map_space() {
+                    self.box_selecting = Some((start, cursor));
+                }
+            }
+            return WorldOutcome::Nothing;
+        }
+
         let cursor = if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
             pt
         } else {
@@ -547,6 +588,13 @@ impl<ID: ObjectID> World<ID> {
             }
         }
 
+        if let Some(key) = self.box_select_key {
+            if ctx.is_key_down(key) && ctx.input.left_mouse_button_pressed() {
+                self.box_selecting = Some((cursor, cursor));
+                return neutral_outcome;
+            }
+        }
+
         // If we're hovering on a draggable thing, only allow zooming, not panning
         let mut allow_panning = true;
         if let Some(id) = self.hovering {
@@ -587,14 +635,9 @@ impl<ID: ObjectID> World<ID> {
     }
 
     fn calculate_hover(&self, cursor: Pt2D) -> Option<ID> {
-        let mut objects = Vec::new();
-        for id in self.quadtree.query_bbox(
-            // Maybe worth tuning. Since we do contains_pt below, it doesn't matter if this is too
-            // big; just a performance impact possibly.
-            Circle::new(cursor, Distance::meters(3.0)).get_bounds(),
-        ) {
-            objects.push(id);
-        }
+        // Maybe worth tuning. Since we do contains_pt below, it doesn't matter if this is too
+        // big; just a performance impact possibly.
+        let mut objects = self.index.query_radius(cursor, Distance::meters(3.0));
         objects.sort_by_key(|id| self.objects[id].zorder);
         objects.reverse();
 
@@ -608,6 +651,28 @@ impl<ID: ObjectID> World<ID> {
         None
     }
 
+    fn objects_in_box(&self, pt1: Pt2D, pt2: Pt2D) -> HashSet<ID> {
+        let mut result = HashSet::new();
+        let rect = if let Some(rect) = Polygon::rectangle_two_corners(pt1, pt2) {
+            rect
+        } else {
+            // The box has no area
+            return result;
+        };
+        for id in self.index.query_bounds(&rect.get_bounds()) {
+            let obj = &self.objects[&id];
+            if obj.draw_hover.is_some()
+                && obj.hitboxes.iter().any(|poly| {
+                    rect.contains_pt(poly.center())
+                        || matches!(poly.intersection(&rect), Ok(list) if !list.is_empty())
+                })
+            {
+                result.insert(id);
+            }
+        }
+        result
+    }
+
     /// Draw objects in the world that're currently visible.
     pub fn draw(&self, g: &mut GfxCtx) {
         // Always draw master batches first
@@ -615,10 +680,7 @@ impl<ID: ObjectID> World<ID> {
             draw.draw(g);
         }
 
-        let mut objects = Vec::new();
-        for id in self.quadtree.query_bbox(g.get_screen_bounds()) {
-            objects.push(id);
-        }
+        let mut objects = self.index.query_bounds(&g.get_screen_bounds());
         objects.sort_by_key(|id| self.objects[id].zorder);
 
         for id in objects {
@@ -640,6 +702,16 @@ impl<ID: ObjectID> World<ID> {
                 obj.draw_normal.draw(g);
             }
         }
+
+        if let Some((pt1, pt2)) = self.box_selecting {
+            if let Some(rect) = Polygon::rectangle_two_corners(pt1, pt2) {
+                g.draw_polygon(Color::CYAN.alpha(0.2), rect.clone());
+                g.draw_polygon(
+                    Color::CYAN.alpha(0.8),
+                    rect.to_outline(Distance::meters(2.0) / g.canvas.cam_zoom),
+                );
+            }
+        }
     }
 
     /// Returns the object currently hovered on.
@@ -0,0 +1,150 @@
+//! Record every input event during a session, then play the session back later. As long as the
+//! app only changes in response to events (including `Event::Update`, which carries the elapsed
+//! time), playback is deterministic. This is useful for demos, bug reports, and teaching.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use abstutil::Timer;
+use geom::Duration;
+
+use crate::{Canvas, Event, ScreenDims};
+
+// Save the recording this often, in case the app exits without closing the window
+const SAVE_FREQUENCY: Duration = Duration::const_seconds(30.0);
+
+/// A recorded session
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    /// Anything the app needs to reproduce the same starting conditions, like command-line
+    /// arguments. widgetry doesn't interpret this.
+    pub metadata: String,
+    /// The size of the window while recording. If the window is a different size during
+    /// playback, clicks may land on the wrong thing.
+    pub window_size: ScreenDims,
+    pub scale_factor: f64,
+    steps: Vec<ReplayStep>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReplayStep {
+    event: Event,
+    /// (cam_x, cam_y, cam_zoom) after handling the event, only if it changed. Restoring this
+    /// makes playback independent of any camera position the app restores on its own.
+    camera: Option<(f64, f64, f64)>,
+}
+
+impl Replay {
+    pub fn load(path: &str) -> Result<Replay> {
+        abstio::maybe_read_binary(path.to_string(), &mut Timer::throwaway())
+    }
+
+    /// How long the recorded session lasted, counting only time when the app was updating
+    pub fn duration(&self) -> Duration {
+        let mut total = Duration::ZERO;
+        for step in &self.steps {
+            if let Event::Update(dt) = step.event {
+                total += dt;
+            }
+        }
+        total
+    }
+}
+
+pub(crate) struct Recorder {
+    path: String,
+    replay: Replay,
+    last_camera: (f64, f64, f64),
+    unsaved_time: Duration,
+}
+
+impl Recorder {
+    pub fn new(path: String, metadata: String, canvas: &Canvas, scale_factor: f64) -> Recorder {
+        info!("Recording all input to {}", path);
+        Recorder {
+            path,
+            replay: Replay {
+                metadata,
+                window_size: ScreenDims::new(canvas.window_width, canvas.window_height),
+                scale_factor,
+                steps: Vec::new(),
+            },
+            last_camera: camera(canvas),
+            unsaved_time: Duration::ZERO,
+        }
+    }
+
+    /// Call after the event has been handled
+    pub fn record(&mut self, event: Event, canvas: &Canvas) {
+        let cam = camera(canvas);
+        self.replay.steps.push(ReplayStep {
+            event,
+            camera: if cam != self.last_camera {
+                Some(cam)
+            } else {
+                None
+            },
+        });
+        self.last_camera = cam;
+
+        if let Event::Update(dt) = event {
+            self.unsaved_time += dt;
+            if self.unsaved_time >= SAVE_FREQUENCY {
+                self.save();
+            }
+        }
+    }
+
+    pub fn save(&mut self) {
+        self.unsaved_time = Duration::ZERO;
+        abstio::write_binary(self.path.clone(), &self.replay);
+    }
+}
+
+pub(crate) struct Player {
+    replay: Replay,
+    next_step: usize,
+}
+
+impl Player {
+    pub fn new(replay: Replay, canvas: &Canvas) -> Player {
+        if replay.window_size != ScreenDims::new(canvas.window_width, canvas.window_height) {
+            warn!(
+                "This replay was recorded with a window size of {:?}, but the window is now {}x{}. \
+                 Playback may not match.",
+                replay.window_size, canvas.window_width, canvas.window_height
+            );
+        }
+        info!(
+            "Playing back {} events, lasting {}",
+            replay.steps.len(),
+            replay.duration()
+        );
+        Player {
+            replay,
+            next_step: 0,
+        }
+    }
+
+    /// Returns events up to and including the next update, so playback proceeds at roughly the
+    /// same pace as the recording. Each event has the camera to restore after handling it.
+    pub fn next_events(&mut self) -> Vec<(Event, Option<(f64, f64, f64)>)> {
+        let mut events = Vec::new();
+        while let Some(step) = self.replay.steps.get(self.next_step) {
+            self.next_step += 1;
+            events.push((step.event, step.camera));
+            if let Event::Update(_) = step.event {
+                break;
+            }
+        }
+        events
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next_step == self.replay.steps.len()
+    }
+}
+
+fn camera(canvas: &Canvas) -> (f64, f64, f64) {
+    (canvas.cam_x, canvas.cam_y, canvas.cam_zoom)
+}
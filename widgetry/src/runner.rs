@@ -10,10 +10,12 @@ use geom::Duration;
 
 use crate::app_state::App;
 use crate::assets::Assets;
-use crate::tools::screenshot::screenshot_everything;
+use crate::replay::{Player, Recorder, Replay};
+use crate::tools::screenshot::{screenshot_everything, screenshot_viewport};
+use crate::touch::TouchTracker;
 use crate::{
-    Canvas, CanvasSettings, Event, EventCtx, GfxCtx, Prerender, SharedAppState, Style, Text,
-    UpdateType, UserInput,
+    Canvas, CanvasSettings, Event, EventCtx, GfxCtx, Key, Line, Prerender, ScreenPt,
+    SharedAppState, Style, Text, UpdateType, UserInput,
 };
 
 const UPDATE_FREQUENCY: std::time::Duration = std::time::Duration::from_millis(1000 / 30);
@@ -91,6 +93,7 @@ impl<A: 'static + SharedAppState> State<A> {
 
             if let Some(pt) = input.get_moved_mouse() {
                 self.canvas.cursor = pt;
+                self.canvas.cursor_moved_at = Instant::now();
             }
 
             if input.event == Event::WindowGainedCursor {
@@ -101,6 +104,20 @@ impl<A: 'static + SharedAppState> State<A> {
             }
         }
 
+        // Shift+F1 toggles "what's this?" mode everywhere, so apps don't each need to handle it.
+        // Escape also leaves the mode.
+        if input.event == Event::KeyPress(Key::F1)
+            && self.canvas.keys_held.contains(&Key::LeftShift)
+        {
+            self.canvas.whats_this = !self.canvas.whats_this;
+            return (Vec::new(), true);
+        }
+        if self.canvas.whats_this && input.event == Event::KeyPress(Key::Escape) {
+            self.canvas.whats_this = false;
+            return (Vec::new(), true);
+        }
+
+        self.canvas.keyboard_focus_renewed = false;
         match panic::catch_unwind(panic::AssertUnwindSafe(|| {
             let mut ctx = EventCtx {
                 fake_mouseover: false,
@@ -119,6 +136,10 @@ impl<A: 'static + SharedAppState> State<A> {
             let started = Instant::now();
             self.app.event(&mut ctx);
             self.focus_owned_by = ctx.next_focus_owned_by.take();
+            // The panel with keyboard focus is gone or isn't taking input anymore
+            if !ctx.canvas.keyboard_focus_renewed {
+                ctx.canvas.keyboard_focus = None;
+            }
             if DEBUG_PERFORMANCE {
                 println!("- event() took {}s", elapsed_seconds(started));
             }
@@ -167,6 +188,16 @@ impl<A: 'static + SharedAppState> State<A> {
             self.app.shared_app_state.dump_before_abort(&self.canvas);
             panic::resume_unwind(err);
         }
+        if self.canvas.whats_this {
+            g.draw_tooltip_at(
+                Text::from_multiline(vec![
+                    Line("What's this?").small_heading(),
+                    Line("Hover over anything to learn about it."),
+                    Line("Press Shift+F1 or Escape to stop."),
+                ]),
+                ScreenPt::new(10.0, 10.0),
+            );
+        }
         let naming_hint = g.naming_hint.take();
 
         if DEBUG_PERFORMANCE {
@@ -203,6 +234,8 @@ pub struct Settings {
     load_default_textures: bool,
     pub(crate) read_svg: Box<dyn Fn(&str) -> Vec<u8>>,
     pub(crate) canvas_settings: CanvasSettings,
+    record_replay: Option<(String, String)>,
+    play_replay: Option<Replay>,
 }
 
 impl Settings {
@@ -231,6 +264,8 @@ impl Settings {
                 buffer
             }),
             canvas_settings: CanvasSettings::new(),
+            record_replay: None,
+            play_replay: None,
         }
     }
 
@@ -306,6 +341,23 @@ impl Settings {
         self.load_default_textures = load_default_textures;
         self
     }
+
+    /// Record every input event and camera movement to a file at this path, which can later be
+    /// played back with `play_replay`. The metadata is stored in the file for the app to
+    /// reproduce its starting conditions.
+    pub fn record_replay(mut self, path: String, metadata: String) -> Self {
+        assert!(self.play_replay.is_none());
+        self.record_replay = Some((path, metadata));
+        self
+    }
+
+    /// Ignore real input and instead play back a recorded session. When the recording ends, real
+    /// input works again.
+    pub fn play_replay(mut self, replay: Replay) -> Self {
+        assert!(self.record_replay.is_none());
+        self.play_replay = Some(replay);
+        self
+    }
 }
 
 pub fn run<
@@ -401,6 +453,12 @@ pub fn run<
     };
 
     let dump_raw_events = settings.dump_raw_events;
+    let mut recorder = settings.record_replay.map(|(path, metadata)| {
+        Recorder::new(path, metadata, &state.canvas, prerender.get_scale_factor())
+    });
+    let mut player = settings
+        .play_replay
+        .map(|replay| Player::new(replay, &state.canvas));
 
     let mut running = true;
     let mut last_update = Instant::now();
@@ -409,11 +467,14 @@ pub fn run<
 
     // Remember the last keycode, so that we can suppress a sequence like Alt+Tab
     let mut previous_keycode = None;
+    let mut touches = TouchTracker::new();
+    // Tooltips appear after the mouse rests, even if nothing else happens, so remember to redraw
+    let mut tooltip_pending = false;
     event_loop.run(move |event, _, control_flow| {
         if dump_raw_events {
             debug!("Event: {:?}", event);
         }
-        let ev = match event {
+        let events: Vec<(Event, Option<(f64, f64, f64)>)> = match event {
             winit::event::Event::WindowEvent {
                 event: winit::event::WindowEvent::CloseRequested,
                 ..
@@ -422,8 +483,15 @@ pub fn run<
                 // GPU stuff is dropped. Better to just abort violently and let the OS clean
                 // up.
                 state.app.shared_app_state.before_quit(&state.canvas);
+                if let Some(ref mut recorder) = recorder {
+                    recorder.save();
+                }
                 std::process::exit(0);
             }
+            winit::event::Event::WindowEvent { .. } if player.is_some() => {
+                // Ignore real input during playback
+                return;
+            }
             winit::event::Event::WindowEvent { event, .. } => {
                 use winit::event::VirtualKeyCode;
 
@@ -442,10 +510,16 @@ pub fn run<
                 }
 
                 let scale_factor = prerender.get_scale_factor();
-                if let Some(ev) =
+                if let winit::event::WindowEvent::Touch(touch) = event {
+                    let events = touches.event(touch, scale_factor, previous_left_click_at);
+                    if events.is_empty() {
+                        return;
+                    }
+                    events.into_iter().map(|ev| (ev, None)).collect()
+                } else if let Some(ev) =
                     Event::from_winit_event(event, scale_factor, previous_left_click_at)
                 {
-                    ev
+                    vec![(ev, None)]
                 } else {
                     // Don't touch control_flow if we got an irrelevant event
                     return;
@@ -456,69 +530,128 @@ pub fn run<
                 prerender.num_uploads.set(0);
                 return;
             }
+            winit::event::Event::MainEventsCleared if player.is_some() => {
+                let events = player.as_mut().unwrap().next_events();
+                if player.as_ref().unwrap().is_done() {
+                    info!("Replay finished");
+                    player = None;
+                }
+                if events.is_empty() {
+                    return;
+                }
+                events
+            }
             winit::event::Event::MainEventsCleared => {
+                if tooltip_pending && state.canvas.tooltip_ready() {
+                    tooltip_pending = false;
+                    prerender.request_redraw();
+                    if !running {
+                        *control_flow = winit::event_loop::ControlFlow::Wait;
+                    }
+                }
+                let mut events = touches.check_long_press();
                 // We might've switched to InputOnly after the WaitUntil was requested.
                 if running {
-                    Event::Update(Duration::realtime_elapsed(last_update))
-                } else {
+                    events.push(Event::Update(Duration::realtime_elapsed(last_update)));
+                }
+                if events.is_empty() {
                     return;
                 }
+                events.into_iter().map(|ev| (ev, None)).collect()
             }
             _ => {
                 return;
             }
         };
 
-        // We want a max of UPDATE_FREQUENCY between updates, so measure the update time before
-        // doing the work (which takes time).
-        match ev {
-            Event::Update(_) => {
-                last_update = Instant::now();
-                *control_flow =
-                    winit::event_loop::ControlFlow::WaitUntil(Instant::now() + UPDATE_FREQUENCY);
+        for (ev, replay_camera) in events {
+            // We want a max of UPDATE_FREQUENCY between updates, so measure the update time before
+            // doing the work (which takes time).
+            match ev {
+                Event::Update(_) => {
+                    last_update = Instant::now();
+                    *control_flow = winit::event_loop::ControlFlow::WaitUntil(
+                        Instant::now() + UPDATE_FREQUENCY,
+                    );
+                }
+                Event::LeftMouseButtonUp {
+                    is_double_click: false,
+                } => {
+                    previous_left_click_at = Instant::now();
+                }
+                _ => {}
             }
-            Event::LeftMouseButtonUp {
-                is_double_click: false,
-            } => {
-                previous_left_click_at = Instant::now();
+
+            let (mut updates, input_used) = state.event(ev, &prerender);
+            if let Some((cam_x, cam_y, cam_zoom)) = replay_camera {
+                state.canvas.cam_x = cam_x;
+                state.canvas.cam_y = cam_y;
+                state.canvas.cam_zoom = cam_zoom;
+                prerender.request_redraw();
+            }
+            if let Some(ref mut recorder) = recorder {
+                recorder.record(ev, &state.canvas);
             }
-            _ => {}
-        }
 
-        let (mut updates, input_used) = state.event(ev, &prerender);
+            if input_used {
+                prerender.request_redraw();
+            }
 
-        if input_used {
-            prerender.request_redraw();
+            if updates.is_empty() {
+                updates.push(UpdateType::InputOnly);
+            }
+            for update in updates {
+                match update {
+                    UpdateType::InputOnly => {
+                        running = false;
+                        *control_flow = winit::event_loop::ControlFlow::Wait;
+                    }
+                    UpdateType::Game => {
+                        // If we just unpaused, then don't act as if lots of time has passed.
+                        if !running {
+                            last_update = Instant::now();
+                            *control_flow = winit::event_loop::ControlFlow::WaitUntil(
+                                Instant::now() + UPDATE_FREQUENCY,
+                            );
+                        }
+
+                        running = true;
+                    }
+                    UpdateType::Pan => {}
+                    UpdateType::ScreenCaptureEverything { dir, zoom, dims } => {
+                        if let Err(err) =
+                            screenshot_everything(&mut state, &dir, &prerender, zoom, dims)
+                        {
+                            error!("Couldn't screenshot everything: {}", err);
+                        }
+                    }
+                    UpdateType::ScreenCaptureViewport { filename } => {
+                        if let Err(err) = screenshot_viewport(&mut state, &filename, &prerender) {
+                            error!("Couldn't screenshot {}: {}", filename, err);
+                        }
+                    }
+                }
+            }
         }
 
-        if updates.is_empty() {
-            updates.push(UpdateType::InputOnly);
+        if player.is_some() {
+            // Keep waking up to play the rest, even if the app isn't updating
+            *control_flow =
+                winit::event_loop::ControlFlow::WaitUntil(Instant::now() + UPDATE_FREQUENCY);
         }
-        for update in updates {
-            match update {
-                UpdateType::InputOnly => {
-                    running = false;
-                    *control_flow = winit::event_loop::ControlFlow::Wait;
-                }
-                UpdateType::Game => {
-                    // If we just unpaused, then don't act as if lots of time has passed.
-                    if !running {
-                        last_update = Instant::now();
-                        *control_flow = winit::event_loop::ControlFlow::WaitUntil(
-                            Instant::now() + UPDATE_FREQUENCY,
-                        );
-                    }
 
-                    running = true;
-                }
-                UpdateType::Pan => {}
-                UpdateType::ScreenCaptureEverything { dir, zoom, dims } => {
-                    if let Err(err) =
-                        screenshot_everything(&mut state, &dir, &prerender, zoom, dims)
-                    {
-                        error!("Couldn't screenshot everything: {}", err);
-                    }
-                }
+        let tooltip_deadline = state.canvas.tooltip_deadline();
+        tooltip_pending = tooltip_deadline.is_some();
+
+        // Without game updates, nothing else would wake us up to notice a long press or show a
+        // tooltip
+        if !running && player.is_none() {
+            let deadline = match (touches.long_press_deadline(), tooltip_deadline) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            if let Some(deadline) = deadline {
+                *control_flow = winit::event_loop::ControlFlow::WaitUntil(deadline);
             }
         }
     });
@@ -5,7 +5,7 @@ use geom::{trim_f64, Polygon, Pt2D};
 use crate::{Canvas, EdgeInsets};
 
 /// ScreenPt is in units of logical pixels, as opposed to physical pixels.
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct ScreenPt {
     pub x: f64,
     pub y: f64,
@@ -18,6 +18,8 @@ pub struct Style {
     pub loading_tips: Text,
     pub section_bg: Color,
     pub section_outline: OutlineStyle,
+    /// Drawn around the widget with keyboard focus
+    pub focus_outline: OutlineStyle,
     pub btn_plain: ButtonStyle,
     pub btn_outline: ButtonStyle,
     pub btn_floating: ButtonStyle,
@@ -160,6 +162,7 @@ impl Style {
             // TODO: replace inner_panel_bg with this
             section_bg: Color::WHITE,
             section_outline: (2.0, Color::WHITE.shade(0.1)),
+            focus_outline: (3.0, AB_ORANGE_1),
             loading_tips: Text::new(),
             icon_fg: hex("#4C4C4C"),
             primary_fg: AB_ORANGE_1,
@@ -198,6 +201,7 @@ impl Style {
             // TODO: replace inner_panel_bg with this
             section_bg: navy,
             section_outline: (DEFAULT_OUTLINE_THICKNESS, navy.shade(0.2)),
+            focus_outline: (3.0, AB_ORANGE_1),
             loading_tips: Text::new(),
             icon_fg: Color::WHITE,
             primary_fg: AB_ORANGE_1,
@@ -260,6 +260,15 @@ impl Text {
         self
     }
 
+    /// All of the lines, without any styling
+    pub(crate) fn to_plain_string(&self) -> String {
+        self.lines
+            .iter()
+            .map(|(_, spans)| spans.iter().map(|s| s.text.as_str()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
     pub fn add_line(&mut self, line: impl Into<TextSpan>) {
         self.lines.push((None, vec![line.into()]));
     }
@@ -395,7 +404,7 @@ impl Text {
     }
 
     pub fn into_widget(self, ctx: &EventCtx) -> Widget {
-        JustDraw::wrap(ctx, self.render(ctx))
+        JustDraw::wrap_text(ctx, self)
     }
     pub fn batch(self, ctx: &EventCtx) -> Widget {
         DeferDraw::new_widget(self.render(ctx))
@@ -1,5 +1,6 @@
 use geom::{Circle, Distance, Line, Polygon, Pt2D, Tessellation};
 
+use crate::tools::Pattern;
 use crate::{Color, EventCtx, Fill, GeomBatch, Line, LinearGradient, Text, Widget};
 
 pub struct ColorLegend {}
@@ -18,6 +19,23 @@ impl ColorLegend {
         ])
     }
 
+    /// Like `row`, but the swatch also shows a pattern drawn over the color.
+    pub fn pattern_row(
+        ctx: &EventCtx,
+        color: Color,
+        pattern: Pattern,
+        label: impl AsRef<str>,
+    ) -> Widget {
+        let square = Polygon::rectangle(30.0, 30.0);
+        let mut batch = GeomBatch::new();
+        batch.push(color, square.clone());
+        pattern.draw(&mut batch, &square, Distance::meters(8.0));
+        Widget::row(vec![
+            batch.into_widget(ctx).centered_vert(),
+            Text::from(label).wrap_to_pct(ctx, 35).into_widget(ctx),
+        ])
+    }
+
     pub fn gradient_with_width<I: Into<String>>(
         ctx: &mut EventCtx,
         scale: &ColorScale,
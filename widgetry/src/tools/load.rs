@@ -457,3 +457,46 @@ where
         self.panel.draw(g);
     }
 }
+
+/// Runs futures without blocking the UI, unlike `FutureLoader`. Callers hold onto the receiver
+/// and check it with `try_recv` on later events.
+pub struct BackgroundRunner {
+    // See the comment in FutureLoader
+    #[cfg(not(target_arch = "wasm32"))]
+    runtime: Runtime,
+}
+
+impl BackgroundRunner {
+    pub fn new() -> BackgroundRunner {
+        BackgroundRunner {
+            #[cfg(not(target_arch = "wasm32"))]
+            runtime: Runtime::new().unwrap(),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn spawn<T: 'static>(
+        &self,
+        future: Pin<Box<dyn Future<Output = Result<T>>>>,
+    ) -> oneshot::Receiver<Result<T>> {
+        let (tx, rx) = oneshot::channel();
+        wasm_bindgen_futures::spawn_local(async move {
+            // The caller may have stopped listening
+            let _ = tx.send(future.await);
+        });
+        rx
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn spawn<T: 'static + Send>(
+        &self,
+        future: Pin<Box<dyn Send + Future<Output = Result<T>>>>,
+    ) -> oneshot::Receiver<Result<T>> {
+        let (tx, rx) = oneshot::channel();
+        self.runtime.spawn(async move {
+            // The caller may have stopped listening
+            let _ = tx.send(future.await);
+        });
+        rx
+    }
+}
@@ -2,9 +2,12 @@ mod choose_something;
 mod colors;
 mod lasso;
 mod load;
+mod pattern;
 mod popup;
 mod prompt_input;
 pub(crate) mod screenshot;
+mod translations;
+mod tween;
 mod url;
 pub(crate) mod warper;
 
@@ -13,9 +16,13 @@ use anyhow::Result;
 pub use choose_something::ChooseSomething;
 pub use colors::{ColorLegend, ColorScale, DivergingScale};
 pub use lasso::{Lasso, PolyLineLasso};
-pub use load::{FileLoader, FutureLoader, RawBytes};
+pub use load::{BackgroundRunner, FileLoader, FutureLoader, RawBytes};
+pub use pattern::Pattern;
 pub use popup::PopupMsg;
 pub use prompt_input::PromptInput;
+pub use screenshot::frames_to_gif;
+pub use translations::Translations;
+pub use tween::{AnimatedDrawable, Easing, Interpolate, Tween};
 pub use url::URLManager;
 
 use crate::{Color, GfxCtx};
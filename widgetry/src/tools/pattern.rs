@@ -0,0 +1,80 @@
+use geom::{Distance, Line, Polygon, Pt2D};
+
+use crate::{Color, GeomBatch};
+
+/// Stripes drawn on top of a fill color, so that categories can be told apart without relying on
+/// hue alone.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Pattern {
+    Solid,
+    DiagonalUp,
+    DiagonalDown,
+    CrossHatch,
+    Horizontal,
+    Vertical,
+}
+
+impl Pattern {
+    /// Cycles through all patterns, starting with a plain fill.
+    pub fn nth(idx: usize) -> Pattern {
+        let all = [
+            Pattern::Solid,
+            Pattern::DiagonalUp,
+            Pattern::DiagonalDown,
+            Pattern::CrossHatch,
+            Pattern::Horizontal,
+            Pattern::Vertical,
+        ];
+        all[idx % all.len()]
+    }
+
+    /// Returns stripes covering the polygon, `spacing` apart.
+    pub fn hatch(self, polygon: &Polygon, spacing: Distance) -> Vec<Polygon> {
+        let directions: Vec<(f64, f64)> = match self {
+            Pattern::Solid => {
+                return Vec::new();
+            }
+            Pattern::DiagonalUp => vec![(1.0, -1.0)],
+            Pattern::DiagonalDown => vec![(1.0, 1.0)],
+            Pattern::CrossHatch => vec![(1.0, -1.0), (1.0, 1.0)],
+            Pattern::Horizontal => vec![(1.0, 0.0)],
+            Pattern::Vertical => vec![(0.0, 1.0)],
+        };
+
+        let bounds = polygon.get_bounds();
+        let center = Pt2D::new(
+            (bounds.min_x + bounds.max_x) / 2.0,
+            (bounds.min_y + bounds.max_y) / 2.0,
+        );
+        // Each stripe is long enough to cross the whole polygon, no matter the direction
+        let radius = (bounds.max_x - bounds.min_x).hypot(bounds.max_y - bounds.min_y) / 2.0;
+        let step = spacing.inner_meters();
+        let num_steps = (radius / step).ceil() as isize;
+
+        let mut stripes = Vec::new();
+        for (dx, dy) in directions {
+            let len = dx.hypot(dy);
+            let (dx, dy) = (dx / len, dy / len);
+            for i in -num_steps..=num_steps {
+                // Shift perpendicular to the stripe's direction
+                let offset = (i as f64) * step;
+                let x = center.x() - dy * offset;
+                let y = center.y() + dx * offset;
+                if let Ok(line) = Line::new(
+                    Pt2D::new(x - dx * radius, y - dy * radius),
+                    Pt2D::new(x + dx * radius, y + dy * radius),
+                ) {
+                    if let Ok(list) = line.make_polygons(spacing * 0.25).intersection(polygon) {
+                        stripes.extend(list);
+                    }
+                }
+            }
+        }
+        stripes
+    }
+
+    /// Draws the stripes for this pattern over a polygon that's already been filled.
+    pub fn draw(self, batch: &mut GeomBatch, polygon: &Polygon, spacing: Distance) {
+        batch.extend(Color::BLACK.alpha(0.5), self.hatch(polygon, spacing));
+    }
+}
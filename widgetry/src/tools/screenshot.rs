@@ -1,3 +1,8 @@
+use std::path::Path;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame};
+
 use abstutil::Timer;
 
 use crate::runner::State;
@@ -71,3 +76,35 @@ pub(crate) fn screenshot_everything<A: 'static + SharedAppState>(
     state.canvas.cam_y = orig_y;
     Ok(())
 }
+
+/// Take a screenshot of just the current viewport.
+pub(crate) fn screenshot_viewport<A: 'static + SharedAppState>(
+    state: &mut State<A>,
+    filename: &str,
+    prerender: &Prerender,
+) -> anyhow::Result<()> {
+    if let Some(dir) = Path::new(filename).parent() {
+        fs_err::create_dir_all(dir)?;
+    }
+    state.draw(prerender, true);
+    prerender
+        .inner
+        .screencap(state.canvas.get_window_dims(), filename.to_string())
+}
+
+/// Combine PNG frames (like the ones from `UpdateType::ScreenCaptureViewport`) into a looping
+/// animated GIF, showing each frame for `delay_ms`.
+pub fn frames_to_gif(frames: &[String], output: &str, delay_ms: u32) -> anyhow::Result<()> {
+    let mut encoder = GifEncoder::new(fs_err::File::create(output)?);
+    encoder.set_repeat(Repeat::Infinite)?;
+    for path in frames {
+        let img = image::open(path)?.to_rgba8();
+        encoder.encode_frame(Frame::from_parts(
+            img,
+            0,
+            0,
+            Delay::from_numer_denom_ms(delay_ms, 1),
+        ))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+
+/// A catalog of translated UI strings for one language. Catalogs are written in a small subset of
+/// the Fluent syntax (<https://projectfluent.org>):
+///
+/// ```text
+/// # Comments start with a hash
+/// options-title = Settings
+/// options-map-label = Map: { $name }
+/// long-message = Indented lines
+///     continue the message above
+/// ```
+///
+/// Terms, selectors, and attributes aren't supported. Any message missing from a catalog falls
+/// back to the English text given at the call site.
+#[derive(Clone, Default)]
+pub struct Translations {
+    language: Option<String>,
+    messages: BTreeMap<String, String>,
+}
+
+impl Translations {
+    /// No catalog; every lookup uses the English text.
+    pub fn english() -> Translations {
+        Translations::default()
+    }
+
+    /// Load the catalog for a language, like "fr" or "pt-BR".
+    pub fn load(language: &str) -> Result<Translations> {
+        let bytes = abstio::slurp_file(path(language))?;
+        Translations::parse(language, &String::from_utf8(bytes)?)
+    }
+
+    /// All languages with a catalog available locally
+    pub fn list_languages() -> Vec<String> {
+        abstio::list_all_objects(abstio::path("system/assets/translations"))
+    }
+
+    pub fn parse(language: &str, contents: &str) -> Result<Translations> {
+        let mut messages = BTreeMap::new();
+        let mut current: Option<String> = None;
+        for (idx, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                current = None;
+                continue;
+            }
+            if line.starts_with(char::is_whitespace) {
+                match current {
+                    Some(ref id) => {
+                        let value: &mut String = messages.get_mut(id).unwrap();
+                        if !value.is_empty() {
+                            value.push('\n');
+                        }
+                        value.push_str(line.trim());
+                    }
+                    None => bail!("line {}: indented line doesn't continue a message", idx + 1),
+                }
+                continue;
+            }
+
+            let (id, value) = match line.split_once('=') {
+                Some(pair) => pair,
+                None => bail!("line {}: expected \"id = message\"", idx + 1),
+            };
+            let id = id.trim();
+            if !is_valid_id(id) {
+                bail!("line {}: {} isn't a valid message ID", idx + 1, id);
+            }
+            if messages
+                .insert(id.to_string(), value.trim().to_string())
+                .is_some()
+            {
+                bail!("line {}: {} is defined twice", idx + 1, id);
+            }
+            current = Some(id.to_string());
+        }
+
+        Ok(Translations {
+            language: Some(language.to_string()),
+            messages,
+        })
+    }
+
+    /// None means English
+    pub fn language(&self) -> Option<&String> {
+        self.language.as_ref()
+    }
+
+    /// Look up a message, or use the English text if this catalog doesn't have it.
+    pub fn get(&self, id: &str, english: &str) -> String {
+        self.get_args(id, english, &[])
+    }
+
+    /// Look up a message and fill in its `{ $name }` placeholders. If this catalog doesn't have
+    /// the message, the English text is used and filled in the same way.
+    pub fn get_args(&self, id: &str, english: &str, args: &[(&str, String)]) -> String {
+        let template = self.messages.get(id).map(|x| x.as_str()).unwrap_or(english);
+        let mut result = template.to_string();
+        for (name, value) in args {
+            result = result
+                .replace(&format!("{{ ${} }}", name), value)
+                .replace(&format!("{{${}}}", name), value);
+        }
+        result
+    }
+}
+
+fn path(language: &str) -> String {
+    abstio::path(format!("system/assets/translations/{}.ftl", language))
+}
+
+fn is_valid_id(id: &str) -> bool {
+    let mut chars = id.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() => {}
+        _ => {
+            return false;
+        }
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let t = Translations::parse(
+            "test",
+            "# A comment\n\
+             greeting = Bonjour\n\
+             with-arg = Carte : { $name }\n\
+             multi = Première ligne\n    deuxième ligne\n",
+        )
+        .unwrap();
+        assert_eq!(t.get("greeting", "Hello"), "Bonjour");
+        assert_eq!(
+            t.get_args(
+                "with-arg",
+                "Map: { $name }",
+                &[("name", "Seattle".to_string())]
+            ),
+            "Carte : Seattle"
+        );
+        assert_eq!(t.get("multi", ""), "Première ligne\ndeuxième ligne");
+        // Missing messages fall back to English
+        assert_eq!(
+            t.get_args(
+                "missing",
+                "Map: { $name }",
+                &[("name", "Seattle".to_string())]
+            ),
+            "Map: Seattle"
+        );
+
+        assert!(Translations::parse("test", "no equals sign").is_err());
+        assert!(Translations::parse("test", "a = 1\na = 2").is_err());
+        assert!(Translations::parse("test", "  dangling").is_err());
+    }
+}
@@ -0,0 +1,258 @@
+use instant::Instant;
+
+use geom::{Duration, Pt2D};
+
+use crate::drawing::MAPSPACE_Z;
+use crate::{Color, Drawable, EventCtx, GeomBatch, GfxCtx, RewriteColor, UpdateType};
+
+/// How an animation progresses over time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    /// Start slowly and speed up
+    EaseIn,
+    /// Start quickly and slow down
+    EaseOut,
+    /// Start and end slowly
+    EaseInOut,
+}
+
+impl Easing {
+    /// Maps the fraction of time elapsed, from 0 to 1, to the fraction of the change to apply.
+    pub fn apply(self, pct: f64) -> f64 {
+        let t = pct.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+/// Something that can be smoothly changed from one value to another
+pub trait Interpolate: Copy {
+    fn interpolate(self, other: Self, pct: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn interpolate(self, other: f64, pct: f64) -> f64 {
+        self + pct * (other - self)
+    }
+}
+
+impl Interpolate for Pt2D {
+    fn interpolate(self, other: Pt2D, pct: f64) -> Pt2D {
+        Pt2D::new(
+            self.x().interpolate(other.x(), pct),
+            self.y().interpolate(other.y(), pct),
+        )
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(self, other: Color, pct: f64) -> Color {
+        self.lerp(other, pct)
+    }
+}
+
+/// Changes a value over wall-clock time. While the tween is running, call `event` to keep
+/// receiving update events, so the new value gets drawn.
+pub struct Tween<T: Interpolate> {
+    from: T,
+    to: T,
+    started: Instant,
+    duration: Duration,
+    easing: Easing,
+}
+
+impl<T: Interpolate> Tween<T> {
+    pub fn new(from: T, to: T, duration: Duration, easing: Easing) -> Tween<T> {
+        Tween {
+            from,
+            to,
+            started: Instant::now(),
+            duration,
+            easing,
+        }
+    }
+
+    /// A tween that's already finished, staying at one value
+    pub fn constant(value: T) -> Tween<T> {
+        Tween::new(value, value, Duration::ZERO, Easing::Linear)
+    }
+
+    pub fn value(&self) -> T {
+        if self.is_done() {
+            return self.to;
+        }
+        let pct = abstutil::elapsed_seconds(self.started) / self.duration.inner_seconds();
+        self.from.interpolate(self.to, self.easing.apply(pct))
+    }
+
+    /// The value the tween ends at
+    pub fn target(&self) -> T {
+        self.to
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.duration == Duration::ZERO
+            || abstutil::elapsed_seconds(self.started) >= self.duration.inner_seconds()
+    }
+
+    /// Start animating towards a new value, from wherever the tween currently is. This avoids a
+    /// jump when a transition is interrupted by another one.
+    pub fn retarget(&mut self, to: T, duration: Duration, easing: Easing) {
+        *self = Tween::new(self.value(), to, duration, easing);
+    }
+
+    /// Returns true while the tween is still running.
+    pub fn event(&self, ctx: &mut EventCtx) -> bool {
+        if self.is_done() {
+            return false;
+        }
+        ctx.request_update(UpdateType::Game);
+        true
+    }
+}
+
+/// A map-space `GeomBatch` that can be moved, scaled, and faded over time. Moving and scaling
+/// don't need to upload anything again; fading re-uploads the batch while the alpha changes.
+pub struct AnimatedDrawable {
+    batch: GeomBatch,
+    draw: Drawable,
+    drawn_alpha: f64,
+    // Scaling happens around this point
+    center: Pt2D,
+
+    offset: Tween<Pt2D>,
+    scale: Tween<f64>,
+    alpha: Tween<f64>,
+}
+
+impl AnimatedDrawable {
+    pub fn new(ctx: &EventCtx, batch: GeomBatch) -> AnimatedDrawable {
+        let center = batch.get_bounds().center();
+        AnimatedDrawable {
+            draw: ctx.upload(batch.clone()),
+            batch,
+            drawn_alpha: 1.0,
+            center,
+
+            offset: Tween::constant(Pt2D::new(0.0, 0.0)),
+            scale: Tween::constant(1.0),
+            alpha: Tween::constant(1.0),
+        }
+    }
+
+    /// Starts invisible, then fades in
+    pub fn fade_in(ctx: &EventCtx, batch: GeomBatch, duration: Duration) -> AnimatedDrawable {
+        let mut draw = AnimatedDrawable::new(ctx, batch);
+        draw.alpha = Tween::new(0.0, 1.0, duration, Easing::EaseOut);
+        draw
+    }
+
+    /// Move the batch so it's offset this much from where it was built
+    pub fn move_to(&mut self, offset: Pt2D, duration: Duration, easing: Easing) {
+        self.offset.retarget(offset, duration, easing);
+    }
+
+    /// Grow or shrink the batch around its center. 1.0 is the original size.
+    pub fn scale_to(&mut self, scale: f64, duration: Duration, easing: Easing) {
+        self.scale.retarget(scale, duration, easing);
+    }
+
+    /// 0.0 is invisible, and 1.0 is the original opacity.
+    pub fn fade_to(&mut self, alpha: f64, duration: Duration, easing: Easing) {
+        self.alpha.retarget(alpha, duration, easing);
+    }
+
+    /// True once every animation has finished
+    pub fn is_done(&self) -> bool {
+        self.offset.is_done() && self.scale.is_done() && self.alpha.is_done()
+    }
+
+    /// True if the batch has faded out completely and doesn't need to be drawn anymore
+    pub fn is_hidden(&self) -> bool {
+        self.alpha.is_done() && self.alpha.target() <= 0.0
+    }
+
+    /// Call every event while the drawable is shown. Returns true while anything is animating.
+    pub fn event(&mut self, ctx: &mut EventCtx) -> bool {
+        let alpha = self.alpha.value();
+        // Don't upload again for imperceptible changes, but make sure to land on the final value
+        let threshold = if self.alpha.is_done() { 0.0 } else { 0.01 };
+        if (alpha - self.drawn_alpha).abs() > threshold {
+            self.draw = ctx.upload(
+                self.batch
+                    .clone()
+                    .color(RewriteColor::MultiplyAlpha(alpha as f32)),
+            );
+            self.drawn_alpha = alpha;
+        }
+
+        let offset = self.offset.event(ctx);
+        let scale = self.scale.event(ctx);
+        let alpha = self.alpha.event(ctx);
+        offset || scale || alpha
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        if self.drawn_alpha <= 0.0 {
+            return;
+        }
+        let offset = self.offset.value();
+        let scale = self.scale.value();
+        #[allow(clippy::float_cmp)]
+        if scale == 1.0 && offset == Pt2D::new(0.0, 0.0) {
+            g.redraw(&self.draw);
+            return;
+        }
+
+        // Anchor the center, after moving it, and zoom around it
+        let moved_center = self.center.offset(offset.x(), offset.y());
+        g.fork(
+            self.center,
+            g.canvas.map_to_screen(moved_center),
+            g.canvas.cam_zoom * scale,
+            Some(MAPSPACE_Z),
+        );
+        g.redraw(&self.draw);
+        g.unfork();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Easing;
+
+    #[test]
+    fn test_easing() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+            // Out of range is clamped
+            assert_eq!(easing.apply(1.5), 1.0);
+
+            // Always moving forwards
+            let mut last = 0.0;
+            for i in 1..=100 {
+                let value = easing.apply((i as f64) / 100.0);
+                assert!(value >= last);
+                last = value;
+            }
+        }
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+    }
+}
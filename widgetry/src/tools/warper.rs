@@ -2,6 +2,7 @@ use instant::Instant;
 
 use geom::{Line, Pt2D};
 
+use crate::tools::Easing;
 use crate::{EventCtx, UpdateType};
 
 pub struct Warper {
@@ -54,6 +55,8 @@ impl Warper {
             }
             false
         } else {
+            // Ease in and out, so the camera doesn't abruptly start and stop moving
+            let percent = Easing::EaseInOut.apply(percent);
             ctx.canvas.cam_zoom = self.cam_zoom.0 + percent * (self.cam_zoom.1 - self.cam_zoom.0);
             if let Some(ref line) = self.line {
                 ctx.canvas
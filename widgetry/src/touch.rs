@@ -0,0 +1,194 @@
+use instant::Instant;
+use winit::event::{Touch, TouchPhase};
+
+use crate::event::MAX_DOUBLE_CLICK_DURATION;
+use crate::{Event, ScreenPt};
+
+// Holding one finger still for this long acts like a right click
+const LONG_PRESS_DURATION: instant::Duration = instant::Duration::from_millis(500);
+// Matches the canvas's DRAG_THRESHOLD; moving a finger further than this starts a drag
+const MOVE_THRESHOLD: f64 = 5.0;
+
+/// Translates touchscreen input into the mouse-like events the rest of widgetry understands:
+///
+/// - tapping is a left click, and double tapping is a double click
+/// - dragging one finger presses the left button and moves the cursor, panning the map
+/// - pinching two fingers zooms, centered between them
+/// - holding one finger still acts like a right click
+pub(crate) struct TouchTracker {
+    // Fingers currently down, in the order they touched
+    fingers: Vec<(u64, ScreenPt)>,
+    state: State,
+}
+
+enum State {
+    Idle,
+    // One finger is down, but we don't know yet if this is a tap, drag, or long press
+    Pending { start: ScreenPt, since: Instant },
+    // The left button is held down and the cursor follows the finger
+    Dragging,
+    // The left button is held down, the cursor is between the two fingers, and the distance
+    // between them controls zoom
+    Pinching { distance: f64 },
+    // A gesture ended, but some fingers are still down. Ignore them until they're lifted.
+    Finished,
+}
+
+impl TouchTracker {
+    pub fn new() -> TouchTracker {
+        TouchTracker {
+            fingers: Vec::new(),
+            state: State::Idle,
+        }
+    }
+
+    pub fn event(
+        &mut self,
+        touch: Touch,
+        scale_factor: f64,
+        previous_click: Instant,
+    ) -> Vec<Event> {
+        let pos = touch.location.to_logical(scale_factor);
+        let pt = ScreenPt::new(pos.x, pos.y);
+
+        match touch.phase {
+            TouchPhase::Started => {
+                self.fingers.push((touch.id, pt));
+                self.finger_down(pt)
+            }
+            TouchPhase::Moved => {
+                if let Some(finger) = self.fingers.iter_mut().find(|(id, _)| *id == touch.id) {
+                    finger.1 = pt;
+                } else {
+                    return Vec::new();
+                }
+                self.finger_moved(pt)
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                let before = self.fingers.len();
+                self.fingers.retain(|(id, _)| *id != touch.id);
+                if self.fingers.len() == before {
+                    return Vec::new();
+                }
+                self.finger_up(touch.phase == TouchPhase::Cancelled, previous_click)
+            }
+        }
+    }
+
+    /// Called periodically. Returns a right click if a finger has been held still long enough.
+    pub fn check_long_press(&mut self) -> Vec<Event> {
+        if let State::Pending { since, .. } = self.state {
+            if since.elapsed() >= LONG_PRESS_DURATION {
+                self.state = State::Finished;
+                return vec![Event::RightMouseButtonDown, Event::RightMouseButtonUp];
+            }
+        }
+        Vec::new()
+    }
+
+    /// If a finger might turn into a long press, when should `check_long_press` next be called?
+    pub fn long_press_deadline(&self) -> Option<Instant> {
+        if let State::Pending { since, .. } = self.state {
+            Some(since + LONG_PRESS_DURATION)
+        } else {
+            None
+        }
+    }
+
+    fn finger_down(&mut self, pt: ScreenPt) -> Vec<Event> {
+        match self.state {
+            State::Idle => {
+                self.state = State::Pending {
+                    start: pt,
+                    since: Instant::now(),
+                };
+                vec![Event::MouseMovedTo(pt)]
+            }
+            State::Pending { .. } | State::Dragging => {
+                if self.fingers.len() != 2 {
+                    return Vec::new();
+                }
+                let mut events = Vec::new();
+                let pressed = matches!(self.state, State::Dragging);
+                // Move the cursor before pressing the button, so the drag starts from the
+                // midpoint
+                events.push(Event::MouseMovedTo(self.midpoint()));
+                if !pressed {
+                    events.push(Event::LeftMouseButtonDown);
+                }
+                self.state = State::Pinching {
+                    distance: self.spread(),
+                };
+                events
+            }
+            State::Pinching { .. } | State::Finished => Vec::new(),
+        }
+    }
+
+    fn finger_moved(&mut self, pt: ScreenPt) -> Vec<Event> {
+        match self.state {
+            State::Idle | State::Finished => Vec::new(),
+            State::Pending { start, .. } => {
+                if dist(start, pt) <= MOVE_THRESHOLD {
+                    return Vec::new();
+                }
+                // The button goes down where the finger first touched, so the drag covers the
+                // full distance
+                self.state = State::Dragging;
+                vec![Event::LeftMouseButtonDown, Event::MouseMovedTo(pt)]
+            }
+            State::Dragging => vec![Event::MouseMovedTo(pt)],
+            State::Pinching { distance } => {
+                let new_distance = self.spread();
+                let mut events = vec![Event::MouseMovedTo(self.midpoint())];
+                if distance > 0.0 && new_distance > 0.0 {
+                    events.push(Event::Pinch(new_distance / distance));
+                }
+                self.state = State::Pinching {
+                    distance: new_distance,
+                };
+                events
+            }
+        }
+    }
+
+    fn finger_up(&mut self, cancelled: bool, previous_click: Instant) -> Vec<Event> {
+        let mut events = Vec::new();
+        match self.state {
+            State::Idle | State::Finished => {}
+            State::Pending { .. } => {
+                if !cancelled {
+                    events.push(Event::LeftMouseButtonDown);
+                    events.push(Event::LeftMouseButtonUp {
+                        is_double_click: previous_click.elapsed() <= MAX_DOUBLE_CLICK_DURATION,
+                    });
+                }
+            }
+            State::Dragging | State::Pinching { .. } => {
+                events.push(Event::LeftMouseButtonUp {
+                    is_double_click: false,
+                });
+            }
+        }
+        self.state = if self.fingers.is_empty() {
+            State::Idle
+        } else {
+            State::Finished
+        };
+        events
+    }
+
+    fn midpoint(&self) -> ScreenPt {
+        let (_, pt1) = self.fingers[0];
+        let (_, pt2) = self.fingers[1];
+        ScreenPt::new((pt1.x + pt2.x) / 2.0, (pt1.y + pt2.y) / 2.0)
+    }
+
+    fn spread(&self) -> f64 {
+        dist(self.fingers[0].1, self.fingers[1].1)
+    }
+}
+
+fn dist(pt1: ScreenPt, pt2: ScreenPt) -> f64 {
+    ((pt1.x - pt2.x).powi(2) + (pt1.y - pt2.y).powi(2)).sqrt()
+}
@@ -0,0 +1,85 @@
+use serde::Serialize;
+
+/// A description of one widget, meant for screen readers and other assistive technology. A
+/// `Panel` exports a tree of these with `accessibility_tree`.
+#[derive(Clone, Debug, Serialize)]
+pub struct AccessibleNode {
+    pub role: AccessibleRole,
+    /// What should be read out for this widget
+    pub label: String,
+    /// The current value of something like a checkbox, dropdown, or text box
+    pub value: Option<String>,
+    pub disabled: bool,
+    /// Does this widget currently have keyboard focus?
+    pub focused: bool,
+    pub children: Vec<AccessibleNode>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum AccessibleRole {
+    /// A row or column of other widgets
+    Group,
+    Text,
+    Button,
+    Checkbox,
+    Dropdown,
+    TextBox,
+    Spinner,
+    Slider,
+    ColorPicker,
+    List,
+    ListItem,
+}
+
+impl AccessibleNode {
+    pub fn new<I: Into<String>>(role: AccessibleRole, label: I) -> AccessibleNode {
+        AccessibleNode {
+            role,
+            label: label.into(),
+            value: None,
+            disabled: false,
+            focused: false,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn value<I: Into<String>>(mut self, value: I) -> AccessibleNode {
+        self.value = Some(value.into());
+        self
+    }
+
+    pub fn disabled(mut self, disabled: bool) -> AccessibleNode {
+        self.disabled = disabled;
+        self
+    }
+
+    /// Describe the whole tree as indented plain text, one widget per line.
+    pub fn to_plain_text(&self) -> String {
+        let mut lines = Vec::new();
+        self.describe(0, &mut lines);
+        lines.join("\n")
+    }
+
+    fn describe(&self, depth: usize, lines: &mut Vec<String>) {
+        // Groups just add nesting; there's nothing to read out
+        let child_depth = if self.role == AccessibleRole::Group {
+            depth
+        } else {
+            let mut line = format!("{}{:?}: {}", "  ".repeat(depth), self.role, self.label);
+            if let Some(ref value) = self.value {
+                line = format!("{} = {}", line, value);
+            }
+            if self.disabled {
+                line = format!("{} (disabled)", line);
+            }
+            if self.focused {
+                line = format!("{} (focused)", line);
+            }
+            lines.push(line);
+            depth + 1
+        };
+        for child in &self.children {
+            child.describe(child_depth, lines);
+        }
+    }
+}
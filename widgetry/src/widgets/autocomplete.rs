@@ -9,6 +9,8 @@ use crate::{
 // If multiple names map to the same data, all of the possible values will be returned
 pub struct Autocomplete<T: Clone> {
     choices: Vec<(String, Vec<T>)>,
+    /// The words of each choice, lowercased, for fuzzy matching
+    words: Vec<Vec<String>>,
     num_search_results: usize,
 
     tb: TextBox,
@@ -33,9 +35,11 @@ impl<T: 'static + Clone + Ord> Autocomplete<T> {
             .into_iter()
             .map(|(k, v)| (k, v.into_iter().collect()))
             .collect();
+        let words = choices.iter().map(|(name, _)| split_words(name)).collect();
 
         let mut a = Autocomplete {
             choices,
+            words,
             num_search_results,
 
             tb: TextBox::new(
@@ -60,16 +64,30 @@ impl<T: 'static + Clone> Autocomplete<T> {
         self.chosen_values.take()
     }
 
+    /// Returns the indices of all choices matching the current query, best matches first.
+    fn matches(&self) -> Vec<usize> {
+        let query = split_words(&self.current_line);
+        let mut scored: Vec<(usize, usize, usize)> = self
+            .choices
+            .iter()
+            .zip(self.words.iter())
+            .enumerate()
+            .filter_map(|(idx, ((name, _), words))| {
+                match_score(&query, words).map(|score| (score, name.len(), idx))
+            })
+            .collect();
+        // Prefer shorter names when the match quality is the same
+        scored.sort();
+        scored.into_iter().map(|(_, _, idx)| idx).collect()
+    }
+
     fn recalc_menu(&mut self, ctx: &mut EventCtx) {
         let mut choices = vec![Choice::new(
             format!("anything matching \"{}\"", self.current_line),
             (),
         )];
-        let query = self.current_line.to_ascii_lowercase();
-        for (name, _) in &self.choices {
-            if name.to_ascii_lowercase().contains(&query) {
-                choices.push(Choice::new(name, ()));
-            }
+        for idx in self.matches() {
+            choices.push(Choice::new(&self.choices[idx].0, ()));
             if choices.len() == self.num_search_results {
                 break;
             }
@@ -112,12 +130,9 @@ impl<T: 'static + Clone> WidgetImpl for Autocomplete<T> {
             self.menu.event(ctx, &mut tmp_output);
             if let Outcome::Clicked(ref choice) = tmp_output.outcome {
                 if choice.starts_with("anything matching") {
-                    let query = self.current_line.to_ascii_lowercase();
                     let mut matches = Vec::new();
-                    for (name, choices) in &self.choices {
-                        if name.to_ascii_lowercase().contains(&query) {
-                            matches.extend(choices.clone());
-                        }
+                    for idx in self.matches() {
+                        matches.extend(self.choices[idx].1.clone());
                     }
                     self.chosen_values = Some(matches);
                 } else {
@@ -139,3 +154,96 @@ impl<T: 'static + Clone> WidgetImpl for Autocomplete<T> {
         self.menu.draw(g);
     }
 }
+
+fn split_words(x: &str) -> Vec<String> {
+    x.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_string())
+        .collect()
+}
+
+/// How well does a query match a choice? Lower is better, and `None` means no match at all.
+/// Every word in the query has to match a different word in the choice, in any order. Words match
+/// exactly, by prefix ("pi" for "pine"), as an abbreviation ("st" for "street"), or with one typo.
+fn match_score(query: &[String], words: &[String]) -> Option<usize> {
+    let mut used = vec![false; words.len()];
+    let mut score = 0;
+    for q in query {
+        let (idx, cost) = words
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !used[*idx])
+            .filter_map(|(idx, w)| word_cost(q, w).map(|cost| (idx, cost)))
+            .min_by_key(|(_, cost)| *cost)?;
+        used[idx] = true;
+        score += cost;
+    }
+    Some(score)
+}
+
+fn word_cost(query: &str, word: &str) -> Option<usize> {
+    if query == word {
+        Some(0)
+    } else if word.starts_with(query) {
+        Some(1)
+    } else if is_abbreviation(query, word) {
+        Some(2)
+    } else if query.chars().count() >= 4 && within_one_edit(query, word) {
+        Some(3)
+    } else {
+        None
+    }
+}
+
+/// Does the abbreviation start with the same letter as the word, then use some of the word's
+/// letters in order? "rd" abbreviates "road", and "blvd" abbreviates "boulevard".
+fn is_abbreviation(abbrev: &str, word: &str) -> bool {
+    if abbrev.chars().next() != word.chars().next() {
+        return false;
+    }
+    let mut letters = word.chars();
+    abbrev.chars().all(|c| letters.any(|w| w == c))
+}
+
+fn within_one_edit(a: &str, b: &str) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > 1 {
+        return false;
+    }
+    // Skip the common prefix and suffix, then there can only be one differing character left
+    let prefix = a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    a.len() - prefix - suffix <= 1 && b.len() - prefix - suffix <= 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(query: &str, choice: &str) -> Option<usize> {
+        match_score(&split_words(query), &split_words(choice))
+    }
+
+    #[test]
+    fn test_fuzzy_matching() {
+        assert_eq!(score("pine street", "Pine Street"), Some(0));
+        assert_eq!(score("e pine st", "East Pine Street"), Some(2));
+        assert_eq!(score("street pine", "Pine Street"), Some(0));
+        assert_eq!(score("broadway rd", "Broadway Road"), Some(2));
+        assert_eq!(score("broedway", "Broadway"), Some(3));
+        assert_eq!(
+            score("cafe", "Cafe Allegro (at 4214 University Way NE)"),
+            Some(0)
+        );
+        assert_eq!(score("oak", "Pine Street"), None);
+        assert_eq!(score("pine pine", "Pine Street"), None);
+        assert!(score("pi", "Pine Street") < score("pi", "Pacific Street"));
+    }
+}
@@ -0,0 +1,176 @@
+use geom::{Distance, PolyLine, Polygon, Pt2D, UnitFmt};
+
+use crate::widgets::plots::{
+    default_dims, horizontal_grid_lines, make_legend_entries, make_y_axis, Axis, HoverRegions,
+    PlotOptions,
+};
+use crate::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, Line, ScreenDims, ScreenPt, Text, Widget,
+    WidgetImpl, WidgetOutput,
+};
+
+/// One box in a `BoxPlot`
+pub struct BoxGroup<Y> {
+    pub label: String,
+    pub color: Color,
+    pub values: Vec<Y>,
+}
+
+/// Summarizes the spread of values in several groups side-by-side, like delay by road type. Each
+/// box covers the middle half of the values, with a line at the median and whiskers reaching the
+/// minimum and maximum.
+pub struct BoxPlot<Y: Axis<Y>> {
+    draw: Drawable,
+    hover: HoverRegions,
+    // The label of each box that's drawn, in order
+    labels: Vec<String>,
+
+    top_left: ScreenPt,
+    dims: ScreenDims,
+}
+
+impl<Y: Axis<Y>> BoxPlot<Y> {
+    /// The X axis just positions the boxes, so `opts.max_x` is ignored. Groups without any values
+    /// are skipped. `label` names the plot itself, like `LinePlot::new_widget`.
+    pub fn new_widget(
+        ctx: &EventCtx,
+        label: &str,
+        mut groups: Vec<BoxGroup<Y>>,
+        opts: PlotOptions<usize, Y>,
+        unit_fmt: UnitFmt,
+    ) -> Widget {
+        let legend = make_legend_entries(
+            ctx,
+            groups.iter().map(|g| (g.label.clone(), g.color)).collect(),
+            &opts,
+        );
+        groups.retain(|g| !opts.disabled.contains(&g.label) && !g.values.is_empty());
+        for g in &mut groups {
+            g.values.sort();
+        }
+
+        let max_y = opts.max_y.unwrap_or_else(|| {
+            groups
+                .iter()
+                .map(|g| *g.values.last().unwrap())
+                .max()
+                .unwrap_or_else(Y::zero)
+        });
+        let dims = opts.dims.unwrap_or_else(|| default_dims(ctx));
+        let to_y = |y: Y| (1.0 - y.to_percent(max_y).min(1.0)) * dims.height;
+
+        let mut batch = GeomBatch::new();
+        horizontal_grid_lines(&mut batch, max_y, dims);
+        let mut hover = HoverRegions::new();
+        let slot_width = dims.width / (groups.len().max(1) as f64);
+        let box_width = 0.6 * slot_width;
+        let thickness = Distance::meters(2.0);
+        for (idx, g) in groups.iter().enumerate() {
+            let min = g.values[0];
+            let q1 = percentile(&g.values, 0.25);
+            let median = percentile(&g.values, 0.5);
+            let q3 = percentile(&g.values, 0.75);
+            let max = *g.values.last().unwrap();
+
+            let center = slot_width * (idx as f64 + 0.5);
+            let x1 = center - box_width / 2.0;
+            let x2 = center + box_width / 2.0;
+
+            // Whiskers
+            for (from, to) in [(min, q1), (q3, max)] {
+                if let Ok(pl) = PolyLine::new(vec![
+                    Pt2D::new(center, to_y(from)),
+                    Pt2D::new(center, to_y(to)),
+                ]) {
+                    batch.push(g.color, pl.make_polygons(thickness));
+                }
+            }
+            for y in [min, max] {
+                batch.push(
+                    g.color,
+                    PolyLine::must_new(vec![
+                        Pt2D::new(center - box_width / 4.0, to_y(y)),
+                        Pt2D::new(center + box_width / 4.0, to_y(y)),
+                    ])
+                    .make_polygons(thickness),
+                );
+            }
+            // The box itself, at least a few pixels tall so it's visible
+            let top = to_y(q3);
+            let height = (to_y(q1) - top).max(thickness.inner_meters());
+            batch.push(
+                g.color.alpha(0.5),
+                Polygon::rectangle(box_width, height).translate(x1, top),
+            );
+            batch.push(
+                g.color,
+                PolyLine::must_new(vec![
+                    Pt2D::new(x1, to_y(median)),
+                    Pt2D::new(x2, to_y(median)),
+                ])
+                .make_polygons(thickness * 2.0),
+            );
+
+            let mut txt = Text::from(Line(&g.label).small_heading());
+            txt.add_line(format!("Maximum: {}", max.prettyprint(&unit_fmt)));
+            txt.add_line(format!("75th percentile: {}", q3.prettyprint(&unit_fmt)));
+            txt.add_line(format!("Median: {}", median.prettyprint(&unit_fmt)));
+            txt.add_line(format!("25th percentile: {}", q1.prettyprint(&unit_fmt)));
+            txt.add_line(format!("Minimum: {}", min.prettyprint(&unit_fmt)));
+            txt.add_line(format!("{} values", g.values.len()));
+            hover.add(
+                Polygon::rectangle(slot_width, dims.height)
+                    .translate(slot_width * (idx as f64), 0.0),
+                txt,
+            );
+        }
+
+        let plot = BoxPlot {
+            draw: ctx.upload(batch),
+            hover,
+            labels: groups.into_iter().map(|g| g.label).collect(),
+
+            top_left: ScreenPt::new(0.0, 0.0),
+            dims,
+        };
+
+        Widget::custom_col(vec![
+            legend.margin_below(10),
+            Widget::custom_row(vec![
+                make_y_axis(ctx, max_y, &unit_fmt),
+                Widget::new(Box::new(plot)).named(label),
+            ]),
+        ])
+        .container()
+    }
+
+    /// The label of the box currently hovered on
+    pub fn get_hovering(&self) -> Option<&String> {
+        self.hover.current().map(|idx| &self.labels[idx])
+    }
+}
+
+impl<Y: Axis<Y>> WidgetImpl for BoxPlot<Y> {
+    fn get_dims(&self) -> ScreenDims {
+        self.dims
+    }
+
+    fn set_pos(&mut self, top_left: ScreenPt) {
+        self.top_left = top_left;
+    }
+
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut WidgetOutput) {
+        self.hover.event(ctx, self.top_left);
+    }
+
+    fn draw(&self, g: &mut GfxCtx) {
+        g.redraw_at(self.top_left, &self.draw);
+        self.hover.draw(g, self.top_left);
+    }
+}
+
+// Nearest-rank percentile of sorted, non-empty values. pct is [0.0, 1.0]
+fn percentile<Y: Copy>(sorted: &[Y], pct: f64) -> Y {
+    let idx = ((sorted.len() - 1) as f64 * pct).round() as usize;
+    sorted[idx]
+}
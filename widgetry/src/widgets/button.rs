@@ -1,10 +1,10 @@
 use geom::Polygon;
 
 use crate::{
-    style::DEFAULT_OUTLINE_THICKNESS, text::Font, ButtonStyle, Color, ContentMode, ControlState,
-    CornerRounding, Drawable, EdgeInsets, EventCtx, GeomBatch, GfxCtx, Image, Line, MultiKey,
-    Outcome, OutlineStyle, RewriteColor, ScreenDims, ScreenPt, Text, Widget, WidgetImpl,
-    WidgetOutput,
+    style::DEFAULT_OUTLINE_THICKNESS, text::Font, AccessibleNode, AccessibleRole, ButtonStyle,
+    Color, ContentMode, ControlState, CornerRounding, Drawable, EdgeInsets, EventCtx, GeomBatch,
+    GfxCtx, Image, Line, MultiKey, Outcome, OutlineStyle, RewriteColor, ScreenDims, ScreenPt, Text,
+    Widget, WidgetImpl, WidgetOutput,
 };
 
 use crate::geom::geom_batch_stack::{Axis, GeomBatchStack};
@@ -106,6 +106,10 @@ impl WidgetImpl for Button {
         }
 
         if self.hovering && ctx.normal_left_click() {
+            // The tooltip already explains the button
+            if ctx.canvas.is_whats_this_mode() {
+                return;
+            }
             self.hovering = false;
             output.outcome = Outcome::Clicked(self.action.clone());
             return;
@@ -139,6 +143,20 @@ impl WidgetImpl for Button {
             g.redraw_at(self.top_left, &self.draw_normal);
         }
     }
+
+    fn can_focus(&self) -> bool {
+        !self.is_disabled
+    }
+
+    fn activate(&mut self, _: &mut EventCtx, output: &mut WidgetOutput) {
+        if !self.is_disabled {
+            output.outcome = Outcome::Clicked(self.action.clone());
+        }
+    }
+
+    fn accessible(&self) -> Option<AccessibleNode> {
+        Some(AccessibleNode::new(AccessibleRole::Button, &self.action).disabled(self.is_disabled))
+    }
 }
 
 #[derive(Clone, Debug, Default)]
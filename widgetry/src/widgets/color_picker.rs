@@ -0,0 +1,311 @@
+use geom::{Circle, Distance, Line as GeomLine, Polygon, Pt2D};
+
+use crate::{
+    AccessibleNode, AccessibleRole, Color, Drawable, EventCtx, Fill, GeomBatch, GfxCtx, Line,
+    LinearGradient, Outcome, ScreenDims, ScreenPt, Text, Widget, WidgetImpl, WidgetOutput,
+};
+
+const SWATCH: f64 = 22.0;
+const GAP: f64 = 4.0;
+const COLUMNS: usize = 8;
+const SQUARE_HEIGHT: f64 = 120.0;
+const HUE_HEIGHT: f64 = 14.0;
+
+/// Some distinct colors to quickly pick from
+const DEFAULT_PALETTE: [&str; 16] = [
+    "#E6194B", "#F58231", "#FFE119", "#BFEF45", "#3CB44B", "#42D4F4", "#4363D8", "#911EB4",
+    "#F032E6", "#FABED4", "#FFD8B1", "#AAFFC3", "#DCBEFF", "#9A6324", "#A9A9A9", "#000000",
+];
+
+/// Pick a color from a palette grid, or precisely by dragging around a saturation/value square
+/// and a hue bar.
+pub struct ColorPicker {
+    label: String,
+    palette: Vec<Color>,
+    // Kept separately from the color, so the hue isn't lost when the saturation or value is 0
+    hue: f64,
+    saturation: f64,
+    value: f64,
+    alpha: f32,
+    dragging: Option<Part>,
+
+    draw: Drawable,
+    top_left: ScreenPt,
+    dims: ScreenDims,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Part {
+    Square,
+    Hue,
+}
+
+impl ColorPicker {
+    pub fn widget<I: Into<String>>(ctx: &EventCtx, label: I, current: Color) -> Widget {
+        ColorPicker::widget_with_palette(
+            ctx,
+            label,
+            current,
+            DEFAULT_PALETTE.iter().map(|hex| Color::hex(hex)).collect(),
+        )
+    }
+
+    pub fn widget_with_palette<I: Into<String>>(
+        ctx: &EventCtx,
+        label: I,
+        current: Color,
+        palette: Vec<Color>,
+    ) -> Widget {
+        let label = label.into();
+        let (hue, saturation, value) = current.to_hsv();
+        let mut picker = ColorPicker {
+            label: label.clone(),
+            palette,
+            hue,
+            saturation,
+            value,
+            alpha: current.a,
+            dragging: None,
+
+            draw: Drawable::empty(ctx),
+            top_left: ScreenPt::new(0.0, 0.0),
+            dims: ScreenDims::new(0.0, 0.0),
+        };
+        picker.dims = ScreenDims::new(width(), picker.preview_top() + SWATCH);
+        picker.recalc(ctx);
+        Widget::new(Box::new(picker)).named(label)
+    }
+
+    pub fn current(&self) -> Color {
+        Color::hsv(self.hue, self.saturation, self.value).alpha(self.alpha)
+    }
+
+    pub fn set_current(&mut self, ctx: &EventCtx, color: Color) {
+        let (hue, saturation, value) = color.to_hsv();
+        self.hue = hue;
+        self.saturation = saturation;
+        self.value = value;
+        self.alpha = color.a;
+        self.recalc(ctx);
+    }
+
+    // Everything below is relative to the widget's top-left
+
+    fn palette_height(&self) -> f64 {
+        let rows = (self.palette.len() + COLUMNS - 1) / COLUMNS;
+        (rows as f64) * (SWATCH + GAP)
+    }
+
+    fn square_top(&self) -> f64 {
+        self.palette_height()
+    }
+
+    fn hue_top(&self) -> f64 {
+        self.square_top() + SQUARE_HEIGHT + GAP
+    }
+
+    fn preview_top(&self) -> f64 {
+        self.hue_top() + HUE_HEIGHT + GAP
+    }
+
+    fn swatch_top_left(&self, idx: usize) -> Pt2D {
+        Pt2D::new(
+            ((idx % COLUMNS) as f64) * (SWATCH + GAP),
+            ((idx / COLUMNS) as f64) * (SWATCH + GAP),
+        )
+    }
+
+    fn recalc(&mut self, ctx: &EventCtx) {
+        let mut batch = GeomBatch::new();
+        let width = width();
+        let current = self.current();
+
+        for (idx, color) in self.palette.iter().enumerate() {
+            let pt = self.swatch_top_left(idx);
+            let swatch = Polygon::rounded_rectangle(SWATCH, SWATCH, 2.0).translate(pt.x(), pt.y());
+            batch.push(*color, swatch.clone());
+            if color.as_hex() == current.as_hex() {
+                batch.push(
+                    ctx.style().text_primary_color,
+                    swatch.to_outline(Distance::meters(2.0)),
+                );
+            }
+        }
+
+        // The saturation increases to the right, and the value increases upwards. The gradients
+        // are defined where the polygons are, so the batch isn't translated afterwards.
+        let top = self.square_top();
+        let square = Polygon::rectangle(width, SQUARE_HEIGHT).translate(0.0, top);
+        batch.push(
+            gradient(
+                Pt2D::new(0.0, top),
+                Pt2D::new(width, top),
+                Color::WHITE,
+                Color::hsv(self.hue, 1.0, 1.0),
+            ),
+            square.clone(),
+        );
+        batch.push(
+            gradient(
+                Pt2D::new(0.0, top),
+                Pt2D::new(0.0, top + SQUARE_HEIGHT),
+                Color::BLACK.alpha(0.0),
+                Color::BLACK,
+            ),
+            square,
+        );
+        batch.push(
+            current.invert().alpha(1.0),
+            Circle::new(
+                Pt2D::new(
+                    self.saturation * width,
+                    top + (1.0 - self.value) * SQUARE_HEIGHT,
+                ),
+                Distance::meters(5.0),
+            )
+            .to_polygon()
+            .to_outline(Distance::meters(2.0)),
+        );
+
+        // The hue bar needs a stop at every primary and secondary color. Colors are only
+        // interpolated between a polygon's points, so split it up.
+        let top = self.hue_top();
+        let segment = width / 6.0;
+        for i in 0..6 {
+            let x = (i as f64) * segment;
+            batch.push(
+                gradient(
+                    Pt2D::new(x, top),
+                    Pt2D::new(x + segment, top),
+                    Color::hsv(60.0 * (i as f64), 1.0, 1.0),
+                    Color::hsv(60.0 * ((i + 1) as f64), 1.0, 1.0),
+                ),
+                Polygon::rectangle(segment, HUE_HEIGHT).translate(x, top),
+            );
+        }
+        batch.push(
+            ctx.style().text_primary_color,
+            Polygon::rectangle(3.0, HUE_HEIGHT + 4.0)
+                .translate(self.hue / 360.0 * width - 1.5, top - 2.0),
+        );
+
+        let top = self.preview_top();
+        batch.push(
+            current,
+            Polygon::rounded_rectangle(2.0 * SWATCH, SWATCH, 2.0).translate(0.0, top),
+        );
+        let txt = Text::from(Line(current.as_hex()).small()).render_autocropped(ctx);
+        let txt_height = txt.get_dims().height;
+        batch.append(txt.translate(2.0 * SWATCH + GAP, top + (SWATCH - txt_height) / 2.0));
+
+        self.draw = ctx.upload(batch);
+    }
+
+    fn update(&mut self, part: Part, pt: ScreenPt) {
+        let x = ((pt.x - self.top_left.x) / width()).clamp(0.0, 1.0);
+        match part {
+            Part::Square => {
+                let y = (pt.y - self.top_left.y - self.square_top()) / SQUARE_HEIGHT;
+                self.saturation = x;
+                self.value = 1.0 - y.clamp(0.0, 1.0);
+            }
+            Part::Hue => {
+                self.hue = 360.0 * x;
+            }
+        }
+    }
+
+    // True if the color changed
+    fn inner_event(&mut self, ctx: &mut EventCtx) -> bool {
+        if let Some(part) = self.dragging {
+            if ctx.input.left_mouse_button_released() {
+                self.dragging = None;
+                return false;
+            }
+            if ctx.input.get_moved_mouse().is_some() {
+                self.update(part, ctx.canvas.get_cursor());
+                return true;
+            }
+            return false;
+        }
+
+        if !ctx.input.left_mouse_button_pressed() {
+            return false;
+        }
+        let pt = if let Some(pt) = ctx.canvas.get_cursor_in_screen_space() {
+            pt
+        } else {
+            return false;
+        };
+        let x = pt.x - self.top_left.x;
+        let y = pt.y - self.top_left.y;
+        if x < 0.0 || x > self.dims.width || y < 0.0 || y > self.dims.height {
+            return false;
+        }
+
+        let clicked_swatch = (0..self.palette.len()).find(|idx| {
+            let corner = self.swatch_top_left(*idx);
+            x >= corner.x()
+                && x <= corner.x() + SWATCH
+                && y >= corner.y()
+                && y <= corner.y() + SWATCH
+        });
+        if let Some(idx) = clicked_swatch {
+            let (hue, saturation, value) = self.palette[idx].to_hsv();
+            self.hue = hue;
+            self.saturation = saturation;
+            self.value = value;
+            return true;
+        }
+
+        let part = if y >= self.square_top() && y <= self.square_top() + SQUARE_HEIGHT {
+            Part::Square
+        } else if y >= self.hue_top() && y <= self.hue_top() + HUE_HEIGHT {
+            Part::Hue
+        } else {
+            return false;
+        };
+        self.dragging = Some(part);
+        self.update(part, pt);
+        true
+    }
+}
+
+impl WidgetImpl for ColorPicker {
+    fn get_dims(&self) -> ScreenDims {
+        self.dims
+    }
+
+    fn set_pos(&mut self, top_left: ScreenPt) {
+        self.top_left = top_left;
+    }
+
+    fn event(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
+        if self.inner_event(ctx) {
+            self.recalc(ctx);
+            output.outcome = Outcome::Changed(self.label.clone());
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx) {
+        g.redraw_at(self.top_left, &self.draw);
+    }
+
+    fn accessible(&self) -> Option<AccessibleNode> {
+        Some(
+            AccessibleNode::new(AccessibleRole::ColorPicker, &self.label)
+                .value(self.current().as_hex()),
+        )
+    }
+}
+
+fn width() -> f64 {
+    (COLUMNS as f64) * (SWATCH + GAP) - GAP
+}
+
+fn gradient(from: Pt2D, to: Pt2D, c1: Color, c2: Color) -> Fill {
+    Fill::LinearGradient(LinearGradient {
+        line: GeomLine::must_new(from, to),
+        stops: vec![(0.0, c1), (1.0, c2)],
+    })
+}
@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use abstutil::Timer;
+
+use crate::{
+    include_labeled_bytes, EventCtx, HorizontalAlignment, Line, PanelDims, ScreenDims, ScreenPt,
+    ScreenRectangle, VerticalAlignment, Widget,
+};
+
+/// Drag this many pixels from the bottom-right corner of a dockable panel to resize it
+pub(crate) const RESIZE_GRIP: f64 = 12.0;
+// Resizing can't make a panel smaller than this
+const MIN_SIZE: f64 = 100.0;
+
+pub(crate) const COLLAPSE: &str = "collapse panel";
+pub(crate) const EXPAND: &str = "expand panel";
+
+/// Which edge of the window a panel is docked to, along one axis
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) enum Dock {
+    Start,
+    Center,
+    End,
+}
+
+/// How the player arranged one dockable panel. These're remembered across sessions, keyed by the
+/// ID passed to `PanelBuilder::dockable`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct PanelLayout {
+    /// Horizontal and vertical docking. Until the player moves the panel, it keeps the alignment
+    /// it was built with.
+    pub dock: Option<(Dock, Dock)>,
+    /// Until the player resizes the panel, it keeps the dims it was built with.
+    pub size: Option<ScreenDims>,
+    pub collapsed: bool,
+}
+
+impl PanelLayout {
+    pub fn alignment(&self) -> Option<(HorizontalAlignment, VerticalAlignment)> {
+        let (horiz, vert) = self.dock?;
+        Some((
+            match horiz {
+                Dock::Start => HorizontalAlignment::LeftInset,
+                Dock::Center => HorizontalAlignment::Center,
+                Dock::End => HorizontalAlignment::RightInset,
+            },
+            match vert {
+                Dock::Start => VerticalAlignment::TopInset,
+                Dock::Center => VerticalAlignment::Center,
+                Dock::End => VerticalAlignment::BottomInset,
+            },
+        ))
+    }
+
+    /// Dock to whichever edges are closest to the panel's current position. The window is split
+    /// into thirds along each axis.
+    pub fn snap_to(&mut self, rect: &ScreenRectangle, window: ScreenDims) {
+        let center = rect.center();
+        self.dock = Some((snap(center.x, window.width), snap(center.y, window.height)));
+    }
+}
+
+fn snap(value: f64, max: f64) -> Dock {
+    if value < max / 3.0 {
+        Dock::Start
+    } else if value > 2.0 * max / 3.0 {
+        Dock::End
+    } else {
+        Dock::Center
+    }
+}
+
+/// The state of a panel built with `PanelBuilder::dockable`
+pub(crate) struct DockState {
+    pub id: String,
+    pub layout: PanelLayout,
+    /// What the panel was built with, to restore after expanding a collapsed panel
+    pub expanded_dims_y: PanelDims,
+    /// The contents are stashed here while the panel is collapsed
+    pub contents: Option<Widget>,
+    pub action: Option<DockAction>,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum DockAction {
+    /// The cursor stays this far from the panel's top-left corner
+    Moving { grab: ScreenPt },
+    /// The top-left corner stays in place while resizing, then the panel goes back to this
+    /// alignment
+    Resizing {
+        align: (HorizontalAlignment, VerticalAlignment),
+    },
+}
+
+/// The titlebar shown above the contents of a dockable panel. Drag it to move the panel.
+pub(crate) fn titlebar(ctx: &EventCtx, title: &str, collapsed: bool) -> Widget {
+    Widget::row(vec![
+        Line(title).small_heading().into_widget(ctx),
+        collapse_button(ctx, collapsed).align_right(),
+    ])
+    .named("dock titlebar")
+}
+
+pub(crate) fn collapse_button(ctx: &EventCtx, collapsed: bool) -> Widget {
+    if collapsed {
+        ctx.style()
+            .btn_plain
+            .icon_bytes(include_labeled_bytes!("../../icons/arrow_down.svg"))
+            .build_widget(ctx, EXPAND)
+    } else {
+        ctx.style()
+            .btn_plain
+            .icon_bytes(include_labeled_bytes!("../../icons/arrow_up.svg"))
+            .build_widget(ctx, COLLAPSE)
+    }
+}
+
+/// The panel's new size, if the bottom-right corner is dragged to the cursor
+pub(crate) fn resized(top_left: ScreenPt, cursor: ScreenPt, window: ScreenDims) -> ScreenDims {
+    ScreenDims::new(
+        (cursor.x - top_left.x).clamp(MIN_SIZE, window.width.max(MIN_SIZE)),
+        (cursor.y - top_left.y).clamp(MIN_SIZE, window.height.max(MIN_SIZE)),
+    )
+}
+
+fn path() -> String {
+    abstio::path_player("panel_layouts.json")
+}
+
+pub(crate) fn load_panel_layouts() -> BTreeMap<String, PanelLayout> {
+    abstio::maybe_read_json(path(), &mut Timer::throwaway()).unwrap_or_default()
+}
+
+pub(crate) fn save_panel_layouts(layouts: &BTreeMap<String, PanelLayout>) {
+    abstio::write_json(path(), layouts);
+}
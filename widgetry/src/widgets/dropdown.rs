@@ -1,8 +1,9 @@
 use geom::{CornerRadii, Distance, Polygon, Pt2D};
 
 use crate::{
-    Button, Choice, Color, ControlState, CornerRounding, EdgeInsets, EventCtx, GeomBatch, GfxCtx,
-    Menu, Outcome, ScreenDims, ScreenPt, ScreenRectangle, WidgetImpl, WidgetOutput,
+    AccessibleNode, AccessibleRole, Button, Choice, Color, ControlState, CornerRounding,
+    EdgeInsets, EventCtx, GeomBatch, GfxCtx, Menu, Outcome, ScreenDims, ScreenPt, ScreenRectangle,
+    WidgetImpl, WidgetOutput,
 };
 
 pub struct Dropdown<T: Clone> {
@@ -175,6 +176,25 @@ impl<T: 'static + Clone> WidgetImpl for Dropdown<T> {
             // menu.
         }
     }
+
+    fn can_focus(&self) -> bool {
+        self.btn.can_focus()
+    }
+
+    fn activate(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
+        if self.menu.is_none() {
+            self.open_menu(ctx);
+            // The menu handles the arrow keys and Enter from now on
+            output.outcome = Outcome::Focused(self.label.clone());
+        }
+    }
+
+    fn accessible(&self) -> Option<AccessibleNode> {
+        Some(
+            AccessibleNode::new(AccessibleRole::Dropdown, &self.label)
+                .value(&self.choices[self.current_idx].label),
+        )
+    }
 }
 
 fn make_btn(ctx: &EventCtx, label: &str, tooltip: &str, is_persisten_split: bool) -> Button {
@@ -0,0 +1,139 @@
+use geom::{Polygon, UnitFmt};
+
+use crate::widgets::plots::{
+    default_dims, horizontal_grid_lines, make_x_axis, make_y_axis, Axis, HoverRegions, PlotOptions,
+};
+use crate::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, ScreenDims, ScreenPt, Text, Widget, WidgetImpl,
+    WidgetOutput,
+};
+
+/// Groups values into equally sized buckets and shows how many fall into each one, like the
+/// distribution of trip durations.
+pub struct HistogramPlot<X: Axis<X>> {
+    draw: Drawable,
+    hover: HoverRegions,
+    // (lower bound, upper bound, count) per bucket
+    buckets: Vec<(X, X, usize)>,
+
+    top_left: ScreenPt,
+    dims: ScreenDims,
+}
+
+impl<X: Axis<X>> HistogramPlot<X> {
+    /// Buckets cover zero up to the largest value, or `opts.max_x`. Larger values are counted in
+    /// the last bucket. `label` names the plot itself, like `LinePlot::new_widget`.
+    pub fn new_widget(
+        ctx: &EventCtx,
+        label: &str,
+        values: Vec<X>,
+        num_buckets: usize,
+        color: Color,
+        opts: PlotOptions<X, usize>,
+        unit_fmt: UnitFmt,
+    ) -> Widget {
+        assert!(num_buckets > 0);
+        let max_x = opts
+            .max_x
+            .unwrap_or_else(|| values.iter().max().cloned().unwrap_or_else(X::zero));
+        let bucket_width = max_x.to_f64() / (num_buckets as f64);
+
+        let mut counts = vec![0; num_buckets];
+        for x in values {
+            let idx = if bucket_width > 0.0 {
+                ((x.to_f64() / bucket_width) as usize).min(num_buckets - 1)
+            } else {
+                0
+            };
+            counts[idx] += 1;
+        }
+        let buckets: Vec<(X, X, usize)> = counts
+            .into_iter()
+            .enumerate()
+            .map(|(idx, count)| {
+                (
+                    max_x.from_f64(bucket_width * (idx as f64)),
+                    max_x.from_f64(bucket_width * ((idx + 1) as f64)),
+                    count,
+                )
+            })
+            .collect();
+
+        let max_y = opts.max_y.unwrap_or_else(|| {
+            buckets
+                .iter()
+                .map(|(_, _, count)| *count)
+                .max()
+                .unwrap_or(0)
+        });
+        let dims = opts.dims.unwrap_or_else(|| default_dims(ctx));
+
+        let mut batch = GeomBatch::new();
+        horizontal_grid_lines(&mut batch, max_y, dims);
+        let mut hover = HoverRegions::new();
+        let bar_width = dims.width / (num_buckets as f64);
+        for (idx, (low, high, count)) in buckets.iter().enumerate() {
+            let x1 = bar_width * (idx as f64);
+            let height = count.to_percent(max_y).min(1.0) * dims.height;
+            if height > 0.0 {
+                // Leave a small gap between bars
+                batch.push(
+                    color,
+                    Polygon::rectangle((bar_width - 1.0).max(1.0), height)
+                        .translate(x1, dims.height - height),
+                );
+            }
+            hover.add(
+                Polygon::rectangle(bar_width, dims.height).translate(x1, 0.0),
+                Text::from(format!(
+                    "{} - {}: {}",
+                    low.prettyprint(&unit_fmt),
+                    high.prettyprint(&unit_fmt),
+                    count.prettyprint(&unit_fmt)
+                )),
+            );
+        }
+
+        let plot = HistogramPlot {
+            draw: ctx.upload(batch),
+            hover,
+            buckets,
+
+            top_left: ScreenPt::new(0.0, 0.0),
+            dims,
+        };
+
+        Widget::custom_col(vec![
+            Widget::custom_row(vec![
+                make_y_axis(ctx, max_y, &unit_fmt),
+                Widget::new(Box::new(plot)).named(label),
+            ]),
+            make_x_axis(ctx, max_x, &unit_fmt),
+        ])
+        .container()
+    }
+
+    /// The bounds and count of the bucket currently hovered on
+    pub fn get_hovering(&self) -> Option<(X, X, usize)> {
+        self.hover.current().map(|idx| self.buckets[idx])
+    }
+}
+
+impl<X: Axis<X>> WidgetImpl for HistogramPlot<X> {
+    fn get_dims(&self) -> ScreenDims {
+        self.dims
+    }
+
+    fn set_pos(&mut self, top_left: ScreenPt) {
+        self.top_left = top_left;
+    }
+
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut WidgetOutput) {
+        self.hover.event(ctx, self.top_left);
+    }
+
+    fn draw(&self, g: &mut GfxCtx) {
+        g.redraw_at(self.top_left, &self.draw);
+        self.hover.draw(g, self.top_left);
+    }
+}
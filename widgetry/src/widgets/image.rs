@@ -336,6 +336,7 @@ impl<'a, 'c> Image<'a, 'c> {
                     Widget::new(Box::new(JustDraw {
                         dims: ScreenDims::new(bounds.width(), bounds.height()),
                         draw: ctx.upload(batch),
+                        label: None,
                         top_left: ScreenPt::new(0.0, 0.0),
                     }))
                 }
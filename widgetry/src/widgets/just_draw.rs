@@ -1,13 +1,15 @@
 use geom::Polygon;
 
 use crate::{
-    ClickOutcome, Drawable, EventCtx, GeomBatch, GfxCtx, Outcome, ScreenDims, ScreenPt,
-    ScreenRectangle, Text, Widget, WidgetImpl, WidgetOutput,
+    AccessibleNode, AccessibleRole, ClickOutcome, Drawable, EventCtx, GeomBatch, GfxCtx, Outcome,
+    ScreenDims, ScreenPt, ScreenRectangle, Text, Widget, WidgetImpl, WidgetOutput,
 };
 
 // Just draw something, no interaction.
 pub struct JustDraw {
     pub draw: Drawable,
+    /// If this draws text, the plain text, for screen readers
+    pub label: Option<String>,
 
     pub top_left: ScreenPt,
     pub dims: ScreenDims,
@@ -18,6 +20,18 @@ impl JustDraw {
         Widget::new(Box::new(JustDraw {
             dims: batch.get_dims(),
             draw: ctx.upload(batch),
+            label: None,
+            top_left: ScreenPt::new(0.0, 0.0),
+        }))
+    }
+
+    pub(crate) fn wrap_text(ctx: &EventCtx, txt: Text) -> Widget {
+        let label = txt.to_plain_string();
+        let batch = txt.render(ctx);
+        Widget::new(Box::new(JustDraw {
+            dims: batch.get_dims(),
+            draw: ctx.upload(batch),
+            label: Some(label),
             top_left: ScreenPt::new(0.0, 0.0),
         }))
     }
@@ -37,6 +51,11 @@ impl WidgetImpl for JustDraw {
     fn draw(&self, g: &mut GfxCtx) {
         g.redraw_at(self.top_left, &self.draw);
     }
+
+    fn accessible(&self) -> Option<AccessibleNode> {
+        let label = self.label.as_ref().filter(|x| !x.is_empty())?;
+        Some(AccessibleNode::new(AccessibleRole::Text, label))
+    }
 }
 
 pub struct DrawWithTooltips {
@@ -1,8 +1,8 @@
 use geom::Pt2D;
 
 use crate::{
-    Choice, EventCtx, GfxCtx, Key, Line, Outcome, ScreenDims, ScreenPt, ScreenRectangle, Style,
-    Text, Widget, WidgetImpl, WidgetOutput,
+    AccessibleNode, AccessibleRole, Choice, EventCtx, GfxCtx, Key, Line, Outcome, ScreenDims,
+    ScreenPt, ScreenRectangle, Style, Text, Widget, WidgetImpl, WidgetOutput,
 };
 
 pub struct Menu<T> {
@@ -204,4 +204,31 @@ impl<T: 'static> WidgetImpl for Menu<T> {
             }
         }
     }
+
+    fn can_focus(&self) -> bool {
+        !self.choices.is_empty()
+    }
+
+    fn activate(&mut self, _: &mut EventCtx, output: &mut WidgetOutput) {
+        let choice = &self.choices[self.current_idx];
+        if choice.active {
+            output.outcome = Outcome::Clicked(choice.label.clone());
+        }
+    }
+
+    fn accessible(&self) -> Option<AccessibleNode> {
+        let mut node = AccessibleNode::new(AccessibleRole::List, "");
+        if let Some(choice) = self.choices.get(self.current_idx) {
+            node = node.value(&choice.label);
+        }
+        node.children = self
+            .choices
+            .iter()
+            .map(|choice| {
+                AccessibleNode::new(AccessibleRole::ListItem, &choice.label)
+                    .disabled(!choice.active)
+            })
+            .collect();
+        Some(node)
+    }
 }
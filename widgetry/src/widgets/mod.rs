@@ -10,21 +10,28 @@ use taffy::style::{
 use abstutil::CloneableAny;
 use geom::{CornerRadii, Distance, Percent, Polygon};
 
+pub use crate::widgets::accessibility::{AccessibleNode, AccessibleRole};
 use crate::widgets::containers::{Container, Nothing};
+pub(crate) use crate::widgets::dock::{load_panel_layouts, PanelLayout};
 pub use crate::widgets::panel::{Panel, PanelBuilder, PanelDims};
 use crate::{
     Button, Choice, Color, DeferDraw, Drawable, Dropdown, EventCtx, GeomBatch, GfxCtx, JustDraw,
     OutlineStyle, ScreenDims, ScreenPt, ScreenRectangle, Text, Toggle,
 };
 
+mod accessibility;
 pub mod autocomplete;
+pub mod box_plot;
 pub mod button;
+pub mod color_picker;
 pub mod compare_times;
 pub mod containers;
+mod dock;
 pub mod drag_drop;
 pub mod dropdown;
 pub mod fan_chart;
 pub mod filler;
+pub mod histogram_plot;
 pub mod image;
 pub mod just_draw;
 pub mod line_plot;
@@ -35,9 +42,11 @@ pub mod plots;
 pub mod scatter_plot;
 pub mod slider;
 pub mod spinner;
+pub mod stacked_area_plot;
 pub mod stash;
 pub mod table;
 pub mod tabs;
+pub mod text_area;
 pub mod text_box;
 pub mod toggle;
 
@@ -66,6 +75,24 @@ pub trait WidgetImpl: downcast_rs::Downcast {
     fn restore(&mut self, _: &mut EventCtx, _prev: &dyn WidgetImpl) {
         unreachable!()
     }
+    /// Can the player move keyboard focus to this widget by pressing Tab? Only named widgets can
+    /// receive focus.
+    fn can_focus(&self) -> bool {
+        false
+    }
+    /// This widget has keyboard focus and the player pressed Enter or Space. Produce the same
+    /// output as a click would. If the outcome stays `Nothing`, hotkeys get the key instead.
+    fn activate(&mut self, _: &mut EventCtx, _: &mut WidgetOutput) {}
+    /// Is the player typing into this widget? If so, Enter and Space go to it, instead of
+    /// activating the widget with keyboard focus.
+    fn is_typing(&self) -> bool {
+        false
+    }
+    /// Describe this widget for screen readers. Returning `None` leaves it out of the
+    /// accessibility tree.
+    fn accessible(&self) -> Option<AccessibleNode> {
+        None
+    }
 }
 
 /// The result of a Panel handling an event
@@ -725,6 +752,47 @@ impl Widget {
         }
     }
 
+    // Populate a list of widgets that can receive keyboard focus, in traversal order
+    fn get_focusable(&self, ids: &mut Vec<String>) {
+        if let Some(container) = self.widget.downcast_ref::<Container>() {
+            for w in &container.members {
+                w.get_focusable(ids);
+            }
+        } else if let Some(ref id) = self.id {
+            if self.widget.can_focus() {
+                ids.push(id.clone());
+            }
+        }
+    }
+
+    fn accessibility_tree(&self, focused: Option<&String>) -> Option<AccessibleNode> {
+        if let Some(container) = self.widget.downcast_ref::<Container>() {
+            let children: Vec<AccessibleNode> = container
+                .members
+                .iter()
+                .filter_map(|w| w.accessibility_tree(focused))
+                .collect();
+            if children.is_empty() {
+                return None;
+            }
+            let mut node = AccessibleNode::new(AccessibleRole::Group, "");
+            node.children = children;
+            return Some(node);
+        }
+        let mut node = self.widget.accessible()?;
+        node.focused = self.id.is_some() && self.id.as_ref() == focused;
+        Some(node)
+    }
+
+    // Is some text box currently taking key presses?
+    fn is_typing(&self) -> bool {
+        if let Some(container) = self.widget.downcast_ref::<Container>() {
+            container.members.iter().any(|w| w.is_typing())
+        } else {
+            self.widget.is_typing()
+        }
+    }
+
     fn currently_hovering(&self) -> Option<&String> {
         if let Some(btn) = self.widget.downcast_ref::<Button>() {
             if btn.hovering {
@@ -7,15 +7,17 @@ use taffy::layout::AvailableSpace;
 use taffy::node::{Node, Taffy};
 use taffy::style::{Dimension, Style};
 
-use geom::Polygon;
+use geom::{Distance, Polygon};
 
+use crate::widgets::dock::{self, DockAction, DockState};
 use crate::widgets::slider;
 use crate::widgets::spinner::SpinnerValue;
 use crate::widgets::Container;
 use crate::{
-    Autocomplete, Button, Color, Dropdown, EventCtx, GfxCtx, HorizontalAlignment, Menu, Outcome,
-    PersistentSplit, ScreenDims, ScreenPt, ScreenRectangle, Slider, Spinner, Stash, TextBox,
-    Toggle, VerticalAlignment, Widget, WidgetImpl, WidgetOutput,
+    AccessibleNode, AccessibleRole, Autocomplete, Button, Canvas, Color, ColorPicker, Dropdown,
+    Event, EventCtx, GfxCtx, HorizontalAlignment, Key, Menu, Outcome, PersistentSplit, ScreenDims,
+    ScreenPt, ScreenRectangle, Slider, Spinner, Stash, TextArea, TextBox, Toggle,
+    VerticalAlignment, Widget, WidgetImpl, WidgetOutput,
 };
 
 pub struct Panel {
@@ -32,6 +34,10 @@ pub struct Panel {
     contents_dims: ScreenDims,
     container_dims: ScreenDims,
     clip_rect: Option<ScreenRectangle>,
+    /// Identifies this panel as the owner of keyboard focus, which is tracked in the `Canvas`
+    id: usize,
+    /// Only for panels built with `PanelBuilder::dockable`
+    dock: Option<DockState>,
 }
 
 impl Panel {
@@ -43,6 +49,7 @@ impl Panel {
             dims_x: PanelDims::MaxPercent(1.0),
             dims_y: PanelDims::MaxPercent(1.0),
             ignore_initial_events: false,
+            dock: None,
         }
     }
 
@@ -65,6 +72,54 @@ impl Panel {
         self.container_dims = ScreenDims::new(width, height);
     }
 
+    // Exact dims are expressed as a minimum size for the contents. Don't set size, because then
+    // scrolling breaks -- the actual size has to be based on the contents.
+    fn apply_min_size(&mut self, ctx: &EventCtx) {
+        let min_size = &mut self.top_level.layout.style.min_size;
+        min_size.width = match self.dims_x {
+            PanelDims::MaxPercent(_) => Style::default().min_size.width,
+            PanelDims::ExactPercent(pct) => {
+                Dimension::Points((pct * ctx.canvas.window_width) as f32)
+            }
+            PanelDims::ExactPixels(x) => Dimension::Points(x as f32),
+        };
+        min_size.height = match self.dims_y {
+            PanelDims::MaxPercent(_) => Style::default().min_size.height,
+            PanelDims::ExactPercent(pct) => {
+                Dimension::Points((pct * ctx.canvas.window_height) as f32)
+            }
+            PanelDims::ExactPixels(x) => Dimension::Points(x as f32),
+        };
+    }
+
+    // There is a dependency cycle in our layout logic. As a consequence:
+    //   1. we have to call `recompute_layout` twice here
+    //   2. panels don't responsively change `contents_dims`
+    //
+    // - `self.top_level.rect`, used here to set content_dims, is set by `recompute_layout`.
+    // - the output of `recompute_layout` depends on `container_dims`
+    // - `container_dims`, in the case of `MaxPercent`, depend on `content_dims`
+    //
+    // So this lays out everything from scratch. It's only needed when the panel is built, or when
+    // something like resizing or collapsing a dockable panel changes the size of the contents.
+    //
+    // TODO: to support Panel's that can resize their `contents_dims`, we'll need to detangle
+    // this dependency. This might entail decomposing the flexbox calculation to layout first
+    // the inner content, and then potentially a second pass to layout any x/y scrollbars.
+    fn recompute_contents_dims(&mut self, ctx: &EventCtx, recompute_bg: bool) {
+        // Forget the old dims, so any scrollbars are unwrapped and not added back yet
+        self.contents_dims = ScreenDims::new(0.0, 0.0);
+        self.container_dims = ScreenDims::new(0.0, 0.0);
+        self.recompute_scrollbar_layout(ctx);
+
+        self.apply_min_size(ctx);
+        self.recompute_layout(ctx, recompute_bg);
+        self.contents_dims =
+            ScreenDims::new(self.top_level.rect.width(), self.top_level.rect.height());
+        self.update_container_dims_for_canvas_dims(ctx.canvas.get_window_dims());
+        self.recompute_layout(ctx, recompute_bg);
+    }
+
     fn recompute_scrollbar_layout(&mut self, ctx: &EventCtx) {
         let old_scrollable_x = self.scrollable_x;
         let old_scrollable_y = self.scrollable_y;
@@ -274,6 +329,10 @@ impl Panel {
     }
 
     pub fn event(&mut self, ctx: &mut EventCtx) -> Outcome {
+        if self.keyboard_focus(ctx.canvas).is_some() {
+            ctx.canvas.keyboard_focus_renewed = true;
+        }
+
         if (self.scrollable_x || self.scrollable_y)
             && ctx
                 .canvas
@@ -301,6 +360,34 @@ impl Panel {
             self.recompute_layout(ctx, false);
         }
 
+        if self.dock.is_some() && self.dock_event(ctx) {
+            return Outcome::Nothing;
+        }
+
+        // The widget with keyboard focus gets Enter and Space before anything else, unless some
+        // widget (like an open dropdown) exclusively owns focus or the player is typing. If the
+        // widget doesn't do anything with the key, hotkeys still get it.
+        if ctx.focus_owned_by.is_none() && !self.top_level.is_typing() {
+            if let Some(id) = self.keyboard_focus(ctx.canvas).cloned() {
+                if !ctx.input.has_been_consumed()
+                    && (ctx.input.event == Event::KeyPress(Key::Enter)
+                        || ctx.input.event == Event::KeyPress(Key::Space))
+                {
+                    let mut output = WidgetOutput::new();
+                    if let Some(w) = self.top_level.find_mut(&id) {
+                        w.widget.activate(ctx, &mut output);
+                    } else {
+                        // The panel changed, and the widget is gone
+                        ctx.canvas.keyboard_focus = None;
+                    }
+                    if !matches!(output.outcome, Outcome::Nothing) {
+                        ctx.input.consume_event();
+                        return self.finish_event(ctx, output);
+                    }
+                }
+            }
+        }
+
         let before = self.scroll_offset();
         let mut output = WidgetOutput::new();
         self.top_level.widget.event(ctx, &mut output);
@@ -310,6 +397,21 @@ impl Panel {
         } else if self.scroll_offset() != before {
             self.recompute_layout_if_needed(ctx, true);
         }
+        if self.dock_clicked(ctx, &output.outcome) {
+            return Outcome::Nothing;
+        }
+
+        // Only move focus if no widget used Tab as a hotkey
+        if matches!(output.outcome, Outcome::Nothing)
+            && ctx.focus_owned_by.is_none()
+            && !ctx.input.has_been_consumed()
+            && ctx.input.event == Event::KeyPress(Key::Tab)
+        {
+            let backwards = ctx.is_key_down(Key::LeftShift);
+            if self.move_keyboard_focus(ctx, backwards) {
+                ctx.input.consume_event();
+            }
+        }
 
         // Remember this for the next event
         if let Outcome::Focused(ref id) = output.outcome {
@@ -320,6 +422,230 @@ impl Panel {
         output.outcome
     }
 
+    fn finish_event(&mut self, ctx: &mut EventCtx, output: WidgetOutput) -> Outcome {
+        if output.redo_layout {
+            self.recompute_layout(ctx, true);
+        }
+        if self.dock_clicked(ctx, &output.outcome) {
+            return Outcome::Nothing;
+        }
+        if let Outcome::Focused(ref id) = output.outcome {
+            assert!(ctx.next_focus_owned_by.is_none());
+            ctx.next_focus_owned_by = Some(id.clone());
+        }
+        output.outcome
+    }
+
+    // Where the panel is on the screen, not counting scrollbars
+    fn panel_bounds(&self) -> ScreenRectangle {
+        self.clip_rect
+            .clone()
+            .unwrap_or_else(|| self.top_level.rect.clone())
+    }
+
+    // Lets the player move and resize a dockable panel. Returns true if the event was used.
+    fn dock_event(&mut self, ctx: &mut EventCtx) -> bool {
+        let window = ctx.canvas.get_window_dims();
+        let action = self.dock.as_ref().unwrap().action;
+        match action {
+            Some(DockAction::Moving { grab }) => {
+                if let Some(pt) = ctx.input.get_moved_mouse() {
+                    self.horiz = HorizontalAlignment::Percent(
+                        ((pt.x - grab.x) / window.width).clamp(0.0, 1.0),
+                    );
+                    self.vert = VerticalAlignment::Percent(
+                        ((pt.y - grab.y) / window.height).clamp(0.0, 1.0),
+                    );
+                    self.recompute_layout_if_needed(ctx, false);
+                }
+                if ctx.input.left_mouse_button_released() {
+                    let bounds = self.panel_bounds();
+                    let dock = self.dock.as_mut().unwrap();
+                    dock.action = None;
+                    dock.layout.snap_to(&bounds, window);
+                    let (horiz, vert) = dock.layout.alignment().unwrap();
+                    self.horiz = horiz;
+                    self.vert = vert;
+                    self.recompute_layout_if_needed(ctx, false);
+                    self.save_dock_layout(ctx);
+                }
+                true
+            }
+            Some(DockAction::Resizing { align }) => {
+                if let Some(pt) = ctx.input.get_moved_mouse() {
+                    let bounds = self.panel_bounds();
+                    let size = dock::resized(ScreenPt::new(bounds.x1, bounds.y1), pt, window);
+                    self.dims_x = PanelDims::ExactPixels(size.width);
+                    self.dims_y = PanelDims::ExactPixels(size.height);
+                    self.dock.as_mut().unwrap().layout.size = Some(size);
+                    self.recompute_contents_dims(ctx, true);
+                }
+                if ctx.input.left_mouse_button_released() {
+                    self.dock.as_mut().unwrap().action = None;
+                    self.horiz = align.0;
+                    self.vert = align.1;
+                    self.recompute_layout_if_needed(ctx, false);
+                    self.save_dock_layout(ctx);
+                }
+                true
+            }
+            None => {
+                if !ctx.input.left_mouse_button_pressed() {
+                    return false;
+                }
+                let pt = if let Some(pt) = ctx.canvas.get_cursor_in_screen_space() {
+                    pt
+                } else {
+                    return false;
+                };
+                let bounds = self.panel_bounds();
+                if !bounds.contains(pt) {
+                    return false;
+                }
+
+                if !self.dock.as_ref().unwrap().layout.collapsed
+                    && pt.x >= bounds.x2 - dock::RESIZE_GRIP
+                    && pt.y >= bounds.y2 - dock::RESIZE_GRIP
+                {
+                    self.dock.as_mut().unwrap().action = Some(DockAction::Resizing {
+                        align: (self.horiz, self.vert),
+                    });
+                    // Keep the top-left corner in place while resizing
+                    self.horiz = HorizontalAlignment::Percent(bounds.x1 / window.width);
+                    self.vert = VerticalAlignment::Percent(bounds.y1 / window.height);
+                    return true;
+                }
+
+                // Don't start dragging when the collapse button is clicked
+                if self.currently_hovering().is_none() && self.rect_of("dock titlebar").contains(pt)
+                {
+                    self.dock.as_mut().unwrap().action = Some(DockAction::Moving {
+                        grab: ScreenPt::new(pt.x - bounds.x1, pt.y - bounds.y1),
+                    });
+                    return true;
+                }
+                false
+            }
+        }
+    }
+
+    // If the collapse button of a dockable panel was clicked, handle it and return true.
+    fn dock_clicked(&mut self, ctx: &mut EventCtx, outcome: &Outcome) -> bool {
+        let action = match outcome {
+            Outcome::Clicked(action) => action,
+            _ => {
+                return false;
+            }
+        };
+        if self.dock.is_none() || (action != dock::COLLAPSE && action != dock::EXPAND) {
+            return false;
+        }
+        let collapsed = action == dock::COLLAPSE;
+
+        // Swap the contents out, or back in
+        let dock = self.dock.as_mut().unwrap();
+        let mut contents = dock.contents.take().unwrap_or_else(Widget::nothing);
+        let container: &mut Container = self.find_mut("dock contents");
+        std::mem::swap(&mut container.members[0], &mut contents);
+        let dock = self.dock.as_mut().unwrap();
+        if collapsed {
+            dock.contents = Some(contents);
+        }
+        dock.layout.collapsed = collapsed;
+        self.dims_y = if collapsed {
+            PanelDims::MaxPercent(1.0)
+        } else if let Some(size) = dock.layout.size {
+            PanelDims::ExactPixels(size.height)
+        } else {
+            dock.expanded_dims_y
+        };
+
+        let old = self.top_level.find_mut(action).unwrap();
+        let mut new = dock::collapse_button(ctx, collapsed);
+        new.layout.style = old.layout.style;
+        *old = new;
+        // Keep focus on the button, so Enter toggles it back
+        if self.keyboard_focus(ctx.canvas) == Some(action) {
+            ctx.canvas.keyboard_focus = Some((
+                self.id,
+                if collapsed {
+                    dock::EXPAND
+                } else {
+                    dock::COLLAPSE
+                }
+                .to_string(),
+            ));
+        }
+
+        self.recompute_contents_dims(ctx, true);
+        self.save_dock_layout(ctx);
+        true
+    }
+
+    fn save_dock_layout(&self, ctx: &mut EventCtx) {
+        let dock = self.dock.as_ref().unwrap();
+        ctx.canvas
+            .panel_layouts
+            .insert(dock.id.clone(), dock.layout.clone());
+        dock::save_panel_layouts(&ctx.canvas.panel_layouts);
+    }
+
+    /// Moves keyboard focus to the next or previous widget in this panel. If another panel has
+    /// focus, this leaves it alone. Past the last widget, focus is released, so other panels can
+    /// take it next. Returns true if the Tab press was used.
+    fn move_keyboard_focus(&mut self, ctx: &mut EventCtx, backwards: bool) -> bool {
+        let current = match ctx.canvas.keyboard_focus {
+            Some((panel, ref id)) if panel == self.id => Some(id.clone()),
+            Some(_) => {
+                return false;
+            }
+            None => None,
+        };
+
+        let mut ids = Vec::new();
+        self.top_level.get_focusable(&mut ids);
+        let idx = match current.and_then(|id| ids.iter().position(|x| *x == id)) {
+            None if ids.is_empty() => None,
+            None if backwards => Some(ids.len() - 1),
+            None => Some(0),
+            Some(idx) if backwards => idx.checked_sub(1),
+            Some(idx) => Some(idx + 1).filter(|idx| *idx < ids.len()),
+        };
+        let id = if let Some(idx) = idx {
+            ids.swap_remove(idx)
+        } else {
+            ctx.canvas.keyboard_focus = None;
+            return false;
+        };
+
+        // Scroll to the newly focused widget if it's hidden
+        if let Some(ref clip) = self.clip_rect {
+            let rect = &self.top_level.find(&id).unwrap().rect;
+            if rect.y1 < clip.y1 || rect.y2 > clip.y2 {
+                self.scroll_to_member(ctx, id.clone());
+            }
+        }
+        ctx.canvas.keyboard_focus = Some((self.id, id));
+        ctx.canvas.keyboard_focus_renewed = true;
+        true
+    }
+
+    /// Which widget in this panel currently has keyboard focus, if any? Only one widget on the
+    /// screen has it at a time.
+    pub fn keyboard_focus<'a>(&self, canvas: &'a Canvas) -> Option<&'a String> {
+        match canvas.keyboard_focus {
+            Some((panel, ref id)) if panel == self.id => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Describe everything in the panel for screen readers and other assistive technology.
+    pub fn accessibility_tree(&self, canvas: &Canvas) -> AccessibleNode {
+        self.top_level
+            .accessibility_tree(self.keyboard_focus(canvas))
+            .unwrap_or_else(|| AccessibleNode::new(AccessibleRole::Group, ""))
+    }
+
     pub fn draw(&self, g: &mut GfxCtx) {
         if let Some(ref rect) = self.clip_rect {
             g.enable_clipping(rect.clone());
@@ -345,6 +671,33 @@ impl Panel {
         }
 
         self.top_level.draw(g);
+        if let Some(id) = self.keyboard_focus(g.canvas) {
+            if let Some(w) = self.top_level.find(id) {
+                let (thickness, color) = g.style().focus_outline;
+                let rect = &w.rect;
+                g.fork_screenspace();
+                g.draw_polygon(
+                    color,
+                    Polygon::rounded_rectangle(rect.width(), rect.height(), 2.0)
+                        .to_outline(Distance::meters(thickness))
+                        .translate(rect.x1, rect.y1),
+                );
+                g.unfork();
+            }
+        }
+        if let Some(ref dock) = self.dock {
+            if !dock.layout.collapsed {
+                // Hint that the bottom-right corner can be dragged
+                let rect = self.panel_bounds();
+                g.fork_screenspace();
+                g.draw_polygon(
+                    g.style().text_secondary_color.alpha(0.5),
+                    Polygon::rounded_rectangle(dock::RESIZE_GRIP, dock::RESIZE_GRIP, 2.0)
+                        .translate(rect.x2 - dock::RESIZE_GRIP, rect.y2 - dock::RESIZE_GRIP),
+                );
+                g.unfork();
+            }
+        }
         if self.scrollable_x || self.scrollable_y {
             g.disable_clipping();
 
@@ -367,6 +720,12 @@ impl Panel {
 
     pub fn restore(&mut self, ctx: &mut EventCtx, prev: &Panel) {
         self.set_scroll_offset(ctx, prev.scroll_offset());
+        if let Some(id) = prev.keyboard_focus(ctx.canvas).cloned() {
+            if self.top_level.find(&id).is_some() {
+                ctx.canvas.keyboard_focus = Some((self.id, id));
+                ctx.canvas.keyboard_focus_renewed = true;
+            }
+        }
 
         self.top_level.restore(ctx, prev);
 
@@ -390,7 +749,7 @@ impl Panel {
     }
 
     pub fn has_widget(&self, name: &str) -> bool {
-        self.top_level.find(name).is_some()
+        self.maybe_find_widget(name).is_some()
     }
 
     pub fn slider(&self, name: &str) -> &Slider {
@@ -422,6 +781,14 @@ impl Panel {
         self.find::<TextBox>(name).get_line()
     }
 
+    pub fn text_area(&self, name: &str) -> String {
+        self.find::<TextArea>(name).get_text()
+    }
+
+    pub fn color_picker(&self, name: &str) -> Color {
+        self.find::<ColorPicker>(name).current()
+    }
+
     pub fn spinner<T: 'static + SpinnerValue>(&self, name: &str) -> T {
         self.find::<Spinner<T>>(name).current
     }
@@ -473,7 +840,23 @@ impl Panel {
     }
 
     pub fn maybe_find_widget(&self, name: &str) -> Option<&Widget> {
-        self.top_level.find(name)
+        self.top_level.find(name).or_else(|| {
+            // A collapsed dockable panel still has its contents
+            self.dock
+                .as_ref()
+                .and_then(|dock| dock.contents.as_ref())
+                .and_then(|contents| contents.find(name))
+        })
+    }
+
+    fn maybe_find_widget_mut(&mut self, name: &str) -> Option<&mut Widget> {
+        if self.top_level.find(name).is_some() {
+            return self.top_level.find_mut(name);
+        }
+        self.dock
+            .as_mut()
+            .and_then(|dock| dock.contents.as_mut())
+            .and_then(|contents| contents.find_mut(name))
     }
 
     pub fn maybe_find<T: WidgetImpl>(&self, name: &str) -> Option<&T> {
@@ -492,7 +875,7 @@ impl Panel {
     }
 
     pub fn find_mut<T: WidgetImpl>(&mut self, name: &str) -> &mut T {
-        if let Some(w) = self.top_level.find_mut(name) {
+        if let Some(w) = self.maybe_find_widget_mut(name) {
             if let Some(x) = w.widget.downcast_mut::<T>() {
                 x
             } else {
@@ -521,7 +904,7 @@ impl Panel {
     }
 
     pub fn rect_of(&self, name: &str) -> &ScreenRectangle {
-        &self.top_level.find(name).unwrap().rect
+        &self.maybe_find_widget(name).unwrap().rect
     }
     // TODO Deprecate
     pub fn center_of(&self, name: &str) -> ScreenPt {
@@ -551,8 +934,7 @@ impl Panel {
         }
         new = new.named(id);
         let old = self
-            .top_level
-            .find_mut(id)
+            .maybe_find_widget_mut(id)
             .unwrap_or_else(|| panic!("Panel doesn't have {}", id));
         new.layout.style = old.layout.style;
         *old = new;
@@ -584,6 +966,7 @@ pub struct PanelBuilder {
     dims_x: PanelDims,
     dims_y: PanelDims,
     ignore_initial_events: bool,
+    dock: Option<DockState>,
 }
 
 #[derive(Clone, Copy)]
@@ -599,8 +982,24 @@ impl PanelBuilder {
         self.build_custom(ctx)
     }
 
-    pub fn build_custom(self, ctx: &mut EventCtx) -> Panel {
+    pub fn build_custom(mut self, ctx: &mut EventCtx) -> Panel {
         let ignore_initial_events = self.ignore_initial_events;
+        // Start from however the player arranged a dockable panel
+        if let Some(ref mut dock) = self.dock {
+            dock.expanded_dims_y = self.dims_y;
+            if let Some((horiz, vert)) = dock.layout.alignment() {
+                self.horiz = horiz;
+                self.vert = vert;
+            }
+            if let Some(size) = dock.layout.size {
+                self.dims_x = PanelDims::ExactPixels(size.width);
+                self.dims_y = PanelDims::ExactPixels(size.height);
+            }
+            if dock.layout.collapsed {
+                self.dims_y = PanelDims::MaxPercent(1.0);
+            }
+        }
+
         let mut panel = Panel {
             top_level: self.top_level,
 
@@ -614,47 +1013,12 @@ impl PanelBuilder {
             contents_dims: ScreenDims::new(0.0, 0.0),
             container_dims: ScreenDims::new(0.0, 0.0),
             clip_rect: None,
+            id: ctx.canvas.next_panel_id,
             cached_flexbox: None,
+            dock: self.dock,
         };
-        match self.dims_x {
-            PanelDims::MaxPercent(_) => {}
-            PanelDims::ExactPercent(pct) => {
-                // Don't set size, because then scrolling breaks -- the actual size has to be based
-                // on the contents.
-                panel.top_level.layout.style.min_size.width =
-                    Dimension::Points((pct * ctx.canvas.window_width) as f32);
-            }
-            PanelDims::ExactPixels(x) => {
-                panel.top_level.layout.style.min_size.width = Dimension::Points(x as f32);
-            }
-        }
-        match self.dims_y {
-            PanelDims::MaxPercent(_) => {}
-            PanelDims::ExactPercent(pct) => {
-                panel.top_level.layout.style.min_size.height =
-                    Dimension::Points((pct * ctx.canvas.window_height) as f32);
-            }
-            PanelDims::ExactPixels(x) => {
-                panel.top_level.layout.style.min_size.height = Dimension::Points(x as f32);
-            }
-        }
-
-        // There is a dependency cycle in our layout logic. As a consequence:
-        //   1. we have to call `recompute_layout` twice here
-        //   2. panels don't responsively change `contents_dims`
-        //
-        // - `panel.top_level.rect`, used here to set content_dims, is set by `recompute_layout`.
-        // - the output of `recompute_layout` depends on `container_dims`
-        // - `container_dims`, in the case of `MaxPercent`, depend on `content_dims`
-        //
-        // TODO: to support Panel's that can resize their `contents_dims`, we'll need to detangle
-        // this dependency. This might entail decomposing the flexbox calculation to layout first
-        // the inner content, and then potentially a second pass to layout any x/y scrollbars.
-        panel.recompute_layout(ctx, false);
-        panel.contents_dims =
-            ScreenDims::new(panel.top_level.rect.width(), panel.top_level.rect.height());
-        panel.update_container_dims_for_canvas_dims(ctx.canvas.get_window_dims());
-        panel.recompute_layout(ctx, false);
+        ctx.canvas.next_panel_id += 1;
+        panel.recompute_contents_dims(ctx, false);
 
         // Just trigger error if a button is double-defined
         panel.get_all_click_actions();
@@ -703,6 +1067,41 @@ impl PanelBuilder {
             .dims_height(PanelDims::ExactPercent((y as f64) / 100.0))
     }
 
+    /// Adds a titlebar to the panel. Players can drag it to dock the panel to another edge of the
+    /// window, drag the bottom-right corner to resize the panel, and collapse the panel down to
+    /// just the titlebar. How the player arranged the panel is remembered across sessions, keyed
+    /// by `id`, so always use the same ID when rebuilding the same panel.
+    ///
+    /// While the panel is collapsed, its widgets can still be found, but they aren't drawn and
+    /// don't receive events.
+    pub fn dockable(mut self, ctx: &EventCtx, id: &str, title: &str) -> PanelBuilder {
+        let layout = ctx
+            .canvas
+            .panel_layouts
+            .get(id)
+            .cloned()
+            .unwrap_or_default();
+        let mut contents = std::mem::replace(&mut self.top_level, Widget::nothing());
+        let mut stashed = None;
+        if layout.collapsed {
+            stashed = Some(contents);
+            contents = Widget::nothing();
+        }
+        self.top_level = Widget::col(vec![
+            dock::titlebar(ctx, title, layout.collapsed),
+            contents.container().named("dock contents"),
+        ]);
+        self.dock = Some(DockState {
+            id: id.to_string(),
+            layout,
+            // Filled out in build_custom
+            expanded_dims_y: self.dims_y,
+            contents: stashed,
+            action: None,
+        });
+        self
+    }
+
     /// When a panel is built, a fake, "no-op" mouseover event is immediately fired, to let all
     /// widgets initially pick up the position of the mouse. Normally this event should only
     /// produce `Outcome::Nothing`, since other outcomes will be lost -- there's no way for the
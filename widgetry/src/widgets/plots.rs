@@ -1,9 +1,15 @@
 use std::collections::HashSet;
 
 use abstutil::prettyprint_usize;
-use geom::{Circle, Distance, Duration, Percent, Pt2D, Tessellation, Time, UnitFmt};
+use geom::{
+    Angle, Circle, Distance, Duration, Percent, PolyLine, Polygon, Pt2D, Tessellation, Time,
+    UnitFmt,
+};
 
-use crate::{Color, EventCtx, GeomBatch, ScreenDims, TextExt, Toggle, Widget};
+use crate::{
+    Color, Drawable, EdgeInsets, EventCtx, GeomBatch, GfxCtx, ScreenDims, ScreenPt, Text, TextExt,
+    Toggle, Widget,
+};
 
 #[derive(Default)]
 pub struct PlotOptions<X: Axis<X>, Y: Axis<Y>> {
@@ -155,30 +161,44 @@ pub fn make_legend<X: Axis<X>, Y: Axis<Y>>(
     ctx: &EventCtx,
     series: &[Series<X, Y>],
     opts: &PlotOptions<X, Y>,
+) -> Widget {
+    make_legend_entries(
+        ctx,
+        series.iter().map(|s| (s.label.clone(), s.color)).collect(),
+        opts,
+    )
+}
+
+/// Build a legend for any kind of plot from a label and color per entry. Repeated labels are only
+/// shown once. If the plot is filterable, each entry is a checkbox named by its label.
+pub fn make_legend_entries<X: Axis<X>, Y: Axis<Y>>(
+    ctx: &EventCtx,
+    entries: Vec<(String, Color)>,
+    opts: &PlotOptions<X, Y>,
 ) -> Widget {
     let mut row = Vec::new();
     let mut seen = HashSet::new();
-    for s in series {
-        if seen.contains(&s.label) {
+    for (label, color) in entries {
+        if seen.contains(&label) {
             continue;
         }
-        seen.insert(s.label.clone());
+        seen.insert(label.clone());
         if opts.filterable {
             row.push(Toggle::colored_checkbox(
                 ctx,
-                &s.label,
-                s.color,
-                !opts.disabled.contains(&s.label),
+                &label,
+                color,
+                !opts.disabled.contains(&label),
             ));
         } else {
             let radius = 15.0;
             row.push(Widget::row(vec![
                 GeomBatch::from(vec![(
-                    s.color,
+                    color,
                     Circle::new(Pt2D::new(radius, radius), Distance::meters(radius)).to_polygon(),
                 )])
                 .into_widget(ctx),
-                s.label.clone().text_widget(ctx),
+                label.text_widget(ctx),
             ]));
         }
     }
@@ -188,6 +208,135 @@ pub fn make_legend<X: Axis<X>, Y: Axis<Y>>(
     }
 }
 
+/// The default size of a plot, if `PlotOptions` doesn't specify one
+pub fn default_dims(ctx: &EventCtx) -> ScreenDims {
+    ScreenDims::new(
+        0.23 * ctx.canvas.window_width,
+        0.2 * ctx.canvas.window_height,
+    )
+}
+
+/// Draw horizontal grid lines for the Y scale, up to 10 lines to cover the order of magnitude of
+/// the range.
+pub fn horizontal_grid_lines<Y: Axis<Y>>(batch: &mut GeomBatch, max_y: Y, dims: ScreenDims) {
+    if max_y == Y::zero() {
+        return;
+    }
+    let order_of_mag = 10.0_f64.powf(max_y.to_f64().log10().ceil());
+    for i in 0..10 {
+        let y = max_y.from_f64(order_of_mag / 10.0 * (i as f64));
+        let pct = y.to_percent(max_y);
+        if pct > 1.0 {
+            break;
+        }
+        batch.push(
+            Color::hex("#7C7C7C"),
+            PolyLine::must_new(vec![
+                Pt2D::new(0.0, (1.0 - pct) * dims.height),
+                Pt2D::new(dims.width, (1.0 - pct) * dims.height),
+            ])
+            .make_polygons(Distance::meters(1.0)),
+        );
+    }
+}
+
+/// Labels evenly spaced along the X axis, from zero to `max_x`
+pub fn make_x_axis<X: Axis<X>>(ctx: &EventCtx, max_x: X, unit_fmt: &UnitFmt) -> Widget {
+    let num_x_labels = 3;
+    let mut row = Vec::new();
+    for i in 0..num_x_labels {
+        let percent_x = (i as f64) / ((num_x_labels - 1) as f64);
+        let batch = Text::from(max_x.from_percent(percent_x).prettyprint(unit_fmt))
+            .render(ctx)
+            .rotate(Angle::degrees(-15.0))
+            .autocrop();
+        row.push(batch.into_widget(ctx));
+    }
+    Widget::custom_row(row)
+        .padding(EdgeInsets {
+            top: 10.0,
+            left: 60.0,
+            right: 10.0,
+            bottom: 10.0,
+        })
+        .evenly_spaced()
+}
+
+/// Labels evenly spaced along the Y axis, from zero to `max_y`
+pub fn make_y_axis<Y: Axis<Y>>(ctx: &EventCtx, max_y: Y, unit_fmt: &UnitFmt) -> Widget {
+    let num_y_labels = 3;
+    let mut col = Vec::new();
+    for i in 0..num_y_labels {
+        let percent_y = (i as f64) / ((num_y_labels - 1) as f64);
+        col.push(
+            max_y
+                .from_percent(percent_y)
+                .prettyprint(unit_fmt)
+                .text_widget(ctx),
+        );
+    }
+    col.reverse();
+    Widget::custom_col(col).padding(10).evenly_spaced()
+}
+
+/// Areas of a plot that show a tooltip and get outlined while the cursor is over them. The
+/// polygons are relative to the top-left of the plot.
+pub struct HoverRegions {
+    regions: Vec<(Polygon, Text)>,
+    current: Option<usize>,
+    draw_current: Option<Drawable>,
+}
+
+impl HoverRegions {
+    pub fn new() -> HoverRegions {
+        HoverRegions {
+            regions: Vec::new(),
+            current: None,
+            draw_current: None,
+        }
+    }
+
+    pub fn add(&mut self, polygon: Polygon, tooltip: Text) {
+        self.regions.push((polygon, tooltip));
+    }
+
+    /// Recalculate which region is hovered, for a plot at `top_left`.
+    pub fn event(&mut self, ctx: &EventCtx, top_left: ScreenPt) {
+        if !ctx.redo_mouseover() {
+            return;
+        }
+        let before = self.current;
+        self.current = ctx.canvas.get_cursor_in_screen_space().and_then(|cursor| {
+            let pt = Pt2D::new(cursor.x - top_left.x, cursor.y - top_left.y);
+            // Later regions are drawn on top
+            self.regions
+                .iter()
+                .rposition(|(polygon, _)| polygon.contains_pt(pt))
+        });
+        if before != self.current {
+            self.draw_current = self.current.map(|idx| {
+                GeomBatch::from(vec![(
+                    Color::WHITE,
+                    self.regions[idx].0.to_outline(Distance::meters(2.0)),
+                )])
+                .upload(ctx)
+            });
+        }
+    }
+
+    /// The index of the hovered region, in the order they were added
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx, top_left: ScreenPt) {
+        if let Some(idx) = self.current {
+            g.redraw_at(top_left, self.draw_current.as_ref().unwrap());
+            g.draw_mouse_tooltip(self.regions[idx].1.clone());
+        }
+    }
+}
+
 // TODO If this proves useful, lift to geom
 pub fn thick_lineseries(pts: Vec<Pt2D>, width: Distance) -> Tessellation {
     use lyon::math::{point, Point};
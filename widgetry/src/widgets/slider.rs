@@ -1,8 +1,8 @@
 use geom::{Circle, Distance, Polygon, Pt2D};
 
 use crate::{
-    Color, Drawable, EdgeInsets, EventCtx, GeomBatch, GfxCtx, Outcome, ScreenDims, ScreenPt,
-    ScreenRectangle, Widget, WidgetImpl, WidgetOutput,
+    AccessibleNode, AccessibleRole, Color, Drawable, EdgeInsets, EventCtx, GeomBatch, GfxCtx,
+    Outcome, ScreenDims, ScreenPt, ScreenRectangle, Widget, WidgetImpl, WidgetOutput,
 };
 
 pub struct Slider {
@@ -329,4 +329,13 @@ impl WidgetImpl for Slider {
         g.canvas
             .mark_covered_area(ScreenRectangle::top_left(self.top_left, self.dims));
     }
+
+    fn accessible(&self) -> Option<AccessibleNode> {
+        // Scrollbars don't have a label
+        let label = self.label.as_ref()?;
+        Some(
+            AccessibleNode::new(AccessibleRole::Slider, label)
+                .value(format!("{}%", (self.current_percent * 100.0).round())),
+        )
+    }
 }
@@ -1,11 +1,11 @@
 use std::ops;
 
-use geom::{trim_f64, CornerRadii, Distance, Polygon, Pt2D};
+use geom::{trim_f64, CornerRadii, Distance, Duration, Polygon, Pt2D, Speed, UnitFmt};
 
 use crate::{
-    include_labeled_bytes, Button, Drawable, EdgeInsets, EventCtx, GeomBatch, GfxCtx, Outcome,
-    OutlineStyle, Prerender, ScreenDims, ScreenPt, ScreenRectangle, Style, Text, Widget,
-    WidgetImpl, WidgetOutput,
+    include_labeled_bytes, AccessibleNode, AccessibleRole, Button, Color, Drawable, EdgeInsets,
+    EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, OutlineStyle, Prerender, ScreenDims, ScreenPt,
+    ScreenRectangle, Style, Text, Widget, WidgetImpl, WidgetOutput,
 };
 
 // Manually tuned
@@ -35,7 +35,14 @@ impl<T> SpinnerValue for T where
 {
 }
 
-// TODO Allow text entry
+/// A value with units that a `Spinner` can show and let the player type in, like "30 mph" or
+/// "1h 30m"
+pub trait SpinnerUnits: SpinnerValue {
+    fn format_units(self, fmt: &UnitFmt) -> String;
+    /// Parse what the player typed, or return `None` if it doesn't make sense
+    fn parse_units(input: &str, fmt: &UnitFmt) -> Option<Self>;
+}
+
 // TODO Allow click and hold
 // TODO Grey out the buttons when we're maxed out
 pub struct Spinner<T> {
@@ -45,6 +52,11 @@ pub struct Spinner<T> {
     pub current: T,
     label: String,
     render_value: Box<dyn Fn(T) -> String>,
+    // Only for spinners created with `widget_with_units`
+    parse_value: Option<Box<dyn Fn(&str) -> Option<T>>>,
+    // What the player has typed so far, and whether they tried to enter it when it was invalid
+    typing: Option<(String, bool)>,
+    hovering_value: bool,
 
     up: Button,
     down: Button,
@@ -92,6 +104,7 @@ impl<T: 'static + SpinnerValue> Spinner<T> {
             current,
             step_size,
             render_value,
+            None,
         )))
         .named(label)
     }
@@ -103,6 +116,7 @@ impl<T: 'static + SpinnerValue> Spinner<T> {
         mut current: T,
         step_size: T,
         render_value: Box<dyn Fn(T) -> String>,
+        parse_value: Option<Box<dyn Fn(&str) -> Option<T>>>,
     ) -> Self {
         let button_builder = ctx
             .style()
@@ -163,6 +177,9 @@ impl<T: 'static + SpinnerValue> Spinner<T> {
             step_size,
             label,
             render_value,
+            parse_value,
+            typing: None,
+            hovering_value: false,
 
             up,
             down,
@@ -190,18 +207,105 @@ impl<T: 'static + SpinnerValue> Spinner<T> {
         }
     }
 
+    // Lets the player type a value while hovering on it. Returns true if the event was used.
+    fn typing_event(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) -> bool {
+        if ctx.redo_mouseover() {
+            self.hovering_value = ctx
+                .canvas
+                .get_cursor_in_screen_space()
+                .map(|pt| {
+                    ScreenRectangle::top_left(
+                        self.top_left,
+                        ScreenDims::new(TEXT_WIDTH, self.dims.height),
+                    )
+                    .contains(pt)
+                })
+                .unwrap_or(false);
+            // Moving away keeps a valid value, but otherwise gives up
+            if !self.hovering_value && self.typing.is_some() {
+                if !self.finish_typing(output) {
+                    self.typing = None;
+                }
+                self.drawable = self.drawable(ctx.prerender, ctx.style());
+            }
+            return false;
+        }
+        if !self.hovering_value {
+            return false;
+        }
+
+        let key = if let Some(key) = ctx.input.any_pressed() {
+            key
+        } else {
+            return false;
+        };
+        match (key, self.typing.as_mut()) {
+            (Key::Enter, Some(_)) => {
+                if !self.finish_typing(output) {
+                    self.typing.as_mut().unwrap().1 = true;
+                }
+            }
+            (Key::Escape, Some(_)) => {
+                self.typing = None;
+            }
+            (Key::Backspace, Some((typed, invalid))) => {
+                typed.pop();
+                *invalid = false;
+            }
+            _ => {
+                if let Some(c) = key.to_char(ctx.is_key_down(Key::LeftShift)) {
+                    let (typed, invalid) =
+                        self.typing.get_or_insert_with(|| (String::new(), false));
+                    typed.push(c);
+                    *invalid = false;
+                } else {
+                    ctx.input.unconsume_event();
+                    return false;
+                }
+            }
+        }
+        self.drawable = self.drawable(ctx.prerender, ctx.style());
+        true
+    }
+
+    // If the player typed a valid value within bounds, use it and return true
+    fn finish_typing(&mut self, output: &mut WidgetOutput) -> bool {
+        let value = if let Some((ref typed, _)) = self.typing {
+            (self.parse_value.as_ref().unwrap())(typed)
+        } else {
+            None
+        };
+        match value {
+            Some(value) if value >= self.low && value <= self.high => {
+                self.current = value;
+                self.typing = None;
+                output.outcome = Outcome::Changed(self.label.clone());
+                true
+            }
+            _ => false,
+        }
+    }
+
     fn drawable(&self, prerender: &Prerender, style: &Style) -> Drawable {
         let mut batch = GeomBatch::from(vec![(
             style.field_bg,
             Polygon::rounded_rectangle(self.dims.width, self.dims.height, 5.0),
         )]);
-        batch.append(
+        let txt = if let Some((ref typed, _)) = self.typing {
+            Text::from_all(vec![Line(typed), Line("|").fg(style.text_primary_color)])
+        } else {
             Text::from((self.render_value)(self.current))
-                .render_autocropped(prerender)
+        };
+        batch.append(
+            txt.render_autocropped(prerender)
                 .centered_on(Pt2D::new(TEXT_WIDTH / 2.0, self.dims.height / 2.0)),
         );
         batch.push(
-            self.outline.1,
+            if let Some((_, true)) = self.typing {
+                Color::RED
+            } else {
+                self.outline.1
+            },
             Polygon::rounded_rectangle(self.dims.width, self.dims.height, 5.0)
                 .to_outline(Distance::meters(self.outline.0)),
         );
@@ -227,6 +331,10 @@ impl<T: 'static + SpinnerValue> WidgetImpl for Spinner<T> {
     }
 
     fn event(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
+        if self.parse_value.is_some() && self.typing_event(ctx, output) {
+            return;
+        }
+
         self.up.event(ctx, output);
         if let Outcome::Clicked(_) = output.outcome {
             output.outcome = Outcome::Changed(self.label.clone());
@@ -282,6 +390,17 @@ impl<T: 'static + SpinnerValue> WidgetImpl for Spinner<T> {
         self.current = prev.current;
         self.drawable = self.drawable(ctx.prerender, ctx.style());
     }
+
+    fn is_typing(&self) -> bool {
+        self.typing.is_some()
+    }
+
+    fn accessible(&self) -> Option<AccessibleNode> {
+        Some(
+            AccessibleNode::new(AccessibleRole::Spinner, &self.label)
+                .value((self.render_value)(self.current)),
+        )
+    }
 }
 
 /// An f64 rounded to 4 decimal places. Useful with Spinners, to avoid values accumulating small
@@ -341,3 +460,183 @@ impl Spinner<RoundedF64> {
         )
     }
 }
+
+impl<T: 'static + SpinnerUnits> Spinner<T> {
+    /// Creates a spinner showing a value with units. While hovering on the value, the player can
+    /// also type a new one, like "45 km/h" or "1h 30m", and press Enter. Values that can't be
+    /// parsed or are out of bounds are rejected.
+    pub fn widget_with_units(
+        ctx: &EventCtx,
+        label: impl Into<String>,
+        (low, high): (T, T),
+        current: T,
+        step_size: T,
+        fmt: UnitFmt,
+    ) -> Widget {
+        let label = label.into();
+        Widget::new(Box::new(Self::new(
+            ctx,
+            label.clone(),
+            (low, high),
+            current,
+            step_size,
+            Box::new(move |x| x.format_units(&fmt)),
+            Some(Box::new(move |input| T::parse_units(input, &fmt))),
+        )))
+        .named(label)
+    }
+}
+
+impl SpinnerUnits for Duration {
+    fn format_units(self, fmt: &UnitFmt) -> String {
+        self.to_string(fmt)
+    }
+
+    /// Accepts things like "90s", "5 min", "1h 30m", or "1:30:00". A bare number is seconds.
+    fn parse_units(input: &str, _: &UnitFmt) -> Option<Duration> {
+        if input.contains(':') {
+            return Duration::parse(input.trim()).ok();
+        }
+        let mut total = Duration::ZERO;
+        for (value, unit) in split_units(input)? {
+            total += Duration::seconds(
+                value
+                    * match unit.as_str() {
+                        "" | "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
+                        "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
+                        "h" | "hr" | "hrs" | "hour" | "hours" => 3600.0,
+                        _ => {
+                            return None;
+                        }
+                    },
+            );
+        }
+        Some(total)
+    }
+}
+
+impl SpinnerUnits for Distance {
+    fn format_units(self, fmt: &UnitFmt) -> String {
+        self.to_string(fmt)
+    }
+
+    /// Accepts meters, kilometers, feet, or miles, like "3.5m" or "500 ft". A bare number uses
+    /// meters or feet, depending on `fmt`.
+    fn parse_units(input: &str, fmt: &UnitFmt) -> Option<Distance> {
+        let mut total = Distance::ZERO;
+        for (value, unit) in split_units(input)? {
+            total += match unit.as_str() {
+                "" if fmt.metric => Distance::meters(value),
+                "" => Distance::feet(value),
+                "m" | "meter" | "meters" | "metre" | "metres" => Distance::meters(value),
+                "km" => Distance::meters(1000.0 * value),
+                "ft" | "foot" | "feet" | "'" => Distance::feet(value),
+                "mi" | "mile" | "miles" => Distance::miles(value),
+                _ => {
+                    return None;
+                }
+            };
+        }
+        Some(total)
+    }
+}
+
+impl SpinnerUnits for Speed {
+    fn format_units(self, fmt: &UnitFmt) -> String {
+        self.to_string(fmt)
+    }
+
+    /// Accepts "km/h", "mph", or "m/s", like "30 mph". A bare number uses km/h or mph, depending
+    /// on `fmt`.
+    fn parse_units(input: &str, fmt: &UnitFmt) -> Option<Speed> {
+        let parts = split_units(input)?;
+        if parts.len() != 1 {
+            return None;
+        }
+        let (value, ref unit) = parts[0];
+        match unit.as_str() {
+            "" if fmt.metric => Some(Speed::km_per_hour(value)),
+            "" => Some(Speed::miles_per_hour(value)),
+            "km/h" | "kmh" | "kph" => Some(Speed::km_per_hour(value)),
+            "mph" => Some(Speed::miles_per_hour(value)),
+            "m/s" => Some(Speed::meters_per_second(value)),
+            _ => None,
+        }
+    }
+}
+
+// Splits input like "1h 30m" or "45km/h" into each number and the (lowercase) unit after it. The
+// unit may be empty.
+fn split_units(input: &str) -> Option<Vec<(f64, String)>> {
+    let mut result = Vec::new();
+    let mut chars = input.trim().chars().peekable();
+    while chars.peek().is_some() {
+        let mut number = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.' || *c == '-') {
+            number.push(c);
+        }
+        let value = number.parse::<f64>().ok()?;
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let mut unit = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_ascii_digit() && !c.is_whitespace()) {
+            unit.push(c.to_ascii_lowercase());
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        result.push((value, unit));
+    }
+    if result.is_empty() {
+        None
+    } else {
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_units() {
+        let metric = UnitFmt::metric();
+        let imperial = UnitFmt::imperial();
+
+        assert_eq!(
+            Duration::parse_units("1h 30m", &metric),
+            Some(Duration::minutes(90))
+        );
+        assert_eq!(
+            Duration::parse_units("45", &metric),
+            Some(Duration::seconds(45.0))
+        );
+        assert_eq!(
+            Duration::parse_units("2 min", &metric),
+            Some(Duration::minutes(2))
+        );
+        assert_eq!(Duration::parse_units("soon", &metric), None);
+
+        assert_eq!(
+            Distance::parse_units("3.5m", &imperial),
+            Some(Distance::meters(3.5))
+        );
+        assert_eq!(
+            Distance::parse_units("10", &metric),
+            Some(Distance::meters(10.0))
+        );
+        assert_eq!(
+            Distance::parse_units("10", &imperial),
+            Some(Distance::feet(10.0))
+        );
+        assert_eq!(Distance::parse_units("1 furlong", &metric), None);
+
+        assert_eq!(
+            Speed::parse_units("30 mph", &metric),
+            Some(Speed::miles_per_hour(30.0))
+        );
+        assert_eq!(
+            Speed::parse_units("50", &metric),
+            Some(Speed::km_per_hour(50.0))
+        );
+        assert_eq!(Speed::parse_units("", &metric), None);
+    }
+}
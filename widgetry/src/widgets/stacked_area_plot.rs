@@ -0,0 +1,182 @@
+use geom::{Polygon, Pt2D, Tessellation, UnitFmt};
+
+use crate::widgets::plots::{
+    default_dims, horizontal_grid_lines, make_legend, make_x_axis, make_y_axis, Axis, HoverRegions,
+    PlotOptions, Series,
+};
+use crate::{
+    Drawable, EventCtx, GeomBatch, GfxCtx, Line, ScreenDims, ScreenPt, Text, Widget, WidgetImpl,
+    WidgetOutput,
+};
+
+/// Stacks several series on top of each other, so the top of the plot shows the total and each
+/// band shows one series' share of it. Mode share over time is a typical use.
+pub struct StackedAreaPlot<X: Axis<X>, Y: Axis<Y>> {
+    draw: Drawable,
+    hover: HoverRegions,
+    // Just to constrain the axis types
+    _axes: std::marker::PhantomData<(X, Y)>,
+
+    top_left: ScreenPt,
+    dims: ScreenDims,
+}
+
+impl<X: Axis<X>, Y: Axis<Y>> StackedAreaPlot<X, Y> {
+    /// Every series must have a value at the same X values, sorted. The first series is drawn at
+    /// the bottom. `label` names the plot itself, like `LinePlot::new_widget`.
+    pub fn new_widget(
+        ctx: &EventCtx,
+        label: &str,
+        mut series: Vec<Series<X, Y>>,
+        opts: PlotOptions<X, Y>,
+        unit_fmt: UnitFmt,
+    ) -> Widget {
+        let legend = make_legend(ctx, &series, &opts);
+        series.retain(|s| !opts.disabled.contains(&s.label));
+
+        let num_pts = series.iter().map(|s| s.pts.len()).min().unwrap_or(0);
+        let xs: Vec<X> = if num_pts == 0 {
+            Vec::new()
+        } else {
+            series[0]
+                .pts
+                .iter()
+                .take(num_pts)
+                .map(|(x, _)| *x)
+                .collect()
+        };
+        if series.iter().any(|s| {
+            s.pts
+                .iter()
+                .take(num_pts)
+                .map(|(x, _)| *x)
+                .ne(xs.iter().cloned())
+        }) {
+            warn!(
+                "StackedAreaPlot {} has series with different X values",
+                label
+            );
+        }
+
+        // The running total below each band, and the total at the top
+        let mut totals = vec![0.0; num_pts];
+        let mut bands = Vec::new();
+        for s in &series {
+            let bottom = totals.clone();
+            for (total, (_, y)) in totals.iter_mut().zip(s.pts.iter()) {
+                *total += y.to_f64();
+            }
+            bands.push((bottom, totals.clone()));
+        }
+
+        let max_x = opts
+            .max_x
+            .unwrap_or_else(|| xs.last().cloned().unwrap_or_else(X::zero));
+        let max_y = opts.max_y.unwrap_or_else(|| {
+            Y::zero().from_f64(totals.iter().cloned().fold(0.0, f64::max).ceil())
+        });
+        let dims = opts.dims.unwrap_or_else(|| default_dims(ctx));
+
+        let to_screen = |x: X, y: f64| {
+            Pt2D::new(
+                x.to_percent(max_x) * dims.width,
+                // Y inversion! :D
+                (1.0 - max_y.from_f64(y).to_percent(max_y)) * dims.height,
+            )
+        };
+
+        let mut batch = GeomBatch::new();
+        horizontal_grid_lines(&mut batch, max_y, dims);
+        if max_x != X::zero() && num_pts >= 2 {
+            for (s, (bottom, top)) in series.iter().zip(bands.iter()) {
+                // Two triangles between each pair of samples
+                let mut pts = Vec::new();
+                let mut indices = Vec::new();
+                for idx in 0..num_pts {
+                    pts.push(to_screen(xs[idx], top[idx]));
+                    pts.push(to_screen(xs[idx], bottom[idx]));
+                    if idx > 0 {
+                        let base = 2 * (idx - 1);
+                        indices.extend([base, base + 1, base + 2, base + 1, base + 3, base + 2]);
+                    }
+                }
+                batch.push(s.color, Tessellation::new(pts, indices));
+            }
+        }
+
+        // Hovering anywhere in a vertical strip around a sample shows every series there
+        let mut hover = HoverRegions::new();
+        if max_x != X::zero() {
+            for idx in 0..num_pts {
+                let x1 = if idx == 0 {
+                    0.0
+                } else {
+                    (to_screen(xs[idx - 1], 0.0).x() + to_screen(xs[idx], 0.0).x()) / 2.0
+                };
+                let x2 = if idx == num_pts - 1 {
+                    dims.width
+                } else {
+                    (to_screen(xs[idx], 0.0).x() + to_screen(xs[idx + 1], 0.0).x()) / 2.0
+                };
+                if x2 <= x1 {
+                    continue;
+                }
+                let mut txt = Text::from(Line(xs[idx].prettyprint(&unit_fmt)).small_heading());
+                // List from the top band down, matching the drawing
+                for s in series.iter().rev() {
+                    txt.add_line(format!(
+                        "{}: {}",
+                        s.label,
+                        s.pts[idx].1.prettyprint(&unit_fmt)
+                    ));
+                }
+                txt.add_line(format!(
+                    "Total: {}",
+                    max_y.from_f64(totals[idx]).prettyprint(&unit_fmt)
+                ));
+                hover.add(
+                    Polygon::rectangle(x2 - x1, dims.height).translate(x1, 0.0),
+                    txt,
+                );
+            }
+        }
+
+        let plot = StackedAreaPlot {
+            draw: ctx.upload(batch),
+            hover,
+            _axes: std::marker::PhantomData,
+
+            top_left: ScreenPt::new(0.0, 0.0),
+            dims,
+        };
+
+        Widget::custom_col(vec![
+            legend.margin_below(10),
+            Widget::custom_row(vec![
+                make_y_axis(ctx, max_y, &unit_fmt),
+                Widget::new(Box::new(plot)).named(label),
+            ]),
+            make_x_axis(ctx, max_x, &unit_fmt),
+        ])
+        .container()
+    }
+}
+
+impl<X: Axis<X>, Y: Axis<Y>> WidgetImpl for StackedAreaPlot<X, Y> {
+    fn get_dims(&self) -> ScreenDims {
+        self.dims
+    }
+
+    fn set_pos(&mut self, top_left: ScreenPt) {
+        self.top_left = top_left;
+    }
+
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut WidgetOutput) {
+        self.hover.event(ctx, self.top_left);
+    }
+
+    fn draw(&self, g: &mut GfxCtx) {
+        g.redraw_at(self.top_left, &self.draw);
+        self.hover.draw(g, self.top_left);
+    }
+}
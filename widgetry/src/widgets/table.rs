@@ -3,7 +3,7 @@ use geom::Polygon;
 
 use crate::{
     include_labeled_bytes, Color, ControlState, EventCtx, GeomBatch, Key, Line, Panel, Text,
-    TextExt, Widget,
+    TextBox, TextExt, Widget,
 };
 
 const ROWS: usize = 8;
@@ -14,6 +14,7 @@ pub struct Table<A, T, F> {
     label_per_row: Box<dyn Fn(&T) -> String>,
     columns: Vec<Column<A, T>>,
     filter: Filter<A, T, F>,
+    search: Option<Search<T>>,
 
     sort_by: String,
     descending: bool,
@@ -31,6 +32,12 @@ struct Column<A, T> {
     col: Col<T>,
 }
 
+struct Search<T> {
+    to_text: Box<dyn Fn(&T) -> String>,
+    // Lowercase
+    query: String,
+}
+
 pub struct Filter<A, T, F> {
     pub state: F,
     pub to_controls: Box<dyn Fn(&mut EventCtx, &A, &F) -> Widget>,
@@ -52,6 +59,7 @@ impl<A, T, F> Table<A, T, F> {
             label_per_row,
             columns: Vec::new(),
             filter,
+            search: None,
 
             sort_by: default_sort_by.to_string(),
             descending: true,
@@ -72,6 +80,15 @@ impl<A, T, F> Table<A, T, F> {
         });
     }
 
+    /// Show a text box above the table. Only rows whose text contains what's typed there are
+    /// shown, ignoring case.
+    pub fn searchable(&mut self, to_text: Box<dyn Fn(&T) -> String>) {
+        self.search = Some(Search {
+            to_text,
+            query: String::new(),
+        });
+    }
+
     pub fn replace_render(&self, ctx: &mut EventCtx, app: &A, panel: &mut Panel) {
         let new_widget = self.render(ctx, app);
         panel.replace(ctx, &self.id, new_widget);
@@ -83,9 +100,17 @@ impl<A, T, F> Table<A, T, F> {
 
         // Filter
         for row in &self.data {
-            if (self.filter.apply)(&self.filter.state, row, app) {
-                data.push(row);
+            if !(self.filter.apply)(&self.filter.state, row, app) {
+                continue;
+            }
+            if let Some(ref search) = self.search {
+                if !search.query.is_empty()
+                    && !(search.to_text)(row).to_lowercase().contains(&search.query)
+                {
+                    continue;
+                }
             }
+            data.push(row);
         }
 
         // Sort
@@ -146,14 +171,37 @@ impl<A, T, F> Table<A, T, F> {
         }
 
         // Put together the UI
-        Widget::col(vec![
+        let table = Widget::col(vec![
             (self.filter.to_controls)(ctx, app, &self.filter.state),
             render_table(ctx, headers, rows, 0.88 * ctx.canvas.window_width),
             make_pagination(ctx, num_filtered, self.skip),
         ])
-        .named(&self.id)
-        // return in separate container in case caller want to apply an outer-name
-        .container()
+        .named(&self.id);
+        if self.search.is_some() {
+            // replace_render only swaps out the part named by id, so the search box keeps its
+            // contents and focus while typing.
+            Widget::col(vec![
+                Widget::row(vec![
+                    "Search:".text_widget(ctx).centered_vert(),
+                    TextBox::widget(ctx, self.search_box_id(), String::new(), false, 30),
+                ]),
+                table,
+            ])
+        } else {
+            // return in separate container in case caller want to apply an outer-name
+            table.container()
+        }
+    }
+
+    /// If the row with this label was clicked, return it.
+    pub fn clicked_row(&self, action: &str) -> Option<&T> {
+        self.data
+            .iter()
+            .find(|row| (self.label_per_row)(row) == action)
+    }
+
+    fn search_box_id(&self) -> String {
+        format!("{} search", self.id)
     }
 
     // Recalculate if true
@@ -183,6 +231,12 @@ impl<A, T, F> Table<A, T, F> {
 
     pub fn panel_changed(&mut self, panel: &Panel) {
         self.filter.state = (self.filter.from_controls)(panel);
+        let search_box_id = self.search_box_id();
+        if let Some(ref mut search) = self.search {
+            if panel.has_widget(&search_box_id) {
+                search.query = panel.text_box(&search_box_id).to_lowercase();
+            }
+        }
         self.skip = 0;
     }
 }
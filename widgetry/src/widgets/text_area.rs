@@ -0,0 +1,247 @@
+use geom::{Distance, Polygon};
+
+use crate::{
+    AccessibleNode, AccessibleRole, EdgeInsets, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome,
+    ScreenDims, ScreenPt, ScreenRectangle, Style, Text, TextSpan, Widget, WidgetImpl, WidgetOutput,
+};
+
+/// Multi-line text entry, like for describing a proposal. Like a `TextBox`, it takes key presses
+/// while the mouse is over it. Enter starts a new line, and the arrow keys move the cursor.
+pub struct TextArea {
+    // Each line is split into characters, so the cursor can't land in the middle of one
+    lines: Vec<Vec<char>>,
+    label: String,
+    // (line, character)
+    cursor: (usize, usize),
+    // If there are more lines than fit, the first one shown
+    scroll: usize,
+    num_lines: usize,
+    has_focus: bool,
+    padding: EdgeInsets,
+
+    top_left: ScreenPt,
+    dims: ScreenDims,
+}
+
+impl TextArea {
+    /// The text area is sized to show `num_lines` lines of about `width_chars` characters. More
+    /// can be typed; it scrolls to keep the cursor in view.
+    pub fn widget<I: Into<String>>(
+        ctx: &EventCtx,
+        label: I,
+        prefilled: String,
+        width_chars: usize,
+        num_lines: usize,
+    ) -> Widget {
+        let label = label.into();
+        let padding = EdgeInsets {
+            top: 6.0,
+            left: 8.0,
+            bottom: 8.0,
+            right: 8.0,
+        };
+        // Same as TextBox
+        let max_char_width = 25.0;
+        let num_lines = num_lines.max(1);
+
+        // There's always at least one line, even if it's empty
+        let lines: Vec<Vec<char>> = prefilled
+            .split('\n')
+            .map(|line| line.chars().collect())
+            .collect();
+        let cursor = (lines.len() - 1, lines.last().unwrap().len());
+
+        let mut text_area = TextArea {
+            lines,
+            label: label.clone(),
+            cursor,
+            scroll: 0,
+            num_lines,
+            has_focus: false,
+            padding,
+            top_left: ScreenPt::new(0.0, 0.0),
+            dims: ScreenDims::new(
+                (width_chars as f64) * max_char_width + (padding.left + padding.right) as f64,
+                (num_lines as f64) * ctx.default_line_height()
+                    + (padding.top + padding.bottom) as f64,
+            ),
+        };
+        text_area.scroll_to_cursor();
+        Widget::new(Box::new(text_area)).named(label)
+    }
+
+    /// All of the lines, separated by newlines
+    pub fn get_text(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| line.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn scroll_to_cursor(&mut self) {
+        if self.cursor.0 < self.scroll {
+            self.scroll = self.cursor.0;
+        } else if self.cursor.0 >= self.scroll + self.num_lines {
+            self.scroll = self.cursor.0 + 1 - self.num_lines;
+        }
+    }
+
+    // None if the key isn't used. Otherwise, did the text change?
+    fn handle_key(&mut self, key: Key, shift_pressed: bool) -> Option<bool> {
+        let (row, col) = self.cursor;
+        match key {
+            Key::LeftArrow => {
+                if col > 0 {
+                    self.cursor.1 -= 1;
+                } else if row > 0 {
+                    self.cursor = (row - 1, self.lines[row - 1].len());
+                }
+                Some(false)
+            }
+            Key::RightArrow => {
+                if col < self.lines[row].len() {
+                    self.cursor.1 += 1;
+                } else if row + 1 < self.lines.len() {
+                    self.cursor = (row + 1, 0);
+                }
+                Some(false)
+            }
+            Key::UpArrow => {
+                if row > 0 {
+                    self.cursor = (row - 1, col.min(self.lines[row - 1].len()));
+                }
+                Some(false)
+            }
+            Key::DownArrow => {
+                if row + 1 < self.lines.len() {
+                    self.cursor = (row + 1, col.min(self.lines[row + 1].len()));
+                }
+                Some(false)
+            }
+            Key::Enter => {
+                let rest = self.lines[row].split_off(col);
+                self.lines.insert(row + 1, rest);
+                self.cursor = (row + 1, 0);
+                Some(true)
+            }
+            Key::Backspace => {
+                if col > 0 {
+                    self.lines[row].remove(col - 1);
+                    self.cursor.1 -= 1;
+                    Some(true)
+                } else if row > 0 {
+                    // Join with the previous line
+                    let line = self.lines.remove(row);
+                    self.cursor = (row - 1, self.lines[row - 1].len());
+                    self.lines[row - 1].extend(line);
+                    Some(true)
+                } else {
+                    Some(false)
+                }
+            }
+            _ => {
+                let c = key.to_char(shift_pressed)?;
+                self.lines[row].insert(col, c);
+                self.cursor.1 += 1;
+                Some(true)
+            }
+        }
+    }
+
+    fn calculate_text(&self, style: &Style) -> Text {
+        let mut txt = Text::new();
+        for (row, line) in self
+            .lines
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(self.num_lines)
+        {
+            let mut spans: Vec<TextSpan> = Vec::new();
+            if self.has_focus && row == self.cursor.0 {
+                let before: String = line[0..self.cursor.1].iter().collect();
+                let after: String = line[self.cursor.1..].iter().collect();
+                spans.push(Line(before));
+                spans.push(Line("|").fg(style.text_primary_color));
+                spans.push(Line(after));
+            } else {
+                spans.push(Line(line.iter().collect::<String>()));
+            }
+            txt.add_appended(spans);
+        }
+        txt
+    }
+}
+
+impl WidgetImpl for TextArea {
+    fn get_dims(&self) -> ScreenDims {
+        self.dims
+    }
+
+    fn set_pos(&mut self, top_left: ScreenPt) {
+        self.top_left = top_left;
+    }
+
+    fn event(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
+        if ctx.redo_mouseover() {
+            if let Some(pt) = ctx.canvas.get_cursor_in_screen_space() {
+                self.has_focus = ScreenRectangle::top_left(self.top_left, self.dims).contains(pt);
+            } else {
+                self.has_focus = false;
+            }
+        }
+
+        if !self.has_focus {
+            return;
+        }
+        if let Some(key) = ctx.input.any_pressed() {
+            match self.handle_key(key, ctx.is_key_down(Key::LeftShift)) {
+                Some(changed) => {
+                    if changed {
+                        output.outcome = Outcome::Changed(self.label.clone());
+                    }
+                    self.scroll_to_cursor();
+                }
+                None => {
+                    ctx.input.unconsume_event();
+                }
+            }
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx) {
+        // TODO Cache
+        let mut batch = GeomBatch::from(vec![(
+            if self.has_focus {
+                g.style().field_bg
+            } else {
+                g.style().field_bg.dull(0.5)
+            },
+            Polygon::rounded_rectangle(self.dims.width, self.dims.height, 2.0),
+        )]);
+
+        let outline_style = g.style().btn_outline.outline;
+        batch.push(
+            outline_style.1,
+            Polygon::rounded_rectangle(self.dims.width, self.dims.height, 2.0)
+                .to_outline(Distance::meters(outline_style.0)),
+        );
+
+        batch.append(
+            self.calculate_text(g.style())
+                .render_autocropped(g)
+                .translate(self.padding.left, self.padding.top),
+        );
+        let draw = g.upload(batch);
+        g.redraw_at(self.top_left, &draw);
+    }
+
+    fn is_typing(&self) -> bool {
+        self.has_focus
+    }
+
+    fn accessible(&self) -> Option<AccessibleNode> {
+        Some(AccessibleNode::new(AccessibleRole::TextBox, &self.label).value(self.get_text()))
+    }
+}
@@ -1,8 +1,8 @@
 use geom::{Distance, Polygon};
 
 use crate::{
-    EdgeInsets, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, ScreenDims, ScreenPt,
-    ScreenRectangle, Style, Text, Widget, WidgetImpl, WidgetOutput,
+    AccessibleNode, AccessibleRole, EdgeInsets, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome,
+    ScreenDims, ScreenPt, ScreenRectangle, Style, Text, Widget, WidgetImpl, WidgetOutput,
 };
 
 // TODO right now, only a single line
@@ -171,4 +171,12 @@ impl WidgetImpl for TextBox {
         let draw = g.upload(batch);
         g.redraw_at(self.top_left, &draw);
     }
+
+    fn is_typing(&self) -> bool {
+        self.autofocus || self.has_focus
+    }
+
+    fn accessible(&self) -> Option<AccessibleNode> {
+        Some(AccessibleNode::new(AccessibleRole::TextBox, &self.label).value(&self.line))
+    }
 }
@@ -1,7 +1,8 @@
 use crate::svg::load_svg_bytes;
 use crate::{
-    include_labeled_bytes, Button, Color, ControlState, EdgeInsets, EventCtx, GfxCtx, MultiKey,
-    Outcome, RewriteColor, ScreenDims, ScreenPt, Text, TextSpan, Widget, WidgetImpl, WidgetOutput,
+    include_labeled_bytes, AccessibleNode, AccessibleRole, Button, Color, ControlState, EdgeInsets,
+    EventCtx, GfxCtx, MultiKey, Outcome, RewriteColor, ScreenDims, ScreenPt, Text, TextSpan,
+    Widget, WidgetImpl, WidgetOutput,
 };
 
 pub struct Toggle {
@@ -270,6 +271,33 @@ impl WidgetImpl for Toggle {
 
     fn event(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
         self.btn.event(ctx, output);
+        self.flip_if_clicked(output);
+    }
+
+    fn draw(&self, g: &mut GfxCtx) {
+        self.btn.draw(g);
+    }
+
+    fn can_focus(&self) -> bool {
+        self.btn.can_focus()
+    }
+
+    fn activate(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
+        self.btn.activate(ctx, output);
+        self.flip_if_clicked(output);
+    }
+
+    fn accessible(&self) -> Option<AccessibleNode> {
+        Some(
+            AccessibleNode::new(AccessibleRole::Checkbox, &self.btn.action)
+                .value(if self.enabled { "on" } else { "off" })
+                .disabled(!self.btn.is_enabled()),
+        )
+    }
+}
+
+impl Toggle {
+    fn flip_if_clicked(&mut self, output: &mut WidgetOutput) {
         if let Outcome::Clicked(_) = output.outcome {
             // Both buttons have the same label
             output.outcome = Outcome::Changed(self.btn.action.clone());
@@ -279,8 +307,4 @@ impl WidgetImpl for Toggle {
             output.redo_layout = true;
         }
     }
-
-    fn draw(&self, g: &mut GfxCtx) {
-        self.btn.draw(g);
-    }
 }
@@ -415,6 +415,8 @@ fn make_tabs(ctx: &mut EventCtx) -> TabController {
         ]),
         Text::from(Line("Spinner").big_heading_styled().size(18)).into_widget(ctx),
         widgetry::Spinner::widget(ctx, "spinner", (0, 11), 1, 1),
+        Text::from(Line("Color Picker").big_heading_styled().size(18)).into_widget(ctx),
+        widgetry::ColorPicker::widget(ctx, "color picker", Color::hex("#4363D8")),
         Text::from(Line("Drag & Drop Cards").big_heading_styled().size(18)).into_widget(ctx),
         build_drag_drop(ctx, 5).into_widget(ctx),
     ]);